@@ -0,0 +1,879 @@
+use wasm_bindgen::prelude::*;
+use js_sys::{Float64Array, Uint32Array};
+
+use super::network::{check_fraction, check_positive, Parameter};
+use super::neural_network::{dot, ActivationFunction};
+use super::optimizer::{Optimizer, UpdateRule};
+
+/// Elementwise functions recorded on a `Graph`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Unary {
+    Neg,
+    Exp,
+    Log,
+    Sqrt,
+    Square,
+    Abs,
+    Pow(f64),
+}
+
+impl Unary {
+    fn apply(self, x: f64) -> f64 {
+        match self {
+            Unary::Neg => -x,
+            Unary::Exp => x.exp(),
+            Unary::Log => x.ln(),
+            Unary::Sqrt => x.sqrt(),
+            Unary::Square => x * x,
+            Unary::Abs => x.abs(),
+            Unary::Pow(p) => x.powf(p),
+        }
+    }
+
+    /// Derivative at input `x` with output `y`
+    fn derivative(self, x: f64, y: f64) -> f64 {
+        match self {
+            Unary::Neg => -1.0,
+            Unary::Exp => y,
+            Unary::Log => 1.0 / x,
+            Unary::Sqrt => 0.5 / y,
+            Unary::Square => 2.0 * x,
+            Unary::Abs => x.signum(),
+            Unary::Pow(p) => p * x.powf(p - 1.0),
+        }
+    }
+}
+
+/// Operation that produced a node, referring to its inputs by node index
+#[derive(Clone, Debug)]
+enum Op {
+    Leaf,
+    Add(usize, usize),
+    Sub(usize, usize),
+    Mul(usize, usize),
+    Div(usize, usize),
+    /// `a . b`, or `a . b^T` when `transpose_b` is set
+    MatMul { a: usize, b: usize, transpose_b: bool },
+    Unary(usize, Unary),
+    /// Activation over the last axis
+    Activation(usize, ActivationFunction),
+    Sum(usize),
+    Mean(usize),
+    /// Input offset of the maximum behind every output value
+    Max(usize, Vec<usize>),
+    Reshape(usize),
+    Transpose(usize),
+}
+
+struct Node {
+    value: Vec<f64>,
+    shape: Vec<usize>,
+    gradient: Vec<f64>,
+    op: Op,
+}
+
+/// Shape of the numpy-style broadcast of two shapes
+fn broadcast_shape(a: &[usize], b: &[usize]) -> Result<Vec<usize>, String> {
+    let rank = a.len().max(b.len());
+    let dim = |shape: &[usize], i: usize| if i + shape.len() >= rank { shape[i + shape.len() - rank] } else { 1 };
+    (0..rank)
+        .map(|i| match (dim(a, i), dim(b, i)) {
+            (x, y) if x == y || y == 1 => Ok(x),
+            (1, y) => Ok(y),
+            _ => Err(format!("Shapes {:?} and {:?} cannot be broadcast together", a, b)),
+        })
+        .collect()
+}
+
+/// Flat offset into `shape` for every element of `target`, a shape `shape` broadcasts to
+fn broadcast_map(shape: &[usize], target: &[usize]) -> Vec<usize> {
+    let offset = target.len() - shape.len();
+    let mut strides = vec![0; target.len()];
+    let mut stride = 1;
+    for i in (0..shape.len()).rev() {
+        if shape[i] != 1 {
+            strides[offset + i] = stride;
+        }
+        stride *= shape[i];
+    }
+
+    let size: usize = target.iter().product();
+    let mut map = Vec::with_capacity(size);
+    let mut index = vec![0; target.len()];
+    let mut position = 0;
+    for _ in 0..size {
+        map.push(position);
+        // Advance the multi-index like an odometer
+        for axis in (0..target.len()).rev() {
+            index[axis] += 1;
+            position += strides[axis];
+            if index[axis] < target[axis] {
+                break;
+            }
+            position -= strides[axis] * index[axis];
+            index[axis] = 0;
+        }
+    }
+    map
+}
+
+/// Tape of tensor operations for reverse-mode automatic differentiation
+///
+/// Nodes are appended in evaluation order, so walking the tape backwards visits every node
+/// after all of its consumers. Values are row-major with numpy-style broadcasting.
+pub(crate) struct Graph {
+    nodes: Vec<Node>,
+}
+
+impl Graph {
+    pub(crate) fn new() -> Graph {
+        Graph { nodes: Vec::new() }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Drop every node from `len` on
+    pub(crate) fn truncate(&mut self, len: usize) {
+        self.nodes.truncate(len);
+    }
+
+    pub(crate) fn value(&self, node: usize) -> &[f64] {
+        &self.nodes[node].value
+    }
+
+    pub(crate) fn shape(&self, node: usize) -> &[usize] {
+        &self.nodes[node].shape
+    }
+
+    /// Gradient from the latest `backward`, empty for nodes it did not reach
+    pub(crate) fn gradient(&self, node: usize) -> &[f64] {
+        &self.nodes[node].gradient
+    }
+
+    /// Values and gradients of a node, for an optimizer update
+    pub(crate) fn take_parameter(&mut self, node: usize) -> Parameter {
+        let node = &mut self.nodes[node];
+        let mut gradients = std::mem::take(&mut node.gradient);
+        gradients.resize(node.value.len(), 0.0);
+        Parameter { values: std::mem::take(&mut node.value), gradients }
+    }
+
+    /// Put back a parameter from `take_parameter`
+    pub(crate) fn restore_parameter(&mut self, node: usize, parameter: Parameter) {
+        self.nodes[node].value = parameter.values;
+        self.nodes[node].gradient = parameter.gradients;
+    }
+
+    fn push(&mut self, value: Vec<f64>, shape: Vec<usize>, op: Op) -> usize {
+        debug_assert_eq!(value.len(), shape.iter().product::<usize>());
+        self.nodes.push(Node { value, shape, gradient: Vec::new(), op });
+        self.nodes.len() - 1
+    }
+
+    /// Input tensor; `values` must hold the product of `shape`
+    pub(crate) fn leaf(&mut self, values: Vec<f64>, shape: Vec<usize>) -> usize {
+        self.push(values, shape, Op::Leaf)
+    }
+
+    fn binary(&mut self, a: usize, b: usize, op: Op, combine: impl Fn(f64, f64) -> f64) -> Result<usize, String> {
+        let shape = broadcast_shape(&self.nodes[a].shape, &self.nodes[b].shape)?;
+        let (map_a, map_b) = (broadcast_map(&self.nodes[a].shape, &shape), broadcast_map(&self.nodes[b].shape, &shape));
+        let (va, vb) = (&self.nodes[a].value, &self.nodes[b].value);
+        let value = map_a.iter().zip(&map_b).map(|(&i, &j)| combine(va[i], vb[j])).collect();
+        Ok(self.push(value, shape, op))
+    }
+
+    pub(crate) fn add(&mut self, a: usize, b: usize) -> Result<usize, String> {
+        self.binary(a, b, Op::Add(a, b), |x, y| x + y)
+    }
+
+    pub(crate) fn sub(&mut self, a: usize, b: usize) -> Result<usize, String> {
+        self.binary(a, b, Op::Sub(a, b), |x, y| x - y)
+    }
+
+    pub(crate) fn mul(&mut self, a: usize, b: usize) -> Result<usize, String> {
+        self.binary(a, b, Op::Mul(a, b), |x, y| x * y)
+    }
+
+    pub(crate) fn div(&mut self, a: usize, b: usize) -> Result<usize, String> {
+        self.binary(a, b, Op::Div(a, b), |x, y| x / y)
+    }
+
+    /// Matrix product of `m x k` and `k x n` matrices, or of `m x k` and the transpose of
+    /// an `n x k` matrix
+    pub(crate) fn matmul(&mut self, a: usize, b: usize, transpose_b: bool) -> Result<usize, String> {
+        let (sa, sb) = (&self.nodes[a].shape, &self.nodes[b].shape);
+        if sa.len() != 2 || sb.len() != 2 {
+            return Err("Matrix product needs two matrices".to_string());
+        }
+        let (m, k) = (sa[0], sa[1]);
+        let (kb, n) = if transpose_b { (sb[1], sb[0]) } else { (sb[0], sb[1]) };
+        if k != kb {
+            return Err(format!("Cannot multiply {:?} by {:?}", sa, sb));
+        }
+
+        let (va, vb) = (&self.nodes[a].value, &self.nodes[b].value);
+        let mut value = vec![0.0; m * n];
+        for (row, out) in va.chunks_exact(k.max(1)).zip(value.chunks_exact_mut(n.max(1))) {
+            if transpose_b {
+                for (o, column) in out.iter_mut().zip(vb.chunks_exact(k.max(1))) {
+                    *o = dot(row, column);
+                }
+            } else {
+                for (x, b_row) in row.iter().zip(vb.chunks_exact(n.max(1))) {
+                    out.iter_mut().zip(b_row).for_each(|(o, w)| *o += x * w);
+                }
+            }
+        }
+        Ok(self.push(value, vec![m, n], Op::MatMul { a, b, transpose_b }))
+    }
+
+    pub(crate) fn unary(&mut self, a: usize, function: Unary) -> usize {
+        let value = self.nodes[a].value.iter().map(|&x| function.apply(x)).collect();
+        let shape = self.nodes[a].shape.clone();
+        self.push(value, shape, Op::Unary(a, function))
+    }
+
+    /// Activation applied elementwise, or over the last axis for softmax
+    pub(crate) fn activation(&mut self, a: usize, function: ActivationFunction) -> usize {
+        let mut value = self.nodes[a].value.clone();
+        let shape = self.nodes[a].shape.clone();
+        function.apply_rows(&mut value, shape.last().copied().unwrap_or(1));
+        self.push(value, shape, Op::Activation(a, function))
+    }
+
+    /// Shape after reducing `axis` to length 1, or to a scalar when `axis` is None
+    fn reduced_shape(&self, a: usize, axis: Option<usize>) -> Result<Vec<usize>, String> {
+        let shape = &self.nodes[a].shape;
+        match axis {
+            None => Ok(Vec::new()),
+            Some(axis) if axis < shape.len() => {
+                let mut reduced = shape.clone();
+                reduced[axis] = 1;
+                Ok(reduced)
+            }
+            Some(axis) => Err(format!("Axis {} is out of range for shape {:?}", axis, shape)),
+        }
+    }
+
+    /// Sum over one axis, kept with length 1, or over all values
+    pub(crate) fn sum(&mut self, a: usize, axis: Option<usize>) -> Result<usize, String> {
+        let shape = self.reduced_shape(a, axis)?;
+        let mut value = vec![0.0; shape.iter().product()];
+        for (&o, x) in broadcast_map(&shape, &self.nodes[a].shape).iter().zip(&self.nodes[a].value) {
+            value[o] += x;
+        }
+        Ok(self.push(value, shape, Op::Sum(a)))
+    }
+
+    /// Mean over one axis, kept with length 1, or over all values
+    pub(crate) fn mean(&mut self, a: usize, axis: Option<usize>) -> Result<usize, String> {
+        let sum = self.sum(a, axis)?;
+        let count = (self.nodes[a].value.len() / self.nodes[sum].value.len().max(1)) as f64;
+        let node = self.nodes.pop().expect("sum node was just pushed");
+        let value = node.value.iter().map(|v| v / count).collect();
+        Ok(self.push(value, node.shape, Op::Mean(a)))
+    }
+
+    /// Maximum over one axis, kept with length 1, or over all values; the gradient flows
+    /// to the first maximum
+    pub(crate) fn max(&mut self, a: usize, axis: Option<usize>) -> Result<usize, String> {
+        let shape = self.reduced_shape(a, axis)?;
+        let size = shape.iter().product();
+        if size > 0 && self.nodes[a].value.is_empty() {
+            return Err("Cannot take the maximum over an empty axis".to_string());
+        }
+        let (mut value, mut positions) = (vec![f64::NEG_INFINITY; size], vec![usize::MAX; size]);
+        for (i, (&o, &x)) in broadcast_map(&shape, &self.nodes[a].shape).iter().zip(&self.nodes[a].value).enumerate() {
+            if positions[o] == usize::MAX || x > value[o] {
+                value[o] = x;
+                positions[o] = i;
+            }
+        }
+        Ok(self.push(value, shape, Op::Max(a, positions)))
+    }
+
+    pub(crate) fn reshape(&mut self, a: usize, shape: Vec<usize>) -> Result<usize, String> {
+        if shape.iter().product::<usize>() != self.nodes[a].value.len() {
+            return Err(format!("Cannot reshape {:?} to {:?}", self.nodes[a].shape, shape));
+        }
+        let value = self.nodes[a].value.clone();
+        Ok(self.push(value, shape, Op::Reshape(a)))
+    }
+
+    /// Transpose of a matrix
+    pub(crate) fn transpose(&mut self, a: usize) -> Result<usize, String> {
+        let shape = &self.nodes[a].shape;
+        if shape.len() != 2 {
+            return Err("Transpose needs a matrix".to_string());
+        }
+        let (rows, columns) = (shape[0], shape[1]);
+        let value = transpose(&self.nodes[a].value, rows, columns);
+        Ok(self.push(value, vec![columns, rows], Op::Transpose(a)))
+    }
+
+    /// Backpropagate from `output`, seeded with `seed` or with ones for a single value
+    ///
+    /// Replaces the gradients of every node; nodes after `output` get zeros.
+    pub(crate) fn backward(&mut self, output: usize, seed: Option<Vec<f64>>) -> Result<(), String> {
+        let size = self.nodes[output].value.len();
+        let seed = match seed {
+            Some(seed) if seed.len() == size => seed,
+            Some(_) => return Err("Seed gradient must match the output size".to_string()),
+            None if size == 1 => vec![1.0],
+            None => return Err("Backward from a tensor with several values needs a seed gradient".to_string()),
+        };
+
+        for node in &mut self.nodes {
+            node.gradient = vec![0.0; node.value.len()];
+        }
+        self.nodes[output].gradient = seed;
+
+        for index in (0..=output).rev() {
+            if matches!(self.nodes[index].op, Op::Leaf) || self.nodes[index].gradient.iter().all(|&g| g == 0.0) {
+                continue;
+            }
+            for (input, contribution) in self.contributions(index) {
+                self.nodes[input].gradient.iter_mut().zip(&contribution).for_each(|(g, c)| *g += c);
+            }
+        }
+        Ok(())
+    }
+
+    /// Gradient contributions of a node to each of its inputs
+    fn contributions(&self, index: usize) -> Vec<(usize, Vec<f64>)> {
+        let node = &self.nodes[index];
+        let g = &node.gradient;
+
+        // Accumulate `g[i] * scale(i)` at the broadcast source of every output element
+        let reduce = |input: usize, scale: &dyn Fn(usize) -> f64| -> (usize, Vec<f64>) {
+            let mut gradient = vec![0.0; self.nodes[input].value.len()];
+            for (i, &j) in broadcast_map(&self.nodes[input].shape, &node.shape).iter().enumerate() {
+                gradient[j] += g[i] * scale(i);
+            }
+            (input, gradient)
+        };
+        let operand = |input: usize| -> Vec<f64> {
+            let map = broadcast_map(&self.nodes[input].shape, &node.shape);
+            map.iter().map(|&j| self.nodes[input].value[j]).collect()
+        };
+
+        match node.op {
+            Op::Leaf => Vec::new(),
+            Op::Add(a, b) => vec![reduce(a, &|_| 1.0), reduce(b, &|_| 1.0)],
+            Op::Sub(a, b) => vec![reduce(a, &|_| 1.0), reduce(b, &|_| -1.0)],
+            Op::Mul(a, b) => {
+                let (va, vb) = (operand(a), operand(b));
+                vec![reduce(a, &|i| vb[i]), reduce(b, &|i| va[i])]
+            }
+            Op::Div(a, b) => {
+                let (va, vb) = (operand(a), operand(b));
+                vec![reduce(a, &|i| 1.0 / vb[i]), reduce(b, &|i| -va[i] / (vb[i] * vb[i]))]
+            }
+            Op::MatMul { a, b, transpose_b } => {
+                let (va, vb) = (&self.nodes[a].value, &self.nodes[b].value);
+                let (m, k, n) = (self.nodes[a].shape[0], self.nodes[a].shape[1], node.shape[1]);
+                let (mut ga, mut gb) = (vec![0.0; m * k], vec![0.0; k * n]);
+
+                for i in 0..m {
+                    let (row, g_row) = (&va[i * k..(i + 1) * k], &g[i * n..(i + 1) * n]);
+                    let ga_row = &mut ga[i * k..(i + 1) * k];
+                    for (j, &gij) in g_row.iter().enumerate() {
+                        if gij == 0.0 {
+                            continue;
+                        }
+                        for l in 0..k {
+                            // b[l][j] is at l * n + j, or at j * k + l when transposed
+                            let offset = if transpose_b { j * k + l } else { l * n + j };
+                            ga_row[l] += gij * vb[offset];
+                            gb[offset] += gij * row[l];
+                        }
+                    }
+                }
+                vec![(a, ga), (b, gb)]
+            }
+            Op::Unary(a, function) => {
+                let va = &self.nodes[a].value;
+                vec![(a, g.iter().enumerate().map(|(i, gi)| gi * function.derivative(va[i], node.value[i])).collect())]
+            }
+            Op::Activation(a, function) => {
+                let width = node.shape.last().copied().unwrap_or(1);
+                vec![(a, function.backward_rows(&self.nodes[a].value, &node.value, g, width))]
+            }
+            Op::Sum(a) | Op::Mean(a) => {
+                let scale = if matches!(node.op, Op::Mean(_)) { node.value.len() as f64 / self.nodes[a].value.len() as f64 } else { 1.0 };
+                let map = broadcast_map(&node.shape, &self.nodes[a].shape);
+                vec![(a, map.iter().map(|&o| g[o] * scale).collect())]
+            }
+            Op::Max(a, ref positions) => {
+                let mut gradient = vec![0.0; self.nodes[a].value.len()];
+                for (o, &i) in positions.iter().enumerate() {
+                    gradient[i] += g[o];
+                }
+                vec![(a, gradient)]
+            }
+            Op::Reshape(a) => vec![(a, g.clone())],
+            Op::Transpose(a) => vec![(a, transpose(g, node.shape[0], node.shape[1]))],
+        }
+    }
+}
+
+/// Transpose a row-major `rows x columns` matrix
+fn transpose(values: &[f64], rows: usize, columns: usize) -> Vec<f64> {
+    (0..columns).flat_map(|j| (0..rows).map(move |i| values[i * columns + j])).collect()
+}
+
+/// Automatic differentiation tape for custom losses and architectures
+///
+/// Tensors are referred to by the index the tape returns for them. Parameters survive
+/// `reset` and are updated by `step` with the tape's optimizer, so a training loop is:
+/// create parameters once, then per batch record the forward pass, call `backward` on the
+/// scalar loss, `step`, and `reset`.
+#[wasm_bindgen]
+pub struct Tape {
+    graph: Graph,
+    /// Parameters are the first nodes of the graph
+    parameters: usize,
+    optimizer: Optimizer,
+}
+
+#[wasm_bindgen]
+impl Tape {
+    /// Create an empty tape whose optimizer is SGD at `learning_rate` (default 0.01)
+    #[wasm_bindgen(constructor)]
+    pub fn new(learning_rate: Option<f64>) -> Result<Tape, JsValue> {
+        let learning_rate = learning_rate.unwrap_or(0.01);
+        check_positive(learning_rate, "Learning rate")?;
+        Ok(Tape { graph: Graph::new(), parameters: 0, optimizer: Optimizer::new(learning_rate) })
+    }
+
+    /// Add a trainable tensor; parameters must be created before any other tensor
+    pub fn parameter(&mut self, values: &JsValue, shape: &[u32]) -> Result<usize, JsValue> {
+        if self.graph.len() != self.parameters {
+            return Err(JsValue::from_str("Parameters must be created before other tensors; call reset first"));
+        }
+        let node = self.tensor(values, shape)?;
+        self.parameters += 1;
+        self.optimizer.reset();
+        Ok(node)
+    }
+
+    /// Add an input tensor of the given shape
+    pub fn tensor(&mut self, values: &JsValue, shape: &[u32]) -> Result<usize, JsValue> {
+        // Convert input to typed array for better performance
+        let values = Float64Array::new(values).to_vec();
+        let shape: Vec<usize> = shape.iter().map(|&d| d as usize).collect();
+        if values.len() != shape.iter().product::<usize>() {
+            return Err(JsValue::from_str("Number of values must match the shape"));
+        }
+        Ok(self.graph.leaf(values, shape))
+    }
+
+    /// Elementwise sum with broadcasting
+    pub fn add(&mut self, a: usize, b: usize) -> Result<usize, JsValue> {
+        self.check(&[a, b])?;
+        Ok(self.graph.add(a, b)?)
+    }
+
+    /// Elementwise difference with broadcasting
+    pub fn sub(&mut self, a: usize, b: usize) -> Result<usize, JsValue> {
+        self.check(&[a, b])?;
+        Ok(self.graph.sub(a, b)?)
+    }
+
+    /// Elementwise product with broadcasting
+    pub fn mul(&mut self, a: usize, b: usize) -> Result<usize, JsValue> {
+        self.check(&[a, b])?;
+        Ok(self.graph.mul(a, b)?)
+    }
+
+    /// Elementwise quotient with broadcasting
+    pub fn div(&mut self, a: usize, b: usize) -> Result<usize, JsValue> {
+        self.check(&[a, b])?;
+        Ok(self.graph.div(a, b)?)
+    }
+
+    /// Matrix product, optionally with the second matrix transposed
+    pub fn matmul(&mut self, a: usize, b: usize, transpose_b: Option<bool>) -> Result<usize, JsValue> {
+        self.check(&[a, b])?;
+        Ok(self.graph.matmul(a, b, transpose_b.unwrap_or(false))?)
+    }
+
+    pub fn neg(&mut self, a: usize) -> Result<usize, JsValue> {
+        self.unary(a, Unary::Neg)
+    }
+
+    pub fn exp(&mut self, a: usize) -> Result<usize, JsValue> {
+        self.unary(a, Unary::Exp)
+    }
+
+    pub fn log(&mut self, a: usize) -> Result<usize, JsValue> {
+        self.unary(a, Unary::Log)
+    }
+
+    pub fn sqrt(&mut self, a: usize) -> Result<usize, JsValue> {
+        self.unary(a, Unary::Sqrt)
+    }
+
+    pub fn square(&mut self, a: usize) -> Result<usize, JsValue> {
+        self.unary(a, Unary::Square)
+    }
+
+    pub fn abs(&mut self, a: usize) -> Result<usize, JsValue> {
+        self.unary(a, Unary::Abs)
+    }
+
+    /// Elementwise power with a constant exponent
+    pub fn pow(&mut self, a: usize, exponent: f64) -> Result<usize, JsValue> {
+        self.unary(a, Unary::Pow(exponent))
+    }
+
+    /// Activation function, applied over the last axis for softmax and log-softmax
+    pub fn activation(&mut self, a: usize, function: ActivationFunction) -> Result<usize, JsValue> {
+        self.check(&[a])?;
+        Ok(self.graph.activation(a, function))
+    }
+
+    /// Sum over `axis` (kept with length 1), or over all values into a scalar
+    pub fn sum(&mut self, a: usize, axis: Option<usize>) -> Result<usize, JsValue> {
+        self.check(&[a])?;
+        Ok(self.graph.sum(a, axis)?)
+    }
+
+    /// Mean over `axis` (kept with length 1), or over all values into a scalar
+    pub fn mean(&mut self, a: usize, axis: Option<usize>) -> Result<usize, JsValue> {
+        self.check(&[a])?;
+        Ok(self.graph.mean(a, axis)?)
+    }
+
+    /// Maximum over `axis` (kept with length 1), or over all values into a scalar
+    pub fn max(&mut self, a: usize, axis: Option<usize>) -> Result<usize, JsValue> {
+        self.check(&[a])?;
+        Ok(self.graph.max(a, axis)?)
+    }
+
+    /// Same values with a new shape of the same size
+    pub fn reshape(&mut self, a: usize, shape: &[u32]) -> Result<usize, JsValue> {
+        self.check(&[a])?;
+        Ok(self.graph.reshape(a, shape.iter().map(|&d| d as usize).collect())?)
+    }
+
+    /// Transpose of a matrix
+    pub fn transpose(&mut self, a: usize) -> Result<usize, JsValue> {
+        self.check(&[a])?;
+        Ok(self.graph.transpose(a)?)
+    }
+
+    /// Compute the gradients of `output` with respect to every tensor
+    ///
+    /// A scalar output is seeded with 1; other outputs need a `seed` gradient of their size.
+    pub fn backward(&mut self, output: usize, seed: Option<Vec<f64>>) -> Result<(), JsValue> {
+        self.check(&[output])?;
+        Ok(self.graph.backward(output, seed)?)
+    }
+
+    /// Values of a tensor
+    pub fn value(&self, node: usize) -> Result<Float64Array, JsValue> {
+        self.check(&[node])?;
+        Ok(Float64Array::from(self.graph.value(node)))
+    }
+
+    /// Shape of a tensor
+    pub fn shape(&self, node: usize) -> Result<Uint32Array, JsValue> {
+        self.check(&[node])?;
+        let shape: Vec<u32> = self.graph.shape(node).iter().map(|&d| d as u32).collect();
+        Ok(Uint32Array::from(&shape[..]))
+    }
+
+    /// Gradient of a tensor from the latest `backward` (zeros if it was not reached)
+    pub fn gradient(&self, node: usize) -> Result<Float64Array, JsValue> {
+        self.check(&[node])?;
+        let mut gradient = self.graph.gradient(node).to_vec();
+        gradient.resize(self.graph.value(node).len(), 0.0);
+        Ok(Float64Array::from(&gradient[..]))
+    }
+
+    /// Update every parameter from its gradient with the tape's optimizer
+    ///
+    /// Tensors computed from the old values are stale afterwards; call `reset` before
+    /// recording the next forward pass.
+    pub fn step(&mut self) {
+        let mut parameters: Vec<Parameter> = (0..self.parameters).map(|node| self.graph.take_parameter(node)).collect();
+        self.optimizer.update(&mut parameters.iter_mut().collect::<Vec<_>>());
+        for (node, parameter) in parameters.into_iter().enumerate() {
+            self.graph.restore_parameter(node, parameter);
+        }
+    }
+
+    /// Drop every tensor except the parameters
+    pub fn reset(&mut self) {
+        self.graph.truncate(self.parameters);
+    }
+
+    /// Use SGD with optional (Nesterov) momentum
+    pub fn set_sgd(&mut self, learning_rate: Option<f64>, momentum: Option<f64>, nesterov: Option<bool>) -> Result<(), JsValue> {
+        let momentum = momentum.unwrap_or(0.0);
+        check_fraction(momentum, "Momentum")?;
+        let nesterov = nesterov.unwrap_or(false);
+        if nesterov && momentum == 0.0 {
+            return Err(JsValue::from_str("Nesterov momentum requires a momentum greater than 0"));
+        }
+        self.set_rule(learning_rate, UpdateRule::Sgd { momentum, nesterov })
+    }
+
+    /// Use Adam (defaults: beta1 0.9, beta2 0.999, epsilon 1e-8)
+    pub fn set_adam(&mut self, learning_rate: Option<f64>, beta1: Option<f64>, beta2: Option<f64>, epsilon: Option<f64>) -> Result<(), JsValue> {
+        let (beta1, beta2, epsilon) = (beta1.unwrap_or(0.9), beta2.unwrap_or(0.999), epsilon.unwrap_or(1e-8));
+        check_fraction(beta1, "Beta1")?;
+        check_fraction(beta2, "Beta2")?;
+        check_positive(epsilon, "Epsilon")?;
+        self.set_rule(learning_rate, UpdateRule::Adam { beta1, beta2, epsilon, weight_decay: 0.0 })
+    }
+
+    /// Number of tensors on the tape, parameters included
+    #[wasm_bindgen(getter)]
+    pub fn num_tensors(&self) -> usize {
+        self.graph.len()
+    }
+
+    /// Number of parameter tensors
+    #[wasm_bindgen(getter)]
+    pub fn num_parameters(&self) -> usize {
+        self.parameters
+    }
+}
+
+impl Tape {
+    fn unary(&mut self, a: usize, function: Unary) -> Result<usize, JsValue> {
+        self.check(&[a])?;
+        Ok(self.graph.unary(a, function))
+    }
+
+    fn check(&self, nodes: &[usize]) -> Result<(), JsValue> {
+        if nodes.iter().any(|&node| node >= self.graph.len()) {
+            return Err(JsValue::from_str("Tensor index out of range"));
+        }
+        Ok(())
+    }
+
+    fn set_rule(&mut self, learning_rate: Option<f64>, rule: UpdateRule) -> Result<(), JsValue> {
+        if let Some(learning_rate) = learning_rate {
+            check_positive(learning_rate, "Learning rate")?;
+            self.optimizer.learning_rate = learning_rate;
+        }
+        self.optimizer.set_rule(rule);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::random::Rng;
+
+    fn normals(count: usize, seed: u64) -> Vec<f64> {
+        let mut rng = Rng::new(seed);
+        (0..count).map(|_| rng.next_normal()).collect()
+    }
+
+    /// Positive values in [0.5, 2.5), for logarithms, roots and divisors
+    fn positives(count: usize, seed: u64) -> Vec<f64> {
+        let mut rng = Rng::new(seed);
+        (0..count).map(|_| 0.5 + 2.0 * rng.next_f64()).collect()
+    }
+
+    type Build = dyn Fn(&mut Graph, &[usize]) -> Result<usize, String>;
+
+    /// Graph holding one leaf per input, and the leaves
+    fn leaves(inputs: &[(Vec<f64>, Vec<usize>)]) -> (Graph, Vec<usize>) {
+        let mut graph = Graph::new();
+        let leaves = inputs.iter().map(|(values, shape)| graph.leaf(values.clone(), shape.clone())).collect();
+        (graph, leaves)
+    }
+
+    /// Value of the output of `build` on leaves `inputs`, weighted by `seed`
+    fn weighted_output(build: &Build, inputs: &[(Vec<f64>, Vec<usize>)], seed: &[f64]) -> f64 {
+        let (mut graph, leaves) = leaves(inputs);
+        let output = build(&mut graph, &leaves).unwrap();
+        graph.value(output).iter().zip(seed).map(|(v, s)| v * s).sum()
+    }
+
+    /// Compare the gradient of every leaf with central differences of the seeded output
+    fn assert_gradients(build: &Build, inputs: &[(Vec<f64>, Vec<usize>)]) {
+        let (mut graph, leaves) = leaves(inputs);
+        let output = build(&mut graph, &leaves).unwrap();
+        let seed = normals(graph.value(output).len(), 99);
+        graph.backward(output, Some(seed.clone())).unwrap();
+
+        let h = 1e-6;
+        let mut perturbed = inputs.to_vec();
+        for (leaf, &node) in leaves.iter().enumerate() {
+            for i in 0..inputs[leaf].0.len() {
+                perturbed[leaf].0[i] += h;
+                let above = weighted_output(build, &perturbed, &seed);
+                perturbed[leaf].0[i] -= 2.0 * h;
+                let below = weighted_output(build, &perturbed, &seed);
+                perturbed[leaf].0[i] += h;
+
+                let (numeric, analytic) = ((above - below) / (2.0 * h), graph.gradient(node)[i]);
+                assert!((numeric - analytic).abs() < 1e-6, "leaf {} [{}]: {} vs {}", leaf, i, numeric, analytic);
+            }
+        }
+    }
+
+    #[test]
+    fn broadcast_shapes_and_offsets() {
+        assert_eq!(broadcast_shape(&[2, 1], &[3]).unwrap(), vec![2, 3]);
+        assert_eq!(broadcast_shape(&[], &[4, 1]).unwrap(), vec![4, 1]);
+        assert_eq!(broadcast_shape(&[2, 3], &[2]).err().unwrap(), "Shapes [2, 3] and [2] cannot be broadcast together");
+
+        assert_eq!(broadcast_map(&[3], &[2, 3]), vec![0, 1, 2, 0, 1, 2]);
+        assert_eq!(broadcast_map(&[2, 1], &[2, 3]), vec![0, 0, 0, 1, 1, 1]);
+        assert_eq!(broadcast_map(&[], &[2, 2]), vec![0; 4]);
+    }
+
+    #[test]
+    fn arithmetic_gradients_with_broadcasting() {
+        let matrix = (normals(6, 1), vec![2, 3]);
+        let row = (normals(3, 2), vec![3]);
+        let column = (normals(2, 3), vec![2, 1]);
+
+        assert_gradients(&|g, x| g.add(x[0], x[1]), &[matrix.clone(), row.clone()]);
+        assert_gradients(&|g, x| g.sub(x[0], x[1]), &[column.clone(), row.clone()]);
+        assert_gradients(&|g, x| g.mul(x[0], x[1]), &[matrix.clone(), column.clone()]);
+        assert_gradients(&|g, x| g.div(x[0], x[1]), &[row, (positives(6, 4), vec![2, 3])]);
+
+        // A node used twice accumulates both contributions
+        assert_gradients(&|g, x| g.mul(x[0], x[0]), &[matrix]);
+    }
+
+    #[test]
+    fn matmul_gradients() {
+        let a = (normals(6, 5), vec![2, 3]);
+        assert_gradients(&|g, x| g.matmul(x[0], x[1], false), &[a.clone(), (normals(12, 6), vec![3, 4])]);
+        assert_gradients(&|g, x| g.matmul(x[0], x[1], true), &[a, (normals(12, 7), vec![4, 3])]);
+
+        let mut graph = Graph::new();
+        let a = graph.leaf(vec![1.0, 2.0, 3.0, 4.0], vec![2, 2]);
+        let b = graph.leaf(vec![5.0, 6.0, 7.0, 8.0], vec![2, 2]);
+        let product = graph.matmul(a, b, false).unwrap();
+        assert_eq!(graph.value(product), [19.0, 22.0, 43.0, 50.0]);
+        let product = graph.matmul(a, b, true).unwrap();
+        assert_eq!(graph.value(product), [17.0, 23.0, 39.0, 53.0]);
+
+        let c = graph.leaf(vec![0.0; 3], vec![3, 1]);
+        assert_eq!(graph.matmul(a, c, false).err().unwrap(), "Cannot multiply [2, 2] by [3, 1]");
+        let v = graph.leaf(vec![0.0; 2], vec![2]);
+        assert_eq!(graph.matmul(a, v, false).err().unwrap(), "Matrix product needs two matrices");
+    }
+
+    #[test]
+    fn elementwise_gradients() {
+        let input = [(positives(6, 8), vec![2, 3])];
+        let functions = [Unary::Neg, Unary::Exp, Unary::Log, Unary::Sqrt, Unary::Square, Unary::Abs, Unary::Pow(1.5), Unary::Pow(-2.0)];
+        for function in functions {
+            assert_gradients(&move |g, x| Ok(g.unary(x[0], function)), &input);
+        }
+
+        let input = [(normals(6, 9), vec![2, 3])];
+        let functions = [ActivationFunction::Tanh, ActivationFunction::Sigmoid, ActivationFunction::Softmax, ActivationFunction::LogSoftmax];
+        for function in functions {
+            assert_gradients(&move |g, x| Ok(g.activation(x[0], function)), &input);
+        }
+    }
+
+    #[test]
+    fn reduction_gradients() {
+        let input = [(normals(12, 10), vec![3, 4])];
+        for axis in [None, Some(0), Some(1)] {
+            assert_gradients(&move |g, x| g.sum(x[0], axis), &input);
+            assert_gradients(&move |g, x| g.mean(x[0], axis), &input);
+            assert_gradients(&move |g, x| g.max(x[0], axis), &input);
+        }
+        assert_gradients(&|g, x| g.reshape(x[0], vec![2, 6]).and_then(|r| g.transpose(r)), &input);
+    }
+
+    #[test]
+    fn reduction_values() {
+        let mut graph = Graph::new();
+        let a = graph.leaf(vec![1.0, 5.0, 3.0, 4.0, 2.0, 6.0], vec![2, 3]);
+        let sum = graph.sum(a, Some(0)).unwrap();
+        assert_eq!((graph.value(sum), graph.shape(sum)), (&[5.0, 7.0, 9.0][..], &[1, 3][..]));
+        let mean = graph.mean(a, Some(1)).unwrap();
+        assert_eq!((graph.value(mean), graph.shape(mean)), (&[3.0, 4.0][..], &[2, 1][..]));
+        let max = graph.max(a, None).unwrap();
+        assert_eq!((graph.value(max), graph.shape(max)), (&[6.0][..], &[][..]));
+
+        // The gradient of a tied maximum goes to the first one
+        let tied = graph.leaf(vec![2.0, 2.0, 1.0], vec![3]);
+        let max = graph.max(tied, None).unwrap();
+        graph.backward(max, None).unwrap();
+        assert_eq!(graph.gradient(tied), [1.0, 0.0, 0.0]);
+
+        assert_eq!(graph.sum(a, Some(2)).err().unwrap(), "Axis 2 is out of range for shape [2, 3]");
+        assert_eq!(graph.reshape(a, vec![4]).err().unwrap(), "Cannot reshape [2, 3] to [4]");
+        let empty = graph.leaf(Vec::new(), vec![2, 0]);
+        assert_eq!(graph.max(empty, Some(1)).err().unwrap(), "Cannot take the maximum over an empty axis");
+        assert!(graph.max(empty, Some(0)).is_ok());
+    }
+
+    #[test]
+    fn custom_loss_gradient() {
+        // Mean squared error of a linear model, written out as tensor operations
+        let build = |g: &mut Graph, x: &[usize]| -> Result<usize, String> {
+            let product = g.matmul(x[0], x[1], true)?;
+            let predictions = g.add(product, x[2])?;
+            let error = g.sub(predictions, x[3])?;
+            let squared = g.unary(error, Unary::Square);
+            g.mean(squared, None)
+        };
+        let inputs = [
+            (normals(8, 11), vec![4, 2]),
+            (normals(6, 12), vec![3, 2]),
+            (normals(3, 13), vec![3]),
+            (normals(12, 14), vec![4, 3]),
+        ];
+        assert_gradients(&build, &inputs);
+    }
+
+    #[test]
+    fn backward_needs_a_matching_seed() {
+        let mut graph = Graph::new();
+        let a = graph.leaf(vec![1.0, 2.0], vec![2]);
+        let b = graph.unary(a, Unary::Square);
+        let error = graph.backward(b, None).err().unwrap();
+        assert_eq!(error, "Backward from a tensor with several values needs a seed gradient");
+        assert_eq!(graph.backward(b, Some(vec![1.0])).err().unwrap(), "Seed gradient must match the output size");
+
+        // Nodes after the output get zero gradients
+        let c = graph.unary(b, Unary::Neg);
+        graph.backward(b, Some(vec![1.0, 1.0])).unwrap();
+        assert_eq!(graph.gradient(a), [2.0, 4.0]);
+        assert_eq!(graph.gradient(c), [0.0, 0.0]);
+    }
+
+    #[test]
+    fn tape_steps_parameters_towards_the_minimum() {
+        let mut tape = Tape::new(Some(0.1)).unwrap();
+        tape.graph.leaf(vec![0.0, 0.0], vec![2]);
+        tape.parameters = 1;
+
+        let target = [3.0, -1.0];
+        for _ in 0..100 {
+            let target = tape.graph.leaf(target.to_vec(), vec![2]);
+            let error = tape.sub(0, target).unwrap();
+            let squared = tape.square(error).unwrap();
+            let loss = tape.sum(squared, None).unwrap();
+            tape.backward(loss, None).unwrap();
+            tape.step();
+            tape.reset();
+        }
+
+        assert_eq!((tape.num_tensors(), tape.num_parameters()), (1, 1));
+        for (value, target) in tape.graph.value(0).iter().zip(target) {
+            assert!((value - target).abs() < 1e-6, "{:?}", tape.graph.value(0));
+        }
+    }
+}
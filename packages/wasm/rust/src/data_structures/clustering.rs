@@ -0,0 +1,598 @@
+use wasm_bindgen::prelude::*;
+use js_sys::{Array, Float64Array, Object, Reflect, Uint32Array};
+
+use super::machine_learning::read_rows;
+use super::neighbors::{DistanceMetric, NeighborAlgorithm, SpatialTree};
+use super::random::Rng;
+
+/// Cluster count from which centroid assignment goes through a KD-tree
+const INDEXED_CENTROIDS: usize = 32;
+
+/// Centroid initialisation strategies for k-means
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KMeansInit {
+    /// Spread initial centroids by sampling proportionally to squared distance
+    KMeansPlusPlus,
+    /// Pick k distinct points uniformly at random
+    Random,
+}
+
+/// K-means clustering implementation
+///
+/// Takes row-major data points and k, and returns cluster assignments and centroids.
+/// The optional arguments set the dimensionality (default 2), the initialisation (default
+/// k-means++), a seed for reproducible runs, the number of restarts keeping the lowest
+/// inertia (default 1), the convergence tolerance relative to the mean feature variance
+/// (default 1e-4) and a mini-batch size that switches to mini-batch k-means for large
+/// data. The result also carries the inertia (sum of squared distances to the assigned
+/// centroid) and the size of every cluster.
+/// This is much faster than using JavaScript, especially for large datasets.
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen]
+pub fn kmeans_clustering_f64(
+    data: &JsValue,
+    k: usize,
+    max_iterations: usize,
+    dims: Option<usize>,
+    init: Option<KMeansInit>,
+    seed: Option<u32>,
+    n_init: Option<usize>,
+    tolerance: Option<f64>,
+    batch_size: Option<usize>,
+) -> Result<JsValue, JsValue> {
+    let dims = dims.unwrap_or(2);
+    let (points, num_points) = read_rows(data, dims)?;
+
+    // Validate inputs
+    if k == 0 {
+        return Err(JsValue::from_str("k must be greater than 0"));
+    }
+
+    if num_points < k {
+        return Err(JsValue::from_str("Number of points must be greater than or equal to k"));
+    }
+
+    if batch_size == Some(0) {
+        return Err(JsValue::from_str("Batch size must be greater than 0"));
+    }
+
+    let options = KMeansOptions {
+        max_iterations,
+        init: init.unwrap_or(KMeansInit::KMeansPlusPlus),
+        n_init: n_init.unwrap_or(1).max(1),
+        tolerance: tolerance.unwrap_or(1e-4),
+        batch_size,
+    };
+    let mut rng = Rng::from_seed(seed);
+    let fitted = kmeans(&points, dims, k, &options, &mut rng);
+
+    // Create result object
+    let result = Object::new();
+
+    // Create assignments array
+    let assignments_array = Array::new_with_length(num_points as u32);
+    for (i, &cluster) in fitted.assignments.iter().enumerate() {
+        assignments_array.set(i as u32, JsValue::from_f64(cluster as f64));
+    }
+
+    let sizes: Vec<u32> = cluster_sizes(&fitted.assignments, k).iter().map(|&size| size as u32).collect();
+
+    // Set result properties
+    Reflect::set(&result, &JsValue::from_str("assignments"), &assignments_array)?;
+    Reflect::set(&result, &JsValue::from_str("centroids"), &Float64Array::from(&fitted.centroids[..]))?;
+    Reflect::set(&result, &JsValue::from_str("iterations"), &JsValue::from_f64(fitted.iterations as f64))?;
+    Reflect::set(&result, &JsValue::from_str("converged"), &JsValue::from_bool(fitted.converged))?;
+    Reflect::set(&result, &JsValue::from_str("inertia"), &JsValue::from_f64(fitted.inertia))?;
+    Reflect::set(&result, &JsValue::from_str("cluster_sizes"), &Uint32Array::from(&sizes[..]))?;
+
+    Ok(result.into())
+}
+
+/// Help choose k for k-means
+///
+/// Takes row-major data points, their dimensionality and an inclusive range of k, and runs
+/// k-means (k-means++, `n_init` restarts, default 3) for every k. Returns the inertia and
+/// mean silhouette coefficient per k, the elbow of the inertia curve (`elbow_k`, the point
+/// furthest below the line joining the first and last inertia) and the k with the highest
+/// silhouette (`best_k`). Silhouettes use a sample of at most 2000 points and are NaN for
+/// k = 1.
+#[wasm_bindgen]
+pub fn kmeans_select_k_f64(
+    data: &JsValue,
+    dims: usize,
+    k_min: usize,
+    k_max: usize,
+    max_iterations: usize,
+    seed: Option<u32>,
+    n_init: Option<usize>,
+) -> Result<JsValue, JsValue> {
+    let (points, num_points) = read_rows(data, dims)?;
+
+    // Validate inputs
+    if k_min == 0 || k_min > k_max {
+        return Err(JsValue::from_str("k range must satisfy 1 <= k_min <= k_max"));
+    }
+
+    if num_points < k_max {
+        return Err(JsValue::from_str("Number of points must be greater than or equal to k_max"));
+    }
+
+    let options = KMeansOptions {
+        max_iterations,
+        init: KMeansInit::KMeansPlusPlus,
+        n_init: n_init.unwrap_or(3).max(1),
+        tolerance: 1e-4,
+        batch_size: None,
+    };
+    let mut rng = Rng::from_seed(seed);
+
+    // Silhouettes are quadratic in the number of points, so score a fixed sample
+    let mut sample: Vec<usize> = (0..num_points).collect();
+    rng.shuffle(&mut sample);
+    sample.truncate(2000);
+    let sample_points: Vec<f64> = sample.iter().flat_map(|&i| points[i * dims..(i + 1) * dims].to_vec()).collect();
+
+    let ks: Vec<u32> = (k_min..=k_max).map(|k| k as u32).collect();
+    let mut inertias = Vec::with_capacity(ks.len());
+    let mut silhouettes = Vec::with_capacity(ks.len());
+
+    for k in k_min..=k_max {
+        let fitted = kmeans(&points, dims, k, &options, &mut rng);
+        inertias.push(fitted.inertia);
+
+        let silhouette = if k < 2 {
+            f64::NAN
+        } else {
+            let labels: Vec<usize> = sample.iter().map(|&i| fitted.assignments[i]).collect();
+            silhouette_score(&sample_points, dims, &labels)
+        };
+        silhouettes.push(silhouette);
+    }
+
+    let best_k = silhouettes
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| !s.is_nan())
+        .max_by(|a, b| a.1.partial_cmp(b.1).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(i, _)| k_min + i);
+
+    // Elbow: largest drop below the chord from the first to the last inertia
+    let count = inertias.len();
+    let mut elbow_k = k_min;
+    if count > 2 {
+        let (first, last) = (inertias[0], inertias[count - 1]);
+        let mut best_gap = f64::NEG_INFINITY;
+        for (i, &inertia) in inertias.iter().enumerate() {
+            let chord = first + (last - first) * i as f64 / (count - 1) as f64;
+            if chord - inertia > best_gap {
+                best_gap = chord - inertia;
+                elbow_k = k_min + i;
+            }
+        }
+    }
+
+    // Create result object
+    let result = Object::new();
+    Reflect::set(&result, &JsValue::from_str("k"), &Uint32Array::from(&ks[..]))?;
+    Reflect::set(&result, &JsValue::from_str("inertia"), &Float64Array::from(&inertias[..]))?;
+    Reflect::set(&result, &JsValue::from_str("silhouette"), &Float64Array::from(&silhouettes[..]))?;
+    Reflect::set(&result, &JsValue::from_str("elbow_k"), &JsValue::from_f64(elbow_k as f64))?;
+    Reflect::set(
+        &result,
+        &JsValue::from_str("best_k"),
+        &best_k.map_or(JsValue::NULL, |k| JsValue::from_f64(k as f64)),
+    )?;
+
+    Ok(result.into())
+}
+
+/// Settings shared by the k-means entry points
+pub(crate) struct KMeansOptions {
+    pub(crate) max_iterations: usize,
+    pub(crate) init: KMeansInit,
+    pub(crate) n_init: usize,
+    pub(crate) tolerance: f64,
+    pub(crate) batch_size: Option<usize>,
+}
+
+/// Outcome of a k-means fit
+pub(crate) struct KMeansFit {
+    pub(crate) centroids: Vec<f64>,
+    pub(crate) assignments: Vec<usize>,
+    pub(crate) inertia: f64,
+    pub(crate) iterations: usize,
+    pub(crate) converged: bool,
+}
+
+/// Run k-means `n_init` times and keep the fit with the lowest inertia
+pub(crate) fn kmeans(points: &[f64], dims: usize, k: usize, options: &KMeansOptions, rng: &mut Rng) -> KMeansFit {
+    // Convergence threshold scales with the spread of the data
+    let num_points = points.len() / dims;
+    let mut mean_variance = 0.0;
+    for d in 0..dims {
+        let mean = (0..num_points).map(|i| points[i * dims + d]).sum::<f64>() / num_points as f64;
+        mean_variance += (0..num_points).map(|i| (points[i * dims + d] - mean).powi(2)).sum::<f64>() / num_points as f64;
+    }
+    let threshold = options.tolerance * mean_variance / dims as f64;
+
+    let mut best: Option<KMeansFit> = None;
+    for _ in 0..options.n_init {
+        let initial = initial_centroids(points, dims, k, options.init, rng);
+        let fit = match options.batch_size {
+            Some(batch_size) => mini_batch(points, dims, initial, batch_size, options.max_iterations, threshold, rng),
+            None => lloyd(points, dims, initial, options.max_iterations, threshold),
+        };
+        if best.as_ref().is_none_or(|b| fit.inertia < b.inertia) {
+            best = Some(fit);
+        }
+    }
+
+    best.expect("n_init is at least 1")
+}
+
+/// Choose k starting centroids
+fn initial_centroids(points: &[f64], dims: usize, k: usize, init: KMeansInit, rng: &mut Rng) -> Vec<f64> {
+    let num_points = points.len() / dims;
+    let mut centroids = Vec::with_capacity(k * dims);
+
+    match init {
+        KMeansInit::Random => {
+            // Partial Fisher-Yates to draw k distinct points
+            let mut indices: Vec<usize> = (0..num_points).collect();
+            for i in 0..k {
+                let j = i + rng.below(num_points - i);
+                indices.swap(i, j);
+                centroids.extend_from_slice(&points[indices[i] * dims..(indices[i] + 1) * dims]);
+            }
+        }
+        KMeansInit::KMeansPlusPlus => {
+            let first = rng.below(num_points);
+            centroids.extend_from_slice(&points[first * dims..(first + 1) * dims]);
+
+            let mut distances: Vec<f64> = (0..num_points)
+                .map(|i| squared_distance(&points[i * dims..(i + 1) * dims], &centroids[..dims]))
+                .collect();
+
+            for _ in 1..k {
+                // Sample the next centroid with probability proportional to squared distance
+                let total: f64 = distances.iter().sum();
+                let next = if total > 0.0 {
+                    let mut target = rng.next_f64() * total;
+                    let mut chosen = num_points - 1;
+                    for (i, &distance) in distances.iter().enumerate() {
+                        target -= distance;
+                        if target < 0.0 {
+                            chosen = i;
+                            break;
+                        }
+                    }
+                    chosen
+                } else {
+                    rng.below(num_points)
+                };
+
+                let start = centroids.len();
+                centroids.extend_from_slice(&points[next * dims..(next + 1) * dims]);
+                for i in 0..num_points {
+                    let distance = squared_distance(&points[i * dims..(i + 1) * dims], &centroids[start..start + dims]);
+                    if distance < distances[i] {
+                        distances[i] = distance;
+                    }
+                }
+            }
+        }
+    }
+
+    centroids
+}
+
+/// Lloyd's algorithm from the given centroids
+fn lloyd(points: &[f64], dims: usize, mut centroids: Vec<f64>, max_iterations: usize, threshold: f64) -> KMeansFit {
+    let num_points = points.len() / dims;
+    let k = centroids.len() / dims;
+    let mut assignments = vec![usize::MAX; num_points];
+    let mut distances = vec![0.0; num_points];
+    let mut iterations = 0;
+    let mut converged = false;
+
+    while iterations < max_iterations {
+        // Assign points to clusters
+        let mut changed = false;
+        for (i, (cluster, distance)) in assign(points, dims, &centroids).into_iter().enumerate() {
+            distances[i] = distance;
+            if assignments[i] != cluster {
+                assignments[i] = cluster;
+                changed = true;
+            }
+        }
+
+        if !changed {
+            converged = true;
+            break;
+        }
+
+        // Update centroids
+        let mut sums = vec![0.0; k * dims];
+        let mut counts = vec![0usize; k];
+        for i in 0..num_points {
+            let cluster = assignments[i];
+            counts[cluster] += 1;
+            for d in 0..dims {
+                sums[cluster * dims + d] += points[i * dims + d];
+            }
+        }
+
+        let mut shift = 0.0;
+        for j in 0..k {
+            let updated: Vec<f64> = if counts[j] > 0 {
+                (0..dims).map(|d| sums[j * dims + d] / counts[j] as f64).collect()
+            } else {
+                // Re-seed an empty cluster with the point furthest from its centroid
+                let far = (0..num_points)
+                    .max_by(|&a, &b| distances[a].partial_cmp(&distances[b]).unwrap_or(std::cmp::Ordering::Equal))
+                    .unwrap_or(0);
+                distances[far] = 0.0;
+                points[far * dims..(far + 1) * dims].to_vec()
+            };
+            shift += squared_distance(&updated, &centroids[j * dims..(j + 1) * dims]);
+            centroids[j * dims..(j + 1) * dims].copy_from_slice(&updated);
+        }
+
+        iterations += 1;
+
+        if shift <= threshold {
+            converged = true;
+            break;
+        }
+    }
+
+    finish(points, dims, centroids, iterations, converged)
+}
+
+/// Mini-batch k-means (Sculley, 2010) with per-centroid learning rates
+fn mini_batch(
+    points: &[f64],
+    dims: usize,
+    mut centroids: Vec<f64>,
+    batch_size: usize,
+    max_iterations: usize,
+    threshold: f64,
+    rng: &mut Rng,
+) -> KMeansFit {
+    let num_points = points.len() / dims;
+    let k = centroids.len() / dims;
+    let mut counts = vec![0usize; k];
+    let mut iterations = 0;
+    let mut converged = false;
+
+    while iterations < max_iterations {
+        let previous = centroids.clone();
+
+        let batch: Vec<usize> = (0..batch_size).map(|_| rng.below(num_points)).collect();
+        let nearest: Vec<usize> = batch
+            .iter()
+            .map(|&i| nearest_centroid(&points[i * dims..(i + 1) * dims], &centroids, dims).0)
+            .collect();
+
+        for (&i, &cluster) in batch.iter().zip(&nearest) {
+            counts[cluster] += 1;
+            let rate = 1.0 / counts[cluster] as f64;
+            for d in 0..dims {
+                let centroid = &mut centroids[cluster * dims + d];
+                *centroid += rate * (points[i * dims + d] - *centroid);
+            }
+        }
+
+        iterations += 1;
+
+        if squared_distance(&previous, &centroids) <= threshold {
+            converged = true;
+            break;
+        }
+    }
+
+    finish(points, dims, centroids, iterations, converged)
+}
+
+/// Final assignment pass and inertia for a set of centroids
+fn finish(points: &[f64], dims: usize, centroids: Vec<f64>, iterations: usize, converged: bool) -> KMeansFit {
+    let mut assignments = Vec::with_capacity(points.len() / dims);
+    let mut inertia = 0.0;
+    for (cluster, distance) in assign(points, dims, &centroids) {
+        assignments.push(cluster);
+        inertia += distance;
+    }
+
+    KMeansFit { centroids, assignments, inertia, iterations, converged }
+}
+
+/// Closest centroid and squared distance for every point
+///
+/// With many clusters the centroids are indexed in a KD-tree instead of being scanned.
+fn assign(points: &[f64], dims: usize, centroids: &[f64]) -> Vec<(usize, f64)> {
+    let rows = points.chunks_exact(dims);
+    if centroids.len() / dims < INDEXED_CENTROIDS {
+        return rows.map(|point| nearest_centroid(point, centroids, dims)).collect();
+    }
+
+    let index = SpatialTree::build(centroids, dims, DistanceMetric::Euclidean, NeighborAlgorithm::KdTree, 4);
+    rows.map(|point| {
+        let (cluster, _) = index.nearest(point, 1)[0];
+        (cluster, squared_distance(point, &centroids[cluster * dims..(cluster + 1) * dims]))
+    })
+    .collect()
+}
+
+/// Index of and squared distance to the closest centroid
+pub(crate) fn nearest_centroid(point: &[f64], centroids: &[f64], dims: usize) -> (usize, f64) {
+    let mut best = (0, f64::INFINITY);
+    for (j, centroid) in centroids.chunks_exact(dims).enumerate() {
+        let distance = squared_distance(point, centroid);
+        if distance < best.1 {
+            best = (j, distance);
+        }
+    }
+    best
+}
+
+/// Squared Euclidean distance between two points
+pub(crate) fn squared_distance(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum()
+}
+
+/// Number of points assigned to each of `k` clusters
+pub(crate) fn cluster_sizes(assignments: &[usize], k: usize) -> Vec<usize> {
+    let mut sizes = vec![0; k];
+    for &cluster in assignments {
+        sizes[cluster] += 1;
+    }
+    sizes
+}
+
+/// Mean silhouette coefficient of a labelling (Euclidean distance)
+///
+/// Points in singleton clusters score 0, as in the original definition.
+pub(crate) fn silhouette_score(points: &[f64], dims: usize, labels: &[usize]) -> f64 {
+    let num_points = labels.len();
+    let num_clusters = labels.iter().max().map_or(0, |&m| m + 1);
+    let sizes = cluster_sizes(labels, num_clusters);
+    if sizes.iter().filter(|&&size| size > 0).count() < 2 {
+        return f64::NAN;
+    }
+
+    let mut total = 0.0;
+    let mut sums = vec![0.0; num_clusters];
+    for i in 0..num_points {
+        sums.iter_mut().for_each(|sum| *sum = 0.0);
+        let point = &points[i * dims..(i + 1) * dims];
+        for j in 0..num_points {
+            if i != j {
+                sums[labels[j]] += squared_distance(point, &points[j * dims..(j + 1) * dims]).sqrt();
+            }
+        }
+
+        let own = labels[i];
+        if sizes[own] < 2 {
+            continue;
+        }
+        let a = sums[own] / (sizes[own] - 1) as f64;
+        let b = (0..num_clusters)
+            .filter(|&c| c != own && sizes[c] > 0)
+            .map(|c| sums[c] / sizes[c] as f64)
+            .fold(f64::INFINITY, f64::min);
+        if a.max(b) > 0.0 {
+            total += (b - a) / a.max(b);
+        }
+    }
+
+    total / num_points as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CENTERS: [[f64; 2]; 3] = [[0.0, 0.0], [10.0, 0.0], [0.0, 10.0]];
+
+    /// Three tight, well separated blobs of 30 points each
+    fn blobs(rng: &mut Rng) -> Vec<f64> {
+        let mut points = Vec::new();
+        for center in CENTERS {
+            for _ in 0..30 {
+                points.push(center[0] + 0.5 * rng.next_normal());
+                points.push(center[1] + 0.5 * rng.next_normal());
+            }
+        }
+        points
+    }
+
+    fn options(init: KMeansInit, batch_size: Option<usize>) -> KMeansOptions {
+        KMeansOptions { max_iterations: 300, init, n_init: 5, tolerance: 1e-4, batch_size }
+    }
+
+    /// Every true center has a fitted centroid within `tolerance`
+    fn assert_recovers_centers(centroids: &[f64], tolerance: f64) {
+        for center in CENTERS {
+            let (_, distance) = nearest_centroid(&center, centroids, 2);
+            assert!(distance.sqrt() < tolerance, "{:?} not found in {:?}", center, centroids);
+        }
+    }
+
+    #[test]
+    fn lloyd_recovers_separated_blobs() {
+        for init in [KMeansInit::KMeansPlusPlus, KMeansInit::Random] {
+            let mut rng = Rng::new(7);
+            let points = blobs(&mut rng);
+            let fit = kmeans(&points, 2, 3, &options(init, None), &mut rng);
+
+            assert!(fit.converged);
+            assert_recovers_centers(&fit.centroids, 0.3);
+            assert_eq!(cluster_sizes(&fit.assignments, 3), vec![30, 30, 30]);
+            for blob in fit.assignments.chunks(30) {
+                assert!(blob.iter().all(|&cluster| cluster == blob[0]));
+            }
+        }
+    }
+
+    #[test]
+    fn inertia_is_the_sum_of_squared_distances() {
+        let mut rng = Rng::new(3);
+        let points = blobs(&mut rng);
+        let fit = kmeans(&points, 2, 3, &options(KMeansInit::KMeansPlusPlus, None), &mut rng);
+
+        let expected: f64 = points
+            .chunks_exact(2)
+            .zip(&fit.assignments)
+            .map(|(point, &cluster)| squared_distance(point, &fit.centroids[cluster * 2..cluster * 2 + 2]))
+            .sum();
+        assert!((fit.inertia - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn mini_batch_approaches_the_blob_centers() {
+        let mut rng = Rng::new(11);
+        let points = blobs(&mut rng);
+        let fit = kmeans(&points, 2, 3, &options(KMeansInit::KMeansPlusPlus, Some(32)), &mut rng);
+
+        assert_recovers_centers(&fit.centroids, 1.0);
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_fit() {
+        let points = blobs(&mut Rng::new(5));
+        let first = kmeans(&points, 2, 4, &options(KMeansInit::Random, None), &mut Rng::new(42));
+        let second = kmeans(&points, 2, 4, &options(KMeansInit::Random, None), &mut Rng::new(42));
+
+        assert_eq!(first.centroids, second.centroids);
+        assert_eq!(first.assignments, second.assignments);
+    }
+
+    #[test]
+    fn indexed_assignment_matches_a_linear_scan() {
+        let mut rng = Rng::new(13);
+        let points: Vec<f64> = (0..600).map(|_| rng.next_f64() * 100.0).collect();
+        let centroids: Vec<f64> = (0..2 * INDEXED_CENTROIDS + 10).map(|_| rng.next_f64() * 100.0).collect();
+
+        for (point, (cluster, distance)) in points.chunks_exact(3).zip(assign(&points, 3, &centroids)) {
+            let (_, expected) = nearest_centroid(point, &centroids, 3);
+            assert!((distance - expected).abs() < 1e-9);
+            assert!((squared_distance(point, &centroids[cluster * 3..cluster * 3 + 3]) - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn silhouette_of_known_labellings() {
+        // Two pairs on a line: a = 1, b = 10 and 9 -> s = 0.9 and 0.888...
+        let points = [0.0, 1.0, 10.0, 11.0];
+        let score = silhouette_score(&points, 1, &[0, 0, 1, 1]);
+        let expected = (2.0 * (1.0 - 1.0 / 10.5)) / 4.0 + (2.0 * (1.0 - 1.0 / 9.5)) / 4.0;
+        assert!((score - expected).abs() < 1e-12);
+
+        // Swapping the labelling makes every point worse off than in the other cluster
+        assert!(silhouette_score(&points, 1, &[0, 1, 0, 1]) < 0.0);
+        // Singletons score zero and a single cluster is undefined
+        assert_eq!(silhouette_score(&points, 1, &[0, 1, 2, 3]), 0.0);
+        assert!(silhouette_score(&points, 1, &[0, 0, 0, 0]).is_nan());
+    }
+}
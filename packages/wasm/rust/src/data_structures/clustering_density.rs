@@ -0,0 +1,466 @@
+use wasm_bindgen::prelude::*;
+use js_sys::{Float64Array, Int32Array, Object, Reflect, Uint8Array};
+
+use super::machine_learning::read_rows;
+use super::neighbors::{metric_distance, prepare_points, DistanceMetric, NeighborAlgorithm, SpatialTree};
+
+/// DBSCAN density-based clustering
+///
+/// Takes row-major data points, their dimensionality, the neighborhood radius `eps` and the
+/// minimum number of points (including the point itself) within `eps` for a core point,
+/// plus an optional metric (default Euclidean). Returns `labels` with -1 for noise,
+/// `core` flags (1 for core points) and `num_clusters`.
+/// Neighborhoods are answered by a KD-tree, so large datasets stay tractable.
+#[wasm_bindgen]
+pub fn dbscan_f64(
+    data: &JsValue,
+    dims: usize,
+    eps: f64,
+    min_samples: usize,
+    metric: Option<DistanceMetric>,
+) -> Result<JsValue, JsValue> {
+    let (points, num_points) = read_rows(data, dims)?;
+
+    // Validate inputs
+    if num_points == 0 {
+        return Err(JsValue::from_str("Data must not be empty"));
+    }
+
+    if eps.is_nan() || eps <= 0.0 {
+        return Err(JsValue::from_str("eps must be greater than 0"));
+    }
+
+    if min_samples == 0 {
+        return Err(JsValue::from_str("min_samples must be greater than 0"));
+    }
+
+    let (labels, core) = dbscan(&points, dims, eps, min_samples, metric.unwrap_or(DistanceMetric::Euclidean));
+    let num_clusters = labels.iter().max().map_or(0, |&m| (m + 1).max(0));
+    let core: Vec<u8> = core.iter().map(|&c| u8::from(c)).collect();
+
+    // Create result object
+    let result = Object::new();
+    Reflect::set(&result, &JsValue::from_str("labels"), &Int32Array::from(&labels[..]))?;
+    Reflect::set(&result, &JsValue::from_str("core"), &Uint8Array::from(&core[..]))?;
+    Reflect::set(&result, &JsValue::from_str("num_clusters"), &JsValue::from_f64(num_clusters as f64))?;
+
+    Ok(result.into())
+}
+
+/// HDBSCAN hierarchical density-based clustering
+///
+/// Takes row-major data points, their dimensionality and the smallest cluster size worth
+/// keeping, plus the neighbor count used for core distances (default `min_cluster_size`)
+/// and a metric (default Euclidean). Clusters are the most stable ones in the condensed
+/// cluster tree (excess of mass). Returns `labels` with -1 for noise, membership
+/// `probabilities`, the `stability` of every selected cluster and `num_clusters`.
+/// The minimum spanning tree is built with Prim's algorithm, which is quadratic in the
+/// number of points.
+#[wasm_bindgen]
+pub fn hdbscan_f64(
+    data: &JsValue,
+    dims: usize,
+    min_cluster_size: usize,
+    min_samples: Option<usize>,
+    metric: Option<DistanceMetric>,
+) -> Result<JsValue, JsValue> {
+    let (points, num_points) = read_rows(data, dims)?;
+
+    // Validate inputs
+    if min_cluster_size < 2 {
+        return Err(JsValue::from_str("min_cluster_size must be at least 2"));
+    }
+
+    let min_samples = min_samples.unwrap_or(min_cluster_size);
+    if min_samples == 0 || min_samples > num_points {
+        return Err(JsValue::from_str("min_samples must be between 1 and the number of points"));
+    }
+
+    let fit = hdbscan(&points, dims, min_cluster_size, min_samples, metric.unwrap_or(DistanceMetric::Euclidean));
+
+    // Create result object
+    let result = Object::new();
+    Reflect::set(&result, &JsValue::from_str("labels"), &Int32Array::from(&fit.labels[..]))?;
+    Reflect::set(&result, &JsValue::from_str("probabilities"), &Float64Array::from(&fit.probabilities[..]))?;
+    Reflect::set(&result, &JsValue::from_str("stability"), &Float64Array::from(&fit.stability[..]))?;
+    Reflect::set(&result, &JsValue::from_str("num_clusters"), &JsValue::from_f64(fit.stability.len() as f64))?;
+
+    Ok(result.into())
+}
+
+/// DBSCAN labels (-1 for noise) and core flags for validated row-major points
+pub(crate) fn dbscan(points: &[f64], dims: usize, eps: f64, min_samples: usize, metric: DistanceMetric) -> (Vec<i32>, Vec<bool>) {
+    let num_points = points.len() / dims;
+    let index = SpatialTree::build(points, dims, metric, NeighborAlgorithm::KdTree, 16);
+    let neighborhoods: Vec<Vec<usize>> = points
+        .chunks_exact(dims)
+        .map(|point| index.within(point, eps).into_iter().map(|(i, _)| i).collect())
+        .collect();
+    let core: Vec<bool> = neighborhoods.iter().map(|n| n.len() >= min_samples).collect();
+
+    // Expand clusters from unlabelled core points
+    let mut labels = vec![-1i32; num_points];
+    let mut cluster = 0;
+    let mut frontier = Vec::new();
+    for start in 0..num_points {
+        if labels[start] != -1 || !core[start] {
+            continue;
+        }
+
+        labels[start] = cluster;
+        frontier.push(start);
+        while let Some(i) = frontier.pop() {
+            for &j in &neighborhoods[i] {
+                if labels[j] == -1 {
+                    labels[j] = cluster;
+                    if core[j] {
+                        frontier.push(j);
+                    }
+                }
+            }
+        }
+        cluster += 1;
+    }
+
+    (labels, core)
+}
+
+/// Outcome of an HDBSCAN fit
+pub(crate) struct HdbscanFit {
+    pub(crate) labels: Vec<i32>,
+    pub(crate) probabilities: Vec<f64>,
+    pub(crate) stability: Vec<f64>,
+}
+
+/// HDBSCAN on validated row-major points
+pub(crate) fn hdbscan(points: &[f64], dims: usize, min_cluster_size: usize, min_samples: usize, metric: DistanceMetric) -> HdbscanFit {
+    let num_points = points.len() / dims;
+    if num_points < 2 {
+        return HdbscanFit { labels: vec![-1; num_points], probabilities: vec![0.0; num_points], stability: Vec::new() };
+    }
+
+    // Core distance: distance to the min_samples-th nearest point, counting the point itself
+    let index = SpatialTree::build(points, dims, metric, NeighborAlgorithm::KdTree, 16);
+    let core: Vec<f64> = points
+        .chunks_exact(dims)
+        .map(|point| index.nearest(point, min_samples).last().map_or(0.0, |&(_, d)| d))
+        .collect();
+
+    let edges = mutual_reachability_tree(points, dims, &core, metric);
+    let hierarchy = single_linkage(num_points, edges);
+    let condensed = condense(&hierarchy, num_points, min_cluster_size);
+    select_clusters(&condensed, num_points)
+}
+
+/// Prim's minimum spanning tree under the mutual reachability distance, as (a, b, weight)
+fn mutual_reachability_tree(points: &[f64], dims: usize, core: &[f64], metric: DistanceMetric) -> Vec<(usize, usize, f64)> {
+    let num_points = core.len();
+    let prepared = prepare_points(points, dims, metric);
+    let row = |i: usize| &prepared[i * dims..(i + 1) * dims];
+
+    let mut in_tree = vec![false; num_points];
+    let mut best = vec![f64::INFINITY; num_points];
+    let mut parent = vec![0; num_points];
+    let mut edges = Vec::with_capacity(num_points - 1);
+    let mut current = 0;
+    in_tree[0] = true;
+
+    for _ in 1..num_points {
+        let mut next = usize::MAX;
+        for j in 0..num_points {
+            if in_tree[j] {
+                continue;
+            }
+            let reach = metric_distance(row(current), row(j), metric).max(core[current]).max(core[j]);
+            if reach < best[j] {
+                best[j] = reach;
+                parent[j] = current;
+            }
+            if next == usize::MAX || best[j] < best[next] {
+                next = j;
+            }
+        }
+        in_tree[next] = true;
+        edges.push((parent[next], next, best[next]));
+        current = next;
+    }
+
+    edges
+}
+
+/// Merge in the single-linkage hierarchy: children (points below `n`, merges from `n`),
+/// distance and size
+struct Merge {
+    left: usize,
+    right: usize,
+    distance: f64,
+    size: usize,
+}
+
+/// Single-linkage dendrogram from minimum spanning tree edges
+fn single_linkage(num_points: usize, mut edges: Vec<(usize, usize, f64)>) -> Vec<Merge> {
+    edges.sort_by(|a, b| a.2.partial_cmp(&b.2).unwrap());
+
+    // Union-find whose roots remember their current dendrogram node and size
+    let mut parent: Vec<usize> = (0..num_points).collect();
+    let mut node: Vec<usize> = (0..num_points).collect();
+    let mut size = vec![1; num_points];
+    let mut merges = Vec::with_capacity(num_points - 1);
+
+    for (a, b, distance) in edges {
+        let (root_a, root_b) = (find(&mut parent, a), find(&mut parent, b));
+        merges.push(Merge { left: node[root_a], right: node[root_b], distance, size: size[root_a] + size[root_b] });
+        parent[root_b] = root_a;
+        size[root_a] += size[root_b];
+        node[root_a] = num_points + merges.len() - 1;
+    }
+
+    merges
+}
+
+pub(crate) fn find(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+/// Edge of the condensed tree: a cluster (numbered from `n`) and a child point or cluster
+/// leaving it at density `lambda = 1 / distance`
+struct CondensedEdge {
+    parent: usize,
+    child: usize,
+    lambda: f64,
+    size: usize,
+}
+
+/// Condense the dendrogram: splits where both sides keep `min_cluster_size` points create
+/// new clusters, everything smaller falls out of its parent as individual points
+fn condense(hierarchy: &[Merge], num_points: usize, min_cluster_size: usize) -> Vec<CondensedEdge> {
+    let root = num_points + hierarchy.len() - 1;
+    let size_of = |node: usize| if node < num_points { 1 } else { hierarchy[node - num_points].size };
+    let mut label = vec![0; root + 1];
+    let mut next_label = num_points + 1;
+    label[root] = num_points;
+
+    let mut condensed = Vec::new();
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        let merge = &hierarchy[node - num_points];
+        let lambda = if merge.distance > 0.0 { 1.0 / merge.distance } else { f64::INFINITY };
+        let parent = label[node];
+        let (left_size, right_size) = (size_of(merge.left), size_of(merge.right));
+
+        for (child, child_size, other_size) in [(merge.left, left_size, right_size), (merge.right, right_size, left_size)] {
+            if child_size >= min_cluster_size && other_size >= min_cluster_size {
+                // Genuine split: the child becomes a new cluster
+                label[child] = next_label;
+                next_label += 1;
+                condensed.push(CondensedEdge { parent, child: label[child], lambda, size: child_size });
+                stack.push(child);
+            } else if child_size >= min_cluster_size {
+                // The parent cluster carries on through this child
+                label[child] = parent;
+                stack.push(child);
+            } else {
+                for point in leaves(hierarchy, num_points, child) {
+                    condensed.push(CondensedEdge { parent, child: point, lambda, size: 1 });
+                }
+            }
+        }
+    }
+
+    condensed
+}
+
+/// Points under a dendrogram node
+fn leaves(hierarchy: &[Merge], num_points: usize, node: usize) -> Vec<usize> {
+    let mut points = Vec::new();
+    let mut stack = vec![node];
+    while let Some(node) = stack.pop() {
+        if node < num_points {
+            points.push(node);
+        } else {
+            stack.push(hierarchy[node - num_points].left);
+            stack.push(hierarchy[node - num_points].right);
+        }
+    }
+    points
+}
+
+/// Excess-of-mass cluster selection, labels and membership strengths
+fn select_clusters(condensed: &[CondensedEdge], num_points: usize) -> HdbscanFit {
+    let num_clusters = condensed.iter().map(|e| e.parent.max(e.child)).max().map_or(num_points + 1, |m| m + 1) - num_points;
+
+    // Birth density of every cluster (the root is born at 0)
+    let mut birth = vec![0.0; num_clusters];
+    let mut parent_of = vec![usize::MAX; num_clusters];
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); num_clusters];
+    for edge in condensed.iter().filter(|e| e.child >= num_points) {
+        let child = edge.child - num_points;
+        birth[child] = edge.lambda;
+        parent_of[child] = edge.parent - num_points;
+        children[edge.parent - num_points].push(child);
+    }
+
+    // Stability: sum of (lambda - birth) over everything leaving the cluster
+    let mut stability = vec![0.0; num_clusters];
+    for edge in condensed {
+        let cluster = edge.parent - num_points;
+        let persistence = edge.lambda - birth[cluster];
+        if persistence.is_finite() {
+            stability[cluster] += persistence * edge.size as f64;
+        }
+    }
+
+    // Children are numbered after their parents, so a reverse sweep sees them first
+    let mut selected = vec![true; num_clusters];
+    selected[0] = false;
+    let mut subtree = stability.clone();
+    for cluster in (1..num_clusters).rev() {
+        let children_total: f64 = children[cluster].iter().map(|&c| subtree[c]).sum();
+        if children_total > stability[cluster] {
+            selected[cluster] = false;
+            subtree[cluster] = children_total;
+        } else {
+            let mut stack = children[cluster].clone();
+            while let Some(descendant) = stack.pop() {
+                selected[descendant] = false;
+                stack.extend(&children[descendant]);
+            }
+        }
+    }
+
+    // Label every point by its selected ancestor cluster
+    let selected_ids: Vec<usize> = (0..num_clusters).filter(|&c| selected[c]).collect();
+    let mut cluster_label = vec![-1i32; num_clusters];
+    for (label, &cluster) in selected_ids.iter().enumerate() {
+        cluster_label[cluster] = label as i32;
+    }
+    let owner = |mut cluster: usize| -> i32 {
+        loop {
+            if selected[cluster] {
+                return cluster_label[cluster];
+            }
+            if parent_of[cluster] == usize::MAX {
+                return -1;
+            }
+            cluster = parent_of[cluster];
+        }
+    };
+
+    let mut labels = vec![-1i32; num_points];
+    let mut point_lambda = vec![0.0; num_points];
+    let mut max_lambda = vec![0.0f64; selected_ids.len()];
+    for edge in condensed.iter().filter(|e| e.child < num_points) {
+        let label = owner(edge.parent - num_points);
+        labels[edge.child] = label;
+        point_lambda[edge.child] = edge.lambda;
+        if label >= 0 {
+            max_lambda[label as usize] = max_lambda[label as usize].max(edge.lambda);
+        }
+    }
+
+    let probabilities = labels
+        .iter()
+        .zip(&point_lambda)
+        .map(|(&label, &lambda)| {
+            if label < 0 {
+                0.0
+            } else {
+                let max = max_lambda[label as usize];
+                if max.is_infinite() || max <= 0.0 {
+                    1.0
+                } else {
+                    lambda.min(max) / max
+                }
+            }
+        })
+        .collect();
+
+    HdbscanFit { labels, probabilities, stability: selected_ids.iter().map(|&c| stability[c]).collect() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::random::Rng;
+
+    /// Two Gaussian blobs of 40 points around (0, 0) and (8, 8), then two far outliers
+    fn blobs_with_outliers() -> Vec<f64> {
+        let mut rng = Rng::new(3);
+        let mut points = Vec::new();
+        for center in [0.0, 8.0] {
+            for _ in 0..40 {
+                points.push(center + 0.5 * rng.next_normal());
+                points.push(center + 0.5 * rng.next_normal());
+            }
+        }
+        points.extend([30.0, -30.0, -30.0, 30.0]);
+        points
+    }
+
+    #[test]
+    fn dbscan_labels_a_chain_with_border_and_noise() {
+        // Five evenly spaced points, a lone point and a separate pair
+        let points = [0.0, 1.0, 2.0, 3.0, 4.0, 10.0, 20.0, 20.5];
+        let (labels, core) = dbscan(&points, 1, 1.0, 3, DistanceMetric::Euclidean);
+
+        assert_eq!(labels, vec![0, 0, 0, 0, 0, -1, -1, -1]);
+        // End points have only one neighbor besides themselves and are border points
+        assert_eq!(core, vec![false, true, true, true, false, false, false, false]);
+
+        let (labels, _) = dbscan(&points, 1, 1.0, 2, DistanceMetric::Euclidean);
+        assert_eq!(labels, vec![0, 0, 0, 0, 0, -1, 1, 1]);
+    }
+
+    #[test]
+    fn dbscan_separates_blobs() {
+        let points = blobs_with_outliers();
+        let (labels, _) = dbscan(&points, 2, 1.5, 4, DistanceMetric::Euclidean);
+
+        assert!(labels[..40].iter().all(|&l| l == labels[0]));
+        assert!(labels[40..80].iter().all(|&l| l == labels[40]));
+        assert_ne!(labels[0], labels[40]);
+        assert_eq!(&labels[80..], &[-1, -1]);
+    }
+
+    #[test]
+    fn hdbscan_finds_two_clusters_and_noise() {
+        let points = blobs_with_outliers();
+        let fit = hdbscan(&points, 2, 10, 5, DistanceMetric::Euclidean);
+
+        assert_eq!(fit.stability.len(), 2);
+        assert!(fit.stability.iter().all(|&s| s > 0.0));
+        assert!(labels_agree(&fit.labels[..40]) && labels_agree(&fit.labels[40..80]));
+        assert!(fit.labels[0] >= 0 && fit.labels[40] >= 0 && fit.labels[0] != fit.labels[40]);
+        assert_eq!(&fit.labels[80..], &[-1, -1]);
+
+        for (&label, &p) in fit.labels.iter().zip(&fit.probabilities) {
+            assert!((0.0..=1.0).contains(&p));
+            assert_eq!(label < 0, p == 0.0);
+        }
+        assert!(fit.probabilities.contains(&1.0));
+    }
+
+    /// Clustered points share a label, allowing a few points to fall out as noise
+    fn labels_agree(labels: &[i32]) -> bool {
+        let clustered: Vec<i32> = labels.iter().copied().filter(|&l| l >= 0).collect();
+        clustered.len() >= labels.len() - 4 && clustered.iter().all(|&l| l == clustered[0])
+    }
+
+    #[test]
+    fn hdbscan_with_too_few_points_is_all_noise() {
+        let fit = hdbscan(&[1.0, 2.0], 2, 2, 1, DistanceMetric::Euclidean);
+        assert_eq!(fit.labels, vec![-1]);
+        assert!(fit.stability.is_empty());
+    }
+
+    #[test]
+    fn find_compresses_paths() {
+        let mut parent = vec![1, 2, 3, 3];
+        assert_eq!(find(&mut parent, 0), 3);
+        assert_eq!(find(&mut parent, 1), 3);
+    }
+}
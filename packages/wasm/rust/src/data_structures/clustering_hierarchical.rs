@@ -0,0 +1,268 @@
+use wasm_bindgen::prelude::*;
+use js_sys::{Float64Array, Int32Array, Object, Reflect};
+
+use super::clustering_density::find;
+use super::machine_learning::read_rows;
+use super::neighbors::{metric_distance, prepare_points, DistanceMetric};
+
+/// Cluster distance updates for agglomerative clustering
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Linkage {
+    /// Closest pair of members
+    Single,
+    /// Farthest pair of members
+    Complete,
+    /// Mean distance over all member pairs (UPGMA)
+    Average,
+    /// Smallest increase in within-cluster variance (Euclidean only)
+    Ward,
+}
+
+/// Agglomerative hierarchical clustering
+///
+/// Takes row-major data points, their dimensionality and the linkage, plus an optional
+/// metric (default Euclidean) and either a number of clusters or a distance threshold at
+/// which to cut the tree. Returns the dendrogram as a `linkage` matrix in SciPy layout
+/// (one row of `[cluster_a, cluster_b, distance, size]` per merge, where ids at or above
+/// the number of points refer to earlier merges) and, when a cut is requested, flat
+/// `labels` numbered in order of first appearance.
+/// Uses the nearest-neighbor chain algorithm on a full distance matrix, so memory is
+/// quadratic in the number of points.
+#[wasm_bindgen]
+pub fn agglomerative_clustering_f64(
+    data: &JsValue,
+    dims: usize,
+    linkage: Linkage,
+    metric: Option<DistanceMetric>,
+    num_clusters: Option<usize>,
+    distance_threshold: Option<f64>,
+) -> Result<JsValue, JsValue> {
+    let (points, num_points) = read_rows(data, dims)?;
+    let metric = metric.unwrap_or(DistanceMetric::Euclidean);
+
+    // Validate inputs
+    if num_points == 0 {
+        return Err(JsValue::from_str("Data must not be empty"));
+    }
+
+    if linkage == Linkage::Ward && metric != DistanceMetric::Euclidean {
+        return Err(JsValue::from_str("Ward linkage requires the Euclidean metric"));
+    }
+
+    if num_clusters.is_some_and(|k| k == 0 || k > num_points) {
+        return Err(JsValue::from_str("Number of clusters must be between 1 and the number of points"));
+    }
+
+    let merges = agglomerate(&points, dims, linkage, metric);
+    let matrix: Vec<f64> = merges.iter().flat_map(|&(a, b, distance, size)| [a as f64, b as f64, distance, size as f64]).collect();
+
+    // Create result object
+    let result = Object::new();
+    Reflect::set(&result, &JsValue::from_str("linkage"), &Float64Array::from(&matrix[..]))?;
+
+    let applied = match (num_clusters, distance_threshold) {
+        (Some(k), _) => Some(num_points - k),
+        (None, Some(threshold)) => Some(merges.iter().take_while(|m| m.2 <= threshold).count()),
+        (None, None) => None,
+    };
+    if let Some(applied) = applied {
+        let labels = cut_tree(&merges, num_points, applied);
+        Reflect::set(&result, &JsValue::from_str("labels"), &Int32Array::from(&labels[..]))?;
+    }
+
+    Ok(result.into())
+}
+
+/// Dendrogram of validated row-major points as SciPy-style (a, b, distance, size) merges
+/// sorted by distance
+pub(crate) fn agglomerate(points: &[f64], dims: usize, linkage: Linkage, metric: DistanceMetric) -> Vec<(usize, usize, f64, usize)> {
+    let num_points = points.len() / dims;
+    let prepared = prepare_points(points, dims, metric);
+
+    // Ward works on squared distances so that the Lance-Williams update is exact
+    let mut distances = vec![0.0; num_points * num_points];
+    for i in 0..num_points {
+        for j in (i + 1)..num_points {
+            let mut d = metric_distance(&prepared[i * dims..(i + 1) * dims], &prepared[j * dims..(j + 1) * dims], metric);
+            if linkage == Linkage::Ward {
+                d *= d;
+            }
+            distances[i * num_points + j] = d;
+            distances[j * num_points + i] = d;
+        }
+    }
+
+    // Nearest-neighbor chain: merge reciprocal nearest neighbors as they are found
+    let mut active = vec![true; num_points];
+    let mut sizes = vec![1usize; num_points];
+    let mut chain: Vec<usize> = Vec::new();
+    let mut merges: Vec<(usize, usize, f64)> = Vec::with_capacity(num_points.saturating_sub(1));
+
+    while merges.len() + 1 < num_points {
+        if chain.is_empty() {
+            chain.push(active.iter().position(|&a| a).unwrap());
+        }
+
+        let (a, b) = loop {
+            let current = chain[chain.len() - 1];
+            let previous = if chain.len() > 1 { Some(chain[chain.len() - 2]) } else { None };
+
+            // Prefer the previous chain element on ties so the chain always terminates
+            let mut nearest = previous;
+            let mut best = previous.map_or(f64::INFINITY, |p| distances[current * num_points + p]);
+            for other in 0..num_points {
+                if active[other] && other != current && distances[current * num_points + other] < best {
+                    best = distances[current * num_points + other];
+                    nearest = Some(other);
+                }
+            }
+
+            // Distances that overflow to infinity never beat the initial bound
+            let nearest = nearest.unwrap_or_else(|| (0..num_points).find(|&other| active[other] && other != current).unwrap());
+            if Some(nearest) == previous {
+                chain.truncate(chain.len() - 2);
+                break (current, nearest);
+            }
+            chain.push(nearest);
+        };
+
+        let d_ab = distances[a * num_points + b];
+        merges.push((a, b, d_ab));
+
+        // Lance-Williams update; the merged cluster lives in slot b
+        let (size_a, size_b) = (sizes[a] as f64, sizes[b] as f64);
+        for k in 0..num_points {
+            if !active[k] || k == a || k == b {
+                continue;
+            }
+            let (d_ka, d_kb) = (distances[k * num_points + a], distances[k * num_points + b]);
+            let updated = match linkage {
+                Linkage::Single => d_ka.min(d_kb),
+                Linkage::Complete => d_ka.max(d_kb),
+                Linkage::Average => (size_a * d_ka + size_b * d_kb) / (size_a + size_b),
+                Linkage::Ward => {
+                    let size_k = sizes[k] as f64;
+                    let d = ((size_a + size_k) * d_ka + (size_b + size_k) * d_kb - size_k * d_ab) / (size_a + size_b + size_k);
+                    // Infinite inputs give inf - inf; the merged distance is still infinite
+                    if d.is_nan() { f64::INFINITY } else { d }
+                }
+            };
+            distances[k * num_points + b] = updated;
+            distances[b * num_points + k] = updated;
+        }
+        active[a] = false;
+        sizes[b] += sizes[a];
+    }
+
+    if linkage == Linkage::Ward {
+        merges.iter_mut().for_each(|m| m.2 = m.2.max(0.0).sqrt());
+    }
+
+    // Sort by height and renumber clusters the way SciPy does
+    merges.sort_by(|x, y| x.2.partial_cmp(&y.2).unwrap());
+    let mut parent: Vec<usize> = (0..num_points).collect();
+    let mut cluster_id: Vec<usize> = (0..num_points).collect();
+    let mut cluster_size = vec![1usize; num_points];
+    merges
+        .into_iter()
+        .enumerate()
+        .map(|(step, (a, b, distance))| {
+            let (root_a, root_b) = (find(&mut parent, a), find(&mut parent, b));
+            let (id_a, id_b) = (cluster_id[root_a], cluster_id[root_b]);
+            let size = cluster_size[root_a] + cluster_size[root_b];
+            parent[root_a] = root_b;
+            cluster_id[root_b] = num_points + step;
+            cluster_size[root_b] = size;
+            (id_a.min(id_b), id_a.max(id_b), distance, size)
+        })
+        .collect()
+}
+
+/// Flat labels after applying the first `applied` merges of a sorted dendrogram
+pub(crate) fn cut_tree(merges: &[(usize, usize, f64, usize)], num_points: usize, applied: usize) -> Vec<i32> {
+    let mut parent: Vec<usize> = (0..num_points + merges.len()).collect();
+    for (step, &(a, b, _, _)) in merges.iter().take(applied).enumerate() {
+        parent[a] = num_points + step;
+        parent[b] = num_points + step;
+    }
+
+    let mut labels = vec![-1i32; num_points];
+    let mut root_label = vec![-1i32; parent.len()];
+    let mut next = 0;
+    for (i, label) in labels.iter_mut().enumerate() {
+        let root = find(&mut parent, i);
+        if root_label[root] < 0 {
+            root_label[root] = next;
+            next += 1;
+        }
+        *label = root_label[root];
+    }
+    labels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINE: [f64; 4] = [0.0, 1.0, 3.0, 7.0];
+
+    fn heights(merges: &[(usize, usize, f64, usize)]) -> Vec<f64> {
+        merges.iter().map(|m| m.2).collect()
+    }
+
+    fn assert_heights(merges: &[(usize, usize, f64, usize)], expected: &[f64]) {
+        for (height, expected) in heights(merges).iter().zip(expected) {
+            assert!((height - expected).abs() < 1e-12, "{:?} vs {:?}", merges, expected);
+        }
+    }
+
+    #[test]
+    fn single_linkage_uses_scipy_numbering() {
+        let merges = agglomerate(&LINE, 1, Linkage::Single, DistanceMetric::Euclidean);
+        assert_eq!(merges, vec![(0, 1, 1.0, 2), (2, 4, 2.0, 3), (3, 5, 4.0, 4)]);
+    }
+
+    #[test]
+    fn linkage_heights_on_a_line() {
+        assert_heights(&agglomerate(&LINE, 1, Linkage::Complete, DistanceMetric::Euclidean), &[1.0, 3.0, 7.0]);
+        assert_heights(&agglomerate(&LINE, 1, Linkage::Average, DistanceMetric::Euclidean), &[1.0, 2.5, 17.0 / 3.0]);
+
+        // Ward height: sqrt(2 n_a n_b / (n_a + n_b)) times the distance between centroids
+        let ward = agglomerate(&LINE, 1, Linkage::Ward, DistanceMetric::Euclidean);
+        let last = (2.0 * 3.0 / 4.0f64).sqrt() * (7.0 - 4.0 / 3.0);
+        assert_heights(&ward, &[1.0, (25.0f64 / 3.0).sqrt(), last]);
+        assert_eq!(ward[2].3, 4);
+    }
+
+    #[test]
+    fn cut_tree_recovers_separated_groups() {
+        let points = [0.0, 0.0, 0.2, 0.1, 10.0, 10.0, 10.1, 9.9, 0.1, 0.3, 9.8, 10.2];
+        for linkage in [Linkage::Single, Linkage::Complete, Linkage::Average, Linkage::Ward] {
+            let merges = agglomerate(&points, 2, linkage, DistanceMetric::Euclidean);
+            assert_eq!(merges.len(), 5);
+            assert!(heights(&merges).windows(2).all(|w| w[0] <= w[1]));
+            assert_eq!(cut_tree(&merges, 6, 4), vec![0, 0, 1, 1, 0, 1]);
+        }
+
+        let merges = agglomerate(&points, 2, Linkage::Single, DistanceMetric::Euclidean);
+        assert_eq!(cut_tree(&merges, 6, 0), vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(cut_tree(&merges, 6, 5), vec![0; 6]);
+    }
+
+    #[test]
+    fn overflowing_distances_still_merge_everything() {
+        // Every pairwise distance overflows to infinity
+        for linkage in [Linkage::Single, Linkage::Complete, Linkage::Average, Linkage::Ward] {
+            let merges = agglomerate(&[-1e308, 0.0, 1e308], 1, linkage, DistanceMetric::Euclidean);
+            assert_eq!(merges.len(), 2);
+            assert_eq!(merges[1].3, 3);
+            assert!(merges.iter().all(|m| m.2 == f64::INFINITY), "{:?}: {:?}", linkage, merges);
+        }
+    }
+
+    #[test]
+    fn single_point_has_no_merges() {
+        assert!(agglomerate(&[1.0, 2.0], 2, Linkage::Average, DistanceMetric::Euclidean).is_empty());
+    }
+}
@@ -0,0 +1,449 @@
+use wasm_bindgen::prelude::*;
+use js_sys::Float64Array;
+
+use super::clustering::{kmeans, KMeansInit, KMeansOptions};
+use super::linalg::cholesky;
+use super::machine_learning::read_rows;
+use super::random::Rng;
+
+/// Covariance structure of the mixture components
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CovarianceType {
+    /// Each component has its own full covariance matrix
+    Full,
+    /// Each component has its own per-feature variances
+    Diagonal,
+    /// Each component has a single variance shared by all features
+    Spherical,
+}
+
+/// Gaussian mixture model fitted by expectation-maximisation
+#[wasm_bindgen]
+pub struct GaussianMixture {
+    dims: usize,
+    covariance_type: CovarianceType,
+    weights: Vec<f64>,
+    /// Row-major `num_components x dims`
+    means: Vec<f64>,
+    /// `dims x dims`, `dims` or 1 values per component depending on the covariance type
+    covariances: Vec<f64>,
+    converged: bool,
+    iterations: usize,
+    log_likelihood: f64,
+}
+
+#[wasm_bindgen]
+impl GaussianMixture {
+    /// Fit a mixture to row-major data
+    ///
+    /// Takes the data, its dimensionality and the number of components, plus the covariance
+    /// type (default full), maximum EM iterations (default 100), the tolerance on the change
+    /// in mean log-likelihood (default 1e-3), a ridge added to every variance (default 1e-6)
+    /// and a seed for the k-means initialisation.
+    #[allow(clippy::too_many_arguments)]
+    pub fn fit(
+        data: &JsValue,
+        dims: usize,
+        num_components: usize,
+        covariance_type: Option<CovarianceType>,
+        max_iterations: Option<usize>,
+        tolerance: Option<f64>,
+        regularization: Option<f64>,
+        seed: Option<u32>,
+    ) -> Result<GaussianMixture, JsValue> {
+        let (points, num_points) = read_rows(data, dims)?;
+
+        // Validate inputs
+        if num_components == 0 || num_components > num_points {
+            return Err(JsValue::from_str("Number of components must be between 1 and the number of points"));
+        }
+
+        let regularization = regularization.unwrap_or(1e-6);
+        if regularization < 0.0 {
+            return Err(JsValue::from_str("Regularization must be non-negative"));
+        }
+
+        GaussianMixture::from_values(
+            &points,
+            dims,
+            num_components,
+            covariance_type.unwrap_or(CovarianceType::Full),
+            max_iterations.unwrap_or(100),
+            tolerance.unwrap_or(1e-3),
+            regularization,
+            &mut Rng::from_seed(seed),
+        )
+        .map_err(|message| JsValue::from_str(&message))
+    }
+
+    /// Most likely component of every row
+    pub fn predict(&self, data: &JsValue) -> Result<JsValue, JsValue> {
+        let (points, _) = read_rows(data, self.dims)?;
+        let k = self.weights.len();
+        let responsibilities = self.responsibilities(&points)?.0;
+        let labels: Vec<f64> = responsibilities
+            .chunks_exact(k)
+            .map(|row| (0..k).fold(0, |best, c| if row[c] > row[best] { c } else { best }) as f64)
+            .collect();
+        Ok(Float64Array::from(&labels[..]).into())
+    }
+
+    /// Row-major posterior probability of every component
+    pub fn predict_proba(&self, data: &JsValue) -> Result<JsValue, JsValue> {
+        let (points, _) = read_rows(data, self.dims)?;
+        Ok(Float64Array::from(&self.responsibilities(&points)?.0[..]).into())
+    }
+
+    /// Log-likelihood of every row under the mixture
+    pub fn score_samples(&self, data: &JsValue) -> Result<JsValue, JsValue> {
+        let (points, _) = read_rows(data, self.dims)?;
+        Ok(Float64Array::from(&self.responsibilities(&points)?.1[..]).into())
+    }
+
+    /// Bayesian information criterion on row-major data (lower is better)
+    pub fn bic(&self, data: &JsValue) -> Result<f64, JsValue> {
+        let (points, num_points) = read_rows(data, self.dims)?;
+        let total: f64 = self.responsibilities(&points)?.1.iter().sum();
+        Ok(-2.0 * total + self.num_parameters() as f64 * (num_points as f64).ln())
+    }
+
+    /// Akaike information criterion on row-major data (lower is better)
+    pub fn aic(&self, data: &JsValue) -> Result<f64, JsValue> {
+        let (points, _) = read_rows(data, self.dims)?;
+        let total: f64 = self.responsibilities(&points)?.1.iter().sum();
+        Ok(-2.0 * total + 2.0 * self.num_parameters() as f64)
+    }
+
+    /// Mixing weight of every component
+    #[wasm_bindgen(getter)]
+    pub fn weights(&self) -> Float64Array {
+        Float64Array::from(&self.weights[..])
+    }
+
+    /// Component means, one row of `dims` values per component
+    #[wasm_bindgen(getter)]
+    pub fn means(&self) -> Float64Array {
+        Float64Array::from(&self.means[..])
+    }
+
+    /// Component covariances: `dims x dims` matrices, `dims` variances or one variance per
+    /// component depending on the covariance type
+    #[wasm_bindgen(getter)]
+    pub fn covariances(&self) -> Float64Array {
+        Float64Array::from(&self.covariances[..])
+    }
+
+    /// Whether EM stopped because the log-likelihood stabilised
+    #[wasm_bindgen(getter)]
+    pub fn converged(&self) -> bool {
+        self.converged
+    }
+
+    /// Number of EM iterations performed
+    #[wasm_bindgen(getter)]
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    /// Mean log-likelihood of the training data
+    #[wasm_bindgen(getter)]
+    pub fn log_likelihood(&self) -> f64 {
+        self.log_likelihood
+    }
+}
+
+impl GaussianMixture {
+    /// Run EM on validated row-major data from a k-means initialisation
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn from_values(
+        points: &[f64],
+        dims: usize,
+        num_components: usize,
+        covariance_type: CovarianceType,
+        max_iterations: usize,
+        tolerance: f64,
+        regularization: f64,
+        rng: &mut Rng,
+    ) -> Result<GaussianMixture, String> {
+        let num_points = points.len() / dims;
+        let options = KMeansOptions {
+            max_iterations: 20,
+            init: KMeansInit::KMeansPlusPlus,
+            n_init: 1,
+            tolerance: 1e-4,
+            batch_size: None,
+        };
+        let initial = kmeans(points, dims, num_components, &options, rng);
+
+        let mut responsibilities = vec![0.0; num_points * num_components];
+        for (i, &cluster) in initial.assignments.iter().enumerate() {
+            responsibilities[i * num_components + cluster] = 1.0;
+        }
+
+        let mut model = GaussianMixture {
+            dims,
+            covariance_type,
+            weights: Vec::new(),
+            means: Vec::new(),
+            covariances: Vec::new(),
+            converged: false,
+            iterations: 0,
+            log_likelihood: f64::NEG_INFINITY,
+        };
+        model.maximise(points, &responsibilities, regularization);
+
+        while model.iterations < max_iterations {
+            let (updated, log_densities) = model.responsibilities(points)?;
+            let log_likelihood = log_densities.iter().sum::<f64>() / num_points as f64;
+            model.iterations += 1;
+
+            let change = (log_likelihood - model.log_likelihood).abs();
+            model.log_likelihood = log_likelihood;
+            if change < tolerance {
+                model.converged = true;
+                break;
+            }
+
+            model.maximise(points, &updated, regularization);
+        }
+
+        Ok(model)
+    }
+
+    /// M-step: weights, means and covariances from row-major responsibilities
+    fn maximise(&mut self, points: &[f64], responsibilities: &[f64], regularization: f64) {
+        let dims = self.dims;
+        let num_points = points.len() / dims;
+        let k = responsibilities.len() / num_points;
+
+        // A tiny floor keeps empty components from dividing by zero
+        let totals: Vec<f64> = (0..k)
+            .map(|c| (0..num_points).map(|i| responsibilities[i * k + c]).sum::<f64>() + 10.0 * f64::EPSILON)
+            .collect();
+        self.weights = totals.iter().map(|t| t / num_points as f64).collect();
+
+        self.means = vec![0.0; k * dims];
+        for (i, point) in points.chunks_exact(dims).enumerate() {
+            for c in 0..k {
+                let r = responsibilities[i * k + c] / totals[c];
+                for (mean, &x) in self.means[c * dims..(c + 1) * dims].iter_mut().zip(point) {
+                    *mean += r * x;
+                }
+            }
+        }
+
+        let block = self.covariance_block();
+        self.covariances = vec![0.0; k * block];
+        for (i, point) in points.chunks_exact(dims).enumerate() {
+            for c in 0..k {
+                let r = responsibilities[i * k + c] / totals[c];
+                let mean = &self.means[c * dims..(c + 1) * dims];
+                let covariance = &mut self.covariances[c * block..(c + 1) * block];
+                match self.covariance_type {
+                    CovarianceType::Full => {
+                        for a in 0..dims {
+                            for b in 0..dims {
+                                covariance[a * dims + b] += r * (point[a] - mean[a]) * (point[b] - mean[b]);
+                            }
+                        }
+                    }
+                    CovarianceType::Diagonal => {
+                        for d in 0..dims {
+                            covariance[d] += r * (point[d] - mean[d]).powi(2);
+                        }
+                    }
+                    CovarianceType::Spherical => {
+                        covariance[0] += r * (0..dims).map(|d| (point[d] - mean[d]).powi(2)).sum::<f64>() / dims as f64;
+                    }
+                }
+            }
+        }
+
+        for covariance in self.covariances.chunks_exact_mut(block) {
+            match self.covariance_type {
+                CovarianceType::Full => (0..dims).for_each(|d| covariance[d * dims + d] += regularization),
+                _ => covariance.iter_mut().for_each(|v| *v += regularization),
+            }
+        }
+    }
+
+    /// Row-major responsibilities and per-row log-likelihoods
+    fn responsibilities(&self, points: &[f64]) -> Result<(Vec<f64>, Vec<f64>), String> {
+        let dims = self.dims;
+        let k = self.weights.len();
+        let block = self.covariance_block();
+        let log_2pi = (2.0 * std::f64::consts::PI).ln();
+
+        // Per component: Cholesky factor (or variances) and log-determinant
+        let mut factors = Vec::with_capacity(k);
+        for covariance in self.covariances.chunks_exact(block) {
+            let (factor, log_det) = match self.covariance_type {
+                CovarianceType::Full => {
+                    let l = cholesky(covariance, dims)
+                        .ok_or_else(|| "Covariance matrix is singular; increase the regularization".to_string())?;
+                    let log_det = 2.0 * (0..dims).map(|d| l[d * dims + d].ln()).sum::<f64>();
+                    (l, log_det)
+                }
+                CovarianceType::Diagonal => (covariance.to_vec(), covariance.iter().map(|v| v.ln()).sum()),
+                CovarianceType::Spherical => (covariance.to_vec(), dims as f64 * covariance[0].ln()),
+            };
+            if !log_det.is_finite() {
+                return Err("Covariance matrix is singular; increase the regularization".to_string());
+            }
+            factors.push((factor, log_det));
+        }
+
+        let mut responsibilities = Vec::with_capacity(points.len() / dims * k);
+        let mut log_likelihoods = Vec::with_capacity(points.len() / dims);
+        let mut logs = vec![0.0; k];
+        let mut z = vec![0.0; dims];
+        for point in points.chunks_exact(dims) {
+            for c in 0..k {
+                let mean = &self.means[c * dims..(c + 1) * dims];
+                let (factor, log_det) = &factors[c];
+                let mahalanobis = match self.covariance_type {
+                    CovarianceType::Full => {
+                        // Forward substitution L z = x - mean
+                        for a in 0..dims {
+                            let sum: f64 = (0..a).map(|b| factor[a * dims + b] * z[b]).sum();
+                            z[a] = (point[a] - mean[a] - sum) / factor[a * dims + a];
+                        }
+                        z.iter().map(|v| v * v).sum::<f64>()
+                    }
+                    CovarianceType::Diagonal => (0..dims).map(|d| (point[d] - mean[d]).powi(2) / factor[d]).sum(),
+                    CovarianceType::Spherical => (0..dims).map(|d| (point[d] - mean[d]).powi(2)).sum::<f64>() / factor[0],
+                };
+                logs[c] = self.weights[c].ln() - 0.5 * (dims as f64 * log_2pi + log_det + mahalanobis);
+            }
+
+            // Log-sum-exp for a stable normaliser
+            let max = logs.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+            let total = max + logs.iter().map(|l| (l - max).exp()).sum::<f64>().ln();
+            responsibilities.extend(logs.iter().map(|l| (l - total).exp()));
+            log_likelihoods.push(total);
+        }
+
+        Ok((responsibilities, log_likelihoods))
+    }
+
+    fn covariance_block(&self) -> usize {
+        match self.covariance_type {
+            CovarianceType::Full => self.dims * self.dims,
+            CovarianceType::Diagonal => self.dims,
+            CovarianceType::Spherical => 1,
+        }
+    }
+
+    /// Free parameters: weights, means and covariances
+    fn num_parameters(&self) -> usize {
+        let k = self.weights.len();
+        let covariance = match self.covariance_type {
+            CovarianceType::Full => self.dims * (self.dims + 1) / 2,
+            CovarianceType::Diagonal => self.dims,
+            CovarianceType::Spherical => 1,
+        };
+        k - 1 + k * self.dims + k * covariance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 150 points around (0, 0) with correlated spread and 50 tight points around (6, 3)
+    fn mixture_data(rng: &mut Rng) -> Vec<f64> {
+        let mut points = Vec::new();
+        for _ in 0..150 {
+            let (u, v) = (rng.next_normal(), rng.next_normal());
+            points.extend([u, 0.8 * u + 0.6 * v]);
+        }
+        for _ in 0..50 {
+            points.extend([6.0 + 0.3 * rng.next_normal(), 3.0 + 0.3 * rng.next_normal()]);
+        }
+        points
+    }
+
+    fn fit(points: &[f64], covariance_type: CovarianceType) -> GaussianMixture {
+        GaussianMixture::from_values(points, 2, 2, covariance_type, 200, 1e-8, 1e-6, &mut Rng::new(1)).unwrap()
+    }
+
+    /// Index of the component whose mean is nearest to (6, 3)
+    fn tight_component(model: &GaussianMixture) -> usize {
+        let distance = |c: usize| (model.means[c * 2] - 6.0).powi(2) + (model.means[c * 2 + 1] - 3.0).powi(2);
+        if distance(0) < distance(1) { 0 } else { 1 }
+    }
+
+    #[test]
+    fn full_covariance_recovers_the_components() {
+        let points = mixture_data(&mut Rng::new(2));
+        let model = fit(&points, CovarianceType::Full);
+        let (tight, wide) = (tight_component(&model), 1 - tight_component(&model));
+
+        assert!(model.converged);
+        assert!((model.weights[tight] - 0.25).abs() < 0.01);
+        assert!((model.weights.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!((model.means[tight * 2] - 6.0).abs() < 0.1 && (model.means[tight * 2 + 1] - 3.0).abs() < 0.1);
+
+        // The wide component keeps its correlation of 0.8
+        let c = &model.covariances[wide * 4..wide * 4 + 4];
+        assert!((c[1] - c[2]).abs() < 1e-12);
+        assert!((c[1] / (c[0] * c[3]).sqrt() - 0.8).abs() < 0.1);
+        assert!((model.covariances[tight * 4] - 0.09).abs() < 0.03);
+    }
+
+    #[test]
+    fn responsibilities_are_normalised() {
+        let points = mixture_data(&mut Rng::new(4));
+        for covariance_type in [CovarianceType::Full, CovarianceType::Diagonal, CovarianceType::Spherical] {
+            let model = fit(&points, covariance_type);
+            let (responsibilities, log_densities) = model.responsibilities(&points).unwrap();
+
+            assert!(responsibilities.chunks_exact(2).all(|row| (row[0] + row[1] - 1.0).abs() < 1e-12));
+            let mean = log_densities.iter().sum::<f64>() / 200.0;
+            assert!((mean - model.log_likelihood).abs() < 1e-6, "{:?}", covariance_type);
+
+            // Points at the centre of the tight blob belong to it
+            let (at_centre, _) = model.responsibilities(&[6.0, 3.0]).unwrap();
+            assert!(at_centre[tight_component(&model)] > 0.99);
+        }
+    }
+
+    #[test]
+    fn richer_covariances_fit_at_least_as_well() {
+        let points = mixture_data(&mut Rng::new(6));
+        let spherical = fit(&points, CovarianceType::Spherical);
+        let full = fit(&points, CovarianceType::Full);
+        assert!(full.log_likelihood > spherical.log_likelihood);
+    }
+
+    #[test]
+    fn single_component_matches_the_sample_moments() {
+        let points = mixture_data(&mut Rng::new(8));
+        let model = GaussianMixture::from_values(&points, 2, 1, CovarianceType::Diagonal, 10, 1e-8, 0.0, &mut Rng::new(0));
+        let model = model.unwrap();
+
+        for d in 0..2 {
+            let values: Vec<f64> = points.iter().skip(d).step_by(2).copied().collect();
+            let mean = values.iter().sum::<f64>() / 200.0;
+            let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / 200.0;
+            assert!((model.means[d] - mean).abs() < 1e-9);
+            assert!((model.covariances[d] - variance).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn parameter_counts() {
+        let points = mixture_data(&mut Rng::new(10));
+        assert_eq!(fit(&points, CovarianceType::Full).num_parameters(), 1 + 4 + 6);
+        assert_eq!(fit(&points, CovarianceType::Diagonal).num_parameters(), 1 + 4 + 4);
+        assert_eq!(fit(&points, CovarianceType::Spherical).num_parameters(), 1 + 4 + 2);
+    }
+
+    #[test]
+    fn degenerate_data_without_regularization_is_singular() {
+        let points = [1.0, 2.0, 1.0, 2.0, 1.0, 2.0, 1.0, 2.0];
+        let result = GaussianMixture::from_values(&points, 2, 1, CovarianceType::Full, 10, 1e-3, 0.0, &mut Rng::new(0));
+        assert!(result.err().unwrap().contains("singular"));
+    }
+}
@@ -0,0 +1,379 @@
+use super::linalg::solve_banded_spd;
+use super::time_series::{ExtrapolationPolicy, InterpolationMethod};
+
+/// Fill NaN entries of `values` sampled at positions `x`
+///
+/// `x` must be strictly increasing and the same length as `values`. Runs of more than
+/// `max_gap` consecutive missing values are left as NaN, as are edges when the policy is
+/// `Leave`. `degree` is only used by the polynomial method.
+pub(crate) fn interpolate_missing(
+    values: &[f64],
+    x: &[f64],
+    method: InterpolationMethod,
+    max_gap: Option<usize>,
+    extrapolation: ExtrapolationPolicy,
+    degree: usize,
+) -> Vec<f64> {
+    let known: Vec<usize> = (0..values.len()).filter(|&i| !values[i].is_nan()).collect();
+    if known.is_empty() {
+        return values.to_vec();
+    }
+
+    let knots_x: Vec<f64> = known.iter().map(|&i| x[i]).collect();
+    let knots_y: Vec<f64> = known.iter().map(|&i| values[i]).collect();
+    let interpolant = Interpolant::new(method, knots_x, knots_y, degree);
+
+    let mut result = values.to_vec();
+    let mut i = 0;
+    while i < values.len() {
+        if !values[i].is_nan() {
+            i += 1;
+            continue;
+        }
+
+        // Locate the run of missing values starting at i
+        let start = i;
+        while i < values.len() && values[i].is_nan() {
+            i += 1;
+        }
+        let end = i;
+
+        if max_gap.is_some_and(|limit| end - start > limit) {
+            continue;
+        }
+
+        let is_edge = start == 0 || end == values.len();
+        for j in start..end {
+            result[j] = if !is_edge {
+                interpolant.evaluate(x[j])
+            } else {
+                match extrapolation {
+                    ExtrapolationPolicy::Leave => f64::NAN,
+                    ExtrapolationPolicy::Hold => interpolant.nearest_end(x[j]),
+                    ExtrapolationPolicy::Extrapolate => interpolant.evaluate(x[j]),
+                }
+            };
+        }
+    }
+
+    result
+}
+
+/// Piecewise interpolant through the known points
+struct Interpolant {
+    method: InterpolationMethod,
+    x: Vec<f64>,
+    y: Vec<f64>,
+    /// Knot derivatives for the cubic Hermite methods
+    slopes: Vec<f64>,
+    degree: usize,
+}
+
+impl Interpolant {
+    fn new(method: InterpolationMethod, x: Vec<f64>, y: Vec<f64>, degree: usize) -> Self {
+        // Cubic methods need enough knots; fall back to linear otherwise
+        let method = match method {
+            InterpolationMethod::CubicSpline | InterpolationMethod::Pchip if x.len() < 3 => InterpolationMethod::Linear,
+            InterpolationMethod::Akima if x.len() < 5 => InterpolationMethod::Linear,
+            other => other,
+        };
+
+        let slopes = match method {
+            InterpolationMethod::CubicSpline => natural_spline_slopes(&x, &y),
+            InterpolationMethod::Pchip => pchip_slopes(&x, &y),
+            InterpolationMethod::Akima => akima_slopes(&x, &y),
+            _ => Vec::new(),
+        };
+
+        Interpolant { method, x, y, slopes, degree }
+    }
+
+    /// Value of the nearest end knot, used to hold edges constant
+    fn nearest_end(&self, position: f64) -> f64 {
+        if position < self.x[0] { self.y[0] } else { self.y[self.y.len() - 1] }
+    }
+
+    fn evaluate(&self, position: f64) -> f64 {
+        let n = self.x.len();
+        if n == 1 {
+            return self.y[0];
+        }
+
+        // Segment k spans [x[k], x[k + 1]], clamped to the end segments outside the range
+        let k = match self.x.partition_point(|&knot| knot <= position) {
+            0 => 0,
+            p => (p - 1).min(n - 2),
+        };
+        let (x0, x1, y0, y1) = (self.x[k], self.x[k + 1], self.y[k], self.y[k + 1]);
+
+        match self.method {
+            InterpolationMethod::Previous => {
+                if position >= x1 { y1 } else { y0 }
+            }
+            InterpolationMethod::Next => {
+                if position <= x0 { y0 } else { y1 }
+            }
+            InterpolationMethod::Nearest => {
+                if position - x0 <= x1 - position { y0 } else { y1 }
+            }
+            InterpolationMethod::Linear => y0 + (y1 - y0) * (position - x0) / (x1 - x0),
+            InterpolationMethod::CubicSpline | InterpolationMethod::Pchip | InterpolationMethod::Akima => {
+                // Extend linearly along the end slope outside the known range
+                if position < self.x[0] {
+                    return self.y[0] + self.slopes[0] * (position - self.x[0]);
+                }
+                if position > self.x[n - 1] {
+                    return self.y[n - 1] + self.slopes[n - 1] * (position - self.x[n - 1]);
+                }
+                hermite(position, x0, x1, y0, y1, self.slopes[k], self.slopes[k + 1])
+            }
+            InterpolationMethod::Polynomial => self.local_polynomial(position, k),
+        }
+    }
+
+    /// Evaluate the polynomial through the `degree + 1` knots centred on segment `k`
+    fn local_polynomial(&self, position: f64, k: usize) -> f64 {
+        let n = self.x.len();
+        let count = (self.degree + 1).clamp(2, n);
+        let first = (k + 1).saturating_sub(count / 2).min(n - count);
+
+        // Neville's algorithm
+        let xs = &self.x[first..first + count];
+        let mut table: Vec<f64> = self.y[first..first + count].to_vec();
+        for level in 1..count {
+            for i in 0..count - level {
+                table[i] = ((position - xs[i + level]) * table[i] + (xs[i] - position) * table[i + 1])
+                    / (xs[i] - xs[i + level]);
+            }
+        }
+        table[0]
+    }
+}
+
+/// Cubic Hermite basis evaluation on [x0, x1]
+fn hermite(position: f64, x0: f64, x1: f64, y0: f64, y1: f64, d0: f64, d1: f64) -> f64 {
+    let h = x1 - x0;
+    let t = (position - x0) / h;
+    let t2 = t * t;
+    let t3 = t2 * t;
+    (2.0 * t3 - 3.0 * t2 + 1.0) * y0
+        + (t3 - 2.0 * t2 + t) * h * d0
+        + (-2.0 * t3 + 3.0 * t2) * y1
+        + (t3 - t2) * h * d1
+}
+
+/// Knot derivatives of the natural cubic spline
+fn natural_spline_slopes(x: &[f64], y: &[f64]) -> Vec<f64> {
+    let n = x.len();
+    let h: Vec<f64> = (0..n - 1).map(|i| x[i + 1] - x[i]).collect();
+    let secant: Vec<f64> = (0..n - 1).map(|i| (y[i + 1] - y[i]) / h[i]).collect();
+
+    // Second derivatives at the interior knots (zero at both ends)
+    let interior = n - 2;
+    let bands: Vec<Vec<f64>> = (0..interior)
+        .map(|i| vec![2.0 * (h[i] + h[i + 1]), if i > 0 { h[i] } else { 0.0 }])
+        .collect();
+    let rhs: Vec<f64> = (0..interior).map(|i| 6.0 * (secant[i + 1] - secant[i])).collect();
+    let inner = solve_banded_spd(&bands, 1, &rhs).unwrap_or_else(|| vec![0.0; interior]);
+
+    let mut second = vec![0.0; n];
+    second[1..n - 1].copy_from_slice(&inner);
+
+    let mut slopes: Vec<f64> = (0..n - 1)
+        .map(|i| secant[i] - h[i] * (2.0 * second[i] + second[i + 1]) / 6.0)
+        .collect();
+    slopes.push(secant[n - 2] + h[n - 2] * (second[n - 2] + 2.0 * second[n - 1]) / 6.0);
+    slopes
+}
+
+/// Monotone knot derivatives (Fritsch-Carlson, as used by PCHIP)
+fn pchip_slopes(x: &[f64], y: &[f64]) -> Vec<f64> {
+    let n = x.len();
+    let h: Vec<f64> = (0..n - 1).map(|i| x[i + 1] - x[i]).collect();
+    let secant: Vec<f64> = (0..n - 1).map(|i| (y[i + 1] - y[i]) / h[i]).collect();
+    let mut slopes = vec![0.0; n];
+
+    // Weighted harmonic mean of neighbouring secants, zero at local extrema
+    for i in 1..n - 1 {
+        if secant[i - 1] * secant[i] > 0.0 {
+            let w1 = 2.0 * h[i] + h[i - 1];
+            let w2 = h[i] + 2.0 * h[i - 1];
+            slopes[i] = (w1 + w2) / (w1 / secant[i - 1] + w2 / secant[i]);
+        }
+    }
+
+    slopes[0] = pchip_end_slope(h[0], h[1], secant[0], secant[1]);
+    slopes[n - 1] = pchip_end_slope(h[n - 2], h[n - 3], secant[n - 2], secant[n - 3]);
+    slopes
+}
+
+/// Shape-preserving three-point end derivative
+fn pchip_end_slope(h0: f64, h1: f64, m0: f64, m1: f64) -> f64 {
+    let slope = ((2.0 * h0 + h1) * m0 - h0 * m1) / (h0 + h1);
+    if m0 == 0.0 || sign(slope) != sign(m0) {
+        0.0
+    } else if sign(m0) != sign(m1) && slope.abs() > 3.0 * m0.abs() {
+        3.0 * m0
+    } else {
+        slope
+    }
+}
+
+/// Sign of `v` as -1, 0 or 1; unlike `f64::signum`, zero maps to zero
+fn sign(v: f64) -> f64 {
+    if v > 0.0 {
+        1.0
+    } else if v < 0.0 {
+        -1.0
+    } else {
+        0.0
+    }
+}
+
+/// Akima knot derivatives, which limit overshoot near outliers
+fn akima_slopes(x: &[f64], y: &[f64]) -> Vec<f64> {
+    let n = x.len();
+
+    // Secants padded with two extrapolated values at each end
+    let mut secant = vec![0.0; n + 3];
+    for i in 0..n - 1 {
+        secant[i + 2] = (y[i + 1] - y[i]) / (x[i + 1] - x[i]);
+    }
+    secant[1] = 2.0 * secant[2] - secant[3];
+    secant[0] = 2.0 * secant[1] - secant[2];
+    secant[n + 1] = 2.0 * secant[n] - secant[n - 1];
+    secant[n + 2] = 2.0 * secant[n + 1] - secant[n];
+
+    (0..n)
+        .map(|i| {
+            let w1 = (secant[i + 3] - secant[i + 2]).abs();
+            let w2 = (secant[i + 1] - secant[i]).abs();
+            if w1 + w2 == 0.0 {
+                0.5 * (secant[i + 1] + secant[i + 2])
+            } else {
+                (w1 * secant[i + 1] + w2 * secant[i + 2]) / (w1 + w2)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NAN: f64 = f64::NAN;
+
+    fn positions(length: usize) -> Vec<f64> {
+        (0..length).map(|i| i as f64).collect()
+    }
+
+    fn fill(values: &[f64], method: InterpolationMethod, extrapolation: ExtrapolationPolicy) -> Vec<f64> {
+        interpolate_missing(values, &positions(values.len()), method, None, extrapolation, 3)
+    }
+
+    fn assert_close(actual: &[f64], expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a.is_nan() && e.is_nan()) || (a - e).abs() < 1e-9, "{:?} vs {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn linear_fill_with_each_edge_policy() {
+        let values = [NAN, 2.0, NAN, NAN, 8.0, NAN];
+
+        assert_close(&fill(&values, InterpolationMethod::Linear, ExtrapolationPolicy::Hold), &[2.0, 2.0, 4.0, 6.0, 8.0, 8.0]);
+        assert_close(&fill(&values, InterpolationMethod::Linear, ExtrapolationPolicy::Leave), &[NAN, 2.0, 4.0, 6.0, 8.0, NAN]);
+        assert_close(
+            &fill(&values, InterpolationMethod::Linear, ExtrapolationPolicy::Extrapolate),
+            &[0.0, 2.0, 4.0, 6.0, 8.0, 10.0],
+        );
+    }
+
+    #[test]
+    fn step_methods_copy_neighbours() {
+        let values = [1.0, NAN, NAN, 4.0];
+
+        assert_close(&fill(&values, InterpolationMethod::Previous, ExtrapolationPolicy::Hold), &[1.0, 1.0, 1.0, 4.0]);
+        assert_close(&fill(&values, InterpolationMethod::Next, ExtrapolationPolicy::Hold), &[1.0, 4.0, 4.0, 4.0]);
+        assert_close(&fill(&values, InterpolationMethod::Nearest, ExtrapolationPolicy::Hold), &[1.0, 1.0, 4.0, 4.0]);
+    }
+
+    #[test]
+    fn gap_limit_and_uneven_positions() {
+        let values = [0.0, NAN, 2.0, NAN, NAN, NAN, 6.0];
+        let limited = interpolate_missing(&values, &positions(7), InterpolationMethod::Linear, Some(2), ExtrapolationPolicy::Hold, 3);
+        assert_close(&limited, &[0.0, 1.0, 2.0, NAN, NAN, NAN, 6.0]);
+
+        let x = [0.0, 1.0, 4.0];
+        let filled = interpolate_missing(&[0.0, NAN, 8.0], &x, InterpolationMethod::Linear, None, ExtrapolationPolicy::Hold, 3);
+        assert_close(&filled, &[0.0, 2.0, 8.0]);
+
+        let untouched = [NAN, NAN];
+        assert!(fill(&untouched, InterpolationMethod::Linear, ExtrapolationPolicy::Hold).iter().all(|v| v.is_nan()));
+    }
+
+    #[test]
+    fn smooth_methods_reproduce_low_order_data() {
+        let line: Vec<f64> = (0..9).map(|i| if i % 3 == 1 { NAN } else { 3.0 - 0.5 * i as f64 }).collect();
+        let expected: Vec<f64> = (0..9).map(|i| 3.0 - 0.5 * i as f64).collect();
+        for method in [InterpolationMethod::CubicSpline, InterpolationMethod::Pchip, InterpolationMethod::Akima] {
+            assert_close(&fill(&line, method, ExtrapolationPolicy::Extrapolate), &expected);
+        }
+
+        let parabola: Vec<f64> = (0..7).map(|i| if i == 3 { NAN } else { (i * i) as f64 }).collect();
+        let filled = interpolate_missing(&parabola, &positions(7), InterpolationMethod::Polynomial, None, ExtrapolationPolicy::Hold, 2);
+        assert!((filled[3] - 9.0).abs() < 1e-9);
+    }
+
+    /// PCHIP evaluated on a grid of 100 points per segment
+    fn pchip_samples(y: &[f64]) -> Vec<f64> {
+        let interpolant = Interpolant::new(InterpolationMethod::Pchip, positions(y.len()), y.to_vec(), 3);
+        (0..=100 * (y.len() - 1)).map(|i| interpolant.evaluate(i as f64 / 100.0)).collect()
+    }
+
+    #[test]
+    fn pchip_stays_within_each_segment() {
+        // Flat first and last secants used to let the end slopes overshoot
+        let cases: [&[f64]; 5] = [
+            &[1.0, 1.0, 0.0, 0.5],
+            &[2.0, 0.0, 3.0, 3.0],
+            &[1.0, 1.0, 3.0, 3.5],
+            &[0.0, 1.0, 5.0, 5.0],
+            &[0.0, 0.0, 1.0, 5.0, 5.1, 5.1],
+        ];
+
+        for y in cases {
+            let samples = pchip_samples(y);
+            for (k, segment) in y.windows(2).enumerate() {
+                let (low, high) = (segment[0].min(segment[1]), segment[0].max(segment[1]));
+                let inside = &samples[100 * k..=100 * (k + 1)];
+                assert!(inside.iter().all(|&v| v >= low - 1e-12 && v <= high + 1e-12), "{:?}", y);
+            }
+        }
+    }
+
+    #[test]
+    fn pchip_is_monotone_through_monotone_data() {
+        let samples = pchip_samples(&[0.0, 0.0, 1.0, 5.0, 5.1, 5.1, 9.0]);
+        assert!(samples.windows(2).all(|w| w[1] >= w[0] - 1e-12));
+    }
+
+    #[test]
+    fn pchip_end_slope_sign_rules() {
+        // Flat end secant, then a three-point slope against the secant's sign
+        assert_eq!(pchip_end_slope(1.0, 1.0, 0.0, 2.0), 0.0);
+        assert_eq!(pchip_end_slope(1.0, 1.0, 1.0, 5.0), 0.0);
+        // Clamped to three secants when the neighbouring secant turns around
+        assert_eq!(pchip_end_slope(1.0, 1.0, 1.0, -5.0), 3.0);
+        assert_eq!(pchip_end_slope(1.0, 1.0, 1.0, 1.0), 1.0);
+    }
+
+    #[test]
+    fn sign_maps_zero_to_zero() {
+        assert_eq!(sign(0.0), 0.0);
+        assert_eq!(sign(-0.0), 0.0);
+        assert_eq!(sign(2.5), 1.0);
+        assert_eq!(sign(-1e-300), -1.0);
+    }
+}
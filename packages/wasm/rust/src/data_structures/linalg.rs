@@ -0,0 +1,424 @@
+/// Solve a symmetric positive definite banded system `A x = b`
+///
+/// `bands[i][d]` holds the element `A[i][i - d]` for `d` in `0..=bandwidth`.
+/// Uses a banded Cholesky factorisation so the cost is linear in the number of rows.
+pub(crate) fn solve_banded_spd(bands: &[Vec<f64>], bandwidth: usize, rhs: &[f64]) -> Option<Vec<f64>> {
+    let n = rhs.len();
+    if bands.len() != n {
+        return None;
+    }
+
+    // Factorise A = L L^T, storing L in the same band layout
+    let mut lower = vec![vec![0.0; bandwidth + 1]; n];
+
+    for i in 0..n {
+        let first = i.saturating_sub(bandwidth);
+
+        for j in first..i {
+            let mut sum = bands[i][i - j];
+            for k in first.max(j.saturating_sub(bandwidth))..j {
+                sum -= lower[i][i - k] * lower[j][j - k];
+            }
+            lower[i][i - j] = sum / lower[j][0];
+        }
+
+        let mut diag = bands[i][0];
+        for k in first..i {
+            diag -= lower[i][i - k] * lower[i][i - k];
+        }
+
+        if diag <= 0.0 {
+            return None;
+        }
+        lower[i][0] = diag.sqrt();
+    }
+
+    // Forward substitution: L y = b
+    let mut y = vec![0.0; n];
+    for i in 0..n {
+        let mut sum = rhs[i];
+        for k in i.saturating_sub(bandwidth)..i {
+            sum -= lower[i][i - k] * y[k];
+        }
+        y[i] = sum / lower[i][0];
+    }
+
+    // Back substitution: L^T x = y
+    let mut x = vec![0.0; n];
+    for i in (0..n).rev() {
+        let mut sum = y[i];
+        for k in (i + 1)..n.min(i + bandwidth + 1) {
+            sum -= lower[k][k - i] * x[k];
+        }
+        x[i] = sum / lower[i][0];
+    }
+
+    Some(x)
+}
+
+/// Eigendecomposition of a symmetric `n x n` row-major matrix (cyclic Jacobi)
+///
+/// Returns the eigenvalues in descending order and the matching unit eigenvectors as the
+/// rows of an `n x n` row-major matrix.
+pub(crate) fn symmetric_eigen(matrix: &[f64], n: usize) -> (Vec<f64>, Vec<f64>) {
+    let mut a = matrix.to_vec();
+    let mut v = vec![0.0; n * n];
+    for i in 0..n {
+        v[i * n + i] = 1.0;
+    }
+
+    let scale: f64 = a.iter().map(|x| x * x).sum::<f64>().max(f64::MIN_POSITIVE);
+
+    for _ in 0..100 {
+        let mut off_diagonal = 0.0;
+        for p in 0..n {
+            for q in (p + 1)..n {
+                off_diagonal += a[p * n + q] * a[p * n + q];
+            }
+        }
+        if off_diagonal <= 1e-30 * scale {
+            break;
+        }
+
+        for p in 0..n {
+            for q in (p + 1)..n {
+                let apq = a[p * n + q];
+                if apq.abs() <= f64::MIN_POSITIVE {
+                    continue;
+                }
+
+                // Rotation angle that zeroes a[p][q]
+                let theta = (a[q * n + q] - a[p * n + p]) / (2.0 * apq);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+
+                for k in 0..n {
+                    let (akp, akq) = (a[k * n + p], a[k * n + q]);
+                    a[k * n + p] = c * akp - s * akq;
+                    a[k * n + q] = s * akp + c * akq;
+                }
+                for k in 0..n {
+                    let (apk, aqk) = (a[p * n + k], a[q * n + k]);
+                    a[p * n + k] = c * apk - s * aqk;
+                    a[q * n + k] = s * apk + c * aqk;
+                }
+                for k in 0..n {
+                    let (vkp, vkq) = (v[k * n + p], v[k * n + q]);
+                    v[k * n + p] = c * vkp - s * vkq;
+                    v[k * n + q] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+
+    // Sort by descending eigenvalue; eigenvectors are the columns of v
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&i, &j| a[j * n + j].partial_cmp(&a[i * n + i]).unwrap_or(std::cmp::Ordering::Equal));
+
+    let values = order.iter().map(|&i| a[i * n + i]).collect();
+    let mut vectors = Vec::with_capacity(n * n);
+    for &i in &order {
+        vectors.extend((0..n).map(|k| v[k * n + i]));
+    }
+
+    (values, vectors)
+}
+
+/// Singular values and right singular vectors of a `rows x cols` row-major matrix
+///
+/// Uses one-sided Jacobi (Hestenes) rotations, which are accurate for small singular
+/// values. Returns the `cols` singular values in descending order and the matching right
+/// singular vectors as the rows of a `cols x cols` row-major matrix.
+pub(crate) fn jacobi_svd(matrix: &[f64], rows: usize, cols: usize) -> (Vec<f64>, Vec<f64>) {
+    let mut u = matrix.to_vec();
+    let mut v = vec![0.0; cols * cols];
+    for i in 0..cols {
+        v[i * cols + i] = 1.0;
+    }
+
+    for _ in 0..60 {
+        let mut rotated = false;
+
+        for p in 0..cols {
+            for q in (p + 1)..cols {
+                let (mut alpha, mut beta, mut gamma) = (0.0, 0.0, 0.0);
+                for k in 0..rows {
+                    let (ukp, ukq) = (u[k * cols + p], u[k * cols + q]);
+                    alpha += ukp * ukp;
+                    beta += ukq * ukq;
+                    gamma += ukp * ukq;
+                }
+
+                if gamma.abs() <= 1e-15 * (alpha * beta).sqrt() || gamma == 0.0 {
+                    continue;
+                }
+                rotated = true;
+
+                // Rotation that makes columns p and q orthogonal
+                let zeta = (beta - alpha) / (2.0 * gamma);
+                let t = zeta.signum() / (zeta.abs() + (1.0 + zeta * zeta).sqrt());
+                let c = 1.0 / (1.0 + t * t).sqrt();
+                let s = c * t;
+
+                for k in 0..rows {
+                    let (ukp, ukq) = (u[k * cols + p], u[k * cols + q]);
+                    u[k * cols + p] = c * ukp - s * ukq;
+                    u[k * cols + q] = s * ukp + c * ukq;
+                }
+                for k in 0..cols {
+                    let (vkp, vkq) = (v[k * cols + p], v[k * cols + q]);
+                    v[k * cols + p] = c * vkp - s * vkq;
+                    v[k * cols + q] = s * vkp + c * vkq;
+                }
+            }
+        }
+
+        if !rotated {
+            break;
+        }
+    }
+
+    // Singular values are the norms of the orthogonalised columns
+    let norms: Vec<f64> = (0..cols)
+        .map(|j| (0..rows).map(|k| u[k * cols + j] * u[k * cols + j]).sum::<f64>().sqrt())
+        .collect();
+    let mut order: Vec<usize> = (0..cols).collect();
+    order.sort_by(|&i, &j| norms[j].partial_cmp(&norms[i]).unwrap_or(std::cmp::Ordering::Equal));
+
+    let values = order.iter().map(|&i| norms[i]).collect();
+    let mut vectors = Vec::with_capacity(cols * cols);
+    for &i in &order {
+        vectors.extend((0..cols).map(|k| v[k * cols + i]));
+    }
+
+    (values, vectors)
+}
+
+/// Least-squares solution of `A x = b` for a `rows x cols` row-major matrix (Householder QR)
+///
+/// Returns the solution and the `cols x cols` upper-triangular factor R, or `None` when
+/// `rows < cols` or A is numerically rank deficient.
+pub(crate) fn least_squares_qr(matrix: &[f64], rows: usize, cols: usize, rhs: &[f64]) -> Option<(Vec<f64>, Vec<f64>)> {
+    if rows < cols {
+        return None;
+    }
+
+    let mut a = matrix.to_vec();
+    let mut b = rhs.to_vec();
+
+    for k in 0..cols {
+        // Householder vector that zeroes column k below the diagonal
+        let norm = (k..rows).map(|i| a[i * cols + k] * a[i * cols + k]).sum::<f64>().sqrt();
+        if norm == 0.0 {
+            continue;
+        }
+        let alpha = if a[k * cols + k] > 0.0 { -norm } else { norm };
+        let mut v: Vec<f64> = (k..rows).map(|i| a[i * cols + k]).collect();
+        v[0] -= alpha;
+        let v_norm_sqr: f64 = v.iter().map(|x| x * x).sum();
+        if v_norm_sqr == 0.0 {
+            continue;
+        }
+
+        // Reflect the remaining columns and the right-hand side
+        for j in k..cols {
+            let dot: f64 = (k..rows).map(|i| v[i - k] * a[i * cols + j]).sum();
+            let factor = 2.0 * dot / v_norm_sqr;
+            for i in k..rows {
+                a[i * cols + j] -= factor * v[i - k];
+            }
+        }
+        let dot: f64 = (k..rows).map(|i| v[i - k] * b[i]).sum();
+        let factor = 2.0 * dot / v_norm_sqr;
+        for i in k..rows {
+            b[i] -= factor * v[i - k];
+        }
+    }
+
+    let mut r = vec![0.0; cols * cols];
+    for i in 0..cols {
+        for j in i..cols {
+            r[i * cols + j] = a[i * cols + j];
+        }
+    }
+
+    // Reject (near) singular triangular factors
+    let largest = (0..cols).map(|i| r[i * cols + i].abs()).fold(0.0, f64::max);
+    if (0..cols).any(|i| r[i * cols + i].abs() <= 1e-12 * largest.max(f64::MIN_POSITIVE)) {
+        return None;
+    }
+
+    // Back substitution: R x = Q^T b
+    let mut x = vec![0.0; cols];
+    for i in (0..cols).rev() {
+        let mut sum = b[i];
+        for j in (i + 1)..cols {
+            sum -= r[i * cols + j] * x[j];
+        }
+        x[i] = sum / r[i * cols + i];
+    }
+
+    Some((x, r))
+}
+
+/// Inverse of an `n x n` row-major upper-triangular matrix with a non-zero diagonal
+pub(crate) fn invert_upper_triangular(r: &[f64], n: usize) -> Vec<f64> {
+    let mut inverse = vec![0.0; n * n];
+    for j in 0..n {
+        inverse[j * n + j] = 1.0 / r[j * n + j];
+        for i in (0..j).rev() {
+            let sum: f64 = ((i + 1)..=j).map(|k| r[i * n + k] * inverse[k * n + j]).sum();
+            inverse[i * n + j] = -sum / r[i * n + i];
+        }
+    }
+    inverse
+}
+
+/// Lower-triangular Cholesky factor `L` of an `n x n` row-major symmetric positive definite
+/// matrix, so that `A = L L^T`
+///
+/// Returns `None` if the matrix is not positive definite.
+pub(crate) fn cholesky(matrix: &[f64], n: usize) -> Option<Vec<f64>> {
+    let mut l = vec![0.0; n * n];
+    for i in 0..n {
+        for j in 0..=i {
+            let sum: f64 = matrix[i * n + j] - (0..j).map(|k| l[i * n + k] * l[j * n + k]).sum::<f64>();
+            if i == j {
+                if sum <= 0.0 {
+                    return None;
+                }
+                l[i * n + i] = sum.sqrt();
+            } else {
+                l[i * n + j] = sum / l[j * n + j];
+            }
+        }
+    }
+    Some(l)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::random::Rng;
+
+    /// Row-major product of a `rows x inner` and an `inner x cols` matrix
+    fn multiply(a: &[f64], b: &[f64], rows: usize, inner: usize, cols: usize) -> Vec<f64> {
+        let mut product = vec![0.0; rows * cols];
+        for i in 0..rows {
+            for k in 0..inner {
+                for j in 0..cols {
+                    product[i * cols + j] += a[i * inner + k] * b[k * cols + j];
+                }
+            }
+        }
+        product
+    }
+
+    fn assert_orthonormal_rows(vectors: &[f64], n: usize) {
+        for i in 0..n {
+            for j in 0..n {
+                let dot: f64 = (0..n).map(|k| vectors[i * n + k] * vectors[j * n + k]).sum();
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((dot - expected).abs() < 1e-10, "rows {} and {}: {}", i, j, dot);
+            }
+        }
+    }
+
+    #[test]
+    fn symmetric_eigen_of_a_known_matrix() {
+        // Eigenvalues of [[2, 1], [1, 2]] are 3 and 1 with axes (1, 1) and (1, -1)
+        let (values, vectors) = symmetric_eigen(&[2.0, 1.0, 1.0, 2.0], 2);
+        assert!((values[0] - 3.0).abs() < 1e-12 && (values[1] - 1.0).abs() < 1e-12);
+        assert!((vectors[0].abs() - 0.5f64.sqrt()).abs() < 1e-12);
+        assert!((vectors[0] - vectors[1]).abs() < 1e-12);
+        assert!((vectors[2] + vectors[3]).abs() < 1e-12);
+    }
+
+    #[test]
+    fn symmetric_eigen_diagonalises_random_matrices() {
+        let mut rng = Rng::new(17);
+        let n = 6;
+        let mut matrix = vec![0.0; n * n];
+        for i in 0..n {
+            for j in i..n {
+                let value = rng.next_normal();
+                matrix[i * n + j] = value;
+                matrix[j * n + i] = value;
+            }
+        }
+
+        let (values, vectors) = symmetric_eigen(&matrix, n);
+        assert!(values.windows(2).all(|w| w[0] >= w[1]));
+        assert_orthonormal_rows(&vectors, n);
+        for (value, vector) in values.iter().zip(vectors.chunks_exact(n)) {
+            let image = multiply(&matrix, vector, n, n, 1);
+            for (a, v) in image.iter().zip(vector) {
+                assert!((a - value * v).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn jacobi_svd_matches_the_gram_eigenvalues() {
+        let mut rng = Rng::new(23);
+        let (rows, cols) = (12, 4);
+        let matrix: Vec<f64> = (0..rows * cols).map(|_| rng.next_normal()).collect();
+
+        let (singular_values, vectors) = jacobi_svd(&matrix, rows, cols);
+        assert!(singular_values.windows(2).all(|w| w[0] >= w[1]));
+        assert_orthonormal_rows(&vectors, cols);
+
+        // A^T A v = s^2 v for every right singular vector
+        let transpose: Vec<f64> = (0..cols * rows).map(|i| matrix[(i % rows) * cols + i / rows]).collect();
+        let gram = multiply(&transpose, &matrix, cols, rows, cols);
+        for (s, vector) in singular_values.iter().zip(vectors.chunks_exact(cols)) {
+            let image = multiply(&gram, vector, cols, cols, 1);
+            for (a, v) in image.iter().zip(vector) {
+                assert!((a - s * s * v).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn jacobi_svd_of_a_rank_deficient_matrix() {
+        // Second column is twice the first
+        let matrix = [1.0, 2.0, 2.0, 4.0, 3.0, 6.0];
+        let (singular_values, _) = jacobi_svd(&matrix, 3, 2);
+        assert!((singular_values[0] - (5.0f64 * 14.0).sqrt()).abs() < 1e-12);
+        assert!(singular_values[1].abs() < 1e-12);
+    }
+
+    #[test]
+    fn least_squares_qr_solves_overdetermined_systems() {
+        // Fit y = 1 + 2x to points that lie exactly on the line
+        let matrix = [1.0, 0.0, 1.0, 1.0, 1.0, 2.0, 1.0, 3.0];
+        let (x, r) = least_squares_qr(&matrix, 4, 2, &[1.0, 3.0, 5.0, 7.0]).unwrap();
+        assert!((x[0] - 1.0).abs() < 1e-12 && (x[1] - 2.0).abs() < 1e-12);
+
+        // R^T R equals A^T A
+        let gram = [4.0, 6.0, 6.0, 14.0];
+        for i in 0..2 {
+            for j in 0..2 {
+                let value: f64 = (0..2).map(|k| r[k * 2 + i] * r[k * 2 + j]).sum();
+                assert!((value - gram[i * 2 + j]).abs() < 1e-12);
+            }
+        }
+
+        assert!(least_squares_qr(&matrix, 2, 4, &[1.0, 3.0]).is_none());
+        assert!(least_squares_qr(&[1.0, 2.0, 2.0, 4.0], 2, 2, &[1.0, 2.0]).is_none());
+    }
+
+    #[test]
+    fn invert_upper_triangular_gives_the_inverse() {
+        let r = [2.0, 1.0, -1.0, 0.0, 3.0, 0.5, 0.0, 0.0, 4.0];
+        let inverse = invert_upper_triangular(&r, 3);
+        let product = multiply(&r, &inverse, 3, 3, 3);
+        for i in 0..3 {
+            for j in 0..3 {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((product[i * 3 + j] - expected).abs() < 1e-12);
+            }
+        }
+    }
+}
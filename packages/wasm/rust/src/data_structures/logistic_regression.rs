@@ -0,0 +1,536 @@
+use wasm_bindgen::prelude::*;
+use js_sys::Float64Array;
+
+use super::machine_learning::read_rows;
+use super::optimize::{lbfgs, proximal_gradient};
+use super::regression::Regularization;
+use super::serialization::{ByteReader, ByteWriter};
+
+/// Optimisers for fitting logistic regression
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LogisticSolver {
+    /// Limited-memory BFGS (smooth penalties only)
+    Lbfgs,
+    /// Proximal gradient descent with backtracking (supports L1)
+    GradientDescent,
+}
+
+const MAGIC: &[u8; 4] = b"RDLR";
+const VERSION: u32 = 1;
+
+/// Binary or multinomial (softmax) logistic regression classifier
+///
+/// Two classes use a single sigmoid output; more classes use one softmax output per class.
+/// Features are used as given, so scale them first when they differ widely in range.
+#[wasm_bindgen]
+pub struct LogisticRegression {
+    dims: usize,
+    classes: Vec<f64>,
+    /// Row-major `outputs x dims`, where outputs is 1 for binary problems
+    weights: Vec<f64>,
+    biases: Vec<f64>,
+    iterations: usize,
+    loss: f64,
+}
+
+#[wasm_bindgen]
+impl LogisticRegression {
+    /// Fit a classifier to row-major features and class labels
+    ///
+    /// Takes the features, the labels (any numeric class values), the number of features
+    /// per row, the penalty, its strength `alpha` (default 1e-4) and elastic-net `l1_ratio`
+    /// (default 0.5), the solver (default L-BFGS, which switches to gradient descent when
+    /// the penalty has an L1 part), optional class weights (an array in ascending class
+    /// order, or the string "balanced"), and iteration limit and tolerance. The loss is the
+    /// weighted mean cross-entropy plus the penalty on the weights (intercepts are not
+    /// penalised).
+    #[allow(clippy::too_many_arguments)]
+    pub fn fit(
+        x: &JsValue,
+        y: &JsValue,
+        dims: usize,
+        regularization: Regularization,
+        alpha: Option<f64>,
+        l1_ratio: Option<f64>,
+        solver: Option<LogisticSolver>,
+        class_weights: &JsValue,
+        max_iterations: Option<usize>,
+        tolerance: Option<f64>,
+    ) -> Result<LogisticRegression, JsValue> {
+        let (features, num_rows) = read_rows(x, dims)?;
+        let labels = Float64Array::new(y).to_vec();
+
+        // Validate inputs
+        if labels.len() != num_rows {
+            return Err(JsValue::from_str("Labels must have one value per row"));
+        }
+
+        if labels.iter().any(|v| !v.is_finite()) {
+            return Err(JsValue::from_str("Labels must not contain NaN or infinite values"));
+        }
+
+        let mut classes = labels.clone();
+        classes.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        classes.dedup();
+        if classes.len() < 2 {
+            return Err(JsValue::from_str("At least two classes are required"));
+        }
+
+        let alpha = alpha.unwrap_or(1e-4);
+        if alpha < 0.0 {
+            return Err(JsValue::from_str("Alpha must be non-negative"));
+        }
+
+        let l1_ratio = match regularization {
+            Regularization::None | Regularization::Ridge => 0.0,
+            Regularization::Lasso => 1.0,
+            Regularization::ElasticNet => l1_ratio.unwrap_or(0.5),
+        };
+        if !(0.0..=1.0).contains(&l1_ratio) {
+            return Err(JsValue::from_str("l1_ratio must be between 0 and 1"));
+        }
+        let alpha = if regularization == Regularization::None { 0.0 } else { alpha };
+
+        let targets: Vec<usize> = labels
+            .iter()
+            .map(|label| classes.iter().position(|c| c == label).unwrap())
+            .collect();
+
+        let weights_per_class = if class_weights.is_undefined() || class_weights.is_null() {
+            vec![1.0; classes.len()]
+        } else if class_weights.as_string().as_deref() == Some("balanced") {
+            let mut counts = vec![0usize; classes.len()];
+            for &t in &targets {
+                counts[t] += 1;
+            }
+            counts.iter().map(|&c| num_rows as f64 / (classes.len() * c) as f64).collect()
+        } else {
+            let weights = Float64Array::new(class_weights).to_vec();
+            if weights.len() != classes.len() || weights.iter().any(|w| !w.is_finite() || *w < 0.0) {
+                return Err(JsValue::from_str("Class weights must be one non-negative value per class"));
+            }
+            weights
+        };
+
+        Ok(LogisticRegression::from_values(
+            &features,
+            &targets,
+            dims,
+            classes,
+            &weights_per_class,
+            alpha,
+            l1_ratio,
+            solver.unwrap_or(LogisticSolver::Lbfgs),
+            max_iterations.unwrap_or(500),
+            tolerance.unwrap_or(1e-6),
+        ))
+    }
+
+    /// Class probabilities for row-major features
+    ///
+    /// Returns a row-major `rows x classes` matrix with columns in ascending class order.
+    pub fn predict_proba(&self, x: &JsValue) -> Result<JsValue, JsValue> {
+        let (features, _) = read_rows(x, self.dims)?;
+        Ok(Float64Array::from(&self.probabilities(&features)[..]).into())
+    }
+
+    /// Most probable class label for every row
+    pub fn predict(&self, x: &JsValue) -> Result<JsValue, JsValue> {
+        let (features, _) = read_rows(x, self.dims)?;
+        let num_classes = self.classes.len();
+        let labels: Vec<f64> = self
+            .probabilities(&features)
+            .chunks_exact(num_classes)
+            .map(|row| {
+                let best = (0..num_classes).fold(0, |best, c| if row[c] > row[best] { c } else { best });
+                self.classes[best]
+            })
+            .collect();
+        Ok(Float64Array::from(&labels[..]).into())
+    }
+
+    /// Serialize the model to bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = ByteWriter::new(MAGIC, VERSION);
+        writer.write_u32(self.dims as u32);
+        writer.write_f64_slice(&self.classes);
+        writer.write_f64_slice(&self.weights);
+        writer.write_f64_slice(&self.biases);
+        writer.into_bytes()
+    }
+
+    /// Restore a model produced by `to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Result<LogisticRegression, JsValue> {
+        LogisticRegression::decode(bytes).map_err(|message| JsValue::from_str(&message))
+    }
+
+    /// Class labels in ascending order
+    #[wasm_bindgen(getter)]
+    pub fn classes(&self) -> Float64Array {
+        Float64Array::from(&self.classes[..])
+    }
+
+    /// Row-major weights, one row of `dims` values per output
+    #[wasm_bindgen(getter)]
+    pub fn coefficients(&self) -> Float64Array {
+        Float64Array::from(&self.weights[..])
+    }
+
+    /// Intercept of every output
+    #[wasm_bindgen(getter)]
+    pub fn intercepts(&self) -> Float64Array {
+        Float64Array::from(&self.biases[..])
+    }
+
+    /// Number of features per row
+    #[wasm_bindgen(getter)]
+    pub fn dims(&self) -> usize {
+        self.dims
+    }
+
+    /// Optimiser iterations used during fitting (0 for deserialized models)
+    #[wasm_bindgen(getter)]
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    /// Final training objective (NaN for deserialized models)
+    #[wasm_bindgen(getter)]
+    pub fn loss(&self) -> f64 {
+        self.loss
+    }
+}
+
+impl LogisticRegression {
+    /// Fit the model to validated data with labels given as class indices
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn from_values(
+        features: &[f64],
+        targets: &[usize],
+        dims: usize,
+        classes: Vec<f64>,
+        class_weights: &[f64],
+        alpha: f64,
+        l1_ratio: f64,
+        solver: LogisticSolver,
+        max_iterations: usize,
+        tolerance: f64,
+    ) -> LogisticRegression {
+        let outputs = if classes.len() == 2 { 1 } else { classes.len() };
+        let sample_weights: Vec<f64> = targets.iter().map(|&t| class_weights[t]).collect();
+        let total_weight: f64 = sample_weights.iter().sum::<f64>().max(f64::MIN_POSITIVE);
+        let num_weights = outputs * dims;
+        let l1 = alpha * l1_ratio;
+        let l2 = alpha * (1.0 - l1_ratio);
+
+        // Parameters are the weights followed by the intercepts
+        let smooth = |params: &[f64], gradient: &mut [f64]| -> f64 {
+            let value = cross_entropy(params, features, targets, &sample_weights, dims, outputs, gradient);
+            let mut penalty = 0.0;
+            for i in 0..num_weights {
+                gradient[i] = gradient[i] / total_weight + l2 * params[i];
+                penalty += 0.5 * l2 * params[i] * params[i];
+            }
+            for g in &mut gradient[num_weights..] {
+                *g /= total_weight;
+            }
+            value / total_weight + penalty
+        };
+
+        let start = vec![0.0; num_weights + outputs];
+        let (params, loss, iterations) = if solver == LogisticSolver::Lbfgs && l1 == 0.0 {
+            lbfgs(smooth, &start, 10, max_iterations, tolerance)
+        } else {
+            proximal_gradient(
+                smooth,
+                |params: &[f64]| l1 * params[..num_weights].iter().map(|w| w.abs()).sum::<f64>(),
+                |params: &mut [f64], step: f64| {
+                    let threshold = step * l1;
+                    for w in &mut params[..num_weights] {
+                        *w = w.signum() * (w.abs() - threshold).max(0.0);
+                    }
+                },
+                &start,
+                max_iterations,
+                tolerance,
+            )
+        };
+
+        LogisticRegression {
+            dims,
+            classes,
+            weights: params[..num_weights].to_vec(),
+            biases: params[num_weights..].to_vec(),
+            iterations,
+            loss,
+        }
+    }
+
+    /// Row-major class probabilities for validated features
+    pub(crate) fn probabilities(&self, features: &[f64]) -> Vec<f64> {
+        let outputs = self.biases.len();
+        let mut probabilities = Vec::with_capacity(features.len() / self.dims * self.classes.len());
+        let mut logits = vec![0.0; outputs];
+
+        for row in features.chunks_exact(self.dims) {
+            logits_into(&self.weights, &self.biases, row, &mut logits);
+            if outputs == 1 {
+                let p = sigmoid(logits[0]);
+                probabilities.push(1.0 - p);
+                probabilities.push(p);
+            } else {
+                let max = logits.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+                let sum: f64 = logits.iter().map(|z| (z - max).exp()).sum();
+                probabilities.extend(logits.iter().map(|z| (z - max).exp() / sum));
+            }
+        }
+
+        probabilities
+    }
+
+    fn decode(bytes: &[u8]) -> Result<LogisticRegression, String> {
+        let (mut reader, version) = ByteReader::new(bytes, MAGIC)?;
+        if version != VERSION {
+            return Err(format!("Unsupported logistic regression format version {}", version));
+        }
+
+        let dims = reader.read_u32()? as usize;
+        let classes = reader.read_f64_vec()?;
+        let weights = reader.read_f64_vec()?;
+        let biases = reader.read_f64_vec()?;
+        reader.finish()?;
+
+        let outputs = if classes.len() == 2 { 1 } else { classes.len() };
+        if dims == 0 || classes.len() < 2 || biases.len() != outputs || outputs.checked_mul(dims) != Some(weights.len()) {
+            return Err("Inconsistent logistic regression payload".to_string());
+        }
+
+        Ok(LogisticRegression { dims, classes, weights, biases, iterations: 0, loss: f64::NAN })
+    }
+}
+
+/// Weighted cross-entropy summed over rows; writes the unnormalised gradient
+fn cross_entropy(
+    params: &[f64],
+    features: &[f64],
+    targets: &[usize],
+    sample_weights: &[f64],
+    dims: usize,
+    outputs: usize,
+    gradient: &mut [f64],
+) -> f64 {
+    let num_weights = outputs * dims;
+    let (weights, biases) = params.split_at(num_weights);
+    gradient.iter_mut().for_each(|g| *g = 0.0);
+
+    let mut logits = vec![0.0; outputs];
+    let mut loss = 0.0;
+
+    for ((row, &target), &weight) in features.chunks_exact(dims).zip(targets).zip(sample_weights) {
+        if weight == 0.0 {
+            continue;
+        }
+        logits_into(weights, biases, row, &mut logits);
+
+        if outputs == 1 {
+            // log(1 + e^z) - y z, written to avoid overflow
+            let z = logits[0];
+            let y = target as f64;
+            loss += weight * (z.max(0.0) + (-z.abs()).exp().ln_1p() - y * z);
+            logits[0] = sigmoid(z) - y;
+        } else {
+            let max = logits.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+            let sum: f64 = logits.iter().map(|z| (z - max).exp()).sum();
+            loss += weight * (max + sum.ln() - logits[target]);
+            for (k, z) in logits.iter_mut().enumerate() {
+                *z = (*z - max).exp() / sum - if k == target { 1.0 } else { 0.0 };
+            }
+        }
+
+        // logits now hold d(loss)/d(logit)
+        for k in 0..outputs {
+            let error = weight * logits[k];
+            for d in 0..dims {
+                gradient[k * dims + d] += error * row[d];
+            }
+            gradient[num_weights + k] += error;
+        }
+    }
+
+    loss
+}
+
+fn logits_into(weights: &[f64], biases: &[f64], row: &[f64], logits: &mut [f64]) {
+    let dims = row.len();
+    for (k, logit) in logits.iter_mut().enumerate() {
+        *logit = biases[k] + weights[k * dims..(k + 1) * dims].iter().zip(row).map(|(w, x)| w * x).sum::<f64>();
+    }
+}
+
+fn sigmoid(z: f64) -> f64 {
+    if z >= 0.0 {
+        1.0 / (1.0 + (-z).exp())
+    } else {
+        let e = z.exp();
+        e / (1.0 + e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::random::Rng;
+
+    /// Two overlapping Gaussian blobs in 2D, labelled 0 and 1
+    fn binary_data(rng: &mut Rng) -> (Vec<f64>, Vec<usize>) {
+        let mut features = Vec::new();
+        let mut targets = Vec::new();
+        for i in 0..200 {
+            let target = i % 2;
+            let shift = if target == 1 { 1.0 } else { -1.0 };
+            features.push(shift + rng.next_normal());
+            features.push(0.5 * shift + rng.next_normal());
+            targets.push(target);
+        }
+        (features, targets)
+    }
+
+    fn fit(features: &[f64], targets: &[usize], classes: usize, alpha: f64, l1_ratio: f64, solver: LogisticSolver) -> LogisticRegression {
+        let labels = (0..classes).map(|c| c as f64).collect();
+        LogisticRegression::from_values(features, targets, 2, labels, &vec![1.0; classes], alpha, l1_ratio, solver, 500, 1e-8)
+    }
+
+    #[test]
+    fn cross_entropy_gradient_matches_finite_differences() {
+        let mut rng = Rng::new(3);
+        let features: Vec<f64> = (0..20).map(|_| rng.next_normal()).collect();
+        let weights: Vec<f64> = (0..10).map(|i| 0.5 + (i % 3) as f64).collect();
+
+        for (outputs, targets) in [(1, vec![0, 1, 1, 0, 1, 0, 0, 1, 1, 0]), (3, vec![0, 1, 2, 2, 1, 0, 1, 2, 0, 1])] {
+            let params: Vec<f64> = (0..outputs * 3).map(|_| rng.next_normal()).collect();
+            let mut gradient = vec![0.0; params.len()];
+            cross_entropy(&params, &features, &targets, &weights, 2, outputs, &mut gradient);
+
+            let mut scratch = vec![0.0; params.len()];
+            for i in 0..params.len() {
+                let (mut plus, mut minus) = (params.clone(), params.clone());
+                plus[i] += 1e-6;
+                minus[i] -= 1e-6;
+                let numeric = (cross_entropy(&plus, &features, &targets, &weights, 2, outputs, &mut scratch)
+                    - cross_entropy(&minus, &features, &targets, &weights, 2, outputs, &mut scratch))
+                    / 2e-6;
+                assert!((gradient[i] - numeric).abs() < 1e-5, "outputs {} parameter {}", outputs, i);
+            }
+        }
+    }
+
+    #[test]
+    fn binary_fit_separates_the_blobs() {
+        let (features, targets) = binary_data(&mut Rng::new(5));
+        let model = fit(&features, &targets, 2, 1e-4, 0.0, LogisticSolver::Lbfgs);
+
+        assert_eq!(model.biases.len(), 1);
+        assert!(model.weights[0] > 0.0 && model.weights[1] > 0.0);
+        let probabilities = model.probabilities(&features);
+        let correct = probabilities
+            .chunks_exact(2)
+            .zip(&targets)
+            .filter(|(row, &target)| usize::from(row[1] > row[0]) == target)
+            .count();
+        assert!(correct > 160, "{} of 200 correct", correct);
+        assert!(probabilities.chunks_exact(2).all(|row| (row[0] + row[1] - 1.0).abs() < 1e-12));
+    }
+
+    #[test]
+    fn solvers_reach_the_same_smooth_optimum() {
+        let (features, targets) = binary_data(&mut Rng::new(8));
+        let lbfgs = fit(&features, &targets, 2, 0.1, 0.0, LogisticSolver::Lbfgs);
+        let descent = fit(&features, &targets, 2, 0.1, 0.0, LogisticSolver::GradientDescent);
+
+        assert!((lbfgs.loss - descent.loss).abs() < 1e-6);
+        for (a, b) in lbfgs.weights.iter().zip(&descent.weights) {
+            assert!((a - b).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn l1_penalty_zeroes_an_uninformative_feature() {
+        let mut rng = Rng::new(11);
+        let mut features = Vec::new();
+        let mut targets = Vec::new();
+        for i in 0..200 {
+            let target = i % 2;
+            features.push(if target == 1 { 1.5 } else { -1.5 } + rng.next_normal());
+            features.push(rng.next_normal());
+            targets.push(target);
+        }
+
+        let model = fit(&features, &targets, 2, 0.1, 1.0, LogisticSolver::Lbfgs);
+        assert!(model.weights[0] > 0.0);
+        assert_eq!(model.weights[1], 0.0);
+    }
+
+    #[test]
+    fn multinomial_probabilities_favour_the_true_class() {
+        let mut rng = Rng::new(14);
+        let centers = [[0.0, 4.0], [-4.0, -2.0], [4.0, -2.0]];
+        let mut features = Vec::new();
+        let mut targets = Vec::new();
+        for i in 0..150 {
+            let target = i % 3;
+            features.push(centers[target][0] + rng.next_normal());
+            features.push(centers[target][1] + rng.next_normal());
+            targets.push(target);
+        }
+
+        let model = fit(&features, &targets, 3, 1e-3, 0.0, LogisticSolver::Lbfgs);
+        assert_eq!(model.biases.len(), 3);
+        let probabilities = model.probabilities(&centers.concat());
+        for (c, row) in probabilities.chunks_exact(3).enumerate() {
+            assert!((row.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+            assert!(row[c] > 0.9, "{:?}", row);
+        }
+    }
+
+    #[test]
+    fn serialized_models_round_trip() {
+        let (features, targets) = binary_data(&mut Rng::new(17));
+        let model = fit(&features, &targets, 2, 1e-3, 0.0, LogisticSolver::Lbfgs);
+        let restored = LogisticRegression::decode(&model.to_bytes()).unwrap();
+
+        assert_eq!(restored.dims, model.dims);
+        assert_eq!(restored.classes, model.classes);
+        assert_eq!(restored.weights, model.weights);
+        assert_eq!(restored.biases, model.biases);
+        assert_eq!(restored.probabilities(&features), model.probabilities(&features));
+    }
+
+    #[test]
+    fn truncated_or_padded_payloads_are_rejected() {
+        let (features, targets) = binary_data(&mut Rng::new(20));
+        let bytes = fit(&features, &targets, 2, 1e-3, 0.0, LogisticSolver::Lbfgs).to_bytes();
+
+        for end in 0..bytes.len() {
+            assert!(LogisticRegression::decode(&bytes[..end]).is_err(), "payload cut at {} was accepted", end);
+        }
+        let mut padded = bytes.clone();
+        padded.push(0);
+        assert!(LogisticRegression::decode(&padded).is_err());
+
+        let mut newer = bytes.clone();
+        newer[4] = 2;
+        assert!(LogisticRegression::decode(&newer).err().unwrap().contains("version"));
+    }
+
+    #[test]
+    fn inconsistent_shapes_are_rejected() {
+        let mut writer = ByteWriter::new(MAGIC, VERSION);
+        writer.write_u32(2);
+        writer.write_f64_slice(&[0.0, 1.0, 2.0]);
+        writer.write_f64_slice(&[0.0; 4]);
+        writer.write_f64_slice(&[0.0; 3]);
+        let message = LogisticRegression::decode(&writer.into_bytes()).err().unwrap();
+        assert_eq!(message, "Inconsistent logistic regression payload");
+    }
+}
@@ -1,5 +1,5 @@
 use wasm_bindgen::prelude::*;
-use js_sys::{Array, Float64Array, Object, Reflect};
+use js_sys::{Float64Array, Object, Reflect};
 use bumpalo::Bump;
 
 #[cfg(feature = "simd")]
@@ -155,9 +155,16 @@ pub fn linear_regression_f64(x: &JsValue, y: &JsValue) -> Result<JsValue, JsValu
 /// Predict values using linear regression
 ///
 /// Takes x array, slope, and intercept, and returns predicted y values.
+/// The slope may also be an array of coefficients, in which case x is a row-major matrix
+/// with one column per coefficient (as returned by `multiple_linear_regression_f64`).
 /// This is much faster than using JavaScript, especially for large arrays.
 #[wasm_bindgen]
-pub fn linear_regression_predict_f64(x: &JsValue, slope: f64, intercept: f64) -> Result<JsValue, JsValue> {
+pub fn linear_regression_predict_f64(x: &JsValue, slope: &JsValue, intercept: f64) -> Result<JsValue, JsValue> {
+    let slope = match slope.as_f64() {
+        Some(slope) => slope,
+        None => return predict_linear_rows(x, &Float64Array::new(slope).to_vec(), intercept),
+    };
+
     // Convert input to typed array for better performance
     let x_array = Float64Array::new(x);
     let length = x_array.length() as usize;
@@ -211,347 +218,38 @@ pub fn linear_regression_predict_f64(x: &JsValue, slope: f64, intercept: f64) ->
     Ok(result_array.into())
 }
 
-/// K-means clustering implementation
+/// Copy a row-major matrix out of a typed array
 ///
-/// Takes data points and k, and returns cluster assignments and centroids.
-/// This is much faster than using JavaScript, especially for large datasets.
-#[wasm_bindgen]
-pub fn kmeans_clustering_f64(data: &JsValue, k: usize, max_iterations: usize) -> Result<JsValue, JsValue> {
-    // Convert input to typed array for better performance
-    let data_array = Float64Array::new(data);
-    let num_points = data_array.length() as usize / 2; // Assuming 2D points (x, y)
-    
-    // Validate inputs
-    if num_points < k {
-        return Err(JsValue::from_str("Number of points must be greater than or equal to k"));
-    }
-    
-    if k == 0 {
-        return Err(JsValue::from_str("k must be greater than 0"));
-    }
-    
-    // Allocate memory for the input data
-    let bump = Bump::new();
-    let points = bump.alloc_slice_fill_copy(num_points * 2, 0.0);
-    
-    // Copy input data
-    for i in 0..(num_points * 2) {
-        points[i] = data_array.get_index(i as u32);
-    }
-    
-    // Initialize centroids using k-means++ initialization
-    let mut centroids = Vec::with_capacity(k * 2);
-    
-    // Choose the first centroid randomly
-    let first_index = (js_sys::Math::random() * num_points as f64) as usize;
-    centroids.push(points[first_index * 2]);
-    centroids.push(points[first_index * 2 + 1]);
-    
-    // Choose the remaining centroids
-    for _ in 1..k {
-        let mut distances = Vec::with_capacity(num_points);
-        
-        // Calculate the distance from each point to the nearest centroid
-        for i in 0..num_points {
-            let mut min_dist = f64::MAX;
-            
-            for j in 0..(centroids.len() / 2) {
-                let dx = points[i * 2] - centroids[j * 2];
-                let dy = points[i * 2 + 1] - centroids[j * 2 + 1];
-                let dist = dx * dx + dy * dy;
-                
-                if dist < min_dist {
-                    min_dist = dist;
-                }
-            }
-            
-            distances.push(min_dist);
-        }
-        
-        // Choose the next centroid with probability proportional to distance squared
-        let mut sum_distances = 0.0;
-        for dist in &distances {
-            sum_distances += dist;
-        }
-        
-        let mut target = js_sys::Math::random() * sum_distances;
-        let mut next_index = 0;
-        
-        for (i, dist) in distances.iter().enumerate() {
-            target -= dist;
-            if target <= 0.0 {
-                next_index = i;
-                break;
-            }
-        }
-        
-        centroids.push(points[next_index * 2]);
-        centroids.push(points[next_index * 2 + 1]);
-    }
-    
-    // Allocate memory for cluster assignments
-    let mut assignments = vec![0; num_points];
-    
-    // Run k-means algorithm
-    let mut converged = false;
-    let mut iteration = 0;
-    
-    while !converged && iteration < max_iterations {
-        // Assign points to clusters
-        let mut changed = false;
-        
-        for i in 0..num_points {
-            let mut min_dist = f64::MAX;
-            let mut min_cluster = 0;
-            
-            for j in 0..k {
-                let dx = points[i * 2] - centroids[j * 2];
-                let dy = points[i * 2 + 1] - centroids[j * 2 + 1];
-                let dist = dx * dx + dy * dy;
-                
-                if dist < min_dist {
-                    min_dist = dist;
-                    min_cluster = j;
-                }
-            }
-            
-            if assignments[i] != min_cluster {
-                assignments[i] = min_cluster;
-                changed = true;
-            }
-        }
-        
-        // Check for convergence
-        if !changed {
-            converged = true;
-            break;
-        }
-        
-        // Update centroids
-        let mut new_centroids = vec![0.0; k * 2];
-        let mut counts = vec![0; k];
-        
-        for i in 0..num_points {
-            let cluster = assignments[i];
-            new_centroids[cluster * 2] += points[i * 2];
-            new_centroids[cluster * 2 + 1] += points[i * 2 + 1];
-            counts[cluster] += 1;
-        }
-        
-        for j in 0..k {
-            if counts[j] > 0 {
-                new_centroids[j * 2] /= counts[j] as f64;
-                new_centroids[j * 2 + 1] /= counts[j] as f64;
-            } else {
-                // If a cluster is empty, reinitialize its centroid
-                let random_index = (js_sys::Math::random() * num_points as f64) as usize;
-                new_centroids[j * 2] = points[random_index * 2];
-                new_centroids[j * 2 + 1] = points[random_index * 2 + 1];
-            }
-        }
-        
-        // Update centroids
-        centroids = new_centroids;
-        
-        iteration += 1;
-    }
-    
-    // Create result object
-    let result = Object::new();
-    
-    // Create assignments array
-    let assignments_array = Array::new_with_length(num_points as u32);
-    for (i, &cluster) in assignments.iter().enumerate() {
-        assignments_array.set(i as u32, JsValue::from_f64(cluster as f64));
-    }
-    
-    // Create centroids array
-    let centroids_array = Float64Array::new_with_length((k * 2) as u32);
-    for (i, &value) in centroids.iter().enumerate() {
-        centroids_array.set_index(i as u32, value);
+/// Takes the array and the number of columns, and returns the values with the row count.
+/// Shared by the model entry points that accept N x D data.
+pub(crate) fn read_rows(data: &JsValue, dims: usize) -> Result<(Vec<f64>, usize), JsValue> {
+    if dims == 0 {
+        return Err(JsValue::from_str("Dimensionality must be greater than 0"));
     }
-    
-    // Set result properties
-    Reflect::set(&result, &JsValue::from_str("assignments"), &assignments_array)?;
-    Reflect::set(&result, &JsValue::from_str("centroids"), &centroids_array)?;
-    Reflect::set(&result, &JsValue::from_str("iterations"), &JsValue::from_f64(iteration as f64))?;
-    Reflect::set(&result, &JsValue::from_str("converged"), &JsValue::from_bool(converged))?;
-    
-    Ok(result.into())
-}
 
-/// Principal Component Analysis (PCA) implementation
-///
-/// Takes data points and returns principal components and explained variance.
-/// This is much faster than using JavaScript, especially for large datasets.
-#[wasm_bindgen]
-pub fn pca_f64(data: &JsValue, num_components: usize) -> Result<JsValue, JsValue> {
     // Convert input to typed array for better performance
-    let data_array = Float64Array::new(data);
-    let num_points = data_array.length() as usize / 2; // Assuming 2D points (x, y)
-    
-    // Validate inputs
-    if num_points == 0 {
-        return Err(JsValue::from_str("Data must not be empty"));
-    }
-    
-    if num_components == 0 || num_components > 2 {
-        return Err(JsValue::from_str("Number of components must be 1 or 2 for 2D data"));
-    }
-    
-    // Allocate memory for the input data
-    let bump = Bump::new();
-    let points = bump.alloc_slice_fill_copy(num_points * 2, 0.0);
-    
-    // Copy input data
-    for i in 0..(num_points * 2) {
-        points[i] = data_array.get_index(i as u32);
-    }
-    
-    // Calculate means
-    let mut mean_x = 0.0;
-    let mut mean_y = 0.0;
-    
-    for i in 0..num_points {
-        mean_x += points[i * 2];
-        mean_y += points[i * 2 + 1];
-    }
-    
-    mean_x /= num_points as f64;
-    mean_y /= num_points as f64;
-    
-    // Center the data
-    for i in 0..num_points {
-        points[i * 2] -= mean_x;
-        points[i * 2 + 1] -= mean_y;
-    }
-    
-    // Calculate covariance matrix
-    let mut cov_xx = 0.0;
-    let mut cov_xy = 0.0;
-    let mut cov_yy = 0.0;
-    
-    for i in 0..num_points {
-        let x = points[i * 2];
-        let y = points[i * 2 + 1];
-        
-        cov_xx += x * x;
-        cov_xy += x * y;
-        cov_yy += y * y;
-    }
-    
-    cov_xx /= num_points as f64;
-    cov_xy /= num_points as f64;
-    cov_yy /= num_points as f64;
-    
-    // Calculate eigenvalues and eigenvectors
-    let trace = cov_xx + cov_yy;
-    let determinant = cov_xx * cov_yy - cov_xy * cov_xy;
-    
-    let discriminant = trace * trace - 4.0 * determinant;
-    
-    if discriminant < 0.0 {
-        return Err(JsValue::from_str("Negative discriminant in PCA"));
-    }
-    
-    let eigenvalue1 = (trace + discriminant.sqrt()) / 2.0;
-    let eigenvalue2 = (trace - discriminant.sqrt()) / 2.0;
-    
-    // Sort eigenvalues and eigenvectors
-    let (lambda1, lambda2) = if eigenvalue1 >= eigenvalue2 {
-        (eigenvalue1, eigenvalue2)
-    } else {
-        (eigenvalue2, eigenvalue1)
-    };
-    
-    // Calculate eigenvectors
-    let mut eigenvector1 = [0.0, 0.0];
-    let mut eigenvector2 = [0.0, 0.0];
-    
-    if cov_xy != 0.0 {
-        eigenvector1[0] = lambda1 - cov_yy;
-        eigenvector1[1] = cov_xy;
-        
-        eigenvector2[0] = lambda2 - cov_yy;
-        eigenvector2[1] = cov_xy;
-    } else {
-        // Special case: covariance matrix is diagonal
-        if cov_xx >= cov_yy {
-            eigenvector1[0] = 1.0;
-            eigenvector1[1] = 0.0;
-            
-            eigenvector2[0] = 0.0;
-            eigenvector2[1] = 1.0;
-        } else {
-            eigenvector1[0] = 0.0;
-            eigenvector1[1] = 1.0;
-            
-            eigenvector2[0] = 1.0;
-            eigenvector2[1] = 0.0;
-        }
-    }
-    
-    // Normalize eigenvectors
-    let norm1 = (eigenvector1[0] * eigenvector1[0] + eigenvector1[1] * eigenvector1[1]).sqrt();
-    eigenvector1[0] /= norm1;
-    eigenvector1[1] /= norm1;
-    
-    let norm2 = (eigenvector2[0] * eigenvector2[0] + eigenvector2[1] * eigenvector2[1]).sqrt();
-    eigenvector2[0] /= norm2;
-    eigenvector2[1] /= norm2;
-    
-    // Calculate explained variance
-    let total_variance = lambda1 + lambda2;
-    let explained_variance1 = lambda1 / total_variance;
-    let explained_variance2 = lambda2 / total_variance;
-    
-    // Project data onto principal components
-    let mut projected = vec![0.0; num_points * num_components];
-    
-    for i in 0..num_points {
-        let x = points[i * 2];
-        let y = points[i * 2 + 1];
-        
-        projected[i * num_components] = x * eigenvector1[0] + y * eigenvector1[1];
-        
-        if num_components > 1 {
-            projected[i * num_components + 1] = x * eigenvector2[0] + y * eigenvector2[1];
-        }
-    }
-    
-    // Create result object
-    let result = Object::new();
-    
-    // Create components array
-    let components_array = Float64Array::new_with_length((num_components * 2) as u32);
-    components_array.set_index(0, eigenvector1[0]);
-    components_array.set_index(1, eigenvector1[1]);
-    
-    if num_components > 1 {
-        components_array.set_index(2, eigenvector2[0]);
-        components_array.set_index(3, eigenvector2[1]);
-    }
-    
-    // Create projected data array
-    let projected_array = Float64Array::new_with_length((num_points * num_components) as u32);
-    for (i, &value) in projected.iter().enumerate() {
-        projected_array.set_index(i as u32, value);
+    let values = Float64Array::new(data).to_vec();
+
+    if values.len() % dims != 0 {
+        return Err(JsValue::from_str("Data length must be a multiple of the dimensionality"));
     }
-    
-    // Create explained variance array
-    let explained_variance_array = Float64Array::new_with_length(num_components as u32);
-    explained_variance_array.set_index(0, explained_variance1);
-    
-    if num_components > 1 {
-        explained_variance_array.set_index(1, explained_variance2);
+
+    if values.iter().any(|v| !v.is_finite()) {
+        return Err(JsValue::from_str("Data must not contain NaN or infinite values"));
     }
-    
-    // Set result properties
-    Reflect::set(&result, &JsValue::from_str("components"), &components_array)?;
-    Reflect::set(&result, &JsValue::from_str("projected"), &projected_array)?;
-    Reflect::set(&result, &JsValue::from_str("explained_variance"), &explained_variance_array)?;
-    Reflect::set(&result, &JsValue::from_str("mean_x"), &JsValue::from_f64(mean_x))?;
-    Reflect::set(&result, &JsValue::from_str("mean_y"), &JsValue::from_f64(mean_y))?;
-    
-    Ok(result.into())
+
+    let rows = values.len() / dims;
+    Ok((values, rows))
+}
+
+/// Predict with a coefficient vector over a row-major feature matrix
+fn predict_linear_rows(x: &JsValue, coefficients: &[f64], intercept: f64) -> Result<JsValue, JsValue> {
+    let (features, _) = read_rows(x, coefficients.len())?;
+
+    let predictions: Vec<f64> = features
+        .chunks_exact(coefficients.len())
+        .map(|row| intercept + row.iter().zip(coefficients).map(|(x, b)| x * b).sum::<f64>())
+        .collect();
+
+    Ok(Float64Array::from(&predictions[..]).into())
 }
@@ -0,0 +1,590 @@
+use wasm_bindgen::prelude::*;
+use js_sys::{Float64Array, Object, Reflect, Uint32Array};
+
+use super::clustering::{silhouette_score, squared_distance};
+use super::machine_learning::read_rows;
+
+/// Confusion matrix of true against predicted labels
+///
+/// Takes the true and predicted labels and returns the sorted union of `labels` and a
+/// row-major `matrix` whose entry (i, j) counts samples of true label i predicted as j.
+#[wasm_bindgen]
+pub fn confusion_matrix_f64(y_true: &JsValue, y_pred: &JsValue) -> Result<JsValue, JsValue> {
+    let (truth, predicted) = read_pair(y_true, y_pred)?;
+    let labels = sorted_labels(truth.iter().chain(&predicted));
+    let matrix = confusion(&truth, &predicted, &labels);
+    let matrix: Vec<u32> = matrix.iter().map(|&c| c as u32).collect();
+
+    // Create result object
+    let result = Object::new();
+    Reflect::set(&result, &JsValue::from_str("labels"), &Float64Array::from(&labels[..]))?;
+    Reflect::set(&result, &JsValue::from_str("matrix"), &Uint32Array::from(&matrix[..]))?;
+
+    Ok(result.into())
+}
+
+/// Per-class precision, recall and F1 with their averages
+///
+/// Takes the true and predicted labels and returns, for every label in sorted order,
+/// `precision`, `recall`, `f1` and `support` (number of true samples), together with the
+/// `accuracy` and the macro (unweighted mean), weighted (by support) and micro averages.
+/// Undefined ratios (no predictions or no samples for a class) are reported as 0.
+#[wasm_bindgen]
+pub fn classification_report_f64(y_true: &JsValue, y_pred: &JsValue) -> Result<JsValue, JsValue> {
+    let (truth, predicted) = read_pair(y_true, y_pred)?;
+    let labels = sorted_labels(truth.iter().chain(&predicted));
+    let k = labels.len();
+    let matrix = confusion(&truth, &predicted, &labels);
+    let ClassScores { precision, recall, f1, support, accuracy } = class_scores(&matrix, k);
+
+    let n = truth.len() as f64;
+    let mean = |values: &[f64]| values.iter().sum::<f64>() / k as f64;
+    let weighted = |values: &[f64]| values.iter().zip(&support).map(|(v, &s)| v * s as f64).sum::<f64>() / n;
+
+    // Create result object
+    let result = Object::new();
+    Reflect::set(&result, &JsValue::from_str("labels"), &Float64Array::from(&labels[..]))?;
+    Reflect::set(&result, &JsValue::from_str("precision"), &Float64Array::from(&precision[..]))?;
+    Reflect::set(&result, &JsValue::from_str("recall"), &Float64Array::from(&recall[..]))?;
+    Reflect::set(&result, &JsValue::from_str("f1"), &Float64Array::from(&f1[..]))?;
+    Reflect::set(&result, &JsValue::from_str("support"), &Uint32Array::from(&support[..]))?;
+    Reflect::set(&result, &JsValue::from_str("accuracy"), &JsValue::from_f64(accuracy))?;
+    Reflect::set(&result, &JsValue::from_str("macro_precision"), &JsValue::from_f64(mean(&precision)))?;
+    Reflect::set(&result, &JsValue::from_str("macro_recall"), &JsValue::from_f64(mean(&recall)))?;
+    Reflect::set(&result, &JsValue::from_str("macro_f1"), &JsValue::from_f64(mean(&f1)))?;
+    Reflect::set(&result, &JsValue::from_str("weighted_f1"), &JsValue::from_f64(weighted(&f1)))?;
+    // Every error is both a false positive and a false negative, so micro P = R = F1 = accuracy
+    Reflect::set(&result, &JsValue::from_str("micro_f1"), &JsValue::from_f64(accuracy))?;
+
+    Ok(result.into())
+}
+
+/// Receiver operating characteristic curve
+///
+/// Takes true labels, scores (higher means more likely positive) and an optional positive
+/// label (default the largest label). Returns the `fpr`, `tpr` and `thresholds` at every
+/// distinct score in decreasing order, starting from (0, 0) at an infinite threshold,
+/// and the trapezoidal `auc`.
+#[wasm_bindgen]
+pub fn roc_curve_f64(y_true: &JsValue, scores: &JsValue, positive_label: Option<f64>) -> Result<JsValue, JsValue> {
+    let counts = read_ranked_counts(y_true, scores, positive_label)?;
+    let (fpr, tpr, thresholds) = roc_points(&counts);
+    let auc = trapezoid(&fpr, &tpr);
+
+    // Create result object
+    let result = Object::new();
+    Reflect::set(&result, &JsValue::from_str("fpr"), &Float64Array::from(&fpr[..]))?;
+    Reflect::set(&result, &JsValue::from_str("tpr"), &Float64Array::from(&tpr[..]))?;
+    Reflect::set(&result, &JsValue::from_str("thresholds"), &Float64Array::from(&thresholds[..]))?;
+    Reflect::set(&result, &JsValue::from_str("auc"), &JsValue::from_f64(auc))?;
+
+    Ok(result.into())
+}
+
+/// Precision-recall curve
+///
+/// Takes true labels, scores and an optional positive label (default the largest label).
+/// Returns `precision`, `recall` and `thresholds` at every distinct score in decreasing
+/// order, starting from precision 1 at recall 0, plus the step-wise `average_precision`
+/// and the trapezoidal `auc`.
+#[wasm_bindgen]
+pub fn precision_recall_curve_f64(y_true: &JsValue, scores: &JsValue, positive_label: Option<f64>) -> Result<JsValue, JsValue> {
+    let counts = read_ranked_counts(y_true, scores, positive_label)?;
+    let (precision, recall, thresholds) = precision_recall_points(&counts);
+    let average_precision: f64 = (1..recall.len()).map(|i| (recall[i] - recall[i - 1]) * precision[i]).sum();
+    let auc = trapezoid(&recall, &precision);
+
+    // Create result object
+    let result = Object::new();
+    Reflect::set(&result, &JsValue::from_str("precision"), &Float64Array::from(&precision[..]))?;
+    Reflect::set(&result, &JsValue::from_str("recall"), &Float64Array::from(&recall[..]))?;
+    Reflect::set(&result, &JsValue::from_str("thresholds"), &Float64Array::from(&thresholds[..]))?;
+    Reflect::set(&result, &JsValue::from_str("average_precision"), &JsValue::from_f64(average_precision))?;
+    Reflect::set(&result, &JsValue::from_str("auc"), &JsValue::from_f64(auc))?;
+
+    Ok(result.into())
+}
+
+/// Mean cross-entropy of predicted probabilities
+///
+/// Takes true labels and either one positive-class probability per sample (binary, the
+/// positive class being the largest label) or row-major probabilities with one column per
+/// class. Columns follow `labels` when given, otherwise the sorted distinct true labels.
+/// Probabilities are clipped to [1e-15, 1 - 1e-15].
+#[wasm_bindgen]
+pub fn log_loss_f64(y_true: &JsValue, probabilities: &JsValue, labels: &JsValue) -> Result<f64, JsValue> {
+    // Convert input to typed array for better performance
+    let truth = Float64Array::new(y_true).to_vec();
+    let probabilities = Float64Array::new(probabilities).to_vec();
+    let n = truth.len();
+
+    // Validate inputs
+    if n == 0 {
+        return Err(JsValue::from_str("Labels must not be empty"));
+    }
+
+    if !probabilities.len().is_multiple_of(n) || probabilities.is_empty() {
+        return Err(JsValue::from_str("Probabilities must have one value or one row per sample"));
+    }
+
+    let classes = if labels.is_undefined() || labels.is_null() {
+        sorted_labels(truth.iter())
+    } else {
+        Float64Array::new(labels).to_vec()
+    };
+
+    log_loss(&truth, &probabilities, &classes).map_err(|message| JsValue::from_str(&message))
+}
+
+/// Regression error metrics
+///
+/// Takes true and predicted values and returns the `mae`, `mse`, `rmse`, `mape` (mean
+/// absolute percentage error over samples with a non-zero target, as a fraction) and
+/// `r_squared`.
+#[wasm_bindgen]
+pub fn regression_metrics_f64(y_true: &JsValue, y_pred: &JsValue) -> Result<JsValue, JsValue> {
+    let (truth, predicted) = read_pair(y_true, y_pred)?;
+    let [mae, mse, mape, r_squared] = regression_errors(&truth, &predicted);
+
+    // Create result object
+    let result = Object::new();
+    Reflect::set(&result, &JsValue::from_str("mae"), &JsValue::from_f64(mae))?;
+    Reflect::set(&result, &JsValue::from_str("mse"), &JsValue::from_f64(mse))?;
+    Reflect::set(&result, &JsValue::from_str("rmse"), &JsValue::from_f64(mse.sqrt()))?;
+    Reflect::set(&result, &JsValue::from_str("mape"), &JsValue::from_f64(mape))?;
+    Reflect::set(&result, &JsValue::from_str("r_squared"), &JsValue::from_f64(r_squared))?;
+
+    Ok(result.into())
+}
+
+/// Mean silhouette coefficient of a clustering
+///
+/// Takes row-major data points, one cluster label per point and the dimensionality.
+/// Negative labels (noise) are ignored. Returns NaN with fewer than two clusters.
+#[wasm_bindgen]
+pub fn silhouette_score_f64(data: &JsValue, labels: &JsValue, dims: usize) -> Result<f64, JsValue> {
+    let (points, labels) = read_clustering(data, labels, dims)?;
+    Ok(silhouette_score(&points, dims, &labels))
+}
+
+/// Davies-Bouldin index of a clustering (lower is better)
+///
+/// Takes row-major data points, one cluster label per point and the dimensionality.
+/// Negative labels (noise) are ignored. Returns NaN with fewer than two clusters.
+#[wasm_bindgen]
+pub fn davies_bouldin_score_f64(data: &JsValue, labels: &JsValue, dims: usize) -> Result<f64, JsValue> {
+    let (points, labels) = read_clustering(data, labels, dims)?;
+    Ok(davies_bouldin_score(&points, dims, &labels))
+}
+
+/// Adjusted Rand index between two labellings of the same samples
+///
+/// Returns 1 for identical partitions (up to renaming) and about 0 for random ones.
+#[wasm_bindgen]
+pub fn adjusted_rand_index_f64(labels_a: &JsValue, labels_b: &JsValue) -> Result<f64, JsValue> {
+    let (a, b) = read_pair(labels_a, labels_b)?;
+    Ok(adjusted_rand_index(&a, &b))
+}
+
+/// Per-class precision, recall, F1 and support from a confusion matrix, plus the accuracy
+struct ClassScores {
+    precision: Vec<f64>,
+    recall: Vec<f64>,
+    f1: Vec<f64>,
+    support: Vec<u32>,
+    accuracy: f64,
+}
+
+/// Scores of every class of a row-major `k x k` confusion matrix
+fn class_scores(matrix: &[usize], k: usize) -> ClassScores {
+    let ratio = |a: f64, b: f64| if b > 0.0 { a / b } else { 0.0 };
+    let mut precision = Vec::with_capacity(k);
+    let mut recall = Vec::with_capacity(k);
+    let mut f1 = Vec::with_capacity(k);
+    let mut support = Vec::with_capacity(k);
+    let mut correct = 0.0;
+    for c in 0..k {
+        let true_positives = matrix[c * k + c] as f64;
+        let predicted_total = (0..k).map(|r| matrix[r * k + c]).sum::<usize>() as f64;
+        let actual_total = (0..k).map(|p| matrix[c * k + p]).sum::<usize>() as f64;
+        let p = ratio(true_positives, predicted_total);
+        let r = ratio(true_positives, actual_total);
+        precision.push(p);
+        recall.push(r);
+        f1.push(ratio(2.0 * p * r, p + r));
+        support.push(actual_total as u32);
+        correct += true_positives;
+    }
+
+    let accuracy = correct / matrix.iter().sum::<usize>() as f64;
+    ClassScores { precision, recall, f1, support, accuracy }
+}
+
+/// ROC curve points (fpr, tpr, thresholds) from `ranked_counts`, starting at (0, 0)
+fn roc_points(counts: &[(f64, f64, f64)]) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+    let positives = counts.last().map_or(0.0, |c| c.1);
+    let negatives = counts.last().map_or(0.0, |c| c.2);
+
+    let mut fpr = vec![0.0];
+    let mut tpr = vec![0.0];
+    let mut thresholds = vec![f64::INFINITY];
+    for &(threshold, tp, fp) in counts {
+        fpr.push(fp / negatives);
+        tpr.push(tp / positives);
+        thresholds.push(threshold);
+    }
+    (fpr, tpr, thresholds)
+}
+
+/// Precision-recall points (precision, recall, thresholds) from `ranked_counts`, starting
+/// at precision 1 and recall 0
+fn precision_recall_points(counts: &[(f64, f64, f64)]) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+    let positives = counts.last().map_or(0.0, |c| c.1);
+
+    let mut precision = vec![1.0];
+    let mut recall = vec![0.0];
+    let mut thresholds = vec![f64::INFINITY];
+    for &(threshold, tp, fp) in counts {
+        precision.push(tp / (tp + fp));
+        recall.push(tp / positives);
+        thresholds.push(threshold);
+    }
+    (precision, recall, thresholds)
+}
+
+/// Trapezoidal area under a curve given by its x and y coordinates
+fn trapezoid(x: &[f64], y: &[f64]) -> f64 {
+    (1..x.len()).map(|i| (x[i] - x[i - 1]) * (y[i] + y[i - 1]) / 2.0).sum()
+}
+
+/// Mean cross-entropy of one probability or one row of class probabilities per label
+fn log_loss(truth: &[f64], probabilities: &[f64], classes: &[f64]) -> Result<f64, String> {
+    let n = truth.len();
+    let columns = probabilities.len() / n;
+    let clip = |p: f64| p.clamp(1e-15, 1.0 - 1e-15);
+
+    let mut total = 0.0;
+    if columns == 1 {
+        let positive = classes.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        for (&t, &p) in truth.iter().zip(probabilities) {
+            total -= if t == positive { clip(p).ln() } else { (1.0 - clip(p)).ln() };
+        }
+    } else {
+        if classes.len() != columns {
+            return Err("Number of probability columns must match the number of labels".to_string());
+        }
+        for (&t, row) in truth.iter().zip(probabilities.chunks_exact(columns)) {
+            let column = classes.iter().position(|&c| c == t).ok_or_else(|| "True label missing from the labels".to_string())?;
+            // Renormalise each row after clipping, as probabilities may not sum to exactly 1
+            let sum: f64 = row.iter().map(|&p| clip(p)).sum();
+            total -= (clip(row[column]) / sum).ln();
+        }
+    }
+
+    Ok(total / n as f64)
+}
+
+/// Mean absolute error, mean squared error, MAPE and R-squared
+fn regression_errors(truth: &[f64], predicted: &[f64]) -> [f64; 4] {
+    let n = truth.len() as f64;
+
+    let mae = truth.iter().zip(predicted).map(|(t, p)| (t - p).abs()).sum::<f64>() / n;
+    let mse = truth.iter().zip(predicted).map(|(t, p)| (t - p) * (t - p)).sum::<f64>() / n;
+
+    let nonzero: Vec<f64> = truth.iter().zip(predicted).filter(|(t, _)| **t != 0.0).map(|(t, p)| ((t - p) / t).abs()).collect();
+    let mape = if nonzero.is_empty() { f64::NAN } else { nonzero.iter().sum::<f64>() / nonzero.len() as f64 };
+
+    let mean = truth.iter().sum::<f64>() / n;
+    let total: f64 = truth.iter().map(|t| (t - mean) * (t - mean)).sum();
+    let r_squared = if total > 0.0 { 1.0 - mse * n / total } else { f64::NAN };
+
+    [mae, mse, mape, r_squared]
+}
+
+/// Davies-Bouldin index of dense cluster labels; NaN with fewer than two clusters
+fn davies_bouldin_score(points: &[f64], dims: usize, labels: &[usize]) -> f64 {
+    let k = labels.iter().max().map_or(0, |&m| m + 1);
+    if k < 2 {
+        return f64::NAN;
+    }
+
+    let mut centroids = vec![0.0; k * dims];
+    let mut sizes = vec![0usize; k];
+    for (point, &label) in points.chunks_exact(dims).zip(labels) {
+        sizes[label] += 1;
+        for d in 0..dims {
+            centroids[label * dims + d] += point[d];
+        }
+    }
+    for (c, &size) in sizes.iter().enumerate() {
+        centroids[c * dims..(c + 1) * dims].iter_mut().for_each(|v| *v /= size as f64);
+    }
+
+    // Mean distance of members to their centroid
+    let mut scatter = vec![0.0; k];
+    for (point, &label) in points.chunks_exact(dims).zip(labels) {
+        scatter[label] += squared_distance(point, &centroids[label * dims..(label + 1) * dims]).sqrt() / sizes[label] as f64;
+    }
+
+    let total: f64 = (0..k)
+        .map(|i| {
+            (0..k)
+                .filter(|&j| j != i)
+                .map(|j| {
+                    let separation = squared_distance(&centroids[i * dims..(i + 1) * dims], &centroids[j * dims..(j + 1) * dims]).sqrt();
+                    if separation > 0.0 { (scatter[i] + scatter[j]) / separation } else { f64::INFINITY }
+                })
+                .fold(0.0, f64::max)
+        })
+        .sum();
+
+    total / k as f64
+}
+
+/// Adjusted Rand index of two labellings of the same samples
+fn adjusted_rand_index(a: &[f64], b: &[f64]) -> f64 {
+    let classes_a = sorted_labels(a.iter());
+    let classes_b = sorted_labels(b.iter());
+
+    let mut table = vec![0usize; classes_a.len() * classes_b.len()];
+    for (x, y) in a.iter().zip(b) {
+        let i = classes_a.binary_search_by(|c| c.partial_cmp(x).unwrap()).unwrap();
+        let j = classes_b.binary_search_by(|c| c.partial_cmp(y).unwrap()).unwrap();
+        table[i * classes_b.len() + j] += 1;
+    }
+
+    let pairs = |n: usize| (n * n.saturating_sub(1)) as f64 / 2.0;
+    let index: f64 = table.iter().map(|&n| pairs(n)).sum();
+    let rows: f64 = table.chunks_exact(classes_b.len()).map(|row| pairs(row.iter().sum())).sum();
+    let columns: f64 = (0..classes_b.len()).map(|j| pairs(table.iter().skip(j).step_by(classes_b.len()).sum())).sum();
+
+    let expected = rows * columns / pairs(a.len());
+    let maximum = (rows + columns) / 2.0;
+    if maximum == expected {
+        return 1.0;
+    }
+
+    (index - expected) / (maximum - expected)
+}
+
+/// Read two equally long, non-empty, finite label or value arrays
+fn read_pair(a: &JsValue, b: &JsValue) -> Result<(Vec<f64>, Vec<f64>), JsValue> {
+    // Convert input to typed array for better performance
+    let a = Float64Array::new(a).to_vec();
+    let b = Float64Array::new(b).to_vec();
+
+    if a.len() != b.len() {
+        return Err(JsValue::from_str("Inputs must have the same length"));
+    }
+
+    if a.is_empty() {
+        return Err(JsValue::from_str("Inputs must not be empty"));
+    }
+
+    if a.iter().chain(&b).any(|v| !v.is_finite()) {
+        return Err(JsValue::from_str("Inputs must not contain NaN or infinite values"));
+    }
+
+    Ok((a, b))
+}
+
+/// Distinct values in ascending order
+fn sorted_labels<'a>(values: impl Iterator<Item = &'a f64>) -> Vec<f64> {
+    let mut labels: Vec<f64> = values.copied().collect();
+    labels.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    labels.dedup();
+    labels
+}
+
+/// Row-major counts of (true, predicted) label pairs
+fn confusion(truth: &[f64], predicted: &[f64], labels: &[f64]) -> Vec<usize> {
+    let k = labels.len();
+    let position = |v: &f64| labels.binary_search_by(|c| c.partial_cmp(v).unwrap()).unwrap();
+    let mut matrix = vec![0; k * k];
+    for (t, p) in truth.iter().zip(predicted) {
+        matrix[position(t) * k + position(p)] += 1;
+    }
+    matrix
+}
+
+/// Validated ranked counts for the ROC and precision-recall curves
+fn read_ranked_counts(y_true: &JsValue, scores: &JsValue, positive_label: Option<f64>) -> Result<Vec<(f64, f64, f64)>, JsValue> {
+    let (truth, scores) = read_pair(y_true, scores)?;
+    let positive = positive_label.unwrap_or_else(|| truth.iter().cloned().fold(f64::NEG_INFINITY, f64::max));
+    let counts = ranked_counts(&truth, &scores, positive);
+
+    let (tp, fp) = counts.last().map_or((0.0, 0.0), |c| (c.1, c.2));
+    if tp == 0.0 || fp == 0.0 {
+        return Err(JsValue::from_str("Both positive and negative samples are required"));
+    }
+
+    Ok(counts)
+}
+
+/// Cumulative (threshold, true positives, false positives) at every distinct score, from
+/// the highest score down
+fn ranked_counts(truth: &[f64], scores: &[f64], positive: f64) -> Vec<(f64, f64, f64)> {
+    let mut order: Vec<usize> = (0..truth.len()).collect();
+    order.sort_by(|&a, &b| scores[b].partial_cmp(&scores[a]).unwrap());
+
+    let mut counts: Vec<(f64, f64, f64)> = Vec::new();
+    let (mut tp, mut fp) = (0.0, 0.0);
+    for (position, &i) in order.iter().enumerate() {
+        if truth[i] == positive {
+            tp += 1.0;
+        } else {
+            fp += 1.0;
+        }
+        let last_of_tie = order.get(position + 1).is_none_or(|&next| scores[next] != scores[i]);
+        if last_of_tie {
+            counts.push((scores[i], tp, fp));
+        }
+    }
+
+    counts
+}
+
+/// Validated points and dense cluster indices with noise (negative labels) removed
+fn read_clustering(data: &JsValue, labels: &JsValue, dims: usize) -> Result<(Vec<f64>, Vec<usize>), JsValue> {
+    let (points, num_points) = read_rows(data, dims)?;
+    let labels = Float64Array::new(labels).to_vec();
+
+    if labels.len() != num_points {
+        return Err(JsValue::from_str("Labels must have one value per point"));
+    }
+
+    if labels.iter().any(|v| !v.is_finite()) {
+        return Err(JsValue::from_str("Labels must not contain NaN or infinite values"));
+    }
+
+    let kept: Vec<usize> = (0..num_points).filter(|&i| labels[i] >= 0.0).collect();
+    let clusters = sorted_labels(kept.iter().map(|&i| &labels[i]));
+    let points = kept.iter().flat_map(|&i| points[i * dims..(i + 1) * dims].to_vec()).collect();
+    let dense = kept
+        .iter()
+        .map(|&i| clusters.binary_search_by(|c| c.partial_cmp(&labels[i]).unwrap()).unwrap())
+        .collect();
+
+    Ok((points, dense))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: &[f64], expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len(), "{:?} vs {:?}", actual, expected);
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-12, "{:?} vs {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn confusion_counts_label_pairs() {
+        let truth = [1.0, 1.0, 2.0, 3.0, 3.0, 3.0];
+        let predicted = [1.0, 2.0, 2.0, 3.0, 1.0, 3.0];
+        let labels = sorted_labels(truth.iter().chain(&predicted));
+
+        assert_eq!(labels, vec![1.0, 2.0, 3.0]);
+        assert_eq!(confusion(&truth, &predicted, &labels), vec![1, 1, 0, 0, 1, 0, 1, 0, 2]);
+    }
+
+    #[test]
+    fn class_scores_from_a_confusion_matrix() {
+        let scores = class_scores(&[1, 1, 0, 0, 1, 0, 1, 0, 2], 3);
+
+        assert_close(&scores.precision, &[0.5, 0.5, 1.0]);
+        assert_close(&scores.recall, &[0.5, 1.0, 2.0 / 3.0]);
+        assert_close(&scores.f1, &[0.5, 2.0 / 3.0, 0.8]);
+        assert_eq!(scores.support, vec![2, 1, 3]);
+        assert!((scores.accuracy - 4.0 / 6.0).abs() < 1e-12);
+
+        // A class that is never predicted has zero precision rather than NaN
+        let never_predicted = class_scores(&[2, 0, 1, 0], 2);
+        assert_close(&never_predicted.precision, &[2.0 / 3.0, 0.0]);
+        assert_close(&never_predicted.f1[1..], &[0.0]);
+    }
+
+    #[test]
+    fn roc_curve_with_tied_scores() {
+        let truth = [1.0, 0.0, 1.0, 1.0, 0.0];
+        let scores = [0.9, 0.8, 0.8, 0.4, 0.1];
+        let counts = ranked_counts(&truth, &scores, 1.0);
+        assert_eq!(counts, vec![(0.9, 1.0, 0.0), (0.8, 2.0, 1.0), (0.4, 3.0, 1.0), (0.1, 3.0, 2.0)]);
+
+        let (fpr, tpr, thresholds) = roc_points(&counts);
+        assert_close(&fpr, &[0.0, 0.0, 0.5, 0.5, 1.0]);
+        assert_close(&tpr, &[0.0, 1.0 / 3.0, 2.0 / 3.0, 1.0, 1.0]);
+        assert_eq!(thresholds[0], f64::INFINITY);
+        // 4 of 6 positive-negative pairs are ordered correctly and the tie counts half
+        assert!((trapezoid(&fpr, &tpr) - 4.5 / 6.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn perfect_and_reversed_rankings() {
+        let truth = [0.0, 0.0, 1.0, 1.0];
+        let (fpr, tpr, _) = roc_points(&ranked_counts(&truth, &[0.1, 0.2, 0.8, 0.9], 1.0));
+        assert_eq!(trapezoid(&fpr, &tpr), 1.0);
+        let (fpr, tpr, _) = roc_points(&ranked_counts(&truth, &[0.9, 0.8, 0.2, 0.1], 1.0));
+        assert_eq!(trapezoid(&fpr, &tpr), 0.0);
+    }
+
+    #[test]
+    fn precision_recall_points_follow_the_ranking() {
+        let counts = ranked_counts(&[1.0, 0.0, 1.0, 0.0], &[0.9, 0.7, 0.6, 0.2], 1.0);
+        let (precision, recall, _) = precision_recall_points(&counts);
+
+        assert_close(&precision, &[1.0, 1.0, 0.5, 2.0 / 3.0, 0.5]);
+        assert_close(&recall, &[0.0, 0.5, 0.5, 1.0, 1.0]);
+        let average_precision: f64 = (1..recall.len()).map(|i| (recall[i] - recall[i - 1]) * precision[i]).sum();
+        assert!((average_precision - (0.5 + 0.5 * 2.0 / 3.0)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn log_loss_for_binary_and_multiclass_probabilities() {
+        let binary = log_loss(&[0.0, 1.0], &[0.2, 0.6], &[0.0, 1.0]).unwrap();
+        assert!((binary - -(0.8f64.ln() + 0.6f64.ln()) / 2.0).abs() < 1e-12);
+
+        // Rows are renormalised, so [0.4, 0.2, 0.2] behaves like [0.5, 0.25, 0.25]
+        let multiclass = log_loss(&[3.0, 1.0], &[0.2, 0.3, 0.5, 0.4, 0.2, 0.2], &[1.0, 2.0, 3.0]).unwrap();
+        assert!((multiclass - -(0.5f64.ln() + 0.5f64.ln()) / 2.0).abs() < 1e-12);
+
+        // Certain mistakes are clipped rather than infinite
+        assert!(log_loss(&[1.0], &[0.0], &[0.0, 1.0]).unwrap().is_finite());
+        assert!(log_loss(&[4.0], &[0.5, 0.5], &[1.0, 2.0]).is_err());
+        assert!(log_loss(&[1.0], &[0.5, 0.5], &[1.0, 2.0, 3.0]).is_err());
+    }
+
+    #[test]
+    fn regression_errors_of_known_predictions() {
+        let [mae, mse, mape, r_squared] = regression_errors(&[1.0, 2.0, 3.0, 0.0], &[2.0, 2.0, 1.0, 1.0]);
+        assert!((mae - 1.0).abs() < 1e-12);
+        assert!((mse - 1.5).abs() < 1e-12);
+        // The zero target is left out of the percentage error
+        assert!((mape - (1.0 + 0.0 + 2.0 / 3.0) / 3.0).abs() < 1e-12);
+        assert!((r_squared - (1.0 - 6.0 / 5.0)).abs() < 1e-12);
+
+        let [_, _, mape, r_squared] = regression_errors(&[0.0, 0.0], &[1.0, 0.0]);
+        assert!(mape.is_nan() && r_squared.is_nan());
+    }
+
+    #[test]
+    fn davies_bouldin_of_two_clusters() {
+        // Scatter 1 in each cluster, centroids 10 apart
+        let points = [0.0, 0.0, 2.0, 0.0, 10.0, 0.0, 12.0, 0.0];
+        let score = davies_bouldin_score(&points, 2, &[0, 0, 1, 1]);
+        assert!((score - 0.2).abs() < 1e-12);
+        assert!(davies_bouldin_score(&points, 2, &[0, 0, 0, 0]).is_nan());
+    }
+
+    #[test]
+    fn adjusted_rand_index_of_known_partitions() {
+        let a = [0.0, 0.0, 1.0, 1.0, 2.0, 2.0];
+        assert_eq!(adjusted_rand_index(&a, &a), 1.0);
+        assert_eq!(adjusted_rand_index(&a, &[5.0, 5.0, 3.0, 3.0, 4.0, 4.0]), 1.0);
+
+        // Hand-computed: index 2, expected 1.2, maximum 4.5
+        let ari = adjusted_rand_index(&a, &[0.0, 0.0, 0.0, 1.0, 1.0, 1.0]);
+        assert!((ari - (2.0 - 1.2) / (4.5 - 1.2)).abs() < 1e-12);
+    }
+}
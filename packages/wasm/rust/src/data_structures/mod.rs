@@ -4,28 +4,86 @@ pub mod numeric;
 pub mod numeric_stats;
 pub mod numeric_stats_extended;
 pub mod time_series;
+pub mod time_series_decomposition;
+pub mod time_series_forecasting;
+pub mod time_series_anomaly;
+pub mod time_series_similarity;
 pub mod machine_learning;
+pub mod clustering;
+pub mod clustering_density;
+pub mod clustering_hierarchical;
+pub mod gaussian_mixture;
+pub mod pca;
+pub mod regression;
+pub mod logistic_regression;
+pub mod trees;
+pub mod neighbors;
+pub mod metrics;
+pub mod model_selection;
+pub mod preprocessing;
 pub mod neural_network;
+pub mod autodiff;
+pub mod network;
+pub mod network_layers;
+pub mod network_recurrent;
+pub mod network_import;
+pub mod network_quantized;
+pub mod network_training;
 pub mod string_ops;
 pub mod regex_ops;
 pub mod nlp_ops;
+pub mod naive_bayes;
 pub mod compression;
 pub mod unicode_ops;
 pub mod matrix;
 pub mod signal;
 pub mod hamt_vector;
 
+// Internal helpers
+mod interpolation;
+mod linalg;
+mod optimize;
+mod distributions;
+mod random;
+mod optimizer;
+mod serialization;
+mod json;
+mod protobuf;
+
 // Export submodules
 pub use list::*;
 pub use numeric::*;
 pub use numeric_stats::*;
 pub use numeric_stats_extended::*;
 pub use time_series::*;
+pub use time_series_decomposition::*;
+pub use time_series_forecasting::*;
+pub use time_series_anomaly::*;
+pub use time_series_similarity::*;
 pub use machine_learning::*;
+pub use clustering::*;
+pub use clustering_density::*;
+pub use clustering_hierarchical::*;
+pub use gaussian_mixture::*;
+pub use pca::*;
+pub use regression::*;
+pub use logistic_regression::*;
+pub use trees::*;
+pub use neighbors::*;
+pub use metrics::*;
+pub use model_selection::*;
+pub use preprocessing::*;
 pub use neural_network::*;
+pub use autodiff::*;
+pub use network::*;
+pub use network_layers::*;
+pub use network_recurrent::*;
+pub use network_import::*;
+pub use network_quantized::*;
 pub use string_ops::*;
 pub use regex_ops::*;
 pub use nlp_ops::*;
+pub use naive_bayes::*;
 pub use compression::*;
 pub use unicode_ops::*;
 pub use matrix::*;
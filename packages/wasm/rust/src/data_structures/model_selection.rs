@@ -0,0 +1,234 @@
+use wasm_bindgen::prelude::*;
+use js_sys::{Array, Float64Array, Object, Reflect, Uint32Array};
+
+use super::random::Rng;
+
+/// Split sample indices into a training and a test set
+///
+/// Takes the number of samples, the fraction to hold out for testing, an optional seed and
+/// optional labels to stratify on (each label keeps its share in both sets). Returns sorted
+/// `train` and `test` index arrays.
+#[wasm_bindgen]
+pub fn train_test_split_indices(
+    num_samples: usize,
+    test_fraction: f64,
+    seed: Option<u32>,
+    stratify: &JsValue,
+) -> Result<JsValue, JsValue> {
+    // Validate inputs
+    if !(test_fraction > 0.0 && test_fraction < 1.0) {
+        return Err(JsValue::from_str("Test fraction must be between 0 and 1"));
+    }
+
+    let groups = read_groups(num_samples, stratify)?;
+    let (train, test) = split_groups(groups, test_fraction, &mut Rng::from_seed(seed));
+
+    if train.is_empty() || test.is_empty() {
+        return Err(JsValue::from_str("Split leaves the training or test set empty"));
+    }
+
+    Ok(fold_object(train, test)?.into())
+}
+
+/// k-fold cross-validation splits
+///
+/// Takes the number of samples, the number of folds and whether to shuffle (with an
+/// optional seed) before cutting contiguous folds. Returns an array of `k` objects with
+/// sorted `train` and `test` index arrays; fold sizes differ by at most one.
+#[wasm_bindgen]
+pub fn k_fold_indices(num_samples: usize, k: usize, shuffle: Option<bool>, seed: Option<u32>) -> Result<JsValue, JsValue> {
+    // Validate inputs
+    if k < 2 || k > num_samples {
+        return Err(JsValue::from_str("Number of folds must be between 2 and the number of samples"));
+    }
+
+    let mut order: Vec<u32> = (0..num_samples as u32).collect();
+    if shuffle.unwrap_or(false) {
+        Rng::from_seed(seed).shuffle(&mut order);
+    }
+
+    fold_array(&contiguous_folds(&order, k), k)
+}
+
+/// Stratified k-fold cross-validation splits
+///
+/// Takes one class label per sample, the number of folds and whether to shuffle within
+/// each class (with an optional seed). Every fold receives close to the same share of
+/// every class. Returns an array of `k` objects with sorted `train` and `test` index arrays.
+#[wasm_bindgen]
+pub fn stratified_k_fold_indices(labels: &JsValue, k: usize, shuffle: Option<bool>, seed: Option<u32>) -> Result<JsValue, JsValue> {
+    let num_samples = Float64Array::new(labels).length() as usize;
+
+    // Validate inputs
+    if k < 2 || k > num_samples {
+        return Err(JsValue::from_str("Number of folds must be between 2 and the number of samples"));
+    }
+
+    let mut groups = read_groups(num_samples, labels)?;
+    if shuffle.unwrap_or(false) {
+        let mut rng = Rng::from_seed(seed);
+        groups.iter_mut().for_each(|group| rng.shuffle(group));
+    }
+
+    fold_array(&dealt_folds(&groups, num_samples, k), k)
+}
+
+/// Shuffle every group and hold out `test_fraction` of it; returns (train, test)
+fn split_groups(groups: Vec<Vec<u32>>, test_fraction: f64, rng: &mut Rng) -> (Vec<u32>, Vec<u32>) {
+    let mut train = Vec::new();
+    let mut test = Vec::new();
+    for mut group in groups {
+        rng.shuffle(&mut group);
+        let held_out = (group.len() as f64 * test_fraction).round() as usize;
+        test.extend_from_slice(&group[..held_out]);
+        train.extend_from_slice(&group[held_out..]);
+    }
+    (train, test)
+}
+
+/// Fold of every sample when `order` is cut into `k` contiguous folds
+///
+/// The first `order.len() % k` folds take one extra sample.
+fn contiguous_folds(order: &[u32], k: usize) -> Vec<usize> {
+    let num_samples = order.len();
+    let mut folds = vec![0usize; num_samples];
+    let mut start = 0;
+    for fold in 0..k {
+        let size = num_samples / k + usize::from(fold < num_samples % k);
+        for &i in &order[start..start + size] {
+            folds[i as usize] = fold;
+        }
+        start += size;
+    }
+    folds
+}
+
+/// Fold of every sample when each group is dealt round-robin over `k` folds
+///
+/// Dealing continues where the previous group stopped so that fold sizes stay balanced.
+fn dealt_folds(groups: &[Vec<u32>], num_samples: usize, k: usize) -> Vec<usize> {
+    let mut folds = vec![0usize; num_samples];
+    let mut next = 0;
+    for &i in groups.iter().flatten() {
+        folds[i as usize] = next;
+        next = (next + 1) % k;
+    }
+    folds
+}
+
+/// Sample indices grouped by label, or a single group when no labels are given
+fn read_groups(num_samples: usize, labels: &JsValue) -> Result<Vec<Vec<u32>>, JsValue> {
+    if labels.is_undefined() || labels.is_null() {
+        return Ok(vec![(0..num_samples as u32).collect()]);
+    }
+
+    // Convert input to typed array for better performance
+    let labels = Float64Array::new(labels).to_vec();
+
+    if labels.len() != num_samples {
+        return Err(JsValue::from_str("Labels must have one value per sample"));
+    }
+
+    if labels.iter().any(|v| !v.is_finite()) {
+        return Err(JsValue::from_str("Labels must not contain NaN or infinite values"));
+    }
+
+    Ok(group_by_label(&labels))
+}
+
+/// Sample indices grouped by label, in ascending label order
+fn group_by_label(labels: &[f64]) -> Vec<Vec<u32>> {
+    let mut classes = labels.to_vec();
+    classes.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    classes.dedup();
+
+    let mut groups = vec![Vec::new(); classes.len()];
+    for (i, label) in labels.iter().enumerate() {
+        let class = classes.binary_search_by(|c| c.partial_cmp(label).unwrap()).unwrap();
+        groups[class].push(i as u32);
+    }
+    groups
+}
+
+/// Array of {train, test} objects from a fold assignment per sample
+fn fold_array(folds: &[usize], k: usize) -> Result<JsValue, JsValue> {
+    let result = Array::new_with_length(k as u32);
+    for fold in 0..k {
+        let (test, train): (Vec<u32>, Vec<u32>) = (0..folds.len() as u32).partition(|&i| folds[i as usize] == fold);
+        result.set(fold as u32, fold_object(train, test)?.into());
+    }
+    Ok(result.into())
+}
+
+fn fold_object(mut train: Vec<u32>, mut test: Vec<u32>) -> Result<Object, JsValue> {
+    train.sort_unstable();
+    test.sort_unstable();
+
+    let fold = Object::new();
+    Reflect::set(&fold, &JsValue::from_str("train"), &Uint32Array::from(&train[..]))?;
+    Reflect::set(&fold, &JsValue::from_str("test"), &Uint32Array::from(&test[..]))?;
+    Ok(fold)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fold_sizes(folds: &[usize], k: usize) -> Vec<usize> {
+        (0..k).map(|fold| folds.iter().filter(|&&f| f == fold).count()).collect()
+    }
+
+    #[test]
+    fn contiguous_folds_differ_by_at_most_one() {
+        let order: Vec<u32> = (0..10).collect();
+        let folds = contiguous_folds(&order, 3);
+        assert_eq!(folds, vec![0, 0, 0, 0, 1, 1, 1, 2, 2, 2]);
+
+        let mut shuffled = order.clone();
+        Rng::new(5).shuffle(&mut shuffled);
+        assert_eq!(fold_sizes(&contiguous_folds(&shuffled, 4), 4), vec![3, 3, 2, 2]);
+    }
+
+    #[test]
+    fn groups_follow_sorted_labels() {
+        let groups = group_by_label(&[2.0, -1.0, 2.0, 0.5, -1.0]);
+        assert_eq!(groups, vec![vec![1, 4], vec![3], vec![0, 2]]);
+    }
+
+    #[test]
+    fn dealt_folds_keep_class_shares() {
+        let labels: Vec<f64> = (0..30).map(|i| if i < 20 { 0.0 } else { 1.0 }).collect();
+        let groups = group_by_label(&labels);
+        let folds = dealt_folds(&groups, 30, 5);
+
+        assert_eq!(fold_sizes(&folds, 5), vec![6; 5]);
+        for fold in 0..5 {
+            let minority = (20..30).filter(|&i| folds[i] == fold).count();
+            assert_eq!(minority, 2);
+        }
+    }
+
+    #[test]
+    fn stratified_split_holds_out_each_class() {
+        let labels: Vec<f64> = (0..40).map(|i| (i % 4) as f64).collect();
+        let (mut train, mut test) = split_groups(group_by_label(&labels), 0.25, &mut Rng::new(7));
+
+        // 2.5 of every 10 samples rounds to 3 per class
+        assert_eq!(test.len(), 12);
+        for class in 0..4 {
+            assert_eq!(test.iter().filter(|&&i| i % 4 == class).count(), 3);
+        }
+
+        // Train and test partition the samples
+        train.append(&mut test);
+        train.sort_unstable();
+        assert_eq!(train, (0..40).collect::<Vec<u32>>());
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_split() {
+        let split = || split_groups(vec![(0..50).collect()], 0.3, &mut Rng::new(11));
+        assert_eq!(split(), split());
+        assert_eq!(split().1.len(), 15);
+    }
+}
@@ -0,0 +1,450 @@
+use std::collections::{HashMap, HashSet};
+
+use wasm_bindgen::prelude::*;
+use js_sys::{Array, Object, Reflect};
+
+use super::nlp_ops::normalized_words;
+use super::serialization::{ByteReader, ByteWriter};
+
+/// Event models for Naive Bayes text classification
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NaiveBayesModel {
+    /// Word counts per document
+    Multinomial,
+    /// Word presence or absence per document
+    Bernoulli,
+}
+
+const MAGIC: &[u8; 4] = b"RDNB";
+const VERSION: u32 = 1;
+
+/// Naive Bayes text classifier trained incrementally on (text, label) pairs
+///
+/// Texts are tokenized like `nlp_word_frequencies` (lowercase words with surrounding
+/// punctuation removed). Words never seen during training are ignored at prediction time.
+#[wasm_bindgen]
+pub struct NaiveBayesClassifier {
+    model: NaiveBayesModel,
+    alpha: f64,
+    vocabulary: HashMap<String, usize>,
+    words: Vec<String>,
+    labels: Vec<String>,
+    /// Documents seen per class
+    documents: Vec<f64>,
+    /// Per class and word: occurrences (multinomial) or documents containing it (Bernoulli)
+    counts: Vec<Vec<f64>>,
+    /// Total word occurrences per class (multinomial)
+    totals: Vec<f64>,
+}
+
+#[wasm_bindgen]
+impl NaiveBayesClassifier {
+    /// Create an empty classifier
+    ///
+    /// Takes the event model (default multinomial) and the Laplace smoothing constant
+    /// (default 1).
+    #[wasm_bindgen(constructor)]
+    pub fn new(model: Option<NaiveBayesModel>, alpha: Option<f64>) -> Result<NaiveBayesClassifier, JsValue> {
+        let alpha = alpha.unwrap_or(1.0);
+        if alpha.is_nan() || alpha <= 0.0 {
+            return Err(JsValue::from_str("Smoothing alpha must be greater than 0"));
+        }
+
+        Ok(NaiveBayesClassifier::empty(model.unwrap_or(NaiveBayesModel::Multinomial), alpha))
+    }
+
+    /// Add one labelled document
+    pub fn train(&mut self, text: &str, label: &str) {
+        self.learn(text, label);
+    }
+
+    /// Add a batch of documents from parallel arrays of texts and labels
+    pub fn train_batch(&mut self, texts: &JsValue, labels: &JsValue) -> Result<(), JsValue> {
+        let texts = read_strings(texts, "Texts")?;
+        let labels = read_strings(labels, "Labels")?;
+
+        if texts.len() != labels.len() {
+            return Err(JsValue::from_str("Texts and labels must have the same length"));
+        }
+
+        for (text, label) in texts.iter().zip(&labels) {
+            self.learn(text, label);
+        }
+        Ok(())
+    }
+
+    /// Most probable label for a text
+    pub fn predict(&self, text: &str) -> Result<String, JsValue> {
+        let scores = self.log_posteriors(text)?;
+        let best = (0..scores.len()).fold(0, |best, c| if scores[c] > scores[best] { c } else { best });
+        Ok(self.labels[best].clone())
+    }
+
+    /// Posterior probability of every label, as an object keyed by label
+    pub fn predict_proba(&self, text: &str) -> Result<JsValue, JsValue> {
+        let scores = self.log_posteriors(text)?;
+
+        // Normalise in log space to avoid underflow on long documents
+        let max = scores.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let total: f64 = scores.iter().map(|s| (s - max).exp()).sum();
+
+        // Create result object
+        let result = Object::new();
+        for (label, score) in self.labels.iter().zip(&scores) {
+            Reflect::set(&result, &JsValue::from_str(label), &JsValue::from_f64((score - max).exp() / total))?;
+        }
+        Ok(result.into())
+    }
+
+    /// The `count` most indicative words for a label
+    ///
+    /// Words are ranked by the log ratio of their smoothed probability within the label to
+    /// that within all other labels pooled (just the within-label log probability when
+    /// there is a single label). Returns an array of `{ word, score }` objects, best first.
+    pub fn top_features(&self, label: &str, count: usize) -> Result<JsValue, JsValue> {
+        let class = self
+            .labels
+            .iter()
+            .position(|l| l == label)
+            .ok_or_else(|| JsValue::from_str("Unknown label"))?;
+
+        let mut rest = NaiveBayesClassifier::empty(self.model, self.alpha);
+        rest.words = self.words.clone();
+        rest.documents = vec![self.documents.iter().sum::<f64>() - self.documents[class]];
+        rest.totals = vec![self.totals.iter().sum::<f64>() - self.totals[class]];
+        rest.counts = vec![(0..self.words.len())
+            .map(|w| self.counts.iter().map(|c| c[w]).sum::<f64>() - self.counts[class][w])
+            .collect()];
+
+        let mut scored: Vec<(usize, f64)> = (0..self.words.len())
+            .map(|w| {
+                let within = self.log_probability(class, w);
+                let score = if self.labels.len() > 1 { within - rest.log_probability(0, w) } else { within };
+                (w, score)
+            })
+            .collect();
+        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap().then(a.0.cmp(&b.0)));
+
+        let result = Array::new();
+        for &(w, score) in scored.iter().take(count) {
+            let feature = Object::new();
+            Reflect::set(&feature, &JsValue::from_str("word"), &JsValue::from_str(&self.words[w]))?;
+            Reflect::set(&feature, &JsValue::from_str("score"), &JsValue::from_f64(score))?;
+            result.push(&feature);
+        }
+        Ok(result.into())
+    }
+
+    /// Serialize the classifier to bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = ByteWriter::new(MAGIC, VERSION);
+        writer.write_u32(self.model as u32);
+        writer.write_f64(self.alpha);
+        writer.write_u32(self.words.len() as u32);
+        for word in &self.words {
+            writer.write_str(word);
+        }
+        writer.write_u32(self.labels.len() as u32);
+        for (class, label) in self.labels.iter().enumerate() {
+            writer.write_str(label);
+            writer.write_f64(self.documents[class]);
+            writer.write_f64(self.totals[class]);
+            writer.write_f64_slice(&self.counts[class]);
+        }
+        writer.into_bytes()
+    }
+
+    /// Restore a classifier produced by `to_bytes`; training can continue afterwards
+    pub fn from_bytes(bytes: &[u8]) -> Result<NaiveBayesClassifier, JsValue> {
+        NaiveBayesClassifier::decode(bytes).map_err(|message| JsValue::from_str(&message))
+    }
+
+    /// Labels in the order they were first seen
+    #[wasm_bindgen(getter)]
+    pub fn labels(&self) -> Array {
+        self.labels.iter().map(|label| JsValue::from_str(label)).collect()
+    }
+
+    /// Number of distinct words seen during training
+    #[wasm_bindgen(getter)]
+    pub fn vocabulary_size(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Number of training documents seen
+    #[wasm_bindgen(getter)]
+    pub fn num_documents(&self) -> usize {
+        self.documents.iter().sum::<f64>() as usize
+    }
+}
+
+impl NaiveBayesClassifier {
+    fn empty(model: NaiveBayesModel, alpha: f64) -> NaiveBayesClassifier {
+        NaiveBayesClassifier {
+            model,
+            alpha,
+            vocabulary: HashMap::new(),
+            words: Vec::new(),
+            labels: Vec::new(),
+            documents: Vec::new(),
+            counts: Vec::new(),
+            totals: Vec::new(),
+        }
+    }
+
+    fn decode(bytes: &[u8]) -> Result<NaiveBayesClassifier, String> {
+        let (mut reader, version) = ByteReader::new(bytes, MAGIC)?;
+        if version != VERSION {
+            return Err(format!("Unsupported Naive Bayes format version {}", version));
+        }
+
+        let model = match reader.read_u32()? {
+            0 => NaiveBayesModel::Multinomial,
+            1 => NaiveBayesModel::Bernoulli,
+            other => return Err(format!("Unknown Naive Bayes model {}", other)),
+        };
+        let alpha = reader.read_f64()?;
+        let num_words = reader.read_u32()? as usize;
+        let words = (0..num_words).map(|_| reader.read_string()).collect::<Result<Vec<String>, String>>()?;
+
+        let num_labels = reader.read_u32()? as usize;
+        if alpha.is_nan() || alpha <= 0.0 {
+            return Err("Invalid smoothing alpha".to_string());
+        }
+        let mut classifier = NaiveBayesClassifier::empty(model, alpha);
+        for _ in 0..num_labels {
+            classifier.labels.push(reader.read_string()?);
+            classifier.documents.push(reader.read_f64()?);
+            classifier.totals.push(reader.read_f64()?);
+            let counts = reader.read_f64_vec()?;
+            if counts.len() != num_words {
+                return Err("Inconsistent Naive Bayes payload".to_string());
+            }
+            classifier.counts.push(counts);
+        }
+        reader.finish()?;
+
+        classifier.vocabulary = words.iter().enumerate().map(|(i, w)| (w.clone(), i)).collect();
+        if classifier.vocabulary.len() != words.len() {
+            return Err("Duplicate words in Naive Bayes payload".to_string());
+        }
+        classifier.words = words;
+        Ok(classifier)
+    }
+
+    /// Update the counts with one document
+    pub(crate) fn learn(&mut self, text: &str, label: &str) {
+        let class = match self.labels.iter().position(|l| l == label) {
+            Some(class) => class,
+            None => {
+                self.labels.push(label.to_string());
+                self.documents.push(0.0);
+                self.totals.push(0.0);
+                self.counts.push(vec![0.0; self.words.len()]);
+                self.labels.len() - 1
+            }
+        };
+
+        let words = normalized_words(text);
+        self.documents[class] += 1.0;
+
+        let mut seen = HashSet::new();
+        for word in words {
+            let index = self.word_index(word);
+            match self.model {
+                NaiveBayesModel::Multinomial => {
+                    self.counts[class][index] += 1.0;
+                    self.totals[class] += 1.0;
+                }
+                NaiveBayesModel::Bernoulli => {
+                    if seen.insert(index) {
+                        self.counts[class][index] += 1.0;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Vocabulary index of a word, adding it when new
+    fn word_index(&mut self, word: String) -> usize {
+        if let Some(&index) = self.vocabulary.get(&word) {
+            return index;
+        }
+        let index = self.words.len();
+        self.vocabulary.insert(word.clone(), index);
+        self.words.push(word);
+        self.counts.iter_mut().for_each(|counts| counts.push(0.0));
+        index
+    }
+
+    /// Smoothed log P(word | class)
+    fn log_probability(&self, class: usize, word: usize) -> f64 {
+        match self.model {
+            NaiveBayesModel::Multinomial => {
+                ((self.counts[class][word] + self.alpha) / (self.totals[class] + self.alpha * self.words.len() as f64)).ln()
+            }
+            NaiveBayesModel::Bernoulli => ((self.counts[class][word] + self.alpha) / (self.documents[class] + 2.0 * self.alpha)).ln(),
+        }
+    }
+
+    /// Unnormalised log posterior of every class
+    pub(crate) fn log_posteriors(&self, text: &str) -> Result<Vec<f64>, String> {
+        if self.labels.is_empty() {
+            return Err("Classifier has not been trained".to_string());
+        }
+
+        let total_documents: f64 = self.documents.iter().sum();
+        let known: Vec<usize> = normalized_words(text).iter().filter_map(|w| self.vocabulary.get(w).copied()).collect();
+
+        let scores = (0..self.labels.len())
+            .map(|class| {
+                let prior = (self.documents[class] / total_documents).ln();
+                match self.model {
+                    NaiveBayesModel::Multinomial => prior + known.iter().map(|&w| self.log_probability(class, w)).sum::<f64>(),
+                    NaiveBayesModel::Bernoulli => {
+                        // Every vocabulary word contributes, present or absent
+                        let present: HashSet<usize> = known.iter().copied().collect();
+                        prior
+                            + (0..self.words.len())
+                                .map(|w| {
+                                    let log_p = self.log_probability(class, w);
+                                    if present.contains(&w) { log_p } else { (1.0 - log_p.exp()).ln() }
+                                })
+                                .sum::<f64>()
+                    }
+                }
+            })
+            .collect();
+        Ok(scores)
+    }
+}
+
+/// Read a JS array of strings
+fn read_strings(values: &JsValue, name: &str) -> Result<Vec<String>, JsValue> {
+    let array = Array::from(values);
+    (0..array.length())
+        .map(|i| array.get(i).as_string().ok_or_else(|| JsValue::from_str(&format!("{} must contain only strings", name))))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trained(model: NaiveBayesModel) -> NaiveBayesClassifier {
+        let mut classifier = NaiveBayesClassifier::empty(model, 1.0);
+        classifier.learn("Good, great!", "pos");
+        classifier.learn("bad awful", "neg");
+        classifier
+    }
+
+    fn assert_scores(actual: &[f64], expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-12, "{:?} vs {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn multinomial_posteriors_match_smoothed_counts() {
+        let classifier = trained(NaiveBayesModel::Multinomial);
+        assert_eq!(classifier.words, vec!["good", "great", "bad", "awful"]);
+
+        // Two words per class and four in the vocabulary: (count + 1) / (2 + 4)
+        let (seen, unseen, prior) = ((2.0f64 / 6.0).ln(), (1.0f64 / 6.0).ln(), 0.5f64.ln());
+        let scores = classifier.log_posteriors("good GOOD bad").unwrap();
+        assert_scores(&scores, &[prior + 2.0 * seen + unseen, prior + seen + 2.0 * unseen]);
+
+        // Unknown words are ignored
+        assert_scores(&classifier.log_posteriors("meh").unwrap(), &[prior, prior]);
+    }
+
+    #[test]
+    fn bernoulli_posteriors_count_absent_words() {
+        let classifier = trained(NaiveBayesModel::Bernoulli);
+
+        // One document per class: P(word | class) is 2/3 when seen and 1/3 otherwise
+        let (high, low, prior) = ((2.0f64 / 3.0).ln(), (1.0f64 / 3.0).ln(), 0.5f64.ln());
+        let scores = classifier.log_posteriors("good good").unwrap();
+        assert_scores(&scores, &[prior + 3.0 * high + low, prior + high + 3.0 * low]);
+    }
+
+    #[test]
+    fn untrained_classifier_refuses_to_predict() {
+        assert!(NaiveBayesClassifier::empty(NaiveBayesModel::Multinomial, 1.0).log_posteriors("text").is_err());
+    }
+
+    #[test]
+    fn round_trip_keeps_predictions_and_training() {
+        for model in [NaiveBayesModel::Multinomial, NaiveBayesModel::Bernoulli] {
+            let mut original = trained(model);
+            let mut restored = NaiveBayesClassifier::decode(&original.to_bytes()).unwrap();
+            assert_eq!((restored.model, restored.alpha), (model, 1.0));
+            assert_eq!(restored.labels, original.labels);
+            assert_eq!(restored.vocabulary, original.vocabulary);
+            assert_eq!(restored.log_posteriors("great bad day").unwrap(), original.log_posteriors("great bad day").unwrap());
+
+            // Training continues where it stopped
+            original.learn("great day", "pos");
+            restored.learn("great day", "pos");
+            assert_eq!(restored.to_bytes(), original.to_bytes());
+        }
+    }
+
+    #[test]
+    fn truncated_or_padded_payloads_are_rejected() {
+        let bytes = trained(NaiveBayesModel::Multinomial).to_bytes();
+        for end in 0..bytes.len() {
+            assert!(NaiveBayesClassifier::decode(&bytes[..end]).is_err(), "payload cut at {} was accepted", end);
+        }
+        let mut padded = bytes.clone();
+        padded.push(0);
+        assert_eq!(NaiveBayesClassifier::decode(&padded).err().unwrap(), "Trailing bytes after payload");
+    }
+
+    #[test]
+    fn invalid_headers_are_rejected() {
+        let bytes = trained(NaiveBayesModel::Multinomial).to_bytes();
+
+        let mut newer = bytes.clone();
+        newer[4] = 2;
+        assert_eq!(NaiveBayesClassifier::decode(&newer).err().unwrap(), "Unsupported Naive Bayes format version 2");
+
+        let mut model = bytes.clone();
+        model[8] = 7;
+        assert_eq!(NaiveBayesClassifier::decode(&model).err().unwrap(), "Unknown Naive Bayes model 7");
+
+        let mut alpha = bytes.clone();
+        alpha[12..20].copy_from_slice(&(-1.0f64).to_le_bytes());
+        assert!(NaiveBayesClassifier::decode(&alpha).is_err());
+
+        let mut foreign = bytes;
+        foreign[0] = b'X';
+        assert!(NaiveBayesClassifier::decode(&foreign).is_err());
+    }
+
+    #[test]
+    fn inconsistent_payloads_are_rejected() {
+        let payload = |words: &[&str], counts: &[f64]| {
+            let mut writer = ByteWriter::new(MAGIC, VERSION);
+            writer.write_u32(0);
+            writer.write_f64(1.0);
+            writer.write_u32(words.len() as u32);
+            words.iter().for_each(|word| writer.write_str(word));
+            writer.write_u32(1);
+            writer.write_str("only");
+            writer.write_f64(1.0);
+            writer.write_f64(counts.iter().sum());
+            writer.write_f64_slice(counts);
+            writer.into_bytes()
+        };
+
+        assert!(NaiveBayesClassifier::decode(&payload(&["a", "b"], &[1.0, 1.0])).is_ok());
+        assert_eq!(NaiveBayesClassifier::decode(&payload(&["a", "b"], &[1.0])).err().unwrap(), "Inconsistent Naive Bayes payload");
+        assert_eq!(
+            NaiveBayesClassifier::decode(&payload(&["a", "a"], &[1.0, 1.0])).err().unwrap(),
+            "Duplicate words in Naive Bayes payload"
+        );
+    }
+}
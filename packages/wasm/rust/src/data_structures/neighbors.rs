@@ -0,0 +1,731 @@
+use wasm_bindgen::prelude::*;
+use js_sys::{Float64Array, Object, Reflect, Uint32Array};
+
+use super::machine_learning::read_rows;
+
+/// Distance functions supported by the spatial indexes
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DistanceMetric {
+    /// Straight-line (L2) distance
+    Euclidean,
+    /// Sum of absolute coordinate differences (L1)
+    Manhattan,
+    /// One minus the cosine similarity
+    Cosine,
+}
+
+/// Spatial index used to answer neighbor queries
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NeighborAlgorithm {
+    /// Axis-aligned bounding boxes; fastest in low dimensions
+    KdTree,
+    /// Nested hyperspheres; degrades more gracefully as dimensionality grows
+    BallTree,
+}
+
+const DEFAULT_LEAF_SIZE: usize = 16;
+
+/// KD-tree over row-major points for k-nearest and radius queries
+#[wasm_bindgen]
+pub struct KdTree {
+    index: SpatialTree,
+}
+
+#[wasm_bindgen]
+impl KdTree {
+    /// Build the tree
+    ///
+    /// Takes row-major points, their dimensionality, the metric (default Euclidean) and the
+    /// maximum number of points per leaf (default 16).
+    #[wasm_bindgen(constructor)]
+    pub fn new(data: &JsValue, dims: usize, metric: Option<DistanceMetric>, leaf_size: Option<usize>) -> Result<KdTree, JsValue> {
+        let index = build_index(data, dims, metric, leaf_size, NeighborAlgorithm::KdTree)?;
+        Ok(KdTree { index })
+    }
+
+    /// k nearest indexed points of every row-major query
+    ///
+    /// Returns `indices` and `distances`, `k` entries per query ordered from nearest.
+    pub fn query(&self, queries: &JsValue, k: usize) -> Result<JsValue, JsValue> {
+        query_nearest(&self.index, queries, k)
+    }
+
+    /// Indexed points within `radius` of every row-major query
+    ///
+    /// Returns flat `indices` and `distances` ordered from nearest, plus `offsets` so that the
+    /// neighbors of query i are entries `offsets[i]..offsets[i + 1]`.
+    pub fn query_radius(&self, queries: &JsValue, radius: f64) -> Result<JsValue, JsValue> {
+        query_within(&self.index, queries, radius)
+    }
+
+    /// Number of indexed points
+    #[wasm_bindgen(getter)]
+    pub fn size(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Dimensionality of the indexed points
+    #[wasm_bindgen(getter)]
+    pub fn dims(&self) -> usize {
+        self.index.dims
+    }
+}
+
+/// Ball tree over row-major points for k-nearest and radius queries
+#[wasm_bindgen]
+pub struct BallTree {
+    index: SpatialTree,
+}
+
+#[wasm_bindgen]
+impl BallTree {
+    /// Build the tree
+    ///
+    /// Takes row-major points, their dimensionality, the metric (default Euclidean) and the
+    /// maximum number of points per leaf (default 16).
+    #[wasm_bindgen(constructor)]
+    pub fn new(data: &JsValue, dims: usize, metric: Option<DistanceMetric>, leaf_size: Option<usize>) -> Result<BallTree, JsValue> {
+        let index = build_index(data, dims, metric, leaf_size, NeighborAlgorithm::BallTree)?;
+        Ok(BallTree { index })
+    }
+
+    /// k nearest indexed points of every row-major query
+    ///
+    /// Returns `indices` and `distances`, `k` entries per query ordered from nearest.
+    pub fn query(&self, queries: &JsValue, k: usize) -> Result<JsValue, JsValue> {
+        query_nearest(&self.index, queries, k)
+    }
+
+    /// Indexed points within `radius` of every row-major query
+    ///
+    /// Returns flat `indices` and `distances` ordered from nearest, plus `offsets` so that the
+    /// neighbors of query i are entries `offsets[i]..offsets[i + 1]`.
+    pub fn query_radius(&self, queries: &JsValue, radius: f64) -> Result<JsValue, JsValue> {
+        query_within(&self.index, queries, radius)
+    }
+
+    /// Number of indexed points
+    #[wasm_bindgen(getter)]
+    pub fn size(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Dimensionality of the indexed points
+    #[wasm_bindgen(getter)]
+    pub fn dims(&self) -> usize {
+        self.index.dims
+    }
+}
+
+/// k-nearest-neighbors classifier
+#[wasm_bindgen]
+pub struct KNeighborsClassifier {
+    index: SpatialTree,
+    k: usize,
+    weighted: bool,
+    classes: Vec<f64>,
+    /// Class index of every training row
+    labels: Vec<usize>,
+}
+
+#[wasm_bindgen]
+impl KNeighborsClassifier {
+    /// Index labelled training rows
+    ///
+    /// Takes row-major features, one class label per row, the dimensionality and k, plus the
+    /// metric (default Euclidean), whether votes are weighted by inverse distance (default
+    /// false) and the index (default KD-tree).
+    pub fn fit(
+        x: &JsValue,
+        y: &JsValue,
+        dims: usize,
+        k: usize,
+        metric: Option<DistanceMetric>,
+        weighted: Option<bool>,
+        algorithm: Option<NeighborAlgorithm>,
+    ) -> Result<KNeighborsClassifier, JsValue> {
+        let (index, targets) = build_model(x, y, dims, k, metric, algorithm)?;
+
+        let mut classes = targets.clone();
+        classes.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        classes.dedup();
+        let labels = targets.iter().map(|t| classes.iter().position(|c| c == t).unwrap()).collect();
+
+        Ok(KNeighborsClassifier { index, k, weighted: weighted.unwrap_or(false), classes, labels })
+    }
+
+    /// Majority (or weighted) vote of the k nearest training rows
+    pub fn predict(&self, x: &JsValue) -> Result<JsValue, JsValue> {
+        let probabilities = self.probabilities(x)?;
+        let predictions: Vec<f64> = probabilities
+            .chunks_exact(self.classes.len())
+            .map(|row| self.classes[(0..row.len()).fold(0, |best, c| if row[c] > row[best] { c } else { best })])
+            .collect();
+        Ok(Float64Array::from(&predictions[..]).into())
+    }
+
+    /// Row-major share of the (weighted) vote per class
+    pub fn predict_proba(&self, x: &JsValue) -> Result<JsValue, JsValue> {
+        Ok(Float64Array::from(&self.probabilities(x)?[..]).into())
+    }
+
+    /// Class labels in ascending order
+    #[wasm_bindgen(getter)]
+    pub fn classes(&self) -> Float64Array {
+        Float64Array::from(&self.classes[..])
+    }
+}
+
+impl KNeighborsClassifier {
+    fn probabilities(&self, x: &JsValue) -> Result<Vec<f64>, JsValue> {
+        let (queries, _) = read_rows(x, self.index.dims)?;
+        let num_classes = self.classes.len();
+        let mut probabilities = Vec::with_capacity(queries.len() / self.index.dims * num_classes);
+
+        for query in queries.chunks_exact(self.index.dims) {
+            let neighbors = self.index.nearest(query, self.k);
+            let weights = vote_weights(&neighbors, self.weighted);
+            let mut votes = vec![0.0; num_classes];
+            for ((i, _), weight) in neighbors.iter().zip(&weights) {
+                votes[self.labels[*i]] += weight;
+            }
+            let total: f64 = votes.iter().sum();
+            probabilities.extend(votes.iter().map(|v| v / total));
+        }
+
+        Ok(probabilities)
+    }
+}
+
+/// k-nearest-neighbors regressor
+#[wasm_bindgen]
+pub struct KNeighborsRegressor {
+    index: SpatialTree,
+    k: usize,
+    weighted: bool,
+    targets: Vec<f64>,
+}
+
+#[wasm_bindgen]
+impl KNeighborsRegressor {
+    /// Index training rows and their target values
+    ///
+    /// Takes row-major features, one target per row, the dimensionality and k, plus the
+    /// metric (default Euclidean), whether the average is weighted by inverse distance
+    /// (default false) and the index (default KD-tree).
+    pub fn fit(
+        x: &JsValue,
+        y: &JsValue,
+        dims: usize,
+        k: usize,
+        metric: Option<DistanceMetric>,
+        weighted: Option<bool>,
+        algorithm: Option<NeighborAlgorithm>,
+    ) -> Result<KNeighborsRegressor, JsValue> {
+        let (index, targets) = build_model(x, y, dims, k, metric, algorithm)?;
+        Ok(KNeighborsRegressor { index, k, weighted: weighted.unwrap_or(false), targets })
+    }
+
+    /// Mean (or inverse-distance weighted mean) target of the k nearest training rows
+    pub fn predict(&self, x: &JsValue) -> Result<JsValue, JsValue> {
+        let (queries, _) = read_rows(x, self.index.dims)?;
+        let predictions: Vec<f64> = queries
+            .chunks_exact(self.index.dims)
+            .map(|query| {
+                let neighbors = self.index.nearest(query, self.k);
+                let weights = vote_weights(&neighbors, self.weighted);
+                let total: f64 = weights.iter().sum();
+                neighbors.iter().zip(&weights).map(|((i, _), w)| self.targets[*i] * w).sum::<f64>() / total
+            })
+            .collect();
+        Ok(Float64Array::from(&predictions[..]).into())
+    }
+}
+
+/// Validate the shared constructor arguments and build the index
+fn build_index(
+    data: &JsValue,
+    dims: usize,
+    metric: Option<DistanceMetric>,
+    leaf_size: Option<usize>,
+    algorithm: NeighborAlgorithm,
+) -> Result<SpatialTree, JsValue> {
+    let (points, num_points) = read_rows(data, dims)?;
+
+    if num_points == 0 {
+        return Err(JsValue::from_str("Data must not be empty"));
+    }
+
+    if leaf_size == Some(0) {
+        return Err(JsValue::from_str("Leaf size must be greater than 0"));
+    }
+
+    Ok(SpatialTree::build(
+        &points,
+        dims,
+        metric.unwrap_or(DistanceMetric::Euclidean),
+        algorithm,
+        leaf_size.unwrap_or(DEFAULT_LEAF_SIZE),
+    ))
+}
+
+/// Validate training data for the kNN models and index the features
+fn build_model(
+    x: &JsValue,
+    y: &JsValue,
+    dims: usize,
+    k: usize,
+    metric: Option<DistanceMetric>,
+    algorithm: Option<NeighborAlgorithm>,
+) -> Result<(SpatialTree, Vec<f64>), JsValue> {
+    let index = build_index(x, dims, metric, None, algorithm.unwrap_or(NeighborAlgorithm::KdTree))?;
+    let targets = Float64Array::new(y).to_vec();
+
+    if targets.len() != index.len() {
+        return Err(JsValue::from_str("Targets must have one value per row"));
+    }
+
+    if targets.iter().any(|v| !v.is_finite()) {
+        return Err(JsValue::from_str("Targets must not contain NaN or infinite values"));
+    }
+
+    if k == 0 || k > index.len() {
+        return Err(JsValue::from_str("k must be between 1 and the number of training rows"));
+    }
+
+    Ok((index, targets))
+}
+
+/// Uniform weights, or inverse distances where exact matches take all the weight
+fn vote_weights(neighbors: &[(usize, f64)], weighted: bool) -> Vec<f64> {
+    if !weighted {
+        return vec![1.0; neighbors.len()];
+    }
+    if neighbors.iter().any(|&(_, d)| d == 0.0) {
+        return neighbors.iter().map(|&(_, d)| if d == 0.0 { 1.0 } else { 0.0 }).collect();
+    }
+    neighbors.iter().map(|&(_, d)| 1.0 / d).collect()
+}
+
+fn query_nearest(index: &SpatialTree, queries: &JsValue, k: usize) -> Result<JsValue, JsValue> {
+    let (queries, _) = read_rows(queries, index.dims)?;
+
+    if k == 0 || k > index.len() {
+        return Err(JsValue::from_str("k must be between 1 and the number of indexed points"));
+    }
+
+    let mut indices = Vec::with_capacity(queries.len() / index.dims * k);
+    let mut distances = Vec::with_capacity(indices.capacity());
+    for query in queries.chunks_exact(index.dims) {
+        for (i, distance) in index.nearest(query, k) {
+            indices.push(i as u32);
+            distances.push(distance);
+        }
+    }
+
+    // Create result object
+    let result = Object::new();
+    Reflect::set(&result, &JsValue::from_str("indices"), &Uint32Array::from(&indices[..]))?;
+    Reflect::set(&result, &JsValue::from_str("distances"), &Float64Array::from(&distances[..]))?;
+    Ok(result.into())
+}
+
+fn query_within(index: &SpatialTree, queries: &JsValue, radius: f64) -> Result<JsValue, JsValue> {
+    let (queries, _) = read_rows(queries, index.dims)?;
+
+    if radius.is_nan() || radius < 0.0 {
+        return Err(JsValue::from_str("Radius must be non-negative"));
+    }
+
+    let mut indices = Vec::new();
+    let mut distances = Vec::new();
+    let mut offsets = vec![0u32];
+    for query in queries.chunks_exact(index.dims) {
+        for (i, distance) in index.within(query, radius) {
+            indices.push(i as u32);
+            distances.push(distance);
+        }
+        offsets.push(indices.len() as u32);
+    }
+
+    // Create result object
+    let result = Object::new();
+    Reflect::set(&result, &JsValue::from_str("indices"), &Uint32Array::from(&indices[..]))?;
+    Reflect::set(&result, &JsValue::from_str("distances"), &Float64Array::from(&distances[..]))?;
+    Reflect::set(&result, &JsValue::from_str("offsets"), &Uint32Array::from(&offsets[..]))?;
+    Ok(result.into())
+}
+
+/// Node covering `order[start..end]`
+///
+/// KD-tree bounds are the per-axis minima followed by the maxima; ball tree bounds are the
+/// centre followed by the radius.
+struct SpatialNode {
+    start: usize,
+    end: usize,
+    children: Option<(usize, usize)>,
+    bounds: Vec<f64>,
+}
+
+/// KD-tree or ball tree shared by the wasm indexes, the kNN models and the clustering code
+///
+/// Searches work on a reduced distance that preserves ordering: the squared L2 distance for
+/// Euclidean, the L1 distance for Manhattan, and for cosine the squared L2 distance between
+/// unit-normalised points (equal to twice the cosine distance).
+pub(crate) struct SpatialTree {
+    pub(crate) dims: usize,
+    metric: DistanceMetric,
+    kind: NeighborAlgorithm,
+    points: Vec<f64>,
+    order: Vec<usize>,
+    nodes: Vec<SpatialNode>,
+    leaf_size: usize,
+}
+
+impl SpatialTree {
+    /// Index validated row-major points
+    pub(crate) fn build(points: &[f64], dims: usize, metric: DistanceMetric, kind: NeighborAlgorithm, leaf_size: usize) -> SpatialTree {
+        let points = prepare_points(points, dims, metric);
+
+        let num_points = points.len() / dims;
+        let mut tree = SpatialTree {
+            dims,
+            metric,
+            kind,
+            points,
+            order: (0..num_points).collect(),
+            nodes: Vec::new(),
+            leaf_size: leaf_size.max(1),
+        };
+        tree.build_node(0, num_points);
+        tree
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    fn point(&self, i: usize) -> &[f64] {
+        &self.points[i * self.dims..(i + 1) * self.dims]
+    }
+
+    /// Build the node over `order[start..end]` and return its index
+    fn build_node(&mut self, start: usize, end: usize) -> usize {
+        let dims = self.dims;
+
+        // Per-axis extent, used for both the KD bounds and the split axis
+        let mut lower = vec![f64::INFINITY; dims];
+        let mut upper = vec![f64::NEG_INFINITY; dims];
+        for &i in &self.order[start..end] {
+            for d in 0..dims {
+                lower[d] = lower[d].min(self.points[i * dims + d]);
+                upper[d] = upper[d].max(self.points[i * dims + d]);
+            }
+        }
+
+        let bounds = match self.kind {
+            NeighborAlgorithm::KdTree => lower.iter().chain(&upper).copied().collect(),
+            NeighborAlgorithm::BallTree => {
+                let mut centre = vec![0.0; dims];
+                for &i in &self.order[start..end] {
+                    for (c, &x) in centre.iter_mut().zip(self.point(i)) {
+                        *c += x / (end - start) as f64;
+                    }
+                }
+                let radius = self.order[start..end]
+                    .iter()
+                    .map(|&i| self.space_distance(&centre, self.point(i)))
+                    .fold(0.0, f64::max);
+                centre.push(radius);
+                centre
+            }
+        };
+
+        let node = self.nodes.len();
+        self.nodes.push(SpatialNode { start, end, children: None, bounds });
+
+        let axis = (0..dims).fold(0, |best, d| if upper[d] - lower[d] > upper[best] - lower[best] { d } else { best });
+        if end - start <= self.leaf_size || upper[axis] <= lower[axis] {
+            return node;
+        }
+
+        // Median split along the widest axis
+        let middle = (end - start) / 2;
+        let points = &self.points;
+        self.order[start..end].select_nth_unstable_by(middle, |&a, &b| {
+            points[a * dims + axis].partial_cmp(&points[b * dims + axis]).unwrap()
+        });
+
+        let left = self.build_node(start, start + middle);
+        let right = self.build_node(start + middle, end);
+        self.nodes[node].children = Some((left, right));
+        node
+    }
+
+    /// L2 distance (L1 for Manhattan) in the indexed space
+    fn space_distance(&self, a: &[f64], b: &[f64]) -> f64 {
+        match self.metric {
+            DistanceMetric::Manhattan => a.iter().zip(b).map(|(x, y)| (x - y).abs()).sum(),
+            _ => a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum::<f64>().sqrt(),
+        }
+    }
+
+    fn reduced_distance(&self, a: &[f64], b: &[f64]) -> f64 {
+        match self.metric {
+            DistanceMetric::Manhattan => a.iter().zip(b).map(|(x, y)| (x - y).abs()).sum(),
+            _ => a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum(),
+        }
+    }
+
+    fn reported_distance(&self, reduced: f64) -> f64 {
+        match self.metric {
+            DistanceMetric::Euclidean => reduced.sqrt(),
+            DistanceMetric::Manhattan => reduced,
+            DistanceMetric::Cosine => reduced / 2.0,
+        }
+    }
+
+    fn reduced_limit(&self, distance: f64) -> f64 {
+        match self.metric {
+            DistanceMetric::Euclidean => distance * distance,
+            DistanceMetric::Manhattan => distance,
+            DistanceMetric::Cosine => distance * 2.0,
+        }
+    }
+
+    /// Lower bound on the reduced distance from the query to any point under a node
+    fn lower_bound(&self, node: &SpatialNode, query: &[f64]) -> f64 {
+        match self.kind {
+            NeighborAlgorithm::KdTree => {
+                let (lower, upper) = node.bounds.split_at(self.dims);
+                let gaps = query.iter().zip(lower.iter().zip(upper)).map(|(&q, (&lo, &hi))| (lo - q).max(q - hi).max(0.0));
+                match self.metric {
+                    DistanceMetric::Manhattan => gaps.sum(),
+                    _ => gaps.map(|g| g * g).sum(),
+                }
+            }
+            NeighborAlgorithm::BallTree => {
+                let (centre, radius) = node.bounds.split_at(self.dims);
+                let gap = (self.space_distance(query, centre) - radius[0]).max(0.0);
+                match self.metric {
+                    DistanceMetric::Manhattan => gap,
+                    _ => gap * gap,
+                }
+            }
+        }
+    }
+
+    fn prepare(&self, query: &[f64]) -> Vec<f64> {
+        let mut query = query.to_vec();
+        if self.metric == DistanceMetric::Cosine {
+            normalise(&mut query);
+        }
+        query
+    }
+
+    /// The k nearest points as (index, distance), nearest first with ties broken by index
+    pub(crate) fn nearest(&self, query: &[f64], k: usize) -> Vec<(usize, f64)> {
+        let query = self.prepare(query);
+        let mut best: Vec<(f64, usize)> = Vec::with_capacity(k + 1);
+        if k > 0 {
+            self.search_nearest(0, &query, k, &mut best);
+        }
+        best.into_iter().map(|(d, i)| (i, self.reported_distance(d))).collect()
+    }
+
+    fn search_nearest(&self, node: usize, query: &[f64], k: usize, best: &mut Vec<(f64, usize)>) {
+        let current = &self.nodes[node];
+        match current.children {
+            None => {
+                for &i in &self.order[current.start..current.end] {
+                    let candidate = (self.reduced_distance(query, self.point(i)), i);
+                    if best.len() < k || candidate < best[best.len() - 1] {
+                        let position = best.partition_point(|entry| *entry < candidate);
+                        best.insert(position, candidate);
+                        best.truncate(k);
+                    }
+                }
+            }
+            Some((left, right)) => {
+                // Visit the closer child first so the farther one is more likely to be pruned
+                let left_bound = self.lower_bound(&self.nodes[left], query);
+                let right_bound = self.lower_bound(&self.nodes[right], query);
+                let visits = if left_bound <= right_bound {
+                    [(left, left_bound), (right, right_bound)]
+                } else {
+                    [(right, right_bound), (left, left_bound)]
+                };
+                for (child, bound) in visits {
+                    if best.len() < k || bound <= best[best.len() - 1].0 {
+                        self.search_nearest(child, query, k, best);
+                    }
+                }
+            }
+        }
+    }
+
+    /// All points within `radius` as (index, distance), nearest first
+    pub(crate) fn within(&self, query: &[f64], radius: f64) -> Vec<(usize, f64)> {
+        let query = self.prepare(query);
+        let limit = self.reduced_limit(radius);
+        let mut found = Vec::new();
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            let current = &self.nodes[node];
+            if self.lower_bound(current, &query) > limit {
+                continue;
+            }
+            match current.children {
+                None => {
+                    for &i in &self.order[current.start..current.end] {
+                        let distance = self.reduced_distance(&query, self.point(i));
+                        if distance <= limit {
+                            found.push((distance, i));
+                        }
+                    }
+                }
+                Some((left, right)) => {
+                    stack.push(right);
+                    stack.push(left);
+                }
+            }
+        }
+        found.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        found.into_iter().map(|(d, i)| (i, self.reported_distance(d))).collect()
+    }
+}
+
+/// Copy of row-major points ready for `metric_distance` (unit-normalised for cosine)
+pub(crate) fn prepare_points(points: &[f64], dims: usize, metric: DistanceMetric) -> Vec<f64> {
+    let mut prepared = points.to_vec();
+    if metric == DistanceMetric::Cosine {
+        prepared.chunks_exact_mut(dims).for_each(normalise);
+    }
+    prepared
+}
+
+/// Distance between two points from `prepare_points`, consistent with the tree searches
+pub(crate) fn metric_distance(a: &[f64], b: &[f64], metric: DistanceMetric) -> f64 {
+    match metric {
+        DistanceMetric::Euclidean => a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum::<f64>().sqrt(),
+        DistanceMetric::Manhattan => a.iter().zip(b).map(|(x, y)| (x - y).abs()).sum(),
+        DistanceMetric::Cosine => a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum::<f64>() / 2.0,
+    }
+}
+
+/// Scale to unit length; zero vectors are left unchanged
+fn normalise(values: &mut [f64]) {
+    let norm = values.iter().map(|v| v * v).sum::<f64>().sqrt();
+    if norm > 0.0 {
+        values.iter_mut().for_each(|v| *v /= norm);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::random::Rng;
+
+    const METRICS: [DistanceMetric; 3] = [DistanceMetric::Euclidean, DistanceMetric::Manhattan, DistanceMetric::Cosine];
+    const KINDS: [NeighborAlgorithm; 2] = [NeighborAlgorithm::KdTree, NeighborAlgorithm::BallTree];
+
+    /// Distance to every point, nearest first with ties broken by index
+    fn brute_force(points: &[f64], dims: usize, query: &[f64], metric: DistanceMetric) -> Vec<(usize, f64)> {
+        let points = prepare_points(points, dims, metric);
+        let query = prepare_points(query, dims, metric);
+        let mut all: Vec<(usize, f64)> =
+            points.chunks_exact(dims).map(|point| metric_distance(point, &query, metric)).enumerate().collect();
+        all.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap().then(a.0.cmp(&b.0)));
+        all
+    }
+
+    fn random_points(rng: &mut Rng, count: usize, dims: usize) -> Vec<f64> {
+        (0..count * dims).map(|_| rng.next_normal()).collect()
+    }
+
+    #[test]
+    fn nearest_matches_brute_force() {
+        let mut rng = Rng::new(1);
+        let points = random_points(&mut rng, 300, 3);
+        let queries = random_points(&mut rng, 20, 3);
+
+        for metric in METRICS {
+            for kind in KINDS {
+                let tree = SpatialTree::build(&points, 3, metric, kind, 4);
+                for query in queries.chunks_exact(3) {
+                    let expected = brute_force(&points, 3, query, metric);
+                    let found = tree.nearest(query, 7);
+                    assert_eq!(found.len(), 7);
+                    for ((i, d), (j, e)) in found.iter().zip(&expected) {
+                        assert_eq!(i, j, "{:?} {:?}", metric, kind);
+                        assert!((d - e).abs() < 1e-12);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn within_matches_brute_force() {
+        let mut rng = Rng::new(2);
+        let points = random_points(&mut rng, 250, 2);
+        let queries = random_points(&mut rng, 10, 2);
+
+        for metric in METRICS {
+            let radius = if metric == DistanceMetric::Cosine { 0.05 } else { 0.6 };
+            for kind in KINDS {
+                let tree = SpatialTree::build(&points, 2, metric, kind, 8);
+                for query in queries.chunks_exact(2) {
+                    let expected: Vec<usize> = brute_force(&points, 2, query, metric)
+                        .into_iter()
+                        .take_while(|&(_, d)| d <= radius)
+                        .map(|(i, _)| i)
+                        .collect();
+                    let found: Vec<usize> = tree.within(query, radius).into_iter().map(|(i, _)| i).collect();
+                    assert_eq!(found, expected, "{:?} {:?}", metric, kind);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn duplicate_points_are_ordered_by_index() {
+        let points = [1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 5.0, 5.0];
+        for kind in KINDS {
+            let tree = SpatialTree::build(&points, 2, DistanceMetric::Euclidean, kind, 1);
+            let found: Vec<usize> = tree.nearest(&[1.0, 1.0], 4).into_iter().map(|(i, _)| i).collect();
+            assert_eq!(found, vec![0, 2, 3, 1]);
+        }
+    }
+
+    #[test]
+    fn cosine_distance_is_one_minus_similarity() {
+        let points = prepare_points(&[1.0, 0.0, 3.0, 3.0, -2.0, 0.0], 2, DistanceMetric::Cosine);
+        let row = |i: usize| &points[i * 2..i * 2 + 2];
+        let distance = |a: usize, b: usize| metric_distance(row(a), row(b), DistanceMetric::Cosine);
+
+        assert!((distance(0, 1) - (1.0 - 0.5f64.sqrt())).abs() < 1e-12);
+        assert!((distance(0, 2) - 2.0).abs() < 1e-12);
+        assert_eq!(distance(1, 1), 0.0);
+    }
+
+    #[test]
+    fn large_k_returns_every_point() {
+        let points = random_points(&mut Rng::new(3), 40, 2);
+        let tree = SpatialTree::build(&points, 2, DistanceMetric::Manhattan, NeighborAlgorithm::BallTree, 3);
+        let found = tree.nearest(&[0.0, 0.0], 100);
+
+        assert_eq!(found.len(), 40);
+        assert!(found.windows(2).all(|w| w[0].1 <= w[1].1));
+        assert!(tree.nearest(&[0.0, 0.0], 0).is_empty());
+    }
+
+    #[test]
+    fn vote_weights_favour_exact_matches() {
+        assert_eq!(vote_weights(&[(0, 2.0), (1, 4.0)], false), vec![1.0, 1.0]);
+        assert_eq!(vote_weights(&[(0, 2.0), (1, 4.0)], true), vec![0.5, 0.25]);
+        assert_eq!(vote_weights(&[(0, 0.0), (1, 4.0), (2, 0.0)], true), vec![1.0, 0.0, 1.0]);
+    }
+}
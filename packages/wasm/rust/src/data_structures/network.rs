@@ -0,0 +1,1260 @@
+use wasm_bindgen::prelude::*;
+use js_sys::{Float64Array, Uint32Array};
+
+use super::autodiff::Graph;
+use super::machine_learning::read_rows;
+use super::network_layers::{Activation, BatchNorm, Conv2D, Dropout, LayerNorm, Pool2D, PoolingType, Shape};
+use super::network_recurrent::{Recurrent, RecurrentCell};
+use super::neural_network::{dot, ActivationFunction, Initializer, LossFunction};
+use super::optimizer::{Optimizer, Schedule, UpdateRule};
+use super::random::Rng;
+use super::serialization::{ByteReader, ByteWriter};
+
+const MAGIC: &[u8; 4] = b"RDNN";
+const VERSION: u32 = 1;
+
+/// Trainable values of a layer together with the gradient from the last backward pass
+pub(crate) struct Parameter {
+    pub(crate) values: Vec<f64>,
+    pub(crate) gradients: Vec<f64>,
+}
+
+impl Parameter {
+    pub(crate) fn new(values: Vec<f64>) -> Parameter {
+        let gradients = vec![0.0; values.len()];
+        Parameter { values, gradients }
+    }
+}
+
+/// Layer of a `Network` working on row-major batches
+///
+/// `forward` caches whatever `backward` needs; `backward` takes the loss gradient with
+/// respect to the outputs of the latest forward pass, stores the parameter gradients and
+/// returns the gradient with respect to the inputs.
+pub(crate) trait Layer {
+    fn input_size(&self) -> usize;
+    fn output_size(&self) -> usize;
+    fn forward(&mut self, inputs: &[f64], batch: usize, training: bool) -> Vec<f64>;
+    fn backward(&mut self, output_gradient: &[f64], batch: usize) -> Vec<f64>;
+    fn parameters(&mut self) -> Vec<&mut Parameter>;
+
+    /// Kind tag identifying the layer in serialized networks
+    fn kind(&self) -> LayerKind;
+
+    /// Write the hyperparameters, enough for `read_layer` to rebuild the layer
+    fn write_config(&self, writer: &mut ByteWriter);
+
+    /// Non-trainable values that are saved with the parameters, such as running statistics
+    fn state(&mut self) -> Vec<&mut Vec<f64>> {
+        Vec::new()
+    }
+
+    /// Shape of each output sample
+    fn output_shape(&self) -> Shape {
+        [self.output_size(), 1, 1]
+    }
+
+    /// Activation applied to the outputs, if any, so a loss can fuse with it
+    fn activation(&self) -> Option<ActivationFunction> {
+        None
+    }
+
+    /// Forget any state carried between batches
+    fn reset_state(&mut self) {}
+
+    /// Backward pass from the gradient with respect to the outputs before activation
+    fn backward_pre_activation(&mut self, gradient: &[f64], batch: usize) -> Vec<f64> {
+        self.backward(gradient, batch)
+    }
+}
+
+/// Kind of a `Network` layer, written before its configuration in serialized networks
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum LayerKind {
+    Dense,
+    Conv2D,
+    Pool2D,
+    BatchNorm,
+    LayerNorm,
+    Dropout,
+    Recurrent,
+    Activation,
+}
+
+/// Fully connected layer with weights stored as `units x inputs`, row-major
+///
+/// Training passes are recorded on an autodiff `Graph`, which supplies the gradients.
+/// Inference passes run directly on the weights and are only recorded if `backward`
+/// follows them.
+pub(crate) struct Dense {
+    inputs: usize,
+    units: usize,
+    activation: ActivationFunction,
+    weights: Parameter,
+    biases: Parameter,
+    /// Graph of the latest recorded forward pass
+    graph: Graph,
+    /// Inputs of the latest inference pass, replayed on the graph by `backward`
+    cached_inputs: Vec<f64>,
+}
+
+impl Dense {
+    // Nodes of the graph recorded by `forward`
+    const INPUTS: usize = 0;
+    const WEIGHTS: usize = 1;
+    const BIASES: usize = 2;
+    const PRE_ACTIVATIONS: usize = 4;
+    const OUTPUTS: usize = 5;
+
+    /// Dense layer with weights drawn by `initializer` and zero biases
+    pub(crate) fn new(inputs: usize, units: usize, activation: ActivationFunction, initializer: Initializer, rng: &mut Rng) -> Dense {
+        let weights = initializer.sample(units, inputs, inputs, units, rng);
+        Dense {
+            inputs,
+            units,
+            activation,
+            weights: Parameter::new(weights),
+            biases: Parameter::new(vec![0.0; units]),
+            graph: Graph::new(),
+            cached_inputs: Vec::new(),
+        }
+    }
+
+    /// Record the forward pass on the graph and return the outputs
+    fn record(&mut self, inputs: Vec<f64>, batch: usize) -> &[f64] {
+        let graph = &mut self.graph;
+        graph.truncate(0);
+        let x = graph.leaf(inputs, vec![batch, self.inputs]);
+        let w = graph.leaf(self.weights.values.clone(), vec![self.units, self.inputs]);
+        let b = graph.leaf(self.biases.values.clone(), vec![self.units]);
+        let product = graph.matmul(x, w, true).expect("dense inputs match the weights");
+        let pre_activations = graph.add(product, b).expect("biases broadcast over the batch");
+        let outputs = graph.activation(pre_activations, self.activation);
+        debug_assert_eq!((pre_activations, outputs), (Dense::PRE_ACTIVATIONS, Dense::OUTPUTS));
+        graph.value(outputs)
+    }
+
+    /// Backpropagate `gradient` from a node of the latest forward pass
+    fn backward_from(&mut self, node: usize, gradient: &[f64]) -> Vec<f64> {
+        if self.graph.len() == 0 {
+            let inputs = std::mem::take(&mut self.cached_inputs);
+            let batch = inputs.len() / self.inputs;
+            self.record(inputs, batch);
+        }
+        self.graph.backward(node, Some(gradient.to_vec())).expect("gradient matches the forward pass");
+        self.weights.gradients.copy_from_slice(self.graph.gradient(Dense::WEIGHTS));
+        self.biases.gradients.copy_from_slice(self.graph.gradient(Dense::BIASES));
+        self.graph.gradient(Dense::INPUTS).to_vec()
+    }
+}
+
+impl Layer for Dense {
+    fn input_size(&self) -> usize {
+        self.inputs
+    }
+
+    fn output_size(&self) -> usize {
+        self.units
+    }
+
+    fn forward(&mut self, inputs: &[f64], batch: usize, training: bool) -> Vec<f64> {
+        if training {
+            self.cached_inputs.clear();
+            return self.record(inputs.to_vec(), batch).to_vec();
+        }
+
+        // Inference reads the weights in place; the graph is rebuilt only if needed
+        self.graph.truncate(0);
+        self.cached_inputs = inputs.to_vec();
+        let mut outputs = Vec::with_capacity(batch * self.units);
+        for row in inputs.chunks_exact(self.inputs) {
+            for (weights, bias) in self.weights.values.chunks_exact(self.inputs).zip(&self.biases.values) {
+                outputs.push(bias + dot(row, weights));
+            }
+        }
+        self.activation.apply_rows(&mut outputs, self.units);
+        outputs
+    }
+
+    fn backward(&mut self, output_gradient: &[f64], _batch: usize) -> Vec<f64> {
+        self.backward_from(Dense::OUTPUTS, output_gradient)
+    }
+
+    fn parameters(&mut self) -> Vec<&mut Parameter> {
+        vec![&mut self.weights, &mut self.biases]
+    }
+
+    fn kind(&self) -> LayerKind {
+        LayerKind::Dense
+    }
+
+    fn write_config(&self, writer: &mut ByteWriter) {
+        writer.write_u32(self.units as u32);
+        writer.write_u32(self.activation as u32);
+    }
+
+    fn activation(&self) -> Option<ActivationFunction> {
+        Some(self.activation)
+    }
+
+    fn backward_pre_activation(&mut self, deltas: &[f64], _batch: usize) -> Vec<f64> {
+        self.backward_from(Dense::PRE_ACTIVATIONS, deltas)
+    }
+}
+
+/// Feed-forward neural network whose layers, weights and training state live in wasm
+///
+/// Batches are passed as row-major `Float64Array`s with one sample per row, so a whole
+/// training loop runs without copying weights between JavaScript and wasm.
+#[wasm_bindgen]
+pub struct Network {
+    input_shape: Shape,
+    layers: Vec<Box<dyn Layer>>,
+    loss: LossFunction,
+    huber_delta: f64,
+    optimizer: Optimizer,
+    /// Weight initialization for layers added from now on
+    initializer: Initializer,
+    rng: Rng,
+    /// Batch size of the latest forward pass, zero before the first one
+    batch: usize,
+}
+
+#[wasm_bindgen]
+impl Network {
+    /// Create a network without layers
+    ///
+    /// Takes the number of input features, the loss used for training (default mean
+    /// squared error), the base learning rate (default 0.01) and an optional seed for
+    /// weight initialization. Training uses plain SGD until another optimizer is set.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        input_size: usize,
+        loss: Option<LossFunction>,
+        learning_rate: Option<f64>,
+        seed: Option<u32>,
+    ) -> Result<Network, JsValue> {
+        // Validate inputs
+        if input_size == 0 {
+            return Err(JsValue::from_str("Input size must be greater than 0"));
+        }
+
+        let learning_rate = learning_rate.unwrap_or(0.01);
+        check_positive(learning_rate, "Learning rate")?;
+
+        Ok(Network {
+            input_shape: [input_size, 1, 1],
+            layers: Vec::new(),
+            loss: loss.unwrap_or(LossFunction::MeanSquaredError),
+            huber_delta: 1.0,
+            optimizer: Optimizer::new(learning_rate),
+            initializer: Initializer::XavierNormal,
+            rng: Rng::from_seed(seed),
+            batch: 0,
+        })
+    }
+
+    /// Set the weight initializer for layers added afterwards (default Xavier normal)
+    ///
+    /// Weights are drawn from the network's seeded generator, so a network built with the
+    /// same seed and layers starts from the same weights in the browser and natively.
+    pub fn set_initializer(&mut self, initializer: Initializer) {
+        self.initializer = initializer;
+    }
+
+    /// Append a fully connected layer
+    pub fn add_dense(&mut self, units: usize, activation: ActivationFunction) -> Result<(), JsValue> {
+        if units == 0 {
+            return Err(JsValue::from_str("Number of units must be greater than 0"));
+        }
+        let layer = Dense::new(self.output_size(), units, activation, self.initializer, &mut self.rng);
+        self.push(Box::new(layer));
+        Ok(())
+    }
+
+    /// Interpret each input row as channel-first `channels x height x width` values, for
+    /// convolution and pooling layers (use height 1 for sequences)
+    ///
+    /// Must be called before any layer is added; the product must equal the input size.
+    pub fn set_input_shape(&mut self, channels: usize, height: usize, width: usize) -> Result<(), JsValue> {
+        if !self.layers.is_empty() {
+            return Err(JsValue::from_str("Input shape must be set before adding layers"));
+        }
+        if channels.checked_mul(height).and_then(|size| size.checked_mul(width)) != Some(self.input_size()) {
+            return Err(JsValue::from_str("Input shape must multiply out to the input size"));
+        }
+        self.input_shape = [channels, height, width];
+        Ok(())
+    }
+
+    /// Append a 1D convolution over (channels, length) inputs (defaults: stride 1, no
+    /// padding, dilation 1)
+    pub fn add_conv1d(
+        &mut self,
+        filters: usize,
+        kernel_size: usize,
+        activation: ActivationFunction,
+        stride: Option<usize>,
+        padding: Option<usize>,
+        dilation: Option<usize>,
+    ) -> Result<(), JsValue> {
+        if self.current_shape()[1] != 1 {
+            return Err(JsValue::from_str("1D convolution expects inputs of height 1"));
+        }
+        self.add_conv(filters, [1, kernel_size], activation, [1, stride.unwrap_or(1)], [0, padding.unwrap_or(0)], [1, dilation.unwrap_or(1)])
+    }
+
+    /// Append a 2D convolution over (channels, height, width) inputs; stride, padding and
+    /// dilation apply to both axes (defaults: stride 1, no padding, dilation 1)
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_conv2d(
+        &mut self,
+        filters: usize,
+        kernel_height: usize,
+        kernel_width: usize,
+        activation: ActivationFunction,
+        stride: Option<usize>,
+        padding: Option<usize>,
+        dilation: Option<usize>,
+    ) -> Result<(), JsValue> {
+        let (stride, padding, dilation) = (stride.unwrap_or(1), padding.unwrap_or(0), dilation.unwrap_or(1));
+        self.add_conv(filters, [kernel_height, kernel_width], activation, [stride; 2], [padding; 2], [dilation; 2])
+    }
+
+    /// Append 1D pooling over (channels, length) inputs (default stride: the window size)
+    pub fn add_pool1d(&mut self, kind: PoolingType, size: usize, stride: Option<usize>) -> Result<(), JsValue> {
+        if self.current_shape()[1] != 1 {
+            return Err(JsValue::from_str("1D pooling expects inputs of height 1"));
+        }
+        self.add_pool(kind, [1, size], [1, stride.unwrap_or(size)])
+    }
+
+    /// Append 2D pooling with a square window (default stride: the window size)
+    pub fn add_pool2d(&mut self, kind: PoolingType, size: usize, stride: Option<usize>) -> Result<(), JsValue> {
+        let stride = stride.unwrap_or(size);
+        self.add_pool(kind, [size; 2], [stride; 2])
+    }
+
+    /// Append batch normalization per channel (defaults: momentum 0.1, epsilon 1e-5)
+    ///
+    /// `momentum` is the weight of each training batch in the running mean and variance
+    /// used for inference.
+    pub fn add_batch_norm(&mut self, momentum: Option<f64>, epsilon: Option<f64>) -> Result<(), JsValue> {
+        let momentum = momentum.unwrap_or(0.1);
+        if !(momentum > 0.0 && momentum <= 1.0) {
+            return Err(JsValue::from_str("Momentum must be in (0, 1]"));
+        }
+        let epsilon = epsilon.unwrap_or(1e-5);
+        check_positive(epsilon, "Epsilon")?;
+
+        let layer = BatchNorm::new(self.current_shape(), momentum, epsilon);
+        self.push(Box::new(layer));
+        Ok(())
+    }
+
+    /// Append layer normalization over each sample (default epsilon 1e-5)
+    pub fn add_layer_norm(&mut self, epsilon: Option<f64>) -> Result<(), JsValue> {
+        let epsilon = epsilon.unwrap_or(1e-5);
+        check_positive(epsilon, "Epsilon")?;
+
+        let layer = LayerNorm::new(self.current_shape(), epsilon);
+        self.push(Box::new(layer));
+        Ok(())
+    }
+
+    /// Append dropout that zeroes a fraction `rate` of values while training
+    pub fn add_dropout(&mut self, rate: f64) -> Result<(), JsValue> {
+        check_fraction(rate, "Dropout rate")?;
+
+        let layer = Dropout::new(self.current_shape(), rate, Rng::new(self.rng.next_u64()));
+        self.push(Box::new(layer));
+        Ok(())
+    }
+
+    /// Append an activation on its own, e.g. after pooling or normalization
+    ///
+    /// Row-wise activations such as softmax act over all values of each sample.
+    pub fn add_activation(&mut self, activation: ActivationFunction) {
+        let layer = Activation::new(self.current_shape(), activation);
+        self.push(Box::new(layer));
+    }
+
+    /// Append a recurrent layer over `(features, 1, steps)` sequences
+    ///
+    /// Takes the cell type, the number of units, whether to output the hidden state of
+    /// every step (default: only the last), whether to carry the final state into the next
+    /// batch (default false; see `reset_states`), and the truncation length for
+    /// backpropagation through time (default 0 for full BPTT).
+    pub fn add_recurrent(
+        &mut self,
+        cell: RecurrentCell,
+        units: usize,
+        return_sequences: Option<bool>,
+        stateful: Option<bool>,
+        truncate_steps: Option<usize>,
+    ) -> Result<(), JsValue> {
+        if units == 0 {
+            return Err(JsValue::from_str("Number of units must be greater than 0"));
+        }
+        let shape = self.current_shape();
+        if shape[1] != 1 {
+            return Err(JsValue::from_str("Recurrent layers expect (features, 1, steps) inputs"));
+        }
+
+        let layer = Recurrent::new(
+            cell,
+            shape,
+            units,
+            return_sequences.unwrap_or(false),
+            stateful.unwrap_or(false),
+            truncate_steps.unwrap_or(0),
+            self.initializer,
+            &mut self.rng,
+        );
+        self.push(Box::new(layer));
+        Ok(())
+    }
+
+    /// Reset the carried state of every stateful recurrent layer, e.g. between independent
+    /// sequences
+    pub fn reset_states(&mut self) {
+        self.layers.iter_mut().for_each(|layer| layer.reset_state());
+    }
+
+    /// Outputs for a batch of inputs, one row per sample
+    ///
+    /// Runs in inference mode unless `training` is set, which makes dropout and batch
+    /// normalization behave as they do in `train_batch`.
+    pub fn forward(&mut self, inputs: &JsValue, training: Option<bool>) -> Result<Float64Array, JsValue> {
+        let (inputs, rows) = read_rows(inputs, self.input_size())?;
+        let outputs = self.forward_rows(&inputs, rows, training.unwrap_or(false))?;
+        Ok(Float64Array::from(&outputs[..]))
+    }
+
+    /// Backpropagate a loss gradient through every layer
+    ///
+    /// Takes the gradient of the loss with respect to the outputs of the latest `forward`
+    /// call, stores the gradient of every weight and bias for `apply_gradients`, and
+    /// returns the gradient with respect to the inputs.
+    pub fn backward(&mut self, output_gradient: &JsValue) -> Result<Float64Array, JsValue> {
+        if self.batch == 0 {
+            return Err(JsValue::from_str("Call forward before backward"));
+        }
+
+        // Convert input to typed array for better performance
+        let output_gradient = Float64Array::new(output_gradient).to_vec();
+        if output_gradient.len() != self.batch * self.output_size() {
+            return Err(JsValue::from_str("Output gradient must match the shape of the latest forward pass"));
+        }
+
+        let input_gradient = self.backward_rows(output_gradient, false);
+        Ok(Float64Array::from(&input_gradient[..]))
+    }
+
+    /// Take one optimizer step with the gradients from the latest `backward` call
+    pub fn apply_gradients(&mut self) {
+        self.step();
+    }
+
+    /// Train on one batch: forward pass, loss, backward pass and weight update
+    ///
+    /// Takes row-major inputs and targets with one row per sample, and returns the loss of
+    /// the batch before the update.
+    pub fn train_batch(&mut self, inputs: &JsValue, targets: &JsValue) -> Result<f64, JsValue> {
+        let (inputs, rows) = read_rows(inputs, self.input_size())?;
+        let targets = self.read_targets(targets, rows)?;
+
+        Ok(self.train_rows(&inputs, &targets, rows)?)
+    }
+
+    /// Loss on a batch without updating the weights
+    pub fn evaluate(&mut self, inputs: &JsValue, targets: &JsValue) -> Result<f64, JsValue> {
+        let (inputs, rows) = read_rows(inputs, self.input_size())?;
+        let targets = self.read_targets(targets, rows)?;
+
+        let predictions = self.forward_rows(&inputs, rows, false)?;
+        Ok(self.loss_gradient(&predictions, &targets).0)
+    }
+
+    /// Change the training loss (Huber delta default 1)
+    pub fn set_loss(&mut self, loss: LossFunction, huber_delta: Option<f64>) -> Result<(), JsValue> {
+        let huber_delta = huber_delta.unwrap_or(1.0);
+        check_positive(huber_delta, "Huber delta")?;
+        self.loss = loss;
+        self.huber_delta = huber_delta;
+        Ok(())
+    }
+
+    /// Weights of a layer: `outputs x inputs` for dense layers, `filters x channels x
+    /// kernel_height x kernel_width` for convolutions, input weights for recurrent layers
+    /// and the scale for normalization layers (all row-major)
+    pub fn get_weights(&mut self, layer: usize) -> Result<Float64Array, JsValue> {
+        let parameters = self.layer_parameters(layer)?;
+        Ok(Float64Array::from(&parameters[0].values[..]))
+    }
+
+    /// Biases of a layer (the shift for normalization layers)
+    pub fn get_biases(&mut self, layer: usize) -> Result<Float64Array, JsValue> {
+        let parameters = self.layer_parameters(layer)?;
+        Ok(Float64Array::from(&parameters[parameters.len() - 1].values[..]))
+    }
+
+    /// Replace the weights and biases of a layer
+    pub fn set_parameters(&mut self, layer: usize, weights: &JsValue, biases: &JsValue) -> Result<(), JsValue> {
+        // Convert inputs to typed arrays for better performance
+        let weights = Float64Array::new(weights).to_vec();
+        let biases = Float64Array::new(biases).to_vec();
+
+        let mut parameters = self.layer_parameters(layer)?;
+        let last = parameters.len() - 1;
+        if weights.len() != parameters[0].values.len() || biases.len() != parameters[last].values.len() {
+            return Err(JsValue::from_str("Weights or biases have the wrong length for this layer"));
+        }
+
+        parameters[0].values = weights;
+        parameters[last].values = biases;
+        Ok(())
+    }
+
+    /// Number of parameter arrays of a layer: 2 for most layers, 3 for recurrent layers
+    /// (input weights, recurrent weights, biases), 0 for pooling and dropout
+    pub fn num_layer_parameters(&mut self, layer: usize) -> Result<usize, JsValue> {
+        let layer = self.layers.get_mut(layer).ok_or_else(|| JsValue::from_str("Layer index out of range"))?;
+        Ok(layer.parameters().len())
+    }
+
+    /// One parameter array of a layer, in the order described by `num_layer_parameters`
+    pub fn get_parameter(&mut self, layer: usize, index: usize) -> Result<Float64Array, JsValue> {
+        let parameters = self.layer_parameters(layer)?;
+        let parameter = parameters.get(index).ok_or_else(|| JsValue::from_str("Parameter index out of range"))?;
+        Ok(Float64Array::from(&parameter.values[..]))
+    }
+
+    /// Replace one parameter array of a layer
+    pub fn set_parameter(&mut self, layer: usize, index: usize, values: &JsValue) -> Result<(), JsValue> {
+        // Convert input to typed array for better performance
+        let values = Float64Array::new(values).to_vec();
+
+        let mut parameters = self.layer_parameters(layer)?;
+        let parameter = parameters.get_mut(index).ok_or_else(|| JsValue::from_str("Parameter index out of range"))?;
+        if values.len() != parameter.values.len() {
+            return Err(JsValue::from_str("Values have the wrong length for this parameter"));
+        }
+        parameter.values = values;
+        Ok(())
+    }
+
+    /// Number of layers
+    #[wasm_bindgen(getter)]
+    pub fn num_layers(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Number of input features
+    #[wasm_bindgen(getter)]
+    pub fn input_size(&self) -> usize {
+        self.input_shape.iter().product()
+    }
+
+    /// Number of outputs of the last layer (the input size when there are no layers)
+    #[wasm_bindgen(getter)]
+    pub fn output_size(&self) -> usize {
+        self.layers.last().map_or(self.input_size(), |layer| layer.output_size())
+    }
+
+    /// Shape of each output sample as `[channels, height, width]`
+    #[wasm_bindgen(getter)]
+    pub fn output_shape(&self) -> Uint32Array {
+        let shape: Vec<u32> = self.current_shape().iter().map(|&d| d as u32).collect();
+        Uint32Array::from(&shape[..])
+    }
+
+    /// Total number of trainable values
+    #[wasm_bindgen(getter)]
+    pub fn num_parameters(&mut self) -> usize {
+        self.layers.iter_mut().flat_map(|layer| layer.parameters()).map(|p| p.values.len()).sum()
+    }
+
+    /// Base learning rate, before warmup and the schedule
+    #[wasm_bindgen(getter)]
+    pub fn learning_rate(&self) -> f64 {
+        self.optimizer.learning_rate
+    }
+
+    /// Learning rate the next optimizer step will use
+    #[wasm_bindgen(getter)]
+    pub fn current_learning_rate(&self) -> f64 {
+        self.optimizer.current_learning_rate()
+    }
+
+    /// Number of optimizer steps taken since the optimizer was last set or layers added
+    #[wasm_bindgen(getter)]
+    pub fn steps(&self) -> f64 {
+        self.optimizer.steps as f64
+    }
+
+    /// Change the base learning rate between training steps
+    pub fn set_learning_rate(&mut self, learning_rate: f64) -> Result<(), JsValue> {
+        check_positive(learning_rate, "Learning rate")?;
+        self.optimizer.learning_rate = learning_rate;
+        Ok(())
+    }
+
+    /// Train with stochastic gradient descent (defaults: no momentum, no Nesterov)
+    pub fn set_sgd(&mut self, learning_rate: Option<f64>, momentum: Option<f64>, nesterov: Option<bool>) -> Result<(), JsValue> {
+        let momentum = momentum.unwrap_or(0.0);
+        check_fraction(momentum, "Momentum")?;
+        let nesterov = nesterov.unwrap_or(false);
+        if nesterov && momentum == 0.0 {
+            return Err(JsValue::from_str("Nesterov momentum requires a momentum greater than 0"));
+        }
+        self.set_rule(learning_rate, UpdateRule::Sgd { momentum, nesterov })
+    }
+
+    /// Train with RMSProp (defaults: decay 0.9, epsilon 1e-8)
+    pub fn set_rmsprop(&mut self, learning_rate: Option<f64>, decay: Option<f64>, epsilon: Option<f64>) -> Result<(), JsValue> {
+        let decay = decay.unwrap_or(0.9);
+        check_fraction(decay, "Decay")?;
+        let epsilon = epsilon.unwrap_or(1e-8);
+        check_positive(epsilon, "Epsilon")?;
+        self.set_rule(learning_rate, UpdateRule::RmsProp { decay, epsilon })
+    }
+
+    /// Train with Adam (defaults: beta1 0.9, beta2 0.999, epsilon 1e-8)
+    pub fn set_adam(&mut self, learning_rate: Option<f64>, beta1: Option<f64>, beta2: Option<f64>, epsilon: Option<f64>) -> Result<(), JsValue> {
+        self.set_adamw(learning_rate, beta1, beta2, epsilon, Some(0.0))
+    }
+
+    /// Train with AdamW, Adam with decoupled weight decay (defaults as `set_adam`, weight
+    /// decay 0.01)
+    pub fn set_adamw(
+        &mut self,
+        learning_rate: Option<f64>,
+        beta1: Option<f64>,
+        beta2: Option<f64>,
+        epsilon: Option<f64>,
+        weight_decay: Option<f64>,
+    ) -> Result<(), JsValue> {
+        let (beta1, beta2) = (beta1.unwrap_or(0.9), beta2.unwrap_or(0.999));
+        check_fraction(beta1, "Beta1")?;
+        check_fraction(beta2, "Beta2")?;
+        let epsilon = epsilon.unwrap_or(1e-8);
+        check_positive(epsilon, "Epsilon")?;
+        let weight_decay = weight_decay.unwrap_or(0.01);
+        check_non_negative(weight_decay, "Weight decay")?;
+        self.set_rule(learning_rate, UpdateRule::Adam { beta1, beta2, epsilon, weight_decay })
+    }
+
+    /// Train with Adagrad (default epsilon 1e-10)
+    pub fn set_adagrad(&mut self, learning_rate: Option<f64>, epsilon: Option<f64>) -> Result<(), JsValue> {
+        let epsilon = epsilon.unwrap_or(1e-10);
+        check_positive(epsilon, "Epsilon")?;
+        self.set_rule(learning_rate, UpdateRule::Adagrad { epsilon })
+    }
+
+    /// L2 regularization strength added to every gradient (0 disables it)
+    pub fn set_weight_decay(&mut self, l2: f64) -> Result<(), JsValue> {
+        check_non_negative(l2, "Weight decay")?;
+        self.optimizer.l2 = l2;
+        Ok(())
+    }
+
+    /// Gradient clipping by global norm and/or by value; pass neither to disable it
+    pub fn set_gradient_clipping(&mut self, max_norm: Option<f64>, max_value: Option<f64>) -> Result<(), JsValue> {
+        if let Some(max_norm) = max_norm {
+            check_positive(max_norm, "Maximum gradient norm")?;
+        }
+        if let Some(max_value) = max_value {
+            check_positive(max_value, "Maximum gradient value")?;
+        }
+        self.optimizer.clip_norm = max_norm;
+        self.optimizer.clip_value = max_value;
+        Ok(())
+    }
+
+    /// Keep the learning rate constant after warmup
+    pub fn set_constant_schedule(&mut self) {
+        self.optimizer.schedule = Schedule::Constant;
+    }
+
+    /// Multiply the learning rate by `gamma` every `step_size` optimizer steps
+    pub fn set_step_schedule(&mut self, step_size: u32, gamma: f64) -> Result<(), JsValue> {
+        if step_size == 0 {
+            return Err(JsValue::from_str("Step size must be greater than 0"));
+        }
+        check_positive(gamma, "Gamma")?;
+        self.optimizer.schedule = Schedule::Step { step_size: step_size as u64, gamma };
+        Ok(())
+    }
+
+    /// Multiply the learning rate by `gamma` every optimizer step
+    pub fn set_exponential_schedule(&mut self, gamma: f64) -> Result<(), JsValue> {
+        check_positive(gamma, "Gamma")?;
+        self.optimizer.schedule = Schedule::Exponential { gamma };
+        Ok(())
+    }
+
+    /// Cosine-anneal the learning rate to `min_learning_rate` (default 0) over `period`
+    /// optimizer steps
+    pub fn set_cosine_schedule(&mut self, period: u32, min_learning_rate: Option<f64>) -> Result<(), JsValue> {
+        if period == 0 {
+            return Err(JsValue::from_str("Period must be greater than 0"));
+        }
+        let min_learning_rate = min_learning_rate.unwrap_or(0.0);
+        check_non_negative(min_learning_rate, "Minimum learning rate")?;
+        self.optimizer.schedule = Schedule::Cosine { period: period as u64, min_learning_rate };
+        Ok(())
+    }
+
+    /// Ramp the learning rate linearly from zero over the first `steps` optimizer steps;
+    /// the schedule starts counting once warmup ends
+    pub fn set_warmup(&mut self, steps: u32) {
+        self.optimizer.warmup_steps = steps as u64;
+    }
+
+    /// Serialize the architecture, loss, base learning rate and weights to bytes
+    ///
+    /// Batch normalization running statistics are included; optimizer state, schedules and
+    /// recurrent state are not.
+    pub fn to_bytes(&mut self) -> Vec<u8> {
+        let mut writer = ByteWriter::new(MAGIC, VERSION);
+        self.input_shape.iter().for_each(|&d| writer.write_u32(d as u32));
+        writer.write_u32(self.loss as u32);
+        writer.write_f64(self.huber_delta);
+        writer.write_f64(self.optimizer.learning_rate);
+
+        writer.write_u32(self.layers.len() as u32);
+        for layer in &mut self.layers {
+            writer.write_u32(layer.kind() as u32);
+            layer.write_config(&mut writer);
+            write_values(&mut writer, layer.parameters().into_iter().map(|p| &p.values).collect());
+            write_values(&mut writer, layer.state().into_iter().map(|values| &*values).collect());
+        }
+        writer.into_bytes()
+    }
+
+    /// Restore a network produced by `to_bytes`, with an optional seed for dropout
+    ///
+    /// Training can continue afterwards, starting from plain SGD at the saved learning rate.
+    pub fn from_bytes(bytes: &[u8], seed: Option<u32>) -> Result<Network, JsValue> {
+        Network::decode(bytes, seed).map_err(|message| JsValue::from_str(&message))
+    }
+}
+
+impl Network {
+    /// Append a layer whose input size matches the current output size
+    pub(crate) fn push(&mut self, layer: Box<dyn Layer>) {
+        debug_assert_eq!(layer.input_size(), self.output_size());
+        self.layers.push(layer);
+        self.optimizer.reset();
+        self.batch = 0;
+    }
+
+    /// Forward pass over a row-major batch
+    pub(crate) fn forward_rows(&mut self, inputs: &[f64], rows: usize, training: bool) -> Result<Vec<f64>, String> {
+        if self.layers.is_empty() {
+            return Err("Network has no layers".to_string());
+        }
+        if rows == 0 {
+            return Err("Inputs must contain at least one sample".to_string());
+        }
+
+        let mut outputs = inputs.to_vec();
+        for layer in &mut self.layers {
+            outputs = layer.forward(&outputs, rows, training);
+        }
+        self.batch = rows;
+        Ok(outputs)
+    }
+
+    /// Backward pass from the loss gradient of the latest forward pass
+    ///
+    /// When `fused` is set the gradient is with respect to the last layer's outputs before
+    /// its activation.
+    pub(crate) fn backward_rows(&mut self, output_gradient: Vec<f64>, fused: bool) -> Vec<f64> {
+        let last = self.layers.len() - 1;
+        let mut gradient = output_gradient;
+        for (index, layer) in self.layers.iter_mut().enumerate().rev() {
+            gradient = if fused && index == last {
+                layer.backward_pre_activation(&gradient, self.batch)
+            } else {
+                layer.backward(&gradient, self.batch)
+            };
+        }
+        gradient
+    }
+
+    /// Loss of the latest forward pass and its gradient, fused with the output activation
+    /// when that is more stable; the flag reports whether fusion happened
+    pub(crate) fn loss_gradient(&self, predictions: &[f64], targets: &[f64]) -> (f64, Vec<f64>, bool) {
+        let width = self.output_size();
+        let fused = self
+            .layers
+            .last()
+            .and_then(|layer| layer.activation())
+            .and_then(|activation| self.loss.evaluate_fused(activation, predictions, targets, width));
+
+        match fused {
+            Some((loss, gradient)) => (loss, gradient, true),
+            None => {
+                let (loss, gradient) = self.loss.evaluate(predictions, targets, width, self.huber_delta);
+                (loss, gradient, false)
+            }
+        }
+    }
+
+    /// Optimizer update of every parameter from the stored gradients
+    pub(crate) fn step(&mut self) {
+        let mut parameters: Vec<&mut Parameter> = self.layers.iter_mut().flat_map(|layer| layer.parameters()).collect();
+        self.optimizer.update(&mut parameters);
+    }
+
+    /// One training step on a batch, returning the loss before the update
+    pub(crate) fn train_rows(&mut self, inputs: &[f64], targets: &[f64], rows: usize) -> Result<f64, String> {
+        let predictions = self.forward_rows(inputs, rows, true)?;
+        let (loss, gradient, fused) = self.loss_gradient(&predictions, targets);
+        self.backward_rows(gradient, fused);
+        self.step();
+        Ok(loss)
+    }
+
+    pub(crate) fn loss(&self) -> LossFunction {
+        self.loss
+    }
+
+    /// Seeded generator of the network, for shuffling and initialization
+    pub(crate) fn rng(&mut self) -> &mut Rng {
+        &mut self.rng
+    }
+
+    /// Layers in order, for code that converts a trained network
+    pub(crate) fn layers_mut(&mut self) -> &mut [Box<dyn Layer>] {
+        &mut self.layers
+    }
+
+    /// Visit every parameter and then every state buffer of each layer, in `to_bytes` order
+    pub(crate) fn for_each_buffer(&mut self, mut visit: impl FnMut(&mut Vec<f64>) -> Result<(), String>) -> Result<(), String> {
+        for layer in &mut self.layers {
+            for parameter in layer.parameters() {
+                visit(&mut parameter.values)?;
+            }
+            for values in layer.state() {
+                visit(values)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Shape of the samples the next layer receives
+    pub(crate) fn current_shape(&self) -> Shape {
+        self.layers.last().map_or(self.input_shape, |layer| layer.output_shape())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn add_conv(
+        &mut self,
+        filters: usize,
+        kernel: [usize; 2],
+        activation: ActivationFunction,
+        stride: [usize; 2],
+        padding: [usize; 2],
+        dilation: [usize; 2],
+    ) -> Result<(), JsValue> {
+        if filters == 0 || kernel.contains(&0) || stride.contains(&0) || dilation.contains(&0) {
+            return Err(JsValue::from_str("Filters, kernel size, stride and dilation must be greater than 0"));
+        }
+        let layer = Conv2D::new(self.current_shape(), filters, kernel, stride, padding, dilation, activation, self.initializer, &mut self.rng)?;
+        self.push(Box::new(layer));
+        Ok(())
+    }
+
+    pub(crate) fn add_pool(&mut self, kind: PoolingType, size: [usize; 2], stride: [usize; 2]) -> Result<(), JsValue> {
+        if size.contains(&0) || stride.contains(&0) {
+            return Err(JsValue::from_str("Pooling size and stride must be greater than 0"));
+        }
+        let layer = Pool2D::new(kind, self.current_shape(), size, stride)?;
+        self.push(Box::new(layer));
+        Ok(())
+    }
+
+    fn decode(bytes: &[u8], seed: Option<u32>) -> Result<Network, String> {
+        let (mut reader, version) = ByteReader::new(bytes, MAGIC)?;
+        if version != VERSION {
+            return Err(format!("Unsupported network format version {}", version));
+        }
+
+        let mut input_shape = [0; 3];
+        for d in input_shape.iter_mut() {
+            *d = reader.read_u32()? as usize;
+        }
+        let loss = LossFunction::from_index(reader.read_u32()?).ok_or("Unknown loss function in network payload")?;
+        let huber_delta = reader.read_f64()?;
+        let learning_rate = reader.read_f64()?;
+        let valid_rates = huber_delta > 0.0 && learning_rate > 0.0 && learning_rate.is_finite();
+        if input_shape.contains(&0) || checked_size(input_shape).is_none() || !valid_rates {
+            return Err("Invalid network payload".to_string());
+        }
+
+        let mut network = Network::new(1, Some(loss), Some(learning_rate), seed).map_err(|_| "Invalid network payload".to_string())?;
+        network.input_shape = input_shape;
+        network.huber_delta = huber_delta;
+
+        let num_layers = reader.read_u32()? as usize;
+        for _ in 0..num_layers {
+            let mut layer = network.read_layer(&mut reader)?;
+            read_values(&mut reader, layer.parameters().into_iter().map(|p| &mut p.values).collect())?;
+            read_values(&mut reader, layer.state())?;
+            network.push(layer);
+        }
+        reader.finish()?;
+        Ok(network)
+    }
+
+    /// Rebuild a layer from the configuration written by `Layer::write_config`
+    fn read_layer(&mut self, reader: &mut ByteReader) -> Result<Box<dyn Layer>, String> {
+        let shape = self.current_shape();
+        let size: usize = shape.iter().product();
+        let invalid = || "Invalid layer configuration in network payload".to_string();
+        let read_pair = |reader: &mut ByteReader| -> Result<[usize; 2], String> { Ok([reader.read_u32()? as usize, reader.read_u32()? as usize]) };
+        let read_activation = |reader: &mut ByteReader| ActivationFunction::from_index(reader.read_u32()?).ok_or_else(invalid);
+        // Values follow the configuration, so a layer whose parameters and state cannot fit
+        // in the rest of the payload is rejected before anything is allocated
+        let check_values = |reader: &ByteReader, count: Option<usize>| match count.and_then(|count| count.checked_mul(8)) {
+            Some(bytes) if bytes <= reader.remaining() => Ok(()),
+            _ => Err("Inconsistent network payload".to_string()),
+        };
+
+        let layer: Box<dyn Layer> = match reader.read_u32()? {
+            kind if kind == LayerKind::Dense as u32 => {
+                let units = reader.read_u32()? as usize;
+                let activation = read_activation(reader)?;
+                if units == 0 {
+                    return Err(invalid());
+                }
+                check_values(reader, size.checked_mul(units).and_then(|weights| weights.checked_add(units)))?;
+                Box::new(Dense::new(size, units, activation, self.initializer, &mut self.rng))
+            }
+            kind if kind == LayerKind::Conv2D as u32 => {
+                let filters = reader.read_u32()? as usize;
+                let (kernel, stride, padding, dilation) = (read_pair(reader)?, read_pair(reader)?, read_pair(reader)?, read_pair(reader)?);
+                let activation = read_activation(reader)?;
+                if filters == 0 || kernel.contains(&0) || stride.contains(&0) || dilation.contains(&0) {
+                    return Err(invalid());
+                }
+                let patch_len = shape[0].checked_mul(kernel[0]).and_then(|len| len.checked_mul(kernel[1]));
+                check_values(reader, patch_len.and_then(|len| len.checked_mul(filters)).and_then(|weights| weights.checked_add(filters)))?;
+                Box::new(Conv2D::new(shape, filters, kernel, stride, padding, dilation, activation, self.initializer, &mut self.rng)?)
+            }
+            kind if kind == LayerKind::Pool2D as u32 => {
+                let pooling = PoolingType::from_index(reader.read_u32()?).ok_or_else(invalid)?;
+                let (pool_size, stride) = (read_pair(reader)?, read_pair(reader)?);
+                if pool_size.contains(&0) || stride.contains(&0) {
+                    return Err(invalid());
+                }
+                Box::new(Pool2D::new(pooling, shape, pool_size, stride)?)
+            }
+            kind if kind == LayerKind::BatchNorm as u32 => {
+                let (momentum, epsilon) = (reader.read_f64()?, reader.read_f64()?);
+                if !(momentum > 0.0 && momentum <= 1.0 && epsilon > 0.0 && epsilon.is_finite()) {
+                    return Err(invalid());
+                }
+                check_values(reader, shape[0].checked_mul(4))?;
+                Box::new(BatchNorm::new(shape, momentum, epsilon))
+            }
+            kind if kind == LayerKind::LayerNorm as u32 => {
+                let epsilon = reader.read_f64()?;
+                if !(epsilon > 0.0 && epsilon.is_finite()) {
+                    return Err(invalid());
+                }
+                check_values(reader, size.checked_mul(2))?;
+                Box::new(LayerNorm::new(shape, epsilon))
+            }
+            kind if kind == LayerKind::Dropout as u32 => {
+                let rate = reader.read_f64()?;
+                if !(0.0..1.0).contains(&rate) {
+                    return Err(invalid());
+                }
+                Box::new(Dropout::new(shape, rate, Rng::new(self.rng.next_u64())))
+            }
+            kind if kind == LayerKind::Recurrent as u32 => {
+                let cell = RecurrentCell::from_index(reader.read_u32()?).ok_or_else(invalid)?;
+                let units = reader.read_u32()? as usize;
+                let (return_sequences, stateful) = (reader.read_u32()? != 0, reader.read_u32()? != 0);
+                let truncate_steps = reader.read_u32()? as usize;
+                if units == 0 || shape[1] != 1 {
+                    return Err(invalid());
+                }
+                // Input weights, recurrent weights and biases for every gate row
+                let rows = cell.gates().checked_mul(units);
+                let columns = shape[0].checked_add(units).and_then(|columns| columns.checked_add(1));
+                check_values(reader, rows.zip(columns).and_then(|(rows, columns)| rows.checked_mul(columns)))?;
+                Box::new(Recurrent::new(cell, shape, units, return_sequences, stateful, truncate_steps, self.initializer, &mut self.rng))
+            }
+            kind if kind == LayerKind::Activation as u32 => Box::new(Activation::new(shape, read_activation(reader)?)),
+            other => return Err(format!("Unknown layer kind {} in network payload", other)),
+        };
+        if checked_size(layer.output_shape()).is_none() {
+            return Err(invalid());
+        }
+        Ok(layer)
+    }
+
+    fn set_rule(&mut self, learning_rate: Option<f64>, rule: UpdateRule) -> Result<(), JsValue> {
+        if let Some(learning_rate) = learning_rate {
+            check_positive(learning_rate, "Learning rate")?;
+            self.optimizer.learning_rate = learning_rate;
+        }
+        self.optimizer.set_rule(rule);
+        Ok(())
+    }
+
+    fn read_targets(&self, targets: &JsValue, rows: usize) -> Result<Vec<f64>, JsValue> {
+        let (targets, target_rows) = read_rows(targets, self.output_size())?;
+        if target_rows != rows {
+            return Err(JsValue::from_str("Inputs and targets must have the same number of samples"));
+        }
+        Ok(targets)
+    }
+
+    fn layer_parameters(&mut self, layer: usize) -> Result<Vec<&mut Parameter>, JsValue> {
+        let parameters = self
+            .layers
+            .get_mut(layer)
+            .ok_or_else(|| JsValue::from_str("Layer index out of range"))?
+            .parameters();
+        if parameters.len() < 2 {
+            return Err(JsValue::from_str("Layer has no weights"));
+        }
+        Ok(parameters)
+    }
+}
+
+/// Write a count followed by each value buffer
+fn write_values(writer: &mut ByteWriter, buffers: Vec<&Vec<f64>>) {
+    writer.write_u32(buffers.len() as u32);
+    buffers.iter().for_each(|values| writer.write_f64_slice(values));
+}
+
+/// Number of values in a shape, or None if it overflows
+fn checked_size(shape: Shape) -> Option<usize> {
+    shape.iter().try_fold(1usize, |size, &d| size.checked_mul(d))
+}
+
+/// Read buffers written by `write_values` into buffers of the same count and lengths
+fn read_values(reader: &mut ByteReader, buffers: Vec<&mut Vec<f64>>) -> Result<(), String> {
+    if reader.read_u32()? as usize != buffers.len() {
+        return Err("Inconsistent network payload".to_string());
+    }
+    for buffer in buffers {
+        let values = reader.read_f64_vec()?;
+        if values.len() != buffer.len() {
+            return Err("Inconsistent network payload".to_string());
+        }
+        *buffer = values;
+    }
+    Ok(())
+}
+
+pub(crate) fn check_positive(value: f64, name: &str) -> Result<(), JsValue> {
+    if !(value > 0.0 && value.is_finite()) {
+        return Err(JsValue::from_str(&format!("{} must be a positive finite number", name)));
+    }
+    Ok(())
+}
+
+fn check_non_negative(value: f64, name: &str) -> Result<(), JsValue> {
+    if !(value >= 0.0 && value.is_finite()) {
+        return Err(JsValue::from_str(&format!("{} must be a non-negative finite number", name)));
+    }
+    Ok(())
+}
+
+pub(crate) fn check_fraction(value: f64, name: &str) -> Result<(), JsValue> {
+    if !(0.0..1.0).contains(&value) {
+        return Err(JsValue::from_str(&format!("{} must be in [0, 1)", name)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn batch(rows: usize, width: usize, seed: u64) -> Vec<f64> {
+        let mut rng = Rng::new(seed);
+        (0..rows * width).map(|_| rng.next_normal()).collect()
+    }
+
+    fn batch_loss(network: &mut Network, inputs: &[f64], targets: &[f64], rows: usize) -> f64 {
+        let predictions = network.forward_rows(inputs, rows, true).unwrap();
+        network.loss_gradient(&predictions, targets).0
+    }
+
+    /// Compare every parameter gradient of one backward pass with central differences
+    fn assert_gradients_match(network: &mut Network, inputs: &[f64], targets: &[f64], rows: usize) {
+        let predictions = network.forward_rows(inputs, rows, true).unwrap();
+        let (_, gradient, fused) = network.loss_gradient(&predictions, targets);
+        network.backward_rows(gradient, fused);
+        let analytic: Vec<Vec<Vec<f64>>> = network
+            .layers_mut()
+            .iter_mut()
+            .map(|layer| layer.parameters().iter().map(|p| p.gradients.clone()).collect())
+            .collect();
+
+        let h = 1e-6;
+        for (l, layer_gradients) in analytic.iter().enumerate() {
+            for (p, gradients) in layer_gradients.iter().enumerate() {
+                for (i, &expected) in gradients.iter().enumerate() {
+                    network.layers_mut()[l].parameters()[p].values[i] += h;
+                    let above = batch_loss(network, inputs, targets, rows);
+                    network.layers_mut()[l].parameters()[p].values[i] -= 2.0 * h;
+                    let below = batch_loss(network, inputs, targets, rows);
+                    network.layers_mut()[l].parameters()[p].values[i] += h;
+
+                    let numeric = (above - below) / (2.0 * h);
+                    let message = format!("layer {} parameter {} [{}]: {} vs {}", l, p, i, numeric, expected);
+                    assert!((numeric - expected).abs() < 1e-6, "{}", message);
+                }
+            }
+        }
+    }
+
+    /// Network touching every layer kind, trained for a few steps so that running statistics
+    /// are non-trivial
+    fn every_layer_network() -> Network {
+        let mut network = Network::new(16, Some(LossFunction::CategoricalCrossEntropy), Some(0.05), Some(3)).unwrap();
+        network.set_input_shape(2, 1, 8).unwrap();
+        network.add_conv1d(3, 3, ActivationFunction::ReLU, None, Some(1), None).unwrap();
+        network.add_batch_norm(None, None).unwrap();
+        network.add_pool1d(PoolingType::Max, 2, None).unwrap();
+        network.add_layer_norm(None).unwrap();
+        network.add_dropout(0.25).unwrap();
+        network.add_recurrent(RecurrentCell::GRU, 4, Some(true), None, None).unwrap();
+        network.add_activation(ActivationFunction::Tanh);
+        network.add_dense(3, ActivationFunction::Softmax).unwrap();
+
+        let inputs = batch(6, 16, 4);
+        let targets: Vec<f64> = (0..6).flat_map(|i| (0..3).map(move |c| if c == i % 3 { 1.0 } else { 0.0 })).collect();
+        for _ in 0..3 {
+            network.train_rows(&inputs, &targets, 6).unwrap();
+        }
+        network
+    }
+
+    #[test]
+    fn dense_gradients_match_finite_differences() {
+        let mut network = Network::new(3, None, Some(0.1), Some(1)).unwrap();
+        network.add_dense(4, ActivationFunction::Tanh).unwrap();
+        network.add_dense(2, ActivationFunction::Linear).unwrap();
+        assert_gradients_match(&mut network, &batch(5, 3, 2), &batch(5, 2, 3), 5);
+
+        // Softmax with cross-entropy takes the fused gradient path
+        let mut network = Network::new(3, Some(LossFunction::CategoricalCrossEntropy), Some(0.1), Some(1)).unwrap();
+        network.add_dense(4, ActivationFunction::Sigmoid).unwrap();
+        network.add_dense(3, ActivationFunction::Softmax).unwrap();
+        let targets = [1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.2, 0.3, 0.5];
+        assert_gradients_match(&mut network, &batch(4, 3, 4), &targets, 4);
+    }
+
+    #[test]
+    fn training_reduces_the_loss() {
+        let inputs = batch(32, 2, 5);
+        let targets: Vec<f64> = inputs.chunks_exact(2).map(|x| x[0] - 2.0 * x[1]).collect();
+
+        let mut network = Network::new(2, None, Some(0.05), Some(6)).unwrap();
+        network.add_dense(8, ActivationFunction::Tanh).unwrap();
+        network.add_dense(1, ActivationFunction::Linear).unwrap();
+        let initial = batch_loss(&mut network, &inputs, &targets, 32);
+        for _ in 0..300 {
+            network.train_rows(&inputs, &targets, 32).unwrap();
+        }
+        assert!(batch_loss(&mut network, &inputs, &targets, 32) < initial / 20.0);
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_weights() {
+        let build = || {
+            let mut network = Network::new(4, None, None, Some(9)).unwrap();
+            network.add_dense(5, ActivationFunction::ReLU).unwrap();
+            network.add_dense(2, ActivationFunction::Linear).unwrap();
+            network.to_bytes()
+        };
+        assert_eq!(build(), build());
+    }
+
+    #[test]
+    fn forward_needs_layers_and_samples() {
+        let mut network = Network::new(2, None, None, Some(1)).unwrap();
+        assert!(network.forward_rows(&[1.0, 2.0], 1, false).is_err());
+        network.add_dense(1, ActivationFunction::Linear).unwrap();
+        assert!(network.forward_rows(&[], 0, false).is_err());
+    }
+
+    #[test]
+    fn round_trip_keeps_every_layer() {
+        let mut original = every_layer_network();
+        let bytes = original.to_bytes();
+        let mut restored = Network::decode(&bytes, Some(3)).unwrap();
+        assert_eq!(restored.num_layers(), original.num_layers());
+        assert_eq!(restored.to_bytes(), bytes);
+
+        let inputs = batch(4, 16, 7);
+        let expected = original.forward_rows(&inputs, 4, false).unwrap();
+        assert_eq!(restored.forward_rows(&inputs, 4, false).unwrap(), expected);
+    }
+
+    #[test]
+    fn truncated_or_padded_payloads_are_rejected() {
+        let bytes = every_layer_network().to_bytes();
+        for end in 0..bytes.len() {
+            assert!(Network::decode(&bytes[..end], Some(1)).is_err(), "payload cut at {} was accepted", end);
+        }
+        let mut padded = bytes;
+        padded.push(0);
+        assert_eq!(Network::decode(&padded, Some(1)).err().unwrap(), "Trailing bytes after payload");
+    }
+
+    #[test]
+    fn invalid_payloads_are_rejected() {
+        let mut network = Network::new(2, None, None, Some(1)).unwrap();
+        network.add_dense(1, ActivationFunction::Linear).unwrap();
+        let bytes = network.to_bytes();
+        assert!(Network::decode(&bytes, Some(1)).is_ok());
+
+        // Header: magic, version, input shape, loss, Huber delta, learning rate, layer count
+        let damaged = |offset: usize, value: &[u8]| {
+            let mut damaged = bytes.clone();
+            damaged[offset..offset + value.len()].copy_from_slice(value);
+            Network::decode(&damaged, Some(1)).err().unwrap()
+        };
+        assert_eq!(damaged(4, &2u32.to_le_bytes()), "Unsupported network format version 2");
+        assert_eq!(damaged(8, &0u32.to_le_bytes()), "Invalid network payload");
+        assert_eq!(damaged(20, &99u32.to_le_bytes()), "Unknown loss function in network payload");
+        assert_eq!(damaged(32, &(-1.0f64).to_le_bytes()), "Invalid network payload");
+        assert_eq!(damaged(32, &f64::INFINITY.to_le_bytes()), "Invalid network payload");
+        assert_eq!(damaged(44, &99u32.to_le_bytes()), "Unknown layer kind 99 in network payload");
+        assert_eq!(damaged(48, &0u32.to_le_bytes()), "Invalid layer configuration in network payload");
+
+        // More layers than the payload holds
+        assert_eq!(damaged(40, &2u32.to_le_bytes()), "Unexpected end of payload");
+    }
+
+    #[test]
+    fn dense_backward_after_inference_matches_training() {
+        let mut rng = Rng::new(2);
+        let mut layer = Dense::new(3, 4, ActivationFunction::Tanh, Initializer::XavierNormal, &mut rng);
+        let (inputs, output_gradient) = (batch(5, 3, 3), batch(5, 4, 4));
+
+        let outputs = layer.forward(&inputs, 5, true);
+        let input_gradient = layer.backward(&output_gradient, 5);
+        let gradients: Vec<Vec<f64>> = layer.parameters().iter().map(|p| p.gradients.clone()).collect();
+
+        // Inference skips the graph; backward rebuilds it from the cached inputs
+        assert_eq!(layer.forward(&inputs, 5, false), outputs);
+        assert_eq!(layer.graph.len(), 0);
+        layer.parameters().iter_mut().for_each(|p| p.gradients.fill(0.0));
+        assert_eq!(layer.backward(&output_gradient, 5), input_gradient);
+        let replayed: Vec<Vec<f64>> = layer.parameters().iter().map(|p| p.gradients.clone()).collect();
+        assert_eq!(replayed, gradients);
+    }
+}
@@ -0,0 +1,1070 @@
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+use js_sys::{Array, Float64Array, Object, Reflect, Uint32Array};
+
+use super::json::{self, Json};
+use super::network::Network;
+use super::network_layers::{PoolingType, Shape};
+use super::neural_network::ActivationFunction;
+use super::protobuf::{self, Field};
+
+/// Tensor read from a weight file, converted to f64 and stored row-major
+struct Tensor {
+    dtype: String,
+    shape: Vec<usize>,
+    data: Vec<f64>,
+}
+
+/// IEEE 754 half-precision bits to f64
+fn half_to_f64(bits: u16) -> f64 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1f) as i32;
+    let fraction = (bits & 0x3ff) as f64;
+    sign * match exponent {
+        0 => fraction * 2f64.powi(-24),
+        31 if fraction == 0.0 => f64::INFINITY,
+        31 => f64::NAN,
+        _ => (1.0 + fraction / 1024.0) * 2f64.powi(exponent - 15),
+    }
+}
+
+/// Decode little-endian values of a safetensors dtype
+fn decode_values(dtype: &str, bytes: &[u8]) -> Result<Vec<f64>, String> {
+    let width = match dtype {
+        "F64" | "I64" | "U64" => 8,
+        "F32" | "I32" | "U32" => 4,
+        "F16" | "BF16" | "I16" | "U16" => 2,
+        "I8" | "U8" | "BOOL" => 1,
+        _ => return Err(format!("Unsupported tensor dtype {}", dtype)),
+    };
+    if !bytes.len().is_multiple_of(width) {
+        return Err(format!("Tensor data is not a whole number of {} values", dtype));
+    }
+
+    let values = bytes.chunks_exact(width).map(|b| {
+        let mut buffer = [0u8; 8];
+        buffer[..width].copy_from_slice(b);
+        let (b2, b4) = ([buffer[0], buffer[1]], [buffer[0], buffer[1], buffer[2], buffer[3]]);
+        match dtype {
+            "F64" => f64::from_le_bytes(buffer),
+            "I64" => i64::from_le_bytes(buffer) as f64,
+            "U64" => u64::from_le_bytes(buffer) as f64,
+            "F32" => f32::from_le_bytes(b4) as f64,
+            "I32" => i32::from_le_bytes(b4) as f64,
+            "U32" => u32::from_le_bytes(b4) as f64,
+            "F16" => half_to_f64(u16::from_le_bytes(b2)),
+            "BF16" => f32::from_bits((u16::from_le_bytes(b2) as u32) << 16) as f64,
+            "I16" => i16::from_le_bytes(b2) as f64,
+            "U16" => u16::from_le_bytes(b2) as f64,
+            "I8" => buffer[0] as i8 as f64,
+            _ => buffer[0] as f64,
+        }
+    });
+    Ok(values.collect())
+}
+
+/// Every tensor of a safetensors file, in header order
+fn parse_safetensors(bytes: &[u8]) -> Result<Vec<(String, Tensor)>, String> {
+    if bytes.len() < 8 {
+        return Err("Not a safetensors file".to_string());
+    }
+    let mut length = [0u8; 8];
+    length.copy_from_slice(&bytes[..8]);
+    let header_end = u64::from_le_bytes(length).saturating_add(8);
+    if header_end > bytes.len() as u64 {
+        return Err("Truncated safetensors header".to_string());
+    }
+    let header_end = header_end as usize;
+    let header = std::str::from_utf8(&bytes[8..header_end]).map_err(|_| "Invalid UTF-8 in safetensors header".to_string())?;
+    let data = &bytes[header_end..];
+
+    let entries = match json::parse(header)? {
+        Json::Object(entries) => entries,
+        _ => return Err("Safetensors header must be a JSON object".to_string()),
+    };
+
+    let mut tensors = Vec::new();
+    for (name, info) in entries {
+        if name == "__metadata__" {
+            continue;
+        }
+        let invalid = || format!("Invalid safetensors entry for {}", name);
+        let dtype = info.get("dtype").and_then(Json::as_str).ok_or_else(invalid)?.to_string();
+        let shape = info
+            .get("shape")
+            .and_then(Json::as_array)
+            .ok_or_else(invalid)?
+            .iter()
+            .map(|d| d.as_f64().filter(|d| *d >= 0.0).map(|d| d as usize).ok_or_else(invalid))
+            .collect::<Result<Vec<usize>, String>>()?;
+        let offsets = info.get("data_offsets").and_then(Json::as_array).ok_or_else(invalid)?;
+        let (begin, end) = match offsets {
+            [Json::Number(begin), Json::Number(end)] => (*begin as usize, *end as usize),
+            _ => return Err(invalid()),
+        };
+        if begin > end || end > data.len() {
+            return Err(format!("Data offsets of {} are out of range", name));
+        }
+
+        let values = decode_values(&dtype, &data[begin..end])?;
+        if values.len() != shape.iter().product::<usize>() {
+            return Err(format!("Data of {} does not match its shape", name));
+        }
+        tensors.push((name, Tensor { dtype, shape, data: values }));
+    }
+    Ok(tensors)
+}
+
+/// Read every tensor of a safetensors file
+///
+/// Returns an object mapping tensor names to `{ dtype, shape, data }`, with the values
+/// converted to a `Float64Array`. PyTorch `Linear` weights are stored as `outputs x inputs`,
+/// the layout `neural_network_forward_f64` expects.
+#[wasm_bindgen]
+pub fn neural_network_read_safetensors_f64(bytes: &[u8]) -> Result<JsValue, JsValue> {
+    let tensors = parse_safetensors(bytes)?;
+
+    // Create result object
+    let result = Object::new();
+    for (name, tensor) in tensors {
+        let shape: Vec<u32> = tensor.shape.iter().map(|&d| d as u32).collect();
+        let entry = Object::new();
+        Reflect::set(&entry, &JsValue::from_str("dtype"), &JsValue::from_str(&tensor.dtype))?;
+        Reflect::set(&entry, &JsValue::from_str("shape"), &Uint32Array::from(&shape[..]))?;
+        Reflect::set(&entry, &JsValue::from_str("data"), &Float64Array::from(&tensor.data[..]))?;
+        Reflect::set(&result, &JsValue::from_str(&name), &entry)?;
+    }
+    Ok(result.into())
+}
+
+/// Layer recovered from an ONNX graph
+enum Imported {
+    Dense { units: usize, activation: ActivationFunction, weights: Vec<f64>, biases: Vec<f64> },
+    Conv {
+        filters: usize,
+        kernel: [usize; 2],
+        stride: [usize; 2],
+        padding: [usize; 2],
+        dilation: [usize; 2],
+        activation: ActivationFunction,
+        weights: Vec<f64>,
+        biases: Vec<f64>,
+    },
+    Pool { kind: PoolingType, size: [usize; 2], stride: [usize; 2] },
+    /// Pooling over the whole height and width
+    GlobalPool(PoolingType),
+    /// Scale, shift, running mean and running variance
+    BatchNorm { momentum: f64, epsilon: f64, values: Vec<Vec<f64>> },
+    Activation(ActivationFunction),
+}
+
+/// ONNX node attribute; only the fields this importer reads
+#[derive(Default)]
+struct Attribute {
+    float: Option<f64>,
+    int: Option<i64>,
+    string: Option<String>,
+    ints: Vec<i64>,
+    tensor: Option<Tensor>,
+}
+
+struct Node {
+    op_type: String,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    attributes: HashMap<String, Attribute>,
+}
+
+impl Node {
+    fn float(&self, name: &str, default: f64) -> f64 {
+        self.attributes.get(name).and_then(|a| a.float).unwrap_or(default)
+    }
+
+    fn int(&self, name: &str, default: i64) -> i64 {
+        self.attributes.get(name).and_then(|a| a.int).unwrap_or(default)
+    }
+
+    fn string(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).and_then(|a| a.string.as_deref())
+    }
+
+    /// Integer list attribute as sizes, or `default` when absent
+    fn sizes(&self, name: &str, default: Vec<usize>) -> Result<Vec<usize>, String> {
+        match self.attributes.get(name) {
+            Some(attribute) => attribute
+                .ints
+                .iter()
+                .map(|&v| usize::try_from(v).map_err(|_| format!("Negative {} in {} node", name, self.op_type)))
+                .collect(),
+            None => Ok(default),
+        }
+    }
+}
+
+/// ONNX TensorProto data types, by their safetensors name
+fn onnx_dtype(data_type: i64) -> Result<&'static str, String> {
+    Ok(match data_type {
+        1 => "F32",
+        2 => "U8",
+        3 => "I8",
+        4 => "U16",
+        5 => "I16",
+        6 => "I32",
+        7 => "I64",
+        9 => "BOOL",
+        10 => "F16",
+        11 => "F64",
+        12 => "U32",
+        13 => "U64",
+        16 => "BF16",
+        other => return Err(format!("Unsupported ONNX tensor data type {}", other)),
+    })
+}
+
+fn parse_tensor(bytes: &[u8]) -> Result<(String, Tensor), String> {
+    let (mut name, mut dims, mut data_type) = (String::new(), Vec::new(), 1);
+    let (mut raw, mut floats, mut ints, mut doubles) = (None, Vec::new(), Vec::new(), Vec::new());
+
+    for (number, field) in protobuf::fields(bytes)? {
+        match number {
+            1 => field.push_i64s(&mut dims)?,
+            2 => data_type = field.as_i64()?,
+            4 => field.push_f32s(&mut floats)?,
+            5 | 7 | 11 => field.push_i64s(&mut ints)?,
+            8 => name = field.as_str()?.to_string(),
+            9 => raw = Some(field.as_bytes()?),
+            10 => field.push_f64s(&mut doubles)?,
+            14 if field.as_i64()? != 0 => return Err(format!("External data of tensor {} is not supported", name)),
+            _ => {}
+        }
+    }
+
+    let dtype = onnx_dtype(data_type)?;
+    let data = if let Some(raw) = raw {
+        decode_values(dtype, raw)?
+    } else if !floats.is_empty() {
+        floats.iter().map(|&v| v as f64).collect()
+    } else if !doubles.is_empty() {
+        doubles
+    } else {
+        // Half-precision values are stored as their bit patterns in int32_data
+        ints.iter()
+            .map(|&v| match dtype {
+                "F16" => half_to_f64(v as u16),
+                "BF16" => f32::from_bits((v as u32) << 16) as f64,
+                "U64" => v as u64 as f64,
+                _ => v as f64,
+            })
+            .collect()
+    };
+
+    let shape = dims
+        .iter()
+        .map(|&d| usize::try_from(d).map_err(|_| format!("Negative dimension in tensor {}", name)))
+        .collect::<Result<Vec<usize>, String>>()?;
+    if data.len() != shape.iter().product::<usize>() {
+        return Err(format!("Data of tensor {} does not match its shape", name));
+    }
+    Ok((name, Tensor { dtype: dtype.to_string(), shape, data }))
+}
+
+fn parse_node(bytes: &[u8]) -> Result<Node, String> {
+    let mut node = Node { op_type: String::new(), inputs: Vec::new(), outputs: Vec::new(), attributes: HashMap::new() };
+
+    for (number, field) in protobuf::fields(bytes)? {
+        match number {
+            1 => node.inputs.push(field.as_str()?.to_string()),
+            2 => node.outputs.push(field.as_str()?.to_string()),
+            4 => node.op_type = field.as_str()?.to_string(),
+            5 => {
+                let (mut name, mut attribute) = (String::new(), Attribute::default());
+                for (number, field) in protobuf::fields(field.as_bytes()?)? {
+                    match number {
+                        1 => name = field.as_str()?.to_string(),
+                        2 => attribute.float = Some(field.as_f32()? as f64),
+                        3 => attribute.int = Some(field.as_i64()?),
+                        4 => attribute.string = Some(field.as_str()?.to_string()),
+                        5 => attribute.tensor = Some(parse_tensor(field.as_bytes()?)?.1),
+                        8 => field.push_i64s(&mut attribute.ints)?,
+                        _ => {}
+                    }
+                }
+                node.attributes.insert(name, attribute);
+            }
+            _ => {}
+        }
+    }
+    Ok(node)
+}
+
+/// Name and dimensions of a graph input or output; symbolic dimensions are None
+fn parse_value_info(bytes: &[u8]) -> Result<(String, Vec<Option<usize>>), String> {
+    let (mut name, mut dims) = (String::new(), Vec::new());
+    for (number, field) in protobuf::fields(bytes)? {
+        match number {
+            1 => name = field.as_str()?.to_string(),
+            2 => {
+                let tensor_type = protobuf::fields(field.as_bytes()?)?.into_iter().find(|(n, _)| *n == 1);
+                let shape = match tensor_type {
+                    Some((_, tensor_type)) => protobuf::fields(tensor_type.as_bytes()?)?.into_iter().find(|(n, _)| *n == 2),
+                    None => None,
+                };
+                if let Some((_, shape)) = shape {
+                    for (_, dim) in protobuf::fields(shape.as_bytes()?)?.into_iter().filter(|(n, _)| *n == 1) {
+                        let value = protobuf::fields(dim.as_bytes()?)?.into_iter().find(|(n, _)| *n == 1);
+                        dims.push(match value {
+                            Some((_, Field::Varint(v))) if v > 0 => Some(v as usize),
+                            _ => None,
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok((name, dims))
+}
+
+/// Map an activation node, checking that its attributes match this crate's definition
+fn onnx_activation(node: &Node) -> Result<Option<ActivationFunction>, String> {
+    let unsupported = |detail: &str| Err(format!("{} with {} is not supported", node.op_type, detail));
+    let activation = match node.op_type.as_str() {
+        "Relu" => ActivationFunction::ReLU,
+        "Sigmoid" => ActivationFunction::Sigmoid,
+        "Tanh" => ActivationFunction::Tanh,
+        "Softmax" => ActivationFunction::Softmax,
+        "LogSoftmax" => ActivationFunction::LogSoftmax,
+        "LeakyRelu" if (node.float("alpha", 0.01) - 0.01).abs() > 1e-6 => return unsupported("a slope other than 0.01"),
+        "LeakyRelu" => ActivationFunction::LeakyReLU,
+        "Elu" if (node.float("alpha", 1.0) - 1.0).abs() > 1e-6 => return unsupported("an alpha other than 1"),
+        "Elu" => ActivationFunction::ELU,
+        "Selu" => ActivationFunction::SELU,
+        "Gelu" if node.string("approximate").unwrap_or("none") != "none" => return unsupported("the tanh approximation"),
+        "Gelu" => ActivationFunction::GELU,
+        _ => return Ok(None),
+    };
+    if activation.is_row_wise() && !matches!(node.int("axis", -1), -1 | 1) {
+        return unsupported("an axis other than the last");
+    }
+    Ok(Some(activation))
+}
+
+/// Spatial attribute of a 1D or 2D node as (height, width), 1 along missing axes
+fn spatial(values: &[usize], rank: usize, name: &str) -> Result<[usize; 2], String> {
+    match (rank, values) {
+        (_, []) => Ok([1, 1]),
+        (1, [w]) => Ok([1, *w]),
+        (2, [h, w]) => Ok([*h, *w]),
+        _ => Err(format!("Unexpected {} attribute for a {}D operation", name, rank)),
+    }
+}
+
+/// Symmetric padding of a 1D or 2D node as (height, width)
+fn symmetric_padding(node: &Node, rank: usize) -> Result<[usize; 2], String> {
+    if !matches!(node.string("auto_pad").unwrap_or("NOTSET"), "NOTSET" | "VALID") {
+        return Err(format!("{} with automatic padding is not supported", node.op_type));
+    }
+    let pads = node.sizes("pads", vec![0; 2 * rank])?;
+    match (rank, &pads[..]) {
+        (1, [left, right]) if left == right => Ok([0, *left]),
+        (2, [top, left, bottom, right]) if top == bottom && left == right => Ok([*top, *left]),
+        _ => Err(format!("{} with asymmetric padding is not supported", node.op_type)),
+    }
+}
+
+/// Parse an ONNX model into its input shape and a sequence of layers
+///
+/// The graph must be a chain: every node consumes the output of the previous one, with all
+/// other inputs stored as initializers or constants.
+fn parse_onnx(bytes: &[u8]) -> Result<(Shape, Vec<Imported>), String> {
+    let graph = protobuf::fields(bytes)?
+        .into_iter()
+        .find(|(number, _)| *number == 7)
+        .ok_or("ONNX model has no graph")?
+        .1
+        .as_bytes()?;
+
+    let (mut nodes, mut initializers, mut inputs, mut outputs) = (Vec::new(), HashMap::new(), Vec::new(), Vec::new());
+    for (number, field) in protobuf::fields(graph)? {
+        match number {
+            1 => nodes.push(parse_node(field.as_bytes()?)?),
+            5 => {
+                let (name, tensor) = parse_tensor(field.as_bytes()?)?;
+                initializers.insert(name, tensor);
+            }
+            11 => inputs.push(parse_value_info(field.as_bytes()?)?),
+            12 => outputs.push(parse_value_info(field.as_bytes()?)?),
+            _ => {}
+        }
+    }
+
+    // Older exporters also list the initializers as graph inputs
+    let (mut current, dims) = inputs.into_iter().find(|(name, _)| !initializers.contains_key(name)).ok_or("ONNX graph has no input")?;
+    let dims = dims[1.min(dims.len())..].iter().map(|d| d.ok_or("ONNX input dimensions must be fixed apart from the batch")).collect::<Result<Vec<usize>, &str>>()?;
+    let shape = match dims[..] {
+        [features] => [features, 1, 1],
+        [channels, length] => [channels, 1, length],
+        [channels, height, width] => [channels, height, width],
+        _ => return Err("ONNX input must have 1 to 3 dimensions besides the batch".to_string()),
+    };
+
+    let mut layers: Vec<Imported> = Vec::new();
+    for node in nodes {
+        if node.op_type == "Constant" {
+            let tensor = node.attributes.into_iter().find_map(|(_, a)| a.tensor).ok_or("Constant node without a tensor value")?;
+            initializers.insert(node.outputs.first().cloned().unwrap_or_default(), tensor);
+            continue;
+        }
+
+        let data_input = node.inputs.iter().find(|name| !name.is_empty() && !initializers.contains_key(*name));
+        if data_input != Some(&current) || node.outputs.is_empty() {
+            return Err(format!("{} node is not part of a sequential chain; only sequential graphs are supported", node.op_type));
+        }
+        current = node.outputs[0].clone();
+
+        let constant = |index: usize| -> Result<&Tensor, String> {
+            node.inputs
+                .get(index)
+                .and_then(|name| initializers.get(name))
+                .ok_or_else(|| format!("{} node needs constant input {}", node.op_type, index))
+        };
+
+        if let Some(activation) = onnx_activation(&node)? {
+            // Fuse into a preceding layer without an activation; row-wise activations of a
+            // convolution would span channels and positions, so they stay separate
+            match layers.last_mut() {
+                Some(Imported::Dense { activation: previous, .. }) if *previous == ActivationFunction::Linear => *previous = activation,
+                Some(Imported::Conv { activation: previous, .. })
+                    if *previous == ActivationFunction::Linear && !activation.is_row_wise() =>
+                {
+                    *previous = activation
+                }
+                _ => layers.push(Imported::Activation(activation)),
+            }
+            continue;
+        }
+
+        match node.op_type.as_str() {
+            "Identity" | "Dropout" | "Flatten" => {
+                if node.int("axis", 1) != 1 {
+                    return Err("Flatten is only supported with axis 1".to_string());
+                }
+            }
+            "Reshape" => {
+                // Only flattening to (batch, features) keeps the channel-first layout
+                if constant(1)?.data.len() != 2 {
+                    return Err("Reshape is only supported for flattening to (batch, features)".to_string());
+                }
+            }
+            "Gemm" => {
+                if node.int("transA", 0) != 0 {
+                    return Err("Gemm with a transposed input is not supported".to_string());
+                }
+                let b = constant(1)?;
+                if b.shape.len() != 2 {
+                    return Err("Gemm weights must be a matrix".to_string());
+                }
+                let alpha = node.float("alpha", 1.0);
+                let mut weights = if node.int("transB", 0) != 0 { b.data.clone() } else { transpose(&b.data, b.shape[0], b.shape[1]) };
+                weights.iter_mut().for_each(|w| *w *= alpha);
+                let units = if node.int("transB", 0) != 0 { b.shape[0] } else { b.shape[1] };
+
+                let biases = match node.inputs.get(2).filter(|name| !name.is_empty()) {
+                    Some(_) => {
+                        let c = constant(2)?;
+                        let beta = node.float("beta", 1.0);
+                        match c.data.len() {
+                            1 => vec![c.data[0] * beta; units],
+                            n if n == units => c.data.iter().map(|v| v * beta).collect(),
+                            _ => return Err("Gemm bias must have one value per output".to_string()),
+                        }
+                    }
+                    None => vec![0.0; units],
+                };
+                layers.push(Imported::Dense { units, activation: ActivationFunction::Linear, weights, biases });
+            }
+            "MatMul" => {
+                let b = constant(1)?;
+                if b.shape.len() != 2 {
+                    return Err("MatMul weights must be a matrix".to_string());
+                }
+                let weights = transpose(&b.data, b.shape[0], b.shape[1]);
+                layers.push(Imported::Dense { units: b.shape[1], activation: ActivationFunction::Linear, weights, biases: vec![0.0; b.shape[1]] });
+            }
+            "Add" => {
+                let addend = node.inputs.iter().find_map(|name| initializers.get(name)).ok_or("Add needs a constant bias")?;
+                match layers.last_mut() {
+                    Some(Imported::Dense { activation: ActivationFunction::Linear, biases, .. })
+                    | Some(Imported::Conv { activation: ActivationFunction::Linear, biases, .. })
+                        if addend.data.len() == biases.len() =>
+                    {
+                        biases.iter_mut().zip(&addend.data).for_each(|(b, a)| *b += a)
+                    }
+                    _ => return Err("Add is only supported as the bias of a MatMul, Gemm or Conv".to_string()),
+                }
+            }
+            "Conv" => {
+                let w = constant(1)?;
+                let rank = w.shape.len().saturating_sub(2);
+                if !(1..=2).contains(&rank) {
+                    return Err("Only 1D and 2D convolutions are supported".to_string());
+                }
+                if node.int("group", 1) != 1 {
+                    return Err("Grouped convolutions are not supported".to_string());
+                }
+                let filters = w.shape[0];
+                let kernel = if rank == 1 { [1, w.shape[2]] } else { [w.shape[2], w.shape[3]] };
+                let stride = spatial(&node.sizes("strides", Vec::new())?, rank, "strides")?;
+                let dilation = spatial(&node.sizes("dilations", Vec::new())?, rank, "dilations")?;
+                let padding = symmetric_padding(&node, rank)?;
+                let biases = match node.inputs.get(2).filter(|name| !name.is_empty()) {
+                    Some(_) => constant(2)?.data.clone(),
+                    None => vec![0.0; filters],
+                };
+                layers.push(Imported::Conv { filters, kernel, stride, padding, dilation, activation: ActivationFunction::Linear, weights: w.data.clone(), biases });
+            }
+            "MaxPool" | "AveragePool" => {
+                let kind = if node.op_type == "MaxPool" { PoolingType::Max } else { PoolingType::Average };
+                let kernel = node.sizes("kernel_shape", Vec::new())?;
+                let rank = kernel.len();
+                if !(1..=2).contains(&rank) {
+                    return Err("Only 1D and 2D pooling is supported".to_string());
+                }
+                if symmetric_padding(&node, rank)? != [0, 0] || node.int("ceil_mode", 0) != 0 || node.sizes("dilations", vec![1; rank])?.iter().any(|&d| d != 1) {
+                    return Err("Pooling with padding, ceil mode or dilation is not supported".to_string());
+                }
+                let size = spatial(&kernel, rank, "kernel_shape")?;
+                let stride = spatial(&node.sizes("strides", Vec::new())?, rank, "strides")?;
+                layers.push(Imported::Pool { kind, size, stride });
+            }
+            "GlobalAveragePool" => layers.push(Imported::GlobalPool(PoolingType::Average)),
+            "GlobalMaxPool" => layers.push(Imported::GlobalPool(PoolingType::Max)),
+            "BatchNormalization" => {
+                let values = (1..5).map(|i| constant(i).map(|t| t.data.clone())).collect::<Result<Vec<Vec<f64>>, String>>()?;
+                let momentum = 1.0 - node.float("momentum", 0.9);
+                layers.push(Imported::BatchNorm { momentum: if momentum > 0.0 { momentum } else { 0.1 }, epsilon: node.float("epsilon", 1e-5), values });
+            }
+            other => return Err(format!("Unsupported ONNX operator {}", other)),
+        }
+    }
+
+    if outputs.len() != 1 || outputs[0].0 != current {
+        return Err("ONNX graph must have a single output at the end of the chain".to_string());
+    }
+    Ok((shape, layers))
+}
+
+/// Transpose a row-major `rows x columns` matrix
+fn transpose(values: &[f64], rows: usize, columns: usize) -> Vec<f64> {
+    (0..columns).flat_map(|j| (0..rows).map(move |i| values[i * columns + j])).collect()
+}
+
+/// Name of an activation in `neural_network_forward_multi_layer_f64`
+fn activation_name(activation: ActivationFunction) -> &'static str {
+    match activation {
+        ActivationFunction::Sigmoid => "sigmoid",
+        ActivationFunction::ReLU => "relu",
+        ActivationFunction::Tanh => "tanh",
+        ActivationFunction::LeakyReLU => "leaky_relu",
+        ActivationFunction::Softmax => "softmax",
+        ActivationFunction::LogSoftmax => "log_softmax",
+        ActivationFunction::GELU => "gelu",
+        ActivationFunction::ELU => "elu",
+        ActivationFunction::SELU => "selu",
+        ActivationFunction::Swish => "swish",
+        ActivationFunction::Linear => "linear",
+    }
+}
+
+/// Import a fully connected ONNX model for `neural_network_forward_multi_layer_f64`
+///
+/// Supports chains of Gemm or MatMul (plus Add) nodes, each optionally followed by one
+/// activation. Returns `{ weights, biases, activations }` with one entry per layer; use
+/// `Network.from_onnx` for models with convolutions, pooling or normalization.
+#[wasm_bindgen]
+pub fn neural_network_import_onnx_f64(bytes: &[u8]) -> Result<JsValue, JsValue> {
+    let (_, layers) = parse_onnx(bytes)?;
+
+    let (weights, biases, activations) = (Array::new(), Array::new(), Array::new());
+    for layer in layers {
+        match layer {
+            Imported::Dense { activation, weights: w, biases: b, .. } => {
+                weights.push(&Float64Array::from(&w[..]));
+                biases.push(&Float64Array::from(&b[..]));
+                activations.push(&JsValue::from_str(activation_name(activation)));
+            }
+            _ => return Err(JsValue::from_str("Model contains layers other than dense layers with one activation; use Network.from_onnx")),
+        }
+    }
+    if weights.length() == 0 {
+        return Err(JsValue::from_str("Model contains no dense layers"));
+    }
+
+    // Create result object
+    let result = Object::new();
+    Reflect::set(&result, &JsValue::from_str("weights"), &weights)?;
+    Reflect::set(&result, &JsValue::from_str("biases"), &biases)?;
+    Reflect::set(&result, &JsValue::from_str("activations"), &activations)?;
+    Ok(result.into())
+}
+
+#[wasm_bindgen]
+impl Network {
+    /// Build a network from an ONNX model, with an optional seed for dropout
+    ///
+    /// Supports sequential graphs of Gemm, MatMul, Add (as a bias), Conv (1D and 2D),
+    /// MaxPool, AveragePool, global pooling, BatchNormalization, Flatten and the
+    /// activations this crate defines. Weights must be stored in the model file.
+    pub fn from_onnx(bytes: &[u8], seed: Option<u32>) -> Result<Network, JsValue> {
+        let (shape, layers) = parse_onnx(bytes)?;
+
+        let mut network = Network::new(shape.iter().product(), None, None, seed)?;
+        network.set_input_shape(shape[0], shape[1], shape[2])?;
+        let mut buffers = Vec::new();
+        for layer in layers {
+            match layer {
+                Imported::Dense { units, activation, weights, biases } => {
+                    network.add_dense(units, activation)?;
+                    buffers.extend([weights, biases]);
+                }
+                Imported::Conv { filters, kernel, stride, padding, dilation, activation, weights, biases } => {
+                    network.add_conv(filters, kernel, activation, stride, padding, dilation)?;
+                    buffers.extend([weights, biases]);
+                }
+                Imported::Pool { kind, size, stride } => network.add_pool(kind, size, stride)?,
+                Imported::GlobalPool(kind) => {
+                    let [_, height, width] = network.current_shape();
+                    network.add_pool(kind, [height, width], [height, width])?;
+                }
+                Imported::BatchNorm { momentum, epsilon, values } => {
+                    network.add_batch_norm(Some(momentum), Some(epsilon))?;
+                    buffers.extend(values);
+                }
+                Imported::Activation(activation) => {
+                    if activation.is_row_wise() && network.current_shape()[1..] != [1, 1] {
+                        return Err(JsValue::from_str("Softmax over channels of a feature map is not supported"));
+                    }
+                    network.add_activation(activation);
+                }
+            }
+        }
+
+        let mut buffers = buffers.into_iter();
+        network.for_each_buffer(|buffer| match buffers.next() {
+            Some(values) if values.len() == buffer.len() => {
+                *buffer = values;
+                Ok(())
+            }
+            _ => Err("ONNX weights do not match the layer shapes".to_string()),
+        })?;
+        Ok(network)
+    }
+
+    /// Load weights from a safetensors file, e.g. a PyTorch `state_dict` saved with
+    /// `safetensors.torch.save_file`
+    ///
+    /// `names` lists one tensor name per parameter and saved statistic, in layer order:
+    /// weights then biases, then running mean and variance for batch normalization
+    /// (`num_layer_parameters` gives the parameter count of each layer). An entry can be an
+    /// array of names whose tensors are summed, such as PyTorch's two recurrent biases.
+    /// Nothing is changed unless every tensor matches.
+    pub fn load_safetensors(&mut self, bytes: &[u8], names: &JsValue) -> Result<(), JsValue> {
+        let tensors: HashMap<String, Tensor> = parse_safetensors(bytes)?.into_iter().collect();
+        let names = Array::from(names);
+
+        let mut lengths = Vec::new();
+        self.for_each_buffer(|buffer| {
+            lengths.push(buffer.len());
+            Ok(())
+        })?;
+        if names.length() as usize != lengths.len() {
+            return Err(JsValue::from_str(&format!("Expected {} tensor names, got {}", lengths.len(), names.length())));
+        }
+
+        let mut staged = Vec::with_capacity(lengths.len());
+        for (index, &length) in lengths.iter().enumerate() {
+            let entry = names.get(index as u32);
+            let keys: Vec<JsValue> = if Array::is_array(&entry) { Array::from(&entry).to_vec() } else { vec![entry] };
+
+            let mut values = vec![0.0; length];
+            for key in keys {
+                let key = key.as_string().ok_or_else(|| JsValue::from_str("Tensor names must be strings"))?;
+                let tensor = tensors.get(&key).ok_or_else(|| JsValue::from_str(&format!("Tensor {} not found", key)))?;
+                if tensor.data.len() != length {
+                    return Err(JsValue::from_str(&format!("Tensor {} has {} values, expected {}", key, tensor.data.len(), length)));
+                }
+                values.iter_mut().zip(&tensor.data).for_each(|(v, t)| *v += t);
+            }
+            staged.push(values);
+        }
+
+        let mut staged = staged.into_iter();
+        self.for_each_buffer(|buffer| {
+            *buffer = staged.next().unwrap_or_default();
+            Ok(())
+        })?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn safetensors(header: &str, data: &[u8]) -> Vec<u8> {
+        let mut bytes = (header.len() as u64).to_le_bytes().to_vec();
+        bytes.extend_from_slice(header.as_bytes());
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    /// Two tensors: a 2x2 F32 matrix and three F16 values
+    fn weights_file() -> Vec<u8> {
+        let header = concat!(
+            r#"{"__metadata__":{"format":"pt"},"#,
+            r#""weight":{"dtype":"F32","shape":[2,2],"data_offsets":[0,16]},"#,
+            r#""bias":{"dtype":"F16","shape":[3],"data_offsets":[16,22]}}"#,
+        );
+        let mut data: Vec<u8> = [1.0f32, -2.0, 0.5, 4.0].iter().flat_map(|v| v.to_le_bytes()).collect();
+        data.extend([0x3c00u16, 0xc000, 0x3800].iter().flat_map(|v| v.to_le_bytes()));
+        safetensors(header, &data)
+    }
+
+    #[test]
+    fn half_precision_values() {
+        assert_eq!(half_to_f64(0x3c00), 1.0);
+        assert_eq!(half_to_f64(0xc000), -2.0);
+        assert_eq!(half_to_f64(0x7bff), 65504.0);
+        assert_eq!(half_to_f64(0x0001), 2f64.powi(-24));
+        assert_eq!(half_to_f64(0x7c00), f64::INFINITY);
+        assert_eq!(half_to_f64(0xfc00), f64::NEG_INFINITY);
+        assert!(half_to_f64(0x7e00).is_nan());
+        assert!(half_to_f64(0x8000).is_sign_negative());
+    }
+
+    #[test]
+    fn decode_every_dtype() {
+        assert_eq!(decode_values("F64", &(-1.5f64).to_le_bytes()).unwrap(), vec![-1.5]);
+        assert_eq!(decode_values("I64", &(-3i64).to_le_bytes()).unwrap(), vec![-3.0]);
+        assert_eq!(decode_values("I32", &(-7i32).to_le_bytes()).unwrap(), vec![-7.0]);
+        assert_eq!(decode_values("U32", &u32::MAX.to_le_bytes()).unwrap(), vec![u32::MAX as f64]);
+        assert_eq!(decode_values("BF16", &0x3f80u16.to_le_bytes()).unwrap(), vec![1.0]);
+        assert_eq!(decode_values("I16", &(-2i16).to_le_bytes()).unwrap(), vec![-2.0]);
+        assert_eq!(decode_values("I8", &[0xff, 0x01]).unwrap(), vec![-1.0, 1.0]);
+        assert_eq!(decode_values("U8", &[0xff]).unwrap(), vec![255.0]);
+        assert_eq!(decode_values("BOOL", &[0, 1]).unwrap(), vec![0.0, 1.0]);
+
+        assert_eq!(decode_values("F32", &[0; 6]).unwrap_err(), "Tensor data is not a whole number of F32 values");
+        assert_eq!(decode_values("C64", &[]).unwrap_err(), "Unsupported tensor dtype C64");
+    }
+
+    #[test]
+    fn safetensors_tensors_in_header_order() {
+        let tensors = parse_safetensors(&weights_file()).unwrap();
+        assert_eq!(tensors.len(), 2);
+
+        let (name, weight) = &tensors[0];
+        assert_eq!((name.as_str(), weight.dtype.as_str()), ("weight", "F32"));
+        assert_eq!((&weight.shape[..], &weight.data[..]), (&[2, 2][..], &[1.0, -2.0, 0.5, 4.0][..]));
+
+        let (name, bias) = &tensors[1];
+        assert_eq!((name.as_str(), bias.dtype.as_str()), ("bias", "F16"));
+        assert_eq!((&bias.shape[..], &bias.data[..]), (&[3][..], &[1.0, -2.0, 0.5][..]));
+    }
+
+    #[test]
+    fn truncated_safetensors_are_rejected() {
+        let bytes = weights_file();
+        for length in 0..bytes.len() {
+            assert!(parse_safetensors(&bytes[..length]).is_err(), "prefix of {} bytes was accepted", length);
+        }
+
+        let mut huge = bytes.clone();
+        huge[..8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(parse_safetensors(&huge).err().unwrap(), "Truncated safetensors header");
+    }
+
+    #[test]
+    fn invalid_safetensors_are_rejected() {
+        let reject = |header: &str, data: &[u8]| parse_safetensors(&safetensors(header, data)).err().unwrap();
+        assert_eq!(reject("[1, 2]", &[]), "Safetensors header must be a JSON object");
+        assert_eq!(reject(r#"{"w":{"shape":[1],"data_offsets":[0,4]}}"#, &[0; 4]), "Invalid safetensors entry for w");
+        assert_eq!(
+            reject(r#"{"w":{"dtype":"F32","shape":[-1],"data_offsets":[0,4]}}"#, &[0; 4]),
+            "Invalid safetensors entry for w",
+        );
+        assert_eq!(
+            reject(r#"{"w":{"dtype":"F32","shape":[1],"data_offsets":[4,0]}}"#, &[0; 4]),
+            "Data offsets of w are out of range",
+        );
+        assert_eq!(
+            reject(r#"{"w":{"dtype":"F32","shape":[2],"data_offsets":[0,4]}}"#, &[0; 4]),
+            "Data of w does not match its shape",
+        );
+        assert_eq!(
+            parse_safetensors(&[2, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xfe]).err().unwrap(),
+            "Invalid UTF-8 in safetensors header",
+        );
+    }
+
+    #[test]
+    fn transpose_swaps_rows_and_columns() {
+        assert_eq!(transpose(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], 2, 3), vec![1.0, 4.0, 2.0, 5.0, 3.0, 6.0]);
+    }
+
+    // Minimal protobuf writer for building ONNX models by hand
+
+    fn varint(mut value: u64, out: &mut Vec<u8>) {
+        while value >= 0x80 {
+            out.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+
+    fn varint_field(number: u32, value: i64) -> Vec<u8> {
+        let mut out = Vec::new();
+        varint((number as u64) << 3, &mut out);
+        varint(value as u64, &mut out);
+        out
+    }
+
+    fn bytes_field(number: u32, payload: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        varint(((number as u64) << 3) | 2, &mut out);
+        varint(payload.len() as u64, &mut out);
+        out.extend_from_slice(payload);
+        out
+    }
+
+    fn float_field(number: u32, value: f32) -> Vec<u8> {
+        let mut out = Vec::new();
+        varint(((number as u64) << 3) | 5, &mut out);
+        out.extend_from_slice(&value.to_le_bytes());
+        out
+    }
+
+    fn tensor(name: &str, dims: &[i64], values: &[f32]) -> Vec<u8> {
+        let mut out: Vec<u8> = dims.iter().flat_map(|&d| varint_field(1, d)).collect();
+        out.extend(varint_field(2, 1));
+        out.extend(bytes_field(8, name.as_bytes()));
+        let raw: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        out.extend(bytes_field(9, &raw));
+        out
+    }
+
+    /// Graph input or output with a symbolic batch dimension
+    fn value_info(name: &str, dims: &[i64]) -> Vec<u8> {
+        let mut shape = bytes_field(1, &bytes_field(2, b"batch"));
+        for &d in dims {
+            shape.extend(bytes_field(1, &varint_field(1, d)));
+        }
+        let mut tensor_type = varint_field(1, 1);
+        tensor_type.extend(bytes_field(2, &shape));
+        let mut out = bytes_field(1, name.as_bytes());
+        out.extend(bytes_field(2, &bytes_field(1, &tensor_type)));
+        out
+    }
+
+    fn int_attribute(name: &str, value: i64) -> Vec<u8> {
+        let mut out = bytes_field(1, name.as_bytes());
+        out.extend(varint_field(3, value));
+        out
+    }
+
+    fn ints_attribute(name: &str, values: &[i64]) -> Vec<u8> {
+        let mut out = bytes_field(1, name.as_bytes());
+        out.extend(values.iter().flat_map(|&v| varint_field(8, v)));
+        out
+    }
+
+    fn float_attribute(name: &str, value: f32) -> Vec<u8> {
+        let mut out = bytes_field(1, name.as_bytes());
+        out.extend(float_field(2, value));
+        out
+    }
+
+    fn node(op_type: &str, inputs: &[&str], output: &str, attributes: &[Vec<u8>]) -> Vec<u8> {
+        let mut out: Vec<u8> = inputs.iter().flat_map(|name| bytes_field(1, name.as_bytes())).collect();
+        out.extend(bytes_field(2, output.as_bytes()));
+        out.extend(bytes_field(4, op_type.as_bytes()));
+        for attribute in attributes {
+            out.extend(bytes_field(5, attribute));
+        }
+        out
+    }
+
+    /// Model whose graph holds the given nodes, initializers, inputs and one output
+    fn model(nodes: &[Vec<u8>], initializers: &[Vec<u8>], inputs: &[Vec<u8>], output: Vec<u8>) -> Vec<u8> {
+        let mut graph: Vec<u8> = nodes.iter().flat_map(|n| bytes_field(1, n)).collect();
+        graph.extend(initializers.iter().flat_map(|t| bytes_field(5, t)));
+        graph.extend(inputs.iter().flat_map(|i| bytes_field(11, i)));
+        graph.extend(bytes_field(12, &output));
+        bytes_field(7, &graph)
+    }
+
+    /// x (3 features) -> Gemm(transB) -> Relu -> y (2 outputs)
+    fn gemm_relu() -> Vec<u8> {
+        model(
+            &[
+                node("Gemm", &["x", "W", "b"], "h", &[int_attribute("transB", 1)]),
+                node("Relu", &["h"], "y", &[]),
+            ],
+            &[tensor("W", &[2, 3], &[1.0, -1.0, 0.5, 2.0, 0.0, -0.5]), tensor("b", &[2], &[0.25, -1.0])],
+            &[value_info("x", &[3])],
+            value_info("y", &[2]),
+        )
+    }
+
+    #[test]
+    fn gemm_fuses_the_following_activation() {
+        let (shape, layers) = parse_onnx(&gemm_relu()).unwrap();
+        assert_eq!(shape, [3, 1, 1]);
+        assert_eq!(layers.len(), 1);
+        match &layers[0] {
+            Imported::Dense { units, activation, weights, biases } => {
+                assert_eq!((*units, *activation), (2, ActivationFunction::ReLU));
+                assert_eq!(weights[..], [1.0, -1.0, 0.5, 2.0, 0.0, -0.5]);
+                assert_eq!(biases[..], [0.25, -1.0]);
+            }
+            _ => panic!("expected a dense layer"),
+        }
+    }
+
+    #[test]
+    fn imported_network_matches_the_graph() {
+        let mut network = Network::from_onnx(&gemm_relu(), Some(1)).unwrap();
+        let outputs = network.forward_rows(&[1.0, 2.0, 3.0, -1.0, 0.5, 4.0], 2, false).unwrap();
+
+        // relu(W x + b) by hand; both second outputs are negative before the ReLU
+        for (actual, expected) in outputs.iter().zip([0.75, 0.0, 0.75, 0.0]) {
+            assert!((actual - expected).abs() < 1e-12, "{:?}", outputs);
+        }
+    }
+
+    #[test]
+    fn matmul_and_add_form_a_dense_layer() {
+        // Older exporters also list initializers among the graph inputs
+        let bytes = model(
+            &[
+                node("MatMul", &["x", "B"], "m", &[]),
+                node("Add", &["m", "c"], "h", &[]),
+                node("Softmax", &["h"], "y", &[int_attribute("axis", -1)]),
+            ],
+            &[tensor("B", &[3, 2], &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]), tensor("c", &[2], &[0.5, -0.5])],
+            &[value_info("B", &[3, 2]), value_info("x", &[3])],
+            value_info("y", &[2]),
+        );
+
+        let (shape, layers) = parse_onnx(&bytes).unwrap();
+        assert_eq!(shape, [3, 1, 1]);
+        match &layers[..] {
+            [Imported::Dense { units: 2, activation: ActivationFunction::Softmax, weights, biases }] => {
+                assert_eq!(weights[..], [1.0, 3.0, 5.0, 2.0, 4.0, 6.0]);
+                assert_eq!(biases[..], [0.5, -0.5]);
+            }
+            _ => panic!("expected one dense softmax layer"),
+        }
+    }
+
+    #[test]
+    fn convolution_pooling_and_flatten() {
+        let bytes = model(
+            &[
+                node("Conv", &["x", "K"], "c", &[ints_attribute("pads", &[1, 1]), ints_attribute("strides", &[2])]),
+                node("MaxPool", &["c"], "p", &[ints_attribute("kernel_shape", &[2])]),
+                node("Flatten", &["p"], "f", &[]),
+                node("Gemm", &["f", "W"], "y", &[]),
+            ],
+            &[tensor("K", &[4, 2, 3], &[0.1; 24]), tensor("W", &[12, 1], &[1.0; 12])],
+            &[value_info("x", &[2, 8])],
+            value_info("y", &[1]),
+        );
+
+        let (shape, layers) = parse_onnx(&bytes).unwrap();
+        assert_eq!(shape, [2, 1, 8]);
+        assert_eq!(layers.len(), 3);
+        match &layers[0] {
+            Imported::Conv { filters: 4, kernel, stride, padding, dilation, biases, .. } => {
+                assert_eq!((*kernel, *stride, *padding, *dilation), ([1, 3], [1, 2], [0, 1], [1, 1]));
+                assert_eq!(biases[..], [0.0; 4]);
+            }
+            _ => panic!("expected a convolution"),
+        }
+        match &layers[1] {
+            Imported::Pool { kind, size, stride } => assert_eq!((*kind, *size, *stride), (PoolingType::Max, [1, 2], [1, 1])),
+            _ => panic!("expected a pooling layer"),
+        }
+        assert!(matches!(layers[2], Imported::Dense { units: 1, .. }));
+
+        // Conv output length (8 + 2 - 3) / 2 + 1 = 4, pooled to 3, flattened to 12 features
+        let mut network = Network::from_onnx(&bytes, Some(1)).unwrap();
+        let outputs = network.forward_rows(&[1.0; 32], 2, false).unwrap();
+        assert_eq!(outputs.len(), 2);
+    }
+
+    #[test]
+    fn onnx_tensor_encodings() {
+        let mut half = varint_field(1, 2);
+        half.extend(varint_field(2, 10));
+        half.extend(bytes_field(8, b"h"));
+        let mut packed = Vec::new();
+        varint(0x3c00, &mut packed);
+        varint(0xc000, &mut packed);
+        half.extend(bytes_field(5, &packed));
+        let (name, decoded) = parse_tensor(&half).unwrap();
+        assert_eq!((name.as_str(), decoded.dtype.as_str()), ("h", "F16"));
+        assert_eq!(decoded.data, vec![1.0, -2.0]);
+
+        let mut floats = varint_field(1, 2);
+        floats.extend(float_field(4, 1.5));
+        floats.extend(float_field(4, -0.25));
+        assert_eq!(parse_tensor(&floats).unwrap().1.data, vec![1.5, -0.25]);
+
+        let mut external = tensor("e", &[1], &[0.0]);
+        external.extend(varint_field(14, 1));
+        assert_eq!(parse_tensor(&external).err().unwrap(), "External data of tensor e is not supported");
+        let mismatched = tensor("s", &[3], &[0.0; 2]);
+        assert_eq!(parse_tensor(&mismatched).err().unwrap(), "Data of tensor s does not match its shape");
+    }
+
+    #[test]
+    fn unsupported_graphs_are_rejected() {
+        let weights = || vec![tensor("W", &[2, 3], &[0.0; 6])];
+        let reject = |nodes: &[Vec<u8>], output: &str| {
+            parse_onnx(&model(nodes, &weights(), &[value_info("x", &[3])], value_info(output, &[2]))).err().unwrap()
+        };
+
+        assert_eq!(
+            reject(&[node("Gemm", &["z", "W"], "y", &[int_attribute("transB", 1)])], "y"),
+            "Gemm node is not part of a sequential chain; only sequential graphs are supported",
+        );
+        assert_eq!(reject(&[node("Sin", &["x"], "y", &[])], "y"), "Unsupported ONNX operator Sin");
+        assert_eq!(
+            reject(&[node("LeakyRelu", &["x"], "y", &[float_attribute("alpha", 0.2)])], "y"),
+            "LeakyRelu with a slope other than 0.01 is not supported",
+        );
+        assert_eq!(
+            reject(&[node("Gemm", &["x", "W"], "y", &[int_attribute("transB", 1)])], "z"),
+            "ONNX graph must have a single output at the end of the chain",
+        );
+        assert_eq!(
+            reject(&[node("Conv", &["x", "W"], "y", &[])], "y"),
+            "Only 1D and 2D convolutions are supported",
+        );
+
+        let symbolic = value_info("x", &[0]);
+        let bytes = model(&[node("Relu", &["x"], "y", &[])], &[], &[symbolic], value_info("y", &[]));
+        assert_eq!(parse_onnx(&bytes).err().unwrap(), "ONNX input dimensions must be fixed apart from the batch");
+        assert_eq!(parse_onnx(&bytes_field(1, b"graph")).err().unwrap(), "ONNX model has no graph");
+    }
+
+    #[test]
+    fn truncated_onnx_models_are_rejected() {
+        let bytes = gemm_relu();
+        for length in 0..bytes.len() {
+            assert!(parse_onnx(&bytes[..length]).is_err(), "prefix of {} bytes was accepted", length);
+        }
+    }
+}
@@ -0,0 +1,896 @@
+use wasm_bindgen::prelude::*;
+
+use super::network::{Layer, LayerKind, Parameter};
+use super::neural_network::{dot, ActivationFunction, Initializer};
+use super::random::Rng;
+use super::serialization::ByteWriter;
+
+/// Pooling operations for `Network` pooling layers
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PoolingType {
+    /// Maximum of each window
+    Max,
+    /// Mean of each window
+    Average,
+}
+
+impl PoolingType {
+    /// Variant with the given discriminant, for deserialization
+    pub(crate) fn from_index(index: u32) -> Option<PoolingType> {
+        [PoolingType::Max, PoolingType::Average].get(index as usize).copied()
+    }
+}
+
+/// Shape of one sample as (channels, height, width); dense features are (features, 1, 1)
+/// and 1D sequences are (channels, 1, length)
+pub(crate) type Shape = [usize; 3];
+
+/// Output length of a sliding window along one axis, or None when the window does not fit
+fn sliding_length(input: usize, kernel: usize, stride: usize, padding: usize, dilation: usize) -> Option<usize> {
+    let span = dilation.checked_mul(kernel - 1)?.checked_add(1)?;
+    let padded = padding.checked_mul(2)?.checked_add(input)?;
+    if padded < span {
+        return None;
+    }
+    Some((padded - span) / stride + 1)
+}
+
+/// 2D convolution over channel-first samples, with weights stored as
+/// `filters x channels x kernel_height x kernel_width`
+///
+/// A 1D convolution is the special case of height 1 and a kernel height of 1.
+pub(crate) struct Conv2D {
+    input_shape: Shape,
+    output_shape: Shape,
+    kernel: [usize; 2],
+    stride: [usize; 2],
+    padding: [usize; 2],
+    dilation: [usize; 2],
+    activation: ActivationFunction,
+    weights: Parameter,
+    biases: Parameter,
+    /// im2col patches of the latest batch: one row of `patch_len` values per output position
+    cached_patches: Vec<f64>,
+    cached_pre_activations: Vec<f64>,
+    cached_outputs: Vec<f64>,
+}
+
+impl Conv2D {
+    /// Convolution layer with weights drawn by `initializer` and zero biases
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        input_shape: Shape,
+        filters: usize,
+        kernel: [usize; 2],
+        stride: [usize; 2],
+        padding: [usize; 2],
+        dilation: [usize; 2],
+        activation: ActivationFunction,
+        initializer: Initializer,
+        rng: &mut Rng,
+    ) -> Result<Conv2D, String> {
+        let [channels, height, width] = input_shape;
+        let output_height = sliding_length(height, kernel[0], stride[0], padding[0], dilation[0]);
+        let output_width = sliding_length(width, kernel[1], stride[1], padding[1], dilation[1]);
+        let (output_height, output_width) = match (output_height, output_width) {
+            (Some(h), Some(w)) => (h, w),
+            _ => return Err("Dilated kernel is larger than the padded input".to_string()),
+        };
+
+        let patch_len = channels * kernel[0] * kernel[1];
+        let weights = initializer.sample(filters, patch_len, patch_len, filters * kernel[0] * kernel[1], rng);
+
+        Ok(Conv2D {
+            input_shape,
+            output_shape: [filters, output_height, output_width],
+            kernel,
+            stride,
+            padding,
+            dilation,
+            activation,
+            weights: Parameter::new(weights),
+            biases: Parameter::new(vec![0.0; filters]),
+            cached_patches: Vec::new(),
+            cached_pre_activations: Vec::new(),
+            cached_outputs: Vec::new(),
+        })
+    }
+
+    fn patch_len(&self) -> usize {
+        self.input_shape[0] * self.kernel[0] * self.kernel[1]
+    }
+
+    fn positions(&self) -> usize {
+        self.output_shape[1] * self.output_shape[2]
+    }
+
+    /// Input offset within a sample for each (output position, patch element), or None for
+    /// padding
+    fn patch_offsets(&self) -> Vec<Option<usize>> {
+        let [channels, height, width] = self.input_shape;
+        let mut offsets = Vec::with_capacity(self.positions() * self.patch_len());
+
+        for oy in 0..self.output_shape[1] {
+            for ox in 0..self.output_shape[2] {
+                for c in 0..channels {
+                    for ky in 0..self.kernel[0] {
+                        for kx in 0..self.kernel[1] {
+                            let y = (oy * self.stride[0] + ky * self.dilation[0]) as isize - self.padding[0] as isize;
+                            let x = (ox * self.stride[1] + kx * self.dilation[1]) as isize - self.padding[1] as isize;
+                            let inside = y >= 0 && x >= 0 && (y as usize) < height && (x as usize) < width;
+                            offsets.push(if inside { Some((c * height + y as usize) * width + x as usize) } else { None });
+                        }
+                    }
+                }
+            }
+        }
+        offsets
+    }
+}
+
+impl Layer for Conv2D {
+    fn input_size(&self) -> usize {
+        self.input_shape.iter().product()
+    }
+
+    fn output_size(&self) -> usize {
+        self.output_shape.iter().product()
+    }
+
+    fn output_shape(&self) -> Shape {
+        self.output_shape
+    }
+
+    fn forward(&mut self, inputs: &[f64], batch: usize, _training: bool) -> Vec<f64> {
+        let (patch_len, positions, filters) = (self.patch_len(), self.positions(), self.output_shape[0]);
+        let offsets = self.patch_offsets();
+
+        let mut patches = Vec::with_capacity(batch * offsets.len());
+        for sample in inputs.chunks_exact(self.input_size()) {
+            patches.extend(offsets.iter().map(|offset| offset.map_or(0.0, |i| sample[i])));
+        }
+
+        let mut pre_activations = vec![0.0; batch * filters * positions];
+        for (b, sample_patches) in patches.chunks_exact(positions * patch_len).enumerate() {
+            let output = &mut pre_activations[b * filters * positions..(b + 1) * filters * positions];
+            for (position, patch) in sample_patches.chunks_exact(patch_len).enumerate() {
+                for (f, weights) in self.weights.values.chunks_exact(patch_len).enumerate() {
+                    output[f * positions + position] = self.biases.values[f] + dot(weights, patch);
+                }
+            }
+        }
+
+        let mut outputs = pre_activations.clone();
+        self.activation.apply_rows(&mut outputs, filters * positions);
+
+        self.cached_patches = patches;
+        self.cached_pre_activations = pre_activations;
+        self.cached_outputs = outputs.clone();
+        outputs
+    }
+
+    fn backward(&mut self, output_gradient: &[f64], batch: usize) -> Vec<f64> {
+        let deltas = self.activation.backward_rows(&self.cached_pre_activations, &self.cached_outputs, output_gradient, self.output_size());
+        self.backward_pre_activation(&deltas, batch)
+    }
+
+    fn parameters(&mut self) -> Vec<&mut Parameter> {
+        vec![&mut self.weights, &mut self.biases]
+    }
+
+    fn kind(&self) -> LayerKind {
+        LayerKind::Conv2D
+    }
+
+    fn write_config(&self, writer: &mut ByteWriter) {
+        writer.write_u32(self.output_shape[0] as u32);
+        for values in [self.kernel, self.stride, self.padding, self.dilation] {
+            values.iter().for_each(|&v| writer.write_u32(v as u32));
+        }
+        writer.write_u32(self.activation as u32);
+    }
+
+    fn activation(&self) -> Option<ActivationFunction> {
+        Some(self.activation)
+    }
+
+    fn backward_pre_activation(&mut self, deltas: &[f64], batch: usize) -> Vec<f64> {
+        let (patch_len, positions, filters) = (self.patch_len(), self.positions(), self.output_shape[0]);
+        let input_size = self.input_size();
+        let offsets = self.patch_offsets();
+
+        self.weights.gradients.iter_mut().for_each(|g| *g = 0.0);
+        self.biases.gradients.iter_mut().for_each(|g| *g = 0.0);
+        let mut input_gradient = vec![0.0; batch * input_size];
+        let mut patch_gradient = vec![0.0; patch_len];
+
+        for b in 0..batch {
+            let sample_deltas = &deltas[b * filters * positions..(b + 1) * filters * positions];
+            let sample_gradient = &mut input_gradient[b * input_size..(b + 1) * input_size];
+
+            for position in 0..positions {
+                let patch_start = (b * positions + position) * patch_len;
+                let patch = &self.cached_patches[patch_start..patch_start + patch_len];
+                patch_gradient.iter_mut().for_each(|g| *g = 0.0);
+
+                for f in 0..filters {
+                    let delta = sample_deltas[f * positions + position];
+                    if delta == 0.0 {
+                        continue;
+                    }
+                    self.biases.gradients[f] += delta;
+                    let weights = &self.weights.values[f * patch_len..(f + 1) * patch_len];
+                    let weight_gradients = &mut self.weights.gradients[f * patch_len..(f + 1) * patch_len];
+                    for k in 0..patch_len {
+                        weight_gradients[k] += delta * patch[k];
+                        patch_gradient[k] += delta * weights[k];
+                    }
+                }
+
+                // Scatter the patch gradient back onto the input (col2im)
+                for (k, offset) in offsets[position * patch_len..(position + 1) * patch_len].iter().enumerate() {
+                    if let Some(i) = offset {
+                        sample_gradient[*i] += patch_gradient[k];
+                    }
+                }
+            }
+        }
+        input_gradient
+    }
+}
+
+/// Max or average pooling over non-padded windows of each channel
+pub(crate) struct Pool2D {
+    kind: PoolingType,
+    input_shape: Shape,
+    output_shape: Shape,
+    size: [usize; 2],
+    stride: [usize; 2],
+    /// Input offset of the maximum of every output value (max pooling)
+    cached_argmax: Vec<usize>,
+}
+
+impl Pool2D {
+    pub(crate) fn new(kind: PoolingType, input_shape: Shape, size: [usize; 2], stride: [usize; 2]) -> Result<Pool2D, String> {
+        let [channels, height, width] = input_shape;
+        match (sliding_length(height, size[0], stride[0], 0, 1), sliding_length(width, size[1], stride[1], 0, 1)) {
+            (Some(output_height), Some(output_width)) => Ok(Pool2D {
+                kind,
+                input_shape,
+                output_shape: [channels, output_height, output_width],
+                size,
+                stride,
+                cached_argmax: Vec::new(),
+            }),
+            _ => Err("Pooling window is larger than the input".to_string()),
+        }
+    }
+
+    /// Input offsets within a sample covered by the window of one output value
+    fn window(&self, c: usize, oy: usize, ox: usize) -> impl Iterator<Item = usize> + '_ {
+        let [_, height, width] = self.input_shape;
+        (0..self.size[0]).flat_map(move |ky| {
+            (0..self.size[1]).map(move |kx| (c * height + oy * self.stride[0] + ky) * width + ox * self.stride[1] + kx)
+        })
+    }
+}
+
+impl Layer for Pool2D {
+    fn input_size(&self) -> usize {
+        self.input_shape.iter().product()
+    }
+
+    fn output_size(&self) -> usize {
+        self.output_shape.iter().product()
+    }
+
+    fn output_shape(&self) -> Shape {
+        self.output_shape
+    }
+
+    fn forward(&mut self, inputs: &[f64], batch: usize, _training: bool) -> Vec<f64> {
+        let [channels, output_height, output_width] = self.output_shape;
+        let window_size = (self.size[0] * self.size[1]) as f64;
+        let mut outputs = Vec::with_capacity(batch * self.output_size());
+        let mut argmax = Vec::new();
+
+        for sample in inputs.chunks_exact(self.input_size()) {
+            for c in 0..channels {
+                for oy in 0..output_height {
+                    for ox in 0..output_width {
+                        match self.kind {
+                            PoolingType::Max => {
+                                let best = self.window(c, oy, ox).fold(usize::MAX, |best, i| {
+                                    if best == usize::MAX || sample[i] > sample[best] { i } else { best }
+                                });
+                                outputs.push(sample[best]);
+                                argmax.push(best);
+                            }
+                            PoolingType::Average => outputs.push(self.window(c, oy, ox).map(|i| sample[i]).sum::<f64>() / window_size),
+                        }
+                    }
+                }
+            }
+        }
+
+        self.cached_argmax = argmax;
+        outputs
+    }
+
+    fn backward(&mut self, output_gradient: &[f64], batch: usize) -> Vec<f64> {
+        let [channels, output_height, output_width] = self.output_shape;
+        let (input_size, output_size) = (self.input_size(), self.output_size());
+        let window_size = (self.size[0] * self.size[1]) as f64;
+        let mut input_gradient = vec![0.0; batch * input_size];
+
+        for b in 0..batch {
+            let sample_gradient = &mut input_gradient[b * input_size..(b + 1) * input_size];
+            let mut index = b * output_size;
+            for c in 0..channels {
+                for oy in 0..output_height {
+                    for ox in 0..output_width {
+                        let g = output_gradient[index];
+                        match self.kind {
+                            PoolingType::Max => sample_gradient[self.cached_argmax[index]] += g,
+                            PoolingType::Average => self.window(c, oy, ox).for_each(|i| sample_gradient[i] += g / window_size),
+                        }
+                        index += 1;
+                    }
+                }
+            }
+        }
+        input_gradient
+    }
+
+    fn parameters(&mut self) -> Vec<&mut Parameter> {
+        Vec::new()
+    }
+
+    fn kind(&self) -> LayerKind {
+        LayerKind::Pool2D
+    }
+
+    fn write_config(&self, writer: &mut ByteWriter) {
+        writer.write_u32(self.kind as u32);
+        for values in [self.size, self.stride] {
+            values.iter().for_each(|&v| writer.write_u32(v as u32));
+        }
+    }
+}
+
+/// Batch normalization per channel (per feature after a dense layer)
+///
+/// Training normalizes with the batch statistics and updates running estimates; inference
+/// uses the running estimates.
+pub(crate) struct BatchNorm {
+    shape: Shape,
+    momentum: f64,
+    epsilon: f64,
+    gamma: Parameter,
+    beta: Parameter,
+    pub(crate) running_mean: Vec<f64>,
+    pub(crate) running_variance: Vec<f64>,
+    cached_normalized: Vec<f64>,
+    cached_inv_std: Vec<f64>,
+    cached_training: bool,
+}
+
+impl BatchNorm {
+    /// `momentum` is the weight of each new batch in the running estimates
+    pub(crate) fn new(shape: Shape, momentum: f64, epsilon: f64) -> BatchNorm {
+        let channels = shape[0];
+        BatchNorm {
+            shape,
+            momentum,
+            epsilon,
+            gamma: Parameter::new(vec![1.0; channels]),
+            beta: Parameter::new(vec![0.0; channels]),
+            running_mean: vec![0.0; channels],
+            running_variance: vec![1.0; channels],
+            cached_normalized: Vec::new(),
+            cached_inv_std: Vec::new(),
+            cached_training: false,
+        }
+    }
+
+    fn spatial(&self) -> usize {
+        self.shape[1] * self.shape[2]
+    }
+
+    /// Values of one channel across the batch, as (sample, offset) ranges
+    fn channel_ranges(&self, batch: usize, c: usize) -> impl Iterator<Item = std::ops::Range<usize>> {
+        let (size, spatial) = (self.shape.iter().product::<usize>(), self.spatial());
+        (0..batch).map(move |b| b * size + c * spatial..b * size + (c + 1) * spatial)
+    }
+}
+
+impl Layer for BatchNorm {
+    fn input_size(&self) -> usize {
+        self.shape.iter().product()
+    }
+
+    fn output_size(&self) -> usize {
+        self.input_size()
+    }
+
+    fn output_shape(&self) -> Shape {
+        self.shape
+    }
+
+    fn forward(&mut self, inputs: &[f64], batch: usize, training: bool) -> Vec<f64> {
+        let count = (batch * self.spatial()) as f64;
+        let mut normalized = vec![0.0; inputs.len()];
+        let mut outputs = vec![0.0; inputs.len()];
+        let mut inv_stds = Vec::with_capacity(self.shape[0]);
+
+        for c in 0..self.shape[0] {
+            let (mean, variance) = if training {
+                let mean = self.channel_ranges(batch, c).flat_map(|r| inputs[r].iter()).sum::<f64>() / count;
+                let variance = self.channel_ranges(batch, c).flat_map(|r| inputs[r].iter()).map(|x| (x - mean) * (x - mean)).sum::<f64>() / count;
+
+                // Running variance uses the unbiased estimate
+                let unbiased = if count > 1.0 { variance * count / (count - 1.0) } else { variance };
+                self.running_mean[c] = (1.0 - self.momentum) * self.running_mean[c] + self.momentum * mean;
+                self.running_variance[c] = (1.0 - self.momentum) * self.running_variance[c] + self.momentum * unbiased;
+                (mean, variance)
+            } else {
+                (self.running_mean[c], self.running_variance[c])
+            };
+
+            let inv_std = 1.0 / (variance + self.epsilon).sqrt();
+            inv_stds.push(inv_std);
+            for range in self.channel_ranges(batch, c) {
+                for i in range {
+                    normalized[i] = (inputs[i] - mean) * inv_std;
+                    outputs[i] = self.gamma.values[c] * normalized[i] + self.beta.values[c];
+                }
+            }
+        }
+
+        self.cached_normalized = normalized;
+        self.cached_inv_std = inv_stds;
+        self.cached_training = training;
+        outputs
+    }
+
+    fn backward(&mut self, output_gradient: &[f64], batch: usize) -> Vec<f64> {
+        let count = (batch * self.spatial()) as f64;
+        let mut input_gradient = vec![0.0; output_gradient.len()];
+
+        for c in 0..self.shape[0] {
+            let (mut sum_gradient, mut sum_scaled) = (0.0, 0.0);
+            for i in self.channel_ranges(batch, c).flatten() {
+                sum_gradient += output_gradient[i];
+                sum_scaled += output_gradient[i] * self.cached_normalized[i];
+            }
+            self.gamma.gradients[c] = sum_scaled;
+            self.beta.gradients[c] = sum_gradient;
+
+            let scale = self.gamma.values[c] * self.cached_inv_std[c];
+            for i in self.channel_ranges(batch, c).flatten() {
+                input_gradient[i] = if self.cached_training {
+                    scale * (output_gradient[i] - sum_gradient / count - self.cached_normalized[i] * sum_scaled / count)
+                } else {
+                    scale * output_gradient[i]
+                };
+            }
+        }
+        input_gradient
+    }
+
+    fn parameters(&mut self) -> Vec<&mut Parameter> {
+        vec![&mut self.gamma, &mut self.beta]
+    }
+
+    fn kind(&self) -> LayerKind {
+        LayerKind::BatchNorm
+    }
+
+    fn write_config(&self, writer: &mut ByteWriter) {
+        writer.write_f64(self.momentum);
+        writer.write_f64(self.epsilon);
+    }
+
+    fn state(&mut self) -> Vec<&mut Vec<f64>> {
+        vec![&mut self.running_mean, &mut self.running_variance]
+    }
+}
+
+/// Layer normalization over all features of each sample, with a scale and shift per feature
+pub(crate) struct LayerNorm {
+    shape: Shape,
+    epsilon: f64,
+    gamma: Parameter,
+    beta: Parameter,
+    cached_normalized: Vec<f64>,
+    cached_inv_std: Vec<f64>,
+}
+
+impl LayerNorm {
+    pub(crate) fn new(shape: Shape, epsilon: f64) -> LayerNorm {
+        let size = shape.iter().product();
+        LayerNorm {
+            shape,
+            epsilon,
+            gamma: Parameter::new(vec![1.0; size]),
+            beta: Parameter::new(vec![0.0; size]),
+            cached_normalized: Vec::new(),
+            cached_inv_std: Vec::new(),
+        }
+    }
+}
+
+impl Layer for LayerNorm {
+    fn input_size(&self) -> usize {
+        self.gamma.values.len()
+    }
+
+    fn output_size(&self) -> usize {
+        self.input_size()
+    }
+
+    fn output_shape(&self) -> Shape {
+        self.shape
+    }
+
+    fn forward(&mut self, inputs: &[f64], batch: usize, _training: bool) -> Vec<f64> {
+        let size = self.input_size();
+        let mut normalized = Vec::with_capacity(inputs.len());
+        let mut inv_stds = Vec::with_capacity(batch);
+
+        for sample in inputs.chunks_exact(size) {
+            let mean = sample.iter().sum::<f64>() / size as f64;
+            let variance = sample.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / size as f64;
+            let inv_std = 1.0 / (variance + self.epsilon).sqrt();
+            normalized.extend(sample.iter().map(|x| (x - mean) * inv_std));
+            inv_stds.push(inv_std);
+        }
+
+        let outputs = normalized
+            .chunks_exact(size)
+            .flat_map(|sample| sample.iter().zip(self.gamma.values.iter().zip(&self.beta.values)).map(|(x, (g, b))| g * x + b))
+            .collect();
+
+        self.cached_normalized = normalized;
+        self.cached_inv_std = inv_stds;
+        outputs
+    }
+
+    fn backward(&mut self, output_gradient: &[f64], _batch: usize) -> Vec<f64> {
+        let size = self.input_size();
+        self.gamma.gradients.iter_mut().for_each(|g| *g = 0.0);
+        self.beta.gradients.iter_mut().for_each(|g| *g = 0.0);
+        let mut input_gradient = Vec::with_capacity(output_gradient.len());
+
+        for ((gradient, normalized), inv_std) in output_gradient.chunks_exact(size).zip(self.cached_normalized.chunks_exact(size)).zip(&self.cached_inv_std) {
+            let mut scaled = Vec::with_capacity(size);
+            for i in 0..size {
+                self.gamma.gradients[i] += gradient[i] * normalized[i];
+                self.beta.gradients[i] += gradient[i];
+                scaled.push(gradient[i] * self.gamma.values[i]);
+            }
+
+            let mean_scaled = scaled.iter().sum::<f64>() / size as f64;
+            let mean_projection = dot(&scaled, normalized) / size as f64;
+            input_gradient.extend((0..size).map(|i| inv_std * (scaled[i] - mean_scaled - normalized[i] * mean_projection)));
+        }
+        input_gradient
+    }
+
+    fn parameters(&mut self) -> Vec<&mut Parameter> {
+        vec![&mut self.gamma, &mut self.beta]
+    }
+
+    fn kind(&self) -> LayerKind {
+        LayerKind::LayerNorm
+    }
+
+    fn write_config(&self, writer: &mut ByteWriter) {
+        writer.write_f64(self.epsilon);
+    }
+}
+
+/// Inverted dropout: zeroes a fraction `rate` of values during training and rescales the
+/// rest, so inference is the identity
+pub(crate) struct Dropout {
+    shape: Shape,
+    rate: f64,
+    rng: Rng,
+    /// Per-value scale of the latest training pass, empty after an inference pass
+    cached_mask: Vec<f64>,
+}
+
+impl Dropout {
+    pub(crate) fn new(shape: Shape, rate: f64, rng: Rng) -> Dropout {
+        Dropout { shape, rate, rng, cached_mask: Vec::new() }
+    }
+}
+
+impl Layer for Dropout {
+    fn input_size(&self) -> usize {
+        self.shape.iter().product()
+    }
+
+    fn output_size(&self) -> usize {
+        self.input_size()
+    }
+
+    fn output_shape(&self) -> Shape {
+        self.shape
+    }
+
+    fn forward(&mut self, inputs: &[f64], _batch: usize, training: bool) -> Vec<f64> {
+        if !training || self.rate == 0.0 {
+            self.cached_mask.clear();
+            return inputs.to_vec();
+        }
+
+        let keep = 1.0 - self.rate;
+        self.cached_mask = (0..inputs.len()).map(|_| if self.rng.next_f64() < keep { 1.0 / keep } else { 0.0 }).collect();
+        inputs.iter().zip(&self.cached_mask).map(|(x, m)| x * m).collect()
+    }
+
+    fn backward(&mut self, output_gradient: &[f64], _batch: usize) -> Vec<f64> {
+        if self.cached_mask.is_empty() {
+            return output_gradient.to_vec();
+        }
+        output_gradient.iter().zip(&self.cached_mask).map(|(g, m)| g * m).collect()
+    }
+
+    fn parameters(&mut self) -> Vec<&mut Parameter> {
+        Vec::new()
+    }
+
+    fn kind(&self) -> LayerKind {
+        LayerKind::Dropout
+    }
+
+    fn write_config(&self, writer: &mut ByteWriter) {
+        writer.write_f64(self.rate);
+    }
+}
+
+/// Standalone activation, for activations that do not follow a dense or convolution layer
+pub(crate) struct Activation {
+    shape: Shape,
+    activation: ActivationFunction,
+    cached_inputs: Vec<f64>,
+    cached_outputs: Vec<f64>,
+}
+
+impl Activation {
+    pub(crate) fn new(shape: Shape, activation: ActivationFunction) -> Activation {
+        Activation { shape, activation, cached_inputs: Vec::new(), cached_outputs: Vec::new() }
+    }
+}
+
+impl Layer for Activation {
+    fn input_size(&self) -> usize {
+        self.shape.iter().product()
+    }
+
+    fn output_size(&self) -> usize {
+        self.input_size()
+    }
+
+    fn output_shape(&self) -> Shape {
+        self.shape
+    }
+
+    fn forward(&mut self, inputs: &[f64], _batch: usize, _training: bool) -> Vec<f64> {
+        let mut outputs = inputs.to_vec();
+        self.activation.apply_rows(&mut outputs, self.output_size());
+        self.cached_inputs = inputs.to_vec();
+        self.cached_outputs = outputs.clone();
+        outputs
+    }
+
+    fn backward(&mut self, output_gradient: &[f64], _batch: usize) -> Vec<f64> {
+        self.activation.backward_rows(&self.cached_inputs, &self.cached_outputs, output_gradient, self.output_size())
+    }
+
+    fn parameters(&mut self) -> Vec<&mut Parameter> {
+        Vec::new()
+    }
+
+    fn kind(&self) -> LayerKind {
+        LayerKind::Activation
+    }
+
+    fn write_config(&self, writer: &mut ByteWriter) {
+        writer.write_u32(self.activation as u32);
+    }
+
+    fn activation(&self) -> Option<ActivationFunction> {
+        Some(self.activation)
+    }
+
+    fn backward_pre_activation(&mut self, gradient: &[f64], _batch: usize) -> Vec<f64> {
+        gradient.to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normals(count: usize, seed: u64) -> Vec<f64> {
+        let mut rng = Rng::new(seed);
+        (0..count).map(|_| rng.next_normal()).collect()
+    }
+
+    fn assert_close(actual: &[f64], expected: &[f64], tolerance: f64) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < tolerance, "{:?} vs {:?}", actual, expected);
+        }
+    }
+
+    /// Check the input and parameter gradients of a training pass against central
+    /// differences of `dot(outputs, g)` for a random `g`
+    fn assert_layer_gradients(layer: &mut dyn Layer, batch: usize, seed: u64) {
+        let inputs = normals(batch * layer.input_size(), seed);
+        let g = normals(batch * layer.output_size(), seed + 1);
+        let h = 1e-6;
+
+        layer.forward(&inputs, batch, true);
+        let input_gradient = layer.backward(&g, batch);
+        let parameter_gradients: Vec<Vec<f64>> = layer.parameters().iter().map(|p| p.gradients.clone()).collect();
+
+        let objective = |layer: &mut dyn Layer, x: &[f64]| dot(&layer.forward(x, batch, true), &g);
+        for i in 0..inputs.len() {
+            let (mut above, mut below) = (inputs.clone(), inputs.clone());
+            above[i] += h;
+            below[i] -= h;
+            let numeric = (objective(layer, &above) - objective(layer, &below)) / (2.0 * h);
+            assert!((numeric - input_gradient[i]).abs() < 1e-5, "input {}: {} vs {}", i, numeric, input_gradient[i]);
+        }
+        for (p, gradients) in parameter_gradients.iter().enumerate() {
+            for (i, &expected) in gradients.iter().enumerate() {
+                layer.parameters()[p].values[i] += h;
+                let above = objective(layer, &inputs);
+                layer.parameters()[p].values[i] -= 2.0 * h;
+                let below = objective(layer, &inputs);
+                layer.parameters()[p].values[i] += h;
+                let numeric = (above - below) / (2.0 * h);
+                assert!((numeric - expected).abs() < 1e-5, "parameter {} [{}]: {} vs {}", p, i, numeric, expected);
+            }
+        }
+    }
+
+    /// Convolution with `[stride, padding, dilation]` given per axis
+    fn conv(
+        input_shape: Shape,
+        filters: usize,
+        kernel: [usize; 2],
+        steps: [[usize; 2]; 3],
+        activation: ActivationFunction,
+    ) -> Result<Conv2D, String> {
+        let [stride, padding, dilation] = steps;
+        let initializer = Initializer::HeNormal;
+        Conv2D::new(input_shape, filters, kernel, stride, padding, dilation, activation, initializer, &mut Rng::new(1))
+    }
+
+    #[test]
+    fn sliding_lengths() {
+        assert_eq!(sliding_length(5, 3, 1, 0, 1), Some(3));
+        assert_eq!(sliding_length(5, 3, 2, 1, 1), Some(3));
+        assert_eq!(sliding_length(7, 3, 1, 0, 2), Some(3));
+        assert_eq!(sliding_length(2, 3, 1, 0, 1), None);
+        assert_eq!(sliding_length(4, 3, 1, 0, usize::MAX), None);
+    }
+
+    #[test]
+    fn convolution_of_a_known_sequence() {
+        let inputs = [1.0, 2.0, 4.0, 8.0];
+        let run = |padding: usize, dilation: usize| {
+            let steps = [[1, 1], [0, padding], [1, dilation]];
+            let mut layer = conv([1, 1, 4], 1, [1, 2], steps, ActivationFunction::Linear).unwrap();
+            layer.weights.values = vec![1.0, -1.0];
+            layer.biases.values = vec![0.5];
+            layer.forward(&inputs, 1, false)
+        };
+        assert_eq!(run(0, 1), vec![-0.5, -1.5, -3.5]);
+        assert_eq!(run(1, 1), vec![-0.5, -0.5, -1.5, -3.5, 8.5]);
+        assert_eq!(run(0, 2), vec![-2.5, -5.5]);
+
+        assert!(conv([1, 1, 2], 1, [1, 3], [[1, 1], [0, 0], [1, 1]], ActivationFunction::Linear).is_err());
+    }
+
+    #[test]
+    fn convolution_gradients() {
+        let cases = [
+            ([2, 5, 4], 3, [3, 2], [[2, 1], [1, 0], [1, 2]], ActivationFunction::Linear),
+            ([2, 1, 9], 2, [1, 3], [[1, 2], [0, 1], [1, 1]], ActivationFunction::Linear),
+            ([1, 4, 4], 2, [2, 2], [[1, 1], [0, 0], [1, 1]], ActivationFunction::Tanh),
+        ];
+        for (seed, (input_shape, filters, kernel, steps, activation)) in cases.into_iter().enumerate() {
+            let mut layer = conv(input_shape, filters, kernel, steps, activation).unwrap();
+            assert_layer_gradients(&mut layer, 2, 10 * seed as u64);
+        }
+    }
+
+    #[test]
+    fn pooling_values_and_gradients() {
+        let inputs = [1.0, 5.0, 2.0, 0.0, 3.0, 4.0, 7.0, 6.0];
+        let mut max = Pool2D::new(PoolingType::Max, [1, 2, 4], [2, 2], [2, 2]).unwrap();
+        assert_eq!(max.forward(&inputs, 1, true), vec![5.0, 7.0]);
+        assert_eq!(max.backward(&[1.0, 2.0], 1), vec![0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 2.0, 0.0]);
+
+        let mut average = Pool2D::new(PoolingType::Average, [1, 2, 4], [1, 2], [1, 1]).unwrap();
+        assert_eq!(average.output_shape(), [1, 2, 3]);
+        assert_eq!(average.forward(&inputs, 1, true), vec![3.0, 3.5, 1.0, 3.5, 5.5, 6.5]);
+
+        assert!(Pool2D::new(PoolingType::Max, [1, 1, 4], [2, 2], [1, 1]).is_err());
+        for kind in [PoolingType::Max, PoolingType::Average] {
+            assert_layer_gradients(&mut Pool2D::new(kind, [2, 4, 5], [2, 3], [2, 1]).unwrap(), 2, 40);
+        }
+    }
+
+    #[test]
+    fn batch_norm_normalises_each_channel() {
+        let mut layer = BatchNorm::new([2, 1, 2], 0.5, 0.0);
+        let inputs = [1.0, 3.0, 10.0, 10.0, 5.0, 7.0, 20.0, 20.0];
+        let outputs = layer.forward(&inputs, 2, true);
+
+        // Channel 0 holds 1, 3, 5, 7 and channel 1 holds 10, 10, 20, 20
+        let s = 5.0f64.sqrt();
+        assert_close(&outputs, &[-3.0 / s, -1.0 / s, -1.0, -1.0, 1.0 / s, 3.0 / s, 1.0, 1.0], 1e-12);
+        assert_close(&layer.running_mean, &[2.0, 7.5], 1e-12);
+        assert_close(&layer.running_variance, &[0.5 + 0.5 * 20.0 / 3.0, 0.5 + 0.5 * 100.0 / 3.0], 1e-12);
+
+        // Inference uses the running estimates
+        let outputs = layer.forward(&[2.0, 2.0, 7.5, 7.5], 1, false);
+        assert_close(&outputs[2..], &[0.0, 0.0], 1e-12);
+
+        assert_layer_gradients(&mut BatchNorm::new([3, 2, 2], 0.1, 1e-5), 4, 50);
+    }
+
+    #[test]
+    fn batch_norm_inference_gradient_is_a_scale() {
+        let mut layer = BatchNorm::new([1, 1, 1], 0.1, 0.0);
+        layer.running_variance = vec![4.0];
+        layer.gamma.values = vec![3.0];
+        layer.forward(&[1.0, 2.0], 2, false);
+        assert_eq!(layer.backward(&[1.0, -2.0], 2), vec![1.5, -3.0]);
+    }
+
+    #[test]
+    fn layer_norm_normalises_each_sample() {
+        let mut layer = LayerNorm::new([4, 1, 1], 0.0);
+        let outputs = layer.forward(&[1.0, 2.0, 3.0, 4.0, -2.0, 0.0, 0.0, 2.0], 2, false);
+        for row in outputs.chunks_exact(4) {
+            let mean = row.iter().sum::<f64>() / 4.0;
+            let variance = row.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / 4.0;
+            assert!(mean.abs() < 1e-12 && (variance - 1.0).abs() < 1e-12);
+        }
+
+        let mut layer = LayerNorm::new([2, 1, 3], 1e-5);
+        layer.gamma.values = normals(6, 60);
+        assert_layer_gradients(&mut layer, 3, 61);
+    }
+
+    #[test]
+    fn dropout_scales_kept_values() {
+        let mut layer = Dropout::new([1000, 1, 1], 0.3, Rng::new(7));
+        let inputs = vec![2.0; 1000];
+        assert_eq!(layer.forward(&inputs, 1, false), inputs);
+
+        let outputs = layer.forward(&inputs, 1, true);
+        let dropped = outputs.iter().filter(|&&v| v == 0.0).count();
+        assert!((250..350).contains(&dropped), "{} dropped", dropped);
+        assert!(outputs.iter().all(|&v| v == 0.0 || (v - 2.0 / 0.7).abs() < 1e-12));
+
+        // The gradient follows the same mask
+        let gradient = layer.backward(&vec![1.0; 1000], 1);
+        assert!(gradient.iter().zip(&outputs).all(|(g, o)| (g * 2.0 - o).abs() < 1e-12));
+    }
+
+    #[test]
+    fn activation_layer_gradients() {
+        for activation in [ActivationFunction::Softmax, ActivationFunction::LogSoftmax, ActivationFunction::GELU] {
+            assert_layer_gradients(&mut Activation::new([2, 1, 3], activation), 2, 70);
+        }
+    }
+}
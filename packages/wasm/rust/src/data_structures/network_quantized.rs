@@ -0,0 +1,535 @@
+use wasm_bindgen::prelude::*;
+use js_sys::{Float64Array, Object, Reflect};
+
+#[cfg(feature = "simd")]
+use wide::{f32x8, i32x8};
+
+use super::machine_learning::read_rows;
+use super::network::{LayerKind, Network};
+use super::neural_network::ActivationFunction;
+
+/// Numeric formats for `QuantizedNetwork` inference
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum QuantizationMode {
+    /// int8 weights with per-channel scales and int8 activations with calibrated per-layer
+    /// scales, accumulated in int32; needs `calibrate` before inference
+    Int8,
+    /// int8 weights with per-channel scales, dequantized inside an f32 kernel
+    WeightOnlyInt8,
+    /// f32 weights and activations
+    Float32,
+}
+
+/// Weights of a quantized dense layer, `units x inputs` row-major
+enum Weights {
+    /// Symmetric int8 values with one scale per output unit
+    Int8 { values: Vec<i8>, scales: Vec<f32> },
+    Float32(Vec<f32>),
+}
+
+enum QuantizedLayer {
+    Dense {
+        inputs: usize,
+        units: usize,
+        weights: Weights,
+        biases: Vec<f32>,
+        activation: ActivationFunction,
+        /// Scale of the int8 inputs, set by calibration
+        input_scale: Option<f32>,
+    },
+    Activation(ActivationFunction),
+}
+
+/// Symmetric int8 quantization of one row: values and the scale that maps them back
+fn quantize_row(row: &[f64]) -> (Vec<i8>, f32) {
+    let max = row.iter().fold(0.0f64, |m, w| m.max(w.abs()));
+    let scale = if max > 0.0 { max / 127.0 } else { 1.0 };
+    (row.iter().map(|w| (w / scale).round().clamp(-127.0, 127.0) as i8).collect(), scale as f32)
+}
+
+/// Dot product of two int8 vectors with int32 accumulation
+fn dot_i8(a: &[i8], b: &[i8]) -> i32 {
+    let mut sum = 0i32;
+
+    #[cfg(feature = "simd")]
+    {
+        let simd_length = a.len() - (a.len() % 8);
+        let mut sum_vec = i32x8::splat(0);
+        for (x, y) in a[..simd_length].chunks_exact(8).zip(b[..simd_length].chunks_exact(8)) {
+            let x_vec = i32x8::from([x[0] as i32, x[1] as i32, x[2] as i32, x[3] as i32, x[4] as i32, x[5] as i32, x[6] as i32, x[7] as i32]);
+            let y_vec = i32x8::from([y[0] as i32, y[1] as i32, y[2] as i32, y[3] as i32, y[4] as i32, y[5] as i32, y[6] as i32, y[7] as i32]);
+            sum_vec += x_vec * y_vec;
+        }
+        sum += sum_vec.reduce_add();
+        for i in simd_length..a.len() {
+            sum += a[i] as i32 * b[i] as i32;
+        }
+    }
+
+    #[cfg(not(feature = "simd"))]
+    {
+        for (x, y) in a.iter().zip(b) {
+            sum += *x as i32 * *y as i32;
+        }
+    }
+
+    sum
+}
+
+/// Dot product of f32 activations with int8 weights, before the weight scale
+fn dot_f32_i8(a: &[f32], b: &[i8]) -> f32 {
+    let mut sum = 0.0f32;
+
+    #[cfg(feature = "simd")]
+    {
+        let simd_length = a.len() - (a.len() % 8);
+        let mut sum_vec = f32x8::splat(0.0);
+        for (x, y) in a[..simd_length].chunks_exact(8).zip(b[..simd_length].chunks_exact(8)) {
+            let x_vec = f32x8::from([x[0], x[1], x[2], x[3], x[4], x[5], x[6], x[7]]);
+            let y_vec = f32x8::from([y[0] as f32, y[1] as f32, y[2] as f32, y[3] as f32, y[4] as f32, y[5] as f32, y[6] as f32, y[7] as f32]);
+            sum_vec += x_vec * y_vec;
+        }
+        sum += sum_vec.reduce_add();
+        for i in simd_length..a.len() {
+            sum += a[i] * b[i] as f32;
+        }
+    }
+
+    #[cfg(not(feature = "simd"))]
+    {
+        for (x, y) in a.iter().zip(b) {
+            sum += x * *y as f32;
+        }
+    }
+
+    sum
+}
+
+/// Dot product of two f32 vectors
+fn dot_f32(a: &[f32], b: &[f32]) -> f32 {
+    let mut sum = 0.0f32;
+
+    #[cfg(feature = "simd")]
+    {
+        let simd_length = a.len() - (a.len() % 8);
+        let mut sum_vec = f32x8::splat(0.0);
+        for (x, y) in a[..simd_length].chunks_exact(8).zip(b[..simd_length].chunks_exact(8)) {
+            let x_vec = f32x8::from([x[0], x[1], x[2], x[3], x[4], x[5], x[6], x[7]]);
+            let y_vec = f32x8::from([y[0], y[1], y[2], y[3], y[4], y[5], y[6], y[7]]);
+            sum_vec += x_vec * y_vec;
+        }
+        sum += sum_vec.reduce_add();
+        for i in simd_length..a.len() {
+            sum += a[i] * b[i];
+        }
+    }
+
+    #[cfg(not(feature = "simd"))]
+    {
+        for (x, y) in a.iter().zip(b) {
+            sum += x * y;
+        }
+    }
+
+    sum
+}
+
+/// Apply an activation to f32 rows through the f64 implementation
+fn activate(values: &mut [f32], width: usize, activation: ActivationFunction) {
+    if activation == ActivationFunction::Linear {
+        return;
+    }
+    let mut wide: Vec<f64> = values.iter().map(|&v| v as f64).collect();
+    activation.apply_rows(&mut wide, width);
+    values.iter_mut().zip(&wide).for_each(|(v, w)| *v = *w as f32);
+}
+
+/// Maximum, mean and root mean square absolute differences between two row-major batches,
+/// and the fraction of rows whose predicted label agrees
+fn accuracy(quantized: &[f64], reference: &[f64], width: usize) -> (f64, f64, f64, f64) {
+    let differences: Vec<f64> = quantized.iter().zip(reference).map(|(q, r)| (q - r).abs()).collect();
+    let max_error = differences.iter().cloned().fold(0.0, f64::max);
+    let mean_error = differences.iter().sum::<f64>() / differences.len() as f64;
+    let rmse = (differences.iter().map(|d| d * d).sum::<f64>() / differences.len() as f64).sqrt();
+
+    let label = |row: &[f64]| -> usize {
+        if row.len() == 1 {
+            (row[0] >= 0.5) as usize
+        } else {
+            row.iter().enumerate().fold(0, |best, (i, v)| if *v > row[best] { i } else { best })
+        }
+    };
+    let agreeing = quantized
+        .chunks_exact(width)
+        .zip(reference.chunks_exact(width))
+        .filter(|(q, r)| label(q) == label(r))
+        .count();
+    let rows = quantized.len() / width;
+    (max_error, mean_error, rmse, agreeing as f64 / rows as f64)
+}
+
+/// Dense network with int8 or f32 weights for inference
+///
+/// Created from a trained `Network` of dense, activation and dropout layers. Int8 weights
+/// use one symmetric scale per output unit; in `Int8` mode the inputs of every dense layer
+/// are quantized with a per-layer scale found by `calibrate` over sample inputs.
+#[wasm_bindgen]
+pub struct QuantizedNetwork {
+    mode: QuantizationMode,
+    input_size: usize,
+    output_size: usize,
+    layers: Vec<QuantizedLayer>,
+}
+
+#[wasm_bindgen]
+impl QuantizedNetwork {
+    /// Quantize the weights of a trained network (default mode: `Int8`)
+    ///
+    /// Dropout is dropped since inference does not use it.
+    pub fn quantize(network: &mut Network, mode: Option<QuantizationMode>) -> Result<QuantizedNetwork, JsValue> {
+        let mode = mode.unwrap_or(QuantizationMode::Int8);
+        let (input_size, output_size) = (network.input_size(), network.output_size());
+
+        let mut layers = Vec::new();
+        for layer in network.layers_mut() {
+            match layer.kind() {
+                LayerKind::Dense => {
+                    let (inputs, units) = (layer.input_size(), layer.output_size());
+                    let activation = layer.activation().unwrap_or(ActivationFunction::Linear);
+                    let parameters = layer.parameters();
+                    let (weights, biases) = (&parameters[0].values, &parameters[1].values);
+
+                    let weights = match mode {
+                        QuantizationMode::Float32 => Weights::Float32(weights.iter().map(|&w| w as f32).collect()),
+                        _ => {
+                            let (rows, scales): (Vec<Vec<i8>>, Vec<f32>) = weights.chunks_exact(inputs).map(quantize_row).unzip();
+                            Weights::Int8 { values: rows.concat(), scales }
+                        }
+                    };
+                    let biases = biases.iter().map(|&b| b as f32).collect();
+                    layers.push(QuantizedLayer::Dense { inputs, units, weights, biases, activation, input_scale: None });
+                }
+                LayerKind::Activation => {
+                    layers.push(QuantizedLayer::Activation(layer.activation().unwrap_or(ActivationFunction::Linear)));
+                }
+                LayerKind::Dropout => {}
+                _ => return Err(JsValue::from_str("Only networks of dense, activation and dropout layers can be quantized")),
+            }
+        }
+
+        if layers.is_empty() {
+            return Err(JsValue::from_str("Network has no layers"));
+        }
+        Ok(QuantizedNetwork { mode, input_size, output_size, layers })
+    }
+
+    /// Set the int8 input scale of every dense layer from sample inputs
+    ///
+    /// Runs the samples through the network in f32 and maps the given percentile of the
+    /// absolute input values of each dense layer (default 100, the maximum) to 127.
+    /// Lower percentiles such as 99.99 clip rare outliers for finer resolution.
+    pub fn calibrate(&mut self, inputs: &JsValue, percentile: Option<f64>) -> Result<(), JsValue> {
+        let (inputs, rows) = read_rows(inputs, self.input_size)?;
+        self.calibrate_rows(&inputs, rows, percentile.unwrap_or(100.0)).map_err(|message| JsValue::from_str(&message))
+    }
+
+    /// Outputs for a batch of inputs, one row per sample
+    pub fn forward(&self, inputs: &JsValue) -> Result<Float64Array, JsValue> {
+        let (inputs, rows) = read_rows(inputs, self.input_size)?;
+        let outputs = self.forward_rows(&inputs, rows)?;
+        Ok(Float64Array::from(&outputs[..]))
+    }
+
+    /// Compare the outputs with those of the f64 network on the same inputs
+    ///
+    /// Returns the maximum and mean absolute differences, their root mean square, and the
+    /// fraction of samples whose predicted label agrees (the argmax for several outputs, the
+    /// side of 0.5 for a single output).
+    pub fn compare(&self, network: &mut Network, inputs: &JsValue) -> Result<JsValue, JsValue> {
+        if network.input_size() != self.input_size || network.output_size() != self.output_size {
+            return Err(JsValue::from_str("Network sizes do not match the quantized network"));
+        }
+        let (inputs, rows) = read_rows(inputs, self.input_size)?;
+        let quantized = self.forward_rows(&inputs, rows)?;
+        let reference = network.forward_rows(&inputs, rows, false)?;
+
+        let (max_error, mean_error, rmse, agreement) = accuracy(&quantized, &reference, self.output_size);
+
+        // Create result object
+        let result = Object::new();
+        Reflect::set(&result, &JsValue::from_str("max_error"), &JsValue::from_f64(max_error))?;
+        Reflect::set(&result, &JsValue::from_str("mean_error"), &JsValue::from_f64(mean_error))?;
+        Reflect::set(&result, &JsValue::from_str("rmse"), &JsValue::from_f64(rmse))?;
+        Reflect::set(&result, &JsValue::from_str("label_agreement"), &JsValue::from_f64(agreement))?;
+        Ok(result.into())
+    }
+
+    /// Inference mode
+    #[wasm_bindgen(getter)]
+    pub fn mode(&self) -> QuantizationMode {
+        self.mode
+    }
+
+    /// Whether every dense layer has an input scale, as `Int8` inference requires
+    #[wasm_bindgen(getter)]
+    pub fn calibrated(&self) -> bool {
+        self.layers.iter().all(|layer| !matches!(layer, QuantizedLayer::Dense { input_scale: None, .. }))
+    }
+
+    /// Number of input features
+    #[wasm_bindgen(getter)]
+    pub fn input_size(&self) -> usize {
+        self.input_size
+    }
+
+    /// Number of outputs
+    #[wasm_bindgen(getter)]
+    pub fn output_size(&self) -> usize {
+        self.output_size
+    }
+
+    /// Bytes taken by weights, scales and biases
+    #[wasm_bindgen(getter)]
+    pub fn size_bytes(&self) -> usize {
+        self.layers
+            .iter()
+            .map(|layer| match layer {
+                QuantizedLayer::Dense { weights: Weights::Int8 { values, scales }, biases, .. } => values.len() + 4 * (scales.len() + biases.len()),
+                QuantizedLayer::Dense { weights: Weights::Float32(values), biases, .. } => 4 * (values.len() + biases.len()),
+                QuantizedLayer::Activation(_) => 0,
+            })
+            .sum()
+    }
+}
+
+impl QuantizedNetwork {
+    /// Forward pass over a row-major batch
+    pub(crate) fn forward_rows(&self, inputs: &[f64], rows: usize) -> Result<Vec<f64>, String> {
+        if rows == 0 {
+            return Err("Inputs must contain at least one sample".to_string());
+        }
+        let int8 = self.mode == QuantizationMode::Int8;
+        if int8 && !self.calibrated() {
+            return Err("Int8 inference needs input scales; call calibrate first".to_string());
+        }
+
+        let mut values: Vec<f32> = inputs.iter().map(|&x| x as f32).collect();
+        for layer in &self.layers {
+            values = self.layer_forward(layer, &values, rows, int8);
+        }
+        Ok(values.iter().map(|&v| v as f64).collect())
+    }
+
+    /// Set the input scale of every dense layer from a row-major batch of samples
+    fn calibrate_rows(&mut self, inputs: &[f64], rows: usize, percentile: f64) -> Result<(), String> {
+        if !(percentile > 0.0 && percentile <= 100.0) {
+            return Err("Percentile must be in (0, 100]".to_string());
+        }
+        if rows == 0 {
+            return Err("Calibration needs at least one sample".to_string());
+        }
+
+        let mut values: Vec<f32> = inputs.iter().map(|&x| x as f32).collect();
+        let mut scales = Vec::new();
+        for layer in &self.layers {
+            if matches!(layer, QuantizedLayer::Dense { .. }) {
+                let mut magnitudes: Vec<f32> = values.iter().map(|v| v.abs()).collect();
+                let rank = ((percentile / 100.0 * magnitudes.len() as f64).ceil() as usize).clamp(1, magnitudes.len()) - 1;
+                let (_, range, _) = magnitudes.select_nth_unstable_by(rank, |a, b| a.total_cmp(b));
+                scales.push(if *range > 0.0 { *range / 127.0 } else { 1.0 });
+            }
+            values = self.layer_forward(layer, &values, rows, false);
+        }
+
+        let mut scales = scales.into_iter();
+        for layer in &mut self.layers {
+            if let QuantizedLayer::Dense { input_scale, .. } = layer {
+                *input_scale = scales.next();
+            }
+        }
+        Ok(())
+    }
+
+    /// One layer on f32 rows; `int8` quantizes the inputs of dense layers
+    fn layer_forward(&self, layer: &QuantizedLayer, inputs: &[f32], rows: usize, int8: bool) -> Vec<f32> {
+        match layer {
+            QuantizedLayer::Dense { inputs: width, units, weights, biases, activation, input_scale } => {
+                let mut outputs = Vec::with_capacity(rows * units);
+                match (weights, int8, input_scale) {
+                    (Weights::Int8 { values, scales }, true, Some(input_scale)) => {
+                        let mut quantized = vec![0i8; *width];
+                        for row in inputs.chunks_exact(*width) {
+                            for (q, x) in quantized.iter_mut().zip(row) {
+                                *q = (x / input_scale).round().clamp(-127.0, 127.0) as i8;
+                            }
+                            for ((weights, scale), bias) in values.chunks_exact(*width).zip(scales).zip(biases) {
+                                outputs.push(bias + dot_i8(&quantized, weights) as f32 * input_scale * scale);
+                            }
+                        }
+                    }
+                    (Weights::Int8 { values, scales }, _, _) => {
+                        for row in inputs.chunks_exact(*width) {
+                            for ((weights, scale), bias) in values.chunks_exact(*width).zip(scales).zip(biases) {
+                                outputs.push(bias + dot_f32_i8(row, weights) * scale);
+                            }
+                        }
+                    }
+                    (Weights::Float32(values), _, _) => {
+                        for row in inputs.chunks_exact(*width) {
+                            for (weights, bias) in values.chunks_exact(*width).zip(biases) {
+                                outputs.push(bias + dot_f32(row, weights));
+                            }
+                        }
+                    }
+                }
+                activate(&mut outputs, *units, *activation);
+                outputs
+            }
+            QuantizedLayer::Activation(activation) => {
+                let mut outputs = inputs.to_vec();
+                activate(&mut outputs, inputs.len() / rows, *activation);
+                outputs
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::random::Rng;
+
+    fn normals(count: usize, seed: u64) -> Vec<f64> {
+        let mut rng = Rng::new(seed);
+        (0..count).map(|_| rng.next_normal()).collect()
+    }
+
+    /// 6 inputs, 16 ReLU units, dropout, then 4 softmax outputs
+    fn classifier() -> Network {
+        let mut network = Network::new(6, None, None, Some(4)).unwrap();
+        network.add_dense(16, ActivationFunction::ReLU).unwrap();
+        network.add_dropout(0.5).unwrap();
+        network.add_dense(4, ActivationFunction::Softmax).unwrap();
+        network
+    }
+
+    #[test]
+    fn rows_quantize_symmetrically() {
+        let row = [0.5, -2.54, 1.0, 0.0];
+        let (values, scale) = quantize_row(&row);
+        assert!((scale as f64 - 0.02).abs() < 1e-9);
+        assert_eq!(values, vec![25, -127, 50, 0]);
+        for (q, w) in values.iter().zip(row) {
+            assert!((*q as f64 * scale as f64 - w).abs() <= scale as f64 / 2.0 + 1e-9);
+        }
+        assert_eq!(quantize_row(&[0.0; 3]), (vec![0; 3], 1.0));
+    }
+
+    #[test]
+    fn kernels_match_plain_dot_products() {
+        // 19 values cover both whole SIMD lanes and the remainder
+        let a: Vec<i8> = (0..19).map(|i| (i * 13 % 255 - 127) as i8).collect();
+        let b: Vec<i8> = (0..19).map(|i| (127 - i * 7) as i8).collect();
+        let x: Vec<f32> = normals(19, 1).iter().map(|&v| v as f32).collect();
+        let y: Vec<f32> = normals(19, 2).iter().map(|&v| v as f32).collect();
+
+        let expected: i32 = a.iter().zip(&b).map(|(a, b)| *a as i32 * *b as i32).sum();
+        assert_eq!(dot_i8(&a, &b), expected);
+
+        let expected: f32 = x.iter().zip(&b).map(|(x, b)| x * *b as f32).sum();
+        assert!((dot_f32_i8(&x, &b) - expected).abs() < 1e-3);
+
+        let expected: f32 = x.iter().zip(&y).map(|(x, y)| x * y).sum();
+        assert!((dot_f32(&x, &y) - expected).abs() < 1e-5);
+    }
+
+    #[test]
+    fn every_mode_tracks_the_f64_network() {
+        let mut network = classifier();
+        let inputs = normals(6 * 50, 3);
+        let reference = network.forward_rows(&inputs, 50, false).unwrap();
+
+        // Largest tolerated absolute difference of the softmax outputs per mode
+        let tolerances = [(QuantizationMode::Float32, 1e-6), (QuantizationMode::WeightOnlyInt8, 0.02), (QuantizationMode::Int8, 0.03)];
+        for (mode, tolerance) in tolerances {
+            let mut quantized = QuantizedNetwork::quantize(&mut network, Some(mode)).unwrap();
+            assert_eq!(quantized.layers.len(), 2, "dropout is left out");
+            quantized.calibrate_rows(&inputs, 50, 100.0).unwrap();
+
+            let outputs = quantized.forward_rows(&inputs, 50).unwrap();
+            let (max_error, mean_error, rmse, agreement) = accuracy(&outputs, &reference, 4);
+            assert!(max_error < tolerance, "{:?}: max error {}", mode, max_error);
+            assert!(mean_error <= rmse && rmse <= max_error);
+            assert!(agreement >= 0.9, "{:?}: label agreement {}", mode, agreement);
+        }
+    }
+
+    #[test]
+    fn int8_inference_needs_calibration() {
+        let mut network = classifier();
+        let mut quantized = QuantizedNetwork::quantize(&mut network, None).unwrap();
+        assert_eq!(quantized.mode(), QuantizationMode::Int8);
+        assert!(!quantized.calibrated());
+        let error = quantized.forward_rows(&[0.0; 6], 1).err().unwrap();
+        assert_eq!(error, "Int8 inference needs input scales; call calibrate first");
+
+        // Weight-only and f32 modes run without calibration
+        for mode in [QuantizationMode::WeightOnlyInt8, QuantizationMode::Float32] {
+            let quantized = QuantizedNetwork::quantize(&mut network, Some(mode)).unwrap();
+            assert!(quantized.forward_rows(&[0.0; 6], 1).is_ok());
+        }
+
+        assert_eq!(quantized.calibrate_rows(&[], 0, 100.0).err().unwrap(), "Calibration needs at least one sample");
+        for percentile in [0.0, 100.5, f64::NAN] {
+            let error = quantized.calibrate_rows(&[0.0; 6], 1, percentile).err().unwrap();
+            assert_eq!(error, "Percentile must be in (0, 100]");
+        }
+    }
+
+    #[test]
+    fn calibration_maps_the_percentile_to_127() {
+        let mut network = classifier();
+        let mut quantized = QuantizedNetwork::quantize(&mut network, None).unwrap();
+        let inputs: Vec<f64> = (1..=60).map(|i| i as f64 / 10.0).collect();
+
+        quantized.calibrate_rows(&inputs, 10, 100.0).unwrap();
+        assert!(quantized.calibrated());
+        let first_scale = |quantized: &QuantizedNetwork| match &quantized.layers[0] {
+            QuantizedLayer::Dense { input_scale, .. } => input_scale.unwrap(),
+            _ => panic!("expected a dense layer"),
+        };
+        assert!((first_scale(&quantized) - 6.0 / 127.0).abs() < 1e-7);
+
+        // The 50th percentile of 0.1..6.0 is 3.0
+        quantized.calibrate_rows(&inputs, 10, 50.0).unwrap();
+        assert!((first_scale(&quantized) - 3.0 / 127.0).abs() < 1e-7);
+    }
+
+    #[test]
+    fn int8_weights_take_a_quarter_of_the_space() {
+        let mut network = classifier();
+        let int8 = QuantizedNetwork::quantize(&mut network, Some(QuantizationMode::WeightOnlyInt8)).unwrap();
+        let float = QuantizedNetwork::quantize(&mut network, Some(QuantizationMode::Float32)).unwrap();
+
+        // 160 weights, 20 biases and, for int8, one scale per unit
+        assert_eq!(int8.size_bytes(), 160 + 4 * (20 + 20));
+        assert_eq!(float.size_bytes(), 4 * (160 + 20));
+        assert_eq!((int8.input_size(), int8.output_size()), (6, 4));
+    }
+
+    #[test]
+    fn accuracy_of_known_outputs() {
+        let reference = [0.1, 0.9, 0.6, 0.4];
+        let quantized = [0.2, 0.8, 0.4, 0.6];
+        let (max_error, mean_error, rmse, agreement) = accuracy(&quantized, &reference, 2);
+        assert!((max_error - 0.2).abs() < 1e-12);
+        assert!((mean_error - 0.15).abs() < 1e-12);
+        assert!((rmse - 0.025f64.sqrt()).abs() < 1e-12);
+        assert_eq!(agreement, 0.5);
+
+        // A single output is labelled by its side of 0.5
+        let (_, _, _, agreement) = accuracy(&[0.4, 0.7, 0.51], &[0.6, 0.9, 0.5], 1);
+        assert!((agreement - 2.0 / 3.0).abs() < 1e-12);
+    }
+}
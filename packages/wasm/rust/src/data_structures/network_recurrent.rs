@@ -0,0 +1,493 @@
+use wasm_bindgen::prelude::*;
+
+use super::network::{Layer, LayerKind, Parameter};
+use super::network_layers::Shape;
+use super::neural_network::{dot, Initializer};
+use super::random::Rng;
+use super::serialization::ByteWriter;
+
+/// Recurrent cells for `Network` sequence layers
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RecurrentCell {
+    /// Elman RNN with a tanh activation
+    SimpleRNN,
+    /// Gated recurrent unit (reset, update and candidate gates)
+    GRU,
+    /// Long short-term memory (input, forget, cell and output gates)
+    LSTM,
+}
+
+impl RecurrentCell {
+    /// Variant with the given discriminant, for deserialization
+    pub(crate) fn from_index(index: u32) -> Option<RecurrentCell> {
+        [RecurrentCell::SimpleRNN, RecurrentCell::GRU, RecurrentCell::LSTM].get(index as usize).copied()
+    }
+
+    pub(crate) fn gates(self) -> usize {
+        match self {
+            RecurrentCell::SimpleRNN => 1,
+            RecurrentCell::GRU => 3,
+            RecurrentCell::LSTM => 4,
+        }
+    }
+}
+
+fn sigmoid(x: f64) -> f64 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+/// `output[i] += matrix[i] . vector` for a row-major `output.len() x vector.len()` matrix
+fn multiply_add(output: &mut [f64], matrix: &[f64], vector: &[f64]) {
+    for (value, row) in output.iter_mut().zip(matrix.chunks_exact(vector.len())) {
+        *value += dot(row, vector);
+    }
+}
+
+/// `output += matrix^T . vector` and `gradient += vector (outer) input`, the two halves of
+/// backpropagating through `matrix . input`
+fn multiply_back(output: &mut [f64], gradient: &mut [f64], matrix: &[f64], vector: &[f64], input: &[f64]) {
+    let width = input.len();
+    for (i, &v) in vector.iter().enumerate() {
+        if v == 0.0 {
+            continue;
+        }
+        let row = &matrix[i * width..(i + 1) * width];
+        let row_gradient = &mut gradient[i * width..(i + 1) * width];
+        for k in 0..width {
+            output[k] += v * row[k];
+            row_gradient[k] += v * input[k];
+        }
+    }
+}
+
+/// Recurrent layer over channel-first `(features, 1, steps)` sequences
+///
+/// Gate blocks are stacked in the weights: input weights are `gates*units x features`,
+/// recurrent weights `gates*units x units`. Outputs are the hidden state of every step as
+/// `(units, 1, steps)`, or only the last one as `(units, 1, 1)`.
+pub(crate) struct Recurrent {
+    cell: RecurrentCell,
+    features: usize,
+    steps: usize,
+    units: usize,
+    return_sequences: bool,
+    /// Carry the final state of one batch into the next instead of starting from zero
+    stateful: bool,
+    /// Cut gradients flowing through the state every this many steps (0 for full BPTT)
+    truncate_steps: usize,
+    input_weights: Parameter,
+    recurrent_weights: Parameter,
+    biases: Parameter,
+    /// Hidden and cell state carried between batches when stateful
+    state_hidden: Vec<f64>,
+    state_cell: Vec<f64>,
+    cached_inputs: Vec<Vec<f64>>,
+    /// Hidden (and LSTM cell) states per step, starting with the initial state
+    cached_hidden: Vec<Vec<f64>>,
+    cached_cell: Vec<Vec<f64>>,
+    /// Activated gates per step
+    cached_gates: Vec<Vec<f64>>,
+}
+
+impl Recurrent {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        cell: RecurrentCell,
+        input_shape: Shape,
+        units: usize,
+        return_sequences: bool,
+        stateful: bool,
+        truncate_steps: usize,
+        initializer: Initializer,
+        rng: &mut Rng,
+    ) -> Recurrent {
+        let [features, _, steps] = input_shape;
+        let rows = cell.gates() * units;
+        let input_weights = initializer.sample(rows, features, features, units, rng);
+        // Recurrent weights stay LeCun normal unless orthogonal weights are asked for
+        let recurrent_initializer = if initializer == Initializer::Orthogonal { initializer } else { Initializer::LeCunNormal };
+        let recurrent_weights = recurrent_initializer.sample(rows, units, units, units, rng);
+
+        // Start LSTM forget gates open so early gradients flow through the cell state
+        let mut biases = vec![0.0; rows];
+        if cell == RecurrentCell::LSTM {
+            biases[units..2 * units].iter_mut().for_each(|b| *b = 1.0);
+        }
+
+        Recurrent {
+            cell,
+            features,
+            steps,
+            units,
+            return_sequences,
+            stateful,
+            truncate_steps,
+            input_weights: Parameter::new(input_weights),
+            recurrent_weights: Parameter::new(recurrent_weights),
+            biases: Parameter::new(biases),
+            state_hidden: Vec::new(),
+            state_cell: Vec::new(),
+            cached_inputs: Vec::new(),
+            cached_hidden: Vec::new(),
+            cached_cell: Vec::new(),
+            cached_gates: Vec::new(),
+        }
+    }
+
+    /// One step for one sample: returns the activated gates, the new hidden state and the
+    /// new cell state (LSTM only, empty otherwise)
+    fn step(&self, x: &[f64], h: &[f64], c: &[f64]) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+        let u = self.units;
+        let mut z = self.biases.values.clone();
+        multiply_add(&mut z, &self.input_weights.values, x);
+
+        match self.cell {
+            RecurrentCell::SimpleRNN => {
+                multiply_add(&mut z, &self.recurrent_weights.values, h);
+                z.iter_mut().for_each(|v| *v = v.tanh());
+                let h = z.clone();
+                (z, h, Vec::new())
+            }
+            RecurrentCell::GRU => {
+                // Reset and update gates see the previous state; the candidate sees it
+                // through the reset gate
+                multiply_add(&mut z[..2 * u], &self.recurrent_weights.values[..2 * u * u], h);
+                z[..2 * u].iter_mut().for_each(|v| *v = sigmoid(*v));
+                let reset_hidden: Vec<f64> = z[..u].iter().zip(h).map(|(r, h)| r * h).collect();
+                multiply_add(&mut z[2 * u..], &self.recurrent_weights.values[2 * u * u..], &reset_hidden);
+                z[2 * u..].iter_mut().for_each(|v| *v = v.tanh());
+
+                let h = (0..u).map(|j| (1.0 - z[u + j]) * z[2 * u + j] + z[u + j] * h[j]).collect();
+                (z, h, Vec::new())
+            }
+            RecurrentCell::LSTM => {
+                multiply_add(&mut z, &self.recurrent_weights.values, h);
+                for (k, v) in z.iter_mut().enumerate() {
+                    *v = if (2 * u..3 * u).contains(&k) { v.tanh() } else { sigmoid(*v) };
+                }
+                let c: Vec<f64> = (0..u).map(|j| z[u + j] * c[j] + z[j] * z[2 * u + j]).collect();
+                let h = (0..u).map(|j| z[3 * u + j] * c[j].tanh()).collect();
+                (z, h, c)
+            }
+        }
+    }
+}
+
+impl Layer for Recurrent {
+    fn input_size(&self) -> usize {
+        self.features * self.steps
+    }
+
+    fn output_size(&self) -> usize {
+        if self.return_sequences { self.units * self.steps } else { self.units }
+    }
+
+    fn output_shape(&self) -> Shape {
+        [self.units, 1, if self.return_sequences { self.steps } else { 1 }]
+    }
+
+    fn forward(&mut self, inputs: &[f64], batch: usize, _training: bool) -> Vec<f64> {
+        let (u, steps) = (self.units, self.steps);
+        let lstm = self.cell == RecurrentCell::LSTM;
+
+        // Gather the step-major inputs from the channel-first samples
+        let inputs_by_step: Vec<Vec<f64>> = (0..steps)
+            .map(|t| {
+                inputs
+                    .chunks_exact(self.input_size())
+                    .flat_map(|sample| (0..self.features).map(move |f| sample[f * steps + t]))
+                    .collect()
+            })
+            .collect();
+
+        let carried = self.stateful && self.state_hidden.len() == batch * u;
+        let mut hidden = vec![if carried { self.state_hidden.clone() } else { vec![0.0; batch * u] }];
+        let mut cells = vec![if carried && lstm { self.state_cell.clone() } else if lstm { vec![0.0; batch * u] } else { Vec::new() }];
+        let mut gates = Vec::with_capacity(steps);
+
+        for x in &inputs_by_step {
+            let (mut next_hidden, mut next_cell, mut step_gates) = (Vec::with_capacity(batch * u), Vec::new(), Vec::new());
+            for b in 0..batch {
+                let c = if lstm { &cells.last().unwrap()[b * u..(b + 1) * u] } else { &[][..] };
+                let (z, h, c) = self.step(&x[b * self.features..(b + 1) * self.features], &hidden.last().unwrap()[b * u..(b + 1) * u], c);
+                step_gates.extend(z);
+                next_hidden.extend(h);
+                next_cell.extend(c);
+            }
+            gates.push(step_gates);
+            hidden.push(next_hidden);
+            cells.push(next_cell);
+        }
+
+        if self.stateful {
+            self.state_hidden = hidden[steps].clone();
+            self.state_cell = cells[steps].clone();
+        }
+
+        let first = if self.return_sequences { 1 } else { steps };
+        let mut outputs = Vec::with_capacity(batch * self.output_size());
+        for b in 0..batch {
+            for j in 0..u {
+                outputs.extend((first..=steps).map(|t| hidden[t][b * u + j]));
+            }
+        }
+
+        self.cached_inputs = inputs_by_step;
+        self.cached_hidden = hidden;
+        self.cached_cell = cells;
+        self.cached_gates = gates;
+        outputs
+    }
+
+    fn backward(&mut self, output_gradient: &[f64], batch: usize) -> Vec<f64> {
+        let (u, f, steps) = (self.units, self.features, self.steps);
+        let rows = self.cell.gates() * u;
+
+        self.input_weights.gradients.iter_mut().for_each(|g| *g = 0.0);
+        self.recurrent_weights.gradients.iter_mut().for_each(|g| *g = 0.0);
+        self.biases.gradients.iter_mut().for_each(|g| *g = 0.0);
+        let mut input_gradient = vec![0.0; batch * f * steps];
+
+        // Gradients flowing back through the hidden and cell state
+        let mut hidden_gradient = vec![0.0; batch * u];
+        let mut cell_gradient = vec![0.0; batch * u];
+
+        for t in (0..steps).rev() {
+            let (x, gates) = (&self.cached_inputs[t], &self.cached_gates[t]);
+            let h_prev = &self.cached_hidden[t];
+            let mut next_hidden_gradient = vec![0.0; batch * u];
+            let mut next_cell_gradient = vec![0.0; batch * u];
+
+            for b in 0..batch {
+                let z = &gates[b * rows..(b + 1) * rows];
+                let h_prev = &h_prev[b * u..(b + 1) * u];
+                let x = &x[b * f..(b + 1) * f];
+
+                // Loss gradient reaching this step's hidden state
+                let mut dh = hidden_gradient[b * u..(b + 1) * u].to_vec();
+                if self.return_sequences || t == steps - 1 {
+                    let sample = &output_gradient[b * self.output_size()..(b + 1) * self.output_size()];
+                    for (j, value) in dh.iter_mut().enumerate() {
+                        *value += if self.return_sequences { sample[j * steps + t] } else { sample[j] };
+                    }
+                }
+
+                let dh_prev = &mut next_hidden_gradient[b * u..(b + 1) * u];
+                let mut dz = vec![0.0; rows];
+
+                match self.cell {
+                    RecurrentCell::SimpleRNN => {
+                        for j in 0..u {
+                            dz[j] = dh[j] * (1.0 - z[j] * z[j]);
+                        }
+                        multiply_back(dh_prev, &mut self.recurrent_weights.gradients, &self.recurrent_weights.values, &dz, h_prev);
+                    }
+                    RecurrentCell::GRU => {
+                        let reset_hidden: Vec<f64> = (0..u).map(|j| z[j] * h_prev[j]).collect();
+                        for j in 0..u {
+                            let (update, candidate) = (z[u + j], z[2 * u + j]);
+                            dz[2 * u + j] = dh[j] * (1.0 - update) * (1.0 - candidate * candidate);
+                            dz[u + j] = dh[j] * (h_prev[j] - candidate) * update * (1.0 - update);
+                            dh_prev[j] += dh[j] * update;
+                        }
+
+                        // The candidate sees the previous state through the reset gate
+                        let mut d_reset_hidden = vec![0.0; u];
+                        multiply_back(
+                            &mut d_reset_hidden,
+                            &mut self.recurrent_weights.gradients[2 * u * u..],
+                            &self.recurrent_weights.values[2 * u * u..],
+                            &dz[2 * u..],
+                            &reset_hidden,
+                        );
+                        for j in 0..u {
+                            dz[j] = d_reset_hidden[j] * h_prev[j] * z[j] * (1.0 - z[j]);
+                            dh_prev[j] += d_reset_hidden[j] * z[j];
+                        }
+                        multiply_back(dh_prev, &mut self.recurrent_weights.gradients[..2 * u * u], &self.recurrent_weights.values[..2 * u * u], &dz[..2 * u], h_prev);
+                    }
+                    RecurrentCell::LSTM => {
+                        let c = &self.cached_cell[t + 1][b * u..(b + 1) * u];
+                        let c_prev = &self.cached_cell[t][b * u..(b + 1) * u];
+                        let dc_prev = &mut next_cell_gradient[b * u..(b + 1) * u];
+                        for j in 0..u {
+                            let (input, forget, candidate, output) = (z[j], z[u + j], z[2 * u + j], z[3 * u + j]);
+                            let tanh_c = c[j].tanh();
+                            let dc = cell_gradient[b * u + j] + dh[j] * output * (1.0 - tanh_c * tanh_c);
+                            dz[j] = dc * candidate * input * (1.0 - input);
+                            dz[u + j] = dc * c_prev[j] * forget * (1.0 - forget);
+                            dz[2 * u + j] = dc * input * (1.0 - candidate * candidate);
+                            dz[3 * u + j] = dh[j] * tanh_c * output * (1.0 - output);
+                            dc_prev[j] = dc * forget;
+                        }
+                        multiply_back(dh_prev, &mut self.recurrent_weights.gradients, &self.recurrent_weights.values, &dz, h_prev);
+                    }
+                }
+
+                for (g, d) in self.biases.gradients.iter_mut().zip(&dz) {
+                    *g += d;
+                }
+                let mut dx = vec![0.0; f];
+                multiply_back(&mut dx, &mut self.input_weights.gradients, &self.input_weights.values, &dz, x);
+                let sample_gradient = &mut input_gradient[b * f * steps..(b + 1) * f * steps];
+                for (k, value) in dx.into_iter().enumerate() {
+                    sample_gradient[k * steps + t] = value;
+                }
+            }
+
+            // Truncated BPTT: stop the state gradient at every chunk boundary
+            if self.truncate_steps > 0 && t % self.truncate_steps == 0 {
+                next_hidden_gradient.iter_mut().for_each(|g| *g = 0.0);
+                next_cell_gradient.iter_mut().for_each(|g| *g = 0.0);
+            }
+            hidden_gradient = next_hidden_gradient;
+            cell_gradient = next_cell_gradient;
+        }
+        input_gradient
+    }
+
+    fn parameters(&mut self) -> Vec<&mut Parameter> {
+        vec![&mut self.input_weights, &mut self.recurrent_weights, &mut self.biases]
+    }
+
+    fn kind(&self) -> LayerKind {
+        LayerKind::Recurrent
+    }
+
+    fn write_config(&self, writer: &mut ByteWriter) {
+        writer.write_u32(self.cell as u32);
+        writer.write_u32(self.units as u32);
+        writer.write_u32(self.return_sequences as u32);
+        writer.write_u32(self.stateful as u32);
+        writer.write_u32(self.truncate_steps as u32);
+    }
+
+    fn reset_state(&mut self) {
+        self.state_hidden.clear();
+        self.state_cell.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CELLS: [RecurrentCell; 3] = [RecurrentCell::SimpleRNN, RecurrentCell::GRU, RecurrentCell::LSTM];
+
+    fn normals(count: usize, seed: u64) -> Vec<f64> {
+        let mut rng = Rng::new(seed);
+        (0..count).map(|_| rng.next_normal()).collect()
+    }
+
+    /// Three-unit layer over two features, with weights that depend only on the cell
+    fn layer(cell: RecurrentCell, steps: usize, return_sequences: bool, stateful: bool, truncate: usize) -> Recurrent {
+        let initializer = Initializer::XavierNormal;
+        Recurrent::new(cell, [2, 1, steps], 3, return_sequences, stateful, truncate, initializer, &mut Rng::new(5))
+    }
+
+    /// Check the input and parameter gradients against central differences of
+    /// `dot(outputs, g)` for a random `g`
+    fn assert_gradients_match(layer: &mut Recurrent, batch: usize, seed: u64) {
+        let inputs = normals(batch * layer.input_size(), seed);
+        let g = normals(batch * layer.output_size(), seed + 1);
+        let h = 1e-6;
+
+        layer.forward(&inputs, batch, true);
+        let input_gradient = layer.backward(&g, batch);
+        let parameter_gradients: Vec<Vec<f64>> = layer.parameters().iter().map(|p| p.gradients.clone()).collect();
+
+        let objective = |layer: &mut Recurrent, x: &[f64]| dot(&layer.forward(x, batch, true), &g);
+        for i in 0..inputs.len() {
+            let (mut above, mut below) = (inputs.clone(), inputs.clone());
+            above[i] += h;
+            below[i] -= h;
+            let numeric = (objective(layer, &above) - objective(layer, &below)) / (2.0 * h);
+            let expected = input_gradient[i];
+            assert!((numeric - expected).abs() < 1e-6, "{:?} input {}: {} vs {}", layer.cell, i, numeric, expected);
+        }
+        for (p, gradients) in parameter_gradients.iter().enumerate() {
+            for (i, &expected) in gradients.iter().enumerate() {
+                layer.parameters()[p].values[i] += h;
+                let above = objective(layer, &inputs);
+                layer.parameters()[p].values[i] -= 2.0 * h;
+                let below = objective(layer, &inputs);
+                layer.parameters()[p].values[i] += h;
+                let numeric = (above - below) / (2.0 * h);
+                let message = format!("{:?} parameter {} [{}]: {} vs {}", layer.cell, p, i, numeric, expected);
+                assert!((numeric - expected).abs() < 1e-6, "{}", message);
+            }
+        }
+    }
+
+    #[test]
+    fn simple_rnn_by_hand() {
+        let initializer = Initializer::XavierNormal;
+        let mut rnn = Recurrent::new(RecurrentCell::SimpleRNN, [1, 1, 2], 1, true, false, 0, initializer, &mut Rng::new(1));
+        rnn.input_weights.values = vec![0.5];
+        rnn.recurrent_weights.values = vec![-1.0];
+        rnn.biases.values = vec![0.1];
+
+        let h1 = (0.5f64 * 2.0 + 0.1).tanh();
+        let h2 = (-0.5 - h1 + 0.1).tanh();
+        let outputs = rnn.forward(&[2.0, -1.0], 1, false);
+        assert!((outputs[0] - h1).abs() < 1e-12 && (outputs[1] - h2).abs() < 1e-12, "{:?}", outputs);
+        assert_eq!(rnn.output_shape(), [1, 1, 2]);
+    }
+
+    #[test]
+    fn lstm_forget_gates_start_open() {
+        let lstm = layer(RecurrentCell::LSTM, 4, false, false, 0);
+        assert_eq!(lstm.biases.values, vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+        assert_eq!((lstm.input_weights.values.len(), lstm.recurrent_weights.values.len()), (24, 36));
+    }
+
+    #[test]
+    fn gradients_match_finite_differences() {
+        for cell in CELLS {
+            for return_sequences in [false, true] {
+                assert_gradients_match(&mut layer(cell, 4, return_sequences, false, 0), 2, 10);
+            }
+        }
+    }
+
+    #[test]
+    fn stateful_batches_continue_the_sequence() {
+        for cell in CELLS {
+            // Two features over six steps, channel-first
+            let sequence = normals(12, 20);
+            let half = |start: usize| -> Vec<f64> {
+                (0..2).flat_map(|f| sequence[f * 6 + start..f * 6 + start + 3].to_vec()).collect()
+            };
+
+            let whole = layer(cell, 6, true, false, 0).forward(&sequence, 1, false);
+            let mut stateful = layer(cell, 3, true, true, 0);
+            let (first, second) = (stateful.forward(&half(0), 1, false), stateful.forward(&half(3), 1, false));
+            for j in 0..3 {
+                let joined = first[j * 3..(j + 1) * 3].iter().chain(&second[j * 3..(j + 1) * 3]);
+                for (a, b) in joined.zip(&whole[j * 6..(j + 1) * 6]) {
+                    assert!((a - b).abs() < 1e-12, "{:?}", cell);
+                }
+            }
+
+            // Resetting starts over from a zero state
+            stateful.reset_state();
+            assert_eq!(stateful.forward(&half(0), 1, false), first);
+        }
+    }
+
+    #[test]
+    fn truncation_cuts_the_state_gradient() {
+        for cell in CELLS {
+            let mut truncated = layer(cell, 4, false, false, 1);
+            truncated.forward(&normals(8, 30), 1, true);
+            let gradient = truncated.backward(&[1.0, 1.0, 1.0], 1);
+
+            // Only the last step sees the loss when every step starts a new chunk
+            for f in 0..2 {
+                assert!(gradient[f * 4..f * 4 + 3].iter().all(|&g| g == 0.0), "{:?}: {:?}", cell, gradient);
+                assert!(gradient[f * 4 + 3] != 0.0);
+            }
+        }
+    }
+}
@@ -0,0 +1,547 @@
+use wasm_bindgen::prelude::*;
+use js_sys::{Float64Array, Function, Object, Reflect};
+
+use super::machine_learning::read_rows;
+use super::network::Network;
+
+/// Quantity watched for early stopping and best-weight checkpointing
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Monitor {
+    Loss,
+    Accuracy,
+    ValidationLoss,
+    ValidationAccuracy,
+}
+
+impl Monitor {
+    fn parse(name: &str) -> Option<Monitor> {
+        match name {
+            "loss" => Some(Monitor::Loss),
+            "accuracy" => Some(Monitor::Accuracy),
+            "val_loss" => Some(Monitor::ValidationLoss),
+            "val_accuracy" => Some(Monitor::ValidationAccuracy),
+            _ => None,
+        }
+    }
+
+    fn higher_is_better(self) -> bool {
+        matches!(self, Monitor::Accuracy | Monitor::ValidationAccuracy)
+    }
+
+    fn needs_validation(self) -> bool {
+        matches!(self, Monitor::ValidationLoss | Monitor::ValidationAccuracy)
+    }
+}
+
+/// Settings of `Network::fit`
+#[derive(Clone, Debug)]
+pub(crate) struct FitOptions {
+    pub(crate) epochs: usize,
+    pub(crate) batch_size: usize,
+    pub(crate) shuffle: bool,
+    /// Fraction of the samples, taken from the end, held out for validation
+    pub(crate) validation_split: f64,
+    pub(crate) monitor: Monitor,
+    /// Stop after this many epochs without improvement of the monitored value
+    pub(crate) patience: Option<usize>,
+    /// Smallest change of the monitored value that counts as an improvement
+    pub(crate) min_delta: f64,
+    /// Restore the weights of the best epoch when training ends
+    pub(crate) restore_best_weights: bool,
+}
+
+impl Default for FitOptions {
+    fn default() -> Self {
+        FitOptions {
+            epochs: 10,
+            batch_size: 32,
+            shuffle: true,
+            validation_split: 0.0,
+            monitor: Monitor::Loss,
+            patience: None,
+            min_delta: 0.0,
+            restore_best_weights: true,
+        }
+    }
+}
+
+/// Metrics of one epoch; accuracies are present for classification losses and the
+/// validation metrics when samples are held out
+#[derive(Clone, Debug)]
+pub(crate) struct EpochLog {
+    pub(crate) epoch: usize,
+    pub(crate) loss: f64,
+    pub(crate) accuracy: Option<f64>,
+    pub(crate) validation_loss: Option<f64>,
+    pub(crate) validation_accuracy: Option<f64>,
+    pub(crate) learning_rate: f64,
+}
+
+impl EpochLog {
+    fn get(&self, monitor: Monitor) -> Option<f64> {
+        match monitor {
+            Monitor::Loss => Some(self.loss),
+            Monitor::Accuracy => self.accuracy,
+            Monitor::ValidationLoss => self.validation_loss,
+            Monitor::ValidationAccuracy => self.validation_accuracy,
+        }
+    }
+
+    fn to_object(&self) -> Result<Object, JsValue> {
+        let log = Object::new();
+        Reflect::set(&log, &JsValue::from_str("epoch"), &JsValue::from_f64(self.epoch as f64))?;
+        Reflect::set(&log, &JsValue::from_str("loss"), &JsValue::from_f64(self.loss))?;
+        for (key, value) in [("accuracy", self.accuracy), ("val_loss", self.validation_loss), ("val_accuracy", self.validation_accuracy)] {
+            if let Some(value) = value {
+                Reflect::set(&log, &JsValue::from_str(key), &JsValue::from_f64(value))?;
+            }
+        }
+        Reflect::set(&log, &JsValue::from_str("learning_rate"), &JsValue::from_f64(self.learning_rate))?;
+        Ok(log)
+    }
+}
+
+/// Outcome of `Network::fit`
+#[derive(Clone, Debug)]
+pub(crate) struct FitHistory {
+    pub(crate) logs: Vec<EpochLog>,
+    /// Epoch with the best monitored value, if any epoch produced a number
+    pub(crate) best_epoch: Option<usize>,
+    pub(crate) stopped_early: bool,
+}
+
+#[wasm_bindgen]
+impl Network {
+    /// Train for several epochs of mini-batches
+    ///
+    /// Takes row-major inputs and targets with one row per sample and an optional options
+    /// object:
+    /// - `epochs` (default 10) and `batch_size` (default 32)
+    /// - `shuffle` (default true), which reshuffles the training samples every epoch with
+    ///   the network's seeded generator
+    /// - `validation_split` (default 0), the fraction of samples held out from the end
+    /// - `monitor`: `"loss"`, `"accuracy"`, `"val_loss"` or `"val_accuracy"` (default
+    ///   `"val_loss"` with a validation split, `"loss"` otherwise); accuracy needs a
+    ///   classification loss
+    /// - `patience` to stop after that many epochs in a row without improvement (0 stops
+    ///   at the first one), and `min_delta` (default 0), the change that counts as one
+    /// - `restore_best_weights` (default true) to end with the weights of the best epoch
+    /// - `on_epoch_end`, a function called after every epoch with the epoch's metrics;
+    ///   returning `false` stops training
+    ///
+    /// Returns the per-epoch `loss`, `accuracy`, `val_loss` and `val_accuracy` arrays (as
+    /// available), the number of `epochs` run, the `best_epoch` and `stopped_early`.
+    pub fn fit(&mut self, inputs: &JsValue, targets: &JsValue, options: &JsValue) -> Result<JsValue, JsValue> {
+        let (inputs, rows) = read_rows(inputs, self.input_size())?;
+        let (targets, target_rows) = read_rows(targets, self.output_size())?;
+        if target_rows != rows {
+            return Err(JsValue::from_str("Inputs and targets must have the same number of samples"));
+        }
+
+        let (options, callback) = read_options(options)?;
+        let history = self.fit_rows(&inputs, &targets, rows, &options, |log| match &callback {
+            Some(callback) => {
+                let log: JsValue = log.to_object()?.into();
+                Ok(callback.call1(&JsValue::NULL, &log)?.as_bool() != Some(false))
+            }
+            None => Ok(true),
+        })?;
+
+        // Create result object
+        type Metric = fn(&EpochLog) -> Option<f64>;
+        let result = Object::new();
+        let series = |metric: Metric| -> Option<Float64Array> {
+            let values: Option<Vec<f64>> = history.logs.iter().map(metric).collect();
+            values.filter(|values| !values.is_empty()).map(|values| Float64Array::from(&values[..]))
+        };
+        let metrics: [(&str, Metric); 4] = [
+            ("loss", |log| Some(log.loss)),
+            ("accuracy", |log| log.accuracy),
+            ("val_loss", |log| log.validation_loss),
+            ("val_accuracy", |log| log.validation_accuracy),
+        ];
+        for (key, metric) in metrics {
+            if let Some(values) = series(metric) {
+                Reflect::set(&result, &JsValue::from_str(key), &values)?;
+            }
+        }
+        Reflect::set(&result, &JsValue::from_str("epochs"), &JsValue::from_f64(history.logs.len() as f64))?;
+        let best_epoch = history.best_epoch.map_or(JsValue::NULL, |epoch| JsValue::from_f64(epoch as f64));
+        Reflect::set(&result, &JsValue::from_str("best_epoch"), &best_epoch)?;
+        Reflect::set(&result, &JsValue::from_str("stopped_early"), &JsValue::from_bool(history.stopped_early))?;
+        Ok(result.into())
+    }
+}
+
+impl Network {
+    /// Training loop behind `fit`; `on_epoch_end` returns whether to continue
+    pub(crate) fn fit_rows(
+        &mut self,
+        inputs: &[f64],
+        targets: &[f64],
+        rows: usize,
+        options: &FitOptions,
+        mut on_epoch_end: impl FnMut(&EpochLog) -> Result<bool, JsValue>,
+    ) -> Result<FitHistory, JsValue> {
+        let (input_width, output_width) = (self.input_size(), self.output_size());
+        let training_rows = self.training_rows(rows, options)?;
+        let validation_rows = rows - training_rows;
+        let classification = self.loss().is_classification();
+
+        let mut order: Vec<usize> = (0..training_rows).collect();
+        let (mut batch_inputs, mut batch_targets) = (Vec::new(), Vec::new());
+        let mut history = FitHistory { logs: Vec::new(), best_epoch: None, stopped_early: false };
+        let (mut best, mut checkpoint, mut waiting) = (None, None, 0);
+
+        for epoch in 0..options.epochs {
+            if options.shuffle {
+                self.rng().shuffle(&mut order);
+            }
+
+            let (mut loss_sum, mut correct, mut total) = (0.0, 0, 0);
+            for batch in order.chunks(options.batch_size) {
+                batch_inputs.clear();
+                batch_targets.clear();
+                for &row in batch {
+                    batch_inputs.extend_from_slice(&inputs[row * input_width..(row + 1) * input_width]);
+                    batch_targets.extend_from_slice(&targets[row * output_width..(row + 1) * output_width]);
+                }
+
+                let predictions = self.forward_rows(&batch_inputs, batch.len(), true)?;
+                let (loss, gradient, fused) = self.loss_gradient(&predictions, &batch_targets);
+                self.backward_rows(gradient, fused);
+                self.step();
+
+                loss_sum += loss * batch.len() as f64;
+                if classification {
+                    let (hits, count) = self.loss().hits(&predictions, &batch_targets, output_width);
+                    correct += hits;
+                    total += count;
+                }
+            }
+
+            let mut log = EpochLog {
+                epoch,
+                loss: loss_sum / training_rows as f64,
+                accuracy: classification.then(|| correct as f64 / total as f64),
+                validation_loss: None,
+                validation_accuracy: None,
+                learning_rate: self.current_learning_rate(),
+            };
+            if validation_rows > 0 {
+                let (loss, accuracy) = self.evaluate_rows(
+                    &inputs[training_rows * input_width..],
+                    &targets[training_rows * output_width..],
+                    validation_rows,
+                    options.batch_size,
+                )?;
+                log.validation_loss = Some(loss);
+                log.validation_accuracy = accuracy;
+            }
+
+            let keep_going = on_epoch_end(&log)?;
+
+            // NaN never counts as an improvement
+            let value = log.get(options.monitor).expect("monitored metric was checked up front");
+            let improved = !value.is_nan()
+                && best.is_none_or(|best: f64| {
+                    if options.monitor.higher_is_better() { value > best + options.min_delta } else { value < best - options.min_delta }
+                });
+            if improved {
+                best = Some(value);
+                history.best_epoch = Some(epoch);
+                waiting = 0;
+                if options.restore_best_weights {
+                    checkpoint = Some(self.snapshot());
+                }
+            } else {
+                waiting += 1;
+            }
+            history.logs.push(log);
+
+            if !keep_going {
+                break;
+            }
+            if !improved && options.patience.is_some_and(|patience| waiting >= patience) {
+                history.stopped_early = true;
+                break;
+            }
+        }
+
+        if let Some(checkpoint) = checkpoint {
+            self.restore(checkpoint);
+        }
+        Ok(history)
+    }
+
+    /// Number of samples the validation split leaves for training, after checking that the
+    /// options suit the samples and the loss
+    fn training_rows(&self, rows: usize, options: &FitOptions) -> Result<usize, String> {
+        let training_rows = (rows as f64 * (1.0 - options.validation_split)).floor() as usize;
+        let validation_rows = rows - training_rows;
+        if training_rows == 0 {
+            return Err("Validation split leaves no training samples".to_string());
+        }
+        if options.validation_split > 0.0 && validation_rows == 0 {
+            return Err("Validation split leaves no validation samples".to_string());
+        }
+        if options.monitor.needs_validation() && validation_rows == 0 {
+            return Err("Monitoring a validation metric needs a validation split".to_string());
+        }
+        if options.monitor.higher_is_better() && !self.loss().is_classification() {
+            return Err("Accuracy needs a classification loss".to_string());
+        }
+        Ok(training_rows)
+    }
+
+    /// Mean loss and accuracy (for classification losses) in inference mode, in batches
+    fn evaluate_rows(&mut self, inputs: &[f64], targets: &[f64], rows: usize, batch_size: usize) -> Result<(f64, Option<f64>), String> {
+        let (input_width, output_width) = (self.input_size(), self.output_size());
+        let classification = self.loss().is_classification();
+        let (mut loss_sum, mut correct, mut total) = (0.0, 0, 0);
+
+        for start in (0..rows).step_by(batch_size) {
+            let end = (start + batch_size).min(rows);
+            let batch_targets = &targets[start * output_width..end * output_width];
+            let predictions = self.forward_rows(&inputs[start * input_width..end * input_width], end - start, false)?;
+            loss_sum += self.loss_gradient(&predictions, batch_targets).0 * (end - start) as f64;
+            if classification {
+                let (hits, count) = self.loss().hits(&predictions, batch_targets, output_width);
+                correct += hits;
+                total += count;
+            }
+        }
+        Ok((loss_sum / rows as f64, classification.then(|| correct as f64 / total as f64)))
+    }
+
+    /// Copy of every parameter and state buffer
+    fn snapshot(&mut self) -> Vec<Vec<f64>> {
+        let mut buffers = Vec::new();
+        self.for_each_buffer(|buffer| {
+            buffers.push(buffer.clone());
+            Ok(())
+        })
+        .expect("copying buffers cannot fail");
+        buffers
+    }
+
+    /// Put back the buffers of a `snapshot`
+    fn restore(&mut self, buffers: Vec<Vec<f64>>) {
+        let mut buffers = buffers.into_iter();
+        self.for_each_buffer(|buffer| {
+            *buffer = buffers.next().expect("snapshot of the same network");
+            Ok(())
+        })
+        .expect("restoring buffers cannot fail");
+    }
+}
+
+/// Read the options object of `fit`, returning the settings and the epoch callback
+fn read_options(options: &JsValue) -> Result<(FitOptions, Option<Function>), JsValue> {
+    let mut settings = FitOptions::default();
+    if options.is_undefined() || options.is_null() {
+        return Ok((settings, None));
+    }
+    if !options.is_object() {
+        return Err(JsValue::from_str("Options must be an object"));
+    }
+
+    let get = |key: &str| -> Result<Option<JsValue>, JsValue> {
+        let value = Reflect::get(options, &JsValue::from_str(key))?;
+        Ok(if value.is_undefined() || value.is_null() { None } else { Some(value) })
+    };
+    let number = |key: &str| -> Result<Option<f64>, JsValue> {
+        get(key)?
+            .map(|value| value.as_f64().ok_or_else(|| JsValue::from_str(&format!("Option {} must be a number", key))))
+            .transpose()
+    };
+    let count = |key: &str, minimum: usize| -> Result<Option<usize>, JsValue> {
+        match number(key)? {
+            Some(value) if value.fract() == 0.0 && value >= minimum as f64 => Ok(Some(value as usize)),
+            Some(_) => Err(JsValue::from_str(&format!("Option {} must be an integer of at least {}", key, minimum))),
+            None => Ok(None),
+        }
+    };
+    let flag = |key: &str| -> Result<Option<bool>, JsValue> {
+        get(key)?
+            .map(|value| value.as_bool().ok_or_else(|| JsValue::from_str(&format!("Option {} must be a boolean", key))))
+            .transpose()
+    };
+
+    settings.epochs = count("epochs", 1)?.unwrap_or(settings.epochs);
+    settings.batch_size = count("batch_size", 1)?.unwrap_or(settings.batch_size);
+    settings.shuffle = flag("shuffle")?.unwrap_or(settings.shuffle);
+    settings.validation_split = number("validation_split")?.unwrap_or(0.0);
+    if !(0.0..1.0).contains(&settings.validation_split) {
+        return Err(JsValue::from_str("Option validation_split must be in [0, 1)"));
+    }
+    settings.monitor = match get("monitor")? {
+        Some(name) => name
+            .as_string()
+            .and_then(|name| Monitor::parse(&name))
+            .ok_or_else(|| JsValue::from_str("Option monitor must be loss, accuracy, val_loss or val_accuracy"))?,
+        None if settings.validation_split > 0.0 => Monitor::ValidationLoss,
+        None => Monitor::Loss,
+    };
+    settings.patience = count("patience", 0)?;
+    settings.min_delta = number("min_delta")?.unwrap_or(0.0);
+    if !(settings.min_delta >= 0.0 && settings.min_delta.is_finite()) {
+        return Err(JsValue::from_str("Option min_delta must be a non-negative finite number"));
+    }
+    settings.restore_best_weights = flag("restore_best_weights")?.unwrap_or(settings.restore_best_weights);
+
+    let callback = get("on_epoch_end")?
+        .map(|value| value.dyn_into::<Function>().map_err(|_| JsValue::from_str("Option on_epoch_end must be a function")))
+        .transpose()?;
+    Ok((settings, callback))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::neural_network::{ActivationFunction, LossFunction};
+    use crate::data_structures::random::Rng;
+
+    /// Two Gaussian blobs in 2D with one-hot targets
+    fn blobs(rows: usize, seed: u64) -> (Vec<f64>, Vec<f64>) {
+        let mut rng = Rng::new(seed);
+        let (mut inputs, mut targets) = (Vec::new(), Vec::new());
+        for row in 0..rows {
+            let class = row % 2;
+            let center = if class == 0 { -1.0 } else { 1.0 };
+            inputs.extend([center + 0.5 * rng.next_normal(), center + 0.5 * rng.next_normal()]);
+            targets.extend(if class == 0 { [1.0, 0.0] } else { [0.0, 1.0] });
+        }
+        (inputs, targets)
+    }
+
+    fn classifier(seed: u32) -> Network {
+        let mut network = Network::new(2, Some(LossFunction::CategoricalCrossEntropy), Some(0.1), Some(seed)).unwrap();
+        network.add_dense(8, ActivationFunction::Tanh).unwrap();
+        network.add_dense(2, ActivationFunction::Softmax).unwrap();
+        network
+    }
+
+    fn regressor() -> Network {
+        let mut network = Network::new(2, None, Some(0.05), Some(1)).unwrap();
+        network.add_dense(1, ActivationFunction::Linear).unwrap();
+        network
+    }
+
+    fn options(epochs: usize) -> FitOptions {
+        FitOptions { epochs, batch_size: 8, restore_best_weights: false, ..FitOptions::default() }
+    }
+
+    #[test]
+    fn monitor_names() {
+        assert_eq!(Monitor::parse("val_accuracy"), Some(Monitor::ValidationAccuracy));
+        assert_eq!(Monitor::parse("loss"), Some(Monitor::Loss));
+        assert_eq!(Monitor::parse("mse"), None);
+        assert!(Monitor::Accuracy.higher_is_better() && !Monitor::ValidationLoss.higher_is_better());
+        assert!(Monitor::ValidationLoss.needs_validation() && !Monitor::Accuracy.needs_validation());
+    }
+
+    #[test]
+    fn training_improves_loss_and_accuracy() {
+        let (inputs, targets) = blobs(64, 1);
+        let mut network = classifier(1);
+        let history = network.fit_rows(&inputs, &targets, 64, &options(20), |_| Ok(true)).unwrap();
+
+        assert_eq!(history.logs.len(), 20);
+        assert!(!history.stopped_early);
+        let (first, last) = (&history.logs[0], &history.logs[19]);
+        assert!(last.loss < first.loss, "{} vs {}", last.loss, first.loss);
+        assert!(last.accuracy.unwrap() > 0.9);
+        assert!(last.validation_loss.is_none());
+        assert_eq!(last.learning_rate, 0.1);
+        assert!(history.logs.iter().enumerate().all(|(i, log)| log.epoch == i));
+
+        // Regression losses have no accuracy
+        let mut network = regressor();
+        let targets: Vec<f64> = inputs.chunks_exact(2).map(|x| x[0] - 2.0 * x[1]).collect();
+        let history = network.fit_rows(&inputs, &targets, 64, &options(3), |_| Ok(true)).unwrap();
+        assert!(history.logs.iter().all(|log| log.accuracy.is_none()));
+    }
+
+    #[test]
+    fn validation_uses_the_last_samples() {
+        let (inputs, targets) = blobs(40, 2);
+        let mut network = classifier(2);
+        let settings = FitOptions { validation_split: 0.25, monitor: Monitor::ValidationLoss, ..options(1) };
+        let history = network.fit_rows(&inputs, &targets, 40, &settings, |_| Ok(true)).unwrap();
+
+        // 30 training samples, 10 held out; the log matches an evaluation of those 10
+        let (loss, accuracy) = network.evaluate_rows(&inputs[60..], &targets[60..], 10, 3).unwrap();
+        let log = &history.logs[0];
+        assert!((log.validation_loss.unwrap() - loss).abs() < 1e-12);
+        assert_eq!(log.validation_accuracy, accuracy);
+        assert_eq!(network.training_rows(40, &settings), Ok(30));
+    }
+
+    #[test]
+    fn early_stopping_restores_the_best_weights() {
+        let (inputs, targets) = blobs(32, 3);
+        let probe = [0.3, -0.2, -1.0, 1.5];
+
+        // A min_delta no epoch can beat leaves the first epoch as the best one
+        let settings = FitOptions { patience: Some(2), min_delta: 1e9, restore_best_weights: true, ..options(10) };
+        let mut stopped = classifier(4);
+        let history = stopped.fit_rows(&inputs, &targets, 32, &settings, |_| Ok(true)).unwrap();
+        assert_eq!(history.logs.len(), 3);
+        assert!(history.stopped_early);
+        assert_eq!(history.best_epoch, Some(0));
+
+        let mut single_epoch = classifier(4);
+        single_epoch.fit_rows(&inputs, &targets, 32, &options(1), |_| Ok(true)).unwrap();
+        let restored = stopped.forward_rows(&probe, 2, false).unwrap();
+        assert_eq!(restored, single_epoch.forward_rows(&probe, 2, false).unwrap());
+
+        // Patience 0 stops at the first epoch without improvement
+        let settings = FitOptions { patience: Some(0), ..settings };
+        let history = classifier(4).fit_rows(&inputs, &targets, 32, &settings, |_| Ok(true)).unwrap();
+        assert_eq!(history.logs.len(), 2);
+    }
+
+    #[test]
+    fn best_epoch_tracks_the_monitored_metric() {
+        let (inputs, targets) = blobs(32, 5);
+        let mut network = classifier(5);
+        let history = network.fit_rows(&inputs, &targets, 32, &options(8), |_| Ok(true)).unwrap();
+
+        let best = history.logs.iter().min_by(|a, b| a.loss.total_cmp(&b.loss)).unwrap().epoch;
+        assert_eq!(history.best_epoch, Some(best));
+    }
+
+    #[test]
+    fn callback_can_stop_training() {
+        let (inputs, targets) = blobs(16, 6);
+        let mut seen = Vec::new();
+        let history = classifier(6)
+            .fit_rows(&inputs, &targets, 16, &options(10), |log| {
+                seen.push(log.epoch);
+                Ok(log.epoch < 2)
+            })
+            .unwrap();
+        assert_eq!(seen, vec![0, 1, 2]);
+        assert_eq!(history.logs.len(), 3);
+        assert!(!history.stopped_early);
+    }
+
+    #[test]
+    fn options_are_checked_against_the_samples() {
+        let network = classifier(7);
+        let split = |validation_split: f64| FitOptions { validation_split, ..FitOptions::default() };
+        assert_eq!(network.training_rows(10, &split(0.0)), Ok(10));
+        let error = network.training_rows(10, &split(0.95)).err().unwrap();
+        assert_eq!(error, "Validation split leaves no training samples");
+        let error = network.training_rows(10, &split(1e-17)).err().unwrap();
+        assert_eq!(error, "Validation split leaves no validation samples");
+
+        let monitor = |monitor: Monitor| FitOptions { monitor, ..FitOptions::default() };
+        let error = network.training_rows(10, &monitor(Monitor::ValidationAccuracy)).err().unwrap();
+        assert_eq!(error, "Monitoring a validation metric needs a validation split");
+        assert_eq!(network.training_rows(10, &monitor(Monitor::Accuracy)), Ok(10));
+        let error = regressor().training_rows(10, &monitor(Monitor::Accuracy)).err().unwrap();
+        assert_eq!(error, "Accuracy needs a classification loss");
+    }
+}
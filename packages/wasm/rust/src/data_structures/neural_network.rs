@@ -2,16 +2,419 @@ use wasm_bindgen::prelude::*;
 use js_sys::{Array, Float64Array, Object, Reflect};
 use bumpalo::Bump;
 
+use super::distributions::normal_cdf;
+use super::random::Rng;
+
 #[cfg(feature = "simd")]
 use wide::{f64x4, CmpLt};
 
 /// Activation functions for neural networks
 #[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ActivationFunction {
     Sigmoid,
     ReLU,
     Tanh,
     LeakyReLU,
+    /// Softmax over each output row
+    Softmax,
+    /// Log of the softmax over each output row
+    LogSoftmax,
+    /// Gaussian error linear unit, x * Phi(x)
+    GELU,
+    /// Exponential linear unit with alpha 1
+    ELU,
+    /// Scaled exponential linear unit
+    SELU,
+    /// x * sigmoid(x)
+    Swish,
+    /// Identity
+    Linear,
+}
+
+/// SELU scale and alpha (Klambauer et al., 2017)
+const SELU_SCALE: f64 = 1.050_700_987_355_480_5;
+const SELU_ALPHA: f64 = 1.673_263_242_354_377_3;
+
+impl ActivationFunction {
+    /// Variant with the given discriminant, for deserialization
+    pub(crate) fn from_index(index: u32) -> Option<ActivationFunction> {
+        use ActivationFunction::*;
+        const ALL: [ActivationFunction; 11] = [Sigmoid, ReLU, Tanh, LeakyReLU, Softmax, LogSoftmax, GELU, ELU, SELU, Swish, Linear];
+        ALL.get(index as usize).copied()
+    }
+
+    /// Whether the activation couples the outputs of a row rather than acting elementwise
+    pub(crate) fn is_row_wise(self) -> bool {
+        matches!(self, ActivationFunction::Softmax | ActivationFunction::LogSoftmax)
+    }
+
+    /// Activation of a single pre-activation value
+    ///
+    /// A row-wise activation treats the value as a row of its own.
+    fn apply(self, x: f64) -> f64 {
+        match self {
+            ActivationFunction::Sigmoid => 1.0 / (1.0 + (-x).exp()),
+            ActivationFunction::ReLU => if x > 0.0 { x } else { 0.0 },
+            ActivationFunction::Tanh => x.tanh(),
+            ActivationFunction::LeakyReLU => if x > 0.0 { x } else { 0.01 * x },
+            ActivationFunction::GELU => x * normal_cdf(x),
+            ActivationFunction::ELU => if x > 0.0 { x } else { x.exp_m1() },
+            ActivationFunction::SELU => SELU_SCALE * if x > 0.0 { x } else { SELU_ALPHA * x.exp_m1() },
+            ActivationFunction::Swish => x / (1.0 + (-x).exp()),
+            ActivationFunction::Linear => x,
+            ActivationFunction::Softmax | ActivationFunction::LogSoftmax => {
+                let mut row = [x];
+                self.apply_rows(&mut row, 1);
+                row[0]
+            }
+        }
+    }
+
+    /// Derivative of the activation, given both the pre-activation and the activated value
+    ///
+    /// A row-wise activation treats the value as a row of its own.
+    fn derivative(self, x: f64, y: f64) -> f64 {
+        match self {
+            ActivationFunction::Sigmoid => y * (1.0 - y),
+            ActivationFunction::ReLU => if x > 0.0 { 1.0 } else { 0.0 },
+            ActivationFunction::Tanh => 1.0 - y * y,
+            ActivationFunction::LeakyReLU => if x > 0.0 { 1.0 } else { 0.01 },
+            ActivationFunction::GELU => normal_cdf(x) + x * (-0.5 * x * x).exp() / (2.0 * std::f64::consts::PI).sqrt(),
+            ActivationFunction::ELU => if x > 0.0 { 1.0 } else { y + 1.0 },
+            ActivationFunction::SELU => if x > 0.0 { SELU_SCALE } else { y + SELU_SCALE * SELU_ALPHA },
+            ActivationFunction::Swish => {
+                let sigmoid = 1.0 / (1.0 + (-x).exp());
+                y + sigmoid * (1.0 - y)
+            }
+            ActivationFunction::Linear => 1.0,
+            ActivationFunction::Softmax | ActivationFunction::LogSoftmax => self.backward_rows(&[x], &[y], &[1.0], 1)[0],
+        }
+    }
+
+    /// Apply the activation in place to row-major values with `width` columns
+    pub(crate) fn apply_rows(self, values: &mut [f64], width: usize) {
+        if !self.is_row_wise() {
+            values.iter_mut().for_each(|x| *x = self.apply(*x));
+            return;
+        }
+
+        // Shift by the row maximum before exponentiating to avoid overflow
+        for row in values.chunks_exact_mut(width) {
+            let max = row.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+            let log_total = max + row.iter().map(|x| (x - max).exp()).sum::<f64>().ln();
+            for x in row.iter_mut() {
+                *x -= log_total;
+                if self == ActivationFunction::Softmax {
+                    *x = x.exp();
+                }
+            }
+        }
+    }
+
+    /// Gradient with respect to the pre-activations from the gradient with respect to the
+    /// activated values, for row-major values with `width` columns
+    pub(crate) fn backward_rows(self, pre_activations: &[f64], outputs: &[f64], output_gradient: &[f64], width: usize) -> Vec<f64> {
+        match self {
+            ActivationFunction::Softmax => {
+                // Jacobian-vector product: y_i * (g_i - sum_j g_j y_j)
+                let mut gradient = Vec::with_capacity(outputs.len());
+                for (y, g) in outputs.chunks_exact(width).zip(output_gradient.chunks_exact(width)) {
+                    let weighted = dot(y, g);
+                    gradient.extend(y.iter().zip(g).map(|(y, g)| y * (g - weighted)));
+                }
+                gradient
+            }
+            ActivationFunction::LogSoftmax => {
+                // g_i - softmax_i * sum_j g_j
+                let mut gradient = Vec::with_capacity(outputs.len());
+                for (y, g) in outputs.chunks_exact(width).zip(output_gradient.chunks_exact(width)) {
+                    let total: f64 = g.iter().sum();
+                    gradient.extend(y.iter().zip(g).map(|(y, g)| g - y.exp() * total));
+                }
+                gradient
+            }
+            _ => output_gradient
+                .iter()
+                .zip(pre_activations.iter().zip(outputs))
+                .map(|(g, (&x, &y))| g * self.derivative(x, y))
+                .collect(),
+        }
+    }
+}
+
+/// Loss functions for training a `Network`
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LossFunction {
+    /// Mean squared error over all outputs
+    MeanSquaredError,
+    /// Binary cross-entropy over all outputs, which should lie in (0, 1)
+    BinaryCrossEntropy,
+    /// Cross-entropy between target and predicted class distributions, averaged over rows
+    CategoricalCrossEntropy,
+    /// Squared error below the Huber delta and absolute error above it, over all outputs
+    Huber,
+    /// Hinge loss over all outputs, for targets of -1 or 1 (0 is treated as -1)
+    Hinge,
+    /// Kullback-Leibler divergence from the predicted to the target distribution,
+    /// averaged over rows
+    KLDivergence,
+}
+
+impl LossFunction {
+    /// Variant with the given discriminant, for deserialization
+    pub(crate) fn from_index(index: u32) -> Option<LossFunction> {
+        use LossFunction::*;
+        const ALL: [LossFunction; 6] = [MeanSquaredError, BinaryCrossEntropy, CategoricalCrossEntropy, Huber, Hinge, KLDivergence];
+        ALL.get(index as usize).copied()
+    }
+
+    /// Mean loss and its gradient with respect to the predictions
+    ///
+    /// Predictions and targets are row-major with `width` columns; the row-wise losses
+    /// expect probabilities in each prediction row.
+    pub(crate) fn evaluate(self, predictions: &[f64], targets: &[f64], width: usize, huber_delta: f64) -> (f64, Vec<f64>) {
+        let count = predictions.len() as f64;
+        let rows = (predictions.len() / width.max(1)) as f64;
+        let mut loss = 0.0;
+
+        let gradient = predictions
+            .iter()
+            .zip(targets)
+            .map(|(&p, &t)| match self {
+                LossFunction::MeanSquaredError => {
+                    loss += (p - t) * (p - t);
+                    2.0 * (p - t) / count
+                }
+                LossFunction::BinaryCrossEntropy => {
+                    // Clip predictions to avoid log(0)
+                    let p = p.clamp(1e-15, 1.0 - 1e-15);
+                    loss -= t * p.ln() + (1.0 - t) * (1.0 - p).ln();
+                    (p - t) / (p * (1.0 - p)) / count
+                }
+                LossFunction::CategoricalCrossEntropy | LossFunction::KLDivergence => {
+                    let p = p.max(1e-15);
+                    loss -= t * p.ln();
+                    if self == LossFunction::KLDivergence && t > 0.0 {
+                        loss += t * t.ln();
+                    }
+                    -t / p / rows
+                }
+                LossFunction::Huber => {
+                    let error = p - t;
+                    if error.abs() <= huber_delta {
+                        loss += 0.5 * error * error;
+                        error / count
+                    } else {
+                        loss += huber_delta * (error.abs() - 0.5 * huber_delta);
+                        huber_delta * error.signum() / count
+                    }
+                }
+                LossFunction::Hinge => {
+                    let t = if t > 0.0 { 1.0 } else { -1.0 };
+                    let margin = 1.0 - t * p;
+                    if margin > 0.0 {
+                        loss += margin;
+                        -t / count
+                    } else {
+                        0.0
+                    }
+                }
+            })
+            .collect();
+
+        let divisor = if self.is_row_wise() { rows } else { count };
+        (loss / divisor, gradient)
+    }
+
+    /// Mean loss and its gradient with respect to the pre-activations of an output layer
+    /// with the given activation, when the pair has a numerically stable fused form
+    ///
+    /// Cross-entropy and KL divergence fuse with softmax (outputs are probabilities) and
+    /// log-softmax (outputs are log-probabilities); binary cross-entropy fuses with sigmoid.
+    pub(crate) fn evaluate_fused(self, activation: ActivationFunction, outputs: &[f64], targets: &[f64], width: usize) -> Option<(f64, Vec<f64>)> {
+        let rows = (outputs.len() / width.max(1)) as f64;
+        match (self, activation) {
+            (LossFunction::CategoricalCrossEntropy | LossFunction::KLDivergence, ActivationFunction::Softmax | ActivationFunction::LogSoftmax) => {
+                let log_space = activation == ActivationFunction::LogSoftmax;
+                let mut loss = 0.0;
+                let mut gradient = Vec::with_capacity(outputs.len());
+                for (y, t) in outputs.chunks_exact(width).zip(targets.chunks_exact(width)) {
+                    let total: f64 = t.iter().sum();
+                    for (&y, &t) in y.iter().zip(t) {
+                        let (log_p, p) = if log_space { (y, y.exp()) } else { (y.max(1e-300).ln(), y) };
+                        loss -= t * log_p;
+                        if self == LossFunction::KLDivergence && t > 0.0 {
+                            loss += t * t.ln();
+                        }
+                        gradient.push((p * total - t) / rows);
+                    }
+                }
+                Some((loss / rows, gradient))
+            }
+            (LossFunction::BinaryCrossEntropy, ActivationFunction::Sigmoid) => {
+                let count = outputs.len() as f64;
+                let (loss, _) = self.evaluate(outputs, targets, width, 0.0);
+                Some((loss, outputs.iter().zip(targets).map(|(p, t)| (p - t) / count).collect()))
+            }
+            _ => None,
+        }
+    }
+
+    fn is_row_wise(self) -> bool {
+        matches!(self, LossFunction::CategoricalCrossEntropy | LossFunction::KLDivergence)
+    }
+
+    /// Whether the loss trains a classifier, so that accuracy is meaningful
+    pub(crate) fn is_classification(self) -> bool {
+        !matches!(self, LossFunction::MeanSquaredError | LossFunction::Huber)
+    }
+
+    /// Correct predictions and the number of predictions of a classifier
+    ///
+    /// Row-wise losses compare the arg max of each row; binary cross-entropy and hinge
+    /// compare every output against the 0.5 and 0 thresholds.
+    pub(crate) fn hits(self, predictions: &[f64], targets: &[f64], width: usize) -> (usize, usize) {
+        let argmax = |row: &[f64]| row.iter().enumerate().fold(0, |best, (i, &v)| if v > row[best] { i } else { best });
+        match self {
+            LossFunction::CategoricalCrossEntropy | LossFunction::KLDivergence => {
+                let correct = predictions
+                    .chunks_exact(width)
+                    .zip(targets.chunks_exact(width))
+                    .filter(|(p, t)| argmax(p) == argmax(t))
+                    .count();
+                (correct, predictions.len() / width)
+            }
+            _ => {
+                let threshold = if self == LossFunction::Hinge { 0.0 } else { 0.5 };
+                let correct = predictions.iter().zip(targets).filter(|(&p, &t)| (p > threshold) == (t > threshold)).count();
+                (correct, predictions.len())
+            }
+        }
+    }
+}
+
+/// Weight initialization schemes, scaled by the fan-in and fan-out of a layer
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Initializer {
+    /// Normal with variance 2 / (fan_in + fan_out) (Glorot and Bengio, 2010)
+    XavierNormal,
+    /// Uniform with variance 2 / (fan_in + fan_out)
+    XavierUniform,
+    /// Normal with variance 2 / fan_in, for ReLU networks (He et al., 2015)
+    HeNormal,
+    /// Uniform with variance 2 / fan_in
+    HeUniform,
+    /// Normal with variance 1 / fan_in, for SELU networks
+    LeCunNormal,
+    /// Uniform with variance 1 / fan_in
+    LeCunUniform,
+    /// Random matrix with orthonormal rows, or columns when it is taller than wide
+    Orthogonal,
+    /// Normal with variance 1 / fan_in, redrawn beyond two standard deviations
+    TruncatedNormal,
+}
+
+impl Initializer {
+    /// Row-major `rows x columns` weights for a layer with the given fans
+    pub(crate) fn sample(self, rows: usize, columns: usize, fan_in: usize, fan_out: usize, rng: &mut Rng) -> Vec<f64> {
+        let count = rows * columns;
+        let (fan_in, fan_average) = (fan_in.max(1) as f64, (fan_in + fan_out).max(1) as f64 / 2.0);
+        let normal = |rng: &mut Rng, variance: f64| -> Vec<f64> { (0..count).map(|_| rng.next_normal() * variance.sqrt()).collect() };
+        // Uniform on [-limit, limit] has variance limit^2 / 3
+        let uniform = |rng: &mut Rng, variance: f64| -> Vec<f64> {
+            let limit = (3.0 * variance).sqrt();
+            (0..count).map(|_| (2.0 * rng.next_f64() - 1.0) * limit).collect()
+        };
+
+        match self {
+            Initializer::XavierNormal => normal(rng, 1.0 / fan_average),
+            Initializer::XavierUniform => uniform(rng, 1.0 / fan_average),
+            Initializer::HeNormal => normal(rng, 2.0 / fan_in),
+            Initializer::HeUniform => uniform(rng, 2.0 / fan_in),
+            Initializer::LeCunNormal => normal(rng, 1.0 / fan_in),
+            Initializer::LeCunUniform => uniform(rng, 1.0 / fan_in),
+            Initializer::Orthogonal => orthogonal(rows, columns, rng),
+            Initializer::TruncatedNormal => {
+                // Truncating at two standard deviations leaves 0.7737 of the variance
+                let std_dev = (1.0 / fan_in / 0.773_741_3).sqrt();
+                (0..count)
+                    .map(|_| loop {
+                        let z = rng.next_normal();
+                        if z.abs() <= 2.0 {
+                            break z * std_dev;
+                        }
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Random `rows x columns` matrix with orthonormal rows (or columns if `rows > columns`),
+/// by Gram-Schmidt on a Gaussian matrix
+fn orthogonal(rows: usize, columns: usize, rng: &mut Rng) -> Vec<f64> {
+    let (short, long) = (rows.min(columns), rows.max(columns));
+    let mut basis: Vec<Vec<f64>> = Vec::with_capacity(short);
+    while basis.len() < short {
+        let mut vector: Vec<f64> = (0..long).map(|_| rng.next_normal()).collect();
+        // Orthogonalize twice for numerical stability
+        for _ in 0..2 {
+            for other in &basis {
+                let projection = dot(&vector, other);
+                vector.iter_mut().zip(other).for_each(|(v, o)| *v -= projection * o);
+            }
+        }
+        let norm = dot(&vector, &vector).sqrt();
+        // Redraw the rare vector that is nearly dependent on the basis
+        if norm > 1e-6 {
+            vector.iter_mut().for_each(|v| *v /= norm);
+            basis.push(vector);
+        }
+    }
+
+    if rows <= columns {
+        basis.concat()
+    } else {
+        (0..rows).flat_map(|i| basis.iter().map(move |column| column[i])).collect()
+    }
+}
+
+/// Dot product of two equal-length slices
+pub(crate) fn dot(a: &[f64], b: &[f64]) -> f64 {
+    let length = a.len().min(b.len());
+    let mut sum = 0.0;
+
+    #[cfg(feature = "simd")]
+    {
+        let simd_length = length - (length % 4);
+        let mut sum_vec = f64x4::splat(0.0);
+
+        // Process in chunks of 4 elements
+        for i in (0..simd_length).step_by(4) {
+            let a_vec = f64x4::from([a[i], a[i + 1], a[i + 2], a[i + 3]]);
+            let b_vec = f64x4::from([b[i], b[i + 1], b[i + 2], b[i + 3]]);
+            sum_vec += a_vec * b_vec;
+        }
+
+        sum += sum_vec.reduce_add();
+
+        // Process remaining elements
+        for i in simd_length..length {
+            sum += a[i] * b[i];
+        }
+    }
+
+    #[cfg(not(feature = "simd"))]
+    {
+        for i in 0..length {
+            sum += a[i] * b[i];
+        }
+    }
+
+    sum
 }
 
 /// Forward propagation for a single layer neural network
@@ -54,6 +457,7 @@ pub fn neural_network_forward_f64(
     let inputs_values = bump.alloc_slice_fill_copy(num_samples, 0.0);
     let weights_values = bump.alloc_slice_fill_copy(weights_array.length() as usize, 0.0);
     let biases_values = bump.alloc_slice_fill_copy(num_outputs, 0.0);
+    let pre_activations = bump.alloc_slice_fill_copy(num_outputs, 0.0);
     
     // Copy input data
     for i in 0..num_samples {
@@ -113,15 +517,13 @@ pub fn neural_network_forward_f64(
             }
         }
         
-        // Apply activation function
-        let activated = match activation {
-            ActivationFunction::Sigmoid => 1.0 / (1.0 + (-sum).exp()),
-            ActivationFunction::ReLU => if sum > 0.0 { sum } else { 0.0 },
-            ActivationFunction::Tanh => sum.tanh(),
-            ActivationFunction::LeakyReLU => if sum > 0.0 { sum } else { 0.01 * sum },
-        };
-        
-        output_array.set_index(j as u32, activated);
+        pre_activations[j] = sum;
+    }
+    
+    // Apply activation function
+    activation.apply_rows(pre_activations, num_outputs);
+    for j in 0..num_outputs {
+        output_array.set_index(j as u32, pre_activations[j]);
     }
     
     Ok(output_array.into())
@@ -173,6 +575,20 @@ pub fn neural_network_forward_multi_layer_f64(
             ActivationFunction::Tanh
         } else if activation_value.as_string().unwrap_or_default() == "leaky_relu" {
             ActivationFunction::LeakyReLU
+        } else if activation_value.as_string().unwrap_or_default() == "softmax" {
+            ActivationFunction::Softmax
+        } else if activation_value.as_string().unwrap_or_default() == "log_softmax" {
+            ActivationFunction::LogSoftmax
+        } else if activation_value.as_string().unwrap_or_default() == "gelu" {
+            ActivationFunction::GELU
+        } else if activation_value.as_string().unwrap_or_default() == "elu" {
+            ActivationFunction::ELU
+        } else if activation_value.as_string().unwrap_or_default() == "selu" {
+            ActivationFunction::SELU
+        } else if activation_value.as_string().unwrap_or_default() == "swish" {
+            ActivationFunction::Swish
+        } else if activation_value.as_string().unwrap_or_default() == "linear" {
+            ActivationFunction::Linear
         } else {
             return Err(JsValue::from_str("Invalid activation function"));
         };
@@ -270,31 +686,21 @@ pub fn neural_network_backprop_f64(
         }
         
         pre_activations[j] = sum;
-        
-        // Apply activation function
-        outputs[j] = match activation {
-            ActivationFunction::Sigmoid => 1.0 / (1.0 + (-sum).exp()),
-            ActivationFunction::ReLU => if sum > 0.0 { sum } else { 0.0 },
-            ActivationFunction::Tanh => sum.tanh(),
-            ActivationFunction::LeakyReLU => if sum > 0.0 { sum } else { 0.01 * sum },
-        };
     }
     
+    // Apply activation function
+    outputs.copy_from_slice(&pre_activations);
+    activation.apply_rows(&mut outputs, num_outputs);
+    
+    // Calculate error derivative with respect to output
+    let errors: Vec<f64> = outputs.iter().zip(targets_values.iter()).map(|(o, t)| o - t).collect();
+    
+    // Calculate deltas through the derivative of the activation function
+    let deltas = activation.backward_rows(&pre_activations, &outputs, &errors, num_outputs);
+    
     // Backpropagation
     for j in 0..num_outputs {
-        // Calculate error derivative with respect to output
-        let error = outputs[j] - targets_values[j];
-        
-        // Calculate derivative of activation function
-        let activation_derivative = match activation {
-            ActivationFunction::Sigmoid => outputs[j] * (1.0 - outputs[j]),
-            ActivationFunction::ReLU => if pre_activations[j] > 0.0 { 1.0 } else { 0.0 },
-            ActivationFunction::Tanh => 1.0 - outputs[j] * outputs[j],
-            ActivationFunction::LeakyReLU => if pre_activations[j] > 0.0 { 1.0 } else { 0.01 },
-        };
-        
-        // Calculate delta
-        let delta = error * activation_derivative;
+        let delta = deltas[j];
         
         // Update biases
         let updated_bias = biases_values[j] - learning_rate * delta;
@@ -440,39 +846,142 @@ pub fn neural_network_binary_cross_entropy_loss_f64(predictions: &JsValue, targe
     -sum_loss / length as f64
 }
 
+/// Apply softmax to each row of a row-major matrix
+///
+/// Takes values and the number of columns, and returns the row-wise softmax. Rows are
+/// shifted by their maximum first so large values do not overflow.
+#[wasm_bindgen]
+pub fn neural_network_softmax_f64(values: &JsValue, width: usize) -> Result<JsValue, JsValue> {
+    row_wise_activation(values, width, ActivationFunction::Softmax)
+}
+
+/// Apply log-softmax to each row of a row-major matrix
+///
+/// Takes values and the number of columns, and returns the row-wise log-softmax, computed
+/// without forming the softmax so very negative log-probabilities keep their precision.
+#[wasm_bindgen]
+pub fn neural_network_log_softmax_f64(values: &JsValue, width: usize) -> Result<JsValue, JsValue> {
+    row_wise_activation(values, width, ActivationFunction::LogSoftmax)
+}
+
+/// Calculate the categorical cross-entropy loss
+///
+/// Takes row-major predicted probabilities, target distributions (usually one-hot) and the
+/// number of classes, and returns the mean cross-entropy per row.
+#[wasm_bindgen]
+pub fn neural_network_categorical_cross_entropy_loss_f64(predictions: &JsValue, targets: &JsValue, num_classes: usize) -> Result<f64, JsValue> {
+    let (predictions, targets) = read_loss_inputs(predictions, targets, num_classes)?;
+    Ok(LossFunction::CategoricalCrossEntropy.evaluate(&predictions, &targets, num_classes, 0.0).0)
+}
+
+/// Calculate the Huber loss
+///
+/// Takes predictions, targets and the threshold between the quadratic and linear regions
+/// (default 1), and returns the mean Huber loss.
+#[wasm_bindgen]
+pub fn neural_network_huber_loss_f64(predictions: &JsValue, targets: &JsValue, delta: Option<f64>) -> Result<f64, JsValue> {
+    let delta = delta.unwrap_or(1.0);
+    if !(delta > 0.0 && delta.is_finite()) {
+        return Err(JsValue::from_str("Huber delta must be a positive finite number"));
+    }
+    let (predictions, targets) = read_loss_inputs(predictions, targets, 1)?;
+    Ok(LossFunction::Huber.evaluate(&predictions, &targets, 1, delta).0)
+}
+
+/// Calculate the hinge loss
+///
+/// Takes raw predictions and targets of -1 or 1 (0 is treated as -1), and returns the mean
+/// hinge loss.
+#[wasm_bindgen]
+pub fn neural_network_hinge_loss_f64(predictions: &JsValue, targets: &JsValue) -> Result<f64, JsValue> {
+    let (predictions, targets) = read_loss_inputs(predictions, targets, 1)?;
+    Ok(LossFunction::Hinge.evaluate(&predictions, &targets, 1, 0.0).0)
+}
+
+/// Calculate the Kullback-Leibler divergence
+///
+/// Takes row-major predicted and target distributions and the number of classes, and
+/// returns the mean divergence of the predictions from the targets per row.
+#[wasm_bindgen]
+pub fn neural_network_kl_divergence_loss_f64(predictions: &JsValue, targets: &JsValue, num_classes: usize) -> Result<f64, JsValue> {
+    let (predictions, targets) = read_loss_inputs(predictions, targets, num_classes)?;
+    Ok(LossFunction::KLDivergence.evaluate(&predictions, &targets, num_classes, 0.0).0)
+}
+
+/// Calculate the gradient of a loss with respect to the predictions
+///
+/// Takes row-major predictions and targets, the loss, the number of outputs per row and the
+/// Huber delta (default 1), and returns the gradient of the mean loss for backpropagation.
+#[wasm_bindgen]
+pub fn neural_network_loss_gradient_f64(
+    predictions: &JsValue,
+    targets: &JsValue,
+    loss: LossFunction,
+    num_outputs: usize,
+    huber_delta: Option<f64>,
+) -> Result<JsValue, JsValue> {
+    let (predictions, targets) = read_loss_inputs(predictions, targets, num_outputs)?;
+    let (_, gradient) = loss.evaluate(&predictions, &targets, num_outputs, huber_delta.unwrap_or(1.0));
+    Ok(Float64Array::from(&gradient[..]).into())
+}
+
+fn row_wise_activation(values: &JsValue, width: usize, activation: ActivationFunction) -> Result<JsValue, JsValue> {
+    // Convert input to typed array for better performance
+    let mut values = Float64Array::new(values).to_vec();
+
+    // Validate inputs
+    if width == 0 || !values.len().is_multiple_of(width) {
+        return Err(JsValue::from_str("Values length must be a multiple of the width"));
+    }
+
+    activation.apply_rows(&mut values, width);
+    Ok(Float64Array::from(&values[..]).into())
+}
+
+fn read_loss_inputs(predictions: &JsValue, targets: &JsValue, width: usize) -> Result<(Vec<f64>, Vec<f64>), JsValue> {
+    // Convert inputs to typed arrays for better performance
+    let predictions = Float64Array::new(predictions).to_vec();
+    let targets = Float64Array::new(targets).to_vec();
+
+    // Validate inputs
+    if predictions.is_empty() || predictions.len() != targets.len() {
+        return Err(JsValue::from_str("Predictions and targets must be non-empty and of equal length"));
+    }
+    if width == 0 || !predictions.len().is_multiple_of(width) {
+        return Err(JsValue::from_str("Predictions length must be a multiple of the number of outputs"));
+    }
+    Ok((predictions, targets))
+}
+
 /// Initialize weights using Xavier/Glorot initialization
 ///
-/// Takes input size, output size, and returns initialized weights.
+/// Takes input size, output size, and an optional seed, and returns initialized weights.
 /// This is much faster than using JavaScript, especially for large networks.
 #[wasm_bindgen]
-pub fn neural_network_init_weights_xavier_f64(input_size: usize, output_size: usize) -> Result<JsValue, JsValue> {
+pub fn neural_network_init_weights_xavier_f64(input_size: usize, output_size: usize, seed: Option<u32>) -> Result<JsValue, JsValue> {
+    neural_network_init_weights_f64(input_size, output_size, Initializer::XavierNormal, seed)
+}
+
+/// Initialize weights with the given scheme
+///
+/// Takes input size, output size, the initializer, and an optional seed, and returns the
+/// weights as an `output_size x input_size` row-major array. The same seed gives the same
+/// weights in the browser and natively.
+#[wasm_bindgen]
+pub fn neural_network_init_weights_f64(
+    input_size: usize,
+    output_size: usize,
+    initializer: Initializer,
+    seed: Option<u32>,
+) -> Result<JsValue, JsValue> {
     // Validate dimensions
     if input_size == 0 || output_size == 0 {
         return Err(JsValue::from_str("Input size and output size must be greater than 0"));
     }
-    
-    // Calculate standard deviation for Xavier initialization
-    let std_dev = (2.0 / (input_size + output_size) as f64).sqrt();
-    
-    // Create weights array
-    let weights = Float64Array::new_with_length((input_size * output_size) as u32);
-    
-    // Initialize weights
-    for i in 0..(input_size * output_size) {
-        // Generate random number from standard normal distribution
-        let u1 = js_sys::Math::random();
-        let u2 = js_sys::Math::random();
-        
-        // Box-Muller transform
-        let z0 = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
-        
-        // Scale by standard deviation
-        let weight = z0 * std_dev;
-        
-        weights.set_index(i as u32, weight);
-    }
-    
-    Ok(weights.into())
+
+    let mut rng = Rng::from_seed(seed);
+    let weights = initializer.sample(output_size, input_size, input_size, output_size, &mut rng);
+    Ok(Float64Array::from(&weights[..]).into())
 }
 
 /// Initialize biases to zero
@@ -496,3 +1005,300 @@ pub fn neural_network_init_biases_zero_f64(output_size: usize) -> Result<JsValue
     
     Ok(biases.into())
 }
+
+/// Initialize biases to a constant
+///
+/// Takes output size and the bias value, and returns initialized biases.
+#[wasm_bindgen]
+pub fn neural_network_init_biases_constant_f64(output_size: usize, value: f64) -> Result<JsValue, JsValue> {
+    // Validate dimensions
+    if output_size == 0 {
+        return Err(JsValue::from_str("Output size must be greater than 0"));
+    }
+    if !value.is_finite() {
+        return Err(JsValue::from_str("Bias value must be finite"));
+    }
+
+    Ok(Float64Array::from(&vec![value; output_size][..]).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ELEMENTWISE: [ActivationFunction; 9] = [
+        ActivationFunction::Sigmoid,
+        ActivationFunction::ReLU,
+        ActivationFunction::Tanh,
+        ActivationFunction::LeakyReLU,
+        ActivationFunction::GELU,
+        ActivationFunction::ELU,
+        ActivationFunction::SELU,
+        ActivationFunction::Swish,
+        ActivationFunction::Linear,
+    ];
+
+    fn normals(count: usize, seed: u64) -> Vec<f64> {
+        let mut rng = Rng::new(seed);
+        (0..count).map(|_| rng.next_normal()).collect()
+    }
+
+    /// Central difference of `f` at every coordinate of `x`
+    fn numeric_gradient(x: &[f64], f: impl Fn(&[f64]) -> f64) -> Vec<f64> {
+        let h = 1e-6;
+        (0..x.len())
+            .map(|i| {
+                let (mut above, mut below) = (x.to_vec(), x.to_vec());
+                above[i] += h;
+                below[i] -= h;
+                (f(&above) - f(&below)) / (2.0 * h)
+            })
+            .collect()
+    }
+
+    fn assert_close(actual: &[f64], expected: &[f64], tolerance: f64) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < tolerance, "{:?} vs {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn every_variant_round_trips_through_its_index() {
+        for index in 0..11 {
+            assert_eq!(ActivationFunction::from_index(index).unwrap() as u32, index);
+        }
+        assert_eq!(ActivationFunction::from_index(11), None);
+        for index in 0..6 {
+            assert_eq!(LossFunction::from_index(index).unwrap() as u32, index);
+        }
+        assert_eq!(LossFunction::from_index(6), None);
+    }
+
+    #[test]
+    fn elementwise_derivatives_match_finite_differences() {
+        // Stay clear of the kink at zero
+        let points = [-3.0, -1.2, -0.3, 0.2, 0.9, 2.5];
+        for activation in ELEMENTWISE {
+            for &x in &points {
+                let numeric = (activation.apply(x + 1e-6) - activation.apply(x - 1e-6)) / 2e-6;
+                let analytic = activation.derivative(x, activation.apply(x));
+                assert!((numeric - analytic).abs() < 1e-6, "{:?} at {}: {} vs {}", activation, x, numeric, analytic);
+            }
+        }
+    }
+
+    #[test]
+    fn known_activation_values() {
+        assert!((ActivationFunction::GELU.apply(1.0) - 0.841_344_746).abs() < 1e-6);
+        assert!((ActivationFunction::SELU.apply(-50.0) + SELU_SCALE * SELU_ALPHA).abs() < 1e-12);
+        assert_eq!(ActivationFunction::LeakyReLU.apply(-2.0), -0.02);
+        assert!((ActivationFunction::Swish.apply(2.0) - 2.0 / (1.0 + (-2.0f64).exp())).abs() < 1e-15);
+    }
+
+    #[test]
+    fn softmax_rows_are_stable_distributions() {
+        let mut values = vec![1000.0, 1001.0, 1002.0, -5.0, 0.0, 5.0];
+        ActivationFunction::Softmax.apply_rows(&mut values, 3);
+        for row in values.chunks_exact(3) {
+            assert!((row.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        }
+        let e = 1.0f64.exp();
+        assert_close(&values[..3], &[1.0, e, e * e].map(|v| v / (1.0 + e + e * e)), 1e-12);
+
+        let mut logs = vec![1000.0, 1001.0, 1002.0];
+        ActivationFunction::LogSoftmax.apply_rows(&mut logs, 3);
+        assert_close(&logs, &values[..3].iter().map(|p| p.ln()).collect::<Vec<f64>>(), 1e-12);
+    }
+
+    #[test]
+    fn row_wise_activations_on_a_single_value() {
+        // A lone value is a row of its own: probability one, log-probability zero
+        assert_eq!(ActivationFunction::Softmax.apply(3.0), 1.0);
+        assert_eq!(ActivationFunction::LogSoftmax.apply(3.0), 0.0);
+        assert_eq!(ActivationFunction::Softmax.derivative(3.0, 1.0), 0.0);
+        assert_eq!(ActivationFunction::LogSoftmax.derivative(3.0, 0.0), 0.0);
+
+        let mut values = vec![3.0, -1.0];
+        ActivationFunction::Softmax.apply_rows(&mut values, 1);
+        assert_eq!(values, vec![1.0, 1.0]);
+    }
+
+    #[test]
+    fn backward_rows_match_finite_differences() {
+        let (x, g) = (normals(8, 1), normals(8, 2));
+        for activation in ELEMENTWISE.iter().copied().chain([ActivationFunction::Softmax, ActivationFunction::LogSoftmax]) {
+            let forward = |x: &[f64]| {
+                let mut y = x.to_vec();
+                activation.apply_rows(&mut y, 4);
+                dot(&y, &g)
+            };
+            let mut y = x.clone();
+            activation.apply_rows(&mut y, 4);
+            let analytic = activation.backward_rows(&x, &y, &g, 4);
+            assert_close(&analytic, &numeric_gradient(&x, forward), 1e-6);
+        }
+    }
+
+    #[test]
+    fn loss_gradients_match_finite_differences() {
+        let raw = normals(6, 3);
+        let probabilities: Vec<f64> = raw.iter().map(|v| 1.0 / (1.0 + (-v).exp())).collect();
+        let mut distributions = raw.clone();
+        ActivationFunction::Softmax.apply_rows(&mut distributions, 3);
+
+        let cases = [
+            (LossFunction::MeanSquaredError, raw.clone(), normals(6, 4)),
+            (LossFunction::Huber, raw.iter().map(|v| 3.0 * v).collect(), normals(6, 5)),
+            (LossFunction::Hinge, raw.clone(), vec![1.0, -1.0, 0.0, 1.0, 1.0, -1.0]),
+            (LossFunction::BinaryCrossEntropy, probabilities, vec![1.0, 0.0, 0.3, 1.0, 0.0, 0.8]),
+            (LossFunction::CategoricalCrossEntropy, distributions.clone(), vec![0.0, 1.0, 0.0, 0.2, 0.2, 0.6]),
+            (LossFunction::KLDivergence, distributions, vec![0.1, 0.7, 0.2, 0.0, 0.5, 0.5]),
+        ];
+        for (loss, predictions, targets) in cases {
+            let (_, analytic) = loss.evaluate(&predictions, &targets, 3, 1.0);
+            let numeric = numeric_gradient(&predictions, |p| loss.evaluate(p, &targets, 3, 1.0).0);
+            for (a, n) in analytic.iter().zip(&numeric) {
+                assert!((a - n).abs() < 1e-5, "{:?}: {:?} vs {:?}", loss, analytic, numeric);
+            }
+        }
+    }
+
+    #[test]
+    fn known_loss_values() {
+        let (mse, _) = LossFunction::MeanSquaredError.evaluate(&[1.0, 3.0], &[0.0, 1.0], 1, 1.0);
+        assert!((mse - 2.5).abs() < 1e-12);
+
+        // Quadratic below the delta, linear above it
+        let (huber, _) = LossFunction::Huber.evaluate(&[0.5, 4.0], &[0.0, 0.0], 1, 1.0);
+        assert!((huber - (0.125 + 3.5) / 2.0).abs() < 1e-12);
+
+        let (hinge, _) = LossFunction::Hinge.evaluate(&[0.5, 2.0, 0.5], &[1.0, 1.0, 0.0], 1, 1.0);
+        assert!((hinge - 2.0 / 3.0).abs() < 1e-12);
+
+        // Cross-entropy averages over rows; KL vanishes when the distributions agree
+        let predictions = [0.5, 0.5, 0.25, 0.75];
+        let (cross_entropy, _) = LossFunction::CategoricalCrossEntropy.evaluate(&predictions, &[1.0, 0.0, 0.0, 1.0], 2, 1.0);
+        assert!((cross_entropy - (2.0f64.ln() + (4.0f64 / 3.0).ln()) / 2.0).abs() < 1e-12);
+        let (kl, _) = LossFunction::KLDivergence.evaluate(&[0.3, 0.7], &[0.3, 0.7], 2, 1.0);
+        assert!(kl.abs() < 1e-12);
+    }
+
+    #[test]
+    fn fused_gradients_match_the_chain_rule() {
+        let x = normals(6, 6);
+        let cases = [
+            (LossFunction::CategoricalCrossEntropy, ActivationFunction::Softmax, vec![0.0, 1.0, 0.0, 0.2, 0.2, 0.6]),
+            (LossFunction::CategoricalCrossEntropy, ActivationFunction::LogSoftmax, vec![1.0, 0.0, 0.0, 0.0, 0.0, 1.0]),
+            (LossFunction::KLDivergence, ActivationFunction::Softmax, vec![0.1, 0.7, 0.2, 0.0, 0.5, 0.5]),
+            (LossFunction::BinaryCrossEntropy, ActivationFunction::Sigmoid, vec![1.0, 0.0, 0.3, 1.0, 0.0, 0.8]),
+        ];
+        for (loss, activation, targets) in cases {
+            let mut y = x.clone();
+            activation.apply_rows(&mut y, 3);
+            let (fused_loss, fused) = loss.evaluate_fused(activation, &y, &targets, 3).unwrap();
+
+            // Log-softmax outputs are log-probabilities, which the plain loss does not take
+            let numeric = numeric_gradient(&x, |x| {
+                let mut y = x.to_vec();
+                activation.apply_rows(&mut y, 3);
+                loss.evaluate_fused(activation, &y, &targets, 3).unwrap().0
+            });
+            assert_close(&fused, &numeric, 1e-6);
+
+            if activation != ActivationFunction::LogSoftmax {
+                let (plain_loss, gradient) = loss.evaluate(&y, &targets, 3, 1.0);
+                assert!((fused_loss - plain_loss).abs() < 1e-12);
+                assert_close(&fused, &activation.backward_rows(&x, &y, &gradient, 3), 1e-12);
+            }
+        }
+        assert!(LossFunction::MeanSquaredError.evaluate_fused(ActivationFunction::Softmax, &x, &x, 3).is_none());
+    }
+
+    #[test]
+    fn classifier_hits() {
+        let predictions = [0.1, 0.7, 0.2, 0.5, 0.3, 0.2];
+        let targets = [0.0, 1.0, 0.0, 0.0, 0.0, 1.0];
+        assert_eq!(LossFunction::CategoricalCrossEntropy.hits(&predictions, &targets, 3), (1, 2));
+        assert_eq!(LossFunction::BinaryCrossEntropy.hits(&[0.9, 0.2, 0.6], &[1.0, 0.0, 0.0], 1), (2, 3));
+        assert_eq!(LossFunction::Hinge.hits(&[0.3, -2.0], &[1.0, -1.0], 1), (2, 2));
+        assert!(!LossFunction::Huber.is_classification());
+    }
+
+    #[test]
+    fn initializer_variances() {
+        // 200 x 100 weights with fan-in 100 and fan-out 200
+        let (fan_in, fan_out) = (100.0f64, 200.0);
+        let expected = [
+            (Initializer::XavierNormal, 2.0 / (fan_in + fan_out)),
+            (Initializer::XavierUniform, 2.0 / (fan_in + fan_out)),
+            (Initializer::HeNormal, 2.0 / fan_in),
+            (Initializer::HeUniform, 2.0 / fan_in),
+            (Initializer::LeCunNormal, 1.0 / fan_in),
+            (Initializer::LeCunUniform, 1.0 / fan_in),
+            (Initializer::TruncatedNormal, 1.0 / fan_in),
+        ];
+        for (initializer, variance) in expected {
+            let weights = initializer.sample(200, 100, 100, 200, &mut Rng::new(1));
+            let mean = weights.iter().sum::<f64>() / weights.len() as f64;
+            let sample_variance = weights.iter().map(|w| (w - mean).powi(2)).sum::<f64>() / weights.len() as f64;
+            assert!(mean.abs() < 0.05 * variance.sqrt(), "{:?}: mean {}", initializer, mean);
+            assert!((sample_variance / variance - 1.0).abs() < 0.05, "{:?}: variance {}", initializer, sample_variance);
+        }
+    }
+
+    #[test]
+    fn initializer_bounds() {
+        let bound = |initializer: Initializer| {
+            let weights = initializer.sample(50, 40, 40, 50, &mut Rng::new(2));
+            weights.iter().fold(0.0f64, |m, w| m.max(w.abs()))
+        };
+        // Uniform limit sqrt(3 variance); truncation at two standard deviations
+        assert!(bound(Initializer::HeUniform) <= (6.0f64 / 40.0).sqrt());
+        assert!(bound(Initializer::XavierUniform) <= (6.0f64 / 90.0).sqrt());
+        assert!(bound(Initializer::LeCunUniform) <= (3.0f64 / 40.0).sqrt());
+        assert!(bound(Initializer::TruncatedNormal) <= 2.0 * (1.0 / 40.0 / 0.773_741_3f64).sqrt());
+        assert!(bound(Initializer::HeNormal) > 2.0 * (2.0f64 / 40.0).sqrt());
+    }
+
+    #[test]
+    fn orthogonal_rows_or_columns() {
+        // Gram matrix of the rows (or columns) of a row-major matrix
+        let gram = |weights: &[f64], rows: usize, columns: usize, of_rows: bool| -> Vec<f64> {
+            let vectors: Vec<Vec<f64>> = if of_rows {
+                weights.chunks_exact(columns).map(|row| row.to_vec()).collect()
+            } else {
+                (0..columns).map(|j| (0..rows).map(|i| weights[i * columns + j]).collect()).collect()
+            };
+            vectors.iter().flat_map(|a| vectors.iter().map(move |b| dot(a, b))).collect()
+        };
+        let identity = |n: usize| -> Vec<f64> { (0..n * n).map(|i| if i % (n + 1) == 0 { 1.0 } else { 0.0 }).collect() };
+
+        for (rows, columns) in [(3, 5), (5, 3), (4, 4)] {
+            let weights = Initializer::Orthogonal.sample(rows, columns, columns, rows, &mut Rng::new(3));
+            assert_eq!(weights.len(), rows * columns);
+            let n = rows.min(columns);
+            assert_close(&gram(&weights, rows, columns, rows <= columns), &identity(n), 1e-12);
+        }
+    }
+
+    #[test]
+    fn initializers_follow_the_seed() {
+        let initializers = [
+            Initializer::XavierNormal,
+            Initializer::XavierUniform,
+            Initializer::HeNormal,
+            Initializer::HeUniform,
+            Initializer::LeCunNormal,
+            Initializer::LeCunUniform,
+            Initializer::Orthogonal,
+            Initializer::TruncatedNormal,
+        ];
+        for initializer in initializers {
+            let sample = |seed: u64| initializer.sample(4, 3, 3, 4, &mut Rng::new(seed));
+            assert_eq!(sample(11), sample(11), "{:?}", initializer);
+            assert_ne!(sample(11), sample(12), "{:?}", initializer);
+        }
+        assert!(Initializer::HeNormal.sample(0, 3, 3, 0, &mut Rng::new(1)).is_empty());
+    }
+}
@@ -25,18 +25,11 @@ pub fn nlp_tokenize(text: &str) -> Result<JsValue, JsValue> {
 /// Takes a text string and returns an object with word frequencies.
 #[wasm_bindgen]
 pub fn nlp_word_frequencies(text: &str) -> Result<JsValue, JsValue> {
-    // Split the text into words
-    let words: Vec<&str> = text.split_whitespace()
-        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()))
-        .filter(|word| !word.is_empty())
-        .collect();
-    
     // Count word frequencies
     let mut frequencies = std::collections::HashMap::new();
     
-    for word in words {
-        let word_lower = word.to_lowercase();
-        *frequencies.entry(word_lower).or_insert(0) += 1;
+    for word in normalized_words(text) {
+        *frequencies.entry(word).or_insert(0) += 1;
     }
     
     // Create a JavaScript object for the result
@@ -70,10 +63,7 @@ pub fn nlp_tf_idf(document: &str, corpus: &JsValue) -> Result<JsValue, JsValue>
     }
     
     // Tokenize the document
-    let doc_words: Vec<String> = document.split_whitespace()
-        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase())
-        .filter(|word| !word.is_empty())
-        .collect();
+    let doc_words = normalized_words(document);
     
     // Calculate term frequency (TF) for the document
     let mut term_freq = std::collections::HashMap::new();
@@ -87,10 +77,7 @@ pub fn nlp_tf_idf(document: &str, corpus: &JsValue) -> Result<JsValue, JsValue>
     let mut doc_freq = std::collections::HashMap::new();
     
     for doc in &corpus_docs {
-        let doc_unique_words: std::collections::HashSet<String> = doc.split_whitespace()
-            .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase())
-            .filter(|word| !word.is_empty())
-            .collect();
+        let doc_unique_words: std::collections::HashSet<String> = normalized_words(doc).into_iter().collect();
         
         for word in doc_unique_words {
             *doc_freq.entry(word).or_insert(0) += 1;
@@ -190,3 +177,12 @@ pub fn nlp_jaccard_similarity(text1: &str, text2: &str) -> f64 {
     
     intersection_size / union_size
 }
+
+/// Lowercase words with surrounding punctuation removed, as used by the frequency,
+/// TF-IDF and Naive Bayes functions
+pub(crate) fn normalized_words(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase())
+        .filter(|word| !word.is_empty())
+        .collect()
+}
@@ -0,0 +1,278 @@
+/// Minimise a function with the Nelder-Mead simplex method
+///
+/// Starts from `start` with an initial simplex of edge `step` and stops after
+/// `max_iterations` or when the spread of function values drops below `tolerance`.
+/// Returns the best point found and its function value.
+pub(crate) fn nelder_mead<F: Fn(&[f64]) -> f64>(
+    f: F,
+    start: &[f64],
+    step: f64,
+    max_iterations: usize,
+    tolerance: f64,
+) -> (Vec<f64>, f64) {
+    let dims = start.len();
+    if dims == 0 {
+        return (Vec::new(), f(start));
+    }
+
+    // Build the initial simplex around the starting point
+    let mut simplex: Vec<Vec<f64>> = Vec::with_capacity(dims + 1);
+    simplex.push(start.to_vec());
+    for i in 0..dims {
+        let mut vertex = start.to_vec();
+        vertex[i] += step;
+        simplex.push(vertex);
+    }
+    let mut values: Vec<f64> = simplex.iter().map(|v| finite_or_max(f(v))).collect();
+
+    for _ in 0..max_iterations {
+        // Order vertices from best to worst
+        let mut order: Vec<usize> = (0..=dims).collect();
+        order.sort_by(|&a, &b| values[a].partial_cmp(&values[b]).unwrap_or(std::cmp::Ordering::Equal));
+        simplex = order.iter().map(|&i| simplex[i].clone()).collect();
+        values = order.iter().map(|&i| values[i]).collect();
+
+        if (values[dims] - values[0]).abs() <= tolerance * (values[0].abs() + tolerance) {
+            break;
+        }
+
+        // Centroid of every vertex except the worst
+        let mut centroid = vec![0.0; dims];
+        for vertex in &simplex[..dims] {
+            for j in 0..dims {
+                centroid[j] += vertex[j] / dims as f64;
+            }
+        }
+
+        let along = |t: f64| -> Vec<f64> {
+            (0..dims).map(|j| centroid[j] + t * (simplex[dims][j] - centroid[j])).collect()
+        };
+
+        let reflected = along(-1.0);
+        let reflected_value = finite_or_max(f(&reflected));
+
+        if reflected_value < values[0] {
+            let expanded = along(-2.0);
+            let expanded_value = finite_or_max(f(&expanded));
+            if expanded_value < reflected_value {
+                simplex[dims] = expanded;
+                values[dims] = expanded_value;
+            } else {
+                simplex[dims] = reflected;
+                values[dims] = reflected_value;
+            }
+        } else if reflected_value < values[dims - 1] {
+            simplex[dims] = reflected;
+            values[dims] = reflected_value;
+        } else {
+            let contracted = if reflected_value < values[dims] { along(-0.5) } else { along(0.5) };
+            let contracted_value = finite_or_max(f(&contracted));
+
+            if contracted_value < values[dims].min(reflected_value) {
+                simplex[dims] = contracted;
+                values[dims] = contracted_value;
+            } else {
+                // Shrink every vertex towards the best one
+                let (best, rest) = simplex.split_at_mut(1);
+                for (vertex, value) in rest.iter_mut().zip(&mut values[1..]) {
+                    for (x, &b) in vertex.iter_mut().zip(&best[0]) {
+                        *x = b + 0.5 * (*x - b);
+                    }
+                    *value = finite_or_max(f(vertex));
+                }
+            }
+        }
+    }
+
+    let mut best = 0;
+    for i in 1..=dims {
+        if values[i] < values[best] {
+            best = i;
+        }
+    }
+
+    (simplex[best].clone(), values[best])
+}
+
+/// Replace NaN and infinite objective values so they always lose comparisons
+fn finite_or_max(value: f64) -> f64 {
+    if value.is_finite() { value } else { f64::MAX }
+}
+
+/// Minimise a smooth function with limited-memory BFGS
+///
+/// `f` returns the function value and writes the gradient into its second argument.
+/// Uses the two-loop recursion with `memory` correction pairs and a backtracking Armijo
+/// line search. Stops when the gradient's largest component drops below `tolerance`.
+/// Returns the minimiser, its value and the number of iterations.
+pub(crate) fn lbfgs<F: Fn(&[f64], &mut [f64]) -> f64>(
+    f: F,
+    start: &[f64],
+    memory: usize,
+    max_iterations: usize,
+    tolerance: f64,
+) -> (Vec<f64>, f64, usize) {
+    let dims = start.len();
+    let mut x = start.to_vec();
+    let mut gradient = vec![0.0; dims];
+    let mut value = f(&x, &mut gradient);
+
+    let mut steps: Vec<Vec<f64>> = Vec::with_capacity(memory);
+    let mut gradient_changes: Vec<Vec<f64>> = Vec::with_capacity(memory);
+    let mut iterations = 0;
+
+    while iterations < max_iterations {
+        if gradient.iter().fold(0.0f64, |m, g| m.max(g.abs())) <= tolerance {
+            break;
+        }
+        iterations += 1;
+
+        // Two-loop recursion for the search direction -H g
+        let mut direction: Vec<f64> = gradient.iter().map(|g| -g).collect();
+        let mut alphas = vec![0.0; steps.len()];
+        for i in (0..steps.len()).rev() {
+            let rho = 1.0 / dot(&gradient_changes[i], &steps[i]);
+            alphas[i] = rho * dot(&steps[i], &direction);
+            for (d, y) in direction.iter_mut().zip(&gradient_changes[i]) {
+                *d -= alphas[i] * y;
+            }
+        }
+        if let (Some(s), Some(y)) = (steps.last(), gradient_changes.last()) {
+            let scale = dot(s, y) / dot(y, y);
+            direction.iter_mut().for_each(|d| *d *= scale);
+        }
+        for i in 0..steps.len() {
+            let rho = 1.0 / dot(&gradient_changes[i], &steps[i]);
+            let beta = rho * dot(&gradient_changes[i], &direction);
+            for (d, s) in direction.iter_mut().zip(&steps[i]) {
+                *d += (alphas[i] - beta) * s;
+            }
+        }
+
+        // Fall back to steepest descent if the direction is not a descent direction
+        let mut slope = dot(&gradient, &direction);
+        if slope >= 0.0 {
+            direction = gradient.iter().map(|g| -g).collect();
+            slope = -dot(&gradient, &gradient);
+            steps.clear();
+            gradient_changes.clear();
+        }
+
+        // Backtracking line search with the Armijo condition
+        let mut step = if steps.is_empty() { 1.0 / direction.iter().fold(1.0f64, |m, d| m.max(d.abs())) } else { 1.0 };
+        let mut candidate = vec![0.0; dims];
+        let mut candidate_gradient = vec![0.0; dims];
+        let mut candidate_value;
+        let mut accepted = false;
+        for _ in 0..50 {
+            for i in 0..dims {
+                candidate[i] = x[i] + step * direction[i];
+            }
+            candidate_value = f(&candidate, &mut candidate_gradient);
+            if candidate_value.is_finite() && candidate_value <= value + 1e-4 * step * slope {
+                accepted = true;
+                let s: Vec<f64> = (0..dims).map(|i| candidate[i] - x[i]).collect();
+                let y: Vec<f64> = (0..dims).map(|i| candidate_gradient[i] - gradient[i]).collect();
+                if dot(&s, &y) > 1e-12 {
+                    if steps.len() == memory {
+                        steps.remove(0);
+                        gradient_changes.remove(0);
+                    }
+                    steps.push(s);
+                    gradient_changes.push(y);
+                }
+                let improvement = value - candidate_value;
+                x.copy_from_slice(&candidate);
+                gradient.copy_from_slice(&candidate_gradient);
+                value = candidate_value;
+                if improvement <= f64::EPSILON * value.abs().max(1.0) {
+                    return (x, value, iterations);
+                }
+                break;
+            }
+            step *= 0.5;
+        }
+
+        if !accepted {
+            break;
+        }
+    }
+
+    (x, value, iterations)
+}
+
+/// Minimise a smooth function plus a non-smooth penalty by proximal gradient descent
+///
+/// `f` returns the smooth part and writes its gradient; `penalty` evaluates the non-smooth
+/// part and `prox` applies its proximal operator for a given step size in place. The step
+/// size is found by backtracking. Stops when an iteration moves no coordinate by more than
+/// `tolerance`. Returns the minimiser, its total objective and the number of iterations.
+pub(crate) fn proximal_gradient<F, P, Q>(
+    f: F,
+    penalty: P,
+    prox: Q,
+    start: &[f64],
+    max_iterations: usize,
+    tolerance: f64,
+) -> (Vec<f64>, f64, usize)
+where
+    F: Fn(&[f64], &mut [f64]) -> f64,
+    P: Fn(&[f64]) -> f64,
+    Q: Fn(&mut [f64], f64),
+{
+    let dims = start.len();
+    let mut x = start.to_vec();
+    let mut gradient = vec![0.0; dims];
+    let mut value = f(&x, &mut gradient);
+    let mut step = 1.0;
+    let mut iterations = 0;
+    let mut scratch = vec![0.0; dims];
+
+    while iterations < max_iterations {
+        iterations += 1;
+
+        // Shrink the step until the quadratic upper bound holds
+        let mut candidate = vec![0.0; dims];
+        let mut candidate_value;
+        loop {
+            for i in 0..dims {
+                candidate[i] = x[i] - step * gradient[i];
+            }
+            prox(&mut candidate, step);
+            candidate_value = f(&candidate, &mut scratch);
+
+            let mut bound = value;
+            let mut distance = 0.0;
+            for i in 0..dims {
+                let delta = candidate[i] - x[i];
+                bound += gradient[i] * delta;
+                distance += delta * delta;
+            }
+            bound += distance / (2.0 * step);
+
+            if candidate_value <= bound + 1e-12 * bound.abs() || step < 1e-20 {
+                break;
+            }
+            step *= 0.5;
+        }
+
+        let largest_move = (0..dims).fold(0.0f64, |m, i| m.max((candidate[i] - x[i]).abs()));
+        x = candidate;
+        value = candidate_value;
+        std::mem::swap(&mut gradient, &mut scratch);
+
+        if largest_move <= tolerance {
+            break;
+        }
+
+        // Let the step grow again so it tracks the local curvature
+        step *= 1.5;
+    }
+
+    let total = value + penalty(&x);
+    (x, total, iterations)
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
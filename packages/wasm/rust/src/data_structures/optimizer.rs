@@ -0,0 +1,327 @@
+use super::network::Parameter;
+
+/// Update rule applied to every parameter
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum UpdateRule {
+    /// Stochastic gradient descent, optionally with (Nesterov) momentum
+    Sgd { momentum: f64, nesterov: bool },
+    /// Divide by a running root mean square of the gradients
+    RmsProp { decay: f64, epsilon: f64 },
+    /// Adam with bias-corrected moments; a non-zero `weight_decay` gives AdamW
+    Adam { beta1: f64, beta2: f64, epsilon: f64, weight_decay: f64 },
+    /// Divide by the root of the accumulated squared gradients
+    Adagrad { epsilon: f64 },
+}
+
+/// Learning-rate schedule over optimizer steps, applied after warmup
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Schedule {
+    Constant,
+    /// Multiply by `gamma` every `step_size` steps
+    Step { step_size: u64, gamma: f64 },
+    /// Multiply by `gamma` every step
+    Exponential { gamma: f64 },
+    /// Cosine annealing to `min_learning_rate` over `period` steps, then held there
+    Cosine { period: u64, min_learning_rate: f64 },
+}
+
+/// Optimizer with per-parameter state, gradient clipping and L2 regularization
+///
+/// State is kept per parameter in the order the parameters are passed to `update`, so the
+/// caller must pass them in the same order every step and call `reset` when it changes.
+#[derive(Clone, Debug)]
+pub(crate) struct Optimizer {
+    pub(crate) rule: UpdateRule,
+    pub(crate) learning_rate: f64,
+    pub(crate) schedule: Schedule,
+    pub(crate) warmup_steps: u64,
+    /// L2 penalty added to the gradients (coupled weight decay)
+    pub(crate) l2: f64,
+    /// Rescale all gradients together when their global norm exceeds this
+    pub(crate) clip_norm: Option<f64>,
+    /// Clamp every gradient to [-clip_value, clip_value]
+    pub(crate) clip_value: Option<f64>,
+    pub(crate) steps: u64,
+    first_moments: Vec<Vec<f64>>,
+    second_moments: Vec<Vec<f64>>,
+}
+
+impl Optimizer {
+    /// Plain SGD at the given learning rate
+    pub(crate) fn new(learning_rate: f64) -> Optimizer {
+        Optimizer {
+            rule: UpdateRule::Sgd { momentum: 0.0, nesterov: false },
+            learning_rate,
+            schedule: Schedule::Constant,
+            warmup_steps: 0,
+            l2: 0.0,
+            clip_norm: None,
+            clip_value: None,
+            steps: 0,
+            first_moments: Vec::new(),
+            second_moments: Vec::new(),
+        }
+    }
+
+    /// Switch the update rule, discarding accumulated state
+    pub(crate) fn set_rule(&mut self, rule: UpdateRule) {
+        self.rule = rule;
+        self.reset();
+    }
+
+    /// Forget the per-parameter state and restart the schedule
+    pub(crate) fn reset(&mut self) {
+        self.steps = 0;
+        self.first_moments.clear();
+        self.second_moments.clear();
+    }
+
+    /// Learning rate for the next step after warmup and the schedule
+    pub(crate) fn current_learning_rate(&self) -> f64 {
+        let warmup = if self.steps < self.warmup_steps { (self.steps + 1) as f64 / self.warmup_steps as f64 } else { 1.0 };
+        let t = self.steps.saturating_sub(self.warmup_steps);
+
+        let scheduled = match self.schedule {
+            Schedule::Constant => self.learning_rate,
+            Schedule::Step { step_size, gamma } => self.learning_rate * gamma.powi((t / step_size) as i32),
+            Schedule::Exponential { gamma } => self.learning_rate * gamma.powf(t as f64),
+            Schedule::Cosine { period, min_learning_rate } => {
+                let progress = t.min(period) as f64 / period as f64;
+                min_learning_rate + (self.learning_rate - min_learning_rate) * 0.5 * (1.0 + (std::f64::consts::PI * progress).cos())
+            }
+        };
+        scheduled * warmup
+    }
+
+    /// Apply one update to every parameter from its stored gradients
+    pub(crate) fn update(&mut self, parameters: &mut [&mut Parameter]) {
+        let learning_rate = self.current_learning_rate();
+        self.clip(parameters);
+
+        if self.first_moments.len() != parameters.len() {
+            self.first_moments = parameters.iter().map(|p| vec![0.0; p.values.len()]).collect();
+            self.second_moments = parameters.iter().map(|p| vec![0.0; p.values.len()]).collect();
+        }
+        self.steps += 1;
+
+        for (index, parameter) in parameters.iter_mut().enumerate() {
+            let first = &mut self.first_moments[index];
+            let second = &mut self.second_moments[index];
+            let Parameter { values, gradients } = &mut **parameter;
+
+            for i in 0..values.len() {
+                let g = gradients[i] + self.l2 * values[i];
+                match self.rule {
+                    UpdateRule::Sgd { momentum, nesterov } => {
+                        if momentum == 0.0 {
+                            values[i] -= learning_rate * g;
+                        } else {
+                            first[i] = momentum * first[i] + g;
+                            let direction = if nesterov { g + momentum * first[i] } else { first[i] };
+                            values[i] -= learning_rate * direction;
+                        }
+                    }
+                    UpdateRule::RmsProp { decay, epsilon } => {
+                        second[i] = decay * second[i] + (1.0 - decay) * g * g;
+                        values[i] -= learning_rate * g / (second[i].sqrt() + epsilon);
+                    }
+                    UpdateRule::Adam { beta1, beta2, epsilon, weight_decay } => {
+                        first[i] = beta1 * first[i] + (1.0 - beta1) * g;
+                        second[i] = beta2 * second[i] + (1.0 - beta2) * g * g;
+                        let first_hat = first[i] / (1.0 - beta1.powf(self.steps as f64));
+                        let second_hat = second[i] / (1.0 - beta2.powf(self.steps as f64));
+                        values[i] -= learning_rate * (first_hat / (second_hat.sqrt() + epsilon) + weight_decay * values[i]);
+                    }
+                    UpdateRule::Adagrad { epsilon } => {
+                        second[i] += g * g;
+                        values[i] -= learning_rate * g / (second[i].sqrt() + epsilon);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Clip the stored gradients by value, then by global norm
+    fn clip(&self, parameters: &mut [&mut Parameter]) {
+        if let Some(limit) = self.clip_value {
+            for parameter in parameters.iter_mut() {
+                parameter.gradients.iter_mut().for_each(|g| *g = g.clamp(-limit, limit));
+            }
+        }
+
+        if let Some(max_norm) = self.clip_norm {
+            let norm = parameters.iter().flat_map(|p| p.gradients.iter()).map(|g| g * g).sum::<f64>().sqrt();
+            if norm > max_norm {
+                let scale = max_norm / norm;
+                for parameter in parameters.iter_mut() {
+                    parameter.gradients.iter_mut().for_each(|g| *g *= scale);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One step on a single parameter holding `values` with the given gradients
+    fn step(optimizer: &mut Optimizer, parameter: &mut Parameter, gradients: &[f64]) {
+        parameter.gradients.copy_from_slice(gradients);
+        optimizer.update(&mut [parameter]);
+    }
+
+    fn assert_close(actual: &[f64], expected: &[f64]) {
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-12, "{:?} vs {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn sgd_with_and_without_momentum() {
+        let mut optimizer = Optimizer::new(0.1);
+        let mut parameter = Parameter::new(vec![1.0, -2.0]);
+        step(&mut optimizer, &mut parameter, &[2.0, -1.0]);
+        assert_close(&parameter.values, &[0.8, -1.9]);
+
+        // Velocity after two equal gradients: g, then 0.9 g + g
+        optimizer.set_rule(UpdateRule::Sgd { momentum: 0.9, nesterov: false });
+        let mut parameter = Parameter::new(vec![0.0]);
+        step(&mut optimizer, &mut parameter, &[1.0]);
+        step(&mut optimizer, &mut parameter, &[1.0]);
+        assert_close(&parameter.values, &[-0.1 - 0.19]);
+
+        // Nesterov looks ahead by one more momentum step
+        optimizer.set_rule(UpdateRule::Sgd { momentum: 0.9, nesterov: true });
+        let mut parameter = Parameter::new(vec![0.0]);
+        step(&mut optimizer, &mut parameter, &[1.0]);
+        assert_close(&parameter.values, &[-0.19]);
+    }
+
+    #[test]
+    fn adaptive_rules_first_step() {
+        let gradients = [4.0, -0.01];
+        let cases = [
+            (UpdateRule::Adam { beta1: 0.9, beta2: 0.999, epsilon: 1e-12, weight_decay: 0.0 }, [-0.1, 0.1]),
+            (UpdateRule::Adagrad { epsilon: 1e-12 }, [-0.1, 0.1]),
+            (UpdateRule::RmsProp { decay: 0.75, epsilon: 1e-12 }, [-0.2, 0.2]),
+        ];
+        for (rule, expected) in cases {
+            let mut optimizer = Optimizer::new(0.1);
+            optimizer.set_rule(rule);
+            let mut parameter = Parameter::new(vec![0.0, 0.0]);
+            step(&mut optimizer, &mut parameter, &gradients);
+
+            // Adam and Adagrad move by the learning rate, RMSProp by it over sqrt(1 - decay)
+            for (value, expected) in parameter.values.iter().zip(expected) {
+                assert!((value - expected).abs() < 1e-9, "{:?}: {:?}", rule, parameter.values);
+            }
+        }
+    }
+
+    #[test]
+    fn adamw_decays_weights_without_gradient() {
+        let mut optimizer = Optimizer::new(0.1);
+        optimizer.set_rule(UpdateRule::Adam { beta1: 0.9, beta2: 0.999, epsilon: 1e-8, weight_decay: 0.5 });
+        let mut parameter = Parameter::new(vec![2.0, -4.0]);
+        step(&mut optimizer, &mut parameter, &[0.0, 0.0]);
+        assert_close(&parameter.values, &[1.9, -3.8]);
+    }
+
+    #[test]
+    fn l2_penalty_adds_to_the_gradient() {
+        let mut optimizer = Optimizer::new(0.5);
+        optimizer.l2 = 0.1;
+        let mut parameter = Parameter::new(vec![2.0]);
+        step(&mut optimizer, &mut parameter, &[1.0]);
+        assert_close(&parameter.values, &[2.0 - 0.5 * 1.2]);
+    }
+
+    #[test]
+    fn clipping_by_value_then_norm() {
+        let mut optimizer = Optimizer::new(1.0);
+        optimizer.clip_value = Some(3.0);
+        optimizer.clip_norm = Some(2.5);
+        let mut first = Parameter::new(vec![0.0]);
+        let mut second = Parameter::new(vec![0.0]);
+        first.gradients[0] = 10.0;
+        second.gradients[0] = -4.0;
+        optimizer.update(&mut [&mut first, &mut second]);
+
+        // (3, -3) clamped by value, then scaled to norm 2.5
+        let scale = 2.5 / 18.0f64.sqrt();
+        assert_close(&[first.values[0], second.values[0]], &[-3.0 * scale, 3.0 * scale]);
+
+        // Gradients under both limits are untouched
+        let mut parameter = Parameter::new(vec![0.0]);
+        step(&mut optimizer, &mut parameter, &[1.0]);
+        assert_close(&parameter.values, &[-1.0]);
+    }
+
+    #[test]
+    fn schedules_and_warmup() {
+        let rate_at = |schedule: Schedule, warmup_steps: u64, steps: u64| {
+            let mut optimizer = Optimizer::new(1.0);
+            optimizer.schedule = schedule;
+            optimizer.warmup_steps = warmup_steps;
+            optimizer.steps = steps;
+            optimizer.current_learning_rate()
+        };
+
+        let step_schedule = Schedule::Step { step_size: 3, gamma: 0.5 };
+        let rates: Vec<f64> = (0..7).map(|t| rate_at(step_schedule, 0, t)).collect();
+        assert_close(&rates, &[1.0, 1.0, 1.0, 0.5, 0.5, 0.5, 0.25]);
+        assert_close(&[rate_at(Schedule::Exponential { gamma: 0.9 }, 0, 2)], &[0.81]);
+
+        let cosine = Schedule::Cosine { period: 4, min_learning_rate: 0.2 };
+        let rates: Vec<f64> = [0, 2, 4, 10].iter().map(|&t| rate_at(cosine, 0, t)).collect();
+        assert_close(&rates, &[1.0, 0.6, 0.2, 0.2]);
+
+        // Warmup ramps up linearly, then the schedule starts from its first step
+        let rates: Vec<f64> = (0..6).map(|t| rate_at(step_schedule, 4, t)).collect();
+        assert_close(&rates, &[0.25, 0.5, 0.75, 1.0, 1.0, 1.0]);
+        assert_close(&[rate_at(step_schedule, 4, 7)], &[0.5]);
+    }
+
+    #[test]
+    fn every_rule_minimises_a_quadratic() {
+        let target = [3.0, -1.0, 0.5];
+        // Adagrad's steps shrink with the accumulated gradients, so it starts larger
+        let rules = [
+            (UpdateRule::Sgd { momentum: 0.0, nesterov: false }, 0.05),
+            (UpdateRule::Sgd { momentum: 0.9, nesterov: false }, 0.05),
+            (UpdateRule::Sgd { momentum: 0.9, nesterov: true }, 0.05),
+            (UpdateRule::RmsProp { decay: 0.9, epsilon: 1e-8 }, 0.05),
+            (UpdateRule::Adam { beta1: 0.9, beta2: 0.999, epsilon: 1e-8, weight_decay: 0.0 }, 0.05),
+            (UpdateRule::Adagrad { epsilon: 1e-8 }, 1.0),
+        ];
+        for (rule, learning_rate) in rules {
+            let mut optimizer = Optimizer::new(learning_rate);
+            optimizer.set_rule(rule);
+            optimizer.schedule = Schedule::Exponential { gamma: 0.995 };
+            let mut parameter = Parameter::new(vec![0.0; 3]);
+            for _ in 0..2000 {
+                let gradients: Vec<f64> = parameter.values.iter().zip(&target).map(|(x, t)| 2.0 * (x - t)).collect();
+                step(&mut optimizer, &mut parameter, &gradients);
+            }
+            for (value, t) in parameter.values.iter().zip(&target) {
+                assert!((value - t).abs() < 1e-2, "{:?}: {:?}", rule, parameter.values);
+            }
+        }
+    }
+
+    #[test]
+    fn reset_forgets_state() {
+        let mut optimizer = Optimizer::new(0.1);
+        optimizer.set_rule(UpdateRule::Sgd { momentum: 0.9, nesterov: false });
+        let mut parameter = Parameter::new(vec![0.0]);
+        step(&mut optimizer, &mut parameter, &[1.0]);
+        assert_eq!(optimizer.steps, 1);
+
+        optimizer.reset();
+        assert_eq!(optimizer.steps, 0);
+        let mut parameter = Parameter::new(vec![0.0]);
+        step(&mut optimizer, &mut parameter, &[1.0]);
+        assert_close(&parameter.values, &[-0.1]);
+    }
+}
@@ -0,0 +1,349 @@
+use wasm_bindgen::prelude::*;
+use js_sys::{Float64Array, Object, Reflect};
+
+use super::linalg::{jacobi_svd, symmetric_eigen};
+use super::machine_learning::read_rows;
+
+/// Numerical methods for computing principal components
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PcaSolver {
+    /// Singular value decomposition of the centred data (more accurate)
+    Svd,
+    /// Eigendecomposition of the covariance matrix (faster when N is much larger than D)
+    Eigen,
+}
+
+/// Principal Component Analysis (PCA) implementation
+///
+/// Takes row-major data points and the number of components, and returns the principal
+/// components (one row of `dims` values per component), the projected data, the explained
+/// variance ratio (`explained_variance`), the absolute variance of each component
+/// (`component_variance`), the singular values and the feature means. The optional
+/// arguments set the dimensionality (default 2), whether to whiten the projection and the
+/// solver (default SVD). For 2D data `mean_x` and `mean_y` are also returned.
+/// This is much faster than using JavaScript, especially for large datasets.
+#[wasm_bindgen]
+pub fn pca_f64(
+    data: &JsValue,
+    num_components: usize,
+    dims: Option<usize>,
+    whiten: Option<bool>,
+    solver: Option<PcaSolver>,
+) -> Result<JsValue, JsValue> {
+    let dims = dims.unwrap_or(2);
+    let (points, num_points) = read_rows(data, dims)?;
+
+    // Validate inputs
+    if num_points == 0 {
+        return Err(JsValue::from_str("Data must not be empty"));
+    }
+
+    if num_components == 0 || num_components > dims.min(num_points) {
+        return Err(JsValue::from_str("Number of components must be between 1 and min(points, dims)"));
+    }
+
+    let pca = Pca::from_values(&points, dims, num_components, whiten.unwrap_or(false), solver.unwrap_or(PcaSolver::Svd));
+    let projected = pca.transform_values(&points);
+
+    // Create result object
+    let result = Object::new();
+    Reflect::set(&result, &JsValue::from_str("components"), &Float64Array::from(&pca.components[..]))?;
+    Reflect::set(&result, &JsValue::from_str("projected"), &Float64Array::from(&projected[..]))?;
+    Reflect::set(
+        &result,
+        &JsValue::from_str("explained_variance"),
+        &Float64Array::from(&pca.explained_variance_ratio[..]),
+    )?;
+    Reflect::set(&result, &JsValue::from_str("component_variance"), &Float64Array::from(&pca.explained_variance[..]))?;
+    Reflect::set(&result, &JsValue::from_str("singular_values"), &Float64Array::from(&pca.singular_values[..]))?;
+    Reflect::set(&result, &JsValue::from_str("mean"), &Float64Array::from(&pca.mean[..]))?;
+
+    if dims == 2 {
+        Reflect::set(&result, &JsValue::from_str("mean_x"), &JsValue::from_f64(pca.mean[0]))?;
+        Reflect::set(&result, &JsValue::from_str("mean_y"), &JsValue::from_f64(pca.mean[1]))?;
+    }
+
+    Ok(result.into())
+}
+
+/// Fitted PCA model that can project new batches of data
+#[wasm_bindgen]
+pub struct Pca {
+    dims: usize,
+    num_components: usize,
+    whiten: bool,
+    /// Row-major `num_components x dims`
+    components: Vec<f64>,
+    explained_variance: Vec<f64>,
+    explained_variance_ratio: Vec<f64>,
+    singular_values: Vec<f64>,
+    mean: Vec<f64>,
+}
+
+#[wasm_bindgen]
+impl Pca {
+    /// Fit PCA to row-major data
+    ///
+    /// Takes the data, its dimensionality, the number of components to keep (default all),
+    /// whether `transform` should whiten (scale components to unit variance) and the solver
+    /// (default SVD).
+    pub fn fit(
+        data: &JsValue,
+        dims: usize,
+        num_components: Option<usize>,
+        whiten: bool,
+        solver: Option<PcaSolver>,
+    ) -> Result<Pca, JsValue> {
+        let (points, num_points) = read_rows(data, dims)?;
+
+        // Validate inputs
+        if num_points < 2 {
+            return Err(JsValue::from_str("At least 2 data points are required for PCA"));
+        }
+
+        let num_components = num_components.unwrap_or(dims.min(num_points));
+        if num_components == 0 || num_components > dims.min(num_points) {
+            return Err(JsValue::from_str("Number of components must be between 1 and min(points, dims)"));
+        }
+
+        Ok(Pca::from_values(&points, dims, num_components, whiten, solver.unwrap_or(PcaSolver::Svd)))
+    }
+
+    /// Project row-major data onto the components
+    pub fn transform(&self, data: &JsValue) -> Result<JsValue, JsValue> {
+        let (points, _) = read_rows(data, self.dims)?;
+        Ok(Float64Array::from(&self.transform_values(&points)[..]).into())
+    }
+
+    /// Map projected data back to the original feature space
+    pub fn inverse_transform(&self, data: &JsValue) -> Result<JsValue, JsValue> {
+        let (projected, _) = read_rows(data, self.num_components)?;
+        Ok(Float64Array::from(&self.inverse_transform_values(&projected)[..]).into())
+    }
+
+    /// Principal axes, one row of `dims` values per component
+    #[wasm_bindgen(getter)]
+    pub fn components(&self) -> Float64Array {
+        Float64Array::from(&self.components[..])
+    }
+
+    /// Variance captured by each component
+    #[wasm_bindgen(getter)]
+    pub fn explained_variance(&self) -> Float64Array {
+        Float64Array::from(&self.explained_variance[..])
+    }
+
+    /// Fraction of the total variance captured by each component
+    #[wasm_bindgen(getter)]
+    pub fn explained_variance_ratio(&self) -> Float64Array {
+        Float64Array::from(&self.explained_variance_ratio[..])
+    }
+
+    /// Singular values of the centred data for each component
+    #[wasm_bindgen(getter)]
+    pub fn singular_values(&self) -> Float64Array {
+        Float64Array::from(&self.singular_values[..])
+    }
+
+    /// Per-feature mean removed before projection
+    #[wasm_bindgen(getter)]
+    pub fn mean(&self) -> Float64Array {
+        Float64Array::from(&self.mean[..])
+    }
+
+    /// Number of input features
+    #[wasm_bindgen(getter)]
+    pub fn dims(&self) -> usize {
+        self.dims
+    }
+
+    /// Number of components kept
+    #[wasm_bindgen(getter)]
+    pub fn num_components(&self) -> usize {
+        self.num_components
+    }
+}
+
+impl Pca {
+    /// Fit the model to already validated row-major data
+    pub(crate) fn from_values(points: &[f64], dims: usize, num_components: usize, whiten: bool, solver: PcaSolver) -> Pca {
+        let num_points = points.len() / dims;
+
+        // Centre the data
+        let mut mean = vec![0.0; dims];
+        for row in points.chunks_exact(dims) {
+            for (m, &value) in mean.iter_mut().zip(row) {
+                *m += value / num_points as f64;
+            }
+        }
+        let centred: Vec<f64> = points
+            .chunks_exact(dims)
+            .flat_map(|row| row.iter().zip(&mean).map(|(value, m)| value - m).collect::<Vec<f64>>())
+            .collect();
+
+        let dof = (num_points.max(2) - 1) as f64;
+        let (singular_values, mut axes) = match solver {
+            PcaSolver::Svd => jacobi_svd(&centred, num_points, dims),
+            PcaSolver::Eigen => {
+                let mut covariance = vec![0.0; dims * dims];
+                for row in centred.chunks_exact(dims) {
+                    for i in 0..dims {
+                        for j in i..dims {
+                            covariance[i * dims + j] += row[i] * row[j];
+                        }
+                    }
+                }
+                for i in 0..dims {
+                    for j in i..dims {
+                        covariance[j * dims + i] = covariance[i * dims + j];
+                    }
+                }
+                let (eigenvalues, vectors) = symmetric_eigen(&covariance, dims);
+                (eigenvalues.iter().map(|&e| e.max(0.0).sqrt()).collect(), vectors)
+            }
+        };
+
+        // Deterministic signs: the largest loading of each axis is positive
+        for axis in axes.chunks_exact_mut(dims) {
+            let largest = axis.iter().fold(0.0f64, |best, &x| if x.abs() > best.abs() { x } else { best });
+            if largest < 0.0 {
+                axis.iter_mut().for_each(|x| *x = -*x);
+            }
+        }
+
+        let total_variance: f64 = singular_values.iter().map(|s| s * s / dof).sum();
+        let singular_values: Vec<f64> = singular_values[..num_components].to_vec();
+        let explained_variance: Vec<f64> = singular_values.iter().map(|s| s * s / dof).collect();
+        let explained_variance_ratio = explained_variance
+            .iter()
+            .map(|v| if total_variance > 0.0 { v / total_variance } else { 0.0 })
+            .collect();
+
+        Pca {
+            dims,
+            num_components,
+            whiten,
+            components: axes[..num_components * dims].to_vec(),
+            explained_variance,
+            explained_variance_ratio,
+            singular_values,
+            mean,
+        }
+    }
+
+    /// Project validated row-major data
+    pub(crate) fn transform_values(&self, points: &[f64]) -> Vec<f64> {
+        let mut projected = Vec::with_capacity(points.len() / self.dims * self.num_components);
+        for row in points.chunks_exact(self.dims) {
+            for (c, axis) in self.components.chunks_exact(self.dims).enumerate() {
+                let mut value: f64 = row.iter().zip(&self.mean).zip(axis).map(|((x, m), a)| (x - m) * a).sum();
+                if self.whiten {
+                    value /= self.explained_variance[c].sqrt().max(f64::EPSILON);
+                }
+                projected.push(value);
+            }
+        }
+        projected
+    }
+
+    /// Reconstruct validated row-major projections
+    pub(crate) fn inverse_transform_values(&self, projected: &[f64]) -> Vec<f64> {
+        let mut points = Vec::with_capacity(projected.len() / self.num_components * self.dims);
+        for row in projected.chunks_exact(self.num_components) {
+            let mut point = self.mean.clone();
+            for (c, axis) in self.components.chunks_exact(self.dims).enumerate() {
+                let scale = if self.whiten { row[c] * self.explained_variance[c].sqrt() } else { row[c] };
+                for (p, a) in point.iter_mut().zip(axis) {
+                    *p += scale * a;
+                }
+            }
+            points.extend(point);
+        }
+        points
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::random::Rng;
+
+    /// Points spread mostly along (1, 1, 0) with small noise on the other axes
+    fn elongated(rng: &mut Rng) -> Vec<f64> {
+        (0..200)
+            .flat_map(|_| {
+                let t = 3.0 * rng.next_normal();
+                let (u, w) = (0.3 * rng.next_normal(), 0.1 * rng.next_normal());
+                [5.0 + t + u, -2.0 + t - u, 1.0 + w]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn first_component_follows_the_dominant_direction() {
+        let points = elongated(&mut Rng::new(2));
+        let pca = Pca::from_values(&points, 3, 2, false, PcaSolver::Svd);
+
+        let axis = &pca.components[..3];
+        let expected = 0.5f64.sqrt();
+        assert!((axis[0] - expected).abs() < 0.01 && (axis[1] - expected).abs() < 0.01 && axis[2].abs() < 0.01);
+        assert!((pca.mean[0] - 5.0).abs() < 0.5 && (pca.mean[2] - 1.0).abs() < 0.05);
+        assert!(pca.explained_variance_ratio[0] > 0.98);
+        assert!(pca.explained_variance[0] > pca.explained_variance[1]);
+    }
+
+    #[test]
+    fn solvers_agree() {
+        let points = elongated(&mut Rng::new(6));
+        let svd = Pca::from_values(&points, 3, 3, false, PcaSolver::Svd);
+        let eigen = Pca::from_values(&points, 3, 3, false, PcaSolver::Eigen);
+
+        for (a, b) in svd.components.iter().zip(&eigen.components) {
+            assert!((a - b).abs() < 1e-8);
+        }
+        for (a, b) in svd.explained_variance.iter().zip(&eigen.explained_variance) {
+            assert!((a - b).abs() < 1e-8 * a.max(1.0));
+        }
+        assert!((svd.explained_variance_ratio.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn explained_variance_is_the_variance_of_the_projection() {
+        let points = elongated(&mut Rng::new(9));
+        let pca = Pca::from_values(&points, 3, 3, false, PcaSolver::Svd);
+        let projected = pca.transform_values(&points);
+
+        for c in 0..3 {
+            let scores: Vec<f64> = projected.iter().skip(c).step_by(3).copied().collect();
+            let mean = scores.iter().sum::<f64>() / scores.len() as f64;
+            let variance = scores.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / (scores.len() - 1) as f64;
+            assert!(mean.abs() < 1e-9);
+            assert!((variance - pca.explained_variance[c]).abs() < 1e-9 * variance.max(1.0));
+        }
+    }
+
+    #[test]
+    fn full_rank_projection_round_trips() {
+        let points = elongated(&mut Rng::new(12));
+        for whiten in [false, true] {
+            let pca = Pca::from_values(&points, 3, 3, whiten, PcaSolver::Svd);
+            let restored = pca.inverse_transform_values(&pca.transform_values(&points));
+            for (a, b) in points.iter().zip(&restored) {
+                assert!((a - b).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn whitened_components_have_unit_variance() {
+        let points = elongated(&mut Rng::new(15));
+        let pca = Pca::from_values(&points, 3, 2, true, PcaSolver::Eigen);
+        let projected = pca.transform_values(&points);
+
+        for c in 0..2 {
+            let variance = projected.iter().skip(c).step_by(2).map(|s| s * s).sum::<f64>() / 199.0;
+            assert!((variance - 1.0).abs() < 1e-9);
+        }
+    }
+}
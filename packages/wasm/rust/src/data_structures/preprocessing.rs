@@ -0,0 +1,1313 @@
+use wasm_bindgen::prelude::*;
+use js_sys::{Float64Array, Uint32Array};
+
+use super::distributions::{normal_cdf, normal_quantile};
+use super::pca::{Pca, PcaSolver};
+use super::regression::{expand_polynomial, polynomial_terms};
+
+/// Memory order of a matrix passed as a flat `Float64Array`
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MatrixLayout {
+    /// One sample after another
+    RowMajor,
+    /// One feature after another
+    ColumnMajor,
+}
+
+/// Statistic used to fill missing values
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImputeStrategy {
+    /// Mean of the observed values
+    Mean,
+    /// Median of the observed values
+    Median,
+    /// Most common observed value (the smallest on ties)
+    MostFrequent,
+}
+
+/// Target distribution of a quantile transformer
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum QuantileOutput {
+    /// Uniform on [0, 1]
+    Uniform,
+    /// Standard normal
+    Normal,
+}
+
+/// Fitted transformation between row-major matrices, shared by the transformers and
+/// `Pipeline`
+pub(crate) trait Transform {
+    fn input_dims(&self) -> usize;
+    fn output_dims(&self) -> usize;
+    fn transform_rows(&self, rows: &[f64]) -> Result<Vec<f64>, String>;
+    fn inverse_rows(&self, rows: &[f64]) -> Result<Vec<f64>, String>;
+}
+
+/// Standardise features to zero mean and unit variance
+///
+/// Missing values (NaN) are ignored when fitting and stay missing.
+#[wasm_bindgen]
+pub struct StandardScaler {
+    mean: Vec<f64>,
+    scale: Vec<f64>,
+}
+
+#[wasm_bindgen]
+impl StandardScaler {
+    /// Learn per-feature means and standard deviations
+    ///
+    /// Takes the data, the number of features, the layout (default row-major) and whether
+    /// to centre (default true) and scale (default true).
+    pub fn fit(
+        data: &JsValue,
+        dims: usize,
+        layout: Option<MatrixLayout>,
+        with_mean: Option<bool>,
+        with_std: Option<bool>,
+    ) -> Result<StandardScaler, JsValue> {
+        let (rows, _) = read_matrix(data, dims, layout)?;
+        StandardScaler::from_values(&rows, dims, with_mean.unwrap_or(true), with_std.unwrap_or(true)).map_err(|message| JsValue::from_str(&message))
+    }
+
+    /// Standardise data in the given layout (default row-major)
+    pub fn transform(&self, data: &JsValue, layout: Option<MatrixLayout>) -> Result<JsValue, JsValue> {
+        apply(self, data, layout, false)
+    }
+
+    /// Undo the standardisation
+    pub fn inverse_transform(&self, data: &JsValue, layout: Option<MatrixLayout>) -> Result<JsValue, JsValue> {
+        apply(self, data, layout, true)
+    }
+
+    /// Per-feature mean removed (0 when not centring)
+    #[wasm_bindgen(getter)]
+    pub fn mean(&self) -> Float64Array {
+        Float64Array::from(&self.mean[..])
+    }
+
+    /// Per-feature divisor (1 when not scaling or for constant features)
+    #[wasm_bindgen(getter)]
+    pub fn scale(&self) -> Float64Array {
+        Float64Array::from(&self.scale[..])
+    }
+}
+
+impl StandardScaler {
+    pub(crate) fn from_values(rows: &[f64], dims: usize, with_mean: bool, with_std: bool) -> Result<StandardScaler, String> {
+        let mut mean = vec![0.0; dims];
+        let mut scale = vec![1.0; dims];
+        for d in 0..dims {
+            let values = observed(rows, dims, d);
+            if values.is_empty() {
+                return Err(format!("Feature {} has no observed values", d));
+            }
+            let m = values.iter().sum::<f64>() / values.len() as f64;
+            if with_mean {
+                mean[d] = m;
+            }
+            if with_std {
+                let std = (values.iter().map(|v| (v - m) * (v - m)).sum::<f64>() / values.len() as f64).sqrt();
+                scale[d] = if std > 0.0 { std } else { 1.0 };
+            }
+        }
+        Ok(StandardScaler { mean, scale })
+    }
+}
+
+impl Transform for StandardScaler {
+    fn input_dims(&self) -> usize {
+        self.mean.len()
+    }
+
+    fn output_dims(&self) -> usize {
+        self.mean.len()
+    }
+
+    fn transform_rows(&self, rows: &[f64]) -> Result<Vec<f64>, String> {
+        Ok(map_columns(rows, self.mean.len(), |d, x| (x - self.mean[d]) / self.scale[d]))
+    }
+
+    fn inverse_rows(&self, rows: &[f64]) -> Result<Vec<f64>, String> {
+        Ok(map_columns(rows, self.mean.len(), |d, x| x * self.scale[d] + self.mean[d]))
+    }
+}
+
+/// Rescale features linearly to a fixed range
+///
+/// Missing values (NaN) are ignored when fitting and stay missing.
+#[wasm_bindgen]
+pub struct MinMaxScaler {
+    data_min: Vec<f64>,
+    data_max: Vec<f64>,
+    range_min: f64,
+    range_max: f64,
+}
+
+#[wasm_bindgen]
+impl MinMaxScaler {
+    /// Learn per-feature minima and maxima
+    ///
+    /// Takes the data, the number of features, the layout (default row-major) and the
+    /// target range (default [0, 1]).
+    pub fn fit(
+        data: &JsValue,
+        dims: usize,
+        layout: Option<MatrixLayout>,
+        range_min: Option<f64>,
+        range_max: Option<f64>,
+    ) -> Result<MinMaxScaler, JsValue> {
+        let (range_min, range_max) = (range_min.unwrap_or(0.0), range_max.unwrap_or(1.0));
+        if range_min.is_nan() || range_max.is_nan() || range_min >= range_max {
+            return Err(JsValue::from_str("Range minimum must be less than the range maximum"));
+        }
+
+        let (rows, _) = read_matrix(data, dims, layout)?;
+        MinMaxScaler::from_values(&rows, dims, range_min, range_max).map_err(|message| JsValue::from_str(&message))
+    }
+
+    /// Rescale data in the given layout (default row-major)
+    pub fn transform(&self, data: &JsValue, layout: Option<MatrixLayout>) -> Result<JsValue, JsValue> {
+        apply(self, data, layout, false)
+    }
+
+    /// Undo the rescaling
+    pub fn inverse_transform(&self, data: &JsValue, layout: Option<MatrixLayout>) -> Result<JsValue, JsValue> {
+        apply(self, data, layout, true)
+    }
+
+    /// Per-feature minimum seen during fitting
+    #[wasm_bindgen(getter)]
+    pub fn data_min(&self) -> Float64Array {
+        Float64Array::from(&self.data_min[..])
+    }
+
+    /// Per-feature maximum seen during fitting
+    #[wasm_bindgen(getter)]
+    pub fn data_max(&self) -> Float64Array {
+        Float64Array::from(&self.data_max[..])
+    }
+}
+
+impl MinMaxScaler {
+    pub(crate) fn from_values(rows: &[f64], dims: usize, range_min: f64, range_max: f64) -> Result<MinMaxScaler, String> {
+        let columns: Vec<Vec<f64>> = (0..dims).map(|d| observed(rows, dims, d)).collect();
+        if let Some(d) = columns.iter().position(|c| c.is_empty()) {
+            return Err(format!("Feature {} has no observed values", d));
+        }
+        let data_min = columns.iter().map(|c| c.iter().cloned().fold(f64::INFINITY, f64::min)).collect();
+        let data_max = columns.iter().map(|c| c.iter().cloned().fold(f64::NEG_INFINITY, f64::max)).collect();
+        Ok(MinMaxScaler { data_min, data_max, range_min, range_max })
+    }
+
+    /// Width of the fitted data range, with constant features treated as unit width
+    fn width(&self, d: usize) -> f64 {
+        let width = self.data_max[d] - self.data_min[d];
+        if width > 0.0 { width } else { 1.0 }
+    }
+}
+
+impl Transform for MinMaxScaler {
+    fn input_dims(&self) -> usize {
+        self.data_min.len()
+    }
+
+    fn output_dims(&self) -> usize {
+        self.data_min.len()
+    }
+
+    fn transform_rows(&self, rows: &[f64]) -> Result<Vec<f64>, String> {
+        let span = self.range_max - self.range_min;
+        Ok(map_columns(rows, self.data_min.len(), |d, x| self.range_min + (x - self.data_min[d]) / self.width(d) * span))
+    }
+
+    fn inverse_rows(&self, rows: &[f64]) -> Result<Vec<f64>, String> {
+        let span = self.range_max - self.range_min;
+        Ok(map_columns(rows, self.data_min.len(), |d, x| self.data_min[d] + (x - self.range_min) / span * self.width(d)))
+    }
+}
+
+/// Centre on the median and scale by an interquantile range, which resists outliers
+///
+/// Missing values (NaN) are ignored when fitting and stay missing.
+#[wasm_bindgen]
+pub struct RobustScaler {
+    center: Vec<f64>,
+    scale: Vec<f64>,
+}
+
+#[wasm_bindgen]
+impl RobustScaler {
+    /// Learn per-feature medians and interquantile ranges
+    ///
+    /// Takes the data, the number of features, the layout (default row-major) and the
+    /// quantile range in percent (default 25 to 75).
+    pub fn fit(
+        data: &JsValue,
+        dims: usize,
+        layout: Option<MatrixLayout>,
+        quantile_low: Option<f64>,
+        quantile_high: Option<f64>,
+    ) -> Result<RobustScaler, JsValue> {
+        let (low, high) = (quantile_low.unwrap_or(25.0), quantile_high.unwrap_or(75.0));
+        if !(0.0 <= low && low < high && high <= 100.0) {
+            return Err(JsValue::from_str("Quantile range must satisfy 0 <= low < high <= 100"));
+        }
+
+        let (rows, _) = read_matrix(data, dims, layout)?;
+        RobustScaler::from_values(&rows, dims, low, high).map_err(|message| JsValue::from_str(&message))
+    }
+
+    /// Scale data in the given layout (default row-major)
+    pub fn transform(&self, data: &JsValue, layout: Option<MatrixLayout>) -> Result<JsValue, JsValue> {
+        apply(self, data, layout, false)
+    }
+
+    /// Undo the scaling
+    pub fn inverse_transform(&self, data: &JsValue, layout: Option<MatrixLayout>) -> Result<JsValue, JsValue> {
+        apply(self, data, layout, true)
+    }
+
+    /// Per-feature median
+    #[wasm_bindgen(getter)]
+    pub fn center(&self) -> Float64Array {
+        Float64Array::from(&self.center[..])
+    }
+
+    /// Per-feature interquantile range (1 for constant features)
+    #[wasm_bindgen(getter)]
+    pub fn scale(&self) -> Float64Array {
+        Float64Array::from(&self.scale[..])
+    }
+}
+
+impl RobustScaler {
+    pub(crate) fn from_values(rows: &[f64], dims: usize, low: f64, high: f64) -> Result<RobustScaler, String> {
+        let mut center = Vec::with_capacity(dims);
+        let mut scale = Vec::with_capacity(dims);
+        for d in 0..dims {
+            let sorted = sorted_observed(rows, dims, d);
+            if sorted.is_empty() {
+                return Err(format!("Feature {} has no observed values", d));
+            }
+            center.push(quantile_sorted(&sorted, 0.5));
+            let range = quantile_sorted(&sorted, high / 100.0) - quantile_sorted(&sorted, low / 100.0);
+            scale.push(if range > 0.0 { range } else { 1.0 });
+        }
+        Ok(RobustScaler { center, scale })
+    }
+}
+
+impl Transform for RobustScaler {
+    fn input_dims(&self) -> usize {
+        self.center.len()
+    }
+
+    fn output_dims(&self) -> usize {
+        self.center.len()
+    }
+
+    fn transform_rows(&self, rows: &[f64]) -> Result<Vec<f64>, String> {
+        Ok(map_columns(rows, self.center.len(), |d, x| (x - self.center[d]) / self.scale[d]))
+    }
+
+    fn inverse_rows(&self, rows: &[f64]) -> Result<Vec<f64>, String> {
+        Ok(map_columns(rows, self.center.len(), |d, x| x * self.scale[d] + self.center[d]))
+    }
+}
+
+/// Expand categorical features into one indicator column per category
+#[wasm_bindgen]
+pub struct OneHotEncoder {
+    categories: Vec<Vec<f64>>,
+    ignore_unknown: bool,
+}
+
+#[wasm_bindgen]
+impl OneHotEncoder {
+    /// Learn the sorted categories of every feature
+    ///
+    /// Takes the data, the number of features, the layout (default row-major) and whether
+    /// unseen categories encode as all zeros instead of failing (default false).
+    pub fn fit(data: &JsValue, dims: usize, layout: Option<MatrixLayout>, ignore_unknown: Option<bool>) -> Result<OneHotEncoder, JsValue> {
+        let (rows, _) = read_matrix(data, dims, layout)?;
+        OneHotEncoder::from_values(&rows, dims, ignore_unknown.unwrap_or(false)).map_err(|message| JsValue::from_str(&message))
+    }
+
+    /// Encode data in the given layout (default row-major)
+    pub fn transform(&self, data: &JsValue, layout: Option<MatrixLayout>) -> Result<JsValue, JsValue> {
+        apply(self, data, layout, false)
+    }
+
+    /// Recover categories from indicator columns (the largest indicator wins; all-zero
+    /// blocks decode to NaN)
+    pub fn inverse_transform(&self, data: &JsValue, layout: Option<MatrixLayout>) -> Result<JsValue, JsValue> {
+        apply(self, data, layout, true)
+    }
+
+    /// Categories of all features concatenated in feature order
+    #[wasm_bindgen(getter)]
+    pub fn categories(&self) -> Float64Array {
+        let flat: Vec<f64> = self.categories.iter().flatten().copied().collect();
+        Float64Array::from(&flat[..])
+    }
+
+    /// Number of categories per feature
+    #[wasm_bindgen(getter)]
+    pub fn category_counts(&self) -> Uint32Array {
+        let counts: Vec<u32> = self.categories.iter().map(|c| c.len() as u32).collect();
+        Uint32Array::from(&counts[..])
+    }
+}
+
+impl OneHotEncoder {
+    pub(crate) fn from_values(rows: &[f64], dims: usize, ignore_unknown: bool) -> Result<OneHotEncoder, String> {
+        Ok(OneHotEncoder { categories: learn_categories(rows, dims)?, ignore_unknown })
+    }
+}
+
+impl Transform for OneHotEncoder {
+    fn input_dims(&self) -> usize {
+        self.categories.len()
+    }
+
+    fn output_dims(&self) -> usize {
+        self.categories.iter().map(|c| c.len()).sum()
+    }
+
+    fn transform_rows(&self, rows: &[f64]) -> Result<Vec<f64>, String> {
+        let mut encoded = Vec::with_capacity(rows.len() / self.input_dims() * self.output_dims());
+        for row in rows.chunks_exact(self.input_dims()) {
+            for (&value, categories) in row.iter().zip(&self.categories) {
+                let position = category_position(categories, value);
+                if position.is_none() && !self.ignore_unknown {
+                    return Err(format!("Unknown category {}", value));
+                }
+                encoded.extend((0..categories.len()).map(|c| if Some(c) == position { 1.0 } else { 0.0 }));
+            }
+        }
+        Ok(encoded)
+    }
+
+    fn inverse_rows(&self, rows: &[f64]) -> Result<Vec<f64>, String> {
+        let mut decoded = Vec::with_capacity(rows.len() / self.output_dims() * self.input_dims());
+        for row in rows.chunks_exact(self.output_dims()) {
+            let mut start = 0;
+            for categories in &self.categories {
+                let block = &row[start..start + categories.len()];
+                let best = (0..block.len()).fold(0, |best, c| if block[c] > block[best] { c } else { best });
+                decoded.push(if block[best] > 0.0 { categories[best] } else { f64::NAN });
+                start += categories.len();
+            }
+        }
+        Ok(decoded)
+    }
+}
+
+/// Replace categorical values with their index among the sorted categories
+#[wasm_bindgen]
+pub struct OrdinalEncoder {
+    categories: Vec<Vec<f64>>,
+    unknown_value: Option<f64>,
+}
+
+#[wasm_bindgen]
+impl OrdinalEncoder {
+    /// Learn the sorted categories of every feature
+    ///
+    /// Takes the data, the number of features, the layout (default row-major) and an
+    /// optional code for unseen categories (by default they are an error).
+    pub fn fit(data: &JsValue, dims: usize, layout: Option<MatrixLayout>, unknown_value: Option<f64>) -> Result<OrdinalEncoder, JsValue> {
+        let (rows, _) = read_matrix(data, dims, layout)?;
+        OrdinalEncoder::from_values(&rows, dims, unknown_value).map_err(|message| JsValue::from_str(&message))
+    }
+
+    /// Encode data in the given layout (default row-major)
+    pub fn transform(&self, data: &JsValue, layout: Option<MatrixLayout>) -> Result<JsValue, JsValue> {
+        apply(self, data, layout, false)
+    }
+
+    /// Map codes back to categories (unknown codes decode to NaN)
+    pub fn inverse_transform(&self, data: &JsValue, layout: Option<MatrixLayout>) -> Result<JsValue, JsValue> {
+        apply(self, data, layout, true)
+    }
+
+    /// Categories of all features concatenated in feature order
+    #[wasm_bindgen(getter)]
+    pub fn categories(&self) -> Float64Array {
+        let flat: Vec<f64> = self.categories.iter().flatten().copied().collect();
+        Float64Array::from(&flat[..])
+    }
+
+    /// Number of categories per feature
+    #[wasm_bindgen(getter)]
+    pub fn category_counts(&self) -> Uint32Array {
+        let counts: Vec<u32> = self.categories.iter().map(|c| c.len() as u32).collect();
+        Uint32Array::from(&counts[..])
+    }
+}
+
+impl OrdinalEncoder {
+    pub(crate) fn from_values(rows: &[f64], dims: usize, unknown_value: Option<f64>) -> Result<OrdinalEncoder, String> {
+        Ok(OrdinalEncoder { categories: learn_categories(rows, dims)?, unknown_value })
+    }
+}
+
+impl Transform for OrdinalEncoder {
+    fn input_dims(&self) -> usize {
+        self.categories.len()
+    }
+
+    fn output_dims(&self) -> usize {
+        self.categories.len()
+    }
+
+    fn transform_rows(&self, rows: &[f64]) -> Result<Vec<f64>, String> {
+        let dims = self.categories.len();
+        let mut encoded = Vec::with_capacity(rows.len());
+        for (i, &value) in rows.iter().enumerate() {
+            let code = match (category_position(&self.categories[i % dims], value), self.unknown_value) {
+                (Some(position), _) => position as f64,
+                (None, Some(unknown)) => unknown,
+                (None, None) => return Err(format!("Unknown category {}", value)),
+            };
+            encoded.push(code);
+        }
+        Ok(encoded)
+    }
+
+    fn inverse_rows(&self, rows: &[f64]) -> Result<Vec<f64>, String> {
+        let dims = self.categories.len();
+        Ok(map_columns(rows, dims, |d, code| {
+            let categories = &self.categories[d];
+            if code >= 0.0 && code.fract() == 0.0 && (code as usize) < categories.len() {
+                categories[code as usize]
+            } else {
+                f64::NAN
+            }
+        }))
+    }
+}
+
+/// Polynomial and interaction features up to a given degree
+#[wasm_bindgen]
+pub struct PolynomialFeatures {
+    dims: usize,
+    include_bias: bool,
+    terms: Vec<Vec<usize>>,
+}
+
+#[wasm_bindgen]
+impl PolynomialFeatures {
+    /// Prepare the expansion for data with `dims` features
+    ///
+    /// Takes the data (only its shape is used), the number of features, the layout (default
+    /// row-major), the degree and whether to include a constant column (default true).
+    /// Terms follow `polynomial_features_f64`.
+    pub fn fit(
+        data: &JsValue,
+        dims: usize,
+        layout: Option<MatrixLayout>,
+        degree: usize,
+        include_bias: Option<bool>,
+    ) -> Result<PolynomialFeatures, JsValue> {
+        read_matrix(data, dims, layout)?;
+        if degree == 0 {
+            return Err(JsValue::from_str("Degree must be greater than 0"));
+        }
+        Ok(PolynomialFeatures::from_values(dims, degree, include_bias.unwrap_or(true)))
+    }
+
+    /// Expand data in the given layout (default row-major)
+    pub fn transform(&self, data: &JsValue, layout: Option<MatrixLayout>) -> Result<JsValue, JsValue> {
+        apply(self, data, layout, false)
+    }
+
+    /// Recover the original features from the degree-one columns
+    pub fn inverse_transform(&self, data: &JsValue, layout: Option<MatrixLayout>) -> Result<JsValue, JsValue> {
+        apply(self, data, layout, true)
+    }
+
+    /// Number of output columns
+    #[wasm_bindgen(getter)]
+    pub fn output_dims(&self) -> usize {
+        self.terms.len()
+    }
+}
+
+impl PolynomialFeatures {
+    pub(crate) fn from_values(dims: usize, degree: usize, include_bias: bool) -> PolynomialFeatures {
+        PolynomialFeatures { dims, include_bias, terms: polynomial_terms(dims, degree, include_bias) }
+    }
+}
+
+impl Transform for PolynomialFeatures {
+    fn input_dims(&self) -> usize {
+        self.dims
+    }
+
+    fn output_dims(&self) -> usize {
+        self.terms.len()
+    }
+
+    fn transform_rows(&self, rows: &[f64]) -> Result<Vec<f64>, String> {
+        Ok(expand_polynomial(rows, self.dims, &self.terms))
+    }
+
+    fn inverse_rows(&self, rows: &[f64]) -> Result<Vec<f64>, String> {
+        // Degree-one terms come right after the optional bias, in feature order
+        let offset = usize::from(self.include_bias);
+        Ok(rows.chunks_exact(self.terms.len()).flat_map(|row| row[offset..offset + self.dims].to_vec()).collect())
+    }
+}
+
+/// Fill missing values (NaN) with a per-feature statistic
+#[wasm_bindgen]
+pub struct SimpleImputer {
+    statistics: Vec<f64>,
+}
+
+#[wasm_bindgen]
+impl SimpleImputer {
+    /// Learn the fill value of every feature
+    ///
+    /// Takes the data, the number of features, the layout (default row-major) and the
+    /// strategy (default mean). Every feature needs at least one observed value.
+    pub fn fit(data: &JsValue, dims: usize, layout: Option<MatrixLayout>, strategy: Option<ImputeStrategy>) -> Result<SimpleImputer, JsValue> {
+        let (rows, _) = read_matrix(data, dims, layout)?;
+        SimpleImputer::from_values(&rows, dims, strategy.unwrap_or(ImputeStrategy::Mean)).map_err(|message| JsValue::from_str(&message))
+    }
+
+    /// Fill missing values in data in the given layout (default row-major)
+    pub fn transform(&self, data: &JsValue, layout: Option<MatrixLayout>) -> Result<JsValue, JsValue> {
+        apply(self, data, layout, false)
+    }
+
+    /// Return the data unchanged, as which values were missing is not recorded
+    pub fn inverse_transform(&self, data: &JsValue, layout: Option<MatrixLayout>) -> Result<JsValue, JsValue> {
+        apply(self, data, layout, true)
+    }
+
+    /// Fill value of every feature
+    #[wasm_bindgen(getter)]
+    pub fn statistics(&self) -> Float64Array {
+        Float64Array::from(&self.statistics[..])
+    }
+}
+
+impl SimpleImputer {
+    pub(crate) fn from_values(rows: &[f64], dims: usize, strategy: ImputeStrategy) -> Result<SimpleImputer, String> {
+        let mut statistics = Vec::with_capacity(dims);
+        for d in 0..dims {
+            let sorted = sorted_observed(rows, dims, d);
+            if sorted.is_empty() {
+                return Err(format!("Feature {} has no observed values", d));
+            }
+            let statistic = match strategy {
+                ImputeStrategy::Mean => sorted.iter().sum::<f64>() / sorted.len() as f64,
+                ImputeStrategy::Median => quantile_sorted(&sorted, 0.5),
+                ImputeStrategy::MostFrequent => {
+                    // Runs in sorted data; strict comparison keeps the smallest value on ties
+                    let (mut best, mut best_count, mut start) = (sorted[0], 0, 0);
+                    for end in 1..=sorted.len() {
+                        if end == sorted.len() || sorted[end] != sorted[start] {
+                            if end - start > best_count {
+                                best = sorted[start];
+                                best_count = end - start;
+                            }
+                            start = end;
+                        }
+                    }
+                    best
+                }
+            };
+            statistics.push(statistic);
+        }
+        Ok(SimpleImputer { statistics })
+    }
+}
+
+impl Transform for SimpleImputer {
+    fn input_dims(&self) -> usize {
+        self.statistics.len()
+    }
+
+    fn output_dims(&self) -> usize {
+        self.statistics.len()
+    }
+
+    fn transform_rows(&self, rows: &[f64]) -> Result<Vec<f64>, String> {
+        Ok(map_columns(rows, self.statistics.len(), |d, x| if x.is_nan() { self.statistics[d] } else { x }))
+    }
+
+    fn inverse_rows(&self, rows: &[f64]) -> Result<Vec<f64>, String> {
+        Ok(rows.to_vec())
+    }
+}
+
+/// Map every feature through its empirical CDF to a uniform or normal distribution
+///
+/// Missing values (NaN) are ignored when fitting and stay missing.
+#[wasm_bindgen]
+pub struct QuantileTransformer {
+    dims: usize,
+    /// Row-major `dims x num_quantiles` feature values at the reference probabilities
+    quantiles: Vec<f64>,
+    references: Vec<f64>,
+    output: QuantileOutput,
+}
+
+/// Clip for the normal output so that the tails stay finite
+const QUANTILE_BOUND: f64 = 1e-7;
+
+#[wasm_bindgen]
+impl QuantileTransformer {
+    /// Learn per-feature quantiles
+    ///
+    /// Takes the data, the number of features, the layout (default row-major), the number
+    /// of quantiles (default 1000, capped at the number of samples) and the output
+    /// distribution (default uniform).
+    pub fn fit(
+        data: &JsValue,
+        dims: usize,
+        layout: Option<MatrixLayout>,
+        num_quantiles: Option<usize>,
+        output: Option<QuantileOutput>,
+    ) -> Result<QuantileTransformer, JsValue> {
+        let (rows, num_rows) = read_matrix(data, dims, layout)?;
+        let num_quantiles = num_quantiles.unwrap_or(1000).min(num_rows);
+        if num_quantiles < 2 {
+            return Err(JsValue::from_str("At least 2 quantiles (and samples) are required"));
+        }
+
+        QuantileTransformer::from_values(&rows, dims, num_quantiles, output.unwrap_or(QuantileOutput::Uniform))
+            .map_err(|message| JsValue::from_str(&message))
+    }
+
+    /// Transform data in the given layout (default row-major)
+    pub fn transform(&self, data: &JsValue, layout: Option<MatrixLayout>) -> Result<JsValue, JsValue> {
+        apply(self, data, layout, false)
+    }
+
+    /// Map transformed values back through the fitted quantiles
+    pub fn inverse_transform(&self, data: &JsValue, layout: Option<MatrixLayout>) -> Result<JsValue, JsValue> {
+        apply(self, data, layout, true)
+    }
+
+    /// Row-major fitted quantiles, one row per feature
+    #[wasm_bindgen(getter)]
+    pub fn quantiles(&self) -> Float64Array {
+        Float64Array::from(&self.quantiles[..])
+    }
+}
+
+impl QuantileTransformer {
+    pub(crate) fn from_values(rows: &[f64], dims: usize, num_quantiles: usize, output: QuantileOutput) -> Result<QuantileTransformer, String> {
+        let references: Vec<f64> = (0..num_quantiles).map(|i| i as f64 / (num_quantiles - 1) as f64).collect();
+        let mut quantiles = Vec::with_capacity(dims * num_quantiles);
+        for d in 0..dims {
+            let sorted = sorted_observed(rows, dims, d);
+            if sorted.is_empty() {
+                return Err(format!("Feature {} has no observed values", d));
+            }
+            quantiles.extend(references.iter().map(|&p| quantile_sorted(&sorted, p)));
+        }
+        Ok(QuantileTransformer { dims, quantiles, references, output })
+    }
+}
+
+impl Transform for QuantileTransformer {
+    fn input_dims(&self) -> usize {
+        self.dims
+    }
+
+    fn output_dims(&self) -> usize {
+        self.dims
+    }
+
+    fn transform_rows(&self, rows: &[f64]) -> Result<Vec<f64>, String> {
+        let n = self.references.len();
+        Ok(map_columns(rows, self.dims, |d, x| {
+            if x.is_nan() {
+                return x;
+            }
+            let quantiles = &self.quantiles[d * n..(d + 1) * n];
+
+            // Repeated quantiles map to the middle of their reference range
+            let lower = quantiles.partition_point(|&q| q < x);
+            let upper = quantiles.partition_point(|&q| q <= x);
+            let p = if lower == n {
+                1.0
+            } else if upper == 0 {
+                0.0
+            } else if lower < upper {
+                (self.references[lower] + self.references[upper - 1]) / 2.0
+            } else {
+                let (q0, q1) = (quantiles[lower - 1], quantiles[lower]);
+                let (r0, r1) = (self.references[lower - 1], self.references[lower]);
+                r0 + (x - q0) / (q1 - q0) * (r1 - r0)
+            };
+
+            match self.output {
+                QuantileOutput::Uniform => p,
+                QuantileOutput::Normal => normal_quantile(p.clamp(QUANTILE_BOUND, 1.0 - QUANTILE_BOUND)),
+            }
+        }))
+    }
+
+    fn inverse_rows(&self, rows: &[f64]) -> Result<Vec<f64>, String> {
+        let n = self.references.len();
+        Ok(map_columns(rows, self.dims, |d, y| {
+            if y.is_nan() {
+                return y;
+            }
+            let quantiles = &self.quantiles[d * n..(d + 1) * n];
+            let p = match self.output {
+                QuantileOutput::Uniform => y,
+                QuantileOutput::Normal => normal_cdf(y),
+            }
+            .clamp(0.0, 1.0);
+
+            // References are evenly spaced, so the bracket follows directly from p
+            let position = p * (n - 1) as f64;
+            let below = (position.floor() as usize).min(n - 2);
+            let fraction = position - below as f64;
+            quantiles[below] + fraction * (quantiles[below + 1] - quantiles[below])
+        }))
+    }
+}
+
+impl Transform for Pca {
+    fn input_dims(&self) -> usize {
+        self.dims()
+    }
+
+    fn output_dims(&self) -> usize {
+        self.num_components()
+    }
+
+    fn transform_rows(&self, rows: &[f64]) -> Result<Vec<f64>, String> {
+        Ok(self.transform_values(rows))
+    }
+
+    fn inverse_rows(&self, rows: &[f64]) -> Result<Vec<f64>, String> {
+        Ok(self.inverse_transform_values(rows))
+    }
+}
+
+/// Unfitted pipeline step and its settings
+enum Step {
+    StandardScaler { with_mean: bool, with_std: bool },
+    MinMaxScaler { range_min: f64, range_max: f64 },
+    RobustScaler { quantile_low: f64, quantile_high: f64 },
+    OneHotEncoder { ignore_unknown: bool },
+    OrdinalEncoder { unknown_value: Option<f64> },
+    PolynomialFeatures { degree: usize, include_bias: bool },
+    Imputer { strategy: ImputeStrategy },
+    QuantileTransformer { num_quantiles: usize, output: QuantileOutput },
+    Pca { num_components: usize, whiten: bool },
+}
+
+/// Chain of transformers fitted one after another
+///
+/// Add steps with the `add_*` methods, then `fit` on training data. `transform` feeds each
+/// step's output to the next and returns a row-major matrix that can be passed directly to
+/// the models (`LogisticRegression.fit`, `multiple_linear_regression_f64`,
+/// `kmeans_clustering_f64` and so on); `inverse_transform` runs the steps backwards.
+#[wasm_bindgen]
+pub struct Pipeline {
+    steps: Vec<Step>,
+    fitted: Vec<Box<dyn Transform>>,
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen]
+impl Pipeline {
+    /// Create an empty pipeline
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Pipeline {
+        Pipeline { steps: Vec::new(), fitted: Vec::new() }
+    }
+
+    /// Append a `StandardScaler` (defaults: centre and scale)
+    pub fn add_standard_scaler(&mut self, with_mean: Option<bool>, with_std: Option<bool>) {
+        self.push(Step::StandardScaler { with_mean: with_mean.unwrap_or(true), with_std: with_std.unwrap_or(true) });
+    }
+
+    /// Append a `MinMaxScaler` (default range [0, 1])
+    pub fn add_min_max_scaler(&mut self, range_min: Option<f64>, range_max: Option<f64>) -> Result<(), JsValue> {
+        let (range_min, range_max) = (range_min.unwrap_or(0.0), range_max.unwrap_or(1.0));
+        if range_min.is_nan() || range_max.is_nan() || range_min >= range_max {
+            return Err(JsValue::from_str("Range minimum must be less than the range maximum"));
+        }
+        self.push(Step::MinMaxScaler { range_min, range_max });
+        Ok(())
+    }
+
+    /// Append a `RobustScaler` (default quantile range 25 to 75)
+    pub fn add_robust_scaler(&mut self, quantile_low: Option<f64>, quantile_high: Option<f64>) -> Result<(), JsValue> {
+        let (quantile_low, quantile_high) = (quantile_low.unwrap_or(25.0), quantile_high.unwrap_or(75.0));
+        if !(0.0 <= quantile_low && quantile_low < quantile_high && quantile_high <= 100.0) {
+            return Err(JsValue::from_str("Quantile range must satisfy 0 <= low < high <= 100"));
+        }
+        self.push(Step::RobustScaler { quantile_low, quantile_high });
+        Ok(())
+    }
+
+    /// Append a `OneHotEncoder`
+    pub fn add_one_hot_encoder(&mut self, ignore_unknown: Option<bool>) {
+        self.push(Step::OneHotEncoder { ignore_unknown: ignore_unknown.unwrap_or(false) });
+    }
+
+    /// Append an `OrdinalEncoder`
+    pub fn add_ordinal_encoder(&mut self, unknown_value: Option<f64>) {
+        self.push(Step::OrdinalEncoder { unknown_value });
+    }
+
+    /// Append `PolynomialFeatures` (default with a bias column)
+    pub fn add_polynomial_features(&mut self, degree: usize, include_bias: Option<bool>) -> Result<(), JsValue> {
+        if degree == 0 {
+            return Err(JsValue::from_str("Degree must be greater than 0"));
+        }
+        self.push(Step::PolynomialFeatures { degree, include_bias: include_bias.unwrap_or(true) });
+        Ok(())
+    }
+
+    /// Append a `SimpleImputer` (default mean)
+    pub fn add_imputer(&mut self, strategy: Option<ImputeStrategy>) {
+        self.push(Step::Imputer { strategy: strategy.unwrap_or(ImputeStrategy::Mean) });
+    }
+
+    /// Append a `QuantileTransformer` (defaults: 1000 quantiles, uniform output)
+    pub fn add_quantile_transformer(&mut self, num_quantiles: Option<usize>, output: Option<QuantileOutput>) {
+        self.push(Step::QuantileTransformer {
+            num_quantiles: num_quantiles.unwrap_or(1000),
+            output: output.unwrap_or(QuantileOutput::Uniform),
+        });
+    }
+
+    /// Append a PCA projection (SVD solver)
+    pub fn add_pca(&mut self, num_components: usize, whiten: Option<bool>) -> Result<(), JsValue> {
+        if num_components == 0 {
+            return Err(JsValue::from_str("Number of components must be greater than 0"));
+        }
+        self.push(Step::Pca { num_components, whiten: whiten.unwrap_or(false) });
+        Ok(())
+    }
+
+    /// Fit every step in order on the output of the previous one
+    pub fn fit(&mut self, data: &JsValue, dims: usize, layout: Option<MatrixLayout>) -> Result<(), JsValue> {
+        let (rows, _) = read_matrix(data, dims, layout)?;
+        self.fit_values(rows, dims).map(|_| ()).map_err(|message| JsValue::from_str(&message))
+    }
+
+    /// Fit the pipeline and return the transformed training data (row-major)
+    pub fn fit_transform(&mut self, data: &JsValue, dims: usize, layout: Option<MatrixLayout>) -> Result<JsValue, JsValue> {
+        let (rows, _) = read_matrix(data, dims, layout)?;
+        let transformed = self.fit_values(rows, dims).map_err(|message| JsValue::from_str(&message))?;
+        Ok(Float64Array::from(&transformed[..]).into())
+    }
+
+    /// Run data in the given layout (default row-major) through every step; the result is
+    /// row-major
+    pub fn transform(&self, data: &JsValue, layout: Option<MatrixLayout>) -> Result<JsValue, JsValue> {
+        let (rows, _) = read_matrix(data, self.input_dims()?, layout)?;
+        let transformed = self
+            .fitted
+            .iter()
+            .try_fold(rows, |rows, step| step.transform_rows(&rows))
+            .map_err(|message| JsValue::from_str(&message))?;
+        Ok(Float64Array::from(&transformed[..]).into())
+    }
+
+    /// Run pipeline output back through every step in reverse; the result uses the given
+    /// layout (default row-major)
+    pub fn inverse_transform(&self, data: &JsValue, layout: Option<MatrixLayout>) -> Result<JsValue, JsValue> {
+        let input_dims = self.input_dims()?;
+        let output_dims = self.fitted.last().map_or(input_dims, |step| step.output_dims());
+        let (rows, _) = read_matrix(data, output_dims, Some(MatrixLayout::RowMajor))?;
+        let restored = self
+            .fitted
+            .iter()
+            .rev()
+            .try_fold(rows, |rows, step| step.inverse_rows(&rows))
+            .map_err(|message| JsValue::from_str(&message))?;
+        Ok(write_matrix(&restored, input_dims, layout))
+    }
+
+    /// Number of steps added
+    #[wasm_bindgen(getter)]
+    pub fn num_steps(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Number of columns produced by the fitted pipeline
+    #[wasm_bindgen(getter)]
+    pub fn output_dims(&self) -> Option<usize> {
+        self.fitted.last().map(|step| step.output_dims())
+    }
+}
+
+impl Pipeline {
+    /// Adding a step invalidates an earlier fit
+    fn push(&mut self, step: Step) {
+        self.steps.push(step);
+        self.fitted.clear();
+    }
+
+    fn input_dims(&self) -> Result<usize, JsValue> {
+        if self.steps.is_empty() {
+            return Err(JsValue::from_str("Pipeline has no steps"));
+        }
+        self.fitted
+            .first()
+            .map(|step| step.input_dims())
+            .ok_or_else(|| JsValue::from_str("Pipeline must be fitted before use"))
+    }
+
+    /// Fit the steps on validated row-major data and return the final output
+    pub(crate) fn fit_values(&mut self, mut rows: Vec<f64>, mut dims: usize) -> Result<Vec<f64>, String> {
+        if self.steps.is_empty() {
+            return Err("Pipeline has no steps".to_string());
+        }
+
+        self.fitted.clear();
+        let mut fitted: Vec<Box<dyn Transform>> = Vec::with_capacity(self.steps.len());
+        for step in &self.steps {
+            let transform = fit_step(step, &rows, dims)?;
+            rows = transform.transform_rows(&rows)?;
+            dims = transform.output_dims();
+            fitted.push(transform);
+        }
+        self.fitted = fitted;
+        Ok(rows)
+    }
+}
+
+fn fit_step(step: &Step, rows: &[f64], dims: usize) -> Result<Box<dyn Transform>, String> {
+    // An earlier step (e.g. a one-hot encoder fitted on no rows) can leave no features
+    if dims == 0 {
+        return Err("Pipeline step received 0 input features".to_string());
+    }
+    let num_rows = rows.len() / dims;
+    Ok(match *step {
+        Step::StandardScaler { with_mean, with_std } => Box::new(StandardScaler::from_values(rows, dims, with_mean, with_std)?),
+        Step::MinMaxScaler { range_min, range_max } => Box::new(MinMaxScaler::from_values(rows, dims, range_min, range_max)?),
+        Step::RobustScaler { quantile_low, quantile_high } => Box::new(RobustScaler::from_values(rows, dims, quantile_low, quantile_high)?),
+        Step::OneHotEncoder { ignore_unknown } => Box::new(OneHotEncoder::from_values(rows, dims, ignore_unknown)?),
+        Step::OrdinalEncoder { unknown_value } => Box::new(OrdinalEncoder::from_values(rows, dims, unknown_value)?),
+        Step::PolynomialFeatures { degree, include_bias } => Box::new(PolynomialFeatures::from_values(dims, degree, include_bias)),
+        Step::Imputer { strategy } => Box::new(SimpleImputer::from_values(rows, dims, strategy)?),
+        Step::QuantileTransformer { num_quantiles, output } => {
+            let num_quantiles = num_quantiles.min(num_rows);
+            if num_quantiles < 2 {
+                return Err("At least 2 quantiles (and samples) are required".to_string());
+            }
+            Box::new(QuantileTransformer::from_values(rows, dims, num_quantiles, output)?)
+        }
+        Step::Pca { num_components, whiten } => {
+            if rows.iter().any(|v| v.is_nan()) {
+                return Err("PCA does not accept missing values; add an imputer first".to_string());
+            }
+            if num_rows < 2 || num_components > dims.min(num_rows) {
+                return Err("Number of components must be between 1 and min(points, dims)".to_string());
+            }
+            Box::new(Pca::from_values(rows, dims, num_components, whiten, PcaSolver::Svd))
+        }
+    })
+}
+
+/// Read a matrix in either layout as row-major rows; NaN marks a missing value
+fn read_matrix(data: &JsValue, dims: usize, layout: Option<MatrixLayout>) -> Result<(Vec<f64>, usize), JsValue> {
+    if dims == 0 {
+        return Err(JsValue::from_str("Dimensionality must be greater than 0"));
+    }
+
+    // Convert input to typed array for better performance
+    let values = Float64Array::new(data).to_vec();
+
+    if !values.len().is_multiple_of(dims) {
+        return Err(JsValue::from_str("Data length must be a multiple of the dimensionality"));
+    }
+
+    if values.iter().any(|v| v.is_infinite()) {
+        return Err(JsValue::from_str("Data must not contain infinite values"));
+    }
+
+    let num_rows = values.len() / dims;
+    let rows = match layout.unwrap_or(MatrixLayout::RowMajor) {
+        MatrixLayout::RowMajor => values,
+        MatrixLayout::ColumnMajor => (0..num_rows * dims).map(|i| values[(i % dims) * num_rows + i / dims]).collect(),
+    };
+    Ok((rows, num_rows))
+}
+
+/// Write row-major rows in the requested layout
+fn write_matrix(rows: &[f64], dims: usize, layout: Option<MatrixLayout>) -> JsValue {
+    let num_rows = rows.len() / dims;
+    let values: Vec<f64> = match layout.unwrap_or(MatrixLayout::RowMajor) {
+        MatrixLayout::RowMajor => rows.to_vec(),
+        MatrixLayout::ColumnMajor => (0..num_rows * dims).map(|i| rows[(i % num_rows) * dims + i / num_rows]).collect(),
+    };
+    Float64Array::from(&values[..]).into()
+}
+
+/// Run a fitted transform (or its inverse) on data in the given layout, keeping the layout
+fn apply(transform: &dyn Transform, data: &JsValue, layout: Option<MatrixLayout>, inverse: bool) -> Result<JsValue, JsValue> {
+    let (from, to) = if inverse {
+        (transform.output_dims(), transform.input_dims())
+    } else {
+        (transform.input_dims(), transform.output_dims())
+    };
+    let (rows, _) = read_matrix(data, from, layout)?;
+    let result = if inverse { transform.inverse_rows(&rows) } else { transform.transform_rows(&rows) };
+    Ok(write_matrix(&result.map_err(|message| JsValue::from_str(&message))?, to, layout))
+}
+
+/// Apply `f(feature, value)` to every entry of a row-major matrix
+fn map_columns(rows: &[f64], dims: usize, f: impl Fn(usize, f64) -> f64) -> Vec<f64> {
+    rows.iter().enumerate().map(|(i, &x)| f(i % dims, x)).collect()
+}
+
+/// Non-missing values of one feature
+fn observed(rows: &[f64], dims: usize, feature: usize) -> Vec<f64> {
+    rows.iter().skip(feature).step_by(dims).copied().filter(|v| !v.is_nan()).collect()
+}
+
+fn sorted_observed(rows: &[f64], dims: usize, feature: usize) -> Vec<f64> {
+    let mut values = observed(rows, dims, feature);
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    values
+}
+
+/// Linearly interpolated quantile (0 to 1) of sorted values
+fn quantile_sorted(sorted: &[f64], q: f64) -> f64 {
+    if sorted.is_empty() {
+        return f64::NAN;
+    }
+    let position = q * (sorted.len() - 1) as f64;
+    let below = position.floor() as usize;
+    let above = (below + 1).min(sorted.len() - 1);
+    sorted[below] + (position - below as f64) * (sorted[above] - sorted[below])
+}
+
+/// Sorted distinct categories of every feature
+fn learn_categories(rows: &[f64], dims: usize) -> Result<Vec<Vec<f64>>, String> {
+    if rows.iter().any(|v| v.is_nan()) {
+        return Err("Encoders do not accept missing values; add an imputer first".to_string());
+    }
+    Ok((0..dims)
+        .map(|d| {
+            let mut categories = sorted_observed(rows, dims, d);
+            categories.dedup();
+            categories
+        })
+        .collect())
+}
+
+fn category_position(categories: &[f64], value: f64) -> Option<usize> {
+    categories.binary_search_by(|c| c.partial_cmp(&value).unwrap_or(std::cmp::Ordering::Less)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::random::Rng;
+
+    fn assert_close(actual: &[f64], expected: &[f64], tolerance: f64) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < tolerance || (a.is_nan() && e.is_nan()), "{:?} vs {:?}", actual, expected);
+        }
+    }
+
+    fn column(rows: &[f64], dims: usize, d: usize) -> Vec<f64> {
+        rows.iter().skip(d).step_by(dims).copied().collect()
+    }
+
+    fn sample(num_rows: usize, seed: u64) -> Vec<f64> {
+        let mut rng = Rng::new(seed);
+        (0..num_rows).flat_map(|_| [3.0 + 2.0 * rng.next_normal(), -10.0 + 0.5 * rng.next_normal()]).collect()
+    }
+
+    #[test]
+    fn standard_scaler_centres_and_scales() {
+        let rows = sample(200, 1);
+        let scaler = StandardScaler::from_values(&rows, 2, true, true).unwrap();
+        let scaled = scaler.transform_rows(&rows).unwrap();
+        for d in 0..2 {
+            let values = column(&scaled, 2, d);
+            let mean = values.iter().sum::<f64>() / 200.0;
+            let variance = values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / 200.0;
+            assert!(mean.abs() < 1e-12 && (variance - 1.0).abs() < 1e-12);
+        }
+        assert_close(&scaler.inverse_rows(&scaled).unwrap(), &rows, 1e-12);
+
+        // Missing values are skipped when fitting and stay missing
+        let scaler = StandardScaler::from_values(&[1.0, f64::NAN, 3.0, 5.0, 5.0, 5.0], 2, true, true).unwrap();
+        let std = (8.0f64 / 3.0).sqrt();
+        let scaled = scaler.transform_rows(&[2.0, f64::NAN, 5.0, 5.0]).unwrap();
+        assert_close(&scaled, &[-1.0 / std, f64::NAN, 2.0 / std, 0.0], 1e-12);
+    }
+
+    #[test]
+    fn standard_scaler_options() {
+        let rows = [1.0, 3.0, 5.0];
+        let centred = StandardScaler::from_values(&rows, 1, true, false).unwrap();
+        assert_close(&centred.transform_rows(&rows).unwrap(), &[-2.0, 0.0, 2.0], 1e-12);
+
+        let scaled = StandardScaler::from_values(&rows, 1, false, true).unwrap();
+        let std = (8.0f64 / 3.0).sqrt();
+        assert_close(&scaled.transform_rows(&rows).unwrap(), &[1.0 / std, 3.0 / std, 5.0 / std], 1e-12);
+
+        assert!(StandardScaler::from_values(&[f64::NAN, 1.0], 2, true, true).is_err());
+    }
+
+    #[test]
+    fn min_max_scaler_maps_to_the_range() {
+        let rows = [2.0, 7.0, 4.0, 7.0, 6.0, 7.0];
+        let scaler = MinMaxScaler::from_values(&rows, 2, -1.0, 1.0).unwrap();
+        let scaled = scaler.transform_rows(&rows).unwrap();
+
+        // The constant feature is treated as unit width
+        assert_close(&scaled, &[-1.0, -1.0, 0.0, -1.0, 1.0, -1.0], 1e-12);
+        assert_close(&scaler.inverse_rows(&scaled).unwrap(), &rows, 1e-12);
+        assert_close(&scaler.transform_rows(&[10.0, 8.0]).unwrap(), &[3.0, 1.0], 1e-12);
+    }
+
+    #[test]
+    fn robust_scaler_ignores_outliers() {
+        let rows = [1.0, 2.0, 3.0, 4.0, 5.0, 1000.0];
+        let scaler = RobustScaler::from_values(&rows, 1, 25.0, 75.0).unwrap();
+
+        // Median 3.5, interquartile range 4.75 - 2.25
+        assert_close(&scaler.transform_rows(&[3.5, 6.0]).unwrap(), &[0.0, 1.0], 1e-12);
+        let scaled = scaler.transform_rows(&rows).unwrap();
+        assert_close(&scaler.inverse_rows(&scaled).unwrap(), &rows, 1e-9);
+    }
+
+    #[test]
+    fn one_hot_encoder_round_trips_categories() {
+        let rows = [2.0, 10.0, 0.0, 20.0, 2.0, 30.0];
+        let encoder = OneHotEncoder::from_values(&rows, 2, false).unwrap();
+        assert_eq!((encoder.input_dims(), encoder.output_dims()), (2, 5));
+
+        let encoded = encoder.transform_rows(&rows).unwrap();
+        assert_eq!(encoded, vec![0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(encoder.inverse_rows(&encoded).unwrap(), rows.to_vec());
+
+        // Soft scores decode to the strongest category, an all-zero block to missing
+        assert_close(&encoder.inverse_rows(&[0.3, 0.7, 0.0, 0.0, 0.0]).unwrap(), &[2.0, f64::NAN], 1e-12);
+    }
+
+    #[test]
+    fn one_hot_encoder_handles_unknown_categories() {
+        let rows = [1.0, 2.0];
+        assert!(OneHotEncoder::from_values(&rows, 1, false).unwrap().transform_rows(&[3.0]).is_err());
+        let lenient = OneHotEncoder::from_values(&rows, 1, true).unwrap();
+        assert_eq!(lenient.transform_rows(&[3.0, 2.0]).unwrap(), vec![0.0, 0.0, 0.0, 1.0]);
+
+        assert!(OneHotEncoder::from_values(&[1.0, f64::NAN], 1, false).is_err());
+    }
+
+    #[test]
+    fn ordinal_encoder_codes_sorted_categories() {
+        let rows = [5.0, -1.0, 5.0, 3.0];
+        let encoder = OrdinalEncoder::from_values(&rows, 1, None).unwrap();
+        assert_eq!(encoder.transform_rows(&rows).unwrap(), vec![2.0, 0.0, 2.0, 1.0]);
+        assert!(encoder.transform_rows(&[4.0]).is_err());
+        assert_close(&encoder.inverse_rows(&[1.0, 2.5, 3.0]).unwrap(), &[3.0, f64::NAN, f64::NAN], 1e-12);
+
+        let lenient = OrdinalEncoder::from_values(&rows, 1, Some(-1.0)).unwrap();
+        assert_eq!(lenient.transform_rows(&[4.0]).unwrap(), vec![-1.0]);
+    }
+
+    #[test]
+    fn polynomial_features_invert_to_the_linear_terms() {
+        let features = PolynomialFeatures::from_values(2, 2, true);
+        assert_eq!(features.output_dims(), 6);
+        let rows = [2.0, 3.0, -1.0, 0.5];
+        let expanded = features.transform_rows(&rows).unwrap();
+        assert_eq!(expanded.len(), 12);
+        assert!(expanded[0] == 1.0 && expanded[6] == 1.0);
+        assert_eq!(features.inverse_rows(&expanded).unwrap(), rows.to_vec());
+    }
+
+    #[test]
+    fn imputer_strategies() {
+        let rows = [1.0, f64::NAN, 2.0, 4.0, 2.0, 4.0, 9.0, 1.0, f64::NAN, 1.0];
+        let fill = |strategy| SimpleImputer::from_values(&rows, 2, strategy).unwrap().transform_rows(&[f64::NAN; 2]).unwrap();
+        assert_close(&fill(ImputeStrategy::Mean), &[3.5, 2.5], 1e-12);
+        assert_close(&fill(ImputeStrategy::Median), &[2.0, 2.5], 1e-12);
+        // Ties go to the smallest value
+        assert_close(&fill(ImputeStrategy::MostFrequent), &[2.0, 1.0], 1e-12);
+
+        assert!(SimpleImputer::from_values(&[f64::NAN, 1.0], 2, ImputeStrategy::Mean).is_err());
+    }
+
+    #[test]
+    fn quantile_transformer_is_monotone_and_invertible() {
+        let rows: Vec<f64> = sample(300, 2).into_iter().step_by(2).collect();
+        // The normal output goes through approximate CDF and quantile functions
+        for (output, tolerance) in [(QuantileOutput::Uniform, 1e-9), (QuantileOutput::Normal, 1e-4)] {
+            let transformer = QuantileTransformer::from_values(&rows, 1, 100, output).unwrap();
+            let mut sorted = rows.clone();
+            sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            let mapped = transformer.transform_rows(&sorted).unwrap();
+            assert!(mapped.windows(2).all(|w| w[0] <= w[1]));
+            assert_close(&transformer.inverse_rows(&mapped).unwrap(), &sorted, tolerance);
+        }
+
+        let uniform = QuantileTransformer::from_values(&rows, 1, 100, QuantileOutput::Uniform).unwrap();
+        assert_eq!(uniform.transform_rows(&[-1e9, 1e9]).unwrap(), vec![0.0, 1.0]);
+    }
+
+    #[test]
+    fn pipeline_chains_and_inverts_steps() {
+        let rows = sample(50, 3);
+        let mut pipeline = Pipeline::new();
+        pipeline.add_standard_scaler(None, None);
+        pipeline.add_min_max_scaler(Some(0.0), Some(2.0)).unwrap();
+        let transformed = pipeline.fit_values(rows.clone(), 2).unwrap();
+        assert_eq!((pipeline.num_steps(), pipeline.output_dims()), (2, Some(2)));
+
+        for d in 0..2 {
+            let values = column(&transformed, 2, d);
+            assert!((values.iter().cloned().fold(f64::INFINITY, f64::min)).abs() < 1e-12);
+            assert!((values.iter().cloned().fold(f64::NEG_INFINITY, f64::max) - 2.0).abs() < 1e-12);
+        }
+
+        let restored = pipeline.fitted.iter().rev().try_fold(transformed, |rows, step| step.inverse_rows(&rows)).unwrap();
+        assert_close(&restored, &rows, 1e-12);
+
+        // Adding a step discards the fit
+        pipeline.add_imputer(None);
+        assert_eq!(pipeline.output_dims(), None);
+    }
+
+    #[test]
+    fn pipeline_rejects_unusable_inputs() {
+        assert!(Pipeline::new().fit_values(vec![1.0], 1).is_err());
+
+        // A one-hot encoder fitted on no rows leaves the next step without features
+        let mut pipeline = Pipeline::new();
+        pipeline.add_one_hot_encoder(None);
+        pipeline.add_standard_scaler(None, None);
+        assert_eq!(pipeline.fit_values(Vec::new(), 2).unwrap_err(), "Pipeline step received 0 input features");
+
+        let mut pipeline = Pipeline::new();
+        pipeline.add_pca(1, None).unwrap();
+        assert!(pipeline.fit_values(vec![1.0, f64::NAN, 2.0, 3.0], 2).is_err());
+
+        let mut pipeline = Pipeline::new();
+        pipeline.add_quantile_transformer(None, None);
+        assert!(pipeline.fit_values(vec![1.0], 1).is_err());
+    }
+}
@@ -0,0 +1,138 @@
+/// Value of one protobuf field, by wire type
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Field<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+    Fixed32(u32),
+}
+
+impl<'a> Field<'a> {
+    pub(crate) fn as_bytes(self) -> Result<&'a [u8], String> {
+        match self {
+            Field::Bytes(bytes) => Ok(bytes),
+            _ => Err("Expected a length-delimited protobuf field".to_string()),
+        }
+    }
+
+    pub(crate) fn as_str(self) -> Result<&'a str, String> {
+        std::str::from_utf8(self.as_bytes()?).map_err(|_| "Invalid UTF-8 in protobuf string".to_string())
+    }
+
+    /// Integer value; int64 fields store negative numbers as ten-byte two's complement
+    pub(crate) fn as_i64(self) -> Result<i64, String> {
+        match self {
+            Field::Varint(value) => Ok(value as i64),
+            _ => Err("Expected a varint protobuf field".to_string()),
+        }
+    }
+
+    pub(crate) fn as_f32(self) -> Result<f32, String> {
+        match self {
+            Field::Fixed32(bits) => Ok(f32::from_bits(bits)),
+            _ => Err("Expected a 32-bit protobuf field".to_string()),
+        }
+    }
+
+    /// Append a repeated integer field, packed or not
+    pub(crate) fn push_i64s(self, values: &mut Vec<i64>) -> Result<(), String> {
+        match self {
+            Field::Bytes(bytes) => {
+                let mut position = 0;
+                while position < bytes.len() {
+                    values.push(read_varint(bytes, &mut position)? as i64);
+                }
+                Ok(())
+            }
+            _ => {
+                values.push(self.as_i64()?);
+                Ok(())
+            }
+        }
+    }
+
+    /// Append a repeated float field, packed or not
+    pub(crate) fn push_f32s(self, values: &mut Vec<f32>) -> Result<(), String> {
+        match self {
+            Field::Bytes(bytes) => {
+                if bytes.len() % 4 != 0 {
+                    return Err("Truncated packed floats in protobuf".to_string());
+                }
+                values.extend(bytes.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])));
+                Ok(())
+            }
+            _ => {
+                values.push(self.as_f32()?);
+                Ok(())
+            }
+        }
+    }
+
+    /// Append a repeated double field, packed or not
+    pub(crate) fn push_f64s(self, values: &mut Vec<f64>) -> Result<(), String> {
+        match self {
+            Field::Bytes(bytes) => {
+                if bytes.len() % 8 != 0 {
+                    return Err("Truncated packed doubles in protobuf".to_string());
+                }
+                values.extend(bytes.chunks_exact(8).map(|b| f64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]])));
+                Ok(())
+            }
+            Field::Fixed64(bits) => {
+                values.push(f64::from_bits(bits));
+                Ok(())
+            }
+            _ => Err("Expected a 64-bit protobuf field".to_string()),
+        }
+    }
+}
+
+fn read_varint(bytes: &[u8], position: &mut usize) -> Result<u64, String> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *bytes.get(*position).ok_or("Truncated protobuf varint")?;
+        *position += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err("Protobuf varint is too long".to_string())
+}
+
+fn take<'a>(bytes: &'a [u8], position: &mut usize, count: usize) -> Result<&'a [u8], String> {
+    if count > bytes.len() - *position {
+        return Err("Truncated protobuf message".to_string());
+    }
+    let slice = &bytes[*position..*position + count];
+    *position += count;
+    Ok(slice)
+}
+
+/// Decode the fields of a message as (field number, value) pairs in wire order
+pub(crate) fn fields(bytes: &[u8]) -> Result<Vec<(u32, Field<'_>)>, String> {
+    let mut fields = Vec::new();
+    let mut position = 0;
+    while position < bytes.len() {
+        let key = read_varint(bytes, &mut position)?;
+        let number = (key >> 3) as u32;
+        let value = match key & 7 {
+            0 => Field::Varint(read_varint(bytes, &mut position)?),
+            1 => {
+                let b = take(bytes, &mut position, 8)?;
+                Field::Fixed64(u64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]))
+            }
+            2 => {
+                let length = read_varint(bytes, &mut position)? as usize;
+                Field::Bytes(take(bytes, &mut position, length)?)
+            }
+            5 => {
+                let b = take(bytes, &mut position, 4)?;
+                Field::Fixed32(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            }
+            wire_type => return Err(format!("Unsupported protobuf wire type {}", wire_type)),
+        };
+        fields.push((number, value));
+    }
+    Ok(fields)
+}
@@ -0,0 +1,108 @@
+/// Small seedable pseudo-random generator (SplitMix64)
+///
+/// Used wherever results must be reproducible from a seed; not suitable for cryptography.
+#[derive(Clone, Debug)]
+pub(crate) struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Create a generator from a 64-bit seed
+    pub(crate) fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    /// Create a generator from an optional seed, drawing one from `Math.random` when absent
+    pub(crate) fn from_seed(seed: Option<u32>) -> Self {
+        match seed {
+            Some(seed) => Rng::new(seed as u64),
+            None => Rng::new((js_sys::Math::random() * u32::MAX as f64) as u64),
+        }
+    }
+
+    /// Next raw 64-bit value
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in [0, 1)
+    pub(crate) fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Standard normal value (Box-Muller)
+    pub(crate) fn next_normal(&mut self) -> f64 {
+        let u1 = 1.0 - self.next_f64();
+        let u2 = self.next_f64();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+
+    /// Uniform index in [0, n)
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        ((self.next_f64() * n as f64) as usize).min(n.saturating_sub(1))
+    }
+
+    /// Shuffle a slice in place (Fisher-Yates)
+    pub(crate) fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i + 1);
+            items.swap(i, j);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_gives_the_same_stream() {
+        let mut a = Rng::new(99);
+        let mut b = Rng::new(99);
+        assert!((0..100).all(|_| a.next_u64() == b.next_u64()));
+        assert_ne!(Rng::new(1).next_u64(), Rng::new(2).next_u64());
+    }
+
+    #[test]
+    fn uniform_values_stay_in_range() {
+        let mut rng = Rng::new(4);
+        let values: Vec<f64> = (0..10_000).map(|_| rng.next_f64()).collect();
+        assert!(values.iter().all(|&v| (0.0..1.0).contains(&v)));
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        assert!((mean - 0.5).abs() < 0.02);
+
+        let mut counts = [0usize; 7];
+        for _ in 0..7_000 {
+            counts[rng.below(7)] += 1;
+        }
+        assert!(counts.iter().all(|&count| (800..1200).contains(&count)), "{:?}", counts);
+        assert_eq!(rng.below(1), 0);
+    }
+
+    #[test]
+    fn normal_values_have_unit_moments() {
+        let mut rng = Rng::new(8);
+        let values: Vec<f64> = (0..20_000).map(|_| rng.next_normal()).collect();
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64;
+
+        assert!(values.iter().all(|v| v.is_finite()));
+        assert!(mean.abs() < 0.03);
+        assert!((variance - 1.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn shuffle_is_a_permutation() {
+        let mut rng = Rng::new(21);
+        let mut items: Vec<usize> = (0..50).collect();
+        rng.shuffle(&mut items);
+
+        assert_ne!(items, (0..50).collect::<Vec<_>>());
+        items.sort_unstable();
+        assert_eq!(items, (0..50).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,490 @@
+use wasm_bindgen::prelude::*;
+use js_sys::{Float64Array, Object, Reflect};
+
+use super::distributions::student_t_cdf;
+use super::linalg::{invert_upper_triangular, least_squares_qr};
+use super::machine_learning::read_rows;
+
+/// Penalties for linear regression
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Regularization {
+    /// Ordinary least squares
+    None,
+    /// L2 penalty
+    Ridge,
+    /// L1 penalty (sparse coefficients)
+    Lasso,
+    /// Mix of L1 and L2 penalties
+    ElasticNet,
+}
+
+/// Multiple linear regression with optional regularization
+///
+/// Takes a row-major feature matrix, the targets, the number of features per row, the
+/// penalty and its strength `alpha`, and the elastic-net `l1_ratio` (default 0.5).
+/// Ordinary least squares is solved via QR; penalised fits use coordinate descent on
+/// (1 / 2n) * ||y - Xb||^2 + alpha * (l1_ratio * ||b||_1 + (1 - l1_ratio) / 2 * ||b||^2),
+/// with the intercept left unpenalised. Returns the coefficients, intercept, R-squared,
+/// adjusted R-squared, residuals and fitted values. Ordinary least squares fits also return
+/// standard errors, t-statistics and p-values for the coefficients and the intercept.
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen]
+pub fn multiple_linear_regression_f64(
+    x: &JsValue,
+    y: &JsValue,
+    dims: usize,
+    regularization: Regularization,
+    alpha: Option<f64>,
+    l1_ratio: Option<f64>,
+    fit_intercept: Option<bool>,
+    max_iterations: Option<usize>,
+) -> Result<JsValue, JsValue> {
+    let (features, num_rows) = read_rows(x, dims)?;
+    let targets = Float64Array::new(y).to_vec();
+
+    // Validate inputs
+    if targets.len() != num_rows {
+        return Err(JsValue::from_str("Targets must have one value per row"));
+    }
+
+    if targets.iter().any(|v| !v.is_finite()) {
+        return Err(JsValue::from_str("Targets must not contain NaN or infinite values"));
+    }
+
+    let fit_intercept = fit_intercept.unwrap_or(true);
+    let parameters = dims + usize::from(fit_intercept);
+    if num_rows < parameters {
+        return Err(JsValue::from_str("At least as many rows as fitted parameters are required"));
+    }
+
+    let alpha = alpha.unwrap_or(1.0);
+    if alpha < 0.0 {
+        return Err(JsValue::from_str("Alpha must be non-negative"));
+    }
+
+    let l1_ratio = match regularization {
+        Regularization::None | Regularization::Ridge => 0.0,
+        Regularization::Lasso => 1.0,
+        Regularization::ElasticNet => l1_ratio.unwrap_or(0.5),
+    };
+    if !(0.0..=1.0).contains(&l1_ratio) {
+        return Err(JsValue::from_str("l1_ratio must be between 0 and 1"));
+    }
+
+    let fit = if regularization == Regularization::None {
+        ordinary_least_squares(&features, &targets, dims, fit_intercept)
+            .ok_or_else(|| JsValue::from_str("Design matrix is rank deficient"))?
+    } else {
+        let (coefficients, intercept, iterations) = coordinate_descent(
+            &features,
+            &targets,
+            dims,
+            alpha,
+            l1_ratio,
+            fit_intercept,
+            max_iterations.unwrap_or(1000),
+            1e-6,
+        );
+        let mut fit = summarise(&features, &targets, dims, coefficients, intercept, fit_intercept);
+        fit.iterations = iterations;
+        fit
+    };
+
+    // Create result object
+    let result = Object::new();
+    Reflect::set(&result, &JsValue::from_str("coefficients"), &Float64Array::from(&fit.coefficients[..]))?;
+    Reflect::set(&result, &JsValue::from_str("intercept"), &JsValue::from_f64(fit.intercept))?;
+    Reflect::set(&result, &JsValue::from_str("r_squared"), &JsValue::from_f64(fit.r_squared))?;
+    Reflect::set(&result, &JsValue::from_str("adjusted_r_squared"), &JsValue::from_f64(fit.adjusted_r_squared))?;
+    Reflect::set(&result, &JsValue::from_str("residuals"), &Float64Array::from(&fit.residuals[..]))?;
+    Reflect::set(&result, &JsValue::from_str("fitted"), &Float64Array::from(&fit.fitted[..]))?;
+
+    if let Some(inference) = &fit.inference {
+        Reflect::set(&result, &JsValue::from_str("standard_errors"), &Float64Array::from(&inference.standard_errors[..]))?;
+        Reflect::set(&result, &JsValue::from_str("t_statistics"), &Float64Array::from(&inference.t_statistics[..]))?;
+        Reflect::set(&result, &JsValue::from_str("p_values"), &Float64Array::from(&inference.p_values[..]))?;
+        if fit_intercept {
+            Reflect::set(&result, &JsValue::from_str("intercept_standard_error"), &JsValue::from_f64(inference.intercept[0]))?;
+            Reflect::set(&result, &JsValue::from_str("intercept_t_statistic"), &JsValue::from_f64(inference.intercept[1]))?;
+            Reflect::set(&result, &JsValue::from_str("intercept_p_value"), &JsValue::from_f64(inference.intercept[2]))?;
+        }
+    } else {
+        Reflect::set(&result, &JsValue::from_str("iterations"), &JsValue::from_f64(fit.iterations as f64))?;
+    }
+
+    Ok(result.into())
+}
+
+/// Polynomial feature expansion
+///
+/// Takes a row-major matrix, the number of features per row, the maximum degree and
+/// whether to include the constant column, and returns an object with the expanded
+/// row-major `features` and their count `dims`. Columns are ordered by degree, then
+/// lexicographically by the feature indices (x0, x1, x0^2, x0 x1, x1^2, ...).
+#[wasm_bindgen]
+pub fn polynomial_features_f64(x: &JsValue, dims: usize, degree: usize, include_bias: bool) -> Result<JsValue, JsValue> {
+    let (features, _) = read_rows(x, dims)?;
+
+    // Validate inputs
+    if degree == 0 && !include_bias {
+        return Err(JsValue::from_str("Degree must be at least 1 without a bias column"));
+    }
+
+    let terms = polynomial_terms(dims, degree, include_bias);
+    let expanded = expand_polynomial(&features, dims, &terms);
+
+    // Create result object
+    let result = Object::new();
+    Reflect::set(&result, &JsValue::from_str("features"), &Float64Array::from(&expanded[..]))?;
+    Reflect::set(&result, &JsValue::from_str("dims"), &JsValue::from_f64(terms.len() as f64))?;
+
+    Ok(result.into())
+}
+
+/// Coefficient inference for ordinary least squares
+pub(crate) struct Inference {
+    pub(crate) standard_errors: Vec<f64>,
+    pub(crate) t_statistics: Vec<f64>,
+    pub(crate) p_values: Vec<f64>,
+    /// Standard error, t-statistic and p-value of the intercept
+    pub(crate) intercept: [f64; 3],
+}
+
+/// Outcome of a linear regression fit
+pub(crate) struct LinearFit {
+    pub(crate) coefficients: Vec<f64>,
+    pub(crate) intercept: f64,
+    pub(crate) r_squared: f64,
+    pub(crate) adjusted_r_squared: f64,
+    pub(crate) residuals: Vec<f64>,
+    pub(crate) fitted: Vec<f64>,
+    pub(crate) inference: Option<Inference>,
+    pub(crate) iterations: usize,
+}
+
+/// Ordinary least squares via QR with coefficient inference
+pub(crate) fn ordinary_least_squares(features: &[f64], targets: &[f64], dims: usize, fit_intercept: bool) -> Option<LinearFit> {
+    let num_rows = targets.len();
+    let cols = dims + usize::from(fit_intercept);
+
+    // Design matrix with a leading column of ones for the intercept
+    let mut design = Vec::with_capacity(num_rows * cols);
+    for row in features.chunks_exact(dims) {
+        if fit_intercept {
+            design.push(1.0);
+        }
+        design.extend_from_slice(row);
+    }
+
+    let (beta, r) = least_squares_qr(&design, num_rows, cols, targets)?;
+    let (intercept, coefficients) = if fit_intercept { (beta[0], beta[1..].to_vec()) } else { (0.0, beta) };
+    let mut fit = summarise(features, targets, dims, coefficients, intercept, fit_intercept);
+
+    // Covariance of the estimates: sigma^2 (R^T R)^-1 = sigma^2 R^-1 R^-T
+    let dof = (num_rows - cols) as f64;
+    let sigma2 = fit.residuals.iter().map(|e| e * e).sum::<f64>() / dof;
+    let r_inverse = invert_upper_triangular(&r, cols);
+    let stats: Vec<[f64; 3]> = (0..cols)
+        .map(|i| {
+            let variance = sigma2 * (i..cols).map(|k| r_inverse[i * cols + k] * r_inverse[i * cols + k]).sum::<f64>();
+            let standard_error = variance.sqrt();
+            let estimate = if fit_intercept { beta_at(&fit, i) } else { fit.coefficients[i] };
+            let t = estimate / standard_error;
+            let p = if dof > 0.0 { 2.0 * (1.0 - student_t_cdf(t.abs(), dof)) } else { f64::NAN };
+            [standard_error, t, p]
+        })
+        .collect();
+
+    let offset = usize::from(fit_intercept);
+    fit.inference = Some(Inference {
+        standard_errors: stats[offset..].iter().map(|s| s[0]).collect(),
+        t_statistics: stats[offset..].iter().map(|s| s[1]).collect(),
+        p_values: stats[offset..].iter().map(|s| s[2]).collect(),
+        intercept: if fit_intercept { stats[0] } else { [f64::NAN; 3] },
+    });
+
+    Some(fit)
+}
+
+/// Estimate `i` of the intercept-first parameter vector
+fn beta_at(fit: &LinearFit, i: usize) -> f64 {
+    if i == 0 { fit.intercept } else { fit.coefficients[i - 1] }
+}
+
+/// Elastic-net coordinate descent; returns coefficients, intercept and iterations
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn coordinate_descent(
+    features: &[f64],
+    targets: &[f64],
+    dims: usize,
+    alpha: f64,
+    l1_ratio: f64,
+    fit_intercept: bool,
+    max_iterations: usize,
+    tolerance: f64,
+) -> (Vec<f64>, f64, usize) {
+    let num_rows = targets.len();
+    let n = num_rows as f64;
+
+    // Centre the data so the intercept drops out of the penalised problem
+    let mut feature_means = vec![0.0; dims];
+    let mut target_mean = 0.0;
+    if fit_intercept {
+        for row in features.chunks_exact(dims) {
+            for (m, &value) in feature_means.iter_mut().zip(row) {
+                *m += value / n;
+            }
+        }
+        target_mean = targets.iter().sum::<f64>() / n;
+    }
+
+    // Column-major centred features for cheap column access
+    let columns: Vec<Vec<f64>> = (0..dims)
+        .map(|j| (0..num_rows).map(|i| features[i * dims + j] - feature_means[j]).collect())
+        .collect();
+    let column_norms: Vec<f64> = columns.iter().map(|c| c.iter().map(|v| v * v).sum()).collect();
+
+    let mut coefficients = vec![0.0; dims];
+    let mut residuals: Vec<f64> = targets.iter().map(|t| t - target_mean).collect();
+    let l1 = n * alpha * l1_ratio;
+    let l2 = n * alpha * (1.0 - l1_ratio);
+    let mut iterations = 0;
+
+    while iterations < max_iterations {
+        iterations += 1;
+        let mut largest_change: f64 = 0.0;
+        let mut largest_coefficient: f64 = 0.0;
+
+        for j in 0..dims {
+            if column_norms[j] == 0.0 {
+                continue;
+            }
+
+            let old = coefficients[j];
+            let rho: f64 = columns[j].iter().zip(&residuals).map(|(x, r)| x * r).sum::<f64>() + column_norms[j] * old;
+            let updated = soft_threshold(rho, l1) / (column_norms[j] + l2);
+
+            if updated != old {
+                let delta = updated - old;
+                for (r, x) in residuals.iter_mut().zip(&columns[j]) {
+                    *r -= delta * x;
+                }
+                coefficients[j] = updated;
+            }
+
+            largest_change = largest_change.max((updated - old).abs());
+            largest_coefficient = largest_coefficient.max(updated.abs());
+        }
+
+        if largest_change <= tolerance * largest_coefficient.max(f64::MIN_POSITIVE) {
+            break;
+        }
+    }
+
+    let intercept = target_mean - coefficients.iter().zip(&feature_means).map(|(b, m)| b * m).sum::<f64>();
+    (coefficients, intercept, iterations)
+}
+
+/// Soft-thresholding operator used by the L1 penalty
+fn soft_threshold(value: f64, threshold: f64) -> f64 {
+    if value > threshold {
+        value - threshold
+    } else if value < -threshold {
+        value + threshold
+    } else {
+        0.0
+    }
+}
+
+/// Residuals and goodness of fit for a set of coefficients
+fn summarise(features: &[f64], targets: &[f64], dims: usize, coefficients: Vec<f64>, intercept: f64, fit_intercept: bool) -> LinearFit {
+    let num_rows = targets.len();
+    let fitted: Vec<f64> = features
+        .chunks_exact(dims)
+        .map(|row| intercept + row.iter().zip(&coefficients).map(|(x, b)| x * b).sum::<f64>())
+        .collect();
+    let residuals: Vec<f64> = targets.iter().zip(&fitted).map(|(t, f)| t - f).collect();
+
+    // Without an intercept R-squared is measured against zero, as is conventional
+    let centre = if fit_intercept { targets.iter().sum::<f64>() / num_rows as f64 } else { 0.0 };
+    let total: f64 = targets.iter().map(|t| (t - centre) * (t - centre)).sum();
+    let residual: f64 = residuals.iter().map(|e| e * e).sum();
+    let r_squared = if total == 0.0 { 0.0 } else { 1.0 - residual / total };
+
+    let dof = num_rows as f64 - dims as f64 - f64::from(u8::from(fit_intercept));
+    let baseline = num_rows as f64 - f64::from(u8::from(fit_intercept));
+    let adjusted_r_squared = if dof > 0.0 { 1.0 - (1.0 - r_squared) * baseline / dof } else { f64::NAN };
+
+    LinearFit {
+        coefficients,
+        intercept,
+        r_squared,
+        adjusted_r_squared,
+        residuals,
+        fitted,
+        inference: None,
+        iterations: 0,
+    }
+}
+
+/// Exponent vectors of every monomial up to `degree`, in output column order
+pub(crate) fn polynomial_terms(dims: usize, degree: usize, include_bias: bool) -> Vec<Vec<usize>> {
+    let mut terms: Vec<Vec<usize>> = Vec::new();
+    if include_bias {
+        terms.push(Vec::new());
+    }
+
+    // Each term is a non-decreasing list of feature indices (combinations with replacement)
+    let mut current: Vec<Vec<usize>> = vec![Vec::new()];
+    for _ in 1..=degree {
+        let mut next = Vec::new();
+        for term in &current {
+            let start = term.last().copied().unwrap_or(0);
+            for feature in start..dims {
+                let mut extended = term.clone();
+                extended.push(feature);
+                next.push(extended);
+            }
+        }
+        terms.extend(next.iter().cloned());
+        current = next;
+    }
+
+    terms
+}
+
+/// Evaluate polynomial terms for every row of a row-major matrix
+pub(crate) fn expand_polynomial(features: &[f64], dims: usize, terms: &[Vec<usize>]) -> Vec<f64> {
+    let mut expanded = Vec::with_capacity(features.len() / dims * terms.len());
+    for row in features.chunks_exact(dims) {
+        for term in terms {
+            expanded.push(term.iter().map(|&feature| row[feature]).product());
+        }
+    }
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::random::Rng;
+
+    /// Rows of two features with targets `1.5 + 2 x0 - 3 x1 + noise`
+    fn linear_data(rng: &mut Rng, noise: f64) -> (Vec<f64>, Vec<f64>) {
+        let features: Vec<f64> = (0..100).map(|_| rng.next_normal()).collect();
+        let targets = features
+            .chunks_exact(2)
+            .map(|row| 1.5 + 2.0 * row[0] - 3.0 * row[1] + noise * rng.next_normal())
+            .collect();
+        (features, targets)
+    }
+
+    #[test]
+    fn ordinary_least_squares_recovers_exact_coefficients() {
+        let (features, targets) = linear_data(&mut Rng::new(1), 0.0);
+        let fit = ordinary_least_squares(&features, &targets, 2, true).unwrap();
+
+        assert!((fit.intercept - 1.5).abs() < 1e-10);
+        assert!((fit.coefficients[0] - 2.0).abs() < 1e-10 && (fit.coefficients[1] + 3.0).abs() < 1e-10);
+        assert!((fit.r_squared - 1.0).abs() < 1e-12);
+        assert!(fit.residuals.iter().all(|e| e.abs() < 1e-10));
+    }
+
+    #[test]
+    fn simple_regression_standard_error_matches_the_closed_form() {
+        let mut rng = Rng::new(4);
+        let x: Vec<f64> = (0..40).map(|i| i as f64 / 4.0).collect();
+        let y: Vec<f64> = x.iter().map(|x| 0.5 + 0.8 * x + rng.next_normal()).collect();
+        let fit = ordinary_least_squares(&x, &y, 1, true).unwrap();
+
+        // se(slope) = sqrt(sigma^2 / Sxx) with sigma^2 = RSS / (n - 2)
+        let mean = x.iter().sum::<f64>() / 40.0;
+        let sxx: f64 = x.iter().map(|x| (x - mean).powi(2)).sum();
+        let sigma2 = fit.residuals.iter().map(|e| e * e).sum::<f64>() / 38.0;
+        let inference = fit.inference.as_ref().unwrap();
+        assert!((inference.standard_errors[0] - (sigma2 / sxx).sqrt()).abs() < 1e-10);
+        assert!((inference.t_statistics[0] - fit.coefficients[0] / inference.standard_errors[0]).abs() < 1e-10);
+        assert!(inference.p_values[0] < 1e-6);
+        assert!((0.0..=1.0).contains(&inference.intercept[2]));
+
+        let adjusted = 1.0 - (1.0 - fit.r_squared) * 39.0 / 38.0;
+        assert!((fit.adjusted_r_squared - adjusted).abs() < 1e-12);
+    }
+
+    #[test]
+    fn ordinary_least_squares_without_intercept() {
+        let x = [1.0, 2.0, 3.0, 4.0];
+        let y = [2.0, 4.1, 5.9, 8.0];
+        let fit = ordinary_least_squares(&x, &y, 1, false).unwrap();
+
+        let expected = x.iter().zip(&y).map(|(x, y)| x * y).sum::<f64>() / x.iter().map(|x| x * x).sum::<f64>();
+        assert!((fit.coefficients[0] - expected).abs() < 1e-12);
+        assert_eq!(fit.intercept, 0.0);
+        assert!(fit.inference.unwrap().intercept[0].is_nan());
+    }
+
+    #[test]
+    fn collinear_features_are_rejected() {
+        let features: Vec<f64> = (0..10).flat_map(|i| [i as f64, 2.0 * i as f64]).collect();
+        let targets: Vec<f64> = (0..10).map(|i| i as f64).collect();
+        assert!(ordinary_least_squares(&features, &targets, 2, true).is_none());
+    }
+
+    #[test]
+    fn unpenalised_coordinate_descent_matches_least_squares() {
+        let (features, targets) = linear_data(&mut Rng::new(7), 0.5);
+        let ols = ordinary_least_squares(&features, &targets, 2, true).unwrap();
+        let (coefficients, intercept, _) = coordinate_descent(&features, &targets, 2, 0.0, 1.0, true, 1000, 1e-12);
+
+        assert!((intercept - ols.intercept).abs() < 1e-8);
+        for (a, b) in coefficients.iter().zip(&ols.coefficients) {
+            assert!((a - b).abs() < 1e-8);
+        }
+    }
+
+    #[test]
+    fn ridge_matches_the_closed_form_for_one_feature() {
+        let mut rng = Rng::new(10);
+        let x: Vec<f64> = (0..50).map(|_| rng.next_normal()).collect();
+        let y: Vec<f64> = x.iter().map(|x| 3.0 * x + rng.next_normal()).collect();
+        let alpha = 0.4;
+        let (coefficients, _, _) = coordinate_descent(&x, &y, 1, alpha, 0.0, true, 100, 1e-12);
+
+        // b = Sxy / (Sxx + n alpha) on centred data
+        let (mx, my) = (x.iter().sum::<f64>() / 50.0, y.iter().sum::<f64>() / 50.0);
+        let sxy: f64 = x.iter().zip(&y).map(|(x, y)| (x - mx) * (y - my)).sum();
+        let sxx: f64 = x.iter().map(|x| (x - mx).powi(2)).sum();
+        assert!((coefficients[0] - sxy / (sxx + 50.0 * alpha)).abs() < 1e-10);
+    }
+
+    #[test]
+    fn lasso_drops_irrelevant_features() {
+        let mut rng = Rng::new(13);
+        let features: Vec<f64> = (0..300).map(|_| rng.next_normal()).collect();
+        let targets: Vec<f64> = features.chunks_exact(3).map(|row| 4.0 * row[0] + 0.1 * rng.next_normal()).collect();
+
+        let (coefficients, _, _) = coordinate_descent(&features, &targets, 3, 0.1, 1.0, true, 1000, 1e-10);
+        assert!(coefficients[0] > 3.5);
+        assert_eq!(&coefficients[1..], &[0.0, 0.0]);
+
+        // A large enough penalty removes every feature and leaves the mean
+        let (coefficients, intercept, _) = coordinate_descent(&features, &targets, 3, 100.0, 1.0, true, 1000, 1e-10);
+        assert!(coefficients.iter().all(|&b| b == 0.0));
+        assert!((intercept - targets.iter().sum::<f64>() / 100.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn polynomial_terms_follow_combinations_with_replacement() {
+        assert_eq!(
+            polynomial_terms(2, 2, true),
+            vec![vec![], vec![0], vec![1], vec![0, 0], vec![0, 1], vec![1, 1]]
+        );
+        // C(dims + degree, degree) terms including the bias
+        assert_eq!(polynomial_terms(3, 3, true).len(), 20);
+        assert_eq!(polynomial_terms(3, 3, false).len(), 19);
+
+        let expanded = expand_polynomial(&[2.0, 3.0, -1.0, 4.0], 2, &polynomial_terms(2, 2, true));
+        assert_eq!(expanded, vec![1.0, 2.0, 3.0, 4.0, 6.0, 9.0, 1.0, -1.0, 4.0, 1.0, -4.0, 16.0]);
+    }
+}
@@ -0,0 +1,184 @@
+/// Little-endian binary writer used by the model `to_bytes` methods
+///
+/// Every format starts with a four-byte magic tag and a format version so that readers can
+/// reject foreign or newer payloads.
+pub(crate) struct ByteWriter {
+    bytes: Vec<u8>,
+}
+
+impl ByteWriter {
+    /// Start a payload with its magic tag and version
+    pub(crate) fn new(magic: &[u8; 4], version: u32) -> Self {
+        let mut writer = ByteWriter { bytes: Vec::new() };
+        writer.bytes.extend_from_slice(magic);
+        writer.write_u32(version);
+        writer
+    }
+
+    pub(crate) fn write_u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub(crate) fn write_f64(&mut self, value: f64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Write a length-prefixed slice of floats
+    pub(crate) fn write_f64_slice(&mut self, values: &[f64]) {
+        self.write_u32(values.len() as u32);
+        for &value in values {
+            self.write_f64(value);
+        }
+    }
+
+    /// Write a length-prefixed UTF-8 string
+    pub(crate) fn write_str(&mut self, value: &str) {
+        self.write_u32(value.len() as u32);
+        self.bytes.extend_from_slice(value.as_bytes());
+    }
+
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+/// Reader for payloads produced by `ByteWriter`
+pub(crate) struct ByteReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    /// Check the magic tag and return the reader with the format version
+    pub(crate) fn new(bytes: &'a [u8], magic: &[u8; 4]) -> Result<(Self, u32), String> {
+        if bytes.len() < 8 || &bytes[..4] != magic {
+            return Err(format!("Not a {} payload", String::from_utf8_lossy(magic)));
+        }
+        let mut reader = ByteReader { bytes, position: 4 };
+        let version = reader.read_u32()?;
+        Ok((reader, version))
+    }
+
+    fn take(&mut self, count: usize) -> Result<&'a [u8], String> {
+        if count > self.bytes.len() - self.position {
+            return Err("Unexpected end of payload".to_string());
+        }
+        let slice = &self.bytes[self.position..self.position + count];
+        self.position += count;
+        Ok(slice)
+    }
+
+    pub(crate) fn read_u32(&mut self) -> Result<u32, String> {
+        let mut buffer = [0u8; 4];
+        buffer.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(buffer))
+    }
+
+    pub(crate) fn read_f64(&mut self) -> Result<f64, String> {
+        let mut buffer = [0u8; 8];
+        buffer.copy_from_slice(self.take(8)?);
+        Ok(f64::from_le_bytes(buffer))
+    }
+
+    /// Read a length-prefixed slice of floats
+    pub(crate) fn read_f64_vec(&mut self) -> Result<Vec<f64>, String> {
+        let length = self.read_u32()? as usize;
+        if length.checked_mul(8).is_none_or(|size| size > self.bytes.len() - self.position) {
+            return Err("Unexpected end of payload".to_string());
+        }
+        (0..length).map(|_| self.read_f64()).collect()
+    }
+
+    /// Read a length-prefixed UTF-8 string
+    pub(crate) fn read_string(&mut self) -> Result<String, String> {
+        let length = self.read_u32()? as usize;
+        let bytes = self.take(length)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| "Invalid UTF-8 in payload".to_string())
+    }
+
+    /// Number of bytes not read yet
+    pub(crate) fn remaining(&self) -> usize {
+        self.bytes.len() - self.position
+    }
+
+    /// Fail if any bytes are left over
+    pub(crate) fn finish(&self) -> Result<(), String> {
+        if self.position == self.bytes.len() {
+            Ok(())
+        } else {
+            Err("Trailing bytes after payload".to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_round_trip() {
+        let mut writer = ByteWriter::new(b"TEST", 3);
+        writer.write_u32(42);
+        writer.write_f64(-1.25);
+        writer.write_f64_slice(&[1.0, f64::INFINITY, -0.0]);
+        writer.write_str("héllo");
+        let bytes = writer.into_bytes();
+
+        let (mut reader, version) = ByteReader::new(&bytes, b"TEST").unwrap();
+        assert_eq!(version, 3);
+        assert_eq!(reader.read_u32().unwrap(), 42);
+        assert_eq!(reader.read_f64().unwrap(), -1.25);
+        let values = reader.read_f64_vec().unwrap();
+        assert_eq!(values, vec![1.0, f64::INFINITY, 0.0]);
+        assert!(values[2].is_sign_negative());
+        assert_eq!(reader.remaining(), 4 + "héllo".len());
+        assert_eq!(reader.read_string().unwrap(), "héllo");
+        assert!(reader.finish().is_ok());
+    }
+
+    #[test]
+    fn foreign_payloads_are_rejected() {
+        let bytes = ByteWriter::new(b"TEST", 1).into_bytes();
+        assert!(ByteReader::new(&bytes, b"ELSE").is_err());
+        assert!(ByteReader::new(&bytes[..7], b"TEST").is_err());
+        assert!(ByteReader::new(&[], b"TEST").is_err());
+    }
+
+    #[test]
+    fn truncated_payloads_are_rejected() {
+        let mut writer = ByteWriter::new(b"TEST", 1);
+        writer.write_f64_slice(&[1.0, 2.0]);
+        writer.write_str("abc");
+        let bytes = writer.into_bytes();
+
+        for end in 8..bytes.len() {
+            let (mut reader, _) = ByteReader::new(&bytes[..end], b"TEST").unwrap();
+            let result = reader.read_f64_vec().and_then(|_| reader.read_string());
+            assert!(result.is_err(), "payload cut at {} was accepted", end);
+        }
+    }
+
+    #[test]
+    fn oversized_length_prefixes_fail_before_allocating() {
+        let mut writer = ByteWriter::new(b"TEST", 1);
+        writer.write_u32(u32::MAX);
+        let bytes = writer.into_bytes();
+
+        let (mut reader, _) = ByteReader::new(&bytes, b"TEST").unwrap();
+        assert_eq!(reader.read_f64_vec().unwrap_err(), "Unexpected end of payload");
+        let (mut reader, _) = ByteReader::new(&bytes, b"TEST").unwrap();
+        assert_eq!(reader.read_string().unwrap_err(), "Unexpected end of payload");
+    }
+
+    #[test]
+    fn invalid_text_and_trailing_bytes_are_rejected() {
+        let mut writer = ByteWriter::new(b"TEST", 1);
+        writer.write_u32(2);
+        writer.write_u32(0xffff);
+        let bytes = writer.into_bytes();
+
+        let (mut reader, _) = ByteReader::new(&bytes, b"TEST").unwrap();
+        assert_eq!(reader.read_string().unwrap_err(), "Invalid UTF-8 in payload");
+        assert_eq!(reader.finish().unwrap_err(), "Trailing bytes after payload");
+    }
+}
@@ -8,19 +8,19 @@ use wide::{f64x4};
 
 /// Complex number struct for FFT calculations
 #[derive(Clone, Copy, Debug)]
-struct Complex {
-    real: f64,
-    imag: f64,
+pub(crate) struct Complex {
+    pub(crate) real: f64,
+    pub(crate) imag: f64,
 }
 
 impl Complex {
     /// Create a new complex number
-    fn new(real: f64, imag: f64) -> Self {
+    pub(crate) fn new(real: f64, imag: f64) -> Self {
         Complex { real, imag }
     }
 
     /// Add two complex numbers
-    fn add(&self, other: &Complex) -> Complex {
+    pub(crate) fn add(&self, other: &Complex) -> Complex {
         Complex::new(
             self.real + other.real,
             self.imag + other.imag
@@ -28,7 +28,7 @@ impl Complex {
     }
 
     /// Subtract two complex numbers
-    fn sub(&self, other: &Complex) -> Complex {
+    pub(crate) fn sub(&self, other: &Complex) -> Complex {
         Complex::new(
             self.real - other.real,
             self.imag - other.imag
@@ -36,12 +36,22 @@ impl Complex {
     }
 
     /// Multiply two complex numbers
-    fn mul(&self, other: &Complex) -> Complex {
+    pub(crate) fn mul(&self, other: &Complex) -> Complex {
         Complex::new(
             self.real * other.real - self.imag * other.imag,
             self.real * other.imag + self.imag * other.real
         )
     }
+
+    /// Squared magnitude
+    pub(crate) fn norm_sqr(&self) -> f64 {
+        self.real * self.real + self.imag * self.imag
+    }
+
+    /// Complex conjugate
+    pub(crate) fn conj(&self) -> Complex {
+        Complex::new(self.real, -self.imag)
+    }
 }
 
 /// Fast Fourier Transform (FFT) implementation
@@ -117,6 +127,29 @@ fn fft_recursive(signal: &mut [Complex], n: usize) -> Vec<Complex> {
     result
 }
 
+/// Forward FFT of a complex buffer for use by other modules
+///
+/// The buffer length must be a power of 2.
+pub(crate) fn fft_complex(signal: &[Complex]) -> Vec<Complex> {
+    let mut buffer = signal.to_vec();
+    let n = buffer.len();
+    fft_recursive(&mut buffer, n)
+}
+
+/// Inverse FFT of a complex buffer for use by other modules
+///
+/// Computed as the conjugate of the forward transform of the conjugate, scaled by 1/n.
+/// The buffer length must be a power of 2.
+pub(crate) fn ifft_complex(spectrum: &[Complex]) -> Vec<Complex> {
+    let mut buffer: Vec<Complex> = spectrum.iter().map(|c| c.conj()).collect();
+    let n = buffer.len();
+    let scale = 1.0 / n as f64;
+    fft_recursive(&mut buffer, n)
+        .iter()
+        .map(|c| Complex::new(c.real * scale, -c.imag * scale))
+        .collect()
+}
+
 /// Convolution implementation
 ///
 /// Takes two signals and returns their convolution.
@@ -2,6 +2,8 @@ use wasm_bindgen::prelude::*;
 use js_sys::Float64Array;
 use bumpalo::Bump;
 
+use super::interpolation::interpolate_missing;
+
 #[cfg(feature = "simd")]
 use wide::{f64x4, CmpLt};
 
@@ -220,12 +222,58 @@ pub fn numeric_detect_outliers_f64(input: &JsValue, threshold: f64) -> Result<Js
     Ok(result_array.into())
 }
 
+/// Interpolation methods for filling missing values
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InterpolationMethod {
+    /// Carry the last known value forward
+    Previous,
+    /// Carry the next known value backward
+    Next,
+    /// Take the closest known value
+    Nearest,
+    /// Straight line between neighbouring known values
+    Linear,
+    /// Natural cubic spline through all known values
+    CubicSpline,
+    /// Monotone piecewise cubic Hermite (no overshoot)
+    Pchip,
+    /// Akima spline, robust to isolated outliers
+    Akima,
+    /// Local polynomial through the nearest known values
+    Polynomial,
+}
+
+/// How missing values before the first or after the last known value are filled
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExtrapolationPolicy {
+    /// Repeat the nearest known value
+    Hold,
+    /// Extend the interpolant past the known range
+    Extrapolate,
+    /// Leave the values as NaN
+    Leave,
+}
+
 /// Interpolate missing values in a numeric array
 ///
 /// Takes a numeric array with NaN values and returns an array with interpolated values.
+/// The optional arguments select the method (default linear), sample positions such as
+/// timestamps for uneven spacing (default the indices), the longest run of missing values
+/// to fill (longer runs stay NaN), the edge policy (default holding the nearest value) and
+/// the polynomial degree (default 2). Cubic methods extend linearly along the end slope
+/// when extrapolating.
 /// This is much faster than using JavaScript, especially for large arrays.
 #[wasm_bindgen]
-pub fn numeric_interpolate_missing_f64(input: &JsValue) -> Result<JsValue, JsValue> {
+pub fn numeric_interpolate_missing_f64(
+    input: &JsValue,
+    method: Option<InterpolationMethod>,
+    x: &JsValue,
+    max_gap: Option<usize>,
+    extrapolation: Option<ExtrapolationPolicy>,
+    degree: Option<usize>,
+) -> Result<JsValue, JsValue> {
     // Convert input to typed array for better performance
     let input_array = Float64Array::new(input);
     let length = input_array.length() as usize;
@@ -235,64 +283,39 @@ pub fn numeric_interpolate_missing_f64(input: &JsValue) -> Result<JsValue, JsVal
         return Ok(Float64Array::new_with_length(0).into());
     }
 
-    // Create a new typed array for the results
-    let result_array = Float64Array::new_with_length(length as u32);
-    
-    // Allocate memory for the input data
-    let bump = Bump::new();
-    let values = bump.alloc_slice_fill_copy(length, 0.0);
-    
-    // Copy input data and find first non-NaN value
-    let mut first_valid_index = None;
-    for i in 0..length {
-        let value = input_array.get_index(i as u32);
-        values[i] = value;
-        if !value.is_nan() && first_valid_index.is_none() {
-            first_valid_index = Some(i);
-        }
-    }
-    
-    // If all values are NaN, return the original array
-    if first_valid_index.is_none() {
-        return Ok(input_array.into());
+    let values = input_array.to_vec();
+
+    // Sample positions default to the indices
+    let positions = if x.is_undefined() || x.is_null() {
+        (0..length).map(|i| i as f64).collect::<Vec<f64>>()
+    } else {
+        Float64Array::new(x).to_vec()
+    };
+
+    // Validate inputs
+    if positions.len() != length {
+        return Err(JsValue::from_str("Positions must have the same length as the input"));
     }
-    
-    // Fill in leading NaN values with the first valid value
-    let first_valid = first_valid_index.unwrap();
-    for i in 0..first_valid {
-        result_array.set_index(i as u32, values[first_valid]);
+
+    if positions.iter().any(|p| !p.is_finite()) || positions.windows(2).any(|pair| pair[1] <= pair[0]) {
+        return Err(JsValue::from_str("Positions must be strictly increasing"));
     }
-    
-    // Copy the first valid value
-    result_array.set_index(first_valid as u32, values[first_valid]);
-    
-    // Interpolate middle values
-    let mut last_valid_index = first_valid;
-    let mut last_valid_value = values[first_valid];
-    
-    for i in (first_valid + 1)..length {
-        if !values[i].is_nan() {
-            // If we have a gap, interpolate
-            if i > last_valid_index + 1 {
-                let gap = i - last_valid_index;
-                let step = (values[i] - last_valid_value) / gap as f64;
-                
-                for j in 1..gap {
-                    result_array.set_index((last_valid_index + j) as u32, last_valid_value + step * j as f64);
-                }
-            }
-            
-            // Copy the current valid value
-            result_array.set_index(i as u32, values[i]);
-            last_valid_index = i;
-            last_valid_value = values[i];
-        } else if i == length - 1 {
-            // Fill in trailing NaN values with the last valid value
-            result_array.set_index(i as u32, last_valid_value);
-        }
+
+    let degree = degree.unwrap_or(2);
+    if degree == 0 {
+        return Err(JsValue::from_str("Polynomial degree must be at least 1"));
     }
-    
-    Ok(result_array.into())
+
+    let result = interpolate_missing(
+        &values,
+        &positions,
+        method.unwrap_or(InterpolationMethod::Linear),
+        max_gap,
+        extrapolation.unwrap_or(ExtrapolationPolicy::Hold),
+        degree,
+    );
+
+    Ok(Float64Array::from(&result[..]).into())
 }
 
 /// Calculate the autocorrelation of a numeric array
@@ -319,6 +342,19 @@ pub fn numeric_autocorrelation_f64(input: &JsValue, lag: usize) -> f64 {
         values[i] = input_array.get_index(i as u32);
     }
     
+    autocorrelation(values, lag)
+}
+
+/// Autocorrelation of a slice at the given lag
+///
+/// Shared by the wasm entry point above and the period detection and forecasting code.
+pub(crate) fn autocorrelation(values: &[f64], lag: usize) -> f64 {
+    let length = values.len();
+    
+    if length <= lag {
+        return f64::NAN;
+    }
+    
     // Calculate mean
     let mut sum = 0.0;
     for i in 0..length {
@@ -0,0 +1,870 @@
+use wasm_bindgen::prelude::*;
+use js_sys::{Float64Array, Object, Reflect, Uint32Array};
+use std::collections::VecDeque;
+
+use super::distributions::{ln_gamma, student_t_quantile};
+
+/// Segment cost functions for PELT change-point detection
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChangePointCost {
+    /// Shift in mean with a common variance
+    Mean,
+    /// Shift in mean and/or variance (Gaussian likelihood)
+    MeanVariance,
+    /// Shift in the rate of non-negative count data
+    Poisson,
+}
+
+/// Two-sided tabular CUSUM change detection
+///
+/// Takes a numeric array, the decision threshold and the drift allowance (both in
+/// standard deviations, typically 4-5 and 0.5), and optionally the in-control target
+/// and standard deviation (estimated from the data when omitted). Returns the indices
+/// where an alarm was raised along with the upper and lower cumulative sums. The sums
+/// reset after every alarm.
+#[wasm_bindgen]
+pub fn time_series_cusum_f64(
+    input: &JsValue,
+    threshold: f64,
+    drift: f64,
+    target: Option<f64>,
+    sigma: Option<f64>,
+) -> Result<JsValue, JsValue> {
+    // Convert input to typed array for better performance
+    let input_array = Float64Array::new(input);
+    let values = input_array.to_vec();
+    let length = values.len();
+
+    // Validate inputs
+    if threshold <= 0.0 {
+        return Err(JsValue::from_str("Threshold must be greater than 0"));
+    }
+
+    if drift < 0.0 {
+        return Err(JsValue::from_str("Drift must be non-negative"));
+    }
+
+    if length == 0 {
+        return Err(JsValue::from_str("Input must not be empty"));
+    }
+
+    let target = target.unwrap_or_else(|| values.iter().sum::<f64>() / length as f64);
+    let sigma = sigma.unwrap_or_else(|| {
+        let variance = values.iter().map(|v| (v - target) * (v - target)).sum::<f64>() / length as f64;
+        variance.sqrt()
+    });
+
+    if sigma <= 0.0 {
+        return Err(JsValue::from_str("Standard deviation must be greater than 0"));
+    }
+
+    let (alarms, positive, negative) = cusum(&values, target, sigma, threshold, drift);
+
+    // Create result object
+    let result = Object::new();
+    Reflect::set(&result, &JsValue::from_str("change_points"), &Uint32Array::from(&alarms[..]))?;
+    Reflect::set(&result, &JsValue::from_str("positive"), &Float64Array::from(&positive[..]))?;
+    Reflect::set(&result, &JsValue::from_str("negative"), &Float64Array::from(&negative[..]))?;
+
+    Ok(result.into())
+}
+
+/// PELT (Pruned Exact Linear Time) change-point detection
+///
+/// Takes a numeric array, the segment cost, an optional penalty per change point and the
+/// minimum segment length, and returns the indices where new segments start. The default
+/// penalty is BIC-like: (parameters per segment + 1) * ln(n). The `Mean` cost is scaled by
+/// a robust noise variance estimate, so its penalties are in units of that variance.
+#[wasm_bindgen]
+pub fn time_series_pelt_f64(
+    input: &JsValue,
+    cost: ChangePointCost,
+    penalty: Option<f64>,
+    min_size: usize,
+) -> Result<JsValue, JsValue> {
+    // Convert input to typed array for better performance
+    let input_array = Float64Array::new(input);
+    let values = input_array.to_vec();
+    let length = values.len();
+
+    // Validate inputs
+    if cost == ChangePointCost::Poisson && values.iter().any(|&v| v < 0.0) {
+        return Err(JsValue::from_str("Poisson cost requires non-negative values"));
+    }
+
+    if let Some(penalty) = penalty {
+        if penalty < 0.0 {
+            return Err(JsValue::from_str("Penalty must be non-negative"));
+        }
+    }
+
+    let min_size = min_size.max(if cost == ChangePointCost::MeanVariance { 2 } else { 1 });
+    if length < 2 * min_size {
+        return Ok(Uint32Array::new_with_length(0).into());
+    }
+
+    let penalty = penalty.unwrap_or_else(|| {
+        let parameters = if cost == ChangePointCost::MeanVariance { 2.0 } else { 1.0 };
+        (parameters + 1.0) * (length as f64).ln()
+    });
+
+    let change_points = pelt(&values, cost, penalty, min_size);
+
+    Ok(Uint32Array::from(&change_points[..]).into())
+}
+
+/// Bayesian online change-point detection over a whole series
+///
+/// Takes a numeric array and the expected run length between changes (the inverse of the
+/// constant hazard rate), and returns the most probable run length after each sample and
+/// the indices where a new segment was detected. The Normal-Gamma prior is centred on the
+/// sample mean and variance.
+#[wasm_bindgen]
+pub fn time_series_bocpd_f64(input: &JsValue, expected_run_length: f64) -> Result<JsValue, JsValue> {
+    // Convert input to typed array for better performance
+    let input_array = Float64Array::new(input);
+    let values = input_array.to_vec();
+    let length = values.len();
+
+    // Validate inputs
+    if expected_run_length <= 1.0 {
+        return Err(JsValue::from_str("Expected run length must be greater than 1"));
+    }
+
+    if length == 0 {
+        return Err(JsValue::from_str("Input must not be empty"));
+    }
+
+    let mean = values.iter().sum::<f64>() / length as f64;
+    let variance = values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / length as f64;
+
+    let mut detector = BayesianChangePointDetector::from_prior(
+        expected_run_length,
+        mean,
+        if variance > 0.0 { variance } else { 1.0 },
+        length + 1,
+    );
+
+    let mut run_lengths = Vec::with_capacity(length);
+    let mut change_points = Vec::new();
+    for (t, &value) in values.iter().enumerate() {
+        if detector.observe(value) {
+            change_points.push((t + 1 - detector.map_run_length) as u32);
+        }
+        run_lengths.push(detector.map_run_length as f64);
+    }
+
+    // Create result object
+    let result = Object::new();
+    Reflect::set(&result, &JsValue::from_str("run_lengths"), &Float64Array::from(&run_lengths[..]))?;
+    Reflect::set(&result, &JsValue::from_str("change_points"), &Uint32Array::from(&change_points[..]))?;
+
+    Ok(result.into())
+}
+
+/// Seasonal hybrid ESD anomaly detection over a whole series
+///
+/// Removes a periodic seasonal component (the per-phase median) and the overall median,
+/// then runs the generalized ESD test with median/MAD statistics. Takes a numeric array, the seasonal period (0 or 1 for
+/// non-seasonal data), the maximum fraction of anomalies and the significance level, and
+/// returns the anomalous indices in ascending order.
+#[wasm_bindgen]
+pub fn time_series_seasonal_esd_f64(
+    input: &JsValue,
+    period: usize,
+    max_anomalies: f64,
+    alpha: f64,
+) -> Result<JsValue, JsValue> {
+    // Convert input to typed array for better performance
+    let input_array = Float64Array::new(input);
+    let values = input_array.to_vec();
+
+    // Validate inputs
+    if max_anomalies <= 0.0 || max_anomalies >= 0.5 {
+        return Err(JsValue::from_str("Maximum anomaly fraction must be between 0 and 0.5 (exclusive)"));
+    }
+
+    if alpha <= 0.0 || alpha >= 1.0 {
+        return Err(JsValue::from_str("Alpha must be between 0 and 1 (exclusive)"));
+    }
+
+    if period >= 2 && values.len() < 2 * period {
+        return Err(JsValue::from_str("At least two full periods are required for seasonal ESD"));
+    }
+
+    let limit = (max_anomalies * values.len() as f64).floor() as usize;
+    let anomalies: Vec<u32> = seasonal_esd(&values, period, limit, alpha).iter().map(|&i| i as u32).collect();
+
+    Ok(Uint32Array::from(&anomalies[..]).into())
+}
+
+/// Streaming Bayesian online change-point detector (Adams & MacKay, 2007)
+///
+/// Maintains the posterior over the current run length with a Normal-Gamma model of each
+/// segment and a constant hazard rate. Each call to `update` consumes one sample and
+/// reports whether a new segment has started.
+#[wasm_bindgen]
+pub struct BayesianChangePointDetector {
+    hazard: f64,
+    prior: NormalGamma,
+    max_run_length: usize,
+    probabilities: Vec<f64>,
+    posteriors: Vec<NormalGamma>,
+    map_run_length: usize,
+    observations: usize,
+}
+
+/// Sufficient statistics of a Normal-Gamma posterior
+#[derive(Clone, Copy)]
+struct NormalGamma {
+    mean: f64,
+    kappa: f64,
+    alpha: f64,
+    beta: f64,
+}
+
+impl NormalGamma {
+    /// Log density of the Student's t posterior predictive at `x`
+    fn log_predictive(&self, x: f64) -> f64 {
+        let dof = 2.0 * self.alpha;
+        let scale2 = self.beta * (self.kappa + 1.0) / (self.alpha * self.kappa);
+        let z = (x - self.mean) * (x - self.mean) / (dof * scale2);
+
+        ln_gamma((dof + 1.0) / 2.0) - ln_gamma(dof / 2.0)
+            - 0.5 * (dof * std::f64::consts::PI * scale2).ln()
+            - (dof + 1.0) / 2.0 * z.ln_1p()
+    }
+
+    /// Posterior after observing `x`
+    fn updated(&self, x: f64) -> NormalGamma {
+        NormalGamma {
+            mean: (self.kappa * self.mean + x) / (self.kappa + 1.0),
+            kappa: self.kappa + 1.0,
+            alpha: self.alpha + 0.5,
+            beta: self.beta + self.kappa * (x - self.mean) * (x - self.mean) / (2.0 * (self.kappa + 1.0)),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl BayesianChangePointDetector {
+    /// Create a detector
+    ///
+    /// Takes the expected run length between changes, the prior mean and variance of the
+    /// data, and the longest run length to track (older hypotheses are truncated).
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        expected_run_length: f64,
+        prior_mean: f64,
+        prior_variance: f64,
+        max_run_length: usize,
+    ) -> Result<BayesianChangePointDetector, JsValue> {
+        if expected_run_length <= 1.0 {
+            return Err(JsValue::from_str("Expected run length must be greater than 1"));
+        }
+
+        if prior_variance <= 0.0 {
+            return Err(JsValue::from_str("Prior variance must be greater than 0"));
+        }
+
+        if max_run_length < 2 {
+            return Err(JsValue::from_str("Maximum run length must be at least 2"));
+        }
+
+        Ok(BayesianChangePointDetector::from_prior(expected_run_length, prior_mean, prior_variance, max_run_length))
+    }
+
+    /// Consume one sample and return true when the most probable run length resets
+    pub fn update(&mut self, value: f64) -> bool {
+        self.observe(value)
+    }
+
+    /// Most probable current run length
+    #[wasm_bindgen(getter)]
+    pub fn run_length(&self) -> usize {
+        self.map_run_length
+    }
+
+    /// Posterior probability of the most probable run length
+    #[wasm_bindgen(getter)]
+    pub fn run_length_probability(&self) -> f64 {
+        self.probabilities[self.map_run_length]
+    }
+
+    /// Posterior distribution over run lengths 0, 1, 2, ...
+    #[wasm_bindgen(getter)]
+    pub fn run_length_distribution(&self) -> Float64Array {
+        Float64Array::from(&self.probabilities[..])
+    }
+
+    /// Number of samples consumed so far
+    #[wasm_bindgen(getter)]
+    pub fn observations(&self) -> usize {
+        self.observations
+    }
+}
+
+impl BayesianChangePointDetector {
+    /// Build a detector from already validated parameters
+    pub(crate) fn from_prior(
+        expected_run_length: f64,
+        prior_mean: f64,
+        prior_variance: f64,
+        max_run_length: usize,
+    ) -> BayesianChangePointDetector {
+        let prior = NormalGamma {
+            mean: prior_mean,
+            kappa: 1.0,
+            alpha: 1.0,
+            beta: prior_variance,
+        };
+
+        BayesianChangePointDetector {
+            hazard: 1.0 / expected_run_length,
+            prior,
+            max_run_length,
+            probabilities: vec![1.0],
+            posteriors: vec![prior],
+            map_run_length: 0,
+            observations: 0,
+        }
+    }
+
+    /// Advance the run-length posterior by one sample
+    pub(crate) fn observe(&mut self, value: f64) -> bool {
+        let previous_map = self.map_run_length;
+        let count = self.probabilities.len();
+
+        // Weight each run-length hypothesis by how well it predicts the sample
+        let log_predictive: Vec<f64> = self.posteriors.iter().map(|p| p.log_predictive(value)).collect();
+        let max_log = log_predictive.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+
+        let mut grown = Vec::with_capacity(count + 1);
+        let mut change = 0.0;
+        grown.push(0.0);
+        for (&probability, &log_p) in self.probabilities.iter().zip(&log_predictive) {
+            let joint = probability * (log_p - max_log).exp();
+            change += joint * self.hazard;
+            grown.push(joint * (1.0 - self.hazard));
+        }
+        grown[0] = change;
+
+        // Truncate the longest hypotheses and renormalise
+        grown.truncate(self.max_run_length);
+        let total: f64 = grown.iter().sum();
+        if total > 0.0 && total.is_finite() {
+            for p in grown.iter_mut() {
+                *p /= total;
+            }
+        } else {
+            grown = vec![0.0; grown.len()];
+            grown[0] = 1.0;
+        }
+
+        let mut posteriors = Vec::with_capacity(grown.len());
+        posteriors.push(self.prior);
+        for posterior in self.posteriors.iter().take(grown.len() - 1) {
+            posteriors.push(posterior.updated(value));
+        }
+
+        self.probabilities = grown;
+        self.posteriors = posteriors;
+        self.observations += 1;
+
+        let mut best = 0;
+        for r in 1..self.probabilities.len() {
+            if self.probabilities[r] > self.probabilities[best] {
+                best = r;
+            }
+        }
+        self.map_run_length = best;
+
+        self.observations > 1 && best < previous_map
+    }
+}
+
+/// Streaming EWMA control chart
+///
+/// Estimates the in-control mean and standard deviation from the first `warmup` samples,
+/// then flags samples whose exponentially weighted mean leaves the control limits
+/// `mean +/- width * sigma * sqrt(lambda / (2 - lambda) * (1 - (1 - lambda)^(2t)))`.
+#[wasm_bindgen]
+pub struct EwmaControlChart {
+    lambda: f64,
+    width: f64,
+    warmup: usize,
+    count: usize,
+    mean: f64,
+    m2: f64,
+    sigma: f64,
+    ewma: f64,
+    steps: usize,
+}
+
+#[wasm_bindgen]
+impl EwmaControlChart {
+    /// Create a control chart
+    ///
+    /// Takes the smoothing factor lambda (typically 0.05-0.3), the control limit width in
+    /// standard deviations (typically 3) and the number of warm-up samples.
+    #[wasm_bindgen(constructor)]
+    pub fn new(lambda: f64, width: f64, warmup: usize) -> Result<EwmaControlChart, JsValue> {
+        if lambda <= 0.0 || lambda > 1.0 {
+            return Err(JsValue::from_str("Lambda must be between 0 and 1 (exclusive of 0)"));
+        }
+
+        if width <= 0.0 {
+            return Err(JsValue::from_str("Control limit width must be greater than 0"));
+        }
+
+        if warmup < 2 {
+            return Err(JsValue::from_str("At least 2 warm-up samples are required"));
+        }
+
+        Ok(EwmaControlChart {
+            lambda,
+            width,
+            warmup,
+            count: 0,
+            mean: 0.0,
+            m2: 0.0,
+            sigma: 0.0,
+            ewma: 0.0,
+            steps: 0,
+        })
+    }
+
+    /// Consume one sample and return true when it is out of control
+    pub fn update(&mut self, value: f64) -> bool {
+        // Accumulate the in-control statistics during warm-up (Welford's algorithm)
+        if self.count < self.warmup {
+            self.count += 1;
+            let delta = value - self.mean;
+            self.mean += delta / self.count as f64;
+            self.m2 += delta * (value - self.mean);
+
+            if self.count == self.warmup {
+                self.sigma = (self.m2 / (self.count - 1) as f64).sqrt();
+                self.ewma = self.mean;
+            }
+            return false;
+        }
+
+        self.steps += 1;
+        self.ewma = self.lambda * value + (1.0 - self.lambda) * self.ewma;
+
+        let margin = self.margin();
+        (self.ewma - self.mean).abs() > margin
+    }
+
+    /// Restart the chart, discarding the warm-up statistics
+    pub fn reset(&mut self) {
+        self.count = 0;
+        self.mean = 0.0;
+        self.m2 = 0.0;
+        self.sigma = 0.0;
+        self.ewma = 0.0;
+        self.steps = 0;
+    }
+
+    /// Current exponentially weighted mean
+    #[wasm_bindgen(getter)]
+    pub fn ewma(&self) -> f64 {
+        self.ewma
+    }
+
+    /// Current upper control limit
+    #[wasm_bindgen(getter)]
+    pub fn upper(&self) -> f64 {
+        self.mean + self.margin()
+    }
+
+    /// Current lower control limit
+    #[wasm_bindgen(getter)]
+    pub fn lower(&self) -> f64 {
+        self.mean - self.margin()
+    }
+
+    /// Whether the warm-up period has finished
+    #[wasm_bindgen(getter)]
+    pub fn ready(&self) -> bool {
+        self.count >= self.warmup
+    }
+}
+
+impl EwmaControlChart {
+    /// Half-width of the control band after the current number of steps
+    fn margin(&self) -> f64 {
+        let decay = (1.0 - self.lambda).powi(2 * self.steps as i32);
+        self.width * self.sigma * (self.lambda / (2.0 - self.lambda) * (1.0 - decay)).sqrt()
+    }
+}
+
+/// Streaming seasonal hybrid ESD detector
+///
+/// Keeps a sliding window of recent samples and flags a new sample when the seasonal
+/// hybrid ESD test over the window marks it as anomalous.
+#[wasm_bindgen]
+pub struct SeasonalEsdDetector {
+    period: usize,
+    window: usize,
+    max_anomalies: f64,
+    alpha: f64,
+    buffer: VecDeque<f64>,
+}
+
+#[wasm_bindgen]
+impl SeasonalEsdDetector {
+    /// Create a detector
+    ///
+    /// Takes the seasonal period (0 or 1 for non-seasonal data), the window length (at
+    /// least two periods), the maximum fraction of anomalies per window and the
+    /// significance level.
+    #[wasm_bindgen(constructor)]
+    pub fn new(period: usize, window: usize, max_anomalies: f64, alpha: f64) -> Result<SeasonalEsdDetector, JsValue> {
+        if window < 2 * period.max(4) {
+            return Err(JsValue::from_str("Window must cover at least two periods and 8 samples"));
+        }
+
+        if max_anomalies <= 0.0 || max_anomalies >= 0.5 {
+            return Err(JsValue::from_str("Maximum anomaly fraction must be between 0 and 0.5 (exclusive)"));
+        }
+
+        if alpha <= 0.0 || alpha >= 1.0 {
+            return Err(JsValue::from_str("Alpha must be between 0 and 1 (exclusive)"));
+        }
+
+        Ok(SeasonalEsdDetector {
+            period,
+            window,
+            max_anomalies,
+            alpha,
+            buffer: VecDeque::with_capacity(window + 1),
+        })
+    }
+
+    /// Consume one sample and return true when it is anomalous within the current window
+    pub fn update(&mut self, value: f64) -> bool {
+        self.buffer.push_back(value);
+        if self.buffer.len() > self.window {
+            self.buffer.pop_front();
+        }
+
+        if self.buffer.len() < self.window {
+            return false;
+        }
+
+        let values: Vec<f64> = self.buffer.iter().copied().collect();
+        let limit = ((self.max_anomalies * values.len() as f64).floor() as usize).max(1);
+        seasonal_esd(&values, self.period, limit, self.alpha).contains(&(values.len() - 1))
+    }
+
+    /// Number of samples currently held in the window
+    #[wasm_bindgen(getter)]
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+}
+
+/// Tabular CUSUM returning the alarm indices and the upper and lower sums
+fn cusum(values: &[f64], target: f64, sigma: f64, threshold: f64, drift: f64) -> (Vec<u32>, Vec<f64>, Vec<f64>) {
+    let mut alarms = Vec::new();
+    let mut positive = Vec::with_capacity(values.len());
+    let mut negative = Vec::with_capacity(values.len());
+    let mut upper: f64 = 0.0;
+    let mut lower: f64 = 0.0;
+
+    for (i, &value) in values.iter().enumerate() {
+        let z = (value - target) / sigma;
+        upper = (upper + z - drift).max(0.0);
+        lower = (lower - z - drift).max(0.0);
+
+        positive.push(upper);
+        negative.push(lower);
+
+        if upper > threshold || lower > threshold {
+            alarms.push(i as u32);
+            upper = 0.0;
+            lower = 0.0;
+        }
+    }
+
+    (alarms, positive, negative)
+}
+
+/// PELT search returning the start index of every segment after the first
+pub(crate) fn pelt(values: &[f64], cost: ChangePointCost, penalty: f64, min_size: usize) -> Vec<u32> {
+    let length = values.len();
+
+    // Prefix sums give O(1) segment costs
+    let mut sums = vec![0.0; length + 1];
+    let mut squares = vec![0.0; length + 1];
+    for i in 0..length {
+        sums[i + 1] = sums[i] + values[i];
+        squares[i + 1] = squares[i] + values[i] * values[i];
+    }
+
+    let noise_variance = if cost == ChangePointCost::Mean { robust_noise_variance(values) } else { 1.0 };
+    let total_variance = ((squares[length] - sums[length] * sums[length] / length as f64) / length as f64).max(1e-12);
+
+    let segment_cost = |start: usize, end: usize| -> f64 {
+        let n = (end - start) as f64;
+        let s1 = sums[end] - sums[start];
+        let s2 = squares[end] - squares[start];
+
+        match cost {
+            ChangePointCost::Mean => (s2 - s1 * s1 / n).max(0.0) / noise_variance,
+            ChangePointCost::MeanVariance => {
+                let variance = ((s2 - s1 * s1 / n) / n).max(1e-8 * total_variance);
+                n * variance.ln()
+            }
+            ChangePointCost::Poisson => {
+                if s1 > 0.0 { 2.0 * (s1 - s1 * (s1 / n).ln()) } else { 0.0 }
+            }
+        }
+    };
+
+    let mut best = vec![f64::INFINITY; length + 1];
+    let mut previous = vec![0usize; length + 1];
+    let mut candidates: Vec<usize> = vec![0];
+    best[0] = -penalty;
+
+    for end in min_size..=length {
+        let mut totals = Vec::with_capacity(candidates.len());
+        for &start in &candidates {
+            if end - start >= min_size {
+                let value = best[start] + segment_cost(start, end) + penalty;
+                if value < best[end] {
+                    best[end] = value;
+                    previous[end] = start;
+                }
+                totals.push(Some(value));
+            } else {
+                totals.push(None);
+            }
+        }
+
+        // Prune candidates that can never be optimal again
+        let threshold = best[end] + penalty;
+        candidates = candidates
+            .iter()
+            .zip(totals.iter())
+            .filter(|(_, total)| total.is_none_or(|value| value <= threshold))
+            .map(|(&start, _)| start)
+            .collect();
+        candidates.push(end);
+    }
+
+    let mut change_points = Vec::new();
+    let mut end = length;
+    while end > 0 {
+        let start = previous[end];
+        if start > 0 {
+            change_points.push(start as u32);
+        }
+        end = start;
+    }
+    change_points.reverse();
+
+    change_points
+}
+
+/// Noise variance from the median absolute deviation of first differences
+fn robust_noise_variance(values: &[f64]) -> f64 {
+    let differences: Vec<f64> = values.windows(2).map(|w| w[1] - w[0]).collect();
+    if !differences.is_empty() {
+        let centre = median(&differences);
+        let deviations: Vec<f64> = differences.iter().map(|d| (d - centre).abs()).collect();
+        let sigma = 1.4826 * median(&deviations) / std::f64::consts::SQRT_2;
+        if sigma > 0.0 {
+            return sigma * sigma;
+        }
+    }
+
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let variance = values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / values.len() as f64;
+    if variance > 0.0 { variance } else { 1.0 }
+}
+
+/// Seasonal hybrid ESD returning the anomalous indices in ascending order
+pub(crate) fn seasonal_esd(values: &[f64], period: usize, max_anomalies: usize, alpha: f64) -> Vec<usize> {
+    let length = values.len();
+    let centre = median(values);
+
+    // Remove a periodic seasonal component (the median of each phase) and the median
+    let residuals: Vec<f64> = if period >= 2 && length >= 2 * period {
+        let seasonal: Vec<f64> = (0..period)
+            .map(|phase| {
+                let cycle: Vec<f64> = values.iter().skip(phase).step_by(period).map(|v| v - centre).collect();
+                median(&cycle)
+            })
+            .collect();
+        (0..length).map(|i| values[i] - seasonal[i % period] - centre).collect()
+    } else {
+        values.iter().map(|v| v - centre).collect()
+    };
+
+    // Generalized ESD with median and MAD in place of mean and standard deviation
+    let mut remaining: Vec<usize> = (0..length).collect();
+    let mut removed = Vec::new();
+    let mut anomaly_count = 0;
+
+    for i in 1..=max_anomalies.min(length.saturating_sub(3)) {
+        let current: Vec<f64> = remaining.iter().map(|&j| residuals[j]).collect();
+        let location = median(&current);
+        let deviations: Vec<f64> = current.iter().map(|v| (v - location).abs()).collect();
+        let mad = 1.4826 * median(&deviations);
+
+        if mad == 0.0 {
+            break;
+        }
+
+        let (position, deviation) = deviations
+            .iter()
+            .enumerate()
+            .fold((0, f64::NEG_INFINITY), |best, (k, &d)| if d > best.1 { (k, d) } else { best });
+
+        let statistic = deviation / mad;
+        let n = remaining.len() as f64;
+        let p = 1.0 - alpha / (2.0 * n);
+        let t = student_t_quantile(p, n - 2.0);
+        let critical = (n - 1.0) * t / ((n - 2.0 + t * t) * n).sqrt();
+
+        if statistic > critical {
+            anomaly_count = i;
+        }
+
+        removed.push(remaining.remove(position));
+    }
+
+    let mut anomalies: Vec<usize> = removed.into_iter().take(anomaly_count).collect();
+    anomalies.sort_unstable();
+    anomalies
+}
+
+/// Median of a slice (NaN for an empty slice)
+fn median(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return f64::NAN;
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        0.5 * (sorted[mid - 1] + sorted[mid])
+    } else {
+        sorted[mid]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::random::Rng;
+
+    /// Unit-variance noise around the given segment means
+    fn segments(means: &[(usize, f64)], seed: u64) -> Vec<f64> {
+        let mut rng = Rng::new(seed);
+        means
+            .iter()
+            .flat_map(|&(length, mean)| (0..length).map(|_| mean).collect::<Vec<f64>>())
+            .map(|mean| mean + rng.next_normal())
+            .collect()
+    }
+
+    #[test]
+    fn cusum_alarms_only_after_the_shift() {
+        let values = segments(&[(100, 0.0), (50, 2.0)], 1);
+        let (alarms, upper, lower) = cusum(&values, 0.0, 1.0, 5.0, 0.5);
+
+        assert!(!alarms.is_empty());
+        assert!(alarms[0] >= 100 && alarms[0] < 110);
+        assert_eq!(upper.len(), values.len());
+        assert!(upper.iter().chain(&lower).all(|&s| s >= 0.0));
+    }
+
+    #[test]
+    fn pelt_finds_mean_shifts() {
+        let values = segments(&[(60, 0.0), (60, 5.0), (60, -3.0)], 2);
+        let penalty = 4.0 * (values.len() as f64).ln();
+        let change_points = pelt(&values, ChangePointCost::Mean, penalty, 1);
+
+        assert_eq!(change_points.len(), 2);
+        assert!((change_points[0] as i64 - 60).abs() <= 2);
+        assert!((change_points[1] as i64 - 120).abs() <= 2);
+    }
+
+    #[test]
+    fn pelt_finds_a_variance_change_and_keeps_a_flat_series_whole() {
+        let mut values = segments(&[(100, 0.0), (100, 0.0)], 3);
+        values[100..].iter_mut().for_each(|v| *v *= 6.0);
+        let penalty = 3.0 * (values.len() as f64).ln();
+        let change_points = pelt(&values, ChangePointCost::MeanVariance, penalty, 2);
+
+        assert_eq!(change_points.len(), 1);
+        assert!((change_points[0] as i64 - 100).abs() <= 5);
+
+        let flat = segments(&[(200, 0.0)], 4);
+        assert!(pelt(&flat, ChangePointCost::Mean, 2.0 * (200f64).ln(), 1).is_empty());
+    }
+
+    #[test]
+    fn pelt_poisson_cost_tracks_count_rates() {
+        let values: Vec<f64> = (0..80).map(|i| if i < 40 { (i % 3) as f64 } else { 10.0 + (i % 5) as f64 }).collect();
+        let change_points = pelt(&values, ChangePointCost::Poisson, 2.0 * (80f64).ln(), 1);
+
+        assert_eq!(change_points, vec![40]);
+    }
+
+    #[test]
+    fn bocpd_restarts_the_run_after_a_change() {
+        let values = segments(&[(80, 0.0), (80, 8.0)], 5);
+        let mut detector = BayesianChangePointDetector::from_prior(100.0, 0.0, 1.0, 500);
+
+        let detections: Vec<usize> = values.iter().enumerate().filter(|&(_, &v)| detector.observe(v)).map(|(i, _)| i).collect();
+
+        assert!(detections.iter().any(|&i| (80..85).contains(&i)));
+        assert!(detections.iter().all(|&i| i >= 80));
+        assert!(detector.map_run_length >= 70 && detector.map_run_length <= 80);
+        assert!((detector.probabilities.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn ewma_chart_flags_a_sustained_shift_after_warm_up() {
+        let values = segments(&[(50, 10.0), (30, 10.0), (30, 13.0)], 6);
+        let mut chart = EwmaControlChart::new(0.2, 3.0, 50).unwrap();
+
+        let flags: Vec<bool> = values.iter().map(|&v| chart.update(v)).collect();
+        assert!(flags[..50].iter().all(|&f| !f));
+        assert!(flags[80..].iter().any(|&f| f));
+        assert!((chart.mean - 10.0).abs() < 0.5);
+
+        chart.reset();
+        assert!(!chart.ready());
+    }
+
+    #[test]
+    fn seasonal_esd_finds_a_spike_in_a_periodic_series() {
+        let pattern = [5.0, 1.0, -2.0, 0.0, 3.0, -4.0, 2.0];
+        // Bounded wobble so that only the spike is far from its phase
+        let mut values: Vec<f64> = (0..70).map(|i| pattern[i % 7] + 0.2 * (1.7 * i as f64).sin()).collect();
+        values[50] += 6.0;
+
+        assert_eq!(seasonal_esd(&values, 7, 7, 0.05), vec![50]);
+
+        let mut detector = SeasonalEsdDetector::new(7, 35, 0.1, 0.05).unwrap();
+        let flagged: Vec<usize> = values.iter().enumerate().filter(|&(_, &v)| detector.update(v)).map(|(i, _)| i).collect();
+        assert_eq!(flagged, vec![50]);
+        assert_eq!(detector.buffered(), 35);
+    }
+
+    #[test]
+    fn median_of_even_and_odd_lengths() {
+        assert_eq!(median(&[3.0, 1.0, 2.0]), 2.0);
+        assert_eq!(median(&[4.0, 1.0, 3.0, 2.0]), 2.5);
+        assert!(median(&[]).is_nan());
+    }
+}
@@ -0,0 +1,682 @@
+use wasm_bindgen::prelude::*;
+use js_sys::{Float64Array, Object, Reflect};
+
+use super::linalg::solve_banded_spd;
+use super::signal::{fft_complex, Complex};
+use super::time_series::autocorrelation;
+
+/// Models for classical seasonal decomposition
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DecompositionModel {
+    Additive,
+    Multiplicative,
+}
+
+/// Methods for automatic period detection
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PeriodDetectionMethod {
+    Autocorrelation,
+    Periodogram,
+}
+
+/// Trend, seasonal and residual components of a decomposed series
+pub(crate) struct Decomposition {
+    pub(crate) trend: Vec<f64>,
+    pub(crate) seasonal: Vec<f64>,
+    pub(crate) residual: Vec<f64>,
+}
+
+/// Classical seasonal decomposition using moving averages
+///
+/// Takes a numeric array, the seasonal period and the model, and returns the trend,
+/// seasonal and residual components along with the per-phase seasonal indices.
+/// The trend (and therefore the residual) is NaN for the first and last half period.
+#[wasm_bindgen]
+pub fn time_series_decompose_f64(
+    input: &JsValue,
+    period: usize,
+    model: DecompositionModel,
+) -> Result<JsValue, JsValue> {
+    // Convert input to typed array for better performance
+    let input_array = Float64Array::new(input);
+    let values = input_array.to_vec();
+
+    // Validate inputs
+    if period < 2 {
+        return Err(JsValue::from_str("Period must be at least 2"));
+    }
+
+    if values.len() < 2 * period {
+        return Err(JsValue::from_str("At least two full periods are required for decomposition"));
+    }
+
+    if model == DecompositionModel::Multiplicative && values.iter().any(|&v| v <= 0.0) {
+        return Err(JsValue::from_str("Multiplicative decomposition requires strictly positive values"));
+    }
+
+    let (decomposition, seasonal_indices) = classical_decompose(&values, period, model);
+
+    // Create result object
+    let result = Object::new();
+    Reflect::set(&result, &JsValue::from_str("trend"), &Float64Array::from(&decomposition.trend[..]))?;
+    Reflect::set(&result, &JsValue::from_str("seasonal"), &Float64Array::from(&decomposition.seasonal[..]))?;
+    Reflect::set(&result, &JsValue::from_str("residual"), &Float64Array::from(&decomposition.residual[..]))?;
+    Reflect::set(&result, &JsValue::from_str("seasonal_indices"), &Float64Array::from(&seasonal_indices[..]))?;
+
+    Ok(result.into())
+}
+
+/// Seasonal-Trend decomposition using LOESS (STL)
+///
+/// Takes a numeric array and the seasonal period, and returns the trend, seasonal and
+/// residual components. The seasonal and trend smoother spans default to the values
+/// recommended by Cleveland et al. when omitted. With `robust` enabled, outer iterations
+/// down-weight outliers and the final robustness weights are returned as well.
+#[wasm_bindgen]
+pub fn time_series_stl_f64(
+    input: &JsValue,
+    period: usize,
+    seasonal_window: Option<usize>,
+    trend_window: Option<usize>,
+    robust: bool,
+) -> Result<JsValue, JsValue> {
+    // Convert input to typed array for better performance
+    let input_array = Float64Array::new(input);
+    let values = input_array.to_vec();
+
+    // Validate inputs
+    if period < 2 {
+        return Err(JsValue::from_str("Period must be at least 2"));
+    }
+
+    if values.len() < 2 * period {
+        return Err(JsValue::from_str("At least two full periods are required for STL"));
+    }
+
+    let seasonal_window = next_odd(seasonal_window.unwrap_or(7).max(3));
+    let trend_window = next_odd(trend_window.unwrap_or_else(|| default_trend_window(period, seasonal_window)).max(3));
+
+    let (decomposition, weights) = stl_decompose(&values, period, seasonal_window, trend_window, robust);
+
+    // Create result object
+    let result = Object::new();
+    Reflect::set(&result, &JsValue::from_str("trend"), &Float64Array::from(&decomposition.trend[..]))?;
+    Reflect::set(&result, &JsValue::from_str("seasonal"), &Float64Array::from(&decomposition.seasonal[..]))?;
+    Reflect::set(&result, &JsValue::from_str("residual"), &Float64Array::from(&decomposition.residual[..]))?;
+    Reflect::set(&result, &JsValue::from_str("weights"), &Float64Array::from(&weights[..]))?;
+
+    Ok(result.into())
+}
+
+/// Hodrick-Prescott filter
+///
+/// Takes a numeric array and the smoothing parameter lambda (1600 for quarterly data),
+/// and returns the trend and cyclical components.
+#[wasm_bindgen]
+pub fn time_series_hodrick_prescott_f64(input: &JsValue, lambda: f64) -> Result<JsValue, JsValue> {
+    // Convert input to typed array for better performance
+    let input_array = Float64Array::new(input);
+    let values = input_array.to_vec();
+    let length = values.len();
+
+    // Validate inputs
+    if lambda < 0.0 {
+        return Err(JsValue::from_str("Lambda must be non-negative"));
+    }
+
+    if length < 3 {
+        return Err(JsValue::from_str("At least 3 data points are required for the Hodrick-Prescott filter"));
+    }
+
+    let trend = hodrick_prescott(&values, lambda)
+        .ok_or_else(|| JsValue::from_str("Hodrick-Prescott system could not be solved"))?;
+    let cycle: Vec<f64> = values.iter().zip(trend.iter()).map(|(y, t)| y - t).collect();
+
+    // Create result object
+    let result = Object::new();
+    Reflect::set(&result, &JsValue::from_str("trend"), &Float64Array::from(&trend[..]))?;
+    Reflect::set(&result, &JsValue::from_str("cycle"), &Float64Array::from(&cycle[..]))?;
+
+    Ok(result.into())
+}
+
+/// LOESS smoothing of an evenly spaced series
+///
+/// Takes a numeric array, the span as a fraction of the series length, and the number of
+/// robustness iterations (0 for a plain local linear fit), and returns the smoothed values.
+#[wasm_bindgen]
+pub fn time_series_loess_f64(input: &JsValue, span: f64, robust_iterations: usize) -> Result<JsValue, JsValue> {
+    // Convert input to typed array for better performance
+    let input_array = Float64Array::new(input);
+    let values = input_array.to_vec();
+    let length = values.len();
+
+    // Validate span
+    if span <= 0.0 || span > 1.0 {
+        return Err(JsValue::from_str("Span must be between 0 and 1 (exclusive of 0)"));
+    }
+
+    // Early return for empty arrays
+    if length == 0 {
+        return Ok(Float64Array::new_with_length(0).into());
+    }
+
+    let window = ((span * length as f64).ceil() as usize).max(2);
+    let mut weights = vec![1.0; length];
+    let mut smoothed = loess_smooth(&values, &weights, window);
+
+    for _ in 0..robust_iterations {
+        let residuals: Vec<f64> = values.iter().zip(smoothed.iter()).map(|(y, s)| y - s).collect();
+        weights = robustness_weights(&residuals);
+        smoothed = loess_smooth(&values, &weights, window);
+    }
+
+    Ok(Float64Array::from(&smoothed[..]).into())
+}
+
+/// Detect the dominant seasonal period of a series
+///
+/// Takes a numeric array, the largest period to consider and the detection method, and
+/// returns the detected period together with its strength (the autocorrelation at that
+/// lag, or the share of spectral power at that frequency). The period is 0 when no
+/// seasonality is found.
+#[wasm_bindgen]
+pub fn time_series_detect_period_f64(
+    input: &JsValue,
+    max_period: usize,
+    method: PeriodDetectionMethod,
+) -> Result<JsValue, JsValue> {
+    // Convert input to typed array for better performance
+    let input_array = Float64Array::new(input);
+    let values = input_array.to_vec();
+
+    // Validate inputs
+    if values.len() < 4 {
+        return Err(JsValue::from_str("At least 4 data points are required for period detection"));
+    }
+
+    let max_period = max_period.min(values.len() / 2);
+    if max_period < 2 {
+        return Err(JsValue::from_str("Maximum period must be at least 2"));
+    }
+
+    let (period, strength) = match method {
+        PeriodDetectionMethod::Autocorrelation => detect_period_acf(&values, max_period),
+        PeriodDetectionMethod::Periodogram => detect_period_periodogram(&values, max_period),
+    };
+
+    // Create result object
+    let result = Object::new();
+    Reflect::set(&result, &JsValue::from_str("period"), &JsValue::from_f64(period as f64))?;
+    Reflect::set(&result, &JsValue::from_str("strength"), &JsValue::from_f64(strength))?;
+
+    Ok(result.into())
+}
+
+/// Classical decomposition returning the components and the normalised seasonal indices
+pub(crate) fn classical_decompose(
+    values: &[f64],
+    period: usize,
+    model: DecompositionModel,
+) -> (Decomposition, Vec<f64>) {
+    let length = values.len();
+    let trend = centered_moving_average(values, period);
+
+    // Average the detrended values for each phase of the cycle
+    let mut phase_sums = vec![0.0; period];
+    let mut phase_counts = vec![0usize; period];
+
+    for i in 0..length {
+        if trend[i].is_nan() {
+            continue;
+        }
+        let detrended = match model {
+            DecompositionModel::Additive => values[i] - trend[i],
+            DecompositionModel::Multiplicative => values[i] / trend[i],
+        };
+        phase_sums[i % period] += detrended;
+        phase_counts[i % period] += 1;
+    }
+
+    let mut indices: Vec<f64> = phase_sums
+        .iter()
+        .zip(phase_counts.iter())
+        .map(|(&sum, &count)| if count > 0 { sum / count as f64 } else { 0.0 })
+        .collect();
+
+    // Normalise so the seasonal component sums to zero (additive) or averages one (multiplicative)
+    let mean_index = indices.iter().sum::<f64>() / period as f64;
+    for index in indices.iter_mut() {
+        match model {
+            DecompositionModel::Additive => *index -= mean_index,
+            DecompositionModel::Multiplicative => *index /= mean_index,
+        }
+    }
+
+    let seasonal: Vec<f64> = (0..length).map(|i| indices[i % period]).collect();
+    let residual: Vec<f64> = (0..length)
+        .map(|i| match model {
+            DecompositionModel::Additive => values[i] - trend[i] - seasonal[i],
+            DecompositionModel::Multiplicative => values[i] / (trend[i] * seasonal[i]),
+        })
+        .collect();
+
+    (Decomposition { trend, seasonal, residual }, indices)
+}
+
+/// Centered moving average over one period (2 x period for even periods), NaN at the edges
+fn centered_moving_average(values: &[f64], period: usize) -> Vec<f64> {
+    let length = values.len();
+    let mut result = vec![f64::NAN; length];
+    let half = period / 2;
+
+    if period % 2 == 1 {
+        for i in half..(length - half) {
+            result[i] = values[(i - half)..=(i + half)].iter().sum::<f64>() / period as f64;
+        }
+    } else {
+        for i in half..(length - half) {
+            let inner: f64 = values[(i - half + 1)..(i + half)].iter().sum();
+            let edges = 0.5 * (values[i - half] + values[i + half]);
+            result[i] = (inner + edges) / period as f64;
+        }
+    }
+
+    result
+}
+
+/// STL decomposition returning the components and the final robustness weights
+pub(crate) fn stl_decompose(
+    values: &[f64],
+    period: usize,
+    seasonal_window: usize,
+    trend_window: usize,
+    robust: bool,
+) -> (Decomposition, Vec<f64>) {
+    let length = values.len();
+    let lowpass_window = next_odd(period);
+    let (inner_iterations, outer_iterations) = if robust { (2, 15) } else { (5, 0) };
+
+    let mut trend = vec![0.0; length];
+    let mut seasonal = vec![0.0; length];
+    let mut weights = vec![1.0; length];
+
+    for outer in 0..=outer_iterations {
+        for _ in 0..inner_iterations {
+            // Detrend and smooth each cycle-subseries, extended by one period at both ends
+            let detrended: Vec<f64> = values.iter().zip(trend.iter()).map(|(y, t)| y - t).collect();
+            let cycle = smooth_cycle_subseries(&detrended, &weights, period, seasonal_window);
+
+            // Low-pass filter the smoothed subseries to remove any leaked trend
+            let lowpass = moving_average(&cycle, period);
+            let lowpass = moving_average(&lowpass, period);
+            let lowpass = moving_average(&lowpass, 3);
+            let lowpass = loess_smooth(&lowpass, &vec![1.0; length], lowpass_window);
+
+            for i in 0..length {
+                seasonal[i] = cycle[period + i] - lowpass[i];
+            }
+
+            // Smooth the deseasonalised series to get the trend
+            let deseasonalised: Vec<f64> = values.iter().zip(seasonal.iter()).map(|(y, s)| y - s).collect();
+            trend = loess_smooth(&deseasonalised, &weights, trend_window);
+        }
+
+        if outer < outer_iterations {
+            let residuals: Vec<f64> = (0..length).map(|i| values[i] - trend[i] - seasonal[i]).collect();
+            weights = robustness_weights(&residuals);
+        }
+    }
+
+    let residual: Vec<f64> = (0..length).map(|i| values[i] - trend[i] - seasonal[i]).collect();
+
+    (Decomposition { trend, seasonal, residual }, weights)
+}
+
+/// Smooth every cycle-subseries with LOESS and return them interleaved with one extra
+/// period on each side (length `n + 2 * period`)
+fn smooth_cycle_subseries(values: &[f64], weights: &[f64], period: usize, window: usize) -> Vec<f64> {
+    let length = values.len();
+    let mut result = vec![0.0; length + 2 * period];
+
+    for phase in 0..period {
+        let subseries: Vec<f64> = values.iter().skip(phase).step_by(period).copied().collect();
+        let subweights: Vec<f64> = weights.iter().skip(phase).step_by(period).copied().collect();
+        let sub_length = subseries.len();
+
+        // Evaluate from one position before the start to one after the end
+        for j in 0..(sub_length + 2) {
+            let position = j as f64 - 1.0;
+            let fallback = subseries[(j.saturating_sub(1)).min(sub_length - 1)];
+            let smoothed = loess_point(&subseries, &subweights, position, window).unwrap_or(fallback);
+            let index = phase + j * period;
+            if index < result.len() {
+                result[index] = smoothed;
+            }
+        }
+    }
+
+    result
+}
+
+/// Smooth a series with LOESS evaluated at every index
+pub(crate) fn loess_smooth(values: &[f64], weights: &[f64], window: usize) -> Vec<f64> {
+    (0..values.len())
+        .map(|i| loess_point(values, weights, i as f64, window).unwrap_or(values[i]))
+        .collect()
+}
+
+/// Local linear fit with tricube weights at position `x` using the `window` nearest points
+///
+/// Positions are the indices of `values`; `x` may fall outside the range for extrapolation.
+/// Returns `None` when every point in the neighbourhood has zero weight.
+pub(crate) fn loess_point(values: &[f64], weights: &[f64], x: f64, window: usize) -> Option<f64> {
+    let length = values.len();
+    if length == 0 {
+        return None;
+    }
+
+    // Select the neighbourhood and its radius, widening it if the window exceeds the data
+    let (lo, hi, radius) = if window >= length {
+        let hi = length - 1;
+        let radius = (x - 0.0).max(hi as f64 - x) + (window - length) as f64 / 2.0;
+        (0, hi, radius)
+    } else {
+        let start = (x - (window as f64 - 1.0) / 2.0).floor().max(0.0) as usize;
+        let lo = start.min(length - window);
+        let hi = lo + window - 1;
+        (lo, hi, (x - lo as f64).max(hi as f64 - x))
+    };
+
+    // Tricube neighbourhood weights combined with the robustness weights
+    let mut local = vec![0.0; hi - lo + 1];
+    let mut total = 0.0;
+    for i in lo..=hi {
+        let distance = (i as f64 - x).abs();
+        let w = if radius <= 0.0 || distance <= 0.001 * radius {
+            1.0
+        } else if distance <= 0.999 * radius {
+            let ratio = distance / radius;
+            let t = 1.0 - ratio * ratio * ratio;
+            t * t * t
+        } else {
+            0.0
+        };
+        local[i - lo] = w * weights[i];
+        total += local[i - lo];
+    }
+
+    if total <= 0.0 {
+        return None;
+    }
+
+    for w in local.iter_mut() {
+        *w /= total;
+    }
+
+    // Add the linear term when the neighbourhood is wide enough
+    if radius > 0.0 {
+        let centre: f64 = (lo..=hi).map(|i| local[i - lo] * i as f64).sum();
+        let spread: f64 = (lo..=hi).map(|i| local[i - lo] * (i as f64 - centre).powi(2)).sum();
+        let range = (length - 1) as f64;
+
+        if spread.sqrt() > 0.001 * range {
+            let slope = (x - centre) / spread;
+            for i in lo..=hi {
+                local[i - lo] *= slope * (i as f64 - centre) + 1.0;
+            }
+        }
+    }
+
+    Some((lo..=hi).map(|i| local[i - lo] * values[i]).sum())
+}
+
+/// Bisquare robustness weights based on six times the median absolute residual
+pub(crate) fn robustness_weights(residuals: &[f64]) -> Vec<f64> {
+    let mut absolute: Vec<f64> = residuals.iter().map(|r| r.abs()).collect();
+    absolute.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mid = absolute.len() / 2;
+    let median = if absolute.len().is_multiple_of(2) {
+        0.5 * (absolute[mid - 1] + absolute[mid])
+    } else {
+        absolute[mid]
+    };
+    let scale = 6.0 * median;
+
+    residuals
+        .iter()
+        .map(|r| {
+            if scale == 0.0 {
+                return 1.0;
+            }
+            let u = r.abs() / scale;
+            if u < 1.0 {
+                let t = 1.0 - u * u;
+                t * t
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
+
+/// Trailing moving average, returning `n - window + 1` values
+fn moving_average(values: &[f64], window: usize) -> Vec<f64> {
+    let mut result = Vec::with_capacity(values.len() + 1 - window);
+    let mut window_sum: f64 = values[..window].iter().sum();
+    result.push(window_sum / window as f64);
+
+    for i in window..values.len() {
+        window_sum += values[i] - values[i - window];
+        result.push(window_sum / window as f64);
+    }
+
+    result
+}
+
+/// Hodrick-Prescott trend, solving `(I + lambda D'D) trend = values`
+pub(crate) fn hodrick_prescott(values: &[f64], lambda: f64) -> Option<Vec<f64>> {
+    let length = values.len();
+    let mut bands = vec![vec![0.0; 3]; length];
+
+    for band in bands.iter_mut() {
+        band[0] = 1.0;
+    }
+
+    // Accumulate lambda * D'D where each row of D is the second difference [1, -2, 1]
+    let coefficients = [1.0, -2.0, 1.0];
+    for row in 0..(length - 2) {
+        for a in 0..3 {
+            for b in 0..=a {
+                bands[row + a][a - b] += lambda * coefficients[a] * coefficients[b];
+            }
+        }
+    }
+
+    solve_banded_spd(&bands, 2, values)
+}
+
+/// Pick the lag with the strongest autocorrelation peak
+fn detect_period_acf(values: &[f64], max_period: usize) -> (usize, f64) {
+    let acf: Vec<f64> = (0..=(max_period + 1).min(values.len() - 1))
+        .map(|lag| autocorrelation(values, lag))
+        .collect();
+
+    let mut best = (0, 0.0);
+    for lag in 2..=max_period.min(acf.len() - 2) {
+        let is_peak = acf[lag] > acf[lag - 1] && acf[lag] >= acf[lag + 1];
+        if is_peak && acf[lag] > best.1 {
+            best = (lag, acf[lag]);
+        }
+    }
+
+    best
+}
+
+/// Pick the period whose frequency carries the most power in the zero-padded periodogram
+fn detect_period_periodogram(values: &[f64], max_period: usize) -> (usize, f64) {
+    let length = values.len();
+    let padded_length = (2 * length).next_power_of_two();
+
+    // Remove the linear trend so it does not dominate the low frequencies
+    let mean_x = (length - 1) as f64 / 2.0;
+    let mean_y = values.iter().sum::<f64>() / length as f64;
+    let mut numerator = 0.0;
+    let mut denominator = 0.0;
+    for (i, &value) in values.iter().enumerate() {
+        numerator += (i as f64 - mean_x) * (value - mean_y);
+        denominator += (i as f64 - mean_x) * (i as f64 - mean_x);
+    }
+    let slope = numerator / denominator;
+
+    let mut buffer = vec![Complex::new(0.0, 0.0); padded_length];
+    for (i, (slot, &value)) in buffer.iter_mut().zip(values).enumerate() {
+        slot.real = value - mean_y - slope * (i as f64 - mean_x);
+    }
+
+    let spectrum = fft_complex(&buffer);
+    let power: Vec<f64> = spectrum[..=padded_length / 2].iter().map(|c| c.norm_sqr()).collect();
+    let total: f64 = power[1..].iter().sum();
+
+    if total == 0.0 {
+        return (0, 0.0);
+    }
+
+    // Only consider frequencies whose period lies in [2, max_period]
+    let min_bin = ((padded_length as f64 / max_period as f64).ceil() as usize).max(1);
+    let mut best_bin = 0;
+    for bin in min_bin..power.len() {
+        if best_bin == 0 || power[bin] > power[best_bin] {
+            best_bin = bin;
+        }
+    }
+
+    if best_bin == 0 {
+        return (0, 0.0);
+    }
+
+    let period = (padded_length as f64 / best_bin as f64).round() as usize;
+    (period, power[best_bin] / total)
+}
+
+/// Default STL trend span from Cleveland et al. (1990)
+fn default_trend_window(period: usize, seasonal_window: usize) -> usize {
+    let span = 1.5 * period as f64 / (1.0 - 1.5 / seasonal_window as f64);
+    span.ceil() as usize
+}
+
+/// Round up to the next odd number
+fn next_odd(value: usize) -> usize {
+    if value.is_multiple_of(2) { value + 1 } else { value }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATTERN: [f64; 4] = [3.0, -1.0, -2.0, 0.0];
+
+    /// Linear trend plus a period-4 pattern that already sums to zero
+    fn seasonal_series(length: usize) -> Vec<f64> {
+        (0..length).map(|i| 10.0 + 0.5 * i as f64 + PATTERN[i % 4]).collect()
+    }
+
+    #[test]
+    fn classical_additive_recovers_linear_trend_and_pattern() {
+        let values = seasonal_series(48);
+        let (decomposition, indices) = classical_decompose(&values, 4, DecompositionModel::Additive);
+
+        for (index, expected) in indices.iter().zip(PATTERN) {
+            assert!((index - expected).abs() < 1e-9);
+        }
+        assert!(decomposition.trend[..2].iter().all(|t| t.is_nan()));
+        assert!(decomposition.trend[46..].iter().all(|t| t.is_nan()));
+        for i in 2..46 {
+            assert!((decomposition.trend[i] - (10.0 + 0.5 * i as f64)).abs() < 1e-9);
+            assert!(decomposition.residual[i].abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn classical_multiplicative_indices_average_one() {
+        let factors = [1.2, 0.8, 1.1, 0.9];
+        let values: Vec<f64> = (0..48).map(|i| 100.0 * factors[i % 4]).collect();
+        let (_, indices) = classical_decompose(&values, 4, DecompositionModel::Multiplicative);
+
+        assert!((indices.iter().sum::<f64>() / 4.0 - 1.0).abs() < 1e-12);
+        for (index, expected) in indices.iter().zip(factors) {
+            assert!((index - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn stl_separates_trend_and_season() {
+        let values = seasonal_series(64);
+        let (decomposition, weights) = stl_decompose(&values, 4, 7, default_trend_window(4, 7), false);
+
+        assert!(weights.iter().all(|&w| w == 1.0));
+        for i in 8..56 {
+            assert!((decomposition.seasonal[i] - PATTERN[i % 4]).abs() < 0.1);
+            assert!(decomposition.residual[i].abs() < 0.1);
+        }
+    }
+
+    #[test]
+    fn robust_stl_down_weights_an_outlier() {
+        // Deterministic noise keeps the median residual away from zero
+        let mut values: Vec<f64> = seasonal_series(64)
+            .iter()
+            .enumerate()
+            .map(|(i, v)| v + 0.3 * ((i * 7919) as f64).sin())
+            .collect();
+        values[30] += 50.0;
+        let (decomposition, weights) = stl_decompose(&values, 4, 7, default_trend_window(4, 7), true);
+
+        assert_eq!(weights[30], 0.0);
+        assert!(weights.iter().enumerate().all(|(i, &w)| i == 30 || w > 0.5));
+        assert!(decomposition.residual[30] > 45.0);
+        for i in 24..36 {
+            assert!((decomposition.seasonal[i] - PATTERN[i % 4]).abs() < 0.2);
+        }
+    }
+
+    #[test]
+    fn hodrick_prescott_keeps_a_line_and_flattens_noise() {
+        let line: Vec<f64> = (0..20).map(|i| 2.0 * i as f64 - 3.0).collect();
+        let trend = hodrick_prescott(&line, 1600.0).unwrap();
+        for (t, v) in trend.iter().zip(&line) {
+            assert!((t - v).abs() < 1e-6);
+        }
+
+        // A very large lambda leaves (almost) the least-squares line
+        let zigzag: Vec<f64> = (0..20).map(|i| if i % 2 == 0 { 1.0 } else { -1.0 }).collect();
+        let smooth = hodrick_prescott(&zigzag, 1e6).unwrap();
+        assert!(smooth.windows(3).all(|w| (w[0] - 2.0 * w[1] + w[2]).abs() < 1e-3));
+        assert!(smooth.iter().all(|t| t.abs() < 0.2));
+        assert_eq!(hodrick_prescott(&zigzag, 0.0).unwrap(), zigzag);
+    }
+
+    #[test]
+    fn period_detection_finds_the_cycle() {
+        let values: Vec<f64> = (0..120)
+            .map(|i| (2.0 * std::f64::consts::PI * i as f64 / 12.0).sin() + 0.01 * i as f64)
+            .collect();
+
+        assert_eq!(detect_period_acf(&values, 30).0, 12);
+        let (period, strength) = detect_period_periodogram(&values, 30);
+        assert_eq!(period, 12);
+        assert!(strength > 0.3);
+    }
+
+    #[test]
+    fn robustness_weights_follow_the_bisquare() {
+        let weights = robustness_weights(&[0.0, 1.0, -1.0, 1.0, 100.0]);
+        assert_eq!(weights[0], 1.0);
+        assert!((weights[1] - (1.0 - 1.0 / 36.0f64).powi(2)).abs() < 1e-12);
+        assert_eq!(weights[4], 0.0);
+        assert_eq!(robustness_weights(&[0.0, 0.0, 5.0]), vec![1.0; 3]);
+    }
+}
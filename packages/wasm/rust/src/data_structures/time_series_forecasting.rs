@@ -0,0 +1,853 @@
+use wasm_bindgen::prelude::*;
+use js_sys::{Float64Array, Object, Reflect};
+
+use super::distributions::normal_quantile;
+use super::optimize::nelder_mead;
+
+/// Seasonal component of a Holt-Winters model
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SeasonalComponent {
+    None,
+    Additive,
+    Multiplicative,
+}
+
+/// Estimation methods for ARIMA models
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArimaMethod {
+    YuleWalker,
+    ConditionalLeastSquares,
+}
+
+/// Holt-Winters exponential smoothing model
+///
+/// Covers simple (level only), double (level and trend) and triple (level, trend and
+/// season) exponential smoothing. Smoothing parameters that are not supplied are fitted
+/// by minimising the one-step-ahead squared error.
+#[wasm_bindgen]
+pub struct HoltWinters {
+    trend: bool,
+    seasonal: SeasonalComponent,
+    period: usize,
+    alpha: f64,
+    beta: f64,
+    gamma: f64,
+    level: f64,
+    slope: f64,
+    season: Vec<f64>,
+    observations: usize,
+    fitted: Vec<f64>,
+    residuals: Vec<f64>,
+    sse: f64,
+}
+
+/// Smoothing state carried between observations
+#[derive(Clone)]
+struct HoltWintersState {
+    level: f64,
+    slope: f64,
+    season: Vec<f64>,
+    observations: usize,
+}
+
+#[wasm_bindgen]
+impl HoltWinters {
+    /// Fit a Holt-Winters model to a series
+    ///
+    /// Takes the series, whether to include a trend, the seasonal component and its period,
+    /// and optional fixed smoothing parameters (alpha for level, beta for trend, gamma for
+    /// season). Omitted parameters are estimated.
+    pub fn fit(
+        input: &JsValue,
+        trend: bool,
+        seasonal: SeasonalComponent,
+        period: usize,
+        alpha: Option<f64>,
+        beta: Option<f64>,
+        gamma: Option<f64>,
+    ) -> Result<HoltWinters, JsValue> {
+        // Convert input to typed array for better performance
+        let input_array = Float64Array::new(input);
+        let values = input_array.to_vec();
+        let length = values.len();
+
+        // Validate inputs
+        if values.iter().any(|v| !v.is_finite()) {
+            return Err(JsValue::from_str("Input must not contain NaN or infinite values"));
+        }
+
+        let period = if seasonal == SeasonalComponent::None { 1 } else { period };
+
+        if seasonal != SeasonalComponent::None && period < 2 {
+            return Err(JsValue::from_str("Seasonal period must be at least 2"));
+        }
+
+        let two_seasons = period.checked_mul(2).ok_or_else(|| JsValue::from_str("Seasonal period is too large"))?;
+        if seasonal != SeasonalComponent::None && length < two_seasons {
+            return Err(JsValue::from_str("At least two full seasons are required for a seasonal model"));
+        }
+
+        if length < 2 {
+            return Err(JsValue::from_str("At least 2 data points are required for exponential smoothing"));
+        }
+
+        if seasonal == SeasonalComponent::Multiplicative && values.iter().any(|&v| v <= 0.0) {
+            return Err(JsValue::from_str("Multiplicative seasonality requires strictly positive values"));
+        }
+
+        for parameter in [alpha, beta, gamma].iter().flatten() {
+            if *parameter < 0.0 || *parameter > 1.0 {
+                return Err(JsValue::from_str("Smoothing parameters must be between 0 and 1"));
+            }
+        }
+
+        Ok(HoltWinters::from_values(&values, trend, seasonal, period, alpha, beta, gamma))
+    }
+
+    /// Forecast the next `horizon` values
+    ///
+    /// Returns an object with the point forecasts and the lower and upper bounds of the
+    /// prediction interval at the given confidence level (e.g. 0.95).
+    pub fn forecast(&self, horizon: usize, confidence: f64) -> Result<JsValue, JsValue> {
+        if confidence <= 0.0 || confidence >= 1.0 {
+            return Err(JsValue::from_str("Confidence must be between 0 and 1 (exclusive)"));
+        }
+
+        let z = normal_quantile(0.5 + confidence / 2.0);
+        let (mean, lower, upper) = self.forecast_values(horizon, z);
+
+        // Create result object
+        let result = Object::new();
+        Reflect::set(&result, &JsValue::from_str("mean"), &Float64Array::from(&mean[..]))?;
+        Reflect::set(&result, &JsValue::from_str("lower"), &Float64Array::from(&lower[..]))?;
+        Reflect::set(&result, &JsValue::from_str("upper"), &Float64Array::from(&upper[..]))?;
+
+        Ok(result.into())
+    }
+
+    /// Update the model state with new observations, keeping the smoothing parameters
+    pub fn update(&mut self, observations: &JsValue) -> Result<(), JsValue> {
+        let values = Float64Array::new(observations).to_vec();
+
+        if values.iter().any(|v| !v.is_finite()) {
+            return Err(JsValue::from_str("Observations must not contain NaN or infinite values"));
+        }
+
+        if self.seasonal == SeasonalComponent::Multiplicative && values.iter().any(|&v| v <= 0.0) {
+            return Err(JsValue::from_str("Multiplicative seasonality requires strictly positive values"));
+        }
+
+        let mut state = HoltWintersState {
+            level: self.level,
+            slope: self.slope,
+            season: self.season.clone(),
+            observations: self.observations,
+        };
+        self.absorb(&mut state, &values);
+
+        Ok(())
+    }
+
+    /// Level smoothing parameter
+    #[wasm_bindgen(getter)]
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    /// Trend smoothing parameter (0 without a trend)
+    #[wasm_bindgen(getter)]
+    pub fn beta(&self) -> f64 {
+        self.beta
+    }
+
+    /// Seasonal smoothing parameter (0 without a season)
+    #[wasm_bindgen(getter)]
+    pub fn gamma(&self) -> f64 {
+        self.gamma
+    }
+
+    /// Current level
+    #[wasm_bindgen(getter)]
+    pub fn level(&self) -> f64 {
+        self.level
+    }
+
+    /// Current trend slope
+    #[wasm_bindgen(getter)]
+    pub fn slope(&self) -> f64 {
+        self.slope
+    }
+
+    /// Current seasonal factors, indexed by phase
+    #[wasm_bindgen(getter)]
+    pub fn seasonals(&self) -> Float64Array {
+        Float64Array::from(&self.season[..])
+    }
+
+    /// One-step-ahead in-sample predictions
+    #[wasm_bindgen(getter)]
+    pub fn fitted(&self) -> Float64Array {
+        Float64Array::from(&self.fitted[..])
+    }
+
+    /// In-sample one-step-ahead residuals
+    #[wasm_bindgen(getter)]
+    pub fn residuals(&self) -> Float64Array {
+        Float64Array::from(&self.residuals[..])
+    }
+
+    /// Sum of squared in-sample residuals
+    #[wasm_bindgen(getter)]
+    pub fn sse(&self) -> f64 {
+        self.sse
+    }
+}
+
+impl HoltWinters {
+    /// Fit the model to an already validated series
+    pub(crate) fn from_values(
+        values: &[f64],
+        trend: bool,
+        seasonal: SeasonalComponent,
+        period: usize,
+        alpha: Option<f64>,
+        beta: Option<f64>,
+        gamma: Option<f64>,
+    ) -> HoltWinters {
+        let mut model = HoltWinters {
+            trend,
+            seasonal,
+            period,
+            alpha: alpha.unwrap_or(0.3),
+            beta: if trend { beta.unwrap_or(0.1) } else { 0.0 },
+            gamma: if seasonal != SeasonalComponent::None { gamma.unwrap_or(0.1) } else { 0.0 },
+            level: 0.0,
+            slope: 0.0,
+            season: Vec::new(),
+            observations: 0,
+            fitted: Vec::new(),
+            residuals: Vec::new(),
+            sse: 0.0,
+        };
+
+        let initial = model.initial_state(values);
+
+        // Estimate the free parameters in logit space so they stay inside (0, 1)
+        let mut free = Vec::new();
+        if alpha.is_none() {
+            free.push(0);
+        }
+        if trend && beta.is_none() {
+            free.push(1);
+        }
+        if seasonal != SeasonalComponent::None && gamma.is_none() {
+            free.push(2);
+        }
+
+        if !free.is_empty() {
+            let base = [model.alpha, model.beta, model.gamma];
+            let start: Vec<f64> = free.iter().map(|&i| logit(base[i])).collect();
+
+            let objective = |point: &[f64]| -> f64 {
+                let mut parameters = base;
+                for (k, &i) in free.iter().enumerate() {
+                    parameters[i] = sigmoid(point[k]);
+                }
+                let mut state = initial.clone();
+                let mut sse = 0.0;
+                for &value in values {
+                    let error = value - model.step(&mut state, value, parameters);
+                    sse += error * error;
+                }
+                sse
+            };
+
+            let (best, _) = nelder_mead(objective, &start, 0.5, 500, 1e-10);
+            for (k, &i) in free.iter().enumerate() {
+                match i {
+                    0 => model.alpha = sigmoid(best[k]),
+                    1 => model.beta = sigmoid(best[k]),
+                    _ => model.gamma = sigmoid(best[k]),
+                }
+            }
+        }
+
+        let mut state = initial;
+        model.absorb(&mut state, values);
+
+        model
+    }
+
+    /// Point forecasts with lower and upper interval bounds for a normal quantile `z`
+    pub(crate) fn forecast_values(&self, horizon: usize, z: f64) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+        let sigma2 = self.residual_variance();
+        let mut mean = Vec::with_capacity(horizon);
+        let mut lower = Vec::with_capacity(horizon);
+        let mut upper = Vec::with_capacity(horizon);
+
+        let mut variance = sigma2;
+        for h in 1..=horizon {
+            let trend_part = self.level + if self.trend { h as f64 * self.slope } else { 0.0 };
+            let phase = (self.observations + h - 1) % self.period;
+            let point = match self.seasonal {
+                SeasonalComponent::None => trend_part,
+                SeasonalComponent::Additive => trend_part + self.season[phase],
+                SeasonalComponent::Multiplicative => trend_part * self.season[phase],
+            };
+
+            // Variance of the h-step error for the additive error form of the model
+            if h > 1 {
+                let j = (h - 1) as f64;
+                let seasonal_hit = self.seasonal != SeasonalComponent::None && (h - 1) % self.period == 0;
+                let c = self.alpha * (1.0 + j * self.beta) + if seasonal_hit { self.gamma } else { 0.0 };
+                variance += sigma2 * c * c;
+            }
+
+            let margin = z * variance.sqrt();
+            mean.push(point);
+            lower.push(point - margin);
+            upper.push(point + margin);
+        }
+
+        (mean, lower, upper)
+    }
+
+    /// Heuristic initial level, slope and seasonal factors
+    fn initial_state(&self, values: &[f64]) -> HoltWintersState {
+        let period = self.period;
+
+        if self.seasonal == SeasonalComponent::None {
+            let slope = if self.trend { values[1] - values[0] } else { 0.0 };
+            return HoltWintersState {
+                level: values[0] - slope,
+                slope,
+                season: vec![0.0],
+                observations: 0,
+            };
+        }
+
+        let first_mean = values[..period].iter().sum::<f64>() / period as f64;
+        let second_mean = values[period..2 * period].iter().sum::<f64>() / period as f64;
+        let slope = if self.trend { (second_mean - first_mean) / period as f64 } else { 0.0 };
+
+        let season = values[..period]
+            .iter()
+            .map(|&v| match self.seasonal {
+                SeasonalComponent::Multiplicative => v / first_mean,
+                _ => v - first_mean,
+            })
+            .collect();
+
+        HoltWintersState {
+            level: first_mean,
+            slope,
+            season,
+            observations: 0,
+        }
+    }
+
+    /// Run one smoothing step and return the one-step-ahead prediction made before it
+    fn step(&self, state: &mut HoltWintersState, value: f64, parameters: [f64; 3]) -> f64 {
+        let [alpha, beta, gamma] = parameters;
+        let phase = state.observations % self.period;
+        let base = state.level + state.slope;
+
+        let (prediction, deseasonalised) = match self.seasonal {
+            SeasonalComponent::None => (base, value),
+            SeasonalComponent::Additive => (base + state.season[phase], value - state.season[phase]),
+            SeasonalComponent::Multiplicative => (base * state.season[phase], value / state.season[phase]),
+        };
+
+        let level = alpha * deseasonalised + (1.0 - alpha) * base;
+        if self.trend {
+            state.slope = beta * (level - state.level) + (1.0 - beta) * state.slope;
+        }
+
+        match self.seasonal {
+            SeasonalComponent::None => {}
+            SeasonalComponent::Additive => {
+                state.season[phase] = gamma * (value - level) + (1.0 - gamma) * state.season[phase];
+            }
+            SeasonalComponent::Multiplicative => {
+                state.season[phase] = gamma * (value / level) + (1.0 - gamma) * state.season[phase];
+            }
+        }
+
+        state.level = level;
+        state.observations += 1;
+
+        prediction
+    }
+
+    /// Feed observations through the model, recording fitted values and residuals
+    fn absorb(&mut self, state: &mut HoltWintersState, values: &[f64]) {
+        let parameters = [self.alpha, self.beta, self.gamma];
+
+        for &value in values {
+            let prediction = self.step(state, value, parameters);
+            let residual = value - prediction;
+            self.fitted.push(prediction);
+            self.residuals.push(residual);
+            self.sse += residual * residual;
+        }
+
+        self.level = state.level;
+        self.slope = state.slope;
+        self.season = state.season.clone();
+        self.observations = state.observations;
+    }
+
+    /// Residual variance used for prediction intervals
+    fn residual_variance(&self) -> f64 {
+        let parameters = 1 + self.trend as usize + (self.seasonal != SeasonalComponent::None) as usize;
+        let dof = self.residuals.len().saturating_sub(parameters).max(1);
+        self.sse / dof as f64
+    }
+}
+
+/// ARIMA(p, d, q) model
+///
+/// Pure AR, MA and ARMA models are the special cases with `d = 0` and `q = 0` or `p = 0`.
+/// A constant mean is estimated when the series is not differenced.
+#[wasm_bindgen]
+pub struct Arima {
+    p: usize,
+    d: usize,
+    q: usize,
+    ar: Vec<f64>,
+    ma: Vec<f64>,
+    mean: f64,
+    sigma2: f64,
+    values: Vec<f64>,
+    residuals: Vec<f64>,
+}
+
+#[wasm_bindgen]
+impl Arima {
+    /// Fit an ARIMA(p, d, q) model to a series
+    ///
+    /// Yule-Walker estimation supports pure autoregressive models only; conditional least
+    /// squares handles moving-average terms by minimising the conditional sum of squares.
+    pub fn fit(input: &JsValue, p: usize, d: usize, q: usize, method: ArimaMethod) -> Result<Arima, JsValue> {
+        // Convert input to typed array for better performance
+        let input_array = Float64Array::new(input);
+        let values = input_array.to_vec();
+
+        // Validate inputs
+        if values.iter().any(|v| !v.is_finite()) {
+            return Err(JsValue::from_str("Input must not contain NaN or infinite values"));
+        }
+
+        if method == ArimaMethod::YuleWalker && q > 0 {
+            return Err(JsValue::from_str("Yule-Walker estimation only supports pure AR models (q = 0)"));
+        }
+
+        if values.len() <= d + p + q + 1 {
+            return Err(JsValue::from_str("Not enough data points for the requested model order"));
+        }
+
+        Ok(Arima::from_values(values, p, d, q, method))
+    }
+
+    /// Forecast the next `horizon` values
+    ///
+    /// Returns an object with the point forecasts and the lower and upper bounds of the
+    /// prediction interval at the given confidence level (e.g. 0.95).
+    pub fn forecast(&self, horizon: usize, confidence: f64) -> Result<JsValue, JsValue> {
+        if confidence <= 0.0 || confidence >= 1.0 {
+            return Err(JsValue::from_str("Confidence must be between 0 and 1 (exclusive)"));
+        }
+
+        let z = normal_quantile(0.5 + confidence / 2.0);
+        let (mean, lower, upper) = self.forecast_values(horizon, z);
+
+        // Create result object
+        let result = Object::new();
+        Reflect::set(&result, &JsValue::from_str("mean"), &Float64Array::from(&mean[..]))?;
+        Reflect::set(&result, &JsValue::from_str("lower"), &Float64Array::from(&lower[..]))?;
+        Reflect::set(&result, &JsValue::from_str("upper"), &Float64Array::from(&upper[..]))?;
+
+        Ok(result.into())
+    }
+
+    /// Append new observations, keeping the fitted coefficients
+    pub fn update(&mut self, observations: &JsValue) -> Result<(), JsValue> {
+        let values = Float64Array::new(observations).to_vec();
+
+        if values.iter().any(|v| !v.is_finite()) {
+            return Err(JsValue::from_str("Observations must not contain NaN or infinite values"));
+        }
+
+        self.values.extend_from_slice(&values);
+
+        let centred: Vec<f64> = difference(&self.values, self.d).iter().map(|v| v - self.mean).collect();
+        self.residuals = arma_residuals(&centred, &self.ar, &self.ma);
+
+        Ok(())
+    }
+
+    /// Autoregressive coefficients
+    #[wasm_bindgen(getter)]
+    pub fn ar(&self) -> Float64Array {
+        Float64Array::from(&self.ar[..])
+    }
+
+    /// Moving-average coefficients
+    #[wasm_bindgen(getter)]
+    pub fn ma(&self) -> Float64Array {
+        Float64Array::from(&self.ma[..])
+    }
+
+    /// Estimated mean of the (undifferenced) series, 0 when d > 0
+    #[wasm_bindgen(getter)]
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    /// Innovation variance
+    #[wasm_bindgen(getter)]
+    pub fn sigma2(&self) -> f64 {
+        self.sigma2
+    }
+
+    /// In-sample residuals of the differenced series
+    #[wasm_bindgen(getter)]
+    pub fn residuals(&self) -> Float64Array {
+        Float64Array::from(&self.residuals[..])
+    }
+
+    /// Akaike information criterion based on the conditional Gaussian likelihood
+    #[wasm_bindgen(getter)]
+    pub fn aic(&self) -> f64 {
+        let n = self.residuals.len().saturating_sub(self.p) as f64;
+        let k = (self.p + self.q + (self.d == 0) as usize + 1) as f64;
+        n * (2.0 * std::f64::consts::PI * self.sigma2).ln() + n + 2.0 * k
+    }
+}
+
+impl Arima {
+    /// Fit the model to an already validated series
+    pub(crate) fn from_values(values: Vec<f64>, p: usize, d: usize, q: usize, method: ArimaMethod) -> Arima {
+        let differenced = difference(&values, d);
+        let mean = if d == 0 {
+            differenced.iter().sum::<f64>() / differenced.len() as f64
+        } else {
+            0.0
+        };
+        let centred: Vec<f64> = differenced.iter().map(|v| v - mean).collect();
+
+        let (mut ar, yule_walker_variance) = if p > 0 {
+            yule_walker(&centred, p)
+        } else {
+            (Vec::new(), variance(&centred))
+        };
+        let mut ma = vec![0.0; q];
+
+        if method == ArimaMethod::ConditionalLeastSquares {
+            let start: Vec<f64> = ar.iter().chain(ma.iter()).copied().collect();
+            let objective = |point: &[f64]| -> f64 {
+                let residuals = arma_residuals(&centred, &point[..p], &point[p..]);
+                residuals.iter().map(|e| e * e).sum()
+            };
+
+            if !start.is_empty() {
+                let (best, _) = nelder_mead(objective, &start, 0.1, 2000, 1e-12);
+                ar = best[..p].to_vec();
+                ma = best[p..].to_vec();
+            }
+        }
+
+        let residuals = arma_residuals(&centred, &ar, &ma);
+        let sigma2 = if method == ArimaMethod::YuleWalker {
+            yule_walker_variance
+        } else {
+            let effective = residuals.len().saturating_sub(p).saturating_sub(p + q).max(1);
+            residuals[p..].iter().map(|e| e * e).sum::<f64>() / effective as f64
+        };
+
+        Arima { p, d, q, ar, ma, mean, sigma2, values, residuals }
+    }
+
+    /// Point forecasts with lower and upper interval bounds for a normal quantile `z`
+    pub(crate) fn forecast_values(&self, horizon: usize, z: f64) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+        // Forecast the differenced, centred series with future shocks set to zero
+        let differenced = difference(&self.values, self.d);
+        let mut history: Vec<f64> = differenced.iter().map(|v| v - self.mean).collect();
+        let mut shocks = self.residuals.clone();
+        let observed = history.len();
+
+        for _ in 0..horizon {
+            let t = history.len();
+            let mut next = 0.0;
+            for i in 0..self.p {
+                if t > i {
+                    next += self.ar[i] * history[t - 1 - i];
+                }
+            }
+            for j in 0..self.q {
+                if t > j {
+                    next += self.ma[j] * shocks[t - 1 - j];
+                }
+            }
+            history.push(next);
+            shocks.push(0.0);
+        }
+
+        let mut forecasts: Vec<f64> = history[observed..].iter().map(|v| v + self.mean).collect();
+
+        // Undo the differencing one level at a time
+        for level in (0..self.d).rev() {
+            let mut last = *difference(&self.values, level).last().unwrap_or(&0.0);
+            for value in forecasts.iter_mut() {
+                last += *value;
+                *value = last;
+            }
+        }
+
+        // Prediction variance from the psi weights of the integrated model
+        let psi = self.psi_weights(horizon);
+        let mut mean = Vec::with_capacity(horizon);
+        let mut lower = Vec::with_capacity(horizon);
+        let mut upper = Vec::with_capacity(horizon);
+
+        let mut cumulative = 0.0;
+        for h in 0..horizon {
+            cumulative += psi[h] * psi[h];
+            let margin = z * (self.sigma2 * cumulative).sqrt();
+            mean.push(forecasts[h]);
+            lower.push(forecasts[h] - margin);
+            upper.push(forecasts[h] + margin);
+        }
+
+        (mean, lower, upper)
+    }
+
+    /// MA(infinity) weights of the model including the differencing operator
+    fn psi_weights(&self, horizon: usize) -> Vec<f64> {
+        // Expand phi(B) (1 - B)^d into a single autoregressive polynomial
+        let mut phi = vec![1.0];
+        for &coefficient in &self.ar {
+            phi.push(-coefficient);
+        }
+        for _ in 0..self.d {
+            let mut expanded = vec![0.0; phi.len() + 1];
+            for (i, &c) in phi.iter().enumerate() {
+                expanded[i] += c;
+                expanded[i + 1] -= c;
+            }
+            phi = expanded;
+        }
+
+        let mut psi = vec![0.0; horizon.max(1)];
+        psi[0] = 1.0;
+        for j in 1..horizon {
+            let mut value = if j <= self.q { self.ma[j - 1] } else { 0.0 };
+            for i in 1..phi.len().min(j + 1) {
+                value -= phi[i] * psi[j - i];
+            }
+            psi[j] = value;
+        }
+
+        psi
+    }
+}
+
+/// Difference a series `order` times
+fn difference(values: &[f64], order: usize) -> Vec<f64> {
+    let mut result = values.to_vec();
+    for _ in 0..order {
+        result = result.windows(2).map(|w| w[1] - w[0]).collect();
+    }
+    result
+}
+
+/// Population variance of a slice
+fn variance(values: &[f64]) -> f64 {
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / values.len() as f64
+}
+
+/// Yule-Walker AR coefficients and innovation variance via the Levinson-Durbin recursion
+fn yule_walker(centred: &[f64], order: usize) -> (Vec<f64>, f64) {
+    let n = centred.len();
+    let autocovariance: Vec<f64> = (0..=order)
+        .map(|lag| (0..(n - lag)).map(|t| centred[t] * centred[t + lag]).sum::<f64>() / n as f64)
+        .collect();
+
+    let mut coefficients = vec![0.0; order];
+    let mut error = autocovariance[0];
+
+    if error == 0.0 {
+        return (coefficients, 0.0);
+    }
+
+    for k in 0..order {
+        let mut reflection = autocovariance[k + 1];
+        for j in 0..k {
+            reflection -= coefficients[j] * autocovariance[k - j];
+        }
+        reflection /= error;
+
+        let previous = coefficients.clone();
+        coefficients[k] = reflection;
+        for j in 0..k {
+            coefficients[j] = previous[j] - reflection * previous[k - 1 - j];
+        }
+
+        error *= 1.0 - reflection * reflection;
+    }
+
+    (coefficients, error)
+}
+
+/// Conditional residuals of an ARMA model, treating pre-sample shocks as zero
+fn arma_residuals(centred: &[f64], ar: &[f64], ma: &[f64]) -> Vec<f64> {
+    let p = ar.len();
+    let mut residuals = vec![0.0; centred.len()];
+
+    for t in p..centred.len() {
+        let mut prediction = 0.0;
+        for i in 0..p {
+            prediction += ar[i] * centred[t - 1 - i];
+        }
+        for j in 0..ma.len() {
+            if t > j {
+                prediction += ma[j] * residuals[t - 1 - j];
+            }
+        }
+        residuals[t] = centred[t] - prediction;
+    }
+
+    residuals
+}
+
+/// Map a probability to the real line
+fn logit(p: f64) -> f64 {
+    let p = p.clamp(1e-6, 1.0 - 1e-6);
+    (p / (1.0 - p)).ln()
+}
+
+/// Map the real line back to a probability
+fn sigmoid(x: f64) -> f64 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::distributions::normal_cdf;
+    use crate::data_structures::random::Rng;
+
+    fn ar1_series(phi: f64, length: usize, seed: u64) -> Vec<f64> {
+        let mut rng = Rng::new(seed);
+        let mut values = vec![0.0; length];
+        for t in 1..length {
+            values[t] = phi * values[t - 1] + rng.next_normal();
+        }
+        values
+    }
+
+    #[test]
+    fn holt_linear_trend_extrapolates_a_line() {
+        let values: Vec<f64> = (0..30).map(|i| 5.0 + 2.0 * i as f64).collect();
+        let model = HoltWinters::from_values(&values, true, SeasonalComponent::None, 1, None, None, None);
+        let (mean, lower, upper) = model.forecast_values(5, 1.96);
+
+        for (h, forecast) in mean.iter().enumerate() {
+            assert!((forecast - (5.0 + 2.0 * (30 + h) as f64)).abs() < 1e-3);
+        }
+        assert!(lower.iter().zip(&upper).all(|(l, u)| l <= u));
+        assert!(model.sse < 1e-6);
+    }
+
+    #[test]
+    fn holt_winters_repeats_an_additive_season() {
+        let pattern = [4.0, -2.0, 1.0, -3.0];
+        let values: Vec<f64> = (0..48).map(|i| 20.0 + 0.5 * i as f64 + pattern[i % 4]).collect();
+        let model = HoltWinters::from_values(&values, true, SeasonalComponent::Additive, 4, None, None, None);
+        let (mean, _, _) = model.forecast_values(8, 1.96);
+
+        for (h, forecast) in mean.iter().enumerate() {
+            let expected = 20.0 + 0.5 * (48 + h) as f64 + pattern[(48 + h) % 4];
+            assert!((forecast - expected).abs() < 0.5, "h = {}: {} vs {}", h, forecast, expected);
+        }
+    }
+
+    #[test]
+    fn holt_winters_intervals_widen_with_the_horizon() {
+        let values: Vec<f64> = (0..40).map(|i| 10.0 + ((i * 37) % 11) as f64 * 0.3).collect();
+        let model = HoltWinters::from_values(&values, false, SeasonalComponent::None, 1, Some(0.5), None, None);
+        let (mean, lower, upper) = model.forecast_values(6, 1.96);
+
+        let widths: Vec<f64> = lower.iter().zip(&upper).map(|(l, u)| u - l).collect();
+        assert!(widths.windows(2).all(|w| w[1] > w[0]));
+        assert!(mean.windows(2).all(|w| w[0] == w[1]));
+        assert_eq!(model.alpha, 0.5);
+    }
+
+    #[test]
+    fn yule_walker_recovers_an_ar1_coefficient() {
+        let values = ar1_series(0.7, 2000, 7);
+        let model = Arima::from_values(values, 1, 0, 0, ArimaMethod::YuleWalker);
+
+        assert!((model.ar[0] - 0.7).abs() < 0.05);
+        assert!((model.sigma2 - 1.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn conditional_least_squares_matches_yule_walker_on_ar1() {
+        let values = ar1_series(-0.5, 1000, 11);
+        let yule_walker = Arima::from_values(values.clone(), 1, 0, 0, ArimaMethod::YuleWalker);
+        let least_squares = Arima::from_values(values, 1, 0, 0, ArimaMethod::ConditionalLeastSquares);
+
+        assert!((yule_walker.ar[0] - least_squares.ar[0]).abs() < 0.02);
+        assert!((least_squares.ar[0] + 0.5).abs() < 0.08);
+    }
+
+    #[test]
+    fn random_walk_forecast_is_flat_with_growing_intervals() {
+        let values: Vec<f64> = ar1_series(1.0, 200, 3);
+        let last = values[199];
+        let model = Arima::from_values(values, 0, 1, 0, ArimaMethod::YuleWalker);
+        let (mean, lower, upper) = model.forecast_values(4, 1.0);
+
+        assert!(mean.iter().all(|&m| (m - last).abs() < 1e-12));
+        // Width grows with sqrt(h) for a random walk
+        let widths: Vec<f64> = lower.iter().zip(&upper).map(|(l, u)| u - l).collect();
+        for (h, width) in widths.iter().enumerate() {
+            assert!((width / widths[0] - ((h + 1) as f64).sqrt()).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn differencing_and_residuals() {
+        assert_eq!(difference(&[1.0, 4.0, 9.0, 16.0], 1), vec![3.0, 5.0, 7.0]);
+        assert_eq!(difference(&[1.0, 4.0, 9.0, 16.0], 2), vec![2.0, 2.0]);
+
+        // An exact AR(1) path leaves zero residuals after the first value
+        let path: Vec<f64> = (0..6).map(|t| 0.5f64.powi(t)).collect();
+        let residuals = arma_residuals(&path, &[0.5], &[]);
+        assert!(residuals.iter().all(|r| r.abs() < 1e-12));
+    }
+
+    #[test]
+    fn nelder_mead_minimises_the_rosenbrock_function() {
+        let rosenbrock = |p: &[f64]| (1.0 - p[0]).powi(2) + 100.0 * (p[1] - p[0] * p[0]).powi(2);
+        let (best, value) = nelder_mead(rosenbrock, &[-1.2, 1.0], 0.5, 5000, 1e-14);
+
+        assert!((best[0] - 1.0).abs() < 1e-3);
+        assert!((best[1] - 1.0).abs() < 1e-3);
+        assert!(value < 1e-6);
+    }
+
+    #[test]
+    fn normal_quantile_inverts_the_cdf() {
+        assert_eq!(normal_quantile(0.5), 0.0);
+        assert!((normal_quantile(0.975) - 1.959_963_985).abs() < 1e-8);
+        for &p in &[0.001, 0.02, 0.3, 0.8, 0.99] {
+            assert!((normal_cdf(normal_quantile(p)) - p).abs() < 1e-6);
+        }
+    }
+}
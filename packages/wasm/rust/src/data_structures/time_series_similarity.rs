@@ -0,0 +1,688 @@
+use wasm_bindgen::prelude::*;
+use js_sys::{Float64Array, Object, Reflect, Uint32Array};
+use std::collections::VecDeque;
+
+use super::signal::{fft_complex, ifft_complex, Complex};
+
+/// Global path constraints for dynamic time warping
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WarpingConstraint {
+    /// Unconstrained warping
+    None,
+    /// Band of fixed radius around the (scaled) diagonal
+    SakoeChiba,
+    /// Parallelogram that bounds the slope of the warping path
+    Itakura,
+}
+
+/// Dynamic time warping distance with warping path
+///
+/// Takes two numeric arrays, a global constraint, the Sakoe-Chiba band radius in samples
+/// (default 10% of the longer series) and the maximum Itakura slope (default 2). Returns
+/// the DTW distance (square root of the summed squared differences along the optimal
+/// path) and the path as two index arrays `path_a` and `path_b`.
+#[wasm_bindgen]
+pub fn time_series_dtw_f64(
+    a: &JsValue,
+    b: &JsValue,
+    constraint: WarpingConstraint,
+    window: Option<usize>,
+    max_slope: Option<f64>,
+) -> Result<JsValue, JsValue> {
+    // Convert inputs to typed arrays for better performance
+    let a_values = Float64Array::new(a).to_vec();
+    let b_values = Float64Array::new(b).to_vec();
+
+    // Validate inputs
+    if a_values.is_empty() || b_values.is_empty() {
+        return Err(JsValue::from_str("Inputs must not be empty"));
+    }
+
+    let max_slope = max_slope.unwrap_or(2.0);
+    if constraint == WarpingConstraint::Itakura && max_slope <= 1.0 {
+        return Err(JsValue::from_str("Maximum slope must be greater than 1"));
+    }
+
+    let window = window.unwrap_or_else(|| a_values.len().max(b_values.len()).div_ceil(10));
+    let bounds = warping_bounds(a_values.len(), b_values.len(), constraint, window, max_slope);
+    let (distance, path) = dtw_with_path(&a_values, &b_values, &bounds);
+
+    let path_a: Vec<u32> = path.iter().map(|&(i, _)| i as u32).collect();
+    let path_b: Vec<u32> = path.iter().map(|&(_, j)| j as u32).collect();
+
+    // Create result object
+    let result = Object::new();
+    Reflect::set(&result, &JsValue::from_str("distance"), &JsValue::from_f64(distance))?;
+    Reflect::set(&result, &JsValue::from_str("path_a"), &Uint32Array::from(&path_a[..]))?;
+    Reflect::set(&result, &JsValue::from_str("path_b"), &Uint32Array::from(&path_b[..]))?;
+
+    Ok(result.into())
+}
+
+/// LB_Keogh lower bound of the DTW distance
+///
+/// Takes a query, a candidate of the same length and the Sakoe-Chiba band radius, and
+/// returns a lower bound of the band-constrained DTW distance between them. The bound is
+/// cheap (linear time) and is used to discard candidates before computing full DTW.
+#[wasm_bindgen]
+pub fn time_series_lb_keogh_f64(query: &JsValue, candidate: &JsValue, window: usize) -> Result<f64, JsValue> {
+    // Convert inputs to typed arrays for better performance
+    let query_values = Float64Array::new(query).to_vec();
+    let candidate_values = Float64Array::new(candidate).to_vec();
+
+    // Validate inputs
+    if query_values.len() != candidate_values.len() {
+        return Err(JsValue::from_str("Query and candidate must have the same length"));
+    }
+
+    if query_values.is_empty() {
+        return Err(JsValue::from_str("Inputs must not be empty"));
+    }
+
+    let (upper, lower) = envelope(&query_values, window);
+    Ok(lb_keogh(&candidate_values, &upper, &lower, f64::INFINITY).sqrt())
+}
+
+/// Best-matching subsequence under band-constrained DTW
+///
+/// Takes a long series, a query, the Sakoe-Chiba band radius and whether to z-normalize
+/// the query and every candidate subsequence. Candidates are pruned with LB_Keogh and
+/// DTW is abandoned early once it exceeds the best distance so far. Returns the start
+/// `index` of the best match and its `distance`.
+#[wasm_bindgen]
+pub fn time_series_dtw_search_f64(
+    series: &JsValue,
+    query: &JsValue,
+    window: usize,
+    normalize: bool,
+) -> Result<JsValue, JsValue> {
+    // Convert inputs to typed arrays for better performance
+    let series_values = Float64Array::new(series).to_vec();
+    let query_values = Float64Array::new(query).to_vec();
+
+    // Validate inputs
+    if query_values.is_empty() {
+        return Err(JsValue::from_str("Query must not be empty"));
+    }
+
+    if query_values.len() > series_values.len() {
+        return Err(JsValue::from_str("Query must not be longer than the series"));
+    }
+
+    let (index, distance) = dtw_search(&series_values, &query_values, window, normalize);
+
+    // Create result object
+    let result = Object::new();
+    Reflect::set(&result, &JsValue::from_str("index"), &JsValue::from_f64(index as f64))?;
+    Reflect::set(&result, &JsValue::from_str("distance"), &JsValue::from_f64(distance))?;
+
+    Ok(result.into())
+}
+
+/// Z-normalized Euclidean distance
+///
+/// Takes two numeric arrays of the same length, rescales each to zero mean and unit
+/// variance, and returns the Euclidean distance between them. Constant series normalize
+/// to all zeros.
+#[wasm_bindgen]
+pub fn time_series_znorm_euclidean_f64(a: &JsValue, b: &JsValue) -> Result<f64, JsValue> {
+    // Convert inputs to typed arrays for better performance
+    let a_values = Float64Array::new(a).to_vec();
+    let b_values = Float64Array::new(b).to_vec();
+
+    // Validate inputs
+    if a_values.len() != b_values.len() {
+        return Err(JsValue::from_str("Inputs must have the same length"));
+    }
+
+    if a_values.is_empty() {
+        return Err(JsValue::from_str("Inputs must not be empty"));
+    }
+
+    let a_normalized = znormalize(&a_values);
+    let b_normalized = znormalize(&b_values);
+    let sum: f64 = a_normalized.iter().zip(&b_normalized).map(|(x, y)| (x - y) * (x - y)).sum();
+
+    Ok(sum.sqrt())
+}
+
+/// Matrix profile of a series (self-join) computed with STOMP
+///
+/// Takes a numeric array, the subsequence length and an optional exclusion zone (default
+/// a quarter of the subsequence length) that suppresses trivial matches. Returns the
+/// z-normalized distance from every subsequence to its nearest neighbour (`profile`), the
+/// neighbour positions (`profile_index`), the best motif pair (`motif_index`,
+/// `motif_neighbor`) and the top discord (`discord_index`).
+#[wasm_bindgen]
+pub fn time_series_matrix_profile_f64(
+    input: &JsValue,
+    window: usize,
+    exclusion_zone: Option<usize>,
+) -> Result<JsValue, JsValue> {
+    // Convert input to typed array for better performance
+    let input_array = Float64Array::new(input);
+    let values = input_array.to_vec();
+
+    // Validate inputs
+    if window < 2 {
+        return Err(JsValue::from_str("Window must be at least 2"));
+    }
+
+    let exclusion_zone = exclusion_zone.unwrap_or_else(|| window.div_ceil(4));
+    if values.len() < window || values.len() - window <= exclusion_zone {
+        return Err(JsValue::from_str("Input is too short for the window and exclusion zone"));
+    }
+
+    if values.iter().any(|v| !v.is_finite()) {
+        return Err(JsValue::from_str("Input must not contain NaN or infinite values"));
+    }
+
+    let (profile, profile_index) = matrix_profile(&values, window, exclusion_zone);
+
+    // Motif is the closest pair, discord the subsequence furthest from its neighbour.
+    // Subsequences with no neighbour outside the exclusion zone keep an infinite profile
+    // and cannot be the discord; the first one always has a neighbour.
+    let mut motif = 0;
+    let mut discord = 0;
+    for i in 1..profile.len() {
+        if profile[i] < profile[motif] {
+            motif = i;
+        }
+        if profile[i].is_finite() && profile[i] > profile[discord] {
+            discord = i;
+        }
+    }
+
+    let index_array: Vec<u32> = profile_index.iter().map(|&j| j as u32).collect();
+
+    // Create result object
+    let result = Object::new();
+    Reflect::set(&result, &JsValue::from_str("profile"), &Float64Array::from(&profile[..]))?;
+    Reflect::set(&result, &JsValue::from_str("profile_index"), &Uint32Array::from(&index_array[..]))?;
+    Reflect::set(&result, &JsValue::from_str("motif_index"), &JsValue::from_f64(motif as f64))?;
+    Reflect::set(&result, &JsValue::from_str("motif_neighbor"), &JsValue::from_f64(profile_index[motif] as f64))?;
+    Reflect::set(&result, &JsValue::from_str("discord_index"), &JsValue::from_f64(discord as f64))?;
+
+    Ok(result.into())
+}
+
+/// Allowed column range (inclusive) of every row of the DTW cost matrix
+pub(crate) fn warping_bounds(
+    rows: usize,
+    columns: usize,
+    constraint: WarpingConstraint,
+    window: usize,
+    max_slope: f64,
+) -> Vec<(usize, usize)> {
+    let last_column = columns - 1;
+    let mut bounds: Vec<(usize, usize)> = (0..rows)
+        .map(|i| {
+            if rows == 1 || columns == 1 {
+                return (0, last_column);
+            }
+
+            // Position of the row along the diagonal, scaled to the column axis
+            let x = i as f64 / (rows - 1) as f64;
+            let diagonal = x * last_column as f64;
+
+            match constraint {
+                WarpingConstraint::None => (0, last_column),
+                WarpingConstraint::SakoeChiba => {
+                    let lo = (diagonal.floor() as usize).saturating_sub(window);
+                    let hi = (diagonal.ceil() as usize + window).min(last_column);
+                    (lo, hi)
+                }
+                WarpingConstraint::Itakura => {
+                    let y_lo = (x / max_slope).max(1.0 - max_slope * (1.0 - x));
+                    let y_hi = (max_slope * x).min(1.0 - (1.0 - x) / max_slope);
+                    let lo = (y_lo * last_column as f64 - 1e-9).ceil().max(0.0) as usize;
+                    let hi = ((y_hi * last_column as f64 + 1e-9).floor() as usize).min(last_column);
+                    if lo > hi {
+                        let nearest = diagonal.round() as usize;
+                        (nearest, nearest)
+                    } else {
+                        (lo, hi)
+                    }
+                }
+            }
+        })
+        .collect();
+
+    // Widen rows where needed so that consecutive rows stay connected
+    for i in (1..rows).rev() {
+        let lo = bounds[i].0;
+        if bounds[i - 1].1 + 1 < lo {
+            bounds[i - 1].1 = lo - 1;
+        }
+    }
+
+    bounds
+}
+
+/// Squared DTW cost restricted to `bounds`, abandoning once every cell of a row exceeds
+/// `cutoff`. Returns infinity when abandoned.
+pub(crate) fn dtw_distance(a: &[f64], b: &[f64], bounds: &[(usize, usize)], cutoff: f64) -> f64 {
+    let columns = b.len();
+    let mut previous = vec![f64::INFINITY; columns];
+    let mut current = vec![f64::INFINITY; columns];
+
+    for (i, &(lo, hi)) in bounds.iter().enumerate() {
+        // `current` still holds the row before last; clear its band
+        if i >= 2 {
+            let (old_lo, old_hi) = bounds[i - 2];
+            for cell in &mut current[old_lo..=old_hi] {
+                *cell = f64::INFINITY;
+            }
+        }
+
+        let mut row_min = f64::INFINITY;
+        for j in lo..=hi {
+            let cost = (a[i] - b[j]) * (a[i] - b[j]);
+            let best = if i == 0 && j == 0 {
+                0.0
+            } else {
+                let mut best = f64::INFINITY;
+                if i > 0 {
+                    best = best.min(previous[j]);
+                    if j > 0 {
+                        best = best.min(previous[j - 1]);
+                    }
+                }
+                if j > 0 {
+                    best = best.min(current[j - 1]);
+                }
+                best
+            };
+            current[j] = cost + best;
+            row_min = row_min.min(current[j]);
+        }
+
+        if row_min > cutoff {
+            return f64::INFINITY;
+        }
+
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[columns - 1]
+}
+
+/// DTW distance and optimal warping path restricted to `bounds`
+pub(crate) fn dtw_with_path(a: &[f64], b: &[f64], bounds: &[(usize, usize)]) -> (f64, Vec<(usize, usize)>) {
+    // Banded storage: each row only keeps the cells inside its bounds
+    let mut cost: Vec<Vec<f64>> = Vec::with_capacity(a.len());
+    let cell = |cost: &Vec<Vec<f64>>, i: usize, j: usize| -> f64 {
+        let (lo, hi) = bounds[i];
+        if j < lo || j > hi { f64::INFINITY } else { cost[i][j - lo] }
+    };
+
+    for (i, &(lo, hi)) in bounds.iter().enumerate() {
+        let mut row = Vec::with_capacity(hi - lo + 1);
+        for j in lo..=hi {
+            let local = (a[i] - b[j]) * (a[i] - b[j]);
+            let best = if i == 0 && j == 0 {
+                0.0
+            } else {
+                let mut best = f64::INFINITY;
+                if i > 0 {
+                    best = best.min(cell(&cost, i - 1, j));
+                    if j > 0 {
+                        best = best.min(cell(&cost, i - 1, j - 1));
+                    }
+                }
+                if j > lo {
+                    best = best.min(row[j - 1 - lo]);
+                }
+                best
+            };
+            row.push(local + best);
+        }
+        cost.push(row);
+    }
+
+    // Walk back from the end, preferring diagonal moves on ties
+    let (mut i, mut j) = (a.len() - 1, b.len() - 1);
+    let mut path = vec![(i, j)];
+    while i > 0 || j > 0 {
+        let diagonal = if i > 0 && j > 0 { cell(&cost, i - 1, j - 1) } else { f64::INFINITY };
+        let up = if i > 0 { cell(&cost, i - 1, j) } else { f64::INFINITY };
+        let left = if j > 0 { cell(&cost, i, j - 1) } else { f64::INFINITY };
+
+        if diagonal <= up && diagonal <= left {
+            i -= 1;
+            j -= 1;
+        } else if up <= left {
+            i -= 1;
+        } else {
+            j -= 1;
+        }
+        path.push((i, j));
+    }
+    path.reverse();
+
+    (cell(&cost, a.len() - 1, b.len() - 1).sqrt(), path)
+}
+
+/// Upper and lower envelopes of a series over a sliding window of radius `window`
+///
+/// Uses monotonic deques (Lemire's streaming min/max), so it runs in linear time.
+pub(crate) fn envelope(values: &[f64], window: usize) -> (Vec<f64>, Vec<f64>) {
+    let length = values.len();
+    let mut upper = vec![0.0; length];
+    let mut lower = vec![0.0; length];
+    let mut max_queue: VecDeque<usize> = VecDeque::new();
+    let mut min_queue: VecDeque<usize> = VecDeque::new();
+
+    // Index `k` enters the window while output `k - window` is produced
+    for k in 0..length + window {
+        if k < length {
+            while max_queue.back().is_some_and(|&back| values[back] <= values[k]) {
+                max_queue.pop_back();
+            }
+            max_queue.push_back(k);
+            while min_queue.back().is_some_and(|&back| values[back] >= values[k]) {
+                min_queue.pop_back();
+            }
+            min_queue.push_back(k);
+        }
+
+        if k >= window {
+            let i = k - window;
+            while max_queue.front().is_some_and(|&front| front + window < i) {
+                max_queue.pop_front();
+            }
+            while min_queue.front().is_some_and(|&front| front + window < i) {
+                min_queue.pop_front();
+            }
+            upper[i] = values[*max_queue.front().unwrap()];
+            lower[i] = values[*min_queue.front().unwrap()];
+        }
+    }
+
+    (upper, lower)
+}
+
+/// Squared LB_Keogh bound of a candidate against a query envelope, abandoning past `cutoff`
+pub(crate) fn lb_keogh(candidate: &[f64], upper: &[f64], lower: &[f64], cutoff: f64) -> f64 {
+    let mut sum = 0.0;
+    for i in 0..candidate.len() {
+        let value = candidate[i];
+        if value > upper[i] {
+            sum += (value - upper[i]) * (value - upper[i]);
+        } else if value < lower[i] {
+            sum += (value - lower[i]) * (value - lower[i]);
+        }
+        if sum > cutoff {
+            break;
+        }
+    }
+    sum
+}
+
+/// Start index and DTW distance of the subsequence of `series` closest to `query`
+pub(crate) fn dtw_search(series: &[f64], query: &[f64], window: usize, normalize: bool) -> (usize, f64) {
+    let length = query.len();
+    let query = if normalize { znormalize(query) } else { query.to_vec() };
+    let (upper, lower) = envelope(&query, window);
+    let bounds = warping_bounds(length, length, WarpingConstraint::SakoeChiba, window, 2.0);
+
+    let mut best_index = 0;
+    let mut best = f64::INFINITY;
+
+    for start in 0..=series.len() - length {
+        let subsequence = &series[start..start + length];
+        let candidate = if normalize { znormalize(subsequence) } else { subsequence.to_vec() };
+
+        if lb_keogh(&candidate, &upper, &lower, best) >= best {
+            continue;
+        }
+
+        let distance = dtw_distance(&query, &candidate, &bounds, best);
+        if distance < best {
+            best = distance;
+            best_index = start;
+        }
+    }
+
+    (best_index, best.sqrt())
+}
+
+/// Rescale a series to zero mean and unit (population) variance
+pub(crate) fn znormalize(values: &[f64]) -> Vec<f64> {
+    let length = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / length;
+    let std_dev = (values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / length).sqrt();
+
+    if std_dev <= f64::EPSILON * mean.abs().max(1.0) {
+        return vec![0.0; values.len()];
+    }
+
+    values.iter().map(|v| (v - mean) / std_dev).collect()
+}
+
+/// Dot products of `query` with every window of `series`, computed via FFT
+pub(crate) fn sliding_dot_product(query: &[f64], series: &[f64]) -> Vec<f64> {
+    let window = query.len();
+    let length = series.len();
+    let size = (length + window).next_power_of_two();
+
+    // Correlation is convolution with the reversed query
+    let mut series_buffer = vec![Complex::new(0.0, 0.0); size];
+    for (slot, &value) in series_buffer.iter_mut().zip(series) {
+        slot.real = value;
+    }
+    let mut query_buffer = vec![Complex::new(0.0, 0.0); size];
+    for (slot, &value) in query_buffer.iter_mut().zip(query.iter().rev()) {
+        slot.real = value;
+    }
+
+    let series_spectrum = fft_complex(&series_buffer);
+    let query_spectrum = fft_complex(&query_buffer);
+    let product: Vec<Complex> = series_spectrum.iter().zip(&query_spectrum).map(|(x, y)| x.mul(y)).collect();
+    let convolution = ifft_complex(&product);
+
+    (0..=length - window).map(|j| convolution[window - 1 + j].real).collect()
+}
+
+/// STOMP self-join matrix profile; returns the profile and nearest-neighbour indices
+pub(crate) fn matrix_profile(values: &[f64], window: usize, exclusion_zone: usize) -> (Vec<f64>, Vec<usize>) {
+    let count = values.len() - window + 1;
+    let m = window as f64;
+
+    // Centre the series to limit cancellation in the running sums (distances are shift invariant)
+    let offset = values.iter().sum::<f64>() / values.len() as f64;
+    let series: Vec<f64> = values.iter().map(|v| v - offset).collect();
+
+    // Rolling window means and standard deviations
+    let mut means = Vec::with_capacity(count);
+    let mut std_devs = Vec::with_capacity(count);
+    let mut sum: f64 = series[..window].iter().sum();
+    let mut sum_squares: f64 = series[..window].iter().map(|v| v * v).sum();
+    for i in 0..count {
+        if i > 0 {
+            let (leaving, entering) = (series[i - 1], series[i + window - 1]);
+            sum += entering - leaving;
+            sum_squares += entering * entering - leaving * leaving;
+        }
+        let mean = sum / m;
+        means.push(mean);
+        std_devs.push((sum_squares / m - mean * mean).max(0.0).sqrt());
+    }
+
+    let distance = |dot: f64, i: usize, j: usize| -> f64 {
+        let flat_i = std_devs[i] <= 1e-12;
+        let flat_j = std_devs[j] <= 1e-12;
+        if flat_i && flat_j {
+            return 0.0;
+        }
+        if flat_i || flat_j {
+            return m.sqrt();
+        }
+        let correlation = ((dot - m * means[i] * means[j]) / (m * std_devs[i] * std_devs[j])).clamp(-1.0, 1.0);
+        (2.0 * m * (1.0 - correlation)).sqrt()
+    };
+
+    let mut dots = sliding_dot_product(&series[..window], &series);
+    let mut profile = vec![f64::INFINITY; count];
+    let mut profile_index = vec![0; count];
+
+    for i in 0..count {
+        // Row i only needs dot products with windows at or after i (the join is symmetric)
+        if i > 0 {
+            for j in (i..count).rev() {
+                dots[j] = dots[j - 1] - series[i - 1] * series[j - 1]
+                    + series[i + window - 1] * series[j + window - 1];
+            }
+        }
+
+        for j in (i + exclusion_zone + 1)..count {
+            let d = distance(dots[j], i, j);
+            if d < profile[i] {
+                profile[i] = d;
+                profile_index[i] = j;
+            }
+            if d < profile[j] {
+                profile[j] = d;
+                profile_index[j] = i;
+            }
+        }
+    }
+
+    (profile, profile_index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::random::Rng;
+
+    fn random_walk(length: usize, seed: u64) -> Vec<f64> {
+        let mut rng = Rng::new(seed);
+        let mut value = 0.0;
+        (0..length)
+            .map(|_| {
+                value += rng.next_normal();
+                value
+            })
+            .collect()
+    }
+
+    fn euclidean(a: &[f64], b: &[f64]) -> f64 {
+        a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum::<f64>().sqrt()
+    }
+
+    #[test]
+    fn dtw_absorbs_a_repeated_sample() {
+        let a = [0.0, 1.0, 2.0, 1.0];
+        let b = [0.0, 0.0, 1.0, 2.0, 1.0];
+        let bounds = warping_bounds(a.len(), b.len(), WarpingConstraint::None, 0, 2.0);
+        let (distance, path) = dtw_with_path(&a, &b, &bounds);
+
+        assert_eq!(distance, 0.0);
+        assert_eq!(path.first(), Some(&(0, 0)));
+        assert_eq!(path.last(), Some(&(3, 4)));
+        assert!(path.windows(2).all(|w| w[1].0 - w[0].0 <= 1 && w[1].1 - w[0].1 <= 1));
+    }
+
+    #[test]
+    fn banded_and_path_distances_agree() {
+        for seed in 0..5 {
+            let a = random_walk(30, seed);
+            let b = random_walk(24, seed + 100);
+            for constraint in [WarpingConstraint::None, WarpingConstraint::SakoeChiba, WarpingConstraint::Itakura] {
+                let bounds = warping_bounds(a.len(), b.len(), constraint, 3, 2.0);
+                let (with_path, _) = dtw_with_path(&a, &b, &bounds);
+                let squared = dtw_distance(&a, &b, &bounds, f64::INFINITY);
+                assert!((with_path - squared.sqrt()).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn zero_width_band_is_the_euclidean_distance() {
+        let a = random_walk(20, 1);
+        let b = random_walk(20, 2);
+        let bounds = warping_bounds(20, 20, WarpingConstraint::SakoeChiba, 0, 2.0);
+
+        assert!((dtw_distance(&a, &b, &bounds, f64::INFINITY).sqrt() - euclidean(&a, &b)).abs() < 1e-9);
+        assert!(dtw_distance(&a, &b, &bounds, 1e-3).is_infinite());
+    }
+
+    #[test]
+    fn envelope_matches_a_brute_force_window() {
+        let values = random_walk(40, 3);
+        let (upper, lower) = envelope(&values, 4);
+
+        for i in 0..values.len() {
+            let window = &values[i.saturating_sub(4)..(i + 5).min(values.len())];
+            assert_eq!(upper[i], window.iter().cloned().fold(f64::NEG_INFINITY, f64::max));
+            assert_eq!(lower[i], window.iter().cloned().fold(f64::INFINITY, f64::min));
+        }
+    }
+
+    #[test]
+    fn lb_keogh_never_exceeds_dtw() {
+        let query = random_walk(32, 4);
+        let (upper, lower) = envelope(&query, 3);
+        let bounds = warping_bounds(32, 32, WarpingConstraint::SakoeChiba, 3, 2.0);
+
+        for seed in 10..20 {
+            let candidate = random_walk(32, seed);
+            let bound = lb_keogh(&candidate, &upper, &lower, f64::INFINITY);
+            assert!(bound <= dtw_distance(&query, &candidate, &bounds, f64::INFINITY) + 1e-9);
+        }
+    }
+
+    #[test]
+    fn dtw_search_matches_an_exhaustive_scan() {
+        let series = random_walk(300, 5);
+        let query: Vec<f64> = series[140..172].iter().map(|v| 3.0 * v + 10.0).collect();
+        let (index, distance) = dtw_search(&series, &query, 3, true);
+
+        assert_eq!(index, 140);
+        assert!(distance < 1e-6);
+
+        let bounds = warping_bounds(32, 32, WarpingConstraint::SakoeChiba, 3, 2.0);
+        let other = random_walk(32, 6);
+        let (index, distance) = dtw_search(&series, &other, 3, false);
+        let exhaustive = (0..=series.len() - 32)
+            .map(|start| dtw_distance(&other, &series[start..start + 32], &bounds, f64::INFINITY).sqrt())
+            .fold(f64::INFINITY, f64::min);
+        assert!((distance - exhaustive).abs() < 1e-9);
+        assert!((dtw_distance(&other, &series[index..index + 32], &bounds, f64::INFINITY).sqrt() - distance).abs() < 1e-9);
+    }
+
+    #[test]
+    fn sliding_dot_product_matches_direct_sums() {
+        let series = random_walk(50, 7);
+        let query = random_walk(8, 8);
+        let dots = sliding_dot_product(&query, &series);
+
+        assert_eq!(dots.len(), 43);
+        for (j, dot) in dots.iter().enumerate() {
+            let direct: f64 = query.iter().zip(&series[j..]).map(|(q, s)| q * s).sum();
+            assert!((dot - direct).abs() < 1e-8);
+        }
+    }
+
+    #[test]
+    fn matrix_profile_matches_brute_force_and_finds_a_motif() {
+        let mut values = random_walk(120, 9);
+        let motif: Vec<f64> = (0..10).map(|i| 5.0 * (i as f64 * 0.7).sin()).collect();
+        values[20..30].copy_from_slice(&motif);
+        values[80..90].iter_mut().zip(&motif).for_each(|(v, m)| *v = 2.0 * m - 1.0);
+
+        let (profile, index) = matrix_profile(&values, 10, 2);
+        for i in 0..profile.len() {
+            let zi = znormalize(&values[i..i + 10]);
+            let best = (0..profile.len())
+                .filter(|&j| i.abs_diff(j) > 2)
+                .map(|j| euclidean(&zi, &znormalize(&values[j..j + 10])))
+                .fold(f64::INFINITY, f64::min);
+            assert!((profile[i] - best).abs() < 1e-6, "{} {} {}", i, profile[i], best);
+        }
+        assert!(profile[20] < 1e-6);
+        assert_eq!(index[20], 80);
+    }
+}
@@ -0,0 +1,1187 @@
+use wasm_bindgen::prelude::*;
+use js_sys::Float64Array;
+use rayon::prelude::*;
+
+use super::machine_learning::read_rows;
+use super::random::Rng;
+use super::serialization::{ByteReader, ByteWriter};
+
+/// Split quality measures for CART trees
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SplitCriterion {
+    /// Gini impurity (classification)
+    Gini,
+    /// Information gain (classification)
+    Entropy,
+    /// Mean squared error (regression)
+    Mse,
+}
+
+/// Loss functions for gradient boosting
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BoostingLoss {
+    /// Least squares regression
+    SquaredError,
+    /// Binary classification on the log-odds scale
+    Logistic,
+}
+
+const TREE_MAGIC: &[u8; 4] = b"RDDT";
+const FOREST_MAGIC: &[u8; 4] = b"RDRF";
+const BOOSTING_MAGIC: &[u8; 4] = b"RDGB";
+const VERSION: u32 = 1;
+
+/// CART decision tree for classification or regression
+///
+/// Gini and entropy criteria grow a classifier over the distinct label values; the MSE
+/// criterion grows a regression tree.
+#[wasm_bindgen]
+pub struct DecisionTree {
+    dims: usize,
+    criterion: SplitCriterion,
+    classes: Vec<f64>,
+    tree: Tree,
+    importances: Vec<f64>,
+}
+
+#[wasm_bindgen]
+impl DecisionTree {
+    /// Grow a tree on row-major features and targets
+    ///
+    /// Takes the features, the targets (class labels or values), the number of features per
+    /// row, the split criterion and optional limits: maximum depth (default unlimited),
+    /// minimum samples to split a node (default 2) and minimum samples per leaf (default 1).
+    pub fn fit(
+        x: &JsValue,
+        y: &JsValue,
+        dims: usize,
+        criterion: SplitCriterion,
+        max_depth: Option<usize>,
+        min_samples_split: Option<usize>,
+        min_samples_leaf: Option<usize>,
+    ) -> Result<DecisionTree, JsValue> {
+        let data = TrainingData::read(x, y, dims, criterion)?;
+        let params = TreeParams {
+            criterion,
+            max_depth: max_depth.unwrap_or(usize::MAX),
+            min_samples_split: min_samples_split.unwrap_or(2).max(2),
+            min_samples_leaf: min_samples_leaf.unwrap_or(1).max(1),
+            max_features: dims,
+        };
+
+        let indices: Vec<usize> = (0..data.targets.len()).collect();
+        let mut importances = vec![0.0; dims];
+        let tree = Tree::grow(&data.features, dims, &data.targets, data.classes.len(), indices, &params, &mut Rng::new(0), &mut importances);
+        normalise(&mut importances);
+
+        Ok(DecisionTree { dims, criterion, classes: data.classes, tree, importances })
+    }
+
+    /// Predicted class labels or values for row-major features
+    pub fn predict(&self, x: &JsValue) -> Result<JsValue, JsValue> {
+        let (features, _) = read_rows(x, self.dims)?;
+        let predictions: Vec<f64> = features
+            .chunks_exact(self.dims)
+            .map(|row| output_value(self.tree.predict_row(row), &self.classes))
+            .collect();
+        Ok(Float64Array::from(&predictions[..]).into())
+    }
+
+    /// Row-major class probabilities (leaf class frequencies) for a classification tree
+    pub fn predict_proba(&self, x: &JsValue) -> Result<JsValue, JsValue> {
+        if self.classes.is_empty() {
+            return Err(JsValue::from_str("Probabilities are only available for classification trees"));
+        }
+        let (features, _) = read_rows(x, self.dims)?;
+        let probabilities: Vec<f64> = features.chunks_exact(self.dims).flat_map(|row| self.tree.predict_row(row).to_vec()).collect();
+        Ok(Float64Array::from(&probabilities[..]).into())
+    }
+
+    /// Serialize the tree to bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = ByteWriter::new(TREE_MAGIC, VERSION);
+        write_header(&mut writer, self.dims, self.criterion, &self.classes, &self.importances);
+        self.tree.write(&mut writer);
+        writer.into_bytes()
+    }
+
+    /// Restore a tree produced by `to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Result<DecisionTree, JsValue> {
+        DecisionTree::decode(bytes).map_err(|message| JsValue::from_str(&message))
+    }
+
+    /// Normalised total impurity decrease contributed by each feature
+    #[wasm_bindgen(getter)]
+    pub fn feature_importances(&self) -> Float64Array {
+        Float64Array::from(&self.importances[..])
+    }
+
+    /// Class labels in ascending order (empty for regression)
+    #[wasm_bindgen(getter)]
+    pub fn classes(&self) -> Float64Array {
+        Float64Array::from(&self.classes[..])
+    }
+
+    /// Number of nodes in the tree
+    #[wasm_bindgen(getter)]
+    pub fn node_count(&self) -> usize {
+        self.tree.nodes.len()
+    }
+
+    /// Depth of the deepest leaf
+    #[wasm_bindgen(getter)]
+    pub fn depth(&self) -> usize {
+        self.tree.depth()
+    }
+}
+
+impl DecisionTree {
+    fn decode(bytes: &[u8]) -> Result<DecisionTree, String> {
+        let mut reader = open(bytes, TREE_MAGIC)?;
+        let (dims, criterion, classes, importances) = read_header(&mut reader)?;
+        let tree = Tree::read(&mut reader, dims, leaf_outputs(criterion, &classes))?;
+        reader.finish()?;
+        Ok(DecisionTree { dims, criterion, classes, tree, importances })
+    }
+}
+
+/// Random forest of CART trees
+///
+/// Trees are grown in parallel on bootstrap samples with a random subset of features
+/// considered at every split; predictions average the trees' class frequencies or values.
+#[wasm_bindgen]
+pub struct RandomForest {
+    dims: usize,
+    criterion: SplitCriterion,
+    classes: Vec<f64>,
+    trees: Vec<Tree>,
+    importances: Vec<f64>,
+}
+
+#[wasm_bindgen]
+impl RandomForest {
+    /// Grow a forest on row-major features and targets
+    ///
+    /// Takes the features, targets, number of features per row, split criterion and number
+    /// of trees, plus optional maximum depth, minimum samples per leaf (default 1), features
+    /// considered per split (default sqrt(dims) for classification and dims / 3 for
+    /// regression), whether to bootstrap (default true) and a seed.
+    #[allow(clippy::too_many_arguments)]
+    pub fn fit(
+        x: &JsValue,
+        y: &JsValue,
+        dims: usize,
+        criterion: SplitCriterion,
+        num_trees: usize,
+        max_depth: Option<usize>,
+        min_samples_leaf: Option<usize>,
+        max_features: Option<usize>,
+        bootstrap: Option<bool>,
+        seed: Option<u32>,
+    ) -> Result<RandomForest, JsValue> {
+        let data = TrainingData::read(x, y, dims, criterion)?;
+
+        if num_trees == 0 {
+            return Err(JsValue::from_str("Number of trees must be greater than 0"));
+        }
+
+        let default_features = if criterion == SplitCriterion::Mse { dims / 3 } else { (dims as f64).sqrt() as usize };
+        let max_features = max_features.unwrap_or(default_features).clamp(1, dims);
+        let params = TreeParams {
+            criterion,
+            max_depth: max_depth.unwrap_or(usize::MAX),
+            min_samples_split: 2,
+            min_samples_leaf: min_samples_leaf.unwrap_or(1).max(1),
+            max_features,
+        };
+        let bootstrap = bootstrap.unwrap_or(true);
+
+        // Per-tree seeds are drawn up front so results do not depend on scheduling
+        let mut rng = Rng::from_seed(seed);
+        let seeds: Vec<u64> = (0..num_trees).map(|_| rng.next_u64()).collect();
+        let num_rows = data.targets.len();
+
+        let grown: Vec<(Tree, Vec<f64>)> = seeds
+            .par_iter()
+            .map(|&tree_seed| {
+                let mut tree_rng = Rng::new(tree_seed);
+                let indices: Vec<usize> = if bootstrap {
+                    (0..num_rows).map(|_| tree_rng.below(num_rows)).collect()
+                } else {
+                    (0..num_rows).collect()
+                };
+                let mut importances = vec![0.0; dims];
+                let tree = Tree::grow(&data.features, dims, &data.targets, data.classes.len(), indices, &params, &mut tree_rng, &mut importances);
+                normalise(&mut importances);
+                (tree, importances)
+            })
+            .collect();
+
+        let mut importances = vec![0.0; dims];
+        for (_, tree_importances) in &grown {
+            for (total, value) in importances.iter_mut().zip(tree_importances) {
+                *total += value / num_trees as f64;
+            }
+        }
+        let trees = grown.into_iter().map(|(tree, _)| tree).collect();
+
+        Ok(RandomForest { dims, criterion, classes: data.classes, trees, importances })
+    }
+
+    /// Predicted class labels or values for row-major features
+    pub fn predict(&self, x: &JsValue) -> Result<JsValue, JsValue> {
+        let (features, _) = read_rows(x, self.dims)?;
+        let predictions: Vec<f64> = features
+            .chunks_exact(self.dims)
+            .map(|row| output_value(&self.average(row), &self.classes))
+            .collect();
+        Ok(Float64Array::from(&predictions[..]).into())
+    }
+
+    /// Row-major class probabilities averaged over the trees
+    pub fn predict_proba(&self, x: &JsValue) -> Result<JsValue, JsValue> {
+        if self.classes.is_empty() {
+            return Err(JsValue::from_str("Probabilities are only available for classification forests"));
+        }
+        let (features, _) = read_rows(x, self.dims)?;
+        let probabilities: Vec<f64> = features.chunks_exact(self.dims).flat_map(|row| self.average(row)).collect();
+        Ok(Float64Array::from(&probabilities[..]).into())
+    }
+
+    /// Serialize the forest to bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = ByteWriter::new(FOREST_MAGIC, VERSION);
+        write_header(&mut writer, self.dims, self.criterion, &self.classes, &self.importances);
+        writer.write_u32(self.trees.len() as u32);
+        for tree in &self.trees {
+            tree.write(&mut writer);
+        }
+        writer.into_bytes()
+    }
+
+    /// Restore a forest produced by `to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Result<RandomForest, JsValue> {
+        RandomForest::decode(bytes).map_err(|message| JsValue::from_str(&message))
+    }
+
+    /// Mean of the per-tree normalised feature importances
+    #[wasm_bindgen(getter)]
+    pub fn feature_importances(&self) -> Float64Array {
+        Float64Array::from(&self.importances[..])
+    }
+
+    /// Class labels in ascending order (empty for regression)
+    #[wasm_bindgen(getter)]
+    pub fn classes(&self) -> Float64Array {
+        Float64Array::from(&self.classes[..])
+    }
+
+    /// Number of trees in the forest
+    #[wasm_bindgen(getter)]
+    pub fn num_trees(&self) -> usize {
+        self.trees.len()
+    }
+}
+
+impl RandomForest {
+    fn decode(bytes: &[u8]) -> Result<RandomForest, String> {
+        let mut reader = open(bytes, FOREST_MAGIC)?;
+        let (dims, criterion, classes, importances) = read_header(&mut reader)?;
+        let count = reader.read_u32()? as usize;
+        let outputs = leaf_outputs(criterion, &classes);
+        let trees = (0..count).map(|_| Tree::read(&mut reader, dims, outputs)).collect::<Result<Vec<Tree>, String>>()?;
+        reader.finish()?;
+        if trees.is_empty() {
+            return Err("Forest payload has no trees".to_string());
+        }
+        Ok(RandomForest { dims, criterion, classes, trees, importances })
+    }
+
+    /// Average leaf output of every tree for one row
+    fn average(&self, row: &[f64]) -> Vec<f64> {
+        let mut total = vec![0.0; self.trees[0].outputs];
+        for tree in &self.trees {
+            for (sum, value) in total.iter_mut().zip(tree.predict_row(row)) {
+                *sum += value / self.trees.len() as f64;
+            }
+        }
+        total
+    }
+}
+
+/// Gradient-boosted regression trees
+///
+/// Each round fits a shallow regression tree to the negative gradient of the loss and adds
+/// it, scaled by the learning rate, to the running prediction. The logistic loss handles
+/// binary classification and uses Newton steps for the leaf values.
+#[wasm_bindgen]
+pub struct GradientBoosting {
+    dims: usize,
+    loss: BoostingLoss,
+    classes: Vec<f64>,
+    base_score: f64,
+    trees: Vec<Tree>,
+    importances: Vec<f64>,
+}
+
+#[wasm_bindgen]
+impl GradientBoosting {
+    /// Fit a boosted ensemble on row-major features and targets
+    ///
+    /// Takes the features, targets (values, or two class labels for the logistic loss), the
+    /// number of features per row, the loss, the number of rounds and the learning rate,
+    /// plus optional tree depth (default 3), minimum samples per leaf (default 1), row
+    /// subsampling fraction per round (default 1) and a seed.
+    #[allow(clippy::too_many_arguments)]
+    pub fn fit(
+        x: &JsValue,
+        y: &JsValue,
+        dims: usize,
+        loss: BoostingLoss,
+        num_rounds: usize,
+        learning_rate: f64,
+        max_depth: Option<usize>,
+        min_samples_leaf: Option<usize>,
+        subsample: Option<f64>,
+        seed: Option<u32>,
+    ) -> Result<GradientBoosting, JsValue> {
+        let criterion = if loss == BoostingLoss::Logistic { SplitCriterion::Gini } else { SplitCriterion::Mse };
+        let data = TrainingData::read(x, y, dims, criterion)?;
+
+        if loss == BoostingLoss::Logistic && data.classes.len() != 2 {
+            return Err(JsValue::from_str("Logistic loss requires exactly two classes"));
+        }
+
+        if learning_rate <= 0.0 {
+            return Err(JsValue::from_str("Learning rate must be greater than 0"));
+        }
+
+        let subsample = subsample.unwrap_or(1.0);
+        if subsample <= 0.0 || subsample > 1.0 {
+            return Err(JsValue::from_str("Subsample must be in (0, 1]"));
+        }
+
+        let params = TreeParams {
+            criterion: SplitCriterion::Mse,
+            max_depth: max_depth.unwrap_or(3),
+            min_samples_split: 2,
+            min_samples_leaf: min_samples_leaf.unwrap_or(1).max(1),
+            max_features: dims,
+        };
+
+        Ok(GradientBoosting::from_values(
+            &data.features,
+            &data.targets,
+            dims,
+            loss,
+            data.classes,
+            num_rounds,
+            learning_rate,
+            subsample,
+            &params,
+            &mut Rng::from_seed(seed),
+        ))
+    }
+
+    /// Predicted values, or class labels for the logistic loss
+    pub fn predict(&self, x: &JsValue) -> Result<JsValue, JsValue> {
+        let (features, _) = read_rows(x, self.dims)?;
+        let predictions: Vec<f64> = features
+            .chunks_exact(self.dims)
+            .map(|row| {
+                let score = self.score(row);
+                match self.loss {
+                    BoostingLoss::SquaredError => score,
+                    BoostingLoss::Logistic => self.classes[usize::from(score > 0.0)],
+                }
+            })
+            .collect();
+        Ok(Float64Array::from(&predictions[..]).into())
+    }
+
+    /// Row-major class probabilities for the logistic loss
+    pub fn predict_proba(&self, x: &JsValue) -> Result<JsValue, JsValue> {
+        if self.loss != BoostingLoss::Logistic {
+            return Err(JsValue::from_str("Probabilities are only available for the logistic loss"));
+        }
+        let (features, _) = read_rows(x, self.dims)?;
+        let probabilities: Vec<f64> = features
+            .chunks_exact(self.dims)
+            .flat_map(|row| {
+                let p = sigmoid(self.score(row));
+                [1.0 - p, p]
+            })
+            .collect();
+        Ok(Float64Array::from(&probabilities[..]).into())
+    }
+
+    /// Serialize the ensemble to bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = ByteWriter::new(BOOSTING_MAGIC, VERSION);
+        writer.write_u32(self.dims as u32);
+        writer.write_u32(self.loss as u32);
+        writer.write_f64_slice(&self.classes);
+        writer.write_f64_slice(&self.importances);
+        writer.write_f64(self.base_score);
+        writer.write_u32(self.trees.len() as u32);
+        for tree in &self.trees {
+            tree.write(&mut writer);
+        }
+        writer.into_bytes()
+    }
+
+    /// Restore an ensemble produced by `to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Result<GradientBoosting, JsValue> {
+        GradientBoosting::decode(bytes).map_err(|message| JsValue::from_str(&message))
+    }
+
+    /// Normalised impurity decrease per feature summed over all rounds
+    #[wasm_bindgen(getter)]
+    pub fn feature_importances(&self) -> Float64Array {
+        Float64Array::from(&self.importances[..])
+    }
+
+    /// Number of boosting rounds fitted
+    #[wasm_bindgen(getter)]
+    pub fn num_trees(&self) -> usize {
+        self.trees.len()
+    }
+
+    /// Initial prediction (mean, or log-odds for the logistic loss)
+    #[wasm_bindgen(getter)]
+    pub fn base_score(&self) -> f64 {
+        self.base_score
+    }
+}
+
+impl GradientBoosting {
+    /// Fit the ensemble to validated data (class indices for the logistic loss)
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn from_values(
+        features: &[f64],
+        targets: &[f64],
+        dims: usize,
+        loss: BoostingLoss,
+        classes: Vec<f64>,
+        num_rounds: usize,
+        learning_rate: f64,
+        subsample: f64,
+        params: &TreeParams,
+        rng: &mut Rng,
+    ) -> GradientBoosting {
+        let num_rows = targets.len();
+        let mean = targets.iter().sum::<f64>() / num_rows as f64;
+        let base_score = match loss {
+            BoostingLoss::SquaredError => mean,
+            BoostingLoss::Logistic => {
+                let p = mean.clamp(1e-6, 1.0 - 1e-6);
+                (p / (1.0 - p)).ln()
+            }
+        };
+
+        let mut scores = vec![base_score; num_rows];
+        let mut trees = Vec::with_capacity(num_rounds);
+        let mut importances = vec![0.0; dims];
+        let sample_size = ((num_rows as f64 * subsample).round() as usize).max(1);
+
+        for _ in 0..num_rounds {
+            // Negative gradient of the loss at the current scores
+            let residuals: Vec<f64> = match loss {
+                BoostingLoss::SquaredError => targets.iter().zip(&scores).map(|(y, f)| y - f).collect(),
+                BoostingLoss::Logistic => targets.iter().zip(&scores).map(|(y, f)| y - sigmoid(*f)).collect(),
+            };
+
+            let indices: Vec<usize> = if sample_size < num_rows {
+                let mut all: Vec<usize> = (0..num_rows).collect();
+                rng.shuffle(&mut all);
+                all.truncate(sample_size);
+                all
+            } else {
+                (0..num_rows).collect()
+            };
+
+            let mut tree = Tree::grow(features, dims, &residuals, 0, indices.clone(), params, rng, &mut importances);
+
+            // Newton step for the logistic leaves: sum(r) / sum(p (1 - p))
+            if loss == BoostingLoss::Logistic {
+                let mut numerators = vec![0.0; tree.nodes.len()];
+                let mut denominators = vec![0.0; tree.nodes.len()];
+                for &i in &indices {
+                    let leaf = tree.leaf(&features[i * dims..(i + 1) * dims]);
+                    let p = sigmoid(scores[i]);
+                    numerators[leaf] += residuals[i];
+                    denominators[leaf] += p * (1.0 - p);
+                }
+                for (node, (n, d)) in numerators.iter().zip(&denominators).enumerate() {
+                    if *d > 0.0 {
+                        tree.values[node] = n / d.max(1e-12);
+                    }
+                }
+            }
+
+            tree.values.iter_mut().for_each(|v| *v *= learning_rate);
+            for (i, score) in scores.iter_mut().enumerate() {
+                *score += tree.predict_row(&features[i * dims..(i + 1) * dims])[0];
+            }
+            trees.push(tree);
+        }
+
+        normalise(&mut importances);
+        GradientBoosting { dims, loss, classes, base_score, trees, importances }
+    }
+
+    /// Raw additive score for one row
+    fn score(&self, row: &[f64]) -> f64 {
+        self.base_score + self.trees.iter().map(|tree| tree.predict_row(row)[0]).sum::<f64>()
+    }
+
+    fn decode(bytes: &[u8]) -> Result<GradientBoosting, String> {
+        let mut reader = open(bytes, BOOSTING_MAGIC)?;
+        let dims = reader.read_u32()? as usize;
+        let loss = match reader.read_u32()? {
+            0 => BoostingLoss::SquaredError,
+            1 => BoostingLoss::Logistic,
+            other => return Err(format!("Unknown boosting loss {}", other)),
+        };
+        let classes = reader.read_f64_vec()?;
+        let importances = reader.read_f64_vec()?;
+        let base_score = reader.read_f64()?;
+        let count = reader.read_u32()? as usize;
+        // Boosting always fits regression trees, whatever the loss
+        let trees = (0..count).map(|_| Tree::read(&mut reader, dims, 1)).collect::<Result<Vec<Tree>, String>>()?;
+        reader.finish()?;
+        if importances.len() != dims || (loss == BoostingLoss::Logistic && classes.len() != 2) {
+            return Err("Inconsistent gradient boosting payload".to_string());
+        }
+        Ok(GradientBoosting { dims, loss, classes, base_score, trees, importances })
+    }
+}
+
+/// Validated training data; classification targets are replaced by class indices
+struct TrainingData {
+    features: Vec<f64>,
+    targets: Vec<f64>,
+    classes: Vec<f64>,
+}
+
+impl TrainingData {
+    fn read(x: &JsValue, y: &JsValue, dims: usize, criterion: SplitCriterion) -> Result<TrainingData, JsValue> {
+        let (features, num_rows) = read_rows(x, dims)?;
+        let targets = Float64Array::new(y).to_vec();
+
+        if targets.len() != num_rows {
+            return Err(JsValue::from_str("Targets must have one value per row"));
+        }
+
+        if num_rows == 0 {
+            return Err(JsValue::from_str("Data must not be empty"));
+        }
+
+        if targets.iter().any(|v| !v.is_finite()) {
+            return Err(JsValue::from_str("Targets must not contain NaN or infinite values"));
+        }
+
+        if criterion == SplitCriterion::Mse {
+            return Ok(TrainingData { features, targets, classes: Vec::new() });
+        }
+
+        let mut classes = targets.clone();
+        classes.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        classes.dedup();
+        let targets = targets.iter().map(|t| classes.iter().position(|c| c == t).unwrap() as f64).collect();
+
+        Ok(TrainingData { features, targets, classes })
+    }
+}
+
+/// Growth limits for a single tree
+pub(crate) struct TreeParams {
+    pub(crate) criterion: SplitCriterion,
+    pub(crate) max_depth: usize,
+    pub(crate) min_samples_split: usize,
+    pub(crate) min_samples_leaf: usize,
+    pub(crate) max_features: usize,
+}
+
+/// Internal node split; leaves have no split
+#[derive(Clone, Debug)]
+struct Node {
+    split: Option<(usize, f64)>,
+    left: usize,
+    right: usize,
+}
+
+/// Array-backed binary tree with `outputs` values per node
+///
+/// Classification trees store class frequencies, regression trees the mean target.
+#[derive(Clone, Debug)]
+pub(crate) struct Tree {
+    nodes: Vec<Node>,
+    values: Vec<f64>,
+    outputs: usize,
+}
+
+impl Tree {
+    /// Grow a tree on the rows in `indices` (duplicates act as bootstrap weights)
+    ///
+    /// `num_classes` is 0 for regression; classification targets are class indices.
+    /// Weighted impurity decreases are added to `importances`.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn grow(
+        features: &[f64],
+        dims: usize,
+        targets: &[f64],
+        num_classes: usize,
+        indices: Vec<usize>,
+        params: &TreeParams,
+        rng: &mut Rng,
+        importances: &mut [f64],
+    ) -> Tree {
+        let outputs = num_classes.max(1);
+        let mut tree = Tree { nodes: Vec::new(), values: Vec::new(), outputs };
+        let total = indices.len() as f64;
+
+        // Depth-first growth with an explicit stack of (node, rows, depth)
+        tree.push_leaf(targets, num_classes, &indices);
+        let mut stack = vec![(0, indices, 0)];
+        let mut candidates: Vec<usize> = (0..dims).collect();
+
+        while let Some((node, rows, depth)) = stack.pop() {
+            if depth >= params.max_depth || rows.len() < params.min_samples_split {
+                continue;
+            }
+
+            // Consider a random subset of features when subsampling
+            if params.max_features < dims {
+                for i in 0..params.max_features {
+                    let j = i + rng.below(dims - i);
+                    candidates.swap(i, j);
+                }
+            }
+
+            let best = best_split(features, dims, targets, num_classes, &rows, &candidates[..params.max_features], params);
+            let (feature, threshold, decrease) = match best {
+                Some(split) => split,
+                None => continue,
+            };
+
+            importances[feature] += decrease / total;
+            let (left_rows, right_rows): (Vec<usize>, Vec<usize>) =
+                rows.iter().partition(|&&i| features[i * dims + feature] <= threshold);
+
+            let left = tree.push_leaf(targets, num_classes, &left_rows);
+            let right = tree.push_leaf(targets, num_classes, &right_rows);
+            tree.nodes[node] = Node { split: Some((feature, threshold)), left, right };
+
+            stack.push((right, right_rows, depth + 1));
+            stack.push((left, left_rows, depth + 1));
+        }
+
+        tree
+    }
+
+    /// Append a leaf summarising `rows` and return its index
+    fn push_leaf(&mut self, targets: &[f64], num_classes: usize, rows: &[usize]) -> usize {
+        let count = rows.len().max(1) as f64;
+        if num_classes == 0 {
+            self.values.push(rows.iter().map(|&i| targets[i]).sum::<f64>() / count);
+        } else {
+            let start = self.values.len();
+            self.values.resize(start + num_classes, 0.0);
+            for &i in rows {
+                self.values[start + targets[i] as usize] += 1.0 / count;
+            }
+        }
+        self.nodes.push(Node { split: None, left: 0, right: 0 });
+        self.nodes.len() - 1
+    }
+
+    /// Index of the leaf reached by a row
+    pub(crate) fn leaf(&self, row: &[f64]) -> usize {
+        let mut node = 0;
+        while let Some((feature, threshold)) = self.nodes[node].split {
+            node = if row[feature] <= threshold { self.nodes[node].left } else { self.nodes[node].right };
+        }
+        node
+    }
+
+    /// Output values of the leaf reached by a row
+    pub(crate) fn predict_row(&self, row: &[f64]) -> &[f64] {
+        let leaf = self.leaf(row);
+        &self.values[leaf * self.outputs..(leaf + 1) * self.outputs]
+    }
+
+    fn depth(&self) -> usize {
+        let mut deepest = 0;
+        let mut stack = vec![(0, 0)];
+        while let Some((node, depth)) = stack.pop() {
+            deepest = deepest.max(depth);
+            if self.nodes[node].split.is_some() {
+                stack.push((self.nodes[node].left, depth + 1));
+                stack.push((self.nodes[node].right, depth + 1));
+            }
+        }
+        deepest
+    }
+
+    /// Portable layout: outputs, node count, then per node (feature + 1 or 0 for a leaf,
+    /// threshold, left, right), then the node values
+    fn write(&self, writer: &mut ByteWriter) {
+        writer.write_u32(self.outputs as u32);
+        writer.write_u32(self.nodes.len() as u32);
+        for node in &self.nodes {
+            let (feature, threshold) = node.split.map_or((0, 0.0), |(f, t)| (f as u32 + 1, t));
+            writer.write_u32(feature);
+            writer.write_f64(threshold);
+            writer.write_u32(node.left as u32);
+            writer.write_u32(node.right as u32);
+        }
+        writer.write_f64_slice(&self.values);
+    }
+
+    /// Read a tree whose leaves must hold exactly `expected_outputs` values
+    fn read(reader: &mut ByteReader, dims: usize, expected_outputs: usize) -> Result<Tree, String> {
+        let outputs = reader.read_u32()? as usize;
+        let count = reader.read_u32()? as usize;
+        let mut nodes = Vec::with_capacity(count.min(1 << 20));
+        for _ in 0..count {
+            let feature = reader.read_u32()? as usize;
+            let threshold = reader.read_f64()?;
+            let left = reader.read_u32()? as usize;
+            let right = reader.read_u32()? as usize;
+            let split = if feature == 0 { None } else { Some((feature - 1, threshold)) };
+            nodes.push(Node { split, left, right });
+        }
+        let values = reader.read_f64_vec()?;
+
+        // Children must point forward so that prediction always terminates
+        let valid = outputs == expected_outputs
+            && count > 0
+            && count.checked_mul(outputs) == Some(values.len())
+            && nodes.iter().enumerate().all(|(i, node)| match node.split {
+                Some((feature, _)) => feature < dims && node.left > i && node.right > i && node.left < count && node.right < count,
+                None => true,
+            });
+        if !valid {
+            return Err("Inconsistent tree payload".to_string());
+        }
+
+        Ok(Tree { nodes, values, outputs })
+    }
+}
+
+/// Best (feature, threshold, weighted impurity decrease) over the candidate features
+fn best_split(
+    features: &[f64],
+    dims: usize,
+    targets: &[f64],
+    num_classes: usize,
+    rows: &[usize],
+    candidates: &[usize],
+    params: &TreeParams,
+) -> Option<(usize, f64, f64)> {
+    let n = rows.len();
+    let mut best: Option<(usize, f64, f64)> = None;
+
+    // Parent statistics
+    let mut parent_counts = vec![0.0; num_classes];
+    let (mut parent_sum, mut parent_squares) = (0.0, 0.0);
+    for &i in rows {
+        if num_classes > 0 {
+            parent_counts[targets[i] as usize] += 1.0;
+        } else {
+            parent_sum += targets[i];
+            parent_squares += targets[i] * targets[i];
+        }
+    }
+    let parent_impurity = if num_classes > 0 {
+        n as f64 * impurity(&parent_counts, n as f64, params.criterion)
+    } else {
+        parent_squares - parent_sum * parent_sum / n as f64
+    };
+    if parent_impurity <= 1e-12 {
+        return None;
+    }
+
+    let mut order = rows.to_vec();
+    let mut left_counts = vec![0.0; num_classes];
+    for &feature in candidates {
+        order.sort_by(|&a, &b| features[a * dims + feature].partial_cmp(&features[b * dims + feature]).unwrap());
+
+        left_counts.iter_mut().for_each(|c| *c = 0.0);
+        let (mut left_sum, mut left_squares) = (0.0, 0.0);
+
+        for position in 0..n - 1 {
+            let i = order[position];
+            if num_classes > 0 {
+                left_counts[targets[i] as usize] += 1.0;
+            } else {
+                left_sum += targets[i];
+                left_squares += targets[i] * targets[i];
+            }
+
+            let left_size = position + 1;
+            let right_size = n - left_size;
+            let value = features[i * dims + feature];
+            let next = features[order[position + 1] * dims + feature];
+            if value == next || left_size < params.min_samples_leaf || right_size < params.min_samples_leaf {
+                continue;
+            }
+
+            let children = if num_classes > 0 {
+                let right_counts: Vec<f64> = parent_counts.iter().zip(&left_counts).map(|(p, l)| p - l).collect();
+                left_size as f64 * impurity(&left_counts, left_size as f64, params.criterion)
+                    + right_size as f64 * impurity(&right_counts, right_size as f64, params.criterion)
+            } else {
+                let right_sum = parent_sum - left_sum;
+                let right_squares = parent_squares - left_squares;
+                (left_squares - left_sum * left_sum / left_size as f64)
+                    + (right_squares - right_sum * right_sum / right_size as f64)
+            };
+
+            let decrease = parent_impurity - children;
+            if decrease > 1e-12 && best.is_none_or(|(_, _, b)| decrease > b) {
+                best = Some((feature, 0.5 * (value + next), decrease));
+            }
+        }
+    }
+
+    best
+}
+
+/// Gini or entropy impurity of class counts
+fn impurity(counts: &[f64], total: f64, criterion: SplitCriterion) -> f64 {
+    match criterion {
+        SplitCriterion::Entropy => counts
+            .iter()
+            .filter(|&&c| c > 0.0)
+            .map(|&c| {
+                let p = c / total;
+                -p * p.log2()
+            })
+            .sum(),
+        _ => 1.0 - counts.iter().map(|&c| (c / total) * (c / total)).sum::<f64>(),
+    }
+}
+
+/// Class label with the highest frequency, or the regression value
+fn output_value(values: &[f64], classes: &[f64]) -> f64 {
+    if classes.is_empty() {
+        values[0]
+    } else {
+        let best = (0..values.len()).fold(0, |best, c| if values[c] > values[best] { c } else { best });
+        classes[best]
+    }
+}
+
+/// Values stored per leaf: one per class for classification, a single mean for regression
+fn leaf_outputs(criterion: SplitCriterion, classes: &[f64]) -> usize {
+    if criterion == SplitCriterion::Mse { 1 } else { classes.len() }
+}
+
+fn normalise(importances: &mut [f64]) {
+    let total: f64 = importances.iter().sum();
+    if total > 0.0 {
+        importances.iter_mut().for_each(|v| *v /= total);
+    }
+}
+
+fn sigmoid(z: f64) -> f64 {
+    1.0 / (1.0 + (-z).exp())
+}
+
+fn open<'a>(bytes: &'a [u8], magic: &[u8; 4]) -> Result<ByteReader<'a>, String> {
+    let (reader, version) = ByteReader::new(bytes, magic)?;
+    if version != VERSION {
+        return Err(format!("Unsupported tree model format version {}", version));
+    }
+    Ok(reader)
+}
+
+fn write_header(writer: &mut ByteWriter, dims: usize, criterion: SplitCriterion, classes: &[f64], importances: &[f64]) {
+    writer.write_u32(dims as u32);
+    writer.write_u32(criterion as u32);
+    writer.write_f64_slice(classes);
+    writer.write_f64_slice(importances);
+}
+
+fn read_header(reader: &mut ByteReader) -> Result<(usize, SplitCriterion, Vec<f64>, Vec<f64>), String> {
+    let dims = reader.read_u32()? as usize;
+    let criterion = match reader.read_u32()? {
+        0 => SplitCriterion::Gini,
+        1 => SplitCriterion::Entropy,
+        2 => SplitCriterion::Mse,
+        other => return Err(format!("Unknown split criterion {}", other)),
+    };
+    let classes = reader.read_f64_vec()?;
+    let importances = reader.read_f64_vec()?;
+    if dims == 0 || importances.len() != dims || (criterion != SplitCriterion::Mse && classes.len() < 2) {
+        return Err("Inconsistent tree model payload".to_string());
+    }
+    Ok((dims, criterion, classes, importances))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(criterion: SplitCriterion, max_depth: usize, min_samples_leaf: usize, max_features: usize) -> TreeParams {
+        TreeParams { criterion, max_depth, min_samples_split: 2, min_samples_leaf, max_features }
+    }
+
+    fn grow(features: &[f64], dims: usize, targets: &[f64], num_classes: usize, params: &TreeParams) -> (Tree, Vec<f64>) {
+        let mut importances = vec![0.0; dims];
+        let indices = (0..targets.len()).collect();
+        let tree = Tree::grow(features, dims, targets, num_classes, indices, params, &mut Rng::new(0), &mut importances);
+        normalise(&mut importances);
+        (tree, importances)
+    }
+
+    /// XOR of the signs of two features on a jittered grid, with class indices as targets
+    fn xor_data() -> (Vec<f64>, Vec<f64>) {
+        let mut rng = Rng::new(1);
+        let mut features = Vec::new();
+        let mut targets = Vec::new();
+        for i in 0..10 {
+            for j in 0..10 {
+                let (a, b) = (i as f64 - 4.5 + 0.1 * rng.next_f64(), j as f64 - 4.5 + 0.1 * rng.next_f64());
+                features.extend([a, b]);
+                targets.push(f64::from(u8::from((a > 0.0) != (b > 0.0))));
+            }
+        }
+        (features, targets)
+    }
+
+    fn classifier() -> DecisionTree {
+        let (features, targets) = xor_data();
+        let (tree, importances) = grow(&features, 2, &targets, 2, &params(SplitCriterion::Gini, usize::MAX, 1, 2));
+        DecisionTree { dims: 2, criterion: SplitCriterion::Gini, classes: vec![-1.0, 1.0], tree, importances }
+    }
+
+    /// Every proper prefix and a padded copy of a payload must fail to decode
+    fn assert_rejects_damaged<T>(bytes: &[u8], decode: impl Fn(&[u8]) -> Result<T, String>) {
+        for end in 0..bytes.len() {
+            assert!(decode(&bytes[..end]).is_err(), "payload cut at {} was accepted", end);
+        }
+        let mut padded = bytes.to_vec();
+        padded.push(0);
+        assert!(decode(&padded).is_err());
+    }
+
+    #[test]
+    fn impurity_of_known_counts() {
+        assert!((impurity(&[2.0, 2.0], 4.0, SplitCriterion::Gini) - 0.5).abs() < 1e-12);
+        assert!((impurity(&[2.0, 2.0], 4.0, SplitCriterion::Entropy) - 1.0).abs() < 1e-12);
+        assert_eq!(impurity(&[4.0, 0.0], 4.0, SplitCriterion::Entropy), 0.0);
+        assert!((impurity(&[1.0, 1.0, 2.0], 4.0, SplitCriterion::Gini) - 0.625).abs() < 1e-12);
+    }
+
+    #[test]
+    fn unlimited_tree_fits_xor_exactly() {
+        let (features, targets) = xor_data();
+        for criterion in [SplitCriterion::Gini, SplitCriterion::Entropy] {
+            let (tree, importances) = grow(&features, 2, &targets, 2, &params(criterion, usize::MAX, 1, 2));
+            for (row, &target) in features.chunks_exact(2).zip(&targets) {
+                assert_eq!(output_value(tree.predict_row(row), &[0.0, 1.0]), target);
+            }
+            assert!((importances.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn regression_stump_finds_the_step() {
+        let features: Vec<f64> = (0..20).map(|i| i as f64 / 20.0).collect();
+        let targets: Vec<f64> = features.iter().map(|&x| if x < 0.42 { 1.0 } else { 3.0 }).collect();
+        let (tree, importances) = grow(&features, 1, &targets, 0, &params(SplitCriterion::Mse, 1, 1, 1));
+
+        assert_eq!(tree.nodes.len(), 3);
+        assert_eq!(tree.depth(), 1);
+        let (feature, threshold) = tree.nodes[0].split.unwrap();
+        assert!(feature == 0 && (threshold - 0.425).abs() < 1e-12);
+        assert_eq!(tree.predict_row(&[0.1]), &[1.0]);
+        assert_eq!(tree.predict_row(&[0.9]), &[3.0]);
+        assert_eq!(importances, vec![1.0]);
+    }
+
+    #[test]
+    fn growth_limits_are_respected() {
+        let (features, targets) = xor_data();
+        let (shallow, _) = grow(&features, 2, &targets, 2, &params(SplitCriterion::Gini, 2, 1, 2));
+        assert!(shallow.depth() <= 2);
+
+        // With at least 30 rows per leaf no leaf can be pure on XOR
+        let (coarse, _) = grow(&features, 2, &targets, 2, &params(SplitCriterion::Gini, usize::MAX, 30, 2));
+        for leaf in (0..coarse.nodes.len()).filter(|&n| coarse.nodes[n].split.is_none()) {
+            let rows = features.chunks_exact(2).filter(|row| coarse.leaf(row) == leaf).count();
+            assert!(rows >= 30, "leaf {} has {} rows", leaf, rows);
+        }
+    }
+
+    #[test]
+    fn irrelevant_features_get_no_importance() {
+        let mut rng = Rng::new(4);
+        let features: Vec<f64> = (0..200).map(|_| rng.next_normal()).collect();
+        let targets: Vec<f64> = features.chunks_exact(2).map(|row| f64::from(u8::from(row[0] > 0.3))).collect();
+        let (_, importances) = grow(&features, 2, &targets, 2, &params(SplitCriterion::Gini, usize::MAX, 1, 2));
+        assert_eq!(importances, vec![1.0, 0.0]);
+    }
+
+    #[test]
+    fn boosting_reduces_training_error() {
+        let mut rng = Rng::new(6);
+        let features: Vec<f64> = (0..200).map(|_| rng.next_f64() * 6.0).collect();
+        let targets: Vec<f64> = features.iter().map(|x| x.sin()).collect();
+        let tree_params = params(SplitCriterion::Mse, 3, 1, 1);
+
+        let mse = |rounds: usize| {
+            let model = GradientBoosting::from_values(
+                &features,
+                &targets,
+                1,
+                BoostingLoss::SquaredError,
+                Vec::new(),
+                rounds,
+                0.1,
+                1.0,
+                &tree_params,
+                &mut Rng::new(0),
+            );
+            features.iter().zip(&targets).map(|(x, y)| (model.score(&[*x]) - y).powi(2)).sum::<f64>() / 200.0
+        };
+        assert!(mse(100) < 0.01);
+        assert!(mse(100) < mse(10));
+        assert!(mse(10) < mse(0));
+    }
+
+    #[test]
+    fn logistic_boosting_separates_classes() {
+        let mut rng = Rng::new(9);
+        let features: Vec<f64> = (0..200).map(|_| rng.next_normal()).collect();
+        let targets: Vec<f64> = features.iter().map(|&x| f64::from(u8::from(x > 0.5))).collect();
+        let model = GradientBoosting::from_values(
+            &features,
+            &targets,
+            1,
+            BoostingLoss::Logistic,
+            vec![0.0, 1.0],
+            50,
+            0.3,
+            0.8,
+            &params(SplitCriterion::Mse, 2, 1, 1),
+            &mut Rng::new(0),
+        );
+
+        let mean = targets.iter().sum::<f64>() / 200.0;
+        assert!((model.base_score - (mean / (1.0 - mean)).ln()).abs() < 1e-12);
+        assert!(sigmoid(model.score(&[2.0])) > 0.95);
+        assert!(sigmoid(model.score(&[-1.0])) < 0.05);
+    }
+
+    #[test]
+    fn decision_tree_round_trips() {
+        let model = classifier();
+        let restored = DecisionTree::decode(&model.to_bytes()).unwrap();
+
+        assert_eq!(restored.dims, model.dims);
+        assert_eq!(restored.criterion, model.criterion);
+        assert_eq!(restored.classes, model.classes);
+        assert_eq!(restored.importances, model.importances);
+        assert_eq!(restored.tree.values, model.tree.values);
+        let (features, _) = xor_data();
+        for row in features.chunks_exact(2) {
+            assert_eq!(restored.tree.leaf(row), model.tree.leaf(row));
+        }
+        assert_rejects_damaged(&model.to_bytes(), DecisionTree::decode);
+    }
+
+    #[test]
+    fn random_forest_round_trips() {
+        let (features, targets) = xor_data();
+        let tree_params = params(SplitCriterion::Entropy, 4, 2, 1);
+        let trees: Vec<Tree> = (0..3)
+            .map(|seed| {
+                let indices = (0..targets.len()).map(|i| (i * 7 + seed) % targets.len()).collect();
+                Tree::grow(&features, 2, &targets, 2, indices, &tree_params, &mut Rng::new(seed as u64), &mut [0.0; 2])
+            })
+            .collect();
+        let model = RandomForest {
+            dims: 2,
+            criterion: SplitCriterion::Entropy,
+            classes: vec![0.0, 1.0],
+            trees,
+            importances: vec![0.5, 0.5],
+        };
+        let restored = RandomForest::decode(&model.to_bytes()).unwrap();
+
+        assert_eq!(restored.trees.len(), 3);
+        for row in features.chunks_exact(2) {
+            assert_eq!(restored.average(row), model.average(row));
+        }
+        assert_rejects_damaged(&model.to_bytes(), RandomForest::decode);
+
+        let empty = RandomForest { trees: Vec::new(), ..model };
+        assert_eq!(RandomForest::decode(&empty.to_bytes()).err().unwrap(), "Forest payload has no trees");
+    }
+
+    #[test]
+    fn gradient_boosting_round_trips() {
+        let features: Vec<f64> = (0..50).map(|i| i as f64).collect();
+        let targets: Vec<f64> = features.iter().map(|x| x * x).collect();
+        let model = GradientBoosting::from_values(
+            &features,
+            &targets,
+            1,
+            BoostingLoss::SquaredError,
+            Vec::new(),
+            5,
+            0.5,
+            1.0,
+            &params(SplitCriterion::Mse, 2, 1, 1),
+            &mut Rng::new(0),
+        );
+        let restored = GradientBoosting::decode(&model.to_bytes()).unwrap();
+
+        assert_eq!(restored.base_score, model.base_score);
+        assert_eq!(restored.trees.len(), 5);
+        for x in &features {
+            assert_eq!(restored.score(&[*x]), model.score(&[*x]));
+        }
+        assert_rejects_damaged(&model.to_bytes(), GradientBoosting::decode);
+    }
+
+    #[test]
+    fn inconsistent_trees_are_rejected() {
+        let decode_tree = |tree: &Tree, dims: usize, outputs: usize| {
+            let mut writer = ByteWriter::new(TREE_MAGIC, VERSION);
+            tree.write(&mut writer);
+            let bytes = writer.into_bytes();
+            let (mut reader, _) = ByteReader::new(&bytes, TREE_MAGIC).unwrap();
+            Tree::read(&mut reader, dims, outputs).map(|_| ())
+        };
+        let stump = Tree {
+            nodes: vec![
+                Node { split: Some((1, 0.5)), left: 1, right: 2 },
+                Node { split: None, left: 0, right: 0 },
+                Node { split: None, left: 0, right: 0 },
+            ],
+            values: vec![0.0, 1.0, 2.0],
+            outputs: 1,
+        };
+        assert!(decode_tree(&stump, 2, 1).is_ok());
+
+        // Feature out of range, wrong leaf width and a child pointing back at the root
+        assert!(decode_tree(&stump, 1, 1).is_err());
+        assert!(decode_tree(&stump, 2, 2).is_err());
+        let mut cyclic = stump.clone();
+        cyclic.nodes[0].left = 0;
+        assert!(decode_tree(&cyclic, 2, 1).is_err());
+        let mut dangling = stump.clone();
+        dangling.nodes[0].right = 3;
+        assert!(decode_tree(&dangling, 2, 1).is_err());
+        let mut short = stump;
+        short.values.pop();
+        assert!(decode_tree(&short, 2, 1).is_err());
+    }
+}
@@ -51,14 +51,17 @@ export interface WasmModule {
   numeric_exponential_moving_average_f64(input: any, alpha: number): any;
   numeric_weighted_moving_average_f64(input: any, windowSize: number): any;
   numeric_detect_outliers_f64(input: any, threshold: number): any;
-  numeric_interpolate_missing_f64(input: any): any;
+  numeric_interpolate_missing_f64(input: any, method?: number, x?: any, maxGap?: number, extrapolation?: number, degree?: number): any;
   numeric_autocorrelation_f64(input: any, lag: number): number;
 
   // Machine learning operations
   linear_regression_f64(x: any, y: any): any;
-  linear_regression_predict_f64(x: any, slope: number, intercept: number): any;
-  kmeans_clustering_f64(data: any, k: number, maxIterations: number): any;
-  pca_f64(data: any, numComponents: number): any;
+  linear_regression_predict_f64(x: any, slope: number | any, intercept: number): any;
+  multiple_linear_regression_f64(x: any, y: any, dims: number, regularization: number, alpha?: number, l1Ratio?: number, fitIntercept?: boolean, maxIterations?: number): any;
+  polynomial_features_f64(x: any, dims: number, degree: number, includeBias: boolean): any;
+  kmeans_clustering_f64(data: any, k: number, maxIterations: number, dims?: number, init?: number, seed?: number, nInit?: number, tolerance?: number, batchSize?: number): any;
+  kmeans_select_k_f64(data: any, dims: number, kMin: number, kMax: number, maxIterations: number, seed?: number, nInit?: number): any;
+  pca_f64(data: any, numComponents: number, dims?: number, whiten?: boolean, solver?: number): any;
 
   // Neural network operations
   neural_network_forward_f64(inputs: any, weights: any, biases: any, activation: number): any;
@@ -66,7 +69,7 @@ export interface WasmModule {
   neural_network_backprop_f64(inputs: any, weights: any, biases: any, targets: any, learningRate: number, activation: number): any;
   neural_network_mse_loss_f64(predictions: any, targets: any): number;
   neural_network_binary_cross_entropy_loss_f64(predictions: any, targets: any): number;
-  neural_network_init_weights_xavier_f64(inputSize: number, outputSize: number): any;
+  neural_network_init_weights_xavier_f64(inputSize: number, outputSize: number, seed?: number): any;
   neural_network_init_biases_zero_f64(outputSize: number): any;
 
   // String operations